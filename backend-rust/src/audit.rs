@@ -13,12 +13,17 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fmt::Write as FmtWrite;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio::fs::OpenOptions;
-use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, RwLock};
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::{info, warn};
 
+use crate::audit_checkpoint::{merkle_root, CheckpointPublisher, MerkleCheckpoint};
+use crate::auth::ClientIdentity;
+use crate::audit_store::{AuditStore, AuditStoreConfig, LEGACY_AUDIT_LOG_PATH};
+
 // ── Audit Event Types ─────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +41,35 @@ pub enum AuditEventType {
     SessionEvent,
     /// Protest replay query executed
     ProtestReplay,
+    /// State-changing command received from a client (director, jury, tracker admin)
+    DirectorCommand,
+    /// Chain head anchored with an external timestamp authority / witness
+    ExternalAnchor,
+    /// Backend restarted and resumed an existing chain (links the pre- and post-restart blocks)
+    ServerRestart,
+    /// Horn/flag actuator driven by a procedure signal (commanded vs actual time)
+    SignalActuation,
+    /// Socket command refused by the permission matrix (older chains; now an `AuthEvent`)
+    PermissionDenied,
+    /// Authentication/authorization event with the client's identity (see `AuthEventKind`)
+    AuthEvent,
+}
+
+/// What an `AUTH_EVENT` block records. Together with the `socketId` of
+/// `DIRECTOR_COMMAND` blocks, `REGISTERED` ties every command to a user and device.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuthEventKind {
+    /// A client registered and got its role
+    Registered,
+    /// `register` refused: invalid or unknown token, or a blacklisted tracker
+    TokenRejected,
+    /// A command its role may not send
+    PermissionDenied,
+    /// `kill-tracker` issued
+    TrackerKilled,
+    /// Repeated failed `register` attempts locked an address or socket out (see `login_guard`)
+    LockedOut,
 }
 
 impl std::fmt::Display for AuditEventType {
@@ -66,6 +100,22 @@ pub struct AuditBlock {
     pub block_hash: String,
 }
 
+/// SHA-256 of (prev_hash || timestamp_ms LE || event_type || payload_json), hex-encoded.
+/// Shared by the hub chain and the per-node microSD chains.
+pub fn chain_hash(prev_hash: &str, timestamp_ms: u64, event_type: &str, payload_json: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(timestamp_ms.to_le_bytes());
+    hasher.update(event_type.as_bytes());
+    hasher.update(payload_json.as_bytes());
+    let result = hasher.finalize();
+    let mut hex = String::with_capacity(64);
+    for byte in result {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
+
 impl AuditBlock {
     fn compute_hash(
         prev_hash: &str,
@@ -73,17 +123,7 @@ impl AuditBlock {
         event_type: &AuditEventType,
         payload_json: &str,
     ) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(prev_hash.as_bytes());
-        hasher.update(timestamp_ms.to_le_bytes());
-        hasher.update(event_type.to_string().as_bytes());
-        hasher.update(payload_json.as_bytes());
-        let result = hasher.finalize();
-        let mut hex = String::with_capacity(64);
-        for byte in result {
-            let _ = write!(hex, "{byte:02x}");
-        }
-        hex
+        chain_hash(prev_hash, timestamp_ms, &event_type.to_string(), payload_json)
     }
 
     pub fn new(
@@ -118,60 +158,316 @@ impl AuditBlock {
     }
 }
 
+// ── Chain Verification ────────────────────────────────────────────────────────
+
+/// Why a block failed chain verification.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChainFault {
+    /// Line could not be parsed as an AuditBlock
+    Malformed { error: String },
+    /// block_hash does not match the recomputed hash of the block contents
+    HashMismatch,
+    /// prev_hash does not link to the preceding block's hash
+    PrevHashMismatch { expected: String, found: String },
+    /// block_seq does not follow the preceding block
+    SequenceGap { expected: u64, found: u64 },
+}
+
+/// First block at which the chain breaks.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainBreak {
+    pub file: String,
+    /// 1-based line number within `file`
+    pub line: usize,
+    pub block_seq: Option<u64>,
+    pub fault: ChainFault,
+}
+
+/// Result of walking one session's audit files from the first block to the last.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainVerification {
+    /// Session verified (None = legacy single-file log)
+    pub session_id: Option<String>,
+    pub files: Vec<String>,
+    pub blocks_checked: u64,
+    /// Number of chains found (a fresh genesis block starts a new one)
+    pub segments: u64,
+    pub last_hash: Option<String>,
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_broken: Option<ChainBreak>,
+}
+
+/// Walk a chain stored across one or more JSONL files (in order) and validate
+/// hash integrity, prev_hash linkage and block_seq continuity. Stops at the
+/// first broken block.
+///
+/// A block with `block_seq == 0` and the genesis prev_hash starts a new segment
+/// (logs written before restart recovery existed); every other block must link to
+/// the one before it, including across rotated files and `SERVER_RESTART` blocks.
+pub async fn verify_chain_files(session_id: Option<String>, paths: &[PathBuf]) -> std::io::Result<ChainVerification> {
+    let mut report = ChainVerification {
+        session_id,
+        files: paths.iter().map(|p| p.display().to_string()).collect(),
+        blocks_checked: 0,
+        segments: 0,
+        last_hash: None,
+        valid: true,
+        first_broken: None,
+    };
+
+    let mut prev: Option<(u64, String)> = None;
+
+    'files: for path in paths {
+        let file = match File::open(path).await {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = BufReader::new(file).lines();
+        let mut line_no = 0usize;
+
+        while let Some(line) = lines.next_line().await? {
+            line_no += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fault_at = |block_seq: Option<u64>, fault: ChainFault| ChainBreak {
+                file: path.display().to_string(),
+                line: line_no,
+                block_seq,
+                fault,
+            };
+
+            let block: AuditBlock = match serde_json::from_str(&line) {
+                Ok(b) => b,
+                Err(e) => {
+                    report.first_broken = Some(fault_at(None, ChainFault::Malformed { error: e.to_string() }));
+                    break 'files;
+                }
+            };
+
+            if !block.verify() {
+                report.first_broken = Some(fault_at(Some(block.block_seq), ChainFault::HashMismatch));
+                break 'files;
+            }
+
+            let is_genesis = block.block_seq == 0 && block.prev_hash == GENESIS_HASH;
+            if is_genesis {
+                report.segments += 1;
+            } else {
+                match &prev {
+                    Some((prev_seq, prev_hash)) => {
+                        if block.block_seq != prev_seq + 1 {
+                            report.first_broken = Some(fault_at(
+                                Some(block.block_seq),
+                                ChainFault::SequenceGap { expected: prev_seq + 1, found: block.block_seq },
+                            ));
+                            break 'files;
+                        }
+                        if &block.prev_hash != prev_hash {
+                            report.first_broken = Some(fault_at(
+                                Some(block.block_seq),
+                                ChainFault::PrevHashMismatch { expected: prev_hash.clone(), found: block.prev_hash.clone() },
+                            ));
+                            break 'files;
+                        }
+                    }
+                    None => {
+                        // Chain does not begin at a genesis block — the head was truncated
+                        report.first_broken = Some(fault_at(
+                            Some(block.block_seq),
+                            ChainFault::PrevHashMismatch { expected: GENESIS_HASH.to_string(), found: block.prev_hash.clone() },
+                        ));
+                        break 'files;
+                    }
+                }
+            }
+
+            report.blocks_checked += 1;
+            report.last_hash = Some(block.block_hash.clone());
+            prev = Some((block.block_seq, block.block_hash));
+        }
+    }
+
+    report.valid = report.first_broken.is_none();
+    Ok(report)
+}
+
+/// Read every parseable block from the given JSONL audit files, in file order.
+/// Malformed lines are skipped — use `verify_chain_files` to detect them.
+pub async fn read_blocks(paths: &[PathBuf]) -> std::io::Result<Vec<AuditBlock>> {
+    let mut blocks = Vec::new();
+    for path in paths {
+        let file = match File::open(path).await {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = BufReader::new(file).lines();
+        while let Some(line) = lines.next_line().await? {
+            if let Ok(block) = serde_json::from_str::<AuditBlock>(&line) {
+                blocks.push(block);
+            }
+        }
+    }
+    Ok(blocks)
+}
+
+/// Last intact block of a chain stored across `files` (searched from the newest file back).
+async fn last_block(files: &[PathBuf]) -> Option<AuditBlock> {
+    for file in files.iter().rev() {
+        let blocks = match read_blocks(std::slice::from_ref(file)).await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("AuditLogger: cannot read {} for chain recovery: {e}", file.display());
+                continue;
+            }
+        };
+        if let Some(last) = blocks.into_iter().rev().find(|b| b.verify()) {
+            return Some(last);
+        }
+    }
+    None
+}
+
 // ── Audit Logger ──────────────────────────────────────────────────────────────
 
-const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
-const AUDIT_LOG_PATH: &str = "/data/audit.jsonl";
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
 
 #[derive(Default)]
 struct AuditState {
     block_seq: u64,
     last_hash: String,
+    /// Block hashes appended since the last Merkle checkpoint
+    window: Vec<String>,
+}
+
+impl AuditState {
+    fn genesis() -> Self {
+        Self {
+            block_seq: 0,
+            last_hash: GENESIS_HASH.to_string(),
+            window: Vec::new(),
+        }
+    }
+
+    /// Continue the chain after `last` (the final block written before a restart or session switch).
+    fn resume(last: &AuditBlock) -> Self {
+        Self {
+            block_seq: last.block_seq + 1,
+            last_hash: last.block_hash.clone(),
+            window: Vec::new(),
+        }
+    }
+
+    /// Close the current checkpoint window (if non-empty) and return its checkpoint.
+    fn take_checkpoint(&mut self, session_id: &str, timestamp_ms: u64) -> Option<MerkleCheckpoint> {
+        let window = std::mem::take(&mut self.window);
+        let merkle_root = merkle_root(&window)?;
+        let block_count = window.len() as u64;
+        Some(MerkleCheckpoint {
+            session_id: session_id.to_string(),
+            first_seq: self.block_seq - block_count,
+            last_seq: self.block_seq - 1,
+            block_count,
+            merkle_root,
+            head_hash: self.last_hash.clone(),
+            timestamp_ms,
+        })
+    }
 }
 
 /// Thread-safe, append-only SHA-256 chained audit logger.
-/// Writes to /data/audit.jsonl (persistent Fly.io volume) as JSON lines.
+/// Writes per-session JSON-line files under /data/audit (persistent Fly.io volume),
+/// see `audit_store` for the rotation and retention layout.
 #[derive(Clone)]
 pub struct AuditLogger {
     state: Arc<RwLock<AuditState>>,
     session_id: Arc<RwLock<String>>,
-    cloud_sync: Arc<RwLock<Option<Arc<crate::cloud_sync::CloudSyncManager>>>>,
+    store: Arc<Mutex<AuditStore>>,
+    uploader: Arc<RwLock<Option<crate::audit_uploader::AuditUploader>>>,
+    checkpoints: Arc<RwLock<Option<CheckpointPublisher>>>,
 }
 
 impl AuditLogger {
-    pub fn new() -> Self {
-        let initial_state = AuditState {
-            block_seq: 0,
-            last_hash: GENESIS_HASH.to_string(),
+    /// Open the store and resume the default session's chain from its last block on disk.
+    /// If a chain was resumed, a `SERVER_RESTART` block is appended linking across the restart.
+    pub async fn open(config: AuditStoreConfig) -> Self {
+        let store = AuditStore::open(config).await;
+        let session_id = "default".to_string();
+        let last = last_block(&store.files_for(&session_id)).await;
+
+        let logger = Self {
+            state: Arc::new(RwLock::new(last.as_ref().map(AuditState::resume).unwrap_or_else(AuditState::genesis))),
+            session_id: Arc::new(RwLock::new(session_id)),
+            store: Arc::new(Mutex::new(store)),
+            uploader: Arc::new(RwLock::new(None)),
+            checkpoints: Arc::new(RwLock::new(None)),
         };
-        Self {
-            state: Arc::new(RwLock::new(initial_state)),
-            session_id: Arc::new(RwLock::new("default".to_string())),
-            cloud_sync: Arc::new(RwLock::new(None)),
+
+        if let Some(last) = last {
+            info!("AuditLogger: resuming chain at block {} ({})", last.block_seq + 1, last.block_hash);
+            logger.append(
+                AuditEventType::ServerRestart,
+                serde_json::json!({
+                    "previousSeq": last.block_seq,
+                    "previousHash": last.block_hash,
+                    "previousTimestampMs": last.timestamp_ms,
+                    "downtimeMs": now_ms().saturating_sub(last.timestamp_ms),
+                }),
+            ).await;
         }
+        logger
     }
 
-    pub async fn attach_cloud_sync(&self, sync: Arc<crate::cloud_sync::CloudSyncManager>) {
-        *self.cloud_sync.write().await = Some(sync);
-        info!("AuditLogger: Attached to AWS Aurora Cloud Sync");
+    /// Mirror every appended block to Supabase/Postgres `audit_log` via the uploader outbox.
+    pub async fn attach_uploader(&self, uploader: crate::audit_uploader::AuditUploader) {
+        *self.uploader.write().await = Some(uploader);
+        info!("AuditLogger: Attached cloud audit uploader");
     }
 
+    /// Publish a Merkle-root checkpoint every `publisher.interval()` blocks.
+    pub async fn attach_checkpoints(&self, publisher: CheckpointPublisher) {
+        *self.checkpoints.write().await = Some(publisher);
+    }
+
+    /// Switch to another session. Each session is its own chain written to its own set
+    /// of files: a new session starts from genesis, a known one resumes after its last block.
     pub async fn set_session(&self, id: String) {
-        *self.session_id.write().await = id;
+        let mut session = self.session_id.write().await;
+        if *session == id {
+            return;
+        }
+        let closing = self.state.write().await.take_checkpoint(&session, now_ms());
+        if let (Some(checkpoint), Some(publisher)) = (closing, self.checkpoints.read().await.as_ref()) {
+            publisher.publish(checkpoint);
+        }
+        let files = self.store.lock().await.files_for(&id);
+        let resumed = last_block(&files).await.as_ref().map(AuditState::resume);
+        *session = id;
+        *self.state.write().await = resumed.unwrap_or_else(AuditState::genesis);
     }
 
     /// Append one audit block. This is the single write path.
     /// Non-blocking in normal operation — failures are logged but don't crash the race.
     pub async fn append(&self, event_type: AuditEventType, payload: serde_json::Value) {
-        let timestamp_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
+        let timestamp_ms = now_ms();
+        let checkpoint_interval = self.checkpoints.read().await.as_ref().map(|p| p.interval());
 
         let payload_json = payload.to_string();
         let session_id = self.session_id.read().await.clone();
 
-        let block = {
+        // Hold the store lock across block creation and write so file order == chain order
+        let mut store = self.store.lock().await;
+
+        let (block, checkpoint) = {
             let mut state = self.state.write().await;
             let block = AuditBlock::new(
                 state.block_seq,
@@ -183,7 +479,19 @@ impl AuditLogger {
             );
             state.last_hash = block.block_hash.clone();
             state.block_seq += 1;
-            block
+
+            let checkpoint = match checkpoint_interval {
+                Some(interval) => {
+                    state.window.push(block.block_hash.clone());
+                    if state.window.len() as u64 >= interval {
+                        state.take_checkpoint(&block.session_id, timestamp_ms)
+                    } else {
+                        None
+                    }
+                }
+                None => None,
+            };
+            (block, checkpoint)
         };
 
         // Verify immediately (should always pass — defensive check)
@@ -198,36 +506,71 @@ impl AuditLogger {
             }
         };
 
-        match OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(AUDIT_LOG_PATH)
-            .await
-        {
-            Ok(mut f) => {
-                if let Err(e) = f.write_all(line.as_bytes()).await {
-                    warn!("Audit: write failed: {e}");
-                }
-            }
-            Err(e) => {
-                // /data/ not available (local mode) — log to stdout only
-                info!("Audit[{}]: {} — {}", block.block_seq, block.event_type, block.block_hash);
-                if !e.kind().eq(&std::io::ErrorKind::NotFound) {
-                    warn!("Audit: could not open {AUDIT_LOG_PATH}: {e}");
-                }
+        if store.is_writable() {
+            if let Err(e) = store.write_block(&block, &line).await {
+                warn!("Audit: write failed: {e}");
             }
+        } else {
+            // /data/ not available (local mode) — log to stdout only
+            info!("Audit[{}]: {} — {}", block.block_seq, block.event_type, block.block_hash);
         }
+        drop(store);
 
-        // AWS Aurora Sync
-        let sync_opt = self.cloud_sync.read().await.clone();
-        if let Some(sync) = sync_opt {
-            let block_clone = block.clone();
-            tokio::spawn(async move {
-                sync.push_audit_block(&block_clone).await;
-            });
+        // Merkle checkpoint — published only after the closing block is on disk
+        if let (Some(checkpoint), Some(publisher)) = (checkpoint, self.checkpoints.read().await.as_ref()) {
+            publisher.publish(checkpoint);
+        }
+
+        // Cloud mirror (Supabase / Aurora) — queued, never blocks the append path
+        if let Some(uploader) = self.uploader.read().await.as_ref() {
+            uploader.enqueue(block);
         }
     }
 
+
+    /// Session, seq and hash of the most recently appended block (None before the first block).
+    pub async fn head(&self) -> Option<(String, u64, String)> {
+        let session_id = self.session_id.read().await.clone();
+        let state = self.state.read().await;
+        state.block_seq.checked_sub(1).map(|seq| (session_id, seq, state.last_hash.clone()))
+    }
+
+    /// Directory holding the session files, index and anchor receipts.
+    pub async fn dir(&self) -> PathBuf {
+        self.store.lock().await.dir().to_path_buf()
+    }
+
+    /// Audit files to read for a session (in chain order), or every file when `None`.
+    pub async fn files_for(&self, session_id: Option<&str>) -> Vec<PathBuf> {
+        let store = self.store.lock().await;
+        match session_id {
+            Some(id) => store.files_for(id),
+            None => store.all_files(),
+        }
+    }
+
+    /// Walk every on-disk chain (legacy log + each indexed session) and validate it.
+    /// Run at startup and on demand via the `verify-audit` director command.
+    pub async fn verify_file(&self) -> std::io::Result<Vec<ChainVerification>> {
+        let (legacy, sessions) = {
+            let store = self.store.lock().await;
+            let legacy = PathBuf::from(LEGACY_AUDIT_LOG_PATH);
+            let sessions: Vec<(String, Vec<PathBuf>)> = store.index().sessions.keys()
+                .map(|id| (id.clone(), store.files_for(id)))
+                .collect();
+            (legacy.exists().then_some(legacy), sessions)
+        };
+
+        let mut reports = Vec::new();
+        if let Some(path) = legacy {
+            reports.push(verify_chain_files(None, &[path]).await?);
+        }
+        for (session_id, files) in sessions {
+            reports.push(verify_chain_files(Some(session_id), &files).await?);
+        }
+        Ok(reports)
+    }
+
     /// Log a race status change (gun, recall, postpone, etc.)
     pub async fn log_race_status_change(&self, from: &str, to: &str, reason: Option<&str>) {
         self.append(
@@ -236,10 +579,67 @@ impl AuditLogger {
                 "from": from,
                 "to": to,
                 "reason": reason,
+                "atMs": crate::time_discipline::now_ms(),
+                "clock": crate::time_discipline::status(),
+            }),
+        ).await;
+    }
+
+    /// Log a state-changing socket command with its raw payload and the issuing client.
+    pub async fn log_director_command(&self, command: &str, socket_id: &str, role: Option<&str>, payload: &serde_json::Value) {
+        self.append(
+            AuditEventType::DirectorCommand,
+            serde_json::json!({
+                "command": command,
+                "socketId": socket_id,
+                "role": role,
+                "payload": payload,
             }),
         ).await;
     }
 
+    /// Log a command the issuing client's role is not allowed to send.
+    pub async fn log_permission_denied(&self, command: &str, socket_id: &str, role: Option<&str>) {
+        let client = ClientIdentity {
+            socket_id: socket_id.to_string(),
+            role: role.map(str::to_string),
+            ..Default::default()
+        };
+        self.log_auth_event(AuthEventKind::PermissionDenied, &client, serde_json::json!({ "command": command })).await;
+    }
+
+    /// Log an authentication/authorization event with who and what device it concerns.
+    pub async fn log_auth_event(&self, kind: AuthEventKind, client: &ClientIdentity, detail: serde_json::Value) {
+        self.append(
+            AuditEventType::AuthEvent,
+            serde_json::json!({
+                "kind": kind,
+                "client": client,
+                "detail": detail,
+            }),
+        ).await;
+    }
+
+    /// Record that an earlier block was anchored externally (full receipt kept in anchors.jsonl).
+    pub async fn log_external_anchor(&self, receipt: &crate::audit_anchor::AnchorReceipt) {
+        self.append(
+            AuditEventType::ExternalAnchor,
+            serde_json::json!({
+                "anchoredSeq": receipt.anchored_seq,
+                "anchoredHash": receipt.anchored_hash,
+                "service": receipt.service,
+                "url": receipt.url,
+                "tsaStatus": receipt.tsa_status,
+                "receiptSha256": receipt.receipt_sha256,
+            }),
+        ).await;
+    }
+
+    /// Record an external horn/flag actuation and how late it was against the command.
+    pub async fn log_signal_actuation(&self, payload: serde_json::Value) {
+        self.append(AuditEventType::SignalActuation, payload).await;
+    }
+
     /// Log OCS detection at gun signal
     pub async fn log_ocs_detected(&self, ocs_boats: &[serde_json::Value]) {
         self.append(
@@ -251,6 +651,27 @@ impl AuditLogger {
         ).await;
     }
 
+    /// Log a protest replay query (who asked, which window)
+    pub async fn log_protest_replay(&self, requested_by: &str, query: &serde_json::Value) {
+        self.append(
+            AuditEventType::ProtestReplay,
+            serde_json::json!({
+                "requested_by": requested_by,
+                "query": query,
+            }),
+        ).await;
+    }
+
+    /// Log a compressed window of raw UWB measurement packets (see `measurement_recorder`).
+    pub async fn log_measurement_batch(&self, payload: serde_json::Value) {
+        self.append(AuditEventType::UwbMeasurementBatch, payload).await;
+    }
+
+    /// Log the inputs and live OCS call of the gun batch solve (`protest_replay::GunSolvePayload`).
+    pub async fn log_uwb_gun_solve(&self, payload: serde_json::Value) {
+        self.append(AuditEventType::UwbGunSolve, payload).await;
+    }
+
     /// Log a session event (director join, takeover, etc.)
     pub async fn log_session_event(&self, event: &str, detail: Option<serde_json::Value>) {
         self.append(
@@ -262,3 +683,10 @@ impl AuditLogger {
         ).await;
     }
 }
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
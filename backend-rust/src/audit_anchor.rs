@@ -0,0 +1,287 @@
+//! # audit_anchor
+//!
+//! Optional external timestamp anchoring of the audit chain head.
+//!
+//! Every `interval` the anchor task takes the hash of the latest block and:
+//!   - submits it to an RFC 3161 Time-Stamp Authority (`AUDIT_TSA_URL`), and/or
+//!   - POSTs it as JSON to a witness webhook (`AUDIT_ANCHOR_WEBHOOK`)
+//!
+//! The raw receipt (DER `TimeStampResp` or webhook response body) is appended to
+//! `<audit dir>/anchors.jsonl`, and an `EXTERNAL_ANCHOR` block recording the
+//! anchored seq/hash and the SHA-256 of the receipt is added to the chain itself.
+//! A third party can later check the receipt against the TSA certificate and
+//! prove that the chain up to `anchoredSeq` existed no later than the TSA time.
+//!
+//! ## Invariants
+//! - Core Invariant #2: wall-clock claims in the chain are bounded by an external clock
+//! - Core Invariant #8: anchoring runs in its own task; TSA outages only log a warning
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::audit::AuditLogger;
+
+const ANCHORS_FILE: &str = "anchors.jsonl";
+/// DER-encoded OID 2.16.840.1.101.3.4.2.1 (id-sha256)
+const OID_SHA256: [u8; 11] = [0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+/// Per TSA / witness request, so an unresponsive one cannot stall anchoring
+const ANCHOR_TIMEOUT: Duration = Duration::from_secs(30);
+
+// ── Configuration ─────────────────────────────────────────────────────────────
+
+pub struct AnchorConfig {
+    /// RFC 3161 Time-Stamp Authority endpoint (optional)
+    pub tsa_url: Option<String>,
+    /// Witness webhook receiving `{ sessionId, blockSeq, blockHash }` (optional)
+    pub webhook_url: Option<String>,
+    /// Time between anchors (default 300 s)
+    pub interval: Duration,
+}
+
+impl Default for AnchorConfig {
+    fn default() -> Self {
+        let url = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        Self {
+            tsa_url: url("AUDIT_TSA_URL"),
+            webhook_url: url("AUDIT_ANCHOR_WEBHOOK"),
+            interval: Duration::from_secs(
+                std::env::var("AUDIT_ANCHOR_INTERVAL_SECS")
+                    .ok().and_then(|v| v.parse().ok()).filter(|&s| s > 0).unwrap_or(300),
+            ),
+        }
+    }
+}
+
+impl AnchorConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.tsa_url.is_some() || self.webhook_url.is_some()
+    }
+}
+
+// ── Receipt ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnchorReceipt {
+    pub session_id: String,
+    pub anchored_seq: u64,
+    pub anchored_hash: String,
+    /// "rfc3161" or "webhook"
+    pub service: String,
+    pub url: String,
+    pub requested_ms: u64,
+    /// RFC 3161 PKIStatus (0 = granted, 1 = grantedWithMods); None for webhooks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tsa_status: Option<u8>,
+    /// Base64 of the raw response body (DER TimeStampResp for RFC 3161)
+    pub receipt: String,
+    /// Hex SHA-256 of the raw response body
+    pub receipt_sha256: String,
+}
+
+// ── Anchor task ───────────────────────────────────────────────────────────────
+
+/// Start the periodic anchor task if a TSA or webhook is configured.
+pub fn spawn(config: AnchorConfig, audit: AuditLogger, dir: PathBuf) {
+    if !config.is_enabled() {
+        return;
+    }
+    info!(
+        "AuditAnchor: anchoring chain head every {:?} (tsa: {}, webhook: {})",
+        config.interval,
+        config.tsa_url.as_deref().unwrap_or("-"),
+        config.webhook_url.as_deref().unwrap_or("-"),
+    );
+    tokio::spawn(run_anchor(config, audit, dir));
+}
+
+async fn run_anchor(config: AnchorConfig, audit: AuditLogger, dir: PathBuf) {
+    let client = reqwest::Client::builder().timeout(ANCHOR_TIMEOUT).build().unwrap_or_default();
+    let mut interval = tokio::time::interval(config.interval);
+    // Chain head once the last anchor's own EXTERNAL_ANCHOR blocks were appended
+    let mut last_anchored: Option<String> = None;
+
+    loop {
+        interval.tick().await;
+
+        let Some((session_id, block_seq, block_hash)) = audit.head().await else { continue };
+        if last_anchored.as_deref() == Some(block_hash.as_str()) {
+            continue;
+        }
+
+        let mut anchored = 0;
+        if let Some(url) = &config.tsa_url {
+            match request_timestamp(&client, url, &block_hash).await {
+                Ok((status, body)) => {
+                    let receipt = receipt(&session_id, block_seq, &block_hash, "rfc3161", url, Some(status), &body);
+                    anchored += record(&audit, &dir, receipt).await as u64;
+                }
+                Err(e) => warn!("AuditAnchor: TSA request failed: {e}"),
+            }
+        }
+        if let Some(url) = &config.webhook_url {
+            let body = serde_json::json!({ "sessionId": session_id, "blockSeq": block_seq, "blockHash": block_hash });
+            match client.post(url).json(&body).send().await.and_then(|r| r.error_for_status()) {
+                Ok(resp) => {
+                    let body = resp.bytes().await.unwrap_or_default();
+                    let receipt = receipt(&session_id, block_seq, &block_hash, "webhook", url, None, &body);
+                    anchored += record(&audit, &dir, receipt).await as u64;
+                }
+                Err(e) => warn!("AuditAnchor: webhook failed: {e}"),
+            }
+        }
+
+        if anchored > 0 {
+            // If nothing but our receipts landed since, the new head needs no anchor of
+            // its own; otherwise keep the old hash so the blocks in between get one
+            last_anchored = match audit.head().await {
+                Some((_, seq, hash)) if seq == block_seq + anchored => Some(hash),
+                _ => Some(block_hash),
+            };
+        }
+    }
+}
+
+fn receipt(
+    session_id: &str,
+    anchored_seq: u64,
+    anchored_hash: &str,
+    service: &str,
+    url: &str,
+    tsa_status: Option<u8>,
+    body: &[u8],
+) -> AnchorReceipt {
+    AnchorReceipt {
+        session_id: session_id.to_string(),
+        anchored_seq,
+        anchored_hash: anchored_hash.to_string(),
+        service: service.to_string(),
+        url: url.to_string(),
+        requested_ms: now_ms(),
+        tsa_status,
+        receipt: BASE64.encode(body),
+        receipt_sha256: hex::encode(Sha256::digest(body)),
+    }
+}
+
+/// Persist the full receipt and chain a summary block. Returns true if the receipt was
+/// kept; a receipt that could not be written is not chained either.
+async fn record(audit: &AuditLogger, dir: &std::path::Path, receipt: AnchorReceipt) -> bool {
+    let line = match serde_json::to_string(&receipt) {
+        Ok(l) => format!("{l}\n"),
+        Err(_) => return false,
+    };
+    let path = dir.join(ANCHORS_FILE);
+    let written = match OpenOptions::new().create(true).append(true).open(&path).await {
+        Ok(mut f) => match f.write_all(line.as_bytes()).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("AuditAnchor: cannot write {}: {e}", path.display());
+                false
+            }
+        },
+        Err(e) => {
+            warn!("AuditAnchor: cannot write {}: {e}", path.display());
+            false
+        }
+    };
+
+    if written {
+        audit.log_external_anchor(&receipt).await;
+    }
+    written
+}
+
+// ── RFC 3161 ──────────────────────────────────────────────────────────────────
+
+/// Submit a TimeStampReq for `block_hash` and return (PKIStatus, raw DER response).
+async fn request_timestamp(client: &reqwest::Client, url: &str, block_hash: &str) -> anyhow::Result<(u8, Vec<u8>)> {
+    let digest = hex::decode(block_hash)?;
+    let nonce = uuid::Uuid::new_v4().as_u64_pair().0 & 0x7fff_ffff_ffff_ffff;
+
+    let resp = client.post(url)
+        .header("Content-Type", "application/timestamp-query")
+        .body(timestamp_request(&digest, nonce))
+        .send()
+        .await?
+        .error_for_status()?;
+    let body = resp.bytes().await?.to_vec();
+
+    let status = pki_status(&body).ok_or_else(|| anyhow::anyhow!("malformed TimeStampResp"))?;
+    if status > 1 {
+        anyhow::bail!("TSA rejected request (PKIStatus {status})");
+    }
+    Ok((status, body))
+}
+
+/// DER TimeStampReq { version 1, messageImprint { sha256, digest }, nonce, certReq TRUE }.
+fn timestamp_request(digest: &[u8], nonce: u64) -> Vec<u8> {
+    let algorithm = der(0x30, &[&OID_SHA256[..], &[0x05, 0x00]].concat());
+    let imprint = der(0x30, &[algorithm, der(0x04, digest)].concat());
+
+    let mut nonce_bytes: Vec<u8> = nonce.to_be_bytes().iter().copied().skip_while(|&b| b == 0).collect();
+    if nonce_bytes.first().is_none_or(|&b| b & 0x80 != 0) {
+        nonce_bytes.insert(0, 0);
+    }
+
+    let body = [
+        der(0x02, &[1]),
+        imprint,
+        der(0x02, &nonce_bytes),
+        der(0x01, &[0xff]),
+    ].concat();
+    der(0x30, &body)
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len.to_be_bytes().iter().copied().skip_while(|&b| b == 0).collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Read PKIStatus from TimeStampResp ::= SEQUENCE { status PKIStatusInfo SEQUENCE { status INTEGER, .. }, .. }
+fn pki_status(resp: &[u8]) -> Option<u8> {
+    let outer = der_content(resp, 0x30)?;
+    let status_info = der_content(outer, 0x30)?;
+    let status = der_content(status_info, 0x02)?;
+    status.last().copied()
+}
+
+/// Content bytes of the DER element at the start of `data`, if it has the expected tag.
+fn der_content(data: &[u8], tag: u8) -> Option<&[u8]> {
+    if *data.first()? != tag {
+        return None;
+    }
+    let first = *data.get(1)? as usize;
+    let (len, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let n = first & 0x7f;
+        let len = data.get(2..2 + n)?.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, 2 + n)
+    };
+    data.get(header..header + len)
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,150 @@
+//! # audit_checkpoint
+//!
+//! Periodic Merkle-root checkpoints over the audit chain.
+//!
+//! Every `interval` blocks the logger computes a Merkle root over the block
+//! hashes of that window and publishes it as a `MerkleCheckpoint`:
+//!   - broadcast to every Socket.IO client as `audit-checkpoint`
+//!   - optionally POSTed as JSON to `AUDIT_CHECKPOINT_URL` (external witness)
+//!
+//! Observers that record these checkpoints during the race can later take the
+//! exported chain, recompute the root for each window and prove the chain they
+//! were given is the one that was broadcast live.
+//!
+//! ## Tree construction
+//! - Leaves are the raw 32-byte SHA-256 block hashes, in `block_seq` order
+//! - Parent = SHA-256(left ‖ right); an odd node at any level is paired with itself
+//! - A single-leaf window's root is that leaf
+//!
+//! ## Invariants
+//! - Core Invariant #2: checkpoints are derived only from committed block hashes
+//! - Core Invariant #8: publishing is queued; a slow or dead endpoint never blocks the race
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use socketioxide::SocketIo;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+const CHANNEL_CAPACITY: usize = 256;
+/// A hung endpoint must not hold up the checkpoints queued behind it
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ── Configuration ─────────────────────────────────────────────────────────────
+
+pub struct CheckpointConfig {
+    /// Blocks per checkpoint window (default 64)
+    pub interval: u64,
+    /// External endpoint receiving each checkpoint as a JSON POST (optional)
+    pub endpoint: Option<String>,
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            interval: std::env::var("AUDIT_CHECKPOINT_INTERVAL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(64),
+            endpoint: std::env::var("AUDIT_CHECKPOINT_URL").ok().filter(|s| !s.is_empty()),
+        }
+    }
+}
+
+// ── Checkpoint ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleCheckpoint {
+    pub session_id: String,
+    /// First block_seq covered (inclusive)
+    pub first_seq: u64,
+    /// Last block_seq covered (inclusive)
+    pub last_seq: u64,
+    pub block_count: u64,
+    /// Hex-encoded Merkle root over the window's block hashes
+    pub merkle_root: String,
+    /// Hash of the last block in the window (chain head at checkpoint time)
+    pub head_hash: String,
+    pub timestamp_ms: u64,
+}
+
+/// Merkle root over hex-encoded block hashes. Returns None for an empty window
+/// or if any hash is not valid hex.
+pub fn merkle_root(block_hashes: &[String]) -> Option<String> {
+    let mut level: Vec<Vec<u8>> = block_hashes.iter()
+        .map(hex::decode)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    if level.is_empty() {
+        return None;
+    }
+
+    while level.len() > 1 {
+        level = level.chunks(2)
+            .map(|pair| {
+                let mut hasher = Sha256::new();
+                hasher.update(&pair[0]);
+                hasher.update(pair.get(1).unwrap_or(&pair[0]));
+                hasher.finalize().to_vec()
+            })
+            .collect();
+    }
+    Some(hex::encode(&level[0]))
+}
+
+// ── Publisher ─────────────────────────────────────────────────────────────────
+
+/// Handle used by `AuditLogger` to publish checkpoints.
+#[derive(Clone)]
+pub struct CheckpointPublisher {
+    interval: u64,
+    tx: mpsc::Sender<MerkleCheckpoint>,
+}
+
+impl CheckpointPublisher {
+    pub fn spawn(config: CheckpointConfig, io: SocketIo) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        info!(
+            "CheckpointPublisher: Merkle checkpoint every {} blocks{}",
+            config.interval,
+            config.endpoint.as_deref().map(|u| format!(", witness endpoint {u}")).unwrap_or_default()
+        );
+        tokio::spawn(run_publisher(io, config.endpoint, rx));
+        Self { interval: config.interval, tx }
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Queue a checkpoint. Never blocks.
+    pub fn publish(&self, checkpoint: MerkleCheckpoint) {
+        if let Err(e) = self.tx.try_send(checkpoint) {
+            warn!("CheckpointPublisher: queue full, checkpoint dropped: {e}");
+        }
+    }
+}
+
+async fn run_publisher(io: SocketIo, endpoint: Option<String>, mut rx: mpsc::Receiver<MerkleCheckpoint>) {
+    let client = reqwest::Client::builder().timeout(ENDPOINT_TIMEOUT).build().unwrap_or_default();
+
+    while let Some(checkpoint) = rx.recv().await {
+        let _ = io.emit("audit-checkpoint", &checkpoint);
+        debug!(
+            "Audit checkpoint [{}] {}..={} root {}",
+            checkpoint.session_id, checkpoint.first_seq, checkpoint.last_seq, checkpoint.merkle_root
+        );
+
+        if let Some(url) = &endpoint {
+            match client.post(url).json(&checkpoint).send().await {
+                Ok(resp) if resp.status().is_success() => {}
+                Ok(resp) => warn!("CheckpointPublisher: witness endpoint returned {}", resp.status()),
+                Err(e) => warn!("CheckpointPublisher: witness endpoint unreachable: {e}"),
+            }
+        }
+    }
+}
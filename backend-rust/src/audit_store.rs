@@ -0,0 +1,265 @@
+//! # audit_store
+//!
+//! On-disk layout for the audit chain: one set of JSONL files per session,
+//! rotated by size/age, pruned by a retention policy, and described by an index.
+//!
+//! ```text
+//! /data/audit/
+//!   index.json                      session_id → ordered file list
+//!   <session_id>.0000.jsonl         first file of the session chain
+//!   <session_id>.0001.jsonl         rotated continuation (prev_hash links across files)
+//! ```
+//!
+//! Replay, export and verification read only the files listed for the session
+//! they care about, in index order. The pre-rotation `/data/audit.jsonl` is still
+//! read as a legacy file when walking every session.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::audit::AuditBlock;
+
+/// Single-file log written before per-session rotation existed.
+pub const LEGACY_AUDIT_LOG_PATH: &str = "/data/audit.jsonl";
+const INDEX_FILE: &str = "index.json";
+
+// ── Configuration ─────────────────────────────────────────────────────────────
+
+pub struct AuditStoreConfig {
+    /// Directory holding session files + index (default /data/audit)
+    pub dir: PathBuf,
+    /// Rotate when the active file reaches this size (default 64 MiB)
+    pub max_file_bytes: u64,
+    /// Rotate when the active file is older than this (default 6 h)
+    pub max_file_age_ms: u64,
+    /// Delete closed sessions whose last write is older than this (default 365 days, 0 = keep forever)
+    pub retention_ms: u64,
+}
+
+impl Default for AuditStoreConfig {
+    fn default() -> Self {
+        let env_u64 = |key: &str, default: u64| std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            dir: std::env::var("AUDIT_DIR").unwrap_or_else(|_| "/data/audit".to_string()).into(),
+            max_file_bytes: env_u64("AUDIT_MAX_FILE_BYTES", 64 * 1024 * 1024),
+            max_file_age_ms: env_u64("AUDIT_MAX_FILE_AGE_SECS", 6 * 3600) * 1000,
+            retention_ms: env_u64("AUDIT_RETENTION_DAYS", 365) * 86_400_000,
+        }
+    }
+}
+
+// ── Index ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFileEntry {
+    /// File name relative to the store directory
+    pub file: String,
+    pub first_seq: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seq: Option<u64>,
+    pub opened_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closed_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditIndex {
+    pub sessions: BTreeMap<String, Vec<AuditFileEntry>>,
+}
+
+struct ActiveFile {
+    session_id: String,
+    path: PathBuf,
+    bytes: u64,
+    opened_ms: u64,
+    last_seq: Option<u64>,
+}
+
+// ── Store ─────────────────────────────────────────────────────────────────────
+
+pub struct AuditStore {
+    config: AuditStoreConfig,
+    index: AuditIndex,
+    active: Option<ActiveFile>,
+    /// False when the store directory is unavailable (local dev without /data)
+    writable: bool,
+}
+
+impl AuditStore {
+    /// Open (or create) the store directory, load the index and apply retention.
+    pub async fn open(config: AuditStoreConfig) -> Self {
+        let writable = match tokio::fs::create_dir_all(&config.dir).await {
+            Ok(()) => true,
+            Err(e) => {
+                info!("AuditStore: {} unavailable ({e}) — audit blocks go to stdout only", config.dir.display());
+                false
+            }
+        };
+
+        let index = match tokio::fs::read_to_string(config.dir.join(INDEX_FILE)).await {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+                warn!("AuditStore: index.json unreadable ({e}), starting a fresh index");
+                AuditIndex::default()
+            }),
+            Err(_) => AuditIndex::default(),
+        };
+
+        let mut store = Self { config, index, active: None, writable };
+        if store.writable {
+            store.apply_retention(now_ms()).await;
+        }
+        store
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.config.dir
+    }
+
+    pub fn index(&self) -> &AuditIndex {
+        &self.index
+    }
+
+    /// Files holding the chain for one session, in chain order.
+    pub fn files_for(&self, session_id: &str) -> Vec<PathBuf> {
+        self.index.sessions.get(session_id)
+            .map(|entries| entries.iter().map(|e| self.config.dir.join(&e.file)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Every known audit file: the legacy single log first, then each session in index order.
+    pub fn all_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        if Path::new(LEGACY_AUDIT_LOG_PATH).exists() {
+            files.push(PathBuf::from(LEGACY_AUDIT_LOG_PATH));
+        }
+        for session_id in self.index.sessions.keys() {
+            files.extend(self.files_for(session_id));
+        }
+        files
+    }
+
+    /// Append one serialized block to the active file of its session,
+    /// rotating first if the session changed or the size/age limit was hit.
+    pub async fn write_block(&mut self, block: &AuditBlock, line: &str) -> std::io::Result<()> {
+        if !self.writable {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "audit store directory unavailable"));
+        }
+
+        let now = now_ms();
+        let needs_rotation = match &self.active {
+            None => true,
+            Some(a) => {
+                a.session_id != block.session_id
+                    || a.bytes + line.len() as u64 > self.config.max_file_bytes
+                    || now.saturating_sub(a.opened_ms) > self.config.max_file_age_ms
+            }
+        };
+        if needs_rotation {
+            self.rotate(&block.session_id, block.block_seq, now).await?;
+        }
+
+        let active = self.active.as_mut().expect("active file set by rotate");
+        let mut f = OpenOptions::new().create(true).append(true).open(&active.path).await?;
+        f.write_all(line.as_bytes()).await?;
+        active.bytes += line.len() as u64;
+        active.last_seq = Some(block.block_seq);
+        Ok(())
+    }
+
+    /// Close the active file (recording its last block) and open the next file for `session_id`.
+    async fn rotate(&mut self, session_id: &str, first_seq: u64, now: u64) -> std::io::Result<()> {
+        if let Some(prev) = self.active.take() {
+            if let Some(entry) = self.index.sessions.get_mut(&prev.session_id).and_then(|v| v.last_mut()) {
+                entry.last_seq = prev.last_seq;
+                entry.closed_ms = Some(now);
+            }
+        }
+
+        let entries = self.index.sessions.entry(session_id.to_string()).or_default();
+        let file = format!("{}.{:04}.jsonl", sanitize(session_id), entries.len());
+        entries.push(AuditFileEntry {
+            file: file.clone(),
+            first_seq,
+            last_seq: None,
+            opened_ms: now,
+            closed_ms: None,
+        });
+
+        let path = self.config.dir.join(&file);
+        let bytes = tokio::fs::metadata(&path).await.map(|m| m.len()).unwrap_or(0);
+        info!("AuditStore: writing session {session_id} to {}", path.display());
+        self.active = Some(ActiveFile { session_id: session_id.to_string(), path, bytes, opened_ms: now, last_seq: None });
+
+        self.apply_retention(now).await;
+        self.save_index().await
+    }
+
+    /// Delete every file of sessions whose last write is older than the retention window.
+    /// The active session is never pruned.
+    async fn apply_retention(&mut self, now: u64) {
+        if self.config.retention_ms == 0 {
+            return;
+        }
+        let active_session = self.active.as_ref().map(|a| a.session_id.clone());
+        let cutoff = now.saturating_sub(self.config.retention_ms);
+
+        let expired: Vec<String> = self.index.sessions.iter()
+            .filter(|(id, _)| Some(*id) != active_session.as_ref())
+            .filter(|(_, entries)| {
+                entries.iter().map(|e| e.closed_ms.unwrap_or(e.opened_ms)).max().unwrap_or(0) < cutoff
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for session_id in expired {
+            for path in self.files_for(&session_id) {
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        warn!("AuditStore: retention could not delete {}: {e}", path.display());
+                        continue;
+                    }
+                }
+            }
+            info!("AuditStore: retention pruned session {session_id}");
+            self.index.sessions.remove(&session_id);
+        }
+    }
+
+    async fn save_index(&self) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.index).map_err(std::io::Error::other)?;
+        let path = self.config.dir.join(INDEX_FILE);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json).await?;
+        tokio::fs::rename(&tmp, &path).await
+    }
+}
+
+/// Session IDs come from clients; keep file names to a safe character set.
+/// An id outside it gets a hash of the original after a `.` (which no safe id
+/// contains), so `a/b` and `a_b` never share files.
+fn sanitize(session_id: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if session_id.chars().all(safe) {
+        return session_id.to_string();
+    }
+    let name: String = session_id.chars().map(|c| if safe(c) { c } else { '_' }).collect();
+    format!("{name}.{}", hex::encode(&Sha256::digest(session_id.as_bytes())[..4]))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
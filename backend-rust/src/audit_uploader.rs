@@ -0,0 +1,273 @@
+//! # audit_uploader
+//!
+//! Ships audit blocks to the cloud `audit_log` table (Supabase or Aurora) without
+//! ever blocking the local append path.
+//!
+//! ## Design
+//! - `AuditLogger::append` hands each block to `AuditUploader::enqueue` (non-blocking `try_send`)
+//! - The uploader task persists every received block to an on-disk outbox
+//!   (`/data/audit_outbox.jsonl`) before attempting upload, so blocks queued during a
+//!   network drop on the committee boat survive a backend restart
+//! - Blocks are inserted in batches of up to `BATCH_SIZE`; a batch that fails to reach
+//!   the database is retried with exponential backoff (1 s → 60 s) and the outbox is
+//!   only trimmed after commit
+//! - A batch the database rejects is retried row by row; rows it still rejects are moved
+//!   to the dead-letter file (`/data/audit_deadletter.jsonl`) so one bad block never
+//!   holds back the rest of the chain
+//! - Inserts are idempotent (`ON CONFLICT (session_id, block_seq) DO NOTHING`) so a
+//!   retried batch that partially landed never duplicates rows
+//! - Rows are filed under the session UUID registered in `race_sessions`, not the
+//!   local session name, and mapped to the target's columns (`AuditTable`)
+//!
+//! ## Invariants
+//! - Core Invariant #2: local file remains the source of truth; cloud is a mirror
+//! - Core Invariant #8: upload failures never interrupt the race
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use sqlx::{Pool, Postgres, QueryBuilder};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::audit::AuditBlock;
+
+const OUTBOX_PATH: &str = "/data/audit_outbox.jsonl";
+const DEAD_LETTER_PATH: &str = "/data/audit_deadletter.jsonl";
+const CHANNEL_CAPACITY: usize = 4096;
+const BATCH_SIZE: usize = 100;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const BACKOFF_MIN: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// Which `audit_log` schema the target database has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditTable {
+    /// infra/supabase/migrations — `timestamp_ms` + `payload_json TEXT`
+    Supabase,
+    /// backend-rust/aws/001_aurora_schema.sql — whole block as `payload JSONB`
+    Aurora,
+}
+
+/// Handle used by `AuditLogger` to queue blocks for upload.
+#[derive(Clone)]
+pub struct AuditUploader {
+    tx: mpsc::Sender<AuditBlock>,
+}
+
+impl AuditUploader {
+    /// Spawn the uploader task against an existing Postgres pool.
+    /// `session` is the `race_sessions` row every block is filed under.
+    /// Any blocks left in the outbox from a previous run are uploaded first.
+    pub fn spawn(pool: Pool<Postgres>, table: AuditTable, session: Uuid) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(run_uploader(pool, table, session, rx, OUTBOX_PATH));
+        Self { tx }
+    }
+
+    /// Queue a block for upload. Never blocks; if the queue is saturated the block
+    /// is still safe in the local audit file and can be re-exported later.
+    pub fn enqueue(&self, block: AuditBlock) {
+        if let Err(e) = self.tx.try_send(block) {
+            warn!("AuditUploader: queue full, block not mirrored to cloud: {e}");
+        }
+    }
+}
+
+// ── Outbox persistence ────────────────────────────────────────────────────────
+
+async fn load_outbox(path: &str) -> VecDeque<AuditBlock> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(data) => data.lines()
+            .filter_map(|l| serde_json::from_str::<AuditBlock>(l).ok())
+            .collect(),
+        Err(_) => VecDeque::new(),
+    }
+}
+
+/// Append blocks as JSON lines (outbox and dead-letter file).
+async fn append_lines<'a>(path: &str, blocks: impl IntoIterator<Item = &'a AuditBlock>) {
+    let mut data = String::new();
+    for block in blocks {
+        if let Ok(l) = serde_json::to_string(block) {
+            data.push_str(&l);
+            data.push('\n');
+        }
+    }
+    if data.is_empty() {
+        return;
+    }
+    match OpenOptions::new().create(true).append(true).open(path).await {
+        Ok(mut f) => {
+            if let Err(e) = f.write_all(data.as_bytes()).await {
+                warn!("AuditUploader: write to {path} failed: {e}");
+            }
+        }
+        Err(e) => debug!("AuditUploader: {path} unavailable: {e}"),
+    }
+}
+
+async fn rewrite_outbox(path: &str, pending: &VecDeque<AuditBlock>) {
+    let mut data = String::new();
+    for block in pending {
+        if let Ok(l) = serde_json::to_string(block) {
+            data.push_str(&l);
+            data.push('\n');
+        }
+    }
+    // Write-then-rename so a crash mid-write never truncates the outbox
+    let tmp = format!("{path}.tmp");
+    if tokio::fs::write(&tmp, data).await.is_ok() {
+        let _ = tokio::fs::rename(&tmp, path).await;
+    }
+}
+
+// ── Upload ────────────────────────────────────────────────────────────────────
+
+async fn insert_batch(
+    pool: &Pool<Postgres>,
+    table: AuditTable,
+    session: Uuid,
+    batch: &[AuditBlock],
+) -> Result<(), sqlx::Error> {
+    let mut qb: QueryBuilder<Postgres> = match table {
+        AuditTable::Supabase => QueryBuilder::new(
+            "INSERT INTO audit_log (session_id, block_seq, event_type, timestamp_ms, prev_hash, payload_json, block_hash) ",
+        ),
+        AuditTable::Aurora => QueryBuilder::new(
+            "INSERT INTO audit_log (session_id, block_seq, event_type, prev_hash, payload, block_hash) ",
+        ),
+    };
+    qb.push_values(batch, |mut row, block| {
+        row.push_bind(session)
+            .push_bind(block.block_seq as i64)
+            .push_bind(block.event_type.to_string());
+        match table {
+            AuditTable::Supabase => {
+                row.push_bind(block.timestamp_ms as i64)
+                    .push_bind(block.prev_hash.clone())
+                    .push_bind(block.payload_json.clone());
+            }
+            AuditTable::Aurora => {
+                row.push_bind(block.prev_hash.clone())
+                    .push_bind(serde_json::to_value(block).unwrap_or_default());
+            }
+        }
+        row.push_bind(block.block_hash.clone());
+    });
+    qb.push(" ON CONFLICT (session_id, block_seq) DO NOTHING");
+    qb.build().execute(pool).await?;
+    Ok(())
+}
+
+/// The database answered and refused the rows (constraint, type, missing column).
+/// Retrying the same rows cannot succeed; anything else is treated as a network drop.
+fn is_rejection(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(_))
+}
+
+/// Insert a rejected batch row by row; returns the rows the database still refuses,
+/// or the first transport error.
+async fn insert_rows(
+    pool: &Pool<Postgres>,
+    table: AuditTable,
+    session: Uuid,
+    batch: &[AuditBlock],
+) -> Result<Vec<AuditBlock>, sqlx::Error> {
+    let mut rejected = Vec::new();
+    for block in batch {
+        match insert_batch(pool, table, session, std::slice::from_ref(block)).await {
+            Ok(()) => {}
+            Err(e) if is_rejection(&e) => {
+                warn!("AuditUploader: block {} rejected, moving to dead letter: {e}", block.block_seq);
+                rejected.push(block.clone());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(rejected)
+}
+
+async fn run_uploader(
+    pool: Pool<Postgres>,
+    table: AuditTable,
+    session: Uuid,
+    mut rx: mpsc::Receiver<AuditBlock>,
+    outbox_path: &'static str,
+) {
+    let mut pending = load_outbox(outbox_path).await;
+    if !pending.is_empty() {
+        info!("AuditUploader: resuming with {} blocks from outbox", pending.len());
+    }
+
+    let mut backoff = BACKOFF_MIN;
+    let mut next_attempt = Instant::now();
+
+    loop {
+        let wake_at = if pending.is_empty() { Instant::now() + FLUSH_INTERVAL } else { next_attempt.max(Instant::now()) };
+
+        tokio::select! {
+            received = rx.recv() => {
+                match received {
+                    Some(block) => {
+                        append_lines(outbox_path, [&block]).await;
+                        pending.push_back(block);
+                        // Flush eagerly once a full batch is waiting (unless backing off)
+                        if pending.len() < BATCH_SIZE {
+                            continue;
+                        }
+                        if backoff == BACKOFF_MIN {
+                            next_attempt = Instant::now();
+                        }
+                    }
+                    None => {
+                        info!("AuditUploader: channel closed, stopping");
+                        return;
+                    }
+                }
+            }
+            _ = tokio::time::sleep_until(wake_at) => {}
+        }
+
+        if pending.is_empty() || Instant::now() < next_attempt {
+            continue;
+        }
+
+        let n = pending.len().min(BATCH_SIZE);
+        let batch: Vec<AuditBlock> = pending.iter().take(n).cloned().collect();
+
+        let result = match insert_batch(&pool, table, session, &batch).await {
+            Err(e) if is_rejection(&e) => {
+                debug!("AuditUploader: batch rejected, retrying row by row: {e}");
+                insert_rows(&pool, table, session, &batch).await
+            }
+            other => other.map(|()| Vec::new()),
+        };
+
+        match result {
+            Ok(rejected) => {
+                if !rejected.is_empty() {
+                    append_lines(DEAD_LETTER_PATH, &rejected).await;
+                }
+                pending.drain(..n);
+                rewrite_outbox(outbox_path, &pending).await;
+                backoff = BACKOFF_MIN;
+                // Drain a backlog back-to-back; otherwise let the next batch accumulate
+                next_attempt = if pending.is_empty() { Instant::now() + FLUSH_INTERVAL } else { Instant::now() };
+                debug!(
+                    "AuditUploader: uploaded {} blocks, {} dead-lettered ({} pending)",
+                    n - rejected.len(), rejected.len(), pending.len()
+                );
+            }
+            Err(e) => {
+                warn!("AuditUploader: batch upload failed ({} pending), retrying in {:?}: {e}", pending.len(), backoff);
+                next_attempt = Instant::now() + backoff;
+                backoff = (backoff * 2).min(BACKOFF_MAX);
+            }
+        }
+    }
+}
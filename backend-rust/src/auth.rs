@@ -2,66 +2,230 @@ use jsonwebtoken::{decode, decode_header, DecodingKey, Validation, Algorithm};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{info, warn, error};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::warn;
 
-#[derive(Debug, Deserialize)]
-pub struct AppleJwks {
-    pub keys: Vec<AppleJwk>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-pub struct AppleJwk {
-    pub kty: String,
-    pub kid: String,
-    pub use_: Option<String>,
-    pub alg: String,
-    pub n: String,
-    pub e: String,
-}
+use crate::local_auth::LocalAuth;
+use crate::login_guard::{LoginGuard, LoginGuardConfig};
+use crate::oidc::{self, KeySet, OidcProvider};
+use crate::permissions::{PermissionMatrix, ALL_EVENTS};
+use crate::roles::{ConnectedClient, CustomRole, RoleError};
+use crate::sessions::{Session, SessionError};
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct AppleClaims {
-    pub iss: String,
+pub struct SupabaseClaims {
     pub sub: String,
-    pub aud: String,
-    pub iat: u64,
+    pub aud: SupabaseAudience,
     pub exp: u64,
-    pub email: Option<String>,
-    pub email_verified: Option<UnionStringOrBool>,
+    pub iss: Option<String>,
+    pub role: Option<String>,
+    pub app_metadata: Option<serde_json::Value>,
+    pub user_metadata: Option<serde_json::Value>,
 }
 
+/// Supabase issues `aud` as a string, but JWT allows a list.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
-pub enum UnionStringOrBool {
-    String(String),
-    Bool(bool),
+pub enum SupabaseAudience {
+    One(String),
+    Many(Vec<String>),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SupabaseClaims {
-    pub sub: String,
-    pub aud: String,
-    pub exp: u64,
+/// Where Supabase access tokens are verified against.
+///
+/// - `SUPABASE_URL` — the project; keys come from `<url>/auth/v1/.well-known/jwks.json`
+///   (asymmetric RS256/ES256 signing keys) and `iss` must be `<url>/auth/v1`
+/// - `SUPABASE_JWT_SECRET` — the legacy shared HS256 secret, if the project still uses it
+/// - `SUPABASE_JWT_AUDIENCE` — accepted `aud` values, comma separated (default `authenticated`)
+/// - `SUPABASE_JWKS_REFRESH_SECS` — key refresh interval (default 600)
+/// - `SUPABASE_JWT_LEEWAY_SECS` — clock skew allowed on `exp`/`nbf` (default 30)
+///
+/// With neither a URL nor a secret every Supabase token is rejected.
+pub struct SupabaseAuthConfig {
+    pub auth_url: Option<String>,
+    pub jwt_secret: Option<String>,
+    pub audience: Vec<String>,
+    pub refresh: Duration,
+    pub leeway_secs: u64,
+}
+
+impl Default for SupabaseAuthConfig {
+    fn default() -> Self {
+        let env = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        Self {
+            auth_url: env("SUPABASE_URL").map(|url| format!("{}/auth/v1", url.trim_end_matches('/'))),
+            jwt_secret: env("SUPABASE_JWT_SECRET"),
+            audience: env("SUPABASE_JWT_AUDIENCE")
+                .map(|v| v.split(',').map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect())
+                .unwrap_or_else(|| vec!["authenticated".to_string()]),
+            refresh: Duration::from_secs(env("SUPABASE_JWKS_REFRESH_SECS").and_then(|v| v.parse().ok()).unwrap_or(600)),
+            leeway_secs: env("SUPABASE_JWT_LEEWAY_SECS").and_then(|v| v.parse().ok()).unwrap_or(30),
+        }
+    }
+}
+
+/// A token accepted by `AuthEngine::resolve_token`.
+#[derive(Debug, Clone)]
+pub struct ResolvedToken {
+    pub role: String,
+    pub sub: Option<String>,
+    /// Expiry, seconds since the epoch; legacy role strings have none
+    pub exp: Option<u64>,
+    /// Boat the token is bound to: an offline tracker token's, a Supabase user's
+    /// `app_metadata.boat_id`, an ID token's `boat_id` claim
+    pub boat_id: Option<String>,
+    /// `supabase`, `oidc:<provider>`, `local` or `legacy`
+    pub method: String,
+}
+
+/// Who is behind a socket, as far as the backend can tell (see `AuthEventKind`).
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientIdentity {
+    pub socket_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub role: Option<String>,
-    pub app_metadata: Option<serde_json::Value>,
-    pub user_metadata: Option<serde_json::Value>,
+    /// Token subject (Supabase user id, provider subject or local user name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boat_id: Option<String>,
+    /// Client address from the proxy headers of the handshake
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
 }
 
 pub struct AuthEngine {
-    keys: RwLock<HashMap<String, DecodingKey>>,
+    supabase: SupabaseAuthConfig,
+    supabase_keys: RwLock<KeySet>,
+    supabase_fetched: Mutex<Option<Instant>>,
     roles: RwLock<HashMap<String, String>>, // socket_id -> role
     tracker_sockets: RwLock<HashMap<String, String>>, // socket_id -> boat_id
+    permissions: PermissionMatrix,
+    custom_roles: RwLock<Vec<CustomRole>>, // active event's roles, see `roles`
+    sessions: RwLock<HashMap<String, Session>>, // socket_id -> token expiry, see `sessions`
+    identities: RwLock<HashMap<String, ClientIdentity>>, // socket_id -> identity at register
+    local: LocalAuth,
+    providers: Vec<OidcProvider>, // Apple, Google and generic issuers, see `oidc`
+    officials: HashMap<String, String>, // verified email -> role
+    login_guard: LoginGuard,
 }
 
 impl AuthEngine {
     pub fn new() -> Arc<Self> {
         Arc::new(Self {
-            keys: RwLock::new(HashMap::new()),
+            supabase: SupabaseAuthConfig::default(),
+            supabase_keys: RwLock::new(HashMap::new()),
+            supabase_fetched: Mutex::new(None),
             roles: RwLock::new(HashMap::new()),
             tracker_sockets: RwLock::new(HashMap::new()),
+            permissions: PermissionMatrix::load(),
+            custom_roles: RwLock::new(Vec::new()),
+            sessions: RwLock::new(HashMap::new()),
+            identities: RwLock::new(HashMap::new()),
+            local: LocalAuth::load(),
+            providers: oidc::providers().into_iter().map(OidcProvider::new).collect(),
+            officials: oidc::officials(),
+            login_guard: LoginGuard::new(LoginGuardConfig::default()),
         })
     }
+
+    /// Throttling of `register` attempts.
+    pub fn login_guard(&self) -> &LoginGuard {
+        &self.login_guard
+    }
+
+    /// The permission matrix, then the active event's custom roles.
+    pub async fn authorize(&self, event: &str, role: Option<&str>) -> bool {
+        if self.permissions.authorize(event, role) {
+            return true;
+        }
+        let Some(role) = role else { return false };
+        self.custom_roles.read().await.iter()
+            .find(|r| r.name == role)
+            .is_some_and(|r| r.events.iter().any(|e| e == ALL_EVENTS || e == event))
+    }
+
+    pub async fn custom_roles(&self) -> Vec<CustomRole> {
+        self.custom_roles.read().await.clone()
+    }
+
+    pub async fn set_custom_roles(&self, roles: Vec<CustomRole>) {
+        *self.custom_roles.write().await = roles;
+    }
+
+    /// Add or replace a custom role; returns the new list to persist.
+    pub async fn define_role(&self, role: CustomRole) -> Result<Vec<CustomRole>, RoleError> {
+        if role.name.trim().is_empty() {
+            return Err(RoleError::Unnamed);
+        }
+        if self.permissions.has_role(&role.name) {
+            return Err(RoleError::BuiltIn(role.name));
+        }
+        let mut roles = self.custom_roles.write().await;
+        match roles.iter_mut().find(|r| r.name == role.name) {
+            Some(existing) => *existing = role,
+            None => roles.push(role),
+        }
+        Ok(roles.clone())
+    }
+
+    /// Remove a custom role no connection holds; returns the new list to persist.
+    pub async fn delete_role(&self, name: &str) -> Result<Vec<CustomRole>, RoleError> {
+        if self.permissions.has_role(name) {
+            return Err(RoleError::BuiltIn(name.to_string()));
+        }
+        let held = self.roles.read().await.values().filter(|r| *r == name).count();
+        if held > 0 {
+            return Err(RoleError::InUse(name.to_string(), held));
+        }
+        let mut roles = self.custom_roles.write().await;
+        let before = roles.len();
+        roles.retain(|r| r.name != name);
+        if roles.len() == before {
+            return Err(RoleError::UnknownRole(name.to_string()));
+        }
+        Ok(roles.clone())
+    }
+
+    pub async fn clients(&self) -> Vec<ConnectedClient> {
+        let roles = self.roles.read().await;
+        let trackers = self.tracker_sockets.read().await;
+        let mut clients: Vec<ConnectedClient> = roles.iter()
+            .map(|(sid, role)| ConnectedClient {
+                socket_id: sid.clone(),
+                role: role.clone(),
+                boat_id: trackers.get(sid).cloned(),
+            })
+            .collect();
+        clients.sort_by(|a, b| a.role.cmp(&b.role).then_with(|| a.socket_id.cmp(&b.socket_id)));
+        clients
+    }
+
+    /// Move a connection to another role; returns its previous role.
+    pub async fn change_role(&self, socket_id: &str, role: &str, boat_id: Option<&str>) -> Result<String, RoleError> {
+        let known = self.permissions.has_role(role) || self.custom_roles.read().await.iter().any(|r| r.name == role);
+        if !known {
+            return Err(RoleError::UnknownRole(role.to_string()));
+        }
+        let previous = self.get_role(socket_id).await.ok_or_else(|| RoleError::NoSuchClient(socket_id.to_string()))?;
+        if role == "tracker" {
+            let boat_id = match boat_id {
+                Some(boat_id) => boat_id.to_string(),
+                None => self.get_tracker_boat(socket_id).await.ok_or(RoleError::NeedsBoat)?,
+            };
+            self.set_tracker_boat(socket_id, &boat_id).await;
+        } else {
+            self.remove_tracker_boat(socket_id).await;
+        }
+        self.set_role(socket_id, role).await;
+        Ok(previous)
+    }
     
     pub async fn set_role(&self, socket_id: &str, role: &str) {
         let mut roles = self.roles.write().await;
@@ -93,80 +257,180 @@ impl AuthEngine {
         trackers.remove(socket_id)
     }
 
-    pub async fn refresh_apple_keys(&self) {
-        info!("Fetching latest Apple public keys from appleid.apple.com/auth/keys...");
-        match reqwest::get("https://appleid.apple.com/auth/keys").await {
-            Ok(res) => {
-                if let Ok(jwks) = res.json::<AppleJwks>().await {
-                    let mut cache = self.keys.write().await;
-                    cache.clear();
-                    let count = jwks.keys.len();
-                    for jwk in jwks.keys {
-                        if let Ok(decoding_key) = DecodingKey::from_rsa_components(&jwk.n, &jwk.e) {
-                            cache.insert(jwk.kid.clone(), decoding_key);
-                        }
-                    }
-                    info!("Successfully cached {} Apple cryptographic keys.", count);
-                } else {
-                    error!("Failed to parse Apple JWKS payload");
-                }
-            }
-            Err(e) => {
-                error!("Network failure pulling Apple JWKS: {}", e);
-            }
+    pub async fn set_identity(&self, identity: ClientIdentity) {
+        self.identities.write().await.insert(identity.socket_id.clone(), identity);
+    }
+
+    pub async fn remove_identity(&self, socket_id: &str) {
+        self.identities.write().await.remove(socket_id);
+    }
+
+    /// The socket's identity with its current role and boat.
+    pub async fn identity(&self, socket_id: &str) -> ClientIdentity {
+        let mut identity = self.identities.read().await.get(socket_id).cloned()
+            .unwrap_or_else(|| ClientIdentity { socket_id: socket_id.to_string(), ..Default::default() });
+        identity.role = self.get_role(socket_id).await;
+        identity.boat_id = self.get_tracker_boat(socket_id).await.or(identity.boat_id);
+        identity
+    }
+
+    pub async fn start_session(&self, socket_id: &str, session: Session) {
+        self.sessions.write().await.insert(socket_id.to_string(), session);
+    }
+
+    pub async fn end_session(&self, socket_id: &str) {
+        self.sessions.write().await.remove(socket_id);
+    }
+
+    pub async fn sessions(&self) -> Vec<(String, Session)> {
+        self.sessions.read().await.iter().map(|(sid, s)| (sid.clone(), s.clone())).collect()
+    }
+
+    pub async fn update_session(&self, socket_id: &str, update: impl FnOnce(&mut Session)) {
+        if let Some(session) = self.sessions.write().await.get_mut(socket_id) {
+            update(session);
         }
     }
 
-    /// Verifies the token and returns the subject (Apple ID) if successful.
-    pub async fn verify_apple_token(&self, token: &str, client_id: &str) -> Option<String> {
-        let header = match decode_header(token) {
-            Ok(h) => h,
-            Err(_) => {
-                warn!("Invalid JWT header format");
-                return None;
-            }
+    /// Extend a session with a fresh token of the same user; returns the new expiry (ms).
+    pub async fn refresh_session(&self, socket_id: &str, token: &str) -> Result<i64, SessionError> {
+        let resolved = self.resolve_token(token).await.ok_or(SessionError::InvalidToken)?;
+        let (Some(sub), Some(exp)) = (resolved.sub, resolved.exp) else {
+            return Err(SessionError::InvalidToken);
         };
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.get_mut(socket_id).ok_or(SessionError::NoSession)?;
+        if session.sub != sub {
+            return Err(SessionError::OtherUser);
+        }
+        *session = Session::new(sub, exp);
+        Ok(session.expires_ms)
+    }
 
-        let kid = match header.kid {
-            Some(k) => k,
-            None => {
-                warn!("JWT is missing 'kid' header field");
-                return None;
-            }
+    /// Role and subject of a token: a Supabase JWT, an identity provider's ID
+    /// token (see `oidc`), an offline token (see `local_auth`), or — in
+    /// development only — a legacy role string.
+    pub async fn resolve_token(&self, token: &str) -> Option<ResolvedToken> {
+        // Only tokens whose issuer is a configured provider go there
+        if let Some(resolved) = self.verify_oidc_token(token).await {
+            return Some(resolved);
+        }
+        if let Some(claims) = self.verify_supabase_token(token).await {
+            let role = claims.role.clone()
+                // Fallback: app_metadata carries custom roles
+                .or_else(|| claims.app_metadata.as_ref()?.get("role")?.as_str().map(str::to_string))
+                // Default authenticated Supabase users to "tracker"
+                .unwrap_or_else(|| "tracker".to_string());
+            // app_metadata, unlike user_metadata, only the project's service role can write
+            let boat_id = claims.app_metadata.as_ref()
+                .and_then(|m| m.get("boat_id")?.as_str().map(str::to_string));
+            return Some(ResolvedToken { role, sub: Some(claims.sub), exp: Some(claims.exp), boat_id, method: "supabase".to_string() });
+        }
+        if let Some(claims) = self.local.verify_token(token) {
+            return Some(ResolvedToken { role: claims.role, sub: Some(claims.sub), exp: Some(claims.exp), boat_id: claims.boat_id, method: "local".to_string() });
+        }
+        let role = self.local.legacy_role(token)?;
+        Some(ResolvedToken { role, sub: None, exp: None, boat_id: None, method: "legacy".to_string() })
+    }
+
+    /// Check local credentials; on success returns the login and an offline token for it.
+    /// The PBKDF2 derivation runs on the blocking pool, off the socket runtime.
+    pub async fn login(self: &Arc<Self>, name: &str, password: &str) -> Option<(ResolvedToken, Option<String>)> {
+        let (engine, name, password) = (self.clone(), name.to_string(), password.to_string());
+        tokio::task::spawn_blocking(move || engine.check_login(&name, &password)).await.ok().flatten()
+    }
+
+    fn check_login(&self, name: &str, password: &str) -> Option<(ResolvedToken, Option<String>)> {
+        let user = self.local.login(name, password)?;
+        let issued = self.local.issue(&user.name, &user.role, user.boat_id.as_deref(), None)
+            .map_err(|e| warn!("Local login of {name}: no offline token issued: {e}"))
+            .ok();
+        let resolved = ResolvedToken {
+            role: user.role.clone(),
+            sub: Some(user.name.clone()),
+            exp: issued.as_ref().map(|(_, claims)| claims.exp),
+            boat_id: user.boat_id.clone(),
+            method: "local".to_string(),
         };
+        Some((resolved, issued.map(|(token, _)| token)))
+    }
 
-        let keys = self.keys.read().await;
-        let decoding_key = match keys.get(&kid) {
-            Some(key) => key,
-            None => {
-                warn!("Key {} not found in local cache. Token rejected.", kid);
-                return None;
-            }
+    pub async fn refresh_oidc_keys(&self) {
+        for provider in &self.providers {
+            provider.refresh_keys().await;
+        }
+    }
+
+    /// Verifies an ID token of the provider that issued it and maps it to a role.
+    async fn verify_oidc_token(&self, token: &str) -> Option<ResolvedToken> {
+        let iss = oidc::unverified_issuer(token)?;
+        let provider = self.providers.iter().find(|p| p.issues(&iss))?;
+        let claims = provider.verify(token, self.supabase.leeway_secs).await?;
+        let sub = claims.get("sub")?.as_str()?.to_string();
+        let Some(role) = oidc::role(&provider.config, &claims, &self.officials) else {
+            warn!("OIDC {}: {sub} authenticated but holds no role", provider.config.name);
+            return None;
         };
+        Some(ResolvedToken {
+            role,
+            sub: Some(sub),
+            exp: claims.get("exp").and_then(serde_json::Value::as_u64),
+            boat_id: claims.get("boat_id").and_then(serde_json::Value::as_str).map(str::to_string),
+            method: format!("oidc:{}", provider.config.name),
+        })
+    }
 
-        let mut validation = Validation::new(Algorithm::RS256);
-        validation.set_audience(&[client_id]);
-        validation.set_issuer(&["https://appleid.apple.com"]);
+    pub fn supabase_refresh_interval(&self) -> Duration {
+        self.supabase.refresh
+    }
 
-        match decode::<AppleClaims>(token, decoding_key, &validation) {
-            Ok(token_data) => Some(token_data.claims.sub),
-            Err(e) => {
-                warn!("Cryptographic validation failed: {}", e);
-                None
-            }
+    pub async fn refresh_supabase_keys(&self) {
+        let Some(auth_url) = &self.supabase.auth_url else { return };
+        *self.supabase_fetched.lock().await = Some(Instant::now());
+        let url = format!("{auth_url}/.well-known/jwks.json");
+        if let Some(keys) = oidc::fetch_jwks("Supabase", &url).await {
+            *self.supabase_keys.write().await = keys;
         }
     }
 
-    /// Verifies a Supabase JWT and returns the parsed claims.
-    pub fn verify_supabase_token(token: &str) -> Option<SupabaseClaims> {
-        let secret = std::env::var("SUPABASE_JWT_SECRET").unwrap_or_else(|_| "your-jwt-secret-here".into());
-        let decoding_key = DecodingKey::from_secret(secret.as_bytes());
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.insecure_disable_signature_validation(); // Temporarily disable audience checks if needed, but signature is enforced
-        validation.set_audience(&["authenticated", "anon"]);
-        
-        // Sometimes Supabase JWT audience is just empty or different, let's skip aud validation
-        validation.validate_aud = false;
+    /// Signing key for a Supabase token header: the shared secret for HS256,
+    /// else the cached JWKS key for `kid` (refetched once if it is unknown).
+    async fn supabase_key(&self, alg: Algorithm, kid: Option<&str>) -> Option<DecodingKey> {
+        if alg == Algorithm::HS256 {
+            return self.supabase.jwt_secret.as_ref().map(|s| DecodingKey::from_secret(s.as_bytes()));
+        }
+        let kid = kid?;
+        let cached = |keys: &KeySet| {
+            keys.get(kid).filter(|(_, a)| *a == alg).map(|(k, _)| k.clone())
+        };
+        if let Some(key) = cached(&*self.supabase_keys.read().await) {
+            return Some(key);
+        }
+        let stale = self.supabase_fetched.lock().await
+            .is_none_or(|at| at.elapsed() >= oidc::MISS_REFETCH);
+        if !stale {
+            return None;
+        }
+        self.refresh_supabase_keys().await;
+        cached(&*self.supabase_keys.read().await)
+    }
+
+    /// Verifies a Supabase JWT (signature, `exp`, `aud` and, with `SUPABASE_URL`,
+    /// `iss`) and returns the parsed claims.
+    pub async fn verify_supabase_token(&self, token: &str) -> Option<SupabaseClaims> {
+        let header = decode_header(token).ok()?;
+        let Some(decoding_key) = self.supabase_key(header.alg, header.kid.as_deref()).await else {
+            warn!("Supabase JWT rejected: no {:?} key for kid {:?}", header.alg, header.kid);
+            return None;
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&self.supabase.audience);
+        if let Some(issuer) = &self.supabase.auth_url {
+            validation.set_issuer(&[issuer]);
+        }
+        validation.set_required_spec_claims(&["exp", "sub", "aud"]);
+        validation.leeway = self.supabase.leeway_secs;
 
         match decode::<SupabaseClaims>(token, &decoding_key, &validation) {
             Ok(token_data) => Some(token_data.claims),
@@ -1,65 +1,577 @@
-use std::time::Duration;
+//! # auto_director
+//!
+//! SRS Auto-Director: picks the boats worth watching, re-evaluated every 2 s and
+//! straight away when the race moves (gun, status change).
+//!
+//! Each boat is scored by weighted heuristics; the weights are the event's
+//! `AutoDirectorConfig` (persisted with the state), so broadcasters can tune
+//! what "exciting" means per event:
+//!
+//! - **speed** — against the class target where one is known (so every class
+//!   gets a look-in), else knots
+//! - **proximity** — within 50 m of a mark or the start line
+//! - **rank change** — places gained or lost in the last 30 s
+//! - **OCS risk** — while a gun is pending, predicted to be (nearly) over at the gun
+//! - **incident** — in a crossing, mark overlap or penalty (see `incidents`),
+//!   by the incident's priority
+//!
+//! ## Gun window
+//! In the last `gunWindowSecs` before a gun (default 60, 0 = off) the director
+//! watches the start line instead: speed and rank changes no longer count, and a
+//! boat scores by
+//!
+//! - **line** — its UWB distance to the line (the tracker's `dtl`, the predicted
+//!   one without it), full at the line, nothing 50 m off (`proximityWeight`)
+//! - **predicted OCS** — predicted over at the gun (or already over on UWB), at
+//!   twice the race weight (`ocsRiskWeight`)
+//!
+//! Boats likely to be over cut into the focus without waiting for the dwell.
+//! Switching is announced as `director-mode { mode: "GUN_WINDOW" | "RACE",
+//! secsToGun?, timestamp }`.
+//!
+//! ## Focus
+//! The focus is a list of `focusCount` slots. A boat in a slot stays for at least
+//! `minDwellSecs` (default 10), and is then only displaced by a challenger
+//! scoring `hysteresis` (default 5) more than it, so the list does not churn
+//! between boats of similar score. A boat in an incident does not wait for the
+//! dwell. Each change is an event of its own:
+//!
+//! - `focus-added { boatId, slot, score, timestamp }`
+//! - `focus-removed { boatId, slot, reason, timestamp }` — `displaced`, `gone`,
+//!   `slots` (fewer slots configured), `pinned` or `excluded`
+//!
+//! `focus_boats_changed { focus_boats, timestamp }` still carries the whole list
+//! every evaluation, in slot order, for clients that join late.
+//!
+//! Along the way it marks the highlights of the race (see `highlights`).
+//!
+//! The focus list also drives the per-camera shots of `camera_assignment` and
+//! the gimbal solutions of `camera_pointing`.
+//!
+//! ## Overrides
+//! `director-override { action, boatId?, durationSecs? }` (director, media):
+//!
+//! - `pin` / `unpin` — a pinned boat takes a slot at once (the weakest unpinned
+//!   boat makes way) and keeps it until unpinned
+//! - `exclude` / `include` — an excluded boat leaves the focus and stays out
+//! - `suspend` (`durationSecs`, up to an hour) / `resume` — automation pauses:
+//!   focus and camera shots hold, only pins and exclusions change them
+//! - `clear` — all of the above
+//!
+//! The overrides are kept with the state and sent to everyone as
+//! `director-overrides`, also when a suspension runs out. Each command is in the
+//! audit chain. On resuming, every focused boat and camera shot gets a fresh
+//! dwell, so the automation takes over one change at a time.
+//!
+//! `director-config` reads the config back (no payload) or changes it (any of
+//! its fields) → `director-config` to everyone, applied on the next tick.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
 use socketioxide::SocketIo;
+use tokio::sync::{broadcast, watch};
 use tokio::time::interval;
 use tracing::info;
-use serde_json::json;
+use serde::Serialize;
+use serde_json::{json, Value};
 
+use crate::camera_assignment::{Assignment, CameraAssigner, Shots};
+use crate::engine_bus::{self, EngineEventEnvelope};
 use crate::handlers::SharedState;
+use crate::highlights::{self, HighlightWatch};
+use crate::incidents::IncidentDetector;
+use crate::procedure_engine::EngineEvent;
+use crate::persistence::save_state;
+use crate::state::{AutoDirectorConfig, BoatState, DirectorOverrides, Highlight, RaceState, RaceStatus};
+
+/// How long a rank change keeps a boat interesting
+const RANK_CHANGE_WINDOW: Duration = Duration::from_secs(30);
+/// Boats further off the line than this score nothing for it in the gun window
+const GUN_WINDOW_LINE_RANGE_M: f64 = 50.0;
+/// OCS likelihood from which a boat skips the dwell in the gun window
+const GUN_WINDOW_URGENT_RISK: f64 = 0.5;
+/// Most boats a config may keep in focus
+const MAX_FOCUS: usize = 20;
+
+/// Longest a `suspend` may pause the automation
+const MAX_SUSPEND_SECS: u64 = 3600;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DirectorConfigError {
+    #[error("Invalid director config: {0}")]
+    Invalid(String),
+    #[error("Invalid director override: {0}")]
+    Override(String),
+}
+
+/// The overrides with the `director-override` command `data` applied.
+pub fn apply_override(current: &DirectorOverrides, data: &Value, now_ms: i64) -> Result<DirectorOverrides, DirectorConfigError> {
+    let mut overrides = current.clone();
+    let action = data.get("action").and_then(Value::as_str).unwrap_or_default();
+    let boat_id = || {
+        data.get("boatId").and_then(Value::as_str).filter(|b| !b.is_empty()).map(str::to_string)
+            .ok_or_else(|| DirectorConfigError::Override(format!("{action} needs a boatId")))
+    };
+    match action {
+        "pin" => {
+            let boat_id = boat_id()?;
+            overrides.excluded.retain(|b| *b != boat_id);
+            if !overrides.pinned.contains(&boat_id) {
+                overrides.pinned.push(boat_id);
+            }
+        }
+        "unpin" => {
+            let boat_id = boat_id()?;
+            overrides.pinned.retain(|b| *b != boat_id);
+        }
+        "exclude" => {
+            let boat_id = boat_id()?;
+            overrides.pinned.retain(|b| *b != boat_id);
+            if !overrides.excluded.contains(&boat_id) {
+                overrides.excluded.push(boat_id);
+            }
+        }
+        "include" => {
+            let boat_id = boat_id()?;
+            overrides.excluded.retain(|b| *b != boat_id);
+        }
+        "suspend" => {
+            let secs = data.get("durationSecs").and_then(Value::as_u64)
+                .filter(|s| (1..=MAX_SUSPEND_SECS).contains(s))
+                .ok_or_else(|| DirectorConfigError::Override(format!("suspend needs durationSecs 1 to {MAX_SUSPEND_SECS}")))?;
+            overrides.suspended_until_ms = Some(now_ms + secs as i64 * 1000);
+        }
+        "resume" => overrides.suspended_until_ms = None,
+        "clear" => overrides = DirectorOverrides::default(),
+        other => return Err(DirectorConfigError::Override(format!("unknown action '{other}'"))),
+    }
+    Ok(overrides)
+}
+
+/// The current config with the fields of `patch` applied.
+pub fn patch_config(current: &AutoDirectorConfig, patch: &Value) -> Result<AutoDirectorConfig, DirectorConfigError> {
+    let mut merged = serde_json::to_value(current).map_err(|e| DirectorConfigError::Invalid(e.to_string()))?;
+    if let (Some(merged), Some(patch)) = (merged.as_object_mut(), patch.as_object()) {
+        for (key, value) in patch {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    let config: AutoDirectorConfig = serde_json::from_value(merged).map_err(|e| DirectorConfigError::Invalid(e.to_string()))?;
+    let weights = [
+        config.speed_weight,
+        config.proximity_weight,
+        config.rank_change_weight,
+        config.ocs_risk_weight,
+        config.incident_weight,
+        config.hysteresis,
+        config.min_dwell_secs,
+        config.gun_window_secs,
+    ];
+    if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+        return Err(DirectorConfigError::Invalid("weights, hysteresis and dwell must be 0 or more".to_string()));
+    }
+    if !(1..=MAX_FOCUS).contains(&config.focus_count) {
+        return Err(DirectorConfigError::Invalid(format!("focusCount must be 1 to {MAX_FOCUS}")));
+    }
+    let mut ids = std::collections::HashSet::new();
+    for camera in &config.cameras {
+        if camera.id.is_empty() || !ids.insert(camera.id.as_str()) {
+            return Err(DirectorConfigError::Invalid("cameras need distinct, non-empty ids".to_string()));
+        }
+        if camera.pos.is_none() && camera.follow_id.is_none() {
+            return Err(DirectorConfigError::Invalid(format!("camera {} needs a pos or a followId", camera.id)));
+        }
+        let lens_ok = camera.height_m.is_finite()
+            && camera.wide_fov_deg > 0.0 && camera.wide_fov_deg < 180.0
+            && camera.max_zoom >= 1.0
+            && camera.frame_m > 0.0
+            && camera.dwell_secs.is_none_or(|d| d.is_finite() && d >= 0.0);
+        if !lens_ok {
+            return Err(DirectorConfigError::Invalid(format!(
+                "camera {}: heightM must be a number, wideFovDeg 0 to 180, maxZoom at least 1, frameM above 0 and dwellSecs 0 or more",
+                camera.id
+            )));
+        }
+    }
+    Ok(config)
+}
+
+/// What the last evaluation decided, for the camera-pointing feed.
+#[derive(Debug, Clone, Default)]
+pub struct DirectorFocus {
+    /// Focus boats, slot order
+    pub boats: Vec<String>,
+    pub cameras: Vec<Assignment>,
+    /// Camera to put on air (see `vision_switcher`)
+    pub program: Option<String>,
+}
+
+/// Latest rank change of a boat.
+struct RankMove {
+    rank: u32,
+    places: u32,
+    at: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum FocusChange {
+    #[serde(rename_all = "camelCase")]
+    Added { boat_id: String, slot: usize, score: f64 },
+    #[serde(rename_all = "camelCase")]
+    Removed { boat_id: String, slot: usize, reason: &'static str },
+}
+
+struct Focused {
+    boat_id: String,
+    since: Instant,
+}
+
+/// The focus slots, with hysteresis and dwell.
+#[derive(Default)]
+pub struct FocusTracker {
+    slots: Vec<Option<Focused>>,
+}
+
+impl FocusTracker {
+    pub fn boats(&self) -> Vec<String> {
+        self.slots.iter().flatten().map(|f| f.boat_id.clone()).collect()
+    }
+
+    /// Give every focused boat a fresh dwell, so the focus changes one boat at a
+    /// time after a pause rather than all at once.
+    pub fn restart_dwell(&mut self, now: Instant) {
+        for focused in self.slots.iter_mut().flatten() {
+            focused.since = now;
+        }
+    }
 
-pub async fn start_auto_director(shared: SharedState, io: SocketIo) {
+    /// Apply a tick's scores (highest first); returns what changed. Boats in
+    /// `urgent` may displace a focused boat still in its dwell. Pinned boats
+    /// take a slot straight away and keep it; while `suspended` only the
+    /// overrides change the focus.
+    pub fn update(
+        &mut self,
+        ranked: &[(String, f64)],
+        urgent: &HashMap<String, f64>,
+        overrides: &DirectorOverrides,
+        suspended: bool,
+        config: &AutoDirectorConfig,
+        now: Instant,
+    ) -> Vec<FocusChange> {
+        let mut changes = Vec::new();
+        let score_of = |id: &str| ranked.iter().find(|(b, _)| b == id).map(|(_, s)| *s);
+
+        // Boats that left the fleet, and slots beyond the configured count
+        for (slot, entry) in self.slots.iter_mut().enumerate() {
+            let reason = match entry {
+                Some(f) if slot >= config.focus_count => Some(("slots", f.boat_id.clone())),
+                Some(f) if score_of(&f.boat_id).is_none() => Some(("gone", f.boat_id.clone())),
+                Some(f) if overrides.excluded.contains(&f.boat_id) => Some(("excluded", f.boat_id.clone())),
+                _ => None,
+            };
+            if let Some((reason, boat_id)) = reason {
+                changes.push(FocusChange::Removed { boat_id, slot, reason });
+                *entry = None;
+            }
+        }
+        self.slots.resize_with(config.focus_count, || None);
+
+        // Pins: an empty slot, else the weakest unpinned boat, dwell or not
+        for boat_id in overrides.pinned.iter().filter(|b| score_of(b).is_some()) {
+            if self.slots.iter().flatten().any(|f| &f.boat_id == boat_id) {
+                continue;
+            }
+            let slot = self.slots.iter().position(Option::is_none).or_else(|| {
+                self.slots.iter().enumerate()
+                    .filter_map(|(slot, f)| f.as_ref().map(|f| (slot, f)))
+                    .filter(|(_, f)| !overrides.pinned.contains(&f.boat_id))
+                    .map(|(slot, f)| (slot, score_of(&f.boat_id).unwrap_or(0.0)))
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+                    .map(|(slot, _)| slot)
+            });
+            // Every slot pinned already
+            let Some(slot) = slot else { break };
+            if let Some(displaced) = self.slots[slot].take() {
+                changes.push(FocusChange::Removed { boat_id: displaced.boat_id, slot, reason: "pinned" });
+            }
+            self.slots[slot] = Some(Focused { boat_id: boat_id.clone(), since: now });
+            changes.push(FocusChange::Added { boat_id: boat_id.clone(), slot, score: score_of(boat_id).unwrap_or(0.0) });
+        }
+        if suspended {
+            return changes;
+        }
+
+        let dwell = Duration::from_secs_f64(config.min_dwell_secs);
+        for (boat_id, score) in ranked {
+            if overrides.excluded.contains(boat_id) || self.slots.iter().flatten().any(|f| &f.boat_id == boat_id) {
+                continue;
+            }
+            // An empty slot first, else the weakest focused boat past its dwell
+            let skip_dwell = urgent.contains_key(boat_id);
+            let slot = match self.slots.iter().position(Option::is_none) {
+                Some(slot) => Some(slot),
+                None => self.slots.iter().enumerate()
+                    .filter_map(|(slot, f)| f.as_ref().map(|f| (slot, f)))
+                    .filter(|(_, f)| !overrides.pinned.contains(&f.boat_id))
+                    .filter(|(_, f)| skip_dwell || now.saturating_duration_since(f.since) >= dwell)
+                    .map(|(slot, f)| (slot, score_of(&f.boat_id).unwrap_or(0.0)))
+                    .filter(|(_, held)| *score > held + config.hysteresis)
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+                    .map(|(slot, _)| slot),
+            };
+            // Candidates come highest first: if this one cannot get in, only an incident boat after it can
+            let Some(slot) = slot else {
+                if urgent.is_empty() {
+                    break;
+                }
+                continue;
+            };
+            if let Some(displaced) = self.slots[slot].take() {
+                changes.push(FocusChange::Removed { boat_id: displaced.boat_id, slot, reason: "displaced" });
+            }
+            self.slots[slot] = Some(Focused { boat_id: boat_id.clone(), since: now });
+            changes.push(FocusChange::Added { boat_id: boat_id.clone(), slot, score: *score });
+        }
+        changes
+    }
+}
+
+impl FocusChange {
+    pub fn event(&self) -> &'static str {
+        match self {
+            Self::Added { .. } => "focus-added",
+            Self::Removed { .. } => "focus-removed",
+        }
+    }
+}
+
+/// Seconds to the gun while inside the configured gun window.
+fn gun_window(state: &RaceState, config: &AutoDirectorConfig, now_ms: i64) -> Option<f64> {
+    let prestart = matches!(state.status, RaceStatus::Warning | RaceStatus::Preparatory | RaceStatus::OneMinute);
+    let secs = state.start_time.map(|gun| (gun - now_ms) as f64 / 1000.0)?;
+    (prestart && config.gun_window_secs > 0.0 && secs > 0.0 && secs <= config.gun_window_secs).then_some(secs)
+}
+
+/// How likely a boat is to be over at the gun, 0–1: predicted (from 5 m behind
+/// the line), or over on UWB right now.
+fn ocs_risk(telemetry: &BoatState) -> f64 {
+    match telemetry.line_prediction.as_ref().and_then(|p| p.dtl_at_gun_m) {
+        Some(dtl_at_gun) => ((dtl_at_gun + 5.0) / 5.0).clamp(0.0, 1.0),
+        None if telemetry.dtl > 0.0 => 1.0,
+        None => 0.0,
+    }
+}
+
+/// Gun-window score: closeness to the line and the risk of being over.
+fn score_gun_window(config: &AutoDirectorConfig, boat_id: &str, telemetry: &BoatState, incident: Option<f64>) -> f64 {
+    let mut score = 0.0;
+
+    // UWB distance to line where the tracker has it, else the predicted one
+    let dtl_m = if telemetry.dtl != 0.0 {
+        telemetry.dtl
+    } else {
+        telemetry.line_prediction.as_ref().map_or(GUN_WINDOW_LINE_RANGE_M, |p| p.dtl_m)
+    };
+    score += config.proximity_weight * (1.0 - dtl_m.abs() / GUN_WINDOW_LINE_RANGE_M).max(0.0) * 20.0;
+
+    score += config.ocs_risk_weight * ocs_risk(telemetry) * 40.0;
+    score += config.incident_weight * incident.unwrap_or(0.0);
+
+    // Tie-breaking jitter
+    score + boat_id.len() as f64 * 0.01
+}
+
+fn score_boat(state: &RaceState, config: &AutoDirectorConfig, boat_id: &str, telemetry: &BoatState, rank_move: Option<&RankMove>, incident: Option<f64>, now: Instant) -> f64 {
+    let mut score = 0.0;
+
+    // Speed (faster = more exciting = higher score)
+    let target = crate::boat_classes::for_boat(state, boat_id)
+        .and_then(|class| crate::boat_classes::target_speed(class, state.wind.speed, telemetry.imu.heading - state.wind.direction))
+        .filter(|t| *t > 0.0);
+    score += config.speed_weight * match target {
+        Some(target) => (telemetry.velocity.speed / target).min(1.5) * 16.0,
+        None => telemetry.velocity.speed * 2.0,
+    };
+
+    // Proximity to Mark / Startline (Lower DTL = higher score)
+    // If they are within 50 meters (5000 cm) of a mark, aggressively boost score
+    let dtl = telemetry.dtl;
+    if dtl < 5000.0 && dtl > 0.0 {
+        score += config.proximity_weight * (5000.0 - dtl) / 100.0;
+    }
+
+    // Rank changes: 10 per place, while recent
+    if let Some(rank_move) = rank_move.filter(|m| now.saturating_duration_since(m.at) < RANK_CHANGE_WINDOW) {
+        score += config.rank_change_weight * rank_move.places as f64 * 10.0;
+    }
+
+    // OCS risk: 20 when predicted over at the gun, fading out 5 m behind the line
+    if telemetry.line_prediction.as_ref().and_then(|p| p.dtl_at_gun_m).is_some() {
+        score += config.ocs_risk_weight * ocs_risk(telemetry) * 20.0;
+    }
+
+    // Incidents: the priority of the most pressing one the boat is in
+    score += config.incident_weight * incident.unwrap_or(0.0);
+
+    // Tie-breaking jitter
+    score + boat_id.len() as f64 * 0.01
+}
+
+pub async fn start_auto_director(shared: SharedState, io: SocketIo, mut events: broadcast::Receiver<EngineEventEnvelope>, focus_tx: watch::Sender<DirectorFocus>) {
     let mut ticker = interval(Duration::from_secs(2)); // Evaluate every 2 seconds
-    
+
     info!("🎬 SRS Auto-Director started.");
-    
+    let mut bus_open = true;
+    let mut rank_moves: HashMap<String, RankMove> = HashMap::new();
+    let mut focus = FocusTracker::default();
+    let mut incidents = IncidentDetector::default();
+    let mut cameras = CameraAssigner::default();
+    let mut was_suspended = false;
+    let mut in_gun_window = false;
+    let mut highlight_watch = HighlightWatch::default();
+
     loop {
-        ticker.tick().await;
-        
+        // Re-evaluate on the ticker, and immediately when the race moves (start, recall, finish)
+        let mut gun_at = None;
+        tokio::select! {
+            _ = ticker.tick() => {}
+            event = engine_bus::next_event(&mut events, "auto-director"), if bus_open => match event {
+                Some(EngineEventEnvelope { event: EngineEvent::GunFired { .. }, at_ms, .. }) => {
+                    gun_at = Some(at_ms as i64);
+                    ticker.reset();
+                }
+                Some(EngineEventEnvelope { event: EngineEvent::StatusChanged { .. }, .. }) => {
+                    ticker.reset();
+                }
+                Some(_) => continue,
+                // Bus gone — keep directing on the ticker alone
+                None => bus_open = false,
+            },
+        }
+
+        // A suspension that has run out is cleared for everyone
+        let now_ms = crate::handlers::now_ms();
+        if shared.read().await.director_overrides.suspended_until_ms.is_some_and(|until| until <= now_ms) {
+            let mut state = shared.write().await;
+            state.director_overrides.suspended_until_ms = None;
+            let _ = save_state(&state).await;
+            info!("🎬 Auto-Director: suspension over, resuming");
+            io.emit("director-overrides", &state.director_overrides).ok();
+        }
+
         // 1. Snapshot the current fleet telemetry
+        let now = Instant::now();
         let state = shared.read().await;
+        let config = state.auto_director.clone();
+        let overrides = state.director_overrides.clone();
+        let suspended = overrides.suspended_until_ms.is_some_and(|until| until > now_ms);
+        if was_suspended && !suspended {
+            focus.restart_dwell(now);
+            cameras.restart_dwell(now);
+        }
+        was_suspended = suspended;
+        rank_moves.retain(|id, _| state.boats.contains_key(id));
+        let new_incidents = incidents.detect(&state, now);
+        let mut urgent = if config.incident_weight > 0.0 { incidents.boat_priorities() } else { HashMap::new() };
+        let secs_to_gun = gun_window(&state, &config, now_ms);
+        if secs_to_gun.is_some() != in_gun_window {
+            in_gun_window = secs_to_gun.is_some();
+            let mode = if in_gun_window { "GUN_WINDOW" } else { "RACE" };
+            info!("🎬 Auto-Director: {mode} mode");
+            io.emit("director-mode", &json!({ "mode": mode, "secsToGun": secs_to_gun, "timestamp": now_ms })).ok();
+        }
         let mut boats: Vec<(String, f64)> = Vec::new(); // (BoatId, Score)
-        
+        let mut gun_urgent: Vec<(String, f64)> = Vec::new();
+
         for (boat_id, telemetry) in &state.boats {
-            let mut score = 0.0;
-            
-            // Heuristic 1: Speed (faster = more exciting = higher score)
-            score += telemetry.velocity.speed * 2.0;
-            
-            // Heuristic 2: Proximity to Mark / Startline (Lower DTL = higher score)
-            // If they are within 50 meters (5000 cm) of a mark, aggressively boost score
-            let dtl = telemetry.dtl;
-            if dtl < 5000.0 && dtl > 0.0 {
-                score += (5000.0 - dtl) / 100.0; 
+            match rank_moves.get_mut(boat_id) {
+                Some(m) if m.rank != telemetry.rank => {
+                    if m.rank > 0 && telemetry.rank > 0 {
+                        m.places = m.rank.abs_diff(telemetry.rank);
+                        m.at = now;
+                    }
+                    m.rank = telemetry.rank;
+                }
+                Some(_) => {}
+                None => {
+                    rank_moves.insert(boat_id.clone(), RankMove { rank: telemetry.rank, places: 0, at: now });
+                }
             }
-            
-            // Tie-breaking jitter
-            score += boat_id.len() as f64 * 0.01;
-            
+            let incident = urgent.get(boat_id).copied();
+            let score = match secs_to_gun {
+                Some(_) => score_gun_window(&config, boat_id, telemetry, incident),
+                None => score_boat(&state, &config, boat_id, telemetry, rank_moves.get(boat_id), incident, now),
+            };
             boats.push((boat_id.clone(), score));
+            if secs_to_gun.is_some() && config.ocs_risk_weight > 0.0 && ocs_risk(telemetry) >= GUN_WINDOW_URGENT_RISK {
+                gun_urgent.push((boat_id.clone(), score));
+            }
         }
-        
-        drop(state);
-        
-        if boats.is_empty() {
-            continue;
+
+        // Likely OCS boats cut in like incident boats
+        for (boat_id, score) in gun_urgent {
+            urgent.entry(boat_id).or_insert(score);
         }
-        
-        // 2. Rank & Select Top 4
+
+        // 2. Rank, and let the best challengers into the focus
         // Sort descending by score
         boats.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        
-        let focus_limit = 4;
-        let top_boats: Vec<String> = boats.into_iter()
-            .take(focus_limit)
-            .map(|(id, _)| id)
-            .collect();
-            
-        // 3. Emit the target list via WebSockets
-        let payload = json!({
-            "focus_boats": top_boats,
-            "timestamp": std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis()
+        let changes = focus.update(&boats, &urgent, &overrides, suspended, &config, now);
+        let focus_boats = focus.boats();
+
+        // 3. Give each camera its shot (held as they are while suspended)
+        let shots = Shots { focus: &focus_boats, ranked: &boats, urgent: &urgent, default_dwell_secs: config.min_dwell_secs };
+        let camera_changes = if suspended { Vec::new() } else { cameras.update(&state, &config.cameras, &shots, now) };
+        // On air: the camera on the focus boat that matters most, incidents first
+        let urgency = |id: &str| urgent.get(id).copied().unwrap_or(0.0);
+        let score_of = |id: &str| boats.iter().find(|(b, _)| b == id).map_or(0.0, |(_, s)| *s);
+        let lead = focus_boats.iter().max_by(|a, b| {
+            urgency(a).total_cmp(&urgency(b)).then(score_of(a).total_cmp(&score_of(b)))
         });
-        
+        focus_tx.send_replace(DirectorFocus {
+            cameras: cameras.assignments(&state, &config.cameras, &focus_boats),
+            program: cameras.program(&config.cameras, lead.map(String::as_str)),
+            boats: focus_boats.clone(),
+        });
+
+        // 4. Mark the highlights
+        let mut marked: Vec<Highlight> = gun_at.map(|at| highlights::gun(&state, at)).into_iter().collect();
+        marked.extend(highlight_watch.scan(&state, now_ms));
+        marked.extend(new_incidents.iter().map(|i| highlights::incident(&state, i, now_ms)));
+        drop(state);
+        if !marked.is_empty() {
+            let mut state = shared.write().await;
+            highlights::record(&mut state, &marked);
+            let _ = save_state(&state).await;
+        }
+
+        // 5. Emit new incidents and highlights, the changes, then the list, via WebSockets
         // Broadcast to all connected clients (React Media Suite & iOS Trackers)
+        let timestamp = crate::handlers::now_ms();
+        for incident in &new_incidents {
+            let mut payload = json!(incident);
+            payload["timestamp"] = json!(timestamp);
+            io.emit("incident", &payload).ok();
+        }
+        for highlight in &marked {
+            io.emit("highlight", highlight).ok();
+        }
+        for change in &changes {
+            let mut payload = json!(change);
+            payload["timestamp"] = json!(timestamp);
+            io.emit(change.event(), &payload).ok();
+        }
+        for change in &camera_changes {
+            let mut payload = json!(change);
+            payload["timestamp"] = json!(timestamp);
+            io.emit("camera-assigned", &payload).ok();
+        }
+        let payload = json!({
+            "focus_boats": focus_boats,
+            "timestamp": timestamp,
+        });
         io.emit("focus_boats_changed", &payload).ok();
     }
 }
@@ -0,0 +1,91 @@
+//! # blacklist
+//!
+//! Trackers the director has pulled off the water. A banned tracker's
+//! `track-update`s are answered with `kill-simulation` and never reach the fleet.
+//!
+//! - `kill-tracker` bans the boat for `BLACKLIST_KILL_SECS` (default 30), or for
+//!   good with `permanent: true`
+//! - `blacklist-add { kind: BOAT | DEVICE, id, reason?, durationSecs? | permanent }`
+//! - `blacklist-remove { kind, id }` — lifts a ban early
+//! - `list-blacklist` — answered with `blacklist-update`, also sent on every change
+//!
+//! `DEVICE` entries match the `deviceId` a tracker sends with `register` or
+//! `track-update`, so a ban survives the device being re-assigned to another boat.
+//! The list lives in `RaceState` and is persisted with it; expired entries are
+//! dropped on the next change.
+//!
+//! ## Invariants
+//! - Core Invariant #2: every change arrives as an audited command
+
+use crate::state::{BlacklistEntry, BlacklistKind, RaceState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlacklistError {
+    #[error("A blacklist entry needs an id")]
+    MissingId,
+    #[error("Not blacklisted: {0}")]
+    NotFound(String),
+}
+
+/// How long `kill-tracker` keeps a boat off the water unless the ban is permanent.
+pub fn kill_duration_ms() -> i64 {
+    std::env::var("BLACKLIST_KILL_SECS").ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(30) * 1000
+}
+
+fn active(entry: &BlacklistEntry, now: i64) -> bool {
+    entry.expires_ms.is_none_or(|t| t > now)
+}
+
+pub fn is_banned(state: &RaceState, boat_id: &str, device_id: Option<&str>, now: i64) -> bool {
+    state.blacklist.iter().any(|e| active(e, now) && match e.kind {
+        BlacklistKind::Boat => e.id == boat_id,
+        BlacklistKind::Device => Some(e.id.as_str()) == device_id,
+    })
+}
+
+/// Drop expired entries. Returns whether anything was removed.
+pub fn prune(state: &mut RaceState, now: i64) -> bool {
+    let before = state.blacklist.len();
+    state.blacklist.retain(|e| active(e, now));
+    state.blacklist.len() != before
+}
+
+/// Ban `id` for `duration_ms`, or permanently with `None`. Replaces an existing
+/// entry of the same kind and id.
+pub fn add(
+    state: &mut RaceState,
+    kind: BlacklistKind,
+    id: &str,
+    reason: Option<String>,
+    duration_ms: Option<i64>,
+    now: i64,
+) -> Result<BlacklistEntry, BlacklistError> {
+    let id = id.trim();
+    if id.is_empty() {
+        return Err(BlacklistError::MissingId);
+    }
+    prune(state, now);
+    state.blacklist.retain(|e| !(e.kind == kind && e.id == id));
+    let entry = BlacklistEntry {
+        kind,
+        id: id.to_string(),
+        reason: reason.filter(|r| !r.trim().is_empty()),
+        added_ms: now,
+        expires_ms: duration_ms.map(|d| now + d),
+    };
+    state.blacklist.push(entry.clone());
+    Ok(entry)
+}
+
+pub fn remove(state: &mut RaceState, kind: BlacklistKind, id: &str, now: i64) -> Result<(), BlacklistError> {
+    prune(state, now);
+    let before = state.blacklist.len();
+    state.blacklist.retain(|e| !(e.kind == kind && e.id == id));
+    if state.blacklist.len() == before {
+        return Err(BlacklistError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
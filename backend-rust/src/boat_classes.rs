@@ -0,0 +1,104 @@
+//! # boat_classes
+//!
+//! Class registry: name, polar, tacking/gybing angles and handicap system per
+//! class, kept in `state.boat_classes`. Entries refer to a class by `classId`.
+//!
+//! - `upsert-boat-class { id, name, polar?, upwindTwa?, downwindTwa?, upwindKn?,
+//!   downwindKn?, handicapSystem? }` / `delete-boat-class { id }` →
+//!   `boat-classes-update`, errors as `class-error`
+//!
+//! Used by:
+//! - `laylines` — a class with angles gets its own layline set (over
+//!   `LAYLINE_CLASS_ANGLES`), and its target speeds for the current correction
+//! - `tide::predict` — `targetTimeToLineS`, close-hauled at the target speed
+//! - `auto_director` — speed is scored against the class target, so a fast
+//!   class does not crowd out a slow one sailing well
+
+use crate::entries;
+use crate::state::{BoatClass, LaylineLeg, RaceState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClassError {
+    #[error("Unknown class: {0}")]
+    NotFound(String),
+    #[error("A class needs an id and a name")]
+    Unnamed,
+    #[error("Class {0} is used by {1} entr(y/ies)")]
+    InUse(String, usize),
+}
+
+pub fn get<'a>(state: &'a RaceState, class_id: &str) -> Option<&'a BoatClass> {
+    state.boat_classes.iter().find(|c| c.id == class_id)
+}
+
+/// The registered class of a boat (by boat id or sail number).
+pub fn for_boat<'a>(state: &'a RaceState, boat_id: &str) -> Option<&'a BoatClass> {
+    get(state, entries::resolve(state, boat_id)?.class_id.as_deref()?)
+}
+
+pub fn upsert(state: &mut RaceState, class: BoatClass) -> Result<(), ClassError> {
+    if class.id.trim().is_empty() || class.name.trim().is_empty() {
+        return Err(ClassError::Unnamed);
+    }
+    match state.boat_classes.iter_mut().find(|c| c.id == class.id) {
+        Some(existing) => *existing = class,
+        None => state.boat_classes.push(class),
+    }
+    Ok(())
+}
+
+pub fn delete(state: &mut RaceState, class_id: &str) -> Result<(), ClassError> {
+    get(state, class_id).ok_or_else(|| ClassError::NotFound(class_id.to_string()))?;
+    let used = state.entries.iter().filter(|e| e.class_id.as_deref() == Some(class_id)).count();
+    if used > 0 {
+        return Err(ClassError::InUse(class_id.to_string(), used));
+    }
+    state.boat_classes.retain(|c| c.id != class_id);
+    Ok(())
+}
+
+/// Linear interpolation of `bsp` over `twa` within one wind-speed row.
+fn along_twa(row: &[(f64, f64)], twa: f64) -> Option<f64> {
+    let first = row.first()?;
+    let last = row.last()?;
+    if twa <= first.0 {
+        return Some(first.1);
+    }
+    if twa >= last.0 {
+        return Some(last.1);
+    }
+    let i = row.iter().position(|p| p.0 >= twa)?;
+    let (a, b) = (row[i - 1], row[i]);
+    Some(a.1 + (b.1 - a.1) * (twa - a.0) / (b.0 - a.0))
+}
+
+/// Target boat speed (knots) at `tws_kn` / `twa_deg` from the polar, interpolated
+/// between the two nearest wind speeds and clamped to the polar's range.
+pub fn target_speed(class: &BoatClass, tws_kn: f64, twa_deg: f64) -> Option<f64> {
+    let twa = twa_deg.rem_euclid(360.0);
+    let twa = if twa > 180.0 { 360.0 - twa } else { twa };
+    let mut speeds: Vec<f64> = class.polar.iter().map(|p| p.tws_kn).collect();
+    speeds.sort_by(f64::total_cmp);
+    speeds.dedup();
+    let row = |tws: f64| {
+        let mut row: Vec<(f64, f64)> = class.polar.iter().filter(|p| p.tws_kn == tws).map(|p| (p.twa_deg, p.bsp_kn)).collect();
+        row.sort_by(|a, b| a.0.total_cmp(&b.0));
+        along_twa(&row, twa)
+    };
+    let below = speeds.iter().rev().find(|&&s| s <= tws_kn).or(speeds.first())?;
+    let above = speeds.iter().find(|&&s| s >= tws_kn).or(speeds.last())?;
+    let (lo, hi) = (row(*below)?, row(*above)?);
+    if above == below {
+        return Some(lo);
+    }
+    Some(lo + (hi - lo) * (tws_kn - below) / (above - below))
+}
+
+/// Target speed on a leg: the polar at the class's angle, else the fixed target.
+pub fn leg_speed(class: &BoatClass, leg: LaylineLeg, tws_kn: f64) -> Option<f64> {
+    let (twa, fixed) = match leg {
+        LaylineLeg::Upwind => (class.upwind_twa, class.upwind_kn),
+        LaylineLeg::Downwind => (class.downwind_twa, class.downwind_kn),
+    };
+    twa.and_then(|twa| target_speed(class, tws_kn, twa)).or(fixed)
+}
@@ -0,0 +1,106 @@
+//! # boat_liveness
+//!
+//! Connection status of tracked boats, so a tracker that went silent does not sit
+//! frozen on the map looking live.
+//!
+//! Every second the sweep compares each boat's last `track-update` (server receive
+//! time) with:
+//!
+//! - `BOAT_STALE_SECS` (default 5) — status `STALE`
+//! - `BOAT_OFFLINE_SECS` (default 15) — status `OFFLINE`
+//! - `BOAT_REMOVE_SECS` (default 300, 0 = never) — dropped from the fleet
+//!
+//! Each change is emitted as `boat-status-changed`; the next `track-update` from a
+//! stale or offline boat brings it back to `LIVE` the same way.
+//!
+//! ## Invariants
+//! - Core Invariant #8: one write lock per sweep, emissions after it is released
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use socketioxide::SocketIo;
+use tracing::info;
+
+use crate::handlers::{now_ms, SharedState};
+use crate::state::{BoatState, BoatStatus};
+use crate::boat_scope::FULL_STATE_EXCEPT;
+
+pub struct LivenessConfig {
+    pub stale_ms: i64,
+    pub offline_ms: i64,
+    /// 0 keeps offline boats indefinitely
+    pub remove_ms: i64,
+}
+
+impl Default for LivenessConfig {
+    fn default() -> Self {
+        let secs = |key: &str, default: i64| std::env::var(key).ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v >= 0)
+            .unwrap_or(default) * 1000;
+        Self {
+            stale_ms: secs("BOAT_STALE_SECS", 5),
+            offline_ms: secs("BOAT_OFFLINE_SECS", 15),
+            remove_ms: secs("BOAT_REMOVE_SECS", 300),
+        }
+    }
+}
+
+impl LivenessConfig {
+    fn status(&self, silent_ms: i64) -> BoatStatus {
+        if silent_ms > self.offline_ms {
+            BoatStatus::Offline
+        } else if silent_ms > self.stale_ms {
+            BoatStatus::Stale
+        } else {
+            BoatStatus::Live
+        }
+    }
+}
+
+/// `boat-status-changed` payload.
+pub fn status_event(boat: &BoatState, now: i64) -> Value {
+    json!({
+        "boatId": boat.boat_id,
+        "status": boat.status,
+        "lastSeenMs": boat.last_seen_ms,
+        "silentMs": now - boat.last_seen_ms,
+    })
+}
+
+pub async fn run_liveness_sweep(config: LivenessConfig, shared: SharedState, io: SocketIo) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        let now = now_ms();
+
+        let mut changed = Vec::new();
+        let mut removed = Vec::new();
+        {
+            let mut state = shared.write().await;
+            state.boats.retain(|id, boat| {
+                let silent_ms = now - boat.last_seen_ms;
+                if config.remove_ms > 0 && silent_ms > config.remove_ms {
+                    removed.push(id.clone());
+                    return false;
+                }
+                let status = config.status(silent_ms);
+                if status != boat.status {
+                    boat.status = status;
+                    changed.push(status_event(boat, now));
+                }
+                true
+            });
+        }
+
+        for event in &changed {
+            let _ = io.emit("boat-status-changed", event);
+        }
+        if !removed.is_empty() {
+            info!("Liveness: removed silent trackers: {:?}", removed);
+            let state = shared.read().await;
+            let _ = io.except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! # boat_scope
+//!
+//! What a tracker may see: its own boat in detail, plus the public race status.
+//!
+//! Trackers are bound to a boat at `register` and sit in the `tracker` room,
+//! which every full `state-update` broadcast skips (`FULL_STATE_EXCEPT`), as do
+//! fleet-wide `boat-update`s, `finishes-update`, `penalty-updated`,
+//! `protests-update`, `new-log` and `log-updated`. The boat comes from the verified token
+//! (offline token, Supabase `app_metadata.boat_id`, ID token `boat_id` claim). Instead, every `SCOPED_STATE_INTERVAL_MS` (default
+//! 500) each boat room (`boat:<boatId>`) gets `state-update` with `view` — when
+//! it changed — and `init-state` on register is the same view. Trackers cannot
+//! subscribe to the delta stream.
+//!
+//! The view keeps the race status, signals, course, wind, entries, results and
+//! standings, and filters the rest to the boat: its `boats` / `fleetHistory`
+//! entry, penalties, OCS detections, roundings and finishes, the protests it is
+//! party to and the messages it can read. Logs, the procedure graph, templates,
+//! the blacklist and weather history are left out.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use socketioxide::extract::SocketRef;
+use socketioxide::SocketIo;
+
+use crate::auth::AuthEngine;
+use crate::handlers::SharedState;
+use crate::messaging;
+use crate::state::{Protest, RaceState};
+use crate::state_delta::DELTA_ROOM;
+
+/// Room of all trackers (their role room)
+pub const SCOPED_ROOM: &str = "tracker";
+
+/// Rooms a full `state-update` broadcast skips.
+pub const FULL_STATE_EXCEPT: [&str; 2] = [DELTA_ROOM, SCOPED_ROOM];
+
+pub struct ScopeConfig {
+    pub interval: Duration,
+}
+
+impl Default for ScopeConfig {
+    fn default() -> Self {
+        let ms = std::env::var("SCOPED_STATE_INTERVAL_MS").ok().and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(500);
+        Self { interval: Duration::from_millis(ms) }
+    }
+}
+
+pub fn boat_room(boat_id: &str) -> String {
+    format!("boat:{boat_id}")
+}
+
+/// The state as the tracker of `boat_id` sees it.
+pub fn view(state: &RaceState, boat_id: &str) -> RaceState {
+    let own = |id: &String| id == boat_id;
+    RaceState {
+        boats: state.boats.iter().filter(|(id, _)| own(id)).map(|(id, b)| (id.clone(), b.clone())).collect(),
+        fleet_history: state.fleet_history.iter().filter(|(id, _)| own(id)).map(|(id, h)| (id.clone(), h.clone())).collect(),
+        penalties: state.penalties.iter().filter(|p| own(&p.boat_id)).cloned().collect(),
+        ocs_detections: state.ocs_detections.iter().filter(|d| own(&d.boat_id)).cloned().collect(),
+        mark_roundings: state.mark_roundings.iter().filter(|r| own(&r.boat_id)).cloned().collect(),
+        finishes: state.finishes.iter().filter(|f| own(&f.boat_id)).cloned().collect(),
+        protests: protests(state, boat_id),
+        messages: messaging::visible_to_boat(state, boat_id),
+        logs: Vec::new(),
+        weather_history: Vec::new(),
+        current_procedure: None,
+        procedure_templates: Vec::new(),
+        blacklist: Vec::new(),
+        ..state.clone()
+    }
+}
+
+/// The protests `boat_id` is party to.
+pub fn protests(state: &RaceState, boat_id: &str) -> Vec<Protest> {
+    state.protests.iter()
+        .filter(|p| p.protestor == boat_id || p.protestees.iter().any(|id| id == boat_id))
+        .cloned()
+        .collect()
+}
+
+/// Whether a socket only gets its boat's view.
+pub fn is_scoped(s: &SocketRef) -> bool {
+    s.rooms().is_ok_and(|rooms| rooms.iter().any(|r| r == SCOPED_ROOM))
+}
+
+/// `state-update` to the sender of a command, unless it is a tracker — those
+/// get their view from `run_scoped_feed`.
+pub fn emit_to_sender(s: &SocketRef, state: &RaceState) {
+    if !is_scoped(s) {
+        let _ = s.emit("state-update", state);
+    }
+}
+
+pub async fn run_scoped_feed(config: ScopeConfig, shared: SharedState, auth: std::sync::Arc<AuthEngine>, io: SocketIo) {
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last: HashMap<String, String> = HashMap::new();
+    loop {
+        ticker.tick().await;
+        let mut boats: Vec<String> = auth.clients().await.into_iter()
+            .filter(|c| c.role == SCOPED_ROOM)
+            .filter_map(|c| c.boat_id)
+            .collect();
+        boats.sort();
+        boats.dedup();
+        last.retain(|boat_id, _| boats.contains(boat_id));
+
+        let views: Vec<(String, RaceState)> = {
+            let state = shared.read().await;
+            boats.into_iter().map(|boat_id| {
+                let view = view(&state, &boat_id);
+                (boat_id, view)
+            }).collect()
+        };
+        for (boat_id, view) in views {
+            let Ok(json) = serde_json::to_string(&view) else { continue };
+            if last.get(&boat_id) == Some(&json) {
+                continue;
+            }
+            let _ = io.to(boat_room(&boat_id)).emit("state-update", &view);
+            last.insert(boat_id, json);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! # broadcast_feed
+//!
+//! Ready-to-render overlay data for the graphics operator, so leaderboards,
+//! gaps and OCS callouts don't have to be re-derived client-side.
+//!
+//! - `broadcast-feed` on the main namespace every `BROADCAST_FEED_INTERVAL_MS`
+//!   (default 1000), and `GET /broadcast-feed` for a one-off pull
+//! - `leaderboard`: ranked boats with sail number, leg, lap, speed and the
+//!   distance/time gap to the leader and to the boat ahead
+//! - `prestart`: before the gun, boats ordered by distance to the line (DTL),
+//!   closest first, with time to line where known
+//! - `ocs`: boats called over the line at the current start
+//!
+//! Time gaps are the distance gap over the trailing boat's speed; None below
+//! `MIN_GAP_SPEED_KN`, where the number would be meaningless.
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use socketioxide::SocketIo;
+
+use crate::entries;
+use crate::handlers::SharedState;
+use crate::state::{BoatState, RaceState, RaceStatus};
+
+const KNOTS_TO_MPS: f64 = 0.514444;
+const MIN_GAP_SPEED_KN: f64 = 0.5;
+
+pub struct BroadcastFeedConfig {
+    pub interval: Duration,
+}
+
+impl Default for BroadcastFeedConfig {
+    fn default() -> Self {
+        let ms = std::env::var("BROADCAST_FEED_INTERVAL_MS").ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v >= 100)
+            .unwrap_or(1000);
+        Self { interval: Duration::from_millis(ms) }
+    }
+}
+
+fn time_gap(gap_m: f64, boat: &BoatState) -> Option<f64> {
+    (boat.velocity.speed >= MIN_GAP_SPEED_KN).then(|| gap_m / (boat.velocity.speed * KNOTS_TO_MPS))
+}
+
+fn is_prestart(status: &RaceStatus) -> bool {
+    matches!(status, RaceStatus::Warning | RaceStatus::Preparatory | RaceStatus::OneMinute)
+}
+
+fn sail_number<'a>(state: &'a RaceState, boat_id: &str) -> Option<&'a str> {
+    entries::resolve(state, boat_id).map(|e| e.sail_number.as_str())
+}
+
+pub fn feed(state: &RaceState) -> Value {
+    let mut ranked: Vec<&BoatState> = state.boats.values().filter(|b| b.rank > 0).collect();
+    ranked.sort_by_key(|b| b.rank);
+    let leader_dtf = ranked.first().map(|b| b.dtf_m);
+    let leaderboard: Vec<Value> = ranked.iter().enumerate().map(|(i, b)| {
+        let gap_m = leader_dtf.map(|d| (b.dtf_m - d).max(0.0)).unwrap_or(0.0);
+        let interval_m = i.checked_sub(1).map(|ahead| (b.dtf_m - ranked[ahead].dtf_m).max(0.0));
+        let entry = entries::resolve(state, &b.boat_id);
+        json!({
+            "rank": b.rank,
+            "boatId": b.boat_id,
+            "sailNumber": entry.map(|e| e.sail_number.as_str()),
+            "boatName": entry.map(|e| e.boat_name.as_str()),
+            "legIndex": b.leg_index,
+            "lap": b.lap,
+            "speedKn": b.velocity.speed,
+            "dtfM": b.dtf_m,
+            "gapM": gap_m,
+            "gapSecs": time_gap(gap_m, b),
+            "intervalM": interval_m,
+            "intervalSecs": interval_m.and_then(|m| time_gap(m, b)),
+            "status": b.status,
+        })
+    }).collect();
+
+    let prestart: Vec<Value> = if is_prestart(&state.status) {
+        let mut boats: Vec<(&BoatState, f64)> = state.boats.values()
+            .map(|b| (b, b.line_prediction.as_ref().map(|p| p.dtl_m).unwrap_or(b.dtl)))
+            .collect();
+        boats.sort_by(|a, b| a.1.abs().total_cmp(&b.1.abs()));
+        boats.into_iter().map(|(b, dtl_m)| json!({
+            "boatId": b.boat_id,
+            "sailNumber": sail_number(state, &b.boat_id),
+            "dtlM": dtl_m,
+            "speedKn": b.velocity.speed,
+            "timeToLineSecs": b.line_prediction.as_ref().and_then(|p| p.time_to_line_s),
+            "dtlAtGunM": b.line_prediction.as_ref().and_then(|p| p.dtl_at_gun_m),
+        })).collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut ocs: Vec<Value> = state.ocs_detections.iter().map(|d| json!({
+        "boatId": d.boat_id,
+        "sailNumber": sail_number(state, &d.boat_id),
+        "dtlCm": d.dtl_cm,
+        "source": "uwb",
+    })).collect();
+    for boat_id in state.ocs_boats.iter().filter(|id| !state.ocs_detections.iter().any(|d| &d.boat_id == *id)) {
+        ocs.push(json!({
+            "boatId": boat_id,
+            "sailNumber": sail_number(state, boat_id),
+            "dtlCm": null,
+            "source": "manual",
+        }));
+    }
+
+    json!({
+        "status": state.status,
+        "startTime": state.start_time,
+        "sequenceTimeRemaining": state.sequence_time_remaining,
+        "wind": state.wind,
+        "leaderboard": leaderboard,
+        "prestart": prestart,
+        "ocs": ocs,
+    })
+}
+
+pub async fn run_broadcast_feed(config: BroadcastFeedConfig, shared: SharedState, io: SocketIo) {
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        let payload = feed(&*shared.read().await);
+        let _ = io.emit("broadcast-feed", &payload);
+    }
+}
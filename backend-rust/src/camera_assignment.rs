@@ -0,0 +1,252 @@
+//! # camera_assignment
+//!
+//! Gives each configured camera its own shot, so the auto-director can drive a
+//! multi-camera broadcast rather than a single focus list. Re-run with every
+//! auto-director evaluation, by the camera's `role`:
+//!
+//! - **MARK** — its `markId`, else the mark with the most boats within 100 m
+//!   (nearest the top focus boat on a tie) that no other camera covers
+//! - **CHASE** — one focus boat each; the chase cameras share the top focus boats
+//!   (incident boats first, then by score) and never two on the same boat
+//! - **WIDE** — the focus boats together, then the whole fleet; a further wide
+//!   camera chases a boat nobody else has
+//!
+//! A camera keeps its target for its `dwellSecs` (default the focus
+//! `minDwellSecs`) and after that as long as the target is still wanted, so
+//! cameras do not swap boats between themselves. A target that is gone is left
+//! at once, and a chase camera cuts early to an incident boat no camera covers.
+//!
+//! Each change is `camera-assigned { cameraId, role, target, reason, timestamp }`
+//! with `target` one of `{ kind: "boat", boatId }`, `{ kind: "mark", markId }`,
+//! `{ kind: "group", group, boats }` (or null) and `reason` `initial`,
+//! `rotation`, `incident` or `gone`. The current assignments also drive the
+//! `assignment` aim of `camera-pointing`.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::ranking_engine::haversine_distance;
+use crate::state::{BoatStatus, CameraConfig, CameraRole, RaceState};
+
+/// Boats this close to a mark count as rounding it
+const MARK_RANGE_M: f64 = 100.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CameraTarget {
+    #[serde(rename_all = "camelCase")]
+    Boat { boat_id: String },
+    #[serde(rename_all = "camelCase")]
+    Mark { mark_id: String },
+    /// `focus` or `fleet`; the boats are refreshed every evaluation
+    Group { group: &'static str, boats: Vec<String> },
+}
+
+impl CameraTarget {
+    /// What makes two targets the same shot (a group keeps its shot as its boats change).
+    fn key(&self) -> String {
+        match self {
+            Self::Boat { boat_id } => format!("boat:{boat_id}"),
+            Self::Mark { mark_id } => format!("mark:{mark_id}"),
+            Self::Group { group, .. } => format!("group:{group}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Assignment {
+    pub camera_id: String,
+    pub role: CameraRole,
+    pub target: Option<CameraTarget>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignmentChange {
+    #[serde(flatten)]
+    pub assignment: Assignment,
+    pub reason: &'static str,
+}
+
+struct Held {
+    target: CameraTarget,
+    since: Instant,
+}
+
+/// What the assigner needs from an auto-director evaluation.
+pub struct Shots<'a> {
+    /// Focus boats, slot order
+    pub focus: &'a [String],
+    /// Every boat's score, highest first
+    pub ranked: &'a [(String, f64)],
+    /// Incident priority of boats in an incident
+    pub urgent: &'a HashMap<String, f64>,
+    pub default_dwell_secs: f64,
+}
+
+#[derive(Default)]
+pub struct CameraAssigner {
+    held: HashMap<String, Held>,
+}
+
+fn group(state: &RaceState, name: &'static str, focus: &[String]) -> CameraTarget {
+    let mut boats: Vec<String> = match name {
+        "focus" => focus.iter().filter(|b| state.boats.contains_key(*b)).cloned().collect(),
+        _ => state.boats.values().filter(|b| b.status == BoatStatus::Live).map(|b| b.boat_id.clone()).collect(),
+    };
+    boats.sort();
+    CameraTarget::Group { group: name, boats }
+}
+
+/// Marks by boats within range (most first), then distance to `lead`.
+fn busy_marks(state: &RaceState, lead: Option<&str>) -> Vec<String> {
+    let lead = lead.and_then(|id| state.boats.get(id));
+    let mut marks: Vec<(String, usize, f64)> = state.course.marks.iter()
+        .map(|mark| {
+            let near = state.boats.values()
+                .filter(|b| b.status == BoatStatus::Live && haversine_distance(&b.pos, &mark.pos) <= MARK_RANGE_M)
+                .count();
+            let to_lead = lead.map_or(0.0, |b| haversine_distance(&b.pos, &mark.pos));
+            (mark.id.clone(), near, to_lead)
+        })
+        .collect();
+    marks.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal)));
+    marks.into_iter().map(|(id, _, _)| id).collect()
+}
+
+impl CameraAssigner {
+    pub fn assignments(&self, state: &RaceState, cameras: &[CameraConfig], focus: &[String]) -> Vec<Assignment> {
+        cameras.iter()
+            .map(|camera| Assignment {
+                camera_id: camera.id.clone(),
+                role: camera.role,
+                target: self.held.get(&camera.id).map(|h| match &h.target {
+                    CameraTarget::Group { group: name, .. } => group(state, name, focus),
+                    target => target.clone(),
+                }),
+            })
+            .collect()
+    }
+
+    /// The camera to put on air: the one on `lead` (the focus boat that matters
+    /// most), else a wide shot, else the first camera with a target.
+    pub fn program(&self, cameras: &[CameraConfig], lead: Option<&str>) -> Option<String> {
+        let on_lead = lead.and_then(|lead| cameras.iter().find(|c| {
+            matches!(self.held.get(&c.id), Some(Held { target: CameraTarget::Boat { boat_id }, .. }) if boat_id == lead)
+        }));
+        on_lead
+            .or_else(|| cameras.iter().find(|c| c.role == CameraRole::Wide && self.held.contains_key(&c.id)))
+            .or_else(|| cameras.iter().find(|c| self.held.contains_key(&c.id)))
+            .map(|c| c.id.clone())
+    }
+
+    /// Give every shot a fresh dwell (after the automation was suspended).
+    pub fn restart_dwell(&mut self, now: Instant) {
+        for held in self.held.values_mut() {
+            held.since = now;
+        }
+    }
+
+    /// Re-assign the cameras; returns the cameras whose target changed.
+    pub fn update(&mut self, state: &RaceState, cameras: &[CameraConfig], shots: &Shots, now: Instant) -> Vec<AssignmentChange> {
+        self.held.retain(|id, _| cameras.iter().any(|c| &c.id == id));
+        let score_of = |id: &str| shots.ranked.iter().find(|(b, _)| b == id).map_or(0.0, |(_, s)| *s);
+
+        // Focus boats in chase order: incidents first, then by score
+        let mut chase_order: Vec<&String> = shots.focus.iter().filter(|b| state.boats.contains_key(*b)).collect();
+        chase_order.sort_by(|a, b| {
+            let urgency = |id: &str| shots.urgent.get(id).copied().unwrap_or(0.0);
+            urgency(b).partial_cmp(&urgency(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(score_of(b).partial_cmp(&score_of(a)).unwrap_or(std::cmp::Ordering::Equal))
+        });
+        let chase_count = cameras.iter().filter(|c| c.role == CameraRole::Chase).count();
+        let wanted: HashSet<&String> = chase_order.iter().take(chase_count).copied().collect();
+        let held_boats: HashSet<String> = self.held.values()
+            .filter_map(|h| match &h.target { CameraTarget::Boat { boat_id } => Some(boat_id.clone()), _ => None })
+            .collect();
+        let uncovered_incident = chase_order.iter().any(|b| shots.urgent.contains_key(*b) && !held_boats.contains(*b));
+
+        // 1. Keep what is still valid: in its dwell, or still wanted
+        let mut taken: HashSet<String> = HashSet::new();
+        let mut reasons: HashMap<&str, (&'static str, String)> = HashMap::new();
+        for camera in cameras {
+            let Some(held) = self.held.get(&camera.id) else { continue };
+            let dwell = Duration::from_secs_f64(camera.dwell_secs.unwrap_or(shots.default_dwell_secs));
+            let in_dwell = now.saturating_duration_since(held.since) < dwell;
+            let (valid, still_wanted, incident_cut) = match &held.target {
+                CameraTarget::Boat { boat_id } => (
+                    state.boats.contains_key(boat_id),
+                    camera.role != CameraRole::Chase || wanted.contains(boat_id),
+                    camera.role == CameraRole::Chase && uncovered_incident && !shots.urgent.contains_key(boat_id),
+                ),
+                CameraTarget::Mark { mark_id } => (
+                    state.course.marks.iter().any(|m| &m.id == mark_id),
+                    camera.mark_id.is_none() || camera.mark_id.as_ref() == Some(mark_id),
+                    false,
+                ),
+                CameraTarget::Group { .. } => (true, true, false),
+            };
+            let key = held.target.key();
+            if valid && !taken.contains(&key) && !incident_cut && (in_dwell || still_wanted) {
+                taken.insert(key);
+                continue;
+            }
+            let reason = if !valid { "gone" } else if incident_cut { "incident" } else { "rotation" };
+            reasons.insert(camera.id.as_str(), (reason, key));
+            self.held.remove(&camera.id);
+        }
+
+        // 2. Free cameras pick, mark cameras first, then chase, then wide
+        let lead = chase_order.first().map(|b| b.as_str());
+        let marks = busy_marks(state, lead);
+        let mut changes = Vec::new();
+        let mut free: Vec<&CameraConfig> = cameras.iter().filter(|c| !self.held.contains_key(&c.id)).collect();
+        free.sort_by_key(|c| match c.role {
+            CameraRole::Mark => 0,
+            CameraRole::Chase => 1,
+            CameraRole::Wide => 2,
+        });
+        for camera in free {
+            let next_boat = |taken: &HashSet<String>| chase_order.iter()
+                .find(|b| !taken.contains(&format!("boat:{b}")))
+                .map(|b| CameraTarget::Boat { boat_id: (*b).clone() });
+            let target = match camera.role {
+                CameraRole::Mark => match &camera.mark_id {
+                    Some(mark_id) => state.course.marks.iter().any(|m| &m.id == mark_id).then(|| CameraTarget::Mark { mark_id: mark_id.clone() }),
+                    None => marks.iter().find(|m| !taken.contains(&format!("mark:{m}"))).map(|m| CameraTarget::Mark { mark_id: m.clone() }),
+                },
+                CameraRole::Chase => next_boat(&taken),
+                CameraRole::Wide => ["focus", "fleet"].into_iter()
+                    .map(|name| group(state, name, shots.focus))
+                    .find(|g| !taken.contains(&g.key()) && matches!(g, CameraTarget::Group { boats, .. } if !boats.is_empty()))
+                    .or_else(|| next_boat(&taken)),
+            };
+            let (reason, previous) = reasons.get(camera.id.as_str()).map_or(("initial", None), |(r, k)| (*r, Some(k)));
+            match target {
+                Some(target) => {
+                    taken.insert(target.key());
+                    let unchanged = previous == Some(&target.key());
+                    self.held.insert(camera.id.clone(), Held { target: target.clone(), since: now });
+                    if unchanged {
+                        continue;
+                    }
+                    changes.push(AssignmentChange {
+                        assignment: Assignment { camera_id: camera.id.clone(), role: camera.role, target: Some(target) },
+                        reason,
+                    });
+                }
+                // Lost its target and found nothing else
+                None if reasons.contains_key(camera.id.as_str()) => changes.push(AssignmentChange {
+                    assignment: Assignment { camera_id: camera.id.clone(), role: camera.role, target: None },
+                    reason,
+                }),
+                None => {}
+            }
+        }
+        changes
+    }
+}
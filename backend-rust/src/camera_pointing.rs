@@ -0,0 +1,208 @@
+//! # camera_pointing
+//!
+//! Pan/tilt/zoom for the broadcast cameras, so gimbal controllers can track the
+//! auto-director's focus without doing the geometry themselves.
+//!
+//! Cameras are part of the event's `AutoDirectorConfig` (`cameras`, set with
+//! `director-config`): a committee-boat, drone or shore camera standing at `pos`,
+//! or moving with the boat or mark `followId`, `heightM` above the water.
+//!
+//! Every `CAMERA_POINTING_INTERVAL_MS` (default 200) `camera-pointing` carries,
+//! per camera, the aim at its assigned shot (see `camera_assignment`) and a
+//! solution for each focus boat in slot order:
+//!
+//! ```text
+//! { cameras: [{ cameraId, platform, role, pos?, assignment?: { target, bearingDeg, … },
+//!   targets: [{ boatId, slot, bearingDeg, panDeg, tiltDeg, distanceM, fovDeg, zoom }] }],
+//!   timestamp }
+//! ```
+//!
+//! - `bearingDeg` — true bearing from the camera; `panDeg` is the same against
+//!   the camera's `headingDeg` (or the heading of the boat it follows), −180..180
+//! - `tiltDeg` — negative looking down
+//! - `zoom` — frames `frameM` of water around the boat, from 1 (`wideFovDeg`) up
+//!   to `maxZoom`; a mark shot three times that, a group shot its spread
+//!
+//! A camera whose position is unknown (followed boat or mark missing) has no
+//! `pos` and no targets.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::json;
+use socketioxide::SocketIo;
+use tokio::sync::watch;
+
+use crate::auto_director::DirectorFocus;
+use crate::camera_assignment::CameraTarget;
+use crate::handlers::SharedState;
+use crate::ranking_engine::{bearing, haversine_distance};
+use crate::state::{CameraConfig, CameraPlatform, CameraRole, LatLon, RaceState};
+
+/// Height of the point aimed at on a boat (deck level)
+const TARGET_HEIGHT_M: f64 = 1.0;
+/// A mark shot takes in this many frames of water, for the boats rounding it
+const MARK_FRAME_FACTOR: f64 = 3.0;
+
+pub struct CameraPointingConfig {
+    pub interval: Duration,
+}
+
+impl Default for CameraPointingConfig {
+    fn default() -> Self {
+        let ms = std::env::var("CAMERA_POINTING_INTERVAL_MS").ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v >= 50)
+            .unwrap_or(200);
+        Self { interval: Duration::from_millis(ms) }
+    }
+}
+
+/// Where to point, from a camera to a target.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Aim {
+    pub bearing_deg: f64,
+    pub pan_deg: f64,
+    pub tilt_deg: f64,
+    pub distance_m: f64,
+    pub fov_deg: f64,
+    pub zoom: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pointing {
+    pub boat_id: String,
+    pub slot: usize,
+    #[serde(flatten)]
+    pub aim: Aim,
+}
+
+/// The camera's assigned shot (see `camera_assignment`), aimed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignedAim {
+    pub target: CameraTarget,
+    #[serde(flatten)]
+    pub aim: Aim,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraSolution {
+    pub camera_id: String,
+    pub platform: CameraPlatform,
+    pub role: CameraRole,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pos: Option<LatLon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignment: Option<AssignedAim>,
+    pub targets: Vec<Pointing>,
+}
+
+/// Where the camera is now, and its pan 0 bearing.
+pub fn locate(state: &RaceState, camera: &CameraConfig) -> Option<(LatLon, f64)> {
+    let Some(follow_id) = &camera.follow_id else {
+        return camera.pos.clone().map(|pos| (pos, camera.heading_deg.unwrap_or(0.0)));
+    };
+    if let Some(boat) = state.boats.get(follow_id) {
+        return Some((boat.pos.clone(), camera.heading_deg.unwrap_or(boat.imu.heading)));
+    }
+    state.course.marks.iter()
+        .find(|m| &m.id == follow_id)
+        .map(|m| (m.pos.clone(), camera.heading_deg.unwrap_or(0.0)))
+}
+
+/// Aim the camera at `target`, zoomed to fill the frame with `frame_m` of water.
+pub fn aim(camera: &CameraConfig, pos: &LatLon, heading: f64, target: &LatLon, frame_m: f64) -> Aim {
+    let round = |v: f64| (v * 10.0).round() / 10.0;
+    let distance = haversine_distance(pos, target);
+    let bearing_deg = bearing(pos, target);
+    let pan = ((bearing_deg - heading).rem_euclid(360.0) + 180.0).rem_euclid(360.0) - 180.0;
+    let tilt = (TARGET_HEIGHT_M - camera.height_m).atan2(distance.max(0.1)).to_degrees();
+    let min_fov = camera.wide_fov_deg / camera.max_zoom.max(1.0);
+    let fov = (2.0 * (frame_m / 2.0).atan2(distance.max(0.1)).to_degrees()).clamp(min_fov, camera.wide_fov_deg);
+    Aim {
+        bearing_deg: round(bearing_deg),
+        pan_deg: round(pan),
+        tilt_deg: round(tilt),
+        distance_m: round(distance),
+        fov_deg: round(fov),
+        zoom: round(camera.wide_fov_deg / fov),
+    }
+}
+
+/// Aim at an assigned shot: a boat, a mark with its zone, or the middle of a group.
+fn aim_assignment(state: &RaceState, camera: &CameraConfig, pos: &LatLon, heading: f64, target: &CameraTarget) -> Option<Aim> {
+    match target {
+        CameraTarget::Boat { boat_id } => state.boats.get(boat_id).map(|b| aim(camera, pos, heading, &b.pos, camera.frame_m)),
+        CameraTarget::Mark { mark_id } => state.course.marks.iter()
+            .find(|m| &m.id == mark_id)
+            .map(|m| aim(camera, pos, heading, &m.pos, camera.frame_m * MARK_FRAME_FACTOR)),
+        CameraTarget::Group { boats, .. } => {
+            let members: Vec<&LatLon> = boats.iter().filter_map(|id| state.boats.get(id)).map(|b| &b.pos).collect();
+            if members.is_empty() {
+                return None;
+            }
+            let n = members.len() as f64;
+            let centre = LatLon {
+                lat: members.iter().map(|p| p.lat).sum::<f64>() / n,
+                lon: members.iter().map(|p| p.lon).sum::<f64>() / n,
+            };
+            let spread = members.iter().map(|p| haversine_distance(&centre, p)).fold(0.0, f64::max);
+            Some(aim(camera, pos, heading, &centre, spread * 2.0 + camera.frame_m))
+        }
+    }
+}
+
+/// Solutions for every configured camera against the focus list and its shot.
+pub fn solve(state: &RaceState, focus: &DirectorFocus) -> Vec<CameraSolution> {
+    state.auto_director.cameras.iter()
+        .map(|camera| {
+            let located = locate(state, camera);
+            let shot = focus.cameras.iter().find(|a| a.camera_id == camera.id).and_then(|a| a.target.as_ref());
+            let (assignment, targets) = match &located {
+                Some((pos, heading)) => (
+                    shot.and_then(|target| {
+                        aim_assignment(state, camera, pos, *heading, target).map(|aim| AssignedAim { target: target.clone(), aim })
+                    }),
+                    focus.boats.iter().enumerate()
+                        .filter_map(|(slot, boat_id)| {
+                            let boat = state.boats.get(boat_id)?;
+                            Some(Pointing { boat_id: boat_id.clone(), slot, aim: aim(camera, pos, *heading, &boat.pos, camera.frame_m) })
+                        })
+                        .collect(),
+                ),
+                None => (None, Vec::new()),
+            };
+            CameraSolution {
+                camera_id: camera.id.clone(),
+                platform: camera.platform,
+                role: camera.role,
+                pos: located.map(|(pos, _)| pos),
+                assignment,
+                targets,
+            }
+        })
+        .collect()
+}
+
+pub async fn run_camera_pointing(config: CameraPointingConfig, shared: SharedState, focus: watch::Receiver<DirectorFocus>, io: SocketIo) {
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        let cameras = {
+            let state = shared.read().await;
+            if state.auto_director.cameras.is_empty() {
+                continue;
+            }
+            solve(&state, &focus.borrow())
+        };
+        let _ = io.emit("camera-pointing", &json!({
+            "cameras": cameras,
+            "timestamp": crate::handlers::now_ms(),
+        }));
+    }
+}
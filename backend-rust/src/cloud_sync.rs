@@ -18,7 +18,6 @@ use std::time::Duration;
 use uuid::Uuid;
 
 use crate::state::RaceState;
-use crate::audit::AuditBlock;
 
 pub struct CloudSyncManager {
     pool: Pool<Postgres>,
@@ -119,31 +118,14 @@ impl CloudSyncManager {
         }
     }
 
-    /// Writes an immutable Audit Block natively to Aurora.
-    /// This satisfies the Invariant: "No component is permitted UPDATE or DELETE access."
-    pub async fn push_audit_block(&self, block: &AuditBlock) {
-        let payload = match serde_json::to_value(&block) {
-            Ok(v) => v,
-            Err(_) => return,
-        };
-
-        let query_result = sqlx::query(
-            r#"
-            INSERT INTO audit_log (session_id, block_seq, prev_hash, block_hash, event_type, payload)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            "#
-        )
-        .bind(Uuid::parse_str(&self.session_id).unwrap_or_default())
-        .bind(block.block_seq as i64)
-        .bind(&block.prev_hash)
-        .bind(&block.block_hash)
-        .bind(block.event_type.to_string())
-        .bind(payload)
-        .execute(&self.pool)
-        .await;
+    /// Shared connection pool (used by the audit uploader).
+    pub fn pool(&self) -> Pool<Postgres> {
+        self.pool.clone()
+    }
 
-        if let Err(e) = query_result {
-            warn!("Aurora Audit Push Failed: {e}");
-        }
+    /// The `race_sessions` row this backend mirrors into, when the configured
+    /// session id is a UUID (rows keyed by anything else fail the foreign key).
+    pub fn session_uuid(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.session_id).ok()
     }
 }
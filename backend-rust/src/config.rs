@@ -0,0 +1,233 @@
+//! # config
+//!
+//! Optional `config.toml` for the knobs that are otherwise environment variables.
+//!
+//! `CONFIG_FILE` (default `config.toml`) is read once at startup. Every setting in
+//! it maps to the environment variable the owning module already reads, and is
+//! only applied when that variable is unset — the environment always overrides
+//! the file. Modules keep reading their env vars as before.
+//!
+//! ```toml
+//! [server]
+//! port = 3001
+//! cors_origins = "https://race.example.org"
+//!
+//! [uwb]
+//! udp_port = 5555
+//! ocs_threshold_m = 0.5
+//!
+//! [persistence]
+//! state_store = "sqlite"
+//! snapshot_keep = 96
+//!
+//! [[oidc]]
+//! name = "club"
+//! issuer = "https://sso.club.org"
+//! audiences = ["regatta"]
+//! ```
+//!
+//! `GET /config` (director token) reads back the effective configuration, with
+//! secrets shown as `"<set>"`.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::oidc::OidcProviderConfig;
+
+/// Printed instead of a secret's value
+const REDACTED: &str = "<set>";
+
+/// Declares a config section: each field, its type and the env var it feeds.
+macro_rules! section {
+    ($name:ident { $($field:ident: $ty:ty => $env:literal;)* }) => {
+        #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        pub struct $name {
+            $(
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub $field: Option<$ty>,
+            )*
+        }
+
+        impl $name {
+            /// (env var, value from the file)
+            fn to_env(&self) -> Vec<(&'static str, Option<String>)> {
+                vec![$(($env, self.$field.as_ref().map(|v| v.to_string())),)*]
+            }
+
+            fn from_env() -> Self {
+                Self {
+                    $($field: std::env::var($env).ok().filter(|v| !v.is_empty()).and_then(|v| v.parse().ok()),)*
+                }
+            }
+        }
+    };
+}
+
+section!(ServerConfig {
+    port: u16 => "PORT";
+    cors_origins: String => "CORS_ORIGINS";
+    backend_mode: String => "BACKEND_MODE";
+});
+
+section!(UwbConfig {
+    udp_port: u16 => "UWB_UDP_PORT";
+    multicast_group: String => "UWB_MULTICAST_GROUP";
+    ocs_threshold_m: f64 => "UWB_OCS_THRESHOLD_M";
+    min_fix_quality: u8 => "UWB_MIN_FIX_QUALITY";
+    line_anchors: String => "UWB_LINE_ANCHORS";
+    node_boats: String => "UWB_NODE_BOATS";
+    audit_batch_ms: u64 => "UWB_AUDIT_BATCH_MS";
+    node_keys_file: String => "UWB_NODE_KEYS_FILE";
+    require_node_auth: bool => "UWB_REQUIRE_NODE_AUTH";
+    hub_key_file: String => "UWB_HUB_KEY_FILE";
+    multicast_port: u16 => "UWB_MULTICAST_PORT";
+    multicast_interval_ms: u64 => "UWB_MULTICAST_INTERVAL_MS";
+});
+
+section!(AuditConfig {
+    dir: String => "AUDIT_DIR";
+    max_file_bytes: u64 => "AUDIT_MAX_FILE_BYTES";
+    max_file_age_secs: u64 => "AUDIT_MAX_FILE_AGE_SECS";
+    retention_days: u64 => "AUDIT_RETENTION_DAYS";
+    checkpoint_interval: u64 => "AUDIT_CHECKPOINT_INTERVAL";
+    checkpoint_url: String => "AUDIT_CHECKPOINT_URL";
+    anchor_interval_secs: u64 => "AUDIT_ANCHOR_INTERVAL_SECS";
+});
+
+section!(AuthConfig {
+    supabase_url: String => "SUPABASE_URL";
+    jwt_secret: String => "SUPABASE_JWT_SECRET";
+    jwt_audience: String => "SUPABASE_JWT_AUDIENCE";
+    jwks_refresh_secs: u64 => "SUPABASE_JWKS_REFRESH_SECS";
+    jwt_leeway_secs: u64 => "SUPABASE_JWT_LEEWAY_SECS";
+    apple_client_id: String => "APPLE_CLIENT_ID";
+    google_client_id: String => "GOOGLE_CLIENT_ID";
+    officials: String => "AUTH_OFFICIALS";
+    default_role: String => "AUTH_DEFAULT_ROLE";
+    oidc_jwks_refresh_secs: u64 => "OIDC_JWKS_REFRESH_SECS";
+    permissions_file: String => "PERMISSIONS_FILE";
+    local_auth_file: String => "LOCAL_AUTH_FILE";
+    local_auth_secret: String => "LOCAL_AUTH_SECRET";
+    local_token_hours: u64 => "LOCAL_TOKEN_HOURS";
+    legacy_role_tokens: bool => "LEGACY_ROLE_TOKENS";
+    session_warn_secs: i64 => "SESSION_WARN_SECS";
+    session_grace_secs: i64 => "SESSION_GRACE_SECS";
+    login_attempts_per_min: u64 => "LOGIN_ATTEMPTS_PER_MIN";
+    login_backoff_base_ms: u64 => "LOGIN_BACKOFF_BASE_MS";
+    login_lockout_failures: u64 => "LOGIN_LOCKOUT_FAILURES";
+    login_lockout_secs: u64 => "LOGIN_LOCKOUT_SECS";
+    trusted_proxies: String => "TRUSTED_PROXIES";
+});
+
+section!(TickConfig {
+    state_delta_interval_ms: u64 => "STATE_DELTA_INTERVAL_MS";
+    state_keyframe_secs: u64 => "STATE_KEYFRAME_SECS";
+    scoped_state_interval_ms: u64 => "SCOPED_STATE_INTERVAL_MS";
+    broadcast_feed_interval_ms: u64 => "BROADCAST_FEED_INTERVAL_MS";
+    camera_pointing_interval_ms: u64 => "CAMERA_POINTING_INTERVAL_MS";
+    spectate_interval_ms: u64 => "SPECTATE_INTERVAL_MS";
+    telemetry_max_hz: u32 => "TELEMETRY_MAX_HZ";
+    recording_interval_ms: u64 => "RECORDING_INTERVAL_MS";
+    track_update_max_hz: f64 => "TRACK_UPDATE_MAX_HZ";
+    track_update_burst: f64 => "TRACK_UPDATE_BURST";
+    track_update_downsample_hz: f64 => "TRACK_UPDATE_DOWNSAMPLE_HZ";
+});
+
+section!(PersistenceConfig {
+    state_store: String => "STATE_STORE";
+    state_sqlite_path: String => "STATE_SQLITE_PATH";
+    state_db_url: String => "STATE_DB_URL";
+    save_debounce_ms: u64 => "STATE_SAVE_DEBOUNCE_MS";
+    snapshot_interval_secs: u64 => "SNAPSHOT_INTERVAL_SECS";
+    snapshot_keep: u64 => "SNAPSHOT_KEEP";
+    log_store_path: String => "LOG_STORE_PATH";
+    log_retention_days: u64 => "LOG_RETENTION_DAYS";
+    track_store_dir: String => "TRACK_STORE_DIR";
+    recording_dir: String => "RECORDING_DIR";
+});
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendConfig {
+    pub server: ServerConfig,
+    pub uwb: UwbConfig,
+    pub audit: AuditConfig,
+    pub auth: AuthConfig,
+    pub ticks: TickConfig,
+    pub persistence: PersistenceConfig,
+    /// Generic OpenID Connect issuers (`[[oidc]]`), exported as `OIDC_PROVIDERS`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub oidc: Vec<OidcProviderConfig>,
+}
+
+impl BackendConfig {
+    fn to_env(&self) -> Vec<(&'static str, Option<String>)> {
+        [
+            self.server.to_env(),
+            self.uwb.to_env(),
+            self.audit.to_env(),
+            self.auth.to_env(),
+            self.ticks.to_env(),
+            self.persistence.to_env(),
+            vec![("OIDC_PROVIDERS", Some(&self.oidc).filter(|p| !p.is_empty()).and_then(|p| serde_json::to_string(p).ok()))],
+        ].concat()
+    }
+}
+
+/// Read `CONFIG_FILE` and export its settings to the environment where unset.
+/// Call first thing in `main`, before any task is spawned or any module reads its config.
+pub fn load() {
+    let path: PathBuf = std::env::var("CONFIG_FILE").ok().filter(|p| !p.is_empty())
+        .unwrap_or_else(|| "config.toml".to_string())
+        .into();
+    let data = match std::fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            warn!("Config: failed to read {}: {e}, using environment only", path.display());
+            return;
+        }
+    };
+    let config = match toml::from_str::<BackendConfig>(&data) {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Config: failed to parse {}: {e}, using environment only", path.display());
+            return;
+        }
+    };
+
+    let mut applied = 0;
+    for (key, value) in config.to_env() {
+        let Some(value) = value else { continue };
+        if std::env::var_os(key).is_some() {
+            info!("Config: {key} set in the environment, ignoring {}", path.display());
+            continue;
+        }
+        std::env::set_var(key, value);
+        applied += 1;
+    }
+    info!("Config: {applied} setting(s) applied from {}", path.display());
+}
+
+/// The configuration in effect (file merged under the environment), secrets redacted.
+pub fn effective() -> BackendConfig {
+    let mut config = BackendConfig {
+        server: ServerConfig::from_env(),
+        uwb: UwbConfig::from_env(),
+        audit: AuditConfig::from_env(),
+        auth: AuthConfig::from_env(),
+        ticks: TickConfig::from_env(),
+        persistence: PersistenceConfig::from_env(),
+        oidc: std::env::var("OIDC_PROVIDERS").ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+    };
+    let redact = |v: &mut Option<String>| if v.is_some() { *v = Some(REDACTED.to_string()) };
+    redact(&mut config.auth.jwt_secret);
+    redact(&mut config.auth.local_auth_secret);
+    redact(&mut config.persistence.state_db_url);
+    config
+}
@@ -0,0 +1,269 @@
+//! # control_plane
+//!
+//! Integrity of what the hub sends out over the race WiFi, so a rogue client on
+//! the network cannot forge a designation change to a node or fake fused
+//! positions on competitors' HUDs.
+//!
+//! - **Downlink** (hub → node): `set-mark-designation { nodeId, designation }`
+//!   (0=boat, 1=markA, 2=markB, 3=committee) sends a `uwb_types::ControlCommand`
+//!   to the address the node last sent an authenticated packet from, tagged with
+//!   the node's key from `node_auth`. A node without a key is never sent an
+//!   unsigned command. `seq` starts at the hub's clock in ms and counts up, so it
+//!   keeps increasing across hub restarts.
+//! - **Multicast** (hub → clients): every `UWB_MULTICAST_INTERVAL_MS` (default
+//!   100) the nodes fused since the last tick go to `UWB_MULTICAST_GROUP` on
+//!   `UWB_MULTICAST_PORT` (default 5556) as `{ "type": "fused", "seq", "epoch_ms",
+//!   "nodes", "batch_mode", "sig" }`. `sig` is the hex Ed25519 signature of the
+//!   compact key-sorted JSON without `sig`. Clients only hold the hub's public
+//!   key, so — unlike a shared HMAC key — none of them can sign a packet.
+//!
+//! ## Key distribution
+//! The hub's Ed25519 key is a hex seed in `UWB_HUB_KEY_FILE` (default
+//! `/data/uwb_hub_key`), generated on first start. Keys are handed out when a
+//! node is provisioned, over the cable, never over the race network:
+//!
+//! ```text
+//! regatta-backend uwb-keys provision <nodeId>  → adds a node key to UWB_NODE_KEYS_FILE,
+//!                                                 prints { nodeId, nodeKey, hubPublicKey }
+//! regatta-backend uwb-keys hub-public          → the public key for HUD and client builds
+//! ```
+//!
+//! The hub reads node keys at start, so restart it after provisioning a node.
+//! Authenticated clients can also read the public key at `GET /uwb/hub-key`.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use ed25519_dalek::{Signer, SigningKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde_json::{json, Map, Value};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+use uwb_types::ControlCommand;
+
+use crate::node_auth::{self, NodeAuth};
+use crate::uwb_hub::FusedNode;
+
+const SIG_FIELD: &str = "sig";
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ControlError {
+    #[error("no key provisioned for node {0}, refusing to send an unsigned command")]
+    NoKey(u32),
+    #[error("node {0} has not been heard from, its address is unknown")]
+    UnknownNode(u32),
+    #[error("sending to node {0} failed: {1}")]
+    Send(u32, String),
+    #[error("the UWB hub is not running")]
+    HubDown,
+}
+
+pub struct ControlPlaneConfig {
+    pub multicast_port: u16,
+    pub multicast_interval: Duration,
+}
+
+impl Default for ControlPlaneConfig {
+    fn default() -> Self {
+        Self {
+            multicast_port: std::env::var("UWB_MULTICAST_PORT").ok().and_then(|v| v.parse().ok()).unwrap_or(5556),
+            multicast_interval: Duration::from_millis(
+                std::env::var("UWB_MULTICAST_INTERVAL_MS").ok().and_then(|v| v.parse().ok()).filter(|&ms| ms > 0).unwrap_or(100),
+            ),
+        }
+    }
+}
+
+// ── Hub signing key ───────────────────────────────────────────────────────────
+
+pub struct HubSigner {
+    key: SigningKey,
+    seq: AtomicU64,
+}
+
+fn hub_key_path() -> PathBuf {
+    std::env::var("UWB_HUB_KEY_FILE").unwrap_or_else(|_| "/data/uwb_hub_key".to_string()).into()
+}
+
+/// 32 bytes from the OS CSPRNG.
+fn random_seed() -> [u8; 32] {
+    let mut seed = [0u8; 32];
+    OsRng.fill_bytes(&mut seed);
+    seed
+}
+
+/// Write a key file readable by the owner only (0600, also when it already existed).
+fn write_secret(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    // `mode` only applies to a file this call creates
+    #[cfg(unix)]
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())
+}
+
+impl HubSigner {
+    /// The key from `UWB_HUB_KEY_FILE`, generated and saved if there is none.
+    pub fn load() -> Self {
+        let path = hub_key_path();
+        let stored = std::fs::read_to_string(&path).ok()
+            .and_then(|text| hex::decode(text.trim()).ok())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+        let seed = match stored {
+            Some(seed) => seed,
+            None => {
+                let seed = random_seed();
+                match write_secret(&path, &hex::encode(seed)) {
+                    Ok(()) => info!("ControlPlane: generated hub signing key in {}", path.display()),
+                    Err(e) => warn!("ControlPlane: could not save hub signing key to {}: {e} — clients will see a new key after restart", path.display()),
+                }
+                seed
+            }
+        };
+        Self {
+            key: SigningKey::from_bytes(&seed),
+            seq: AtomicU64::new(crate::time_discipline::now_ms()),
+        }
+    }
+
+    pub fn public_hex(&self) -> String {
+        hex::encode(self.key.verifying_key().to_bytes())
+    }
+
+    pub fn next_seq(&self) -> u64 {
+        self.seq.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Sign the packet (compact, key-sorted JSON without `sig`).
+    pub fn sign(&self, packet: &mut Map<String, Value>) {
+        packet.remove(SIG_FIELD);
+        let body = serde_json::to_vec(packet).unwrap_or_default();
+        let sig = self.key.sign(&body);
+        packet.insert(SIG_FIELD.to_string(), Value::from(hex::encode(sig.to_bytes())));
+    }
+
+    /// A signed fused-position multicast packet.
+    pub fn fused_packet(&self, nodes: Vec<FusedNode>, batch_mode: bool) -> Vec<u8> {
+        let mut packet = match json!({
+            "type": "fused",
+            "seq": self.next_seq(),
+            "epoch_ms": crate::time_discipline::now_ms(),
+            "nodes": nodes,
+            "batch_mode": batch_mode,
+        }) {
+            Value::Object(packet) => packet,
+            _ => Map::new(),
+        };
+        self.sign(&mut packet);
+        serde_json::to_vec(&packet).unwrap_or_default()
+    }
+}
+
+// ── Downlink ──────────────────────────────────────────────────────────────────
+
+/// A command on its way to the hub task, which knows the node addresses.
+pub struct Downlink {
+    pub node_id: u32,
+    pub command: ControlCommand,
+    pub reply: oneshot::Sender<Result<u64, ControlError>>,
+}
+
+/// Handle for the socket handlers to send commands to nodes.
+#[derive(Clone)]
+pub struct ControlDownlink {
+    tx: mpsc::Sender<Downlink>,
+}
+
+impl ControlDownlink {
+    pub fn channel() -> (Self, mpsc::Receiver<Downlink>) {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        (Self { tx }, rx)
+    }
+
+    /// Send a command; returns its `seq` once it is on the wire.
+    pub async fn send(&self, node_id: u32, command: ControlCommand) -> Result<u64, ControlError> {
+        let (reply, result) = oneshot::channel();
+        self.tx.send(Downlink { node_id, command, reply }).await.map_err(|_| ControlError::HubDown)?;
+        result.await.map_err(|_| ControlError::HubDown)?
+    }
+}
+
+/// The signed datagram for a command, and where it goes.
+pub fn control_packet(
+    signer: &HubSigner,
+    node_auth: &NodeAuth,
+    node_addrs: &HashMap<u32, SocketAddr>,
+    node_id: u32,
+    command: ControlCommand,
+) -> Result<(u64, SocketAddr, Vec<u8>), ControlError> {
+    let addr = *node_addrs.get(&node_id).ok_or(ControlError::UnknownNode(node_id))?;
+    let seq = signer.next_seq();
+    let mut packet = match serde_json::to_value(command) {
+        Ok(Value::Object(packet)) => packet,
+        _ => Map::new(),
+    };
+    packet.insert("type".to_string(), Value::from("control"));
+    packet.insert("node_id".to_string(), Value::from(node_id));
+    packet.insert("seq".to_string(), Value::from(seq));
+    packet.insert("issued_ms".to_string(), Value::from(crate::time_discipline::now_ms()));
+    node_auth.sign(&mut packet, node_id).map_err(|_| ControlError::NoKey(node_id))?;
+    Ok((seq, addr, serde_json::to_vec(&packet).unwrap_or_default()))
+}
+
+// ── Provisioning tool ─────────────────────────────────────────────────────────
+
+/// `regatta-backend uwb-keys …`; returns the process exit code.
+pub fn run_tool(args: &[String]) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("provision") if args.len() == 2 => {
+            let Ok(node_id) = args[1].parse::<u32>() else {
+                eprintln!("uwb-keys provision: node id must be a number");
+                return 1;
+            };
+            let path = std::env::var("UWB_NODE_KEYS_FILE").unwrap_or_else(|_| "/data/uwb_node_keys.json".to_string());
+            let mut keys: Map<String, Value> = match std::fs::read_to_string(&path) {
+                Ok(text) => match node_auth::parse_keys(&text).and_then(|_| serde_json::from_str(&text).map_err(|e| e.to_string())) {
+                    Ok(keys) => keys,
+                    Err(e) => {
+                        eprintln!("uwb-keys provision: {path} is not a valid key file: {e}");
+                        return 2;
+                    }
+                },
+                Err(_) => Map::new(),
+            };
+            let node_key = hex::encode(random_seed());
+            keys.insert(node_id.to_string(), Value::from(node_key.clone()));
+            if let Err(e) = write_secret(Path::new(&path), &serde_json::to_string_pretty(&keys).unwrap_or_default()) {
+                eprintln!("uwb-keys provision: could not write {path}: {e}");
+                return 2;
+            }
+            let bundle = json!({
+                "nodeId": node_id,
+                "nodeKey": node_key,
+                "hubPublicKey": HubSigner::load().public_hex(),
+            });
+            println!("{}", serde_json::to_string_pretty(&bundle).unwrap_or_default());
+            0
+        }
+        Some("hub-public") => {
+            println!("{}", HubSigner::load().public_hex());
+            0
+        }
+        _ => {
+            eprintln!("usage: regatta-backend uwb-keys provision <nodeId>");
+            eprintln!("       regatta-backend uwb-keys hub-public");
+            1
+        }
+    }
+}
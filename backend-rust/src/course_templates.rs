@@ -0,0 +1,181 @@
+//! # course_templates
+//!
+//! `generate-course` lays a standard course square to the wind in one step,
+//! replacing the marks, start and finish lines and the course order.
+//!
+//! ```json
+//! { "type": "WINDWARD_LEEWARD", "windDirection": 225, "legLengthM": 1200,
+//!   "origin": { "lat": 60.16, "lon": 24.95 }, "laps": 2 }
+//! ```
+//!
+//! The origin is the middle of the start line (default: the current start line,
+//! then the default location); the wind defaults to `state.wind`. Marks are
+//! rounded to port; gates are downwind gates, the `s` mark rounded to starboard.
+//!
+//! - `WINDWARD_LEEWARD` — mark 1 one leg upwind, leeward gate 4s/4p just above the
+//!   line: Start, (1, 4s/4p) × laps, Finish
+//! - `TRIANGLE` — mark 1, wing mark 2 on a 60° triangle to port, leeward mark 3:
+//!   Start, 1, 2, 3, then (1, 3) for further laps, Finish
+//! - `TRAPEZOID` — mark 1, reach to mark 2, run to gate 3s/3p parallel to the beat:
+//!   Start, 1, (2, 3s/3p) × laps, Finish
+//!
+//! Start line `lineLengthM` (default 150) wide, gates `gateWidthM` (default 60);
+//! the finish is a half-width line across the last beat, a tenth of a leg below
+//! mark 1.
+
+use serde::Deserialize;
+
+use crate::ranking_engine::destination;
+use crate::state::{
+    Buoy, BuoyDesign, BuoyType, CourseElement, CourseElementType, CourseLine, LatLon, RaceState, Rounding,
+};
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CourseType {
+    WindwardLeeward,
+    Triangle,
+    Trapezoid,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseSpec {
+    #[serde(rename = "type")]
+    pub course_type: CourseType,
+    pub wind_direction: Option<f64>,
+    pub leg_length_m: Option<f64>,
+    pub leg_length_nm: Option<f64>,
+    pub origin: Option<LatLon>,
+    pub line_length_m: Option<f64>,
+    pub gate_width_m: Option<f64>,
+    pub laps: Option<u32>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CourseTemplateError {
+    #[error("A course needs a leg length")]
+    MissingLegLength,
+    #[error("No origin: set a start line or default location, or pass one")]
+    MissingOrigin,
+}
+
+fn buoy(id: &str, buoy_type: BuoyType, name: &str, pos: LatLon, rounding: Option<Rounding>, pair_id: Option<&str>) -> Buoy {
+    let gate_direction = (buoy_type == BuoyType::Gate).then(|| "DOWNWIND".to_string());
+    Buoy {
+        id: id.to_string(),
+        buoy_type,
+        name: name.to_string(),
+        pos,
+        color: Some("Orange".to_string()),
+        rounding,
+        pair_id: pair_id.map(str::to_string),
+        gate_direction,
+        design: Some(BuoyDesign::Cylindrical),
+        label: None,
+        show_laylines: true,
+        layline_direction: 0.0,
+    }
+}
+
+fn element(id: &str, element_type: CourseElementType, name: &str, marks: &[&str]) -> CourseElement {
+    CourseElement {
+        id: id.to_string(),
+        element_type,
+        name: name.to_string(),
+        marks: marks.iter().map(|m| m.to_string()).collect(),
+    }
+}
+
+fn origin(state: &RaceState, spec: &CourseSpec) -> Option<LatLon> {
+    if let Some(origin) = &spec.origin {
+        return Some(origin.clone());
+    }
+    if let Some(CourseLine { p1: Some(p1), p2: Some(p2) }) = &state.course.start_line {
+        return Some(LatLon { lat: (p1.lat + p2.lat) / 2.0, lon: (p1.lon + p2.lon) / 2.0 });
+    }
+    state.default_location.as_ref().map(|l| LatLon { lat: l.lat, lon: l.lon })
+}
+
+/// Lay the course described by `spec` into `state.course` and `active_course_order`.
+pub fn generate(state: &mut RaceState, spec: &CourseSpec) -> Result<(), CourseTemplateError> {
+    let leg = spec.leg_length_m
+        .or(spec.leg_length_nm.map(|nm| nm * 1852.0))
+        .filter(|l| *l > 0.0)
+        .ok_or(CourseTemplateError::MissingLegLength)?;
+    let o = origin(state, spec).ok_or(CourseTemplateError::MissingOrigin)?;
+    let twd = spec.wind_direction.unwrap_or(state.wind.direction).rem_euclid(360.0);
+    let line = spec.line_length_m.filter(|l| *l > 0.0).unwrap_or(150.0);
+    let gate = spec.gate_width_m.filter(|g| *g > 0.0).unwrap_or(60.0);
+    let laps = spec.laps.unwrap_or(2).max(1);
+
+    // Upwind along the wind, starboard looking upwind
+    let up = |p: &LatLon, d: f64| destination(p, twd, d);
+    let across = |p: &LatLon, d: f64| destination(p, twd + 90.0, d);
+    let pair = |centre: &LatLon, width: f64| (across(centre, -width / 2.0), across(centre, width / 2.0));
+
+    let (pin, committee) = pair(&o, line);
+    let mark1 = up(&o, leg);
+    let (finish_pin, finish_boat) = pair(&up(&o, leg * 0.9), line / 2.0);
+
+    let mut marks = vec![
+        buoy("start-pin", BuoyType::Start, "Start Pin", pin.clone(), None, None),
+        buoy("start-committee", BuoyType::Start, "Committee Boat", committee.clone(), None, None),
+        buoy("mark-1", BuoyType::Mark, "Mark 1", mark1, Some(Rounding::Port), None),
+    ];
+    let mut order = vec![element("el-start", CourseElementType::StartLine, "Start", &["start-pin", "start-committee"])];
+    let m1 = element("el-1", CourseElementType::Mark, "Mark 1", &["mark-1"]);
+
+    match spec.course_type {
+        CourseType::WindwardLeeward => {
+            let (g_s, g_p) = pair(&up(&o, leg * 0.1), gate);
+            marks.push(buoy("gate-4s", BuoyType::Gate, "Gate 4s", g_s, Some(Rounding::Starboard), Some("gate-4")));
+            marks.push(buoy("gate-4p", BuoyType::Gate, "Gate 4p", g_p, Some(Rounding::Port), Some("gate-4")));
+            let g4 = element("el-4", CourseElementType::Gate, "Gate 4s/4p", &["gate-4s", "gate-4p"]);
+            for _ in 0..laps {
+                order.push(m1.clone());
+                order.push(g4.clone());
+            }
+        }
+        CourseType::Triangle => {
+            // Equilateral with the 1–3 beat as base, wing mark to port looking upwind
+            let mark3 = up(&o, leg * 0.1);
+            let side = leg * 0.9;
+            let mark2 = across(&up(&mark3, side / 2.0), -side * 3f64.sqrt() / 2.0);
+            marks.push(buoy("mark-2", BuoyType::Mark, "Mark 2", mark2, Some(Rounding::Port), None));
+            marks.push(buoy("mark-3", BuoyType::Mark, "Mark 3", mark3, Some(Rounding::Port), None));
+            let m2 = element("el-2", CourseElementType::Mark, "Mark 2", &["mark-2"]);
+            let m3 = element("el-3", CourseElementType::Mark, "Mark 3", &["mark-3"]);
+            order.extend([m1.clone(), m2, m3.clone()]);
+            for _ in 1..laps {
+                order.push(m1.clone());
+                order.push(m3.clone());
+            }
+        }
+        CourseType::Trapezoid => {
+            // Reach at 60° below the wind to port, then a run parallel to the beat
+            let mark2 = destination(&up(&o, leg), twd - 120.0, leg * 2.0 / 3.0);
+            let (g_s, g_p) = pair(&destination(&mark2, twd + 180.0, leg * 0.8), gate);
+            marks.push(buoy("mark-2", BuoyType::Mark, "Mark 2", mark2, Some(Rounding::Port), None));
+            marks.push(buoy("gate-3s", BuoyType::Gate, "Gate 3s", g_s, Some(Rounding::Starboard), Some("gate-3")));
+            marks.push(buoy("gate-3p", BuoyType::Gate, "Gate 3p", g_p, Some(Rounding::Port), Some("gate-3")));
+            let m2 = element("el-2", CourseElementType::Mark, "Mark 2", &["mark-2"]);
+            let g3 = element("el-3", CourseElementType::Gate, "Gate 3s/3p", &["gate-3s", "gate-3p"]);
+            order.push(m1.clone());
+            for _ in 0..laps {
+                order.push(m2.clone());
+                order.push(g3.clone());
+            }
+        }
+    }
+
+    marks.push(buoy("finish-pin", BuoyType::Finish, "Finish Pin", finish_pin.clone(), None, None));
+    marks.push(buoy("finish-boat", BuoyType::Finish, "Finish Boat", finish_boat.clone(), None, None));
+    order.push(element("el-finish", CourseElementType::FinishLine, "Finish", &["finish-pin", "finish-boat"]));
+
+    state.course.marks = marks;
+    state.course.start_line = Some(CourseLine { p1: Some(pin), p2: Some(committee) });
+    state.course.finish_line = Some(CourseLine { p1: Some(finish_pin), p2: Some(finish_boat) });
+    state.active_course_order = Some(order);
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! # crew
+//!
+//! Emergency contacts of the crews in the entry list.
+//!
+//! Names, roles and nationalities travel with the entry (`Entry::crew`) to every
+//! client. An `emergencyContact` sent with `upsert-entry` is taken off the entry
+//! before it reaches the state and kept here instead, in a `state_store`
+//! document per event (`crew_contacts`, `crew_contacts-<eventId>`), so it never
+//! goes out with `state-update`. The command's audit block is written without
+//! them too, as the chain is uploaded and exported with event bundles.
+//!
+//! - `get-crew-contacts { boatId? }` → `crew-contacts { <boatId>: { <crew name>: contact } }`,
+//!   director and jury only (see `permissions`)
+//!
+//! Removing an entry removes its contacts.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::state::{EmergencyContact, Entry, RaceState};
+use crate::state_store::store;
+
+/// boat id → crew member name → contact
+pub type CrewContacts = HashMap<String, HashMap<String, EmergencyContact>>;
+
+/// Store key of the active event's contacts.
+pub fn key(state: &RaceState) -> String {
+    match &state.active_event_id {
+        Some(id) => format!("crew_contacts-{id}"),
+        None => "crew_contacts".to_string(),
+    }
+}
+
+/// Take the contacts off an incoming entry: crew member name → contact.
+pub fn take_contacts(entry: &mut Entry) -> HashMap<String, EmergencyContact> {
+    entry.crew.iter_mut()
+        .filter_map(|m| Some((m.name.clone(), m.emergency_contact.take()?)))
+        .collect()
+}
+
+/// An `upsert-entry` payload without its crew's emergency contacts, for the audit chain.
+pub fn without_contacts(data: &Value) -> Value {
+    let mut data = data.clone();
+    if let Some(crew) = data.get_mut("crew").and_then(Value::as_array_mut) {
+        for member in crew.iter_mut().filter_map(Value::as_object_mut) {
+            member.remove("emergencyContact");
+        }
+    }
+    data
+}
+
+pub async fn load(key: &str) -> anyhow::Result<CrewContacts> {
+    match store().load(key).await? {
+        Some(data) => Ok(serde_json::from_str(&data)?),
+        None => Ok(CrewContacts::new()),
+    }
+}
+
+/// Replace the boat's contacts, keeping only members in `crew`; None removes the boat.
+pub async fn save(key: &str, boat_id: &str, crew: &[String], contacts: Option<HashMap<String, EmergencyContact>>) -> anyhow::Result<()> {
+    let mut all = load(key).await?;
+    match contacts {
+        Some(mut contacts) => {
+            // Members sent without a contact keep the one on file
+            for (name, contact) in all.remove(boat_id).unwrap_or_default() {
+                contacts.entry(name).or_insert(contact);
+            }
+            contacts.retain(|name, _| crew.contains(name));
+            all.insert(boat_id.to_string(), contacts);
+        }
+        None => {
+            all.remove(boat_id);
+        }
+    }
+    store().save(key, &serde_json::to_string(&all)?).await
+}
@@ -0,0 +1,135 @@
+//! # engine_bus
+//!
+//! Typed event bus for procedure engine transitions.
+//!
+//! The engines record `EngineEvent`s as they move (node entered, sound fired,
+//! starting signal, sequence complete, race status changed). The tick loops
+//! drain them every tick and publish them here, tagged with the engine they
+//! came from (`"main"` or a class id) and the disciplined time they were seen,
+//! after `RaceState` has been updated for that tick.
+//! Subsystems that react to transitions — the UWB gun solve, the auto-director,
+//! the status audit, the horn/flag outputs — subscribe instead of polling and
+//! re-deriving transitions from `RaceState`.
+//!
+//! Every event is also forwarded to clients as `engine-event`.
+//!
+//! ## Invariants
+//! - Core Invariant #8: publishing never blocks the tick loop; a lagging subscriber
+//!   loses the oldest events and is told how many it missed
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+use socketioxide::SocketIo;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::audit::AuditLogger;
+use crate::handlers::audit_status_change;
+use crate::procedure_engine::EngineEvent;
+use crate::state::RaceStatus;
+use crate::uwb_hub;
+
+/// Events buffered per subscriber before it starts lagging
+const BUS_CAPACITY: usize = 256;
+
+/// Engine id of the main (single-fleet) sequence
+pub const MAIN_ENGINE: &str = "main";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineEventEnvelope {
+    /// `"main"` or the class id of a per-class engine
+    pub source: String,
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub event: EngineEvent,
+}
+
+#[derive(Clone)]
+pub struct EngineBus {
+    tx: broadcast::Sender<EngineEventEnvelope>,
+}
+
+impl EngineBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(BUS_CAPACITY);
+        Self { tx }
+    }
+
+    pub fn publish(&self, source: &str, events: Vec<EngineEvent>) {
+        let at_ms = crate::time_discipline::now_ms();
+        for event in events {
+            // No subscribers is fine — nothing is listening yet
+            let _ = self.tx.send(EngineEventEnvelope { source: source.to_string(), at_ms, event });
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EngineEventEnvelope> {
+        self.tx.subscribe()
+    }
+}
+
+/// Receive the next event, skipping over any we lagged behind on.
+/// `None` once the bus is gone.
+pub async fn next_event(rx: &mut broadcast::Receiver<EngineEventEnvelope>, subscriber: &str) -> Option<EngineEventEnvelope> {
+    loop {
+        match rx.recv().await {
+            Ok(envelope) => return Some(envelope),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("EngineBus: {subscriber} lagged, {missed} events dropped");
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Forward every engine event to clients.
+pub async fn run_client_forwarder(mut rx: broadcast::Receiver<EngineEventEnvelope>, io: SocketIo) {
+    while let Some(envelope) = next_event(&mut rx, "client forwarder").await {
+        let _ = io.emit("engine-event", &envelope);
+    }
+}
+
+/// Audit the status changes the sequences made on their own. Changes made by a
+/// command (`by_sequence: false`) are audited by the command with its own reason.
+pub async fn run_status_audit(mut rx: broadcast::Receiver<EngineEventEnvelope>, audit: AuditLogger) {
+    // Sources whose sequence just ran out; the tick loop records how it ended
+    let mut completed: HashSet<String> = HashSet::new();
+    while let Some(envelope) = next_event(&mut rx, "status audit").await {
+        let source = envelope.source;
+        match envelope.event {
+            EngineEvent::SequenceComplete => {
+                completed.insert(source);
+            }
+            EngineEvent::StatusChanged { from, to, by_sequence } => {
+                let ended = completed.remove(&source) && to == RaceStatus::Idle;
+                if !by_sequence || ended {
+                    continue;
+                }
+                let reason = match (source.as_str(), &to) {
+                    (MAIN_ENGINE, RaceStatus::Racing) => "gun".to_string(),
+                    (MAIN_ENGINE, _) => "procedure".to_string(),
+                    (class_id, _) => format!("class {class_id}"),
+                };
+                audit_status_change(&audit, &from, &to, &reason).await;
+            }
+            EngineEvent::NodeEntered { .. } => {
+                completed.remove(&source);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Trigger the UWB concurrent batch solve for sub-cm OCS detection at the main start signal.
+pub async fn run_gun_solve(mut rx: broadcast::Receiver<EngineEventEnvelope>, gun_tx: tokio::sync::mpsc::Sender<uwb_hub::GunSignal>) {
+    while let Some(envelope) = next_event(&mut rx, "gun solve").await {
+        if envelope.source == MAIN_ENGINE && matches!(envelope.event, EngineEvent::GunFired { .. }) {
+            info!("🎯 T-0 GUN FIRED: UWB concurrent batch solve for OCS detection");
+            if gun_tx.send(uwb_hub::GunSignal { gun_ms: envelope.at_ms }).await.is_err() {
+                warn!("EngineBus: UWB hub is not running, no gun batch solve");
+            }
+        }
+    }
+}
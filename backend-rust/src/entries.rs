@@ -0,0 +1,83 @@
+//! # entries
+//!
+//! Entry list: which real boat (sail number, name, class, rating, crew, UWB tag)
+//! is behind each `boat_id`.
+//!
+//! - An entry's `boat_id` defaults to its sail number, so a tracker registered with
+//!   the sail number needs no extra mapping
+//! - Commands that name a boat (`issue-penalty`, `record-finish`, …) accept either
+//!   the boat id or the sail number; `canonical_id` turns both into the boat id
+//! - UWB node ids resolve through the entries' `node_id` before the
+//!   `UWB_NODE_BOATS` fallback
+//!
+//! - Crew members carry name, role and nationality; their emergency contacts are
+//!   kept apart by `crew`
+//!
+//! Unregistered boat ids keep working as before, so a session without an entry
+//! list behaves exactly as it used to.
+
+use crate::state::{Entry, RaceState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum EntryError {
+    #[error("An entry needs a sail number")]
+    MissingSailNumber,
+    #[error("Sail number {0} is already entered")]
+    DuplicateSailNumber(String),
+    #[error("UWB node {0} is already fitted to {1}")]
+    DuplicateNode(u32, String),
+    #[error("Unknown entry: {0}")]
+    NotFound(String),
+}
+
+fn same_sail(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// Entry by boat id or sail number.
+pub fn resolve<'a>(state: &'a RaceState, key: &str) -> Option<&'a Entry> {
+    state.entries.iter().find(|e| e.boat_id == key)
+        .or_else(|| state.entries.iter().find(|e| same_sail(&e.sail_number, key)))
+}
+
+/// The boat id for a boat id or sail number; unregistered keys pass through.
+pub fn canonical_id(state: &RaceState, key: &str) -> String {
+    resolve(state, key).map(|e| e.boat_id.clone()).unwrap_or_else(|| key.to_string())
+}
+
+pub fn for_node(entries: &[Entry], node_id: u32) -> Option<&Entry> {
+    entries.iter().find(|e| e.node_id == Some(node_id))
+}
+
+/// Add an entry, or replace the one with the same boat id.
+pub fn upsert(state: &mut RaceState, mut entry: Entry) -> Result<Entry, EntryError> {
+    entry.sail_number = entry.sail_number.trim().to_string();
+    if entry.sail_number.is_empty() {
+        return Err(EntryError::MissingSailNumber);
+    }
+    if entry.boat_id.trim().is_empty() {
+        entry.boat_id = entry.sail_number.clone();
+    }
+    let others = || state.entries.iter().filter(|e| e.boat_id != entry.boat_id);
+    if others().any(|e| same_sail(&e.sail_number, &entry.sail_number)) {
+        return Err(EntryError::DuplicateSailNumber(entry.sail_number));
+    }
+    if let Some(node) = entry.node_id {
+        if let Some(other) = others().find(|e| e.node_id == Some(node)) {
+            return Err(EntryError::DuplicateNode(node, other.sail_number.clone()));
+        }
+    }
+
+    match state.entries.iter_mut().find(|e| e.boat_id == entry.boat_id) {
+        Some(existing) => *existing = entry.clone(),
+        None => state.entries.push(entry.clone()),
+    }
+    state.entries.sort_by(|a, b| a.sail_number.cmp(&b.sail_number));
+    Ok(entry)
+}
+
+pub fn remove(state: &mut RaceState, key: &str) -> Result<Entry, EntryError> {
+    let boat_id = resolve(state, key).map(|e| e.boat_id.clone()).ok_or_else(|| EntryError::NotFound(key.to_string()))?;
+    let index = state.entries.iter().position(|e| e.boat_id == boat_id).ok_or_else(|| EntryError::NotFound(key.to_string()))?;
+    Ok(state.entries.remove(index))
+}
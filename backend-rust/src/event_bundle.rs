@@ -0,0 +1,140 @@
+//! # event_bundle
+//!
+//! One file that carries a whole event between backends, e.g. from the laptop on
+//! the committee boat to the cloud instance after racing.
+//!
+//! A bundle is zstd-compressed JSON:
+//!
+//! ```json
+//! { "format": "regatta-event-bundle", "version": 1, "exportedMs": 0,
+//!   "state": { … persistent RaceState: entries, course, procedures, results … },
+//!   "logs": [LogEntry], "audit": [{ "name": "default-….jsonl", "data": "<base64>" }] }
+//! ```
+//!
+//! - `GET /api/v1/bundle` exports (`event-bundle.json.zst`)
+//! - `POST /api/v1/bundle` imports: refused while a sequence runs; a `pre-import`
+//!   snapshot is taken first, then the state's persistent fields are replaced as
+//!   by `restore-snapshot`. Logs are merged into the log store (same ids win).
+//!   The audit files are kept under `<AUDIT_DIR>/imported/<exportedMs>/` for
+//!   verification — never merged, so the live chain stays intact
+//!
+//! ## Invariants
+//! - Core Invariant #2: an import is recorded in the audit chain
+//! - Core Invariant #8: file I/O runs after the state lock is released
+
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::audit::AuditLogger;
+use crate::log_store;
+use crate::persistence::saveable;
+use crate::state::{LogEntry, RaceState};
+
+pub const FORMAT: &str = "regatta-event-bundle";
+pub const VERSION: u32 = 1;
+const ZSTD_LEVEL: i32 = 9;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("Not an event bundle: {0}")]
+    Unreadable(String),
+    #[error("Unsupported bundle version {0} (this backend reads {VERSION})")]
+    Version(u32),
+    #[error("Stop the running sequence before importing an event")]
+    SequenceRunning,
+    #[error("Bundle I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledFile {
+    pub name: String,
+    /// Base64 file content
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_ms: i64,
+    pub state: RaceState,
+    #[serde(default)]
+    pub logs: Vec<LogEntry>,
+    #[serde(default)]
+    pub audit: Vec<BundledFile>,
+}
+
+/// What an import brought in, for the response and the audit block.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub exported_ms: i64,
+    pub entries: usize,
+    pub races: usize,
+    pub logs: usize,
+    pub audit_files: usize,
+    pub audit_dir: String,
+}
+
+/// Package `state` with every stored log entry and audit file.
+pub async fn export(state: RaceState, audit: &AuditLogger, now: i64) -> Result<Vec<u8>, BundleError> {
+    let mut files = Vec::new();
+    for path in audit.files_for(None).await {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else { continue };
+        match tokio::fs::read(&path).await {
+            Ok(data) => files.push(BundledFile { name, data: BASE64.encode(data) }),
+            Err(e) => warn!("EventBundle: skipping audit file {}: {e}", path.display()),
+        }
+    }
+    let bundle = EventBundle {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_ms: now,
+        state: saveable(&state),
+        logs: log_store::all().await,
+        audit: files,
+    };
+    let json = serde_json::to_vec(&bundle).map_err(|e| BundleError::Unreadable(e.to_string()))?;
+    Ok(zstd::encode_all(json.as_slice(), ZSTD_LEVEL)?)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<EventBundle, BundleError> {
+    let json = zstd::decode_all(bytes).map_err(|e| BundleError::Unreadable(e.to_string()))?;
+    let bundle: EventBundle = serde_json::from_slice(&json).map_err(|e| BundleError::Unreadable(e.to_string()))?;
+    if bundle.format != FORMAT {
+        return Err(BundleError::Unreadable(format!("format {}", bundle.format)));
+    }
+    if bundle.version > VERSION {
+        return Err(BundleError::Version(bundle.version));
+    }
+    Ok(bundle)
+}
+
+/// Write the bundle's logs and audit files; call once its state is in place.
+pub async fn store_files(bundle: &EventBundle, audit_dir: &Path) -> Result<ImportSummary, BundleError> {
+    for log in &bundle.logs {
+        log_store::append(log).await;
+    }
+    let dir: PathBuf = audit_dir.join("imported").join(bundle.exported_ms.to_string());
+    tokio::fs::create_dir_all(&dir).await?;
+    for file in &bundle.audit {
+        // Bundles come from elsewhere: keep only the file name
+        let Some(name) = Path::new(&file.name).file_name() else { continue };
+        let data = BASE64.decode(&file.data).map_err(|e| BundleError::Unreadable(format!("audit file {}: {e}", file.name)))?;
+        tokio::fs::write(dir.join(name), data).await?;
+    }
+    Ok(ImportSummary {
+        exported_ms: bundle.exported_ms,
+        entries: bundle.state.entries.len(),
+        races: bundle.state.results.len(),
+        logs: bundle.logs.len(),
+        audit_files: bundle.audit.len(),
+        audit_dir: dir.display().to_string(),
+    })
+}
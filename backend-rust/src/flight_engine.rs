@@ -1,8 +1,105 @@
-use crate::state::{Flight, FlightStatus, Pairing, Team};
+//! # flight_engine
+//!
+//! League schedules for `generate-flights { flightCount, boats, balanced? }`:
+//! the opponent/boat balanced generator by default, the cyclic rotation with
+//! `balanced: false`. Either way the sender gets `flight-fairness` — a
+//! `FairnessReport` of how often each pair of teams meets and how often each
+//! team sails each boat.
+//!
+//! Both honour the event's `ScheduleConstraints` (`set-schedule-constraints
+//! { minRestRaces, unavailable: [{ teamId, fromFlight, toFlight, reason? }] }`):
+//! a team sits out the flights it is unavailable for, and between two of its
+//! races there are at least `minRestRaces` other races. Races within a flight are
+//! reordered, then teams swapped between them, to make the rest; what cannot be
+//! made is returned as `ScheduleError::Infeasible` with every conflict and no
+//! schedule is written.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::Serialize;
+use crate::state::{Flight, FlightOfficials, FlightStatus, Pairing, ScheduleConstraints, Team};
 use uuid::Uuid;
 
+/// Improvement passes per flight before the balanced generator settles
+const MAX_PASSES: usize = 50;
+
+/// Race orders tried per flight when making rest; above this many races only rotations
+const MAX_PERMUTED_RACES: usize = 6;
+
 pub struct FlightEngine;
 
+/// A team that cannot get its rest before a flight.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleConflict {
+    pub team_id: String,
+    pub flight_number: u32,
+    /// Races between its previous race and its race in this flight
+    pub rest_races: u32,
+    pub required: u32,
+}
+
+impl std::fmt::Display for ScheduleConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "team {} rests {} of {} races before flight {}", self.team_id, self.rest_races, self.required, self.flight_number)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("No schedule gives every team its rest: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    Infeasible(Vec<ScheduleConflict>),
+}
+
+/// Lowest and highest of a count, and the gap between them.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Spread {
+    pub min: u32,
+    pub max: u32,
+    pub spread: u32,
+}
+
+impl Spread {
+    fn of(counts: impl Iterator<Item = u32>) -> Self {
+        let (min, max) = counts.fold((u32::MAX, 0), |(lo, hi), c| (lo.min(c), hi.max(c)));
+        if min == u32::MAX {
+            return Self::default();
+        }
+        Self { min, max, spread: max - min }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairMeetings {
+    pub team_a: String,
+    pub team_b: String,
+    pub meetings: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamBoats {
+    pub team_id: String,
+    /// Races in each boat, by boat id
+    pub boats: BTreeMap<String, u32>,
+}
+
+/// How evenly a schedule spreads opponents and boats over the teams.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FairnessReport {
+    pub flights: u32,
+    /// Races each pair of teams sails together
+    pub pair_meetings: Spread,
+    /// Pairs that never race each other
+    pub unmet_pairs: usize,
+    /// Races each team sails in each boat
+    pub boat_usage: Spread,
+    pub pairs: Vec<PairMeetings>,
+    pub teams: Vec<TeamBoats>,
+}
+
 impl FlightEngine {
     /// Generates a fair rotation schedule for League racing.
     /// 
@@ -14,13 +111,14 @@ impl FlightEngine {
         teams: Vec<Team>,
         boat_count: u32,
         flight_count: u32,
-    ) -> (Vec<Flight>, Vec<Pairing>) {
+        constraints: &ScheduleConstraints,
+    ) -> Result<(Vec<Flight>, Vec<Pairing>), ScheduleError> {
         let mut flights = Vec::new();
         let mut pairings = Vec::new();
         
         let num_teams = teams.len();
         if num_teams == 0 || boat_count == 0 || flight_count == 0 {
-            return (flights, pairings);
+            return Ok((flights, pairings));
         }
         
         // Calculate races per flight
@@ -34,6 +132,8 @@ impl FlightEngine {
                 flight_number: f + 1,
                 group_label: format!("Flight {}", f + 1),
                 status: FlightStatus::Scheduled,
+                stage: None,
+                officials: FlightOfficials::default(),
             };
             flights.push(flight);
             
@@ -112,6 +212,312 @@ impl FlightEngine {
             }
         }
         
-        (flights, pairings)
+        // 3. Constraints: unavailable teams sit out, then rest between races
+        pairings.retain(|p| {
+            let number = flights.iter().find(|f| f.id == p.flight_id).map_or(0, |f| f.flight_number);
+            !constraints.is_unavailable(&p.team_id, number)
+        });
+        for flight in &flights {
+            Self::compact_races(&flight.id, &mut pairings);
+        }
+        Self::enforce_rest(&flights, &mut pairings, constraints.min_rest_races)?;
+        
+        Ok((flights, pairings))
+    }
+
+    /// Generates a League schedule that balances opponents and boats rather than
+    /// relying on a fixed rotation.
+    ///
+    /// Race sizes per flight are the same as `generate_rotation_schedule`, over
+    /// the teams available for the flight. Each
+    /// flight, teams are placed in the race where they have met the fewest of its
+    /// teams so far, then swapped between races while that lowers the repeat
+    /// meetings. Boats go to the team that has sailed them least; keeping a team
+    /// in its boat between back-to-back races only breaks ties.
+    pub fn generate_balanced_schedule(
+        teams: Vec<Team>,
+        boat_count: u32,
+        flight_count: u32,
+        constraints: &ScheduleConstraints,
+    ) -> Result<(Vec<Flight>, Vec<Pairing>, FairnessReport), ScheduleError> {
+        let mut flights = Vec::new();
+        let mut pairings = Vec::new();
+
+        let n = teams.len();
+        let boats = boat_count as usize;
+        if n == 0 || boats == 0 || flight_count == 0 {
+            return Ok((flights, pairings, FairnessReport::default()));
+        }
+
+        let mut meets = vec![vec![0u32; n]; n];
+        let mut usage = vec![vec![0u32; boats]; n];
+        // Boat of each team that sailed the previous flight's last race
+        let mut finishers: HashMap<usize, usize> = HashMap::new();
+
+        for f in 0..flight_count {
+            let flight_id = Uuid::new_v4().to_string();
+            flights.push(Flight {
+                id: flight_id.clone(),
+                flight_number: f + 1,
+                group_label: format!("Flight {}", f + 1),
+                status: FlightStatus::Scheduled,
+                stage: None,
+                officials: FlightOfficials::default(),
+            });
+
+            let available: Vec<usize> = (0..n).filter(|&t| !constraints.is_unavailable(&teams[t].id, f + 1)).collect();
+            let race_count = available.len().div_ceil(boats);
+            let sizes: Vec<usize> = (0..race_count).map(|r| (available.len() - r * boats).min(boats)).collect();
+
+            // 1. Teams into races, fewest repeat meetings first
+            let cost = |t: usize, race: &[usize], skip: usize| -> u32 {
+                race.iter().filter(|&&u| u != t && u != skip).map(|&u| meets[t][u]).sum()
+            };
+            let mut races: Vec<Vec<usize>> = vec![Vec::new(); race_count];
+            let shift = (f as usize * 7) % available.len().max(1);
+            for t in (0..available.len()).map(|i| available[(i + shift) % available.len()]) {
+                let best = (0..race_count)
+                    .filter(|&r| races[r].len() < sizes[r])
+                    .min_by_key(|&r| cost(t, &races[r], usize::MAX))
+                    .unwrap_or(0);
+                races[best].push(t);
+            }
+            for _ in 0..MAX_PASSES {
+                let mut improved = false;
+                for ra in 0..race_count {
+                    for rb in (ra + 1)..race_count {
+                        for i in 0..races[ra].len() {
+                            for j in 0..races[rb].len() {
+                                let (t, u) = (races[ra][i], races[rb][j]);
+                                let before = cost(t, &races[ra], t) + cost(u, &races[rb], u);
+                                let after = cost(t, &races[rb], u) + cost(u, &races[ra], t);
+                                if after < before {
+                                    races[ra][i] = u;
+                                    races[rb][j] = t;
+                                    improved = true;
+                                }
+                            }
+                        }
+                    }
+                }
+                if !improved {
+                    break;
+                }
+            }
+
+            // 2. Boats within each race, least sailed first
+            let mut last_race = HashMap::new();
+            for (r, race) in races.iter().enumerate() {
+                let boat_cost = |t: usize, b: usize| -> i64 {
+                    let retained = r == 0 && finishers.get(&t) == Some(&b);
+                    usage[t][b] as i64 * 2 - retained as i64
+                };
+                let mut assigned: Vec<usize> = Vec::with_capacity(race.len());
+                for &t in race {
+                    let best = (0..boats)
+                        .filter(|b| !assigned.contains(b))
+                        .min_by_key(|&b| boat_cost(t, b))
+                        .unwrap_or(0);
+                    assigned.push(best);
+                }
+                for _ in 0..MAX_PASSES {
+                    let mut improved = false;
+                    for i in 0..race.len() {
+                        for j in (i + 1)..race.len() {
+                            let (t, u) = (race[i], race[j]);
+                            let before = boat_cost(t, assigned[i]) + boat_cost(u, assigned[j]);
+                            let after = boat_cost(t, assigned[j]) + boat_cost(u, assigned[i]);
+                            if after < before {
+                                assigned.swap(i, j);
+                                improved = true;
+                            }
+                        }
+                        // A boat sitting out this race
+                        for b in 0..boats {
+                            if !assigned.contains(&b) && boat_cost(race[i], b) < boat_cost(race[i], assigned[i]) {
+                                assigned[i] = b;
+                                improved = true;
+                            }
+                        }
+                    }
+                    if !improved {
+                        break;
+                    }
+                }
+
+                for (&t, &b) in race.iter().zip(&assigned) {
+                    for &u in race.iter().filter(|&&u| u != t) {
+                        meets[t][u] += 1;
+                    }
+                    usage[t][b] += 1;
+                    if r + 1 == race_count {
+                        last_race.insert(t, b);
+                    }
+                    pairings.push(Pairing {
+                        id: Uuid::new_v4().to_string(),
+                        flight_id: flight_id.clone(),
+                        team_id: teams[t].id.clone(),
+                        boat_id: (b + 1).to_string(),
+                        race_index: r as u32,
+                    });
+                }
+            }
+            finishers = last_race;
+        }
+
+        Self::enforce_rest(&flights, &mut pairings, constraints.min_rest_races)?;
+        let report = Self::fairness_report(&teams, boat_count, &pairings);
+        Ok((flights, pairings, report))
+    }
+
+    /// Close the gaps left in a flight's race indices by teams sitting out.
+    fn compact_races(flight_id: &str, pairings: &mut [Pairing]) {
+        let used: BTreeSet<u32> = pairings.iter().filter(|p| p.flight_id == flight_id).map(|p| p.race_index).collect();
+        let index: HashMap<u32, u32> = used.into_iter().enumerate().map(|(i, r)| (r, i as u32)).collect();
+        for p in pairings.iter_mut().filter(|p| p.flight_id == flight_id) {
+            p.race_index = index[&p.race_index];
+        }
+    }
+
+    /// Reorder each flight's races, then swap teams between them, so every team
+    /// has `min_rest` races between two of its own. `flights` are in sailing order.
+    fn enforce_rest(flights: &[Flight], pairings: &mut [Pairing], min_rest: u32) -> Result<(), ScheduleError> {
+        if min_rest == 0 {
+            return Ok(());
+        }
+        let mut conflicts = Vec::new();
+        // Global position of each team's last race
+        let mut last: HashMap<String, usize> = HashMap::new();
+        let mut start = 0usize;
+        for flight in flights {
+            let members: Vec<usize> = (0..pairings.len()).filter(|&i| pairings[i].flight_id == flight.id).collect();
+            let Some(race_count) = members.iter().map(|&i| pairings[i].race_index as usize + 1).max() else { continue };
+            let rest = |team: &str, slot: usize| last.get(team).map(|&l| (start + slot - l - 1) as u32);
+            let short = |team: &str, slot: usize| rest(team, slot).is_some_and(|r| r < min_rest);
+
+            // 1. The race order with the fewest short rests, then the longest shortest rest
+            let orders: Vec<Vec<usize>> = if race_count <= MAX_PERMUTED_RACES {
+                permutations(race_count)
+            } else {
+                (0..race_count).map(|k| (0..race_count).map(|r| (r + k) % race_count).collect()).collect()
+            };
+            let score = |order: &Vec<usize>| {
+                let mut shorts = 0usize;
+                let mut least = u32::MAX;
+                for (slot, &race) in order.iter().enumerate() {
+                    for &i in members.iter().filter(|&&i| pairings[i].race_index as usize == race) {
+                        if let Some(r) = rest(&pairings[i].team_id, slot) {
+                            shorts += (r < min_rest) as usize;
+                            least = least.min(r);
+                        }
+                    }
+                }
+                (shorts, std::cmp::Reverse(least))
+            };
+            let best = orders.iter().min_by_key(|o| score(o)).cloned().unwrap_or_default();
+            let slot_of: HashMap<usize, usize> = best.iter().enumerate().map(|(slot, &race)| (race, slot)).collect();
+            for &i in &members {
+                pairings[i].race_index = slot_of[&(pairings[i].race_index as usize)] as u32;
+            }
+
+            // 2. Swap a team still short into a later race, with a team that can go earlier
+            for &i in &members {
+                let slot = pairings[i].race_index as usize;
+                if !short(&pairings[i].team_id, slot) {
+                    continue;
+                }
+                let swap = members.iter().copied().find(|&j| {
+                    let other = pairings[j].race_index as usize;
+                    other > slot && !short(&pairings[i].team_id, other) && !short(&pairings[j].team_id, slot)
+                });
+                if let Some(j) = swap {
+                    let team = std::mem::take(&mut pairings[i].team_id);
+                    pairings[i].team_id = std::mem::replace(&mut pairings[j].team_id, team);
+                }
+            }
+
+            for &i in &members {
+                let slot = pairings[i].race_index as usize;
+                if let Some(r) = rest(&pairings[i].team_id, slot).filter(|r| *r < min_rest) {
+                    conflicts.push(ScheduleConflict {
+                        team_id: pairings[i].team_id.clone(),
+                        flight_number: flight.flight_number,
+                        rest_races: r,
+                        required: min_rest,
+                    });
+                }
+            }
+            for &i in &members {
+                last.insert(pairings[i].team_id.clone(), start + pairings[i].race_index as usize);
+            }
+            start += race_count;
+        }
+        if conflicts.is_empty() { Ok(()) } else { Err(ScheduleError::Infeasible(conflicts)) }
+    }
+
+    /// Opponent and boat counts of a schedule, from either generator or edited by hand.
+    pub fn fairness_report(teams: &[Team], boat_count: u32, pairings: &[Pairing]) -> FairnessReport {
+        let mut races: HashMap<(&str, u32), Vec<&str>> = HashMap::new();
+        let mut boats: HashMap<&str, BTreeMap<String, u32>> = teams.iter()
+            .map(|t| (t.id.as_str(), (1..=boat_count).map(|b| (b.to_string(), 0)).collect()))
+            .collect();
+        for p in pairings {
+            races.entry((p.flight_id.as_str(), p.race_index)).or_default().push(p.team_id.as_str());
+            if let Some(counts) = boats.get_mut(p.team_id.as_str()) {
+                *counts.entry(p.boat_id.clone()).or_insert(0) += 1;
+            }
+        }
+
+        let mut meetings: HashMap<(&str, &str), u32> = HashMap::new();
+        for race in races.values() {
+            for (i, a) in race.iter().enumerate() {
+                for b in &race[i + 1..] {
+                    let key = if a < b { (*a, *b) } else { (*b, *a) };
+                    *meetings.entry(key).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut pairs = Vec::new();
+        for (i, a) in teams.iter().enumerate() {
+            for b in &teams[i + 1..] {
+                let key = if a.id < b.id { (a.id.as_str(), b.id.as_str()) } else { (b.id.as_str(), a.id.as_str()) };
+                pairs.push(PairMeetings {
+                    team_a: a.id.clone(),
+                    team_b: b.id.clone(),
+                    meetings: meetings.get(&key).copied().unwrap_or(0),
+                });
+            }
+        }
+
+        let teams: Vec<TeamBoats> = teams.iter()
+            .map(|t| TeamBoats { team_id: t.id.clone(), boats: boats.remove(t.id.as_str()).unwrap_or_default() })
+            .collect();
+        let flights = pairings.iter().map(|p| p.flight_id.as_str()).collect::<HashSet<_>>().len() as u32;
+        FairnessReport {
+            flights,
+            pair_meetings: Spread::of(pairs.iter().map(|p| p.meetings)),
+            unmet_pairs: pairs.iter().filter(|p| p.meetings == 0).count(),
+            boat_usage: Spread::of(teams.iter().flat_map(|t| t.boats.values().copied())),
+            pairs,
+            teams,
+        }
+    }
+}
+
+/// Every order of `n` races (Heap's algorithm).
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    fn heap(k: usize, order: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+        if k <= 1 {
+            out.push(order.clone());
+            return;
+        }
+        heap(k - 1, order, out);
+        for i in 0..k - 1 {
+            order.swap(if k.is_multiple_of(2) { i } else { 0 }, k - 1);
+            heap(k - 1, order, out);
+        }
     }
+    let mut out = Vec::new();
+    heap(n, &mut (0..n).collect(), &mut out);
+    out
 }
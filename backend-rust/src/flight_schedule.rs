@@ -0,0 +1,211 @@
+//! # flight_schedule
+//!
+//! Targeted edits to a league schedule, next to the wholesale `update-pairings`
+//! and `generate-flights`:
+//!
+//! - `delete-flight { flightId }` — the flight and its pairings; later flights
+//!   move up one number
+//! - `swap-boats { flightId, teamA, teamB }` — the two teams trade boats in that flight
+//! - `set-flight-status { flightId, status }` — `SCHEDULED`, `IN_PROGRESS`,
+//!   `COMPLETED` or `ABANDONED`
+//! - `insert-resail { flightId }` — a copy of the flight's pairings as a new
+//!   flight right after it; later flights move down one number
+//! - `create-resail { flightId, flightNumber? }` — the same copy as flight
+//!   `flightNumber` (default right after the original), with the original marked
+//!   `ABANDONED`; for breakdowns and general recalls
+//! - `substitute-boat { boatId, substituteBoatId, flightId?, reason? }` — a boat
+//!   that broke down is replaced by the substitute in every flight still
+//!   `SCHEDULED` or `IN_PROGRESS`; the team sailing it in `flightId` (where it
+//!   broke down) is flagged for redress (see `scoring::flag_redress`)
+//! - `decide-redress { redressId, status }` — `GRANTED` or `DENIED`
+//!
+//! Each edit returns a `FlightDelta` with only what changed, broadcast as
+//! `flight-delta` instead of a full `state-update`, so the published pairings
+//! change at once.
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::scoring;
+use crate::state::{Flight, FlightStatus, Pairing, RaceState, Redress, RedressStatus};
+
+#[derive(Debug, thiserror::Error)]
+pub enum FlightEditError {
+    #[error("Unknown flight: {0}")]
+    UnknownFlight(String),
+    #[error("Team {0} has no pairing in this flight")]
+    UnpairedTeam(String),
+    #[error("Flight number {0} is outside the schedule (1 to {1})")]
+    InvalidPosition(u32, u32),
+    #[error("A boat cannot substitute for itself")]
+    SameBoat,
+    #[error("Boat {0} has no pairings left to substitute")]
+    UnusedBoat(String),
+    #[error("Boat {0} already sails in flight {1}, race {2}")]
+    BoatInUse(String, u32, u32),
+    #[error("Unknown redress: {0}")]
+    UnknownRedress(String),
+}
+
+/// What an edit changed: upserted flights and pairings, removed ids.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightDelta {
+    pub flights: Vec<Flight>,
+    pub removed_flight_ids: Vec<String>,
+    pub pairings: Vec<Pairing>,
+    pub removed_pairing_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub redress: Vec<Redress>,
+}
+
+fn flight(state: &RaceState, flight_id: &str) -> Result<Flight, FlightEditError> {
+    state.flights.get(flight_id).cloned().ok_or_else(|| FlightEditError::UnknownFlight(flight_id.to_string()))
+}
+
+/// Shift every flight numbered above `after` by `by`, recording them in `delta`.
+fn renumber(state: &mut RaceState, after: u32, by: i32, delta: &mut FlightDelta) {
+    for f in state.flights.values_mut().filter(|f| f.flight_number > after) {
+        f.flight_number = (f.flight_number as i32 + by).max(1) as u32;
+        f.group_label = format!("Flight {}", f.flight_number);
+        delta.flights.push(f.clone());
+    }
+}
+
+pub fn delete_flight(state: &mut RaceState, flight_id: &str) -> Result<FlightDelta, FlightEditError> {
+    let removed = flight(state, flight_id)?;
+    state.flights.remove(flight_id);
+    let mut delta = FlightDelta { removed_flight_ids: vec![flight_id.to_string()], ..Default::default() };
+    delta.removed_pairing_ids = state.pairings.iter()
+        .filter(|p| p.flight_id == flight_id)
+        .map(|p| p.id.clone())
+        .collect();
+    state.pairings.retain(|p| p.flight_id != flight_id);
+    if state.active_flight_id.as_deref() == Some(flight_id) {
+        state.active_flight_id = None;
+    }
+    renumber(state, removed.flight_number, -1, &mut delta);
+    Ok(delta)
+}
+
+pub fn swap_boats(state: &mut RaceState, flight_id: &str, team_a: &str, team_b: &str) -> Result<FlightDelta, FlightEditError> {
+    flight(state, flight_id)?;
+    let index = |team: &str| state.pairings.iter()
+        .position(|p| p.flight_id == flight_id && p.team_id == team)
+        .ok_or_else(|| FlightEditError::UnpairedTeam(team.to_string()));
+    let (a, b) = (index(team_a)?, index(team_b)?);
+    let boat_a = std::mem::take(&mut state.pairings[a].boat_id);
+    state.pairings[a].boat_id = std::mem::replace(&mut state.pairings[b].boat_id, boat_a);
+    Ok(FlightDelta { pairings: vec![state.pairings[a].clone(), state.pairings[b].clone()], ..Default::default() })
+}
+
+pub fn set_status(state: &mut RaceState, flight_id: &str, status: FlightStatus) -> Result<FlightDelta, FlightEditError> {
+    let f = state.flights.get_mut(flight_id).ok_or_else(|| FlightEditError::UnknownFlight(flight_id.to_string()))?;
+    f.status = status;
+    Ok(FlightDelta { flights: vec![f.clone()], ..Default::default() })
+}
+
+/// Copy the pairings of `original` into a new flight numbered `number`, making
+/// room by moving that number and the ones above it down.
+fn resail(state: &mut RaceState, original: &Flight, number: u32, delta: &mut FlightDelta) {
+    renumber(state, number - 1, 1, delta);
+
+    let resail = Flight {
+        id: Uuid::new_v4().to_string(),
+        flight_number: number,
+        group_label: original.stage.map_or_else(|| format!("Flight {number}"), |stage| stage.label().to_string()),
+        status: FlightStatus::Scheduled,
+        stage: original.stage,
+        officials: original.officials.clone(),
+    };
+    let pairings: Vec<Pairing> = state.pairings.iter()
+        .filter(|p| p.flight_id == original.id)
+        .map(|p| Pairing { id: Uuid::new_v4().to_string(), flight_id: resail.id.clone(), ..p.clone() })
+        .collect();
+    state.pairings.extend(pairings.iter().cloned());
+    state.flights.insert(resail.id.clone(), resail.clone());
+    delta.flights.push(resail);
+    delta.pairings = pairings;
+}
+
+pub fn insert_resail(state: &mut RaceState, flight_id: &str) -> Result<FlightDelta, FlightEditError> {
+    let original = flight(state, flight_id)?;
+    let mut delta = FlightDelta::default();
+    resail(state, &original, original.flight_number + 1, &mut delta);
+    Ok(delta)
+}
+
+pub fn create_resail(state: &mut RaceState, flight_id: &str, number: Option<u32>) -> Result<FlightDelta, FlightEditError> {
+    let original = flight(state, flight_id)?;
+    let last = state.flights.values().map(|f| f.flight_number).max().unwrap_or(0);
+    let number = number.unwrap_or(original.flight_number + 1);
+    if number == 0 || number > last + 1 {
+        return Err(FlightEditError::InvalidPosition(number, last + 1));
+    }
+    let mut delta = FlightDelta::default();
+    resail(state, &original, number, &mut delta);
+
+    // The original may have moved down too; report it once, abandoned
+    if let Some(f) = state.flights.get_mut(flight_id) {
+        f.status = FlightStatus::Abandoned;
+        delta.flights.retain(|d| d.id != f.id);
+        delta.flights.push(f.clone());
+    }
+    Ok(delta)
+}
+
+pub fn substitute_boat(
+    state: &mut RaceState,
+    boat_id: &str,
+    substitute: &str,
+    breakdown_flight: Option<&str>,
+    reason: &str,
+    now_ms: i64,
+) -> Result<FlightDelta, FlightEditError> {
+    if boat_id == substitute {
+        return Err(FlightEditError::SameBoat);
+    }
+    if let Some(flight_id) = breakdown_flight {
+        flight(state, flight_id)?;
+    }
+    let remaining = |state: &RaceState, p: &Pairing| state.flights.get(&p.flight_id)
+        .is_some_and(|f| matches!(f.status, FlightStatus::Scheduled | FlightStatus::InProgress));
+    let affected: Vec<usize> = state.pairings.iter()
+        .enumerate()
+        .filter(|(_, p)| p.boat_id == boat_id && remaining(state, p))
+        .map(|(i, _)| i)
+        .collect();
+    if affected.is_empty() {
+        return Err(FlightEditError::UnusedBoat(boat_id.to_string()));
+    }
+    // The substitute cannot sail two teams in one race
+    for &i in &affected {
+        let p = &state.pairings[i];
+        if state.pairings.iter().any(|q| q.flight_id == p.flight_id && q.race_index == p.race_index && q.boat_id == substitute) {
+            let number = state.flights.get(&p.flight_id).map_or(0, |f| f.flight_number);
+            return Err(FlightEditError::BoatInUse(substitute.to_string(), number, p.race_index + 1));
+        }
+    }
+
+    let mut delta = FlightDelta::default();
+    if let Some(flight_id) = breakdown_flight {
+        let spoiled: Vec<(String, u32)> = state.pairings.iter()
+            .filter(|p| p.flight_id == flight_id && p.boat_id == boat_id)
+            .map(|p| (p.team_id.clone(), p.race_index))
+            .collect();
+        for (team_id, race_index) in spoiled {
+            delta.redress.push(scoring::flag_redress(state, &team_id, flight_id, race_index, boat_id, reason, now_ms));
+        }
+    }
+    for i in affected {
+        state.pairings[i].boat_id = substitute.to_string();
+        delta.pairings.push(state.pairings[i].clone());
+    }
+    Ok(delta)
+}
+
+pub fn decide_redress(state: &mut RaceState, redress_id: &str, status: RedressStatus) -> Result<FlightDelta, FlightEditError> {
+    let redress = scoring::decide_redress(state, redress_id, status)
+        .ok_or_else(|| FlightEditError::UnknownRedress(redress_id.to_string()))?;
+    Ok(FlightDelta { redress: vec![redress], ..Default::default() })
+}
@@ -0,0 +1,51 @@
+//! # handicap
+//!
+//! Corrected times for handicap racing, from the entries' ratings and the elapsed
+//! times of a closed race.
+//!
+//! - `SCRATCH` — one-design, corrected = elapsed
+//! - `TIME_ON_TIME` — rating is a time correction factor (ORC ToT, IRC TCC)
+//! - `PHRF_TIME_ON_TIME` — corrected = elapsed × a / (b + rating), default 650 / 550
+//! - `TIME_ON_DISTANCE` — rating in s/nm (PHRF, ORC ToD) times the course distance
+//!   is taken off the elapsed time
+//!
+//! A boat without a rating sails at scratch in a handicap race. `scoring` places
+//! the finishers of a handicap race in corrected-time order.
+
+use crate::state::{CorrectedTime, Entry, Handicap, RaceResult};
+
+fn rating(entries: &[Entry], boat_id: &str) -> Option<f64> {
+    entries.iter()
+        .find(|e| e.boat_id == boat_id || e.sail_number.eq_ignore_ascii_case(boat_id))
+        .and_then(|e| e.rating)
+}
+
+fn corrected_ms(handicap: &Handicap, elapsed_ms: i64, rating: Option<f64>) -> i64 {
+    let elapsed = elapsed_ms as f64;
+    let corrected = match (handicap, rating) {
+        (Handicap::Scratch, _) | (_, None) => elapsed,
+        (Handicap::TimeOnTime, Some(tcf)) => elapsed * tcf,
+        (Handicap::PhrfTimeOnTime { a, b }, Some(r)) if b + r > 0.0 => elapsed * a / (b + r),
+        (Handicap::PhrfTimeOnTime { .. }, Some(_)) => elapsed,
+        (Handicap::TimeOnDistance { distance_nm }, Some(r)) => elapsed - r * distance_nm * 1000.0,
+    };
+    corrected.round() as i64
+}
+
+/// Elapsed and corrected time of every finisher, fastest corrected first.
+pub fn correct(race: &RaceResult, entries: &[Entry]) -> Vec<CorrectedTime> {
+    let Some(start_ms) = race.start_ms else { return Vec::new() };
+    let mut times: Vec<CorrectedTime> = race.finishes.iter().map(|f| {
+        let elapsed_ms = f.finish_ms - start_ms;
+        let rating = rating(entries, &f.boat_id);
+        CorrectedTime {
+            boat_id: f.boat_id.clone(),
+            elapsed_ms,
+            corrected_ms: corrected_ms(&race.handicap, elapsed_ms, rating),
+            rating,
+        }
+    }).collect();
+    // Ties on corrected time keep finishing order
+    times.sort_by_key(|t| t.corrected_ms);
+    times
+}
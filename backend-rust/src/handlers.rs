@@ -1,33 +1,132 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
 
+use axum::extract::ConnectInfo;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use socketioxide::extract::{Data, SocketRef};
+use socketioxide::SocketIo;
 use tokio::sync::RwLock;
 use tracing::{info, warn, error};
 
+use crate::audit::{AuditLogger, AuthEventKind};
+use crate::auto_director;
+use crate::auth::ClientIdentity;
+use crate::blacklist;
+use crate::boat_classes;
+use crate::boat_liveness;
+use crate::course_templates::{self, CourseSpec};
+use crate::control_plane::ControlDownlink;
+use crate::crew;
+use crate::entries;
+use crate::flight_schedule;
+use crate::laylines;
+use crate::knockout;
+use crate::league;
+use crate::line_bias;
+use crate::log_store::{self, LogQuery};
+use crate::login_guard;
+use crate::mark_rounding;
+use crate::messaging;
+use crate::ocs_recall;
+use crate::officials;
+use crate::penalties;
 use crate::persistence::save_state;
 use crate::procedure_engine::ProcedureEngine;
+use crate::procedure_templates;
+use crate::procedure_validator;
+use crate::protests;
+use crate::pursuit;
+use crate::race_session;
+use crate::regatta;
+use crate::roles;
+use crate::sessions::Session;
+use crate::boat_scope::{self, FULL_STATE_EXCEPT, SCOPED_ROOM};
+use crate::state_delta::{SharedDelta, DELTA_ROOM};
+use crate::tide;
+use crate::track_store::{self, TrackQuery};
+use crate::time_sync::{self, ClientOffset};
+use crate::weather;
+use crate::rate_limit::{RateLimitConfig, TrackLimiter, Verdict};
+use crate::rehearsal::{self, RehearsalUpdate, Rehearsals};
+use crate::rule_packs::{self, RulePack, SoundConvention};
+use crate::scoring;
+use crate::snapshots::{self, SnapshotConfig};
+use crate::protest_replay::{ProtestReplayEngine, ReplayQuery};
 use crate::state::{
-    BoatState, CourseState, DefaultLocation, ImuData, LatLon, LogCategory, LogEntry,
-    Penalty, PenaltyType, PrepFlag, ProcedureGraph, RaceState, RaceStatus,
-    SequenceInfo, SoundSignal, VelocityData, WindState,
+    BlacklistKind, BoatClass, BoatState, BoatStatus, ClassSequenceUpdate, CourseState, CurrentSource, CurrentState,
+    DefaultLocation, Entry, FlightStatus, Handicap, KnockoutRound,
+    Hearing, ImuData, LatLon, LogCategory, LogEntry, MessageTarget, Penalty, PenaltyType, PrepFlag,
+    ProcedureGraph, ProcedureTemplate, ProtestDecision, ProtestStatus, PursuitStart, RaceState, RaceStatus, RedressStatus,
+    ScoringSettings, SequenceInfo, SoundSignal, VelocityData, WeatherProvider, WeatherReport, WindState,
 };
+use uwb_types::{ControlCommand, NodeDesignation};
 
 // ─── Shared State Types ───────────────────────────────────────────────────────
 
 pub type SharedState = Arc<RwLock<RaceState>>;
 pub type SharedEngine = Arc<RwLock<ProcedureEngine>>;
-pub type DeadBoats = Arc<RwLock<HashSet<String>>>;
+/// Concurrent start sequences keyed by class/fleet id, ticking alongside the main engine
+pub type ClassEngines = Arc<RwLock<HashMap<String, ProcedureEngine>>>;
 
 // ─── Helper: get unix ms ─────────────────────────────────────────────────────
 
 pub fn now_ms() -> i64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as i64
+    crate::time_discipline::now_ms() as i64
+}
+
+// ─── Helper: command outlet ──────────────────────────────────────────────────
+
+/// Where a command's updates go. A socket command echoes to its sender and
+/// broadcasts to everyone else; a REST command emits to the whole namespace.
+#[derive(Clone)]
+pub enum Outlet {
+    Socket(SocketRef),
+    Io(SocketIo),
+}
+
+impl Outlet {
+    pub fn emit<T: ?Sized + serde::Serialize>(&self, event: &str, data: &T) {
+        match self {
+            Outlet::Socket(s) => {
+                let _ = s.broadcast().emit(event, data);
+                let _ = s.emit(event, data);
+            }
+            Outlet::Io(io) => {
+                let _ = io.emit(event, data);
+            }
+        }
+    }
+
+    /// Like `emit`, but never to trackers (their view leaves it out).
+    pub fn emit_unscoped<T: ?Sized + serde::Serialize>(&self, event: &str, data: &T) {
+        match self {
+            Outlet::Socket(s) => {
+                let _ = s.broadcast().except(SCOPED_ROOM).emit(event, data);
+                if !boat_scope::is_scoped(s) {
+                    let _ = s.emit(event, data);
+                }
+            }
+            Outlet::Io(io) => {
+                let _ = io.except(SCOPED_ROOM).emit(event, data);
+            }
+        }
+    }
+
+    /// Full `state-update`; delta subscribers get it as a patch instead.
+    pub fn emit_state(&self, state: &RaceState) {
+        match self {
+            Outlet::Socket(s) => {
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", state);
+                boat_scope::emit_to_sender(s, state);
+            }
+            Outlet::Io(io) => {
+                let _ = io.except(FULL_STATE_EXCEPT).emit("state-update", state);
+            }
+        }
+    }
 }
 
 pub async fn emit_log(
@@ -38,9 +137,21 @@ pub async fn emit_log(
     message: String,
     data: Option<Value>,
     is_active: bool,
+) {
+    log_to(shared, &Outlet::Socket(socket.clone()), category, source, message, data, is_active).await;
+}
+
+pub async fn log_to(
+    shared: &SharedState,
+    out: &Outlet,
+    category: LogCategory,
+    source: String,
+    message: String,
+    data: Option<Value>,
+    is_active: bool,
 ) {
     let log = LogEntry {
-        id: format!("log-{}", now_ms()),
+        id: log_store::next_id(),
         timestamp: now_ms(),
         category,
         source,
@@ -51,17 +162,477 @@ pub async fn emit_log(
         jury_notes: None,
     };
 
+    log_store::push(&mut *shared.write().await, log.clone());
+    log_store::append(&log).await;
+
+    out.emit_unscoped("new-log", &log);
+}
+
+// ─── Helper: audit trail for state-changing commands ────────────────────────
+
+/// Append a `DIRECTOR_COMMAND` block for a state-changing socket event (Invariant #2).
+pub async fn audit_command(
+    audit: &AuditLogger,
+    auth: &crate::auth::AuthEngine,
+    socket: &SocketRef,
+    command: &str,
+    data: &Value,
+) {
+    let socket_id = socket.id.to_string();
+    let role = auth.get_role(&socket_id).await;
+    audit.log_director_command(command, &socket_id, role.as_deref(), data).await;
+}
+
+/// Check the permission matrix for a guarded command; a refusal is warned and
+/// appended to the audit chain as a `PERMISSION_DENIED` auth event.
+pub async fn authorize_command(
+    audit: &AuditLogger,
+    auth: &crate::auth::AuthEngine,
+    socket: &SocketRef,
+    command: &str,
+) -> bool {
+    let socket_id = socket.id.to_string();
+    let role = auth.get_role(&socket_id).await;
+    if auth.authorize(command, role.as_deref()).await {
+        return true;
+    }
+    warn!("Unauthorized {command} attempt by: {socket_id} (role {})", role.as_deref().unwrap_or("none"));
+    audit.log_auth_event(AuthEventKind::PermissionDenied, &auth.identity(&socket_id).await, json!({ "command": command })).await;
+    false
+}
+
+/// Identity of a registering client: token subject, device and the handshake's
+/// peer address (see `login_guard::client_address` for proxies).
+fn client_identity(s: &SocketRef, data: &Value, sub: Option<String>) -> ClientIdentity {
+    let parts = s.req_parts();
+    let peer = parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip());
+    ClientIdentity {
+        socket_id: s.id.to_string(),
+        role: None,
+        sub,
+        device_id: data["deviceId"].as_str().map(str::to_string),
+        boat_id: data["boatId"].as_str().map(str::to_string),
+        address: login_guard::client_address(peer, &parts.headers),
+        user_agent: parts.headers.get("user-agent").and_then(|v| v.to_str().ok()).map(str::to_string),
+    }
+}
+
+/// Append a `RACE_STATUS_CHANGE` block if the status actually changed.
+pub async fn audit_status_change(audit: &AuditLogger, from: &RaceStatus, to: &RaceStatus, reason: &str) {
+    if from == to {
+        return;
+    }
+    let name = |st: &RaceStatus| serde_json::to_value(st).ok()
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_default();
+    audit.log_race_status_change(&name(from), &name(to), Some(reason)).await;
+}
+
+// ─── Commands shared by the socket handlers and the REST API ─────────────────
+
+/// `start-sequence`: deploy a rule-pack preset, the loaded graph or the standard procedure and start it.
+pub async fn start_sequence(shared: &SharedState, engine: &SharedEngine, audit: &AuditLogger, out: &Outlet, data: &Value) {
+    let status_before = shared.read().await.status.clone();
+    
+    let preset = data["preset"].as_str().and_then(RulePack::parse);
+    let prep_flag_str = preset.and_then(RulePack::prep_flag)
+        .unwrap_or_else(|| data["prepFlag"].as_str().unwrap_or("P"));
+
+    let mut eng = engine.write().await;
+    
+    // An explicit rule-pack preset replaces the deployed graph; otherwise keep
+    // the deployed graph if present, or load standard
+    let graph = if let Some(pack) = preset {
+        let sounds = data["sounds"].as_str().map(SoundConvention::parse).unwrap_or_default();
+        let g = rule_packs::procedure(pack, data["minutes"].as_u64().unwrap_or(5), prep_flag_str, sounds);
+        eng.load_procedure(g.clone());
+        g
+    } else if let Some(g) = &eng.graph {
+        g.clone()
+    } else {
+        let minutes = data["minutes"].as_u64().unwrap_or(5);
+        let g = standard_procedure(minutes, prep_flag_str);
+        eng.load_procedure(g.clone());
+        g
+    };
+
+    let update = eng.start();
+    let status = eng.current_race_status();
+    drop(eng);
+    audit_status_change(audit, &status_before, &status, "start-sequence").await;
+
+    {
+        let mut state = shared.write().await;
+        state.status = status;
+        state.current_procedure = Some(graph);
+        state.ocs_boats.clear();
+        state.ocs_detections.clear();
+        state.start_time = None;
+        if let Some(p) = state.pursuit.as_mut() {
+            pursuit::reset(p);
+        }
+        mark_rounding::reset(&mut state);
+        state.prep_flag = match prep_flag_str {
+            "I" => PrepFlag::I,
+            "Z" => PrepFlag::Z,
+            "U" => PrepFlag::U,
+            "BLACK" => PrepFlag::Black,
+            _ => PrepFlag::P,
+        };
+        if let Some(upd) = &update {
+            state.current_sequence = Some(upd.current_sequence.clone());
+            state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+        }
+    }
+
+    if let Some(upd) = update {
+        out.emit("sequence-update", &upd);
+    }
+
+    let state = shared.read().await;
+    out.emit_state(&state);
+
+    log_to(shared, out, LogCategory::Procedure, "Director".to_string(), "Started sequence".to_string(), None, false).await;
+}
+
+/// `procedure-action`: postpone, recall, abandon, shorten, course change or reset.
+pub async fn procedure_action(shared: &SharedState, engine: &SharedEngine, audit: &AuditLogger, out: &Outlet, data: &Value) {
+    
+    let action = data["action"].as_str().unwrap_or("");
+    info!("Procedure action: {action}");
+    let status_before = shared.read().await.status.clone();
+
+    match action {
+        // ── POSTPONE (AP flag + 2 sounds) ─────────────────────
+        "POSTPONE" => {
+            engine.write().await.stop();
+            {
+                let mut state = shared.write().await;
+                state.status = RaceStatus::Postponed;
+                state.current_sequence = Some(SequenceInfo {
+                    event: "Postponed".to_string(),
+                    flags: vec!["AP".to_string()],
+                });
+                state.sequence_time_remaining = None;
+                state.waiting_for_trigger = false;
+                state.action_label = None;
+
+                out.emit_state(&state);
+            }
+
+            log_to(shared, out, LogCategory::Procedure, "Director".to_string(),
+                "Race postponed — AP flag raised, 2 sounds".to_string(),
+                Some(json!({ "signal": "AP", "sounds": 2 })), false).await;
+
+            // Auto-resume: spawn a task that waits 60s then starts new Warning
+            let shared_r = shared.clone();
+            let engine_r = engine.clone();
+            let audit_r = audit.clone();
+            let out_r = out.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                
+                // Only resume if still postponed (RC may have manually changed)
+                let is_still_postponed = shared_r.read().await.status == RaceStatus::Postponed;
+                if !is_still_postponed { return; }
+
+                info!("AP lowered — resuming with new Warning in 1 min");
+                
+                // Restart the engine
+                let mut eng = engine_r.write().await;
+                let update = eng.start();
+                let status = eng.current_race_status();
+                drop(eng);
+                audit_status_change(&audit_r, &RaceStatus::Postponed, &status, "AP lowered").await;
+
+                {
+                    let mut state = shared_r.write().await;
+                    state.status = status;
+                    if let Some(upd) = &update {
+                        state.current_sequence = Some(upd.current_sequence.clone());
+                        state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+                    }
+                }
+
+                if let Some(upd) = update {
+                    out_r.emit("sequence-update", &upd);
+                }
+
+                let state = shared_r.read().await;
+                out_r.emit_state(&state);
+
+                log_to(&shared_r, &out_r, LogCategory::Procedure, "Director".to_string(),
+                    "AP lowered — new Warning signal, 1 sound".to_string(),
+                    Some(json!({ "signal": "AP_DOWN", "sounds": 1 })), false).await;
+            });
+        }
+
+        // ── INDIVIDUAL RECALL (X flag + 1 sound) ──────────────
+        "INDIVIDUAL_RECALL" => {
+            // Don't stop the engine — racing continues
+            let sent: Option<Vec<String>> = data["boats"].as_array()
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect());
+
+            let (ocs_boats, ocs_detail) = {
+                let mut state = shared.write().await;
+                // Without a list from the director, the UWB gun-solve detections are the OCS list
+                let ocs_boats = sent.unwrap_or_else(|| ocs_recall::detected_boats(&state));
+                let ocs_detail = ocs_recall::detail(&state, &ocs_boats);
+                state.status = RaceStatus::IndividualRecall;
+                state.ocs_boats = ocs_boats.clone();
+                state.current_sequence = Some(SequenceInfo {
+                    event: "Individual Recall".to_string(),
+                    flags: vec!["X".to_string()],
+                });
+
+                out.emit_state(&state);
+                (ocs_boats, ocs_detail)
+            };
+
+            log_to(shared, out, LogCategory::Procedure, "Director".to_string(),
+                format!("Individual Recall — X flag raised, OCS: {}", if ocs_boats.is_empty() { "none identified".to_string() } else { ocs_boats.join(", ") }),
+                Some(json!({ "signal": "X", "sounds": 1, "ocsBoats": ocs_boats, "ocs": ocs_detail })), false).await;
+
+            // Auto-clear X flag after 5 minutes (DNS default)
+            let shared_r = shared.clone();
+            let audit_r = audit.clone();
+            let out_r = out.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(300)).await; // 5 min
+                
+                let is_still_recall = shared_r.read().await.status == RaceStatus::IndividualRecall;
+                if !is_still_recall { return; }
+
+                info!("X flag auto-lowered after 5 minutes");
+                {
+                    let mut state = shared_r.write().await;
+                    state.status = RaceStatus::Racing;
+                    state.current_sequence = Some(SequenceInfo {
+                        event: "Racing".to_string(),
+                        flags: vec![],
+                    });
+
+                    // Issue DNS to OCS boats
+                    let ocs_list = state.ocs_boats.clone();
+                    for boat_id in &ocs_list {
+                        state.penalties.push(Penalty::new(boat_id.clone(), PenaltyType::Dns, now_ms()));
+                    }
+                    state.ocs_boats.clear();
+
+                    out_r.emit_state(&state);
+                }
+
+                audit_status_change(&audit_r, &RaceStatus::IndividualRecall, &RaceStatus::Racing, "X flag lowered").await;
+                log_to(&shared_r, &out_r, LogCategory::Procedure, "Director".to_string(),
+                    "X flag lowered — DNS applied to OCS boats".to_string(), None, false).await;
+            });
+        }
+
+        // ── GENERAL RECALL (1st Substitute + 2 sounds) ────────
+        "GENERAL_RECALL" => {
+            engine.write().await.stop();
+            {
+                let mut state = shared.write().await;
+                state.status = RaceStatus::GeneralRecall;
+                state.current_sequence = Some(SequenceInfo {
+                    event: "General Recall".to_string(),
+                    flags: vec!["FIRST_SUB".to_string()],
+                });
+                state.sequence_time_remaining = None;
+                state.waiting_for_trigger = false;
+                state.action_label = None;
+
+                out.emit_state(&state);
+            }
+
+            log_to(shared, out, LogCategory::Procedure, "Director".to_string(),
+                "General Recall — 1st Substitute raised, 2 sounds".to_string(),
+                Some(json!({ "signal": "FIRST_SUB", "sounds": 2 })), false).await;
+
+            // Auto: 1st Sub down + 1 sound, new Warning 1 min later
+            let shared_r = shared.clone();
+            let engine_r = engine.clone();
+            let audit_r = audit.clone();
+            let out_r = out.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+
+                let is_still_recall = shared_r.read().await.status == RaceStatus::GeneralRecall;
+                if !is_still_recall { return; }
+
+                info!("1st Substitute lowered — new Warning sequence starting");
+
+                let mut eng = engine_r.write().await;
+                let update = eng.start();
+                let status = eng.current_race_status();
+                drop(eng);
+                audit_status_change(&audit_r, &RaceStatus::GeneralRecall, &status, "1st Substitute lowered").await;
+
+                {
+                    let mut state = shared_r.write().await;
+                    state.status = status;
+                    if let Some(upd) = &update {
+                        state.current_sequence = Some(upd.current_sequence.clone());
+                        state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+                    }
+                }
+
+                if let Some(upd) = update {
+                    out_r.emit("sequence-update", &upd);
+                }
+
+                let state = shared_r.read().await;
+                out_r.emit_state(&state);
+
+                log_to(&shared_r, &out_r, LogCategory::Procedure, "Director".to_string(),
+                    "1st Substitute lowered — new Warning signal, 1 sound".to_string(),
+                    Some(json!({ "signal": "FIRST_SUB_DOWN", "sounds": 1 })), false).await;
+            });
+        }
+
+        // ── ABANDON (N flag + 3 sounds) ───────────────────────
+        "ABANDON" => {
+            engine.write().await.stop();
+            {
+                let mut state = shared.write().await;
+                state.status = RaceStatus::Abandoned;
+                state.current_sequence = Some(SequenceInfo {
+                    event: "Abandoned".to_string(),
+                    flags: vec!["N".to_string()],
+                });
+                state.sequence_time_remaining = None;
+                state.waiting_for_trigger = false;
+                state.action_label = None;
+                state.ocs_boats.clear();
+                state.ocs_detections.clear();
+
+                out.emit_state(&state);
+            }
+
+            log_to(shared, out, LogCategory::Procedure, "Director".to_string(),
+                "Race abandoned — N flag raised, 3 sounds".to_string(),
+                Some(json!({ "signal": "N", "sounds": 3 })), false).await;
+        }
+
+        // ── SHORTEN COURSE (S flag + 2 sounds) ────────────────
+        "SHORTEN_COURSE" => {
+            log_to(shared, out, LogCategory::Procedure, "Director".to_string(),
+                "Shorten Course — S flag raised, 2 sounds".to_string(),
+                Some(json!({ "signal": "S", "sounds": 2 })), false).await;
+        }
+
+        // ── COURSE CHANGE (C flag + repetitive sounds) ────────
+        "COURSE_CHANGE" => {
+            log_to(shared, out, LogCategory::Procedure, "Director".to_string(),
+                "Course Change — C flag raised, repetitive sounds".to_string(),
+                Some(json!({ "signal": "C", "sounds": "repetitive" })), false).await;
+        }
+
+        // ── RESET TO IDLE ──────────────────────────────────────
+        "RESET" => {
+            engine.write().await.stop();
+            {
+                let mut state = shared.write().await;
+                state.status = RaceStatus::Idle;
+                state.current_sequence = None;
+                state.sequence_time_remaining = None;
+                state.start_time = None;
+                state.waiting_for_trigger = false;
+                state.action_label = None;
+                state.is_post_trigger = false;
+                state.ocs_boats.clear();
+                state.ocs_detections.clear();
+                mark_rounding::reset(&mut state);
+
+                out.emit_state(&state);
+            }
+
+            log_to(shared, out, LogCategory::Procedure, "Director".to_string(),
+                "Race reset to Idle".to_string(), None, false).await;
+        }
+
+        _ => {
+            warn!("Unknown procedure action: {action}");
+        }
+    }
+
+    let status_after = shared.read().await.status.clone();
+    audit_status_change(audit, &status_before, &status_after, action).await;
+}
+
+/// `issue-penalty`: record a penalty and announce the matching umpire signal.
+/// Lower AP or the 1st Substitute for one class: its held engine starts a new
+/// Warning sequence. Nothing happens once the class has left `held`.
+async fn resume_class(shared: &SharedState, class_engines: &ClassEngines, audit: &AuditLogger, out: &Outlet, class_id: &str, held: RaceStatus, reason: &str) {
+    let current = shared.read().await.class_sequences.get(class_id).map(|c| c.status.clone());
+    if current != Some(held.clone()) {
+        return;
+    }
+    let (update, status) = {
+        let mut engines = class_engines.write().await;
+        let Some(eng) = engines.get_mut(class_id) else {
+            warn!("Class {class_id}: no procedure engine to resume");
+            return;
+        };
+        let update = eng.start();
+        (update, eng.current_race_status())
+    };
+    info!("Class {class_id}: {reason} — new Warning sequence");
+    audit_status_change(audit, &held, &status, &format!("{reason} {class_id}")).await;
+
     {
         let mut state = shared.write().await;
-        state.logs.push(log.clone());
-        // Keep logs at a reasonable size
-        if state.logs.len() > 100 {
-            state.logs.remove(0);
+        let class_state = state.class_sequences.entry(class_id.to_string()).or_default();
+        class_state.status = status;
+        class_state.start_time = None;
+        if let Some(upd) = &update {
+            class_state.apply(upd);
         }
+        out.emit_state(&state);
+    }
+    if let Some(upd) = update {
+        out.emit("class-sequence-update", &ClassSequenceUpdate { class_id: class_id.to_string(), update: upd });
+    }
+    log_to(shared, out, LogCategory::Procedure, "Director".to_string(),
+        format!("Class {class_id}: {reason} — new Warning signal, 1 sound"), Some(json!({ "classId": class_id })), false).await;
+}
+
+pub async fn issue_penalty(shared: &SharedState, out: &Outlet, data: &Value) {
+    let boat_id = entries::canonical_id(&*shared.read().await, data["boatId"].as_str().unwrap_or(""));
+    let penalty_type_str = data["type"].as_str().unwrap_or("UMPIRE_PENALTY");
+    let penalty_type = match penalty_type_str {
+        "OCS" => PenaltyType::Ocs,
+        "DSQ" => PenaltyType::Dsq,
+        "DNF" => PenaltyType::Dnf,
+        "DNS" => PenaltyType::Dns,
+        "TLE" => PenaltyType::Tle,
+        "TURN_360" => PenaltyType::Turn360,
+        "UMPIRE_NO_ACTION" => PenaltyType::UmpireNoAction,
+        "UMPIRE_DSQ" => PenaltyType::UmpireDsq,
+        _ => PenaltyType::UmpirePenalty,
+    };
+
+    let penalty = Penalty::new(boat_id.clone(), penalty_type.clone(), data["timestamp"].as_i64().unwrap_or_else(now_ms));
+    info!("Penalty: {:?} on {}", penalty.penalty_type, penalty.boat_id);
+
+    // Determine umpire signal flags + sounds
+    let (signal, flag, sounds) = match &penalty.penalty_type {
+        PenaltyType::UmpireNoAction => ("Umpire: No penalty", "GREEN_WHITE", "1 long"),
+        PenaltyType::UmpirePenalty | PenaltyType::Turn360 => ("Umpire: Penalty imposed", "RED", "1 long"),
+        PenaltyType::UmpireDsq => ("Umpire: DSQ — leave course", "BLACK_UMPIRE", "1 long"),
+        _ => ("Penalty", "", ""),
+    };
+
+    {
+        let mut state = shared.write().await;
+        state.penalties.push(penalty.clone());
     }
+    out.emit("penalty-issued", &penalty);
 
-    let _ = socket.broadcast().emit("new-log", &log);
-    let _ = socket.emit("new-log", &log);
+    log_to(shared, out, LogCategory::Jury, "Chief Umpire".to_string(),
+        format!("{}: {} on {}", signal, penalty_type_str, boat_id),
+        Some(json!({ "boatId": boat_id, "type": penalty_type_str, "flag": flag, "sounds": sounds })),
+        false).await;
 }
 
 // ─── Built-in Standard Procedure Graphs (RRS 26 compliant) ──────────────────
@@ -91,6 +662,8 @@ pub fn standard_procedure(minutes: u64, prep_flag: &str) -> ProcedureGraph {
                 post_trigger_duration: 0.0,
                 post_trigger_flags: vec![],
                 race_status: Some("IDLE".into()),
+                announce_lead: 0.0,
+                metadata: None,
             },
         },
         ProcedureNode {
@@ -108,6 +681,8 @@ pub fn standard_procedure(minutes: u64, prep_flag: &str) -> ProcedureGraph {
                 post_trigger_duration: 0.0,
                 post_trigger_flags: vec![],
                 race_status: Some("WARNING".into()),
+                announce_lead: 0.0,
+                metadata: None,
             },
         },
         ProcedureNode {
@@ -125,6 +700,8 @@ pub fn standard_procedure(minutes: u64, prep_flag: &str) -> ProcedureGraph {
                 post_trigger_duration: 0.0,
                 post_trigger_flags: vec![],
                 race_status: Some("PREPARATORY".into()),
+                announce_lead: 10.0,
+                metadata: None,
             },
         },
         ProcedureNode {
@@ -142,6 +719,8 @@ pub fn standard_procedure(minutes: u64, prep_flag: &str) -> ProcedureGraph {
                 post_trigger_duration: 0.0,
                 post_trigger_flags: vec![],
                 race_status: Some("ONE_MINUTE".into()),
+                announce_lead: 10.0,
+                metadata: None,
             },
         },
         ProcedureNode {
@@ -159,6 +738,8 @@ pub fn standard_procedure(minutes: u64, prep_flag: &str) -> ProcedureGraph {
                 post_trigger_duration: 0.0,
                 post_trigger_flags: vec![],
                 race_status: Some("RACING".into()),
+                announce_lead: 10.0,
+                metadata: None,
             },
         },
         ProcedureNode {
@@ -176,6 +757,8 @@ pub fn standard_procedure(minutes: u64, prep_flag: &str) -> ProcedureGraph {
                 post_trigger_duration: 0.0,
                 post_trigger_flags: vec![],
                 race_status: Some("RACING".into()),
+                announce_lead: 0.0,
+                metadata: None,
             },
         },
     ];
@@ -202,8 +785,12 @@ pub async fn on_connect(
     socket: SocketRef,
     shared: SharedState,
     engine: SharedEngine,
-    dead_boats: DeadBoats,
+    class_engines: ClassEngines,
+    rehearsals: Rehearsals,
+    delta: SharedDelta,
     auth: std::sync::Arc<crate::auth::AuthEngine>,
+    audit: AuditLogger,
+    downlink: ControlDownlink,
 ) {
     let socket_id = socket.id.to_string();
     info!("Client connected: {socket_id}");
@@ -211,9 +798,14 @@ pub async fn on_connect(
     // Cleanup on disconnect
     socket.on_disconnect({
         let auth = auth.clone();
+        let rehearsals = rehearsals.clone();
         let sid = socket_id.clone();
         move |_: SocketRef| async move {
             auth.remove_role(&sid).await;
+            auth.end_session(&sid).await;
+            auth.remove_identity(&sid).await;
+            rehearsals.write().await.remove(&sid);
+            time_sync::remove(&sid);
             info!("Client disconnected, roles cleaned: {sid}");
         }
     });
@@ -223,65 +815,170 @@ pub async fn on_connect(
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("register", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
                 let token = data["token"]
                     .as_str()
                     .or_else(|| data["type"].as_str())
                     .or_else(|| data["role"].as_str())
                     .unwrap_or("unknown");
-                
-                let mut client_type = "unknown".to_string();
-                
-                // 1) First attempt cryptographically secure Supabase JWT validation
-                if let Some(claims) = crate::auth::AuthEngine::verify_supabase_token(token) {
-                    client_type = claims.role.unwrap_or_else(|| {
-                        // Fallback: check app_metadata for custom roles
-                        if let Some(app_meta) = &claims.app_metadata {
-                            if let Some(r) = app_meta.get("role").and_then(|v| v.as_str()) {
-                                return r.to_string();
-                            }
-                        }
-                        // Default authenticated users form Supabase to "tracker"
-                        "tracker".to_string()
-                    });
-                } else {
-                    // 2) Fallback to insecure legacy/mock tokens for the web dashboard transition window
-                    client_type = match token {
-                        "director123" => "director".to_string(),
-                        "jury123" => "jury".to_string(),
-                        "media123" => "media".to_string(),
-                        "tracker123" => "tracker".to_string(),
-                        "director" => "director".to_string(),
-                        "jury" => "jury".to_string(),
-                        "media" => "media".to_string(),
-                        "tracker" => "tracker".to_string(),
-                        _ => "unknown".to_string(),
-                    };
+
+                // Throttle guessing before any token is looked at (see `login_guard`)
+                let guard_keys = login_guard::keys(&s.id.to_string(), client_identity(&s, &data, None).address.as_deref());
+                if let Err(refusal) = auth.login_guard().check(&guard_keys, Instant::now()) {
+                    warn!("Client {}: register refused, {:?} on {} for {} ms", s.id, refusal.reason, refusal.key, refusal.retry_after_ms);
+                    let _ = s.emit("auth-error", &json!({
+                        "error": "Too many login attempts, try again later",
+                        "reason": refusal.reason,
+                        "retryAfterMs": refusal.retry_after_ms,
+                    }));
+                    let _ = s.disconnect();
+                    return;
                 }
 
+                // Supabase JWT, provider ID token (see `oidc`), offline token or local credentials (see `local_auth`)
+                let login = match (data["username"].as_str(), data["password"].as_str()) {
+                    (Some(name), Some(password)) => auth.login(name, password).await,
+                    _ => auth.resolve_token(token).await.map(|resolved| (resolved, None)),
+                };
+                let (client_type, token_sub, method, token_boat) = match &login {
+                    Some((resolved, issued)) => {
+                        match (&resolved.sub, resolved.exp) {
+                            (Some(sub), Some(exp)) => auth.start_session(&s.id.to_string(), Session::new(sub.clone(), exp)).await,
+                            _ => auth.end_session(&s.id.to_string()).await,
+                        }
+                        if let Some(issued) = issued {
+                            let expires_ms = resolved.exp.map(|exp| exp as i64 * 1000);
+                            let _ = s.emit("local-token", &json!({ "token": issued, "expiresMs": expires_ms }));
+                        }
+                        (resolved.role.clone(), resolved.sub.clone(), resolved.method.clone(), resolved.boat_id.clone())
+                    }
+                    None => ("unknown".to_string(), None, "none".to_string(), None),
+                };
+
+                let identity = client_identity(&s, &data, token_sub.clone());
                 if client_type == "unknown" {
                     warn!("Client {}: rejected, invalid or unknown authentication token", s.id);
+                    // Enough to match a leaked token later, never the token itself
+                    let fingerprint = hex::encode(&Sha256::digest(token.as_bytes())[..8]);
+                    audit.log_auth_event(AuthEventKind::TokenRejected, &identity, json!({
+                        "reason": if data["username"].is_string() { "invalid credentials" } else { "invalid or unknown token" },
+                        "username": data["username"],
+                        "tokenSha256Prefix": fingerprint,
+                    })).await;
+                    for lockout in auth.login_guard().record_failure(&guard_keys, Instant::now()) {
+                        warn!("Client {}: {} locked out after {} failed logins", s.id, lockout.key, lockout.failures);
+                        let alert = json!({
+                            "kind": "locked-out",
+                            "key": lockout.key,
+                            "failures": lockout.failures,
+                            "lockoutSecs": lockout.lockout_secs,
+                            "address": identity.address,
+                            "userAgent": identity.user_agent,
+                            "username": data["username"],
+                        });
+                        audit.log_auth_event(AuthEventKind::LockedOut, &identity, alert.clone()).await;
+                        let _ = s.to("director").emit("auth-alert", &alert);
+                    }
                     let _ = s.disconnect();
                     return;
                 }
+                auth.login_guard().record_success(&guard_keys);
 
                 auth.set_role(&s.id.to_string(), &client_type).await;
                 info!("Client {}: registered and authenticated securely as Role: {}", s.id, client_type);
                 
                 // Map the tracker socket to the specific physical boat
                 if client_type == "tracker" {
-                    let bid = data["boatId"].as_str().unwrap_or("virtual-boat-1");
+                    // The verified token decides the boat; only development role strings may name one
+                    let bid = match token_boat.as_deref() {
+                        Some(bid) => bid,
+                        None if method == "legacy" => data["boatId"].as_str().unwrap_or("virtual-boat-1"),
+                        None => {
+                            warn!("Client {}: rejected, tracker token is bound to no boat", s.id);
+                            audit.log_auth_event(AuthEventKind::TokenRejected, &identity, json!({ "reason": "tracker token names no boat" })).await;
+                            let _ = s.disconnect();
+                            return;
+                        }
+                    };
+                    if blacklist::is_banned(&*shared.read().await, bid, data["deviceId"].as_str(), now_ms()) {
+                        warn!("Client {}: rejected, tracker for {bid} is blacklisted", s.id);
+                        audit.log_auth_event(AuthEventKind::TokenRejected, &identity, json!({ "reason": "blacklisted" })).await;
+                        let _ = s.emit("kill-simulation", &json!({ "id": bid }));
+                        let _ = s.disconnect();
+                        return;
+                    }
                     auth.set_tracker_boat(&s.id.to_string(), bid).await;
+                    let _ = s.join(boat_scope::boat_room(bid));
                     info!("Client {}: mapped hardware to Boat ID: {}", s.id, bid);
                 }
 
                 let _ = s.join(client_type.to_string());
+                let _ = s.join(roles::client_room(&s.id.to_string()));
+
+                auth.set_identity(identity).await;
+                audit.log_auth_event(AuthEventKind::Registered, &auth.identity(&s.id.to_string()).await, json!({
+                    "method": method,
+                })).await;
+
+                // A client of a regatta that is not the live one sees that event's stored state
+                if let Some(event_id) = data["eventId"].as_str() {
+                    let _ = s.join(regatta::room(event_id));
+                    let live = shared.read().await.active_event_id.as_deref().is_none_or(|id| id == event_id);
+                    if !live {
+                        match regatta::load(event_id).await {
+                            Ok(Some(stored)) => {
+                                match auth.get_tracker_boat(&s.id.to_string()).await {
+                                    Some(bid) if client_type == "tracker" => {
+                                        let _ = s.emit("init-state", &boat_scope::view(&stored, &bid));
+                                    }
+                                    _ => {
+                                        let _ = s.emit("init-state", &stored);
+                                    }
+                                }
+                                return;
+                            }
+                            Ok(None) => {}
+                            Err(e) => warn!("Client {}: failed to load event {event_id}: {e}", s.id),
+                        }
+                    }
+                }
 
                 let state = shared.read().await;
-                let _ = s.emit("init-state", &*state);
+                match auth.get_tracker_boat(&s.id.to_string()).await {
+                    Some(bid) if client_type == "tracker" => {
+                        let _ = s.emit("init-state", &boat_scope::view(&state, &bid));
+                    }
+                    _ => {
+                        let _ = s.emit("init-state", &*state);
+                    }
+                }
+            }
+        });
+    }
+
+    // ── refresh-token (see `sessions`) ────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let auth = auth.clone();
+        socket.on("refresh-token", move |s: SocketRef, Data::<Value>(data)| {
+            let auth = auth.clone();
+            async move {
+                let sid = s.id.to_string();
+                match auth.refresh_session(&sid, data["token"].as_str().unwrap_or_default()).await {
+                    Ok(expires_ms) => {
+                        info!("Client {sid}: session refreshed until {expires_ms}");
+                        let _ = s.emit("token-refreshed", &json!({ "expiresMs": expires_ms }));
+                    }
+                    Err(e) => {
+                        warn!("Client {sid}: token refresh refused: {e}");
+                        let _ = s.emit("auth-error", &json!({ "error": e.to_string() }));
+                    }
+                }
             }
         });
     }
@@ -317,15 +1014,67 @@ pub async fn on_connect(
         });
     }
 
+    // ── time-sync ─────────────────────────────────────────────────────────────
+    socket.on("time-sync", |s: SocketRef, Data::<Value>(data)| async move {
+        let t1 = crate::time_discipline::now_ms();
+        let _ = s.emit("time-sync", &time_sync::reply(data["t0"].as_f64(), t1));
+    });
+
+    // ── time-sync-report ──────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let auth = auth.clone();
+        socket.on("time-sync-report", move |s: SocketRef, Data::<Value>(data)| {
+            let auth = auth.clone();
+            async move {
+                let (Some(offset_ms), Some(rtt_ms)) = (data["offsetMs"].as_f64(), data["rttMs"].as_f64()) else {
+                    return;
+                };
+                let sid = s.id.to_string();
+                let offset = ClientOffset {
+                    role: auth.get_role(&sid).await,
+                    boat_id: auth.get_tracker_boat(&sid).await,
+                    socket_id: sid,
+                    offset_ms,
+                    rtt_ms,
+                    samples: data["samples"].as_u64().unwrap_or(1) as u32,
+                    reported_ms: crate::time_discipline::now_ms(),
+                };
+                let _ = s.to("director").emit("client-clock-offset", &offset);
+                time_sync::record(offset);
+            }
+        });
+    }
+
+    // ── list-clock-offsets ────────────────────────────────────────────────────
+    socket.on("list-clock-offsets", |s: SocketRef| async move {
+        let _ = s.emit("clock-offsets", &time_sync::list());
+    });
+
     // ── track-update ──────────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        let dead_boats = dead_boats.clone();
+        let limiter = Arc::new(std::sync::Mutex::new(TrackLimiter::new(RateLimitConfig::default())));
         socket.on("track-update", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
-            let dead_boats = dead_boats.clone();
+            let limiter = limiter.clone();
             async move {
+                // Flood protection before anything touches the logs or the state lock
+                let now = Instant::now();
+                let (verdict, warning, max_hz) = {
+                    let mut limiter = limiter.lock().unwrap_or_else(|e| e.into_inner());
+                    let verdict = limiter.check(now);
+                    (verdict, limiter.take_warning(now), limiter.config().max_hz)
+                };
+                if let Some(dropped) = warning {
+                    warn!("Client {}: track-update rate limited, {dropped} update(s) dropped", s.id);
+                    let _ = s.emit("rate-limited", &json!({ "event": "track-update", "dropped": dropped, "maxHz": max_hz }));
+                }
+                if verdict != Verdict::Accept {
+                    return;
+                }
+
                 info!("🛠️ [RAW-DEBUG] track-update raw event fired! payload: {:?}", data);
                 
                 let boat_id = match data["boatId"].as_str() {
@@ -343,7 +1092,7 @@ pub async fn on_connect(
                 }
 
                 // Check blacklist
-                if dead_boats.read().await.contains(&boat_id) {
+                if blacklist::is_banned(&*shared.read().await, &boat_id, data["deviceId"].as_str(), now_ms()) {
                     let _ = s.emit("kill-simulation", &json!({ "id": boat_id }));
                     return;
                 }
@@ -378,20 +1127,14 @@ pub async fn on_connect(
                 let speed_setting = data["speedSetting"].as_f64();
                 let path_progress = data["pathProgress"].as_f64();
 
+                let received_ms = now_ms();
+                let mut revived = false;
+                let sampled;
                 {
                     let mut state = shared.write().await;
                     
-                    let hist = state.fleet_history.entry(boat_id.clone()).or_insert_with(Vec::new);
-                    if hist.is_empty() || timestamp - hist.last().unwrap().timestamp > 5000 {
-                        hist.push(crate::state::HistoricalPing {
-                            timestamp,
-                            lat: pos.lat,
-                            lon: pos.lon,
-                        });
-                        if hist.len() > 360 {
-                            hist.remove(0);
-                        }
-                    }
+                    sampled = track_store::sample(&mut state, &boat_id, timestamp, pos.lat, pos.lon)
+                        .map(|ping| (track_store::race_key(&state), ping));
 
                     if let Some(existing) = state.boats.get_mut(&boat_id) {
                         existing.pos = pos;
@@ -399,6 +1142,11 @@ pub async fn on_connect(
                         existing.velocity = velocity;
                         existing.dtl = dtl;
                         existing.timestamp = timestamp;
+                        existing.last_seen_ms = received_ms;
+                        if existing.status != BoatStatus::Live {
+                            existing.status = BoatStatus::Live;
+                            revived = true;
+                        }
                         
                         if let Some(path) = sim_path { existing.simulation_path = path; }
                         if let Some(sim) = is_simulating { existing.is_simulating = sim; }
@@ -419,17 +1167,35 @@ pub async fn on_connect(
                             leg_index: 0,
                             dtf_m: 0.0,
                             rank: 0,
+                            lap: 0,
+                            last_seen_ms: received_ms,
+                            status: BoatStatus::Live,
+                            line_prediction: None,
                         };
                         state.boats.insert(boat_id.clone(), boat);
                     }
+                    let prediction = state.boats.get(&boat_id).and_then(|b| tide::predict(&state, b, received_ms));
+                    if let Some(boat) = state.boats.get_mut(&boat_id) {
+                        boat.line_prediction = prediction;
+                    }
                 }
 
                 let state = shared.read().await;
                 if let Some(boat) = state.boats.get(&boat_id) {
-                    let _ = s.broadcast().emit("boat-update", &boat);
-                    let _ = s.broadcast().emit("media-boat-update", &boat);
+                    if revived {
+                        let event = boat_liveness::status_event(boat, received_ms);
+                        let _ = s.broadcast().emit("boat-status-changed", &event);
+                        let _ = s.emit("boat-status-changed", &event);
+                    }
+                    let _ = s.broadcast().except(SCOPED_ROOM).emit("boat-update", &boat);
+                    let _ = s.to(boat_scope::boat_room(&boat_id)).emit("boat-update", &boat);
                     let _ = s.to("media").emit("media-boat-update", &boat);
                 }
+                drop(state);
+
+                if let Some((race_id, ping)) = sampled {
+                    track_store::append(&race_id, &boat_id, ping).await;
+                }
             }
         });
     }
@@ -438,9 +1204,15 @@ pub async fn on_connect(
     {
         let socket = socket.clone();
         let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("update-tracker-simulation", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
+                audit_command(&audit, &auth, &s, "update-tracker-simulation", &data).await;
+
                 let boat_id = match data["boatId"].as_str() {
                     Some(id) => id.to_string(),
                     None => return,
@@ -470,7 +1242,8 @@ pub async fn on_connect(
                         boat.speed_setting = speed_setting;
                         boat.path_progress = path_progress;
                         
-                        let _ = s.broadcast().emit("boat-update", &*boat);
+                        let _ = s.broadcast().except(SCOPED_ROOM).emit("boat-update", &*boat);
+                        let _ = s.to(boat_scope::boat_room(&boat_id)).emit("boat-update", &*boat);
                         let _ = s.emit("boat-update", &*boat);
 
                         drop(state);
@@ -491,978 +1264,3068 @@ pub async fn on_connect(
         let shared = shared.clone();
         let engine = engine.clone();
         let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("start-sequence", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let engine = engine.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized starting sequence attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, "start-sequence").await {
                     return;
                 }
-                
-                let prep_flag_str = data["prepFlag"].as_str().unwrap_or("P");
 
-                let mut eng = engine.write().await;
-                
-                // Keep the deployed graph if present, otherwise load standard
-                let graph = if let Some(g) = &eng.graph {
-                    g.clone()
-                } else {
-                    let minutes = data["minutes"].as_u64().unwrap_or(5);
-                    let g = standard_procedure(minutes, prep_flag_str);
-                    eng.load_procedure(g.clone());
-                    g
-                };
+                audit_command(&audit, &auth, &s, "start-sequence", &data).await;
+                start_sequence(&shared, &engine, &audit, &Outlet::Socket(s.clone()), &data).await;
+            }
+        });
+    }
 
-                let update = eng.start();
-                let status = eng.current_race_status();
-                drop(eng);
+    // ── configure-pursuit / clear-pursuit (handicap starts) ───────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("configure-pursuit", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "configure-pursuit").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "configure-pursuit", &data).await;
+
+                let mut pursuit = PursuitStart {
+                    system: serde_json::from_value(data["system"].clone()).unwrap_or_default(),
+                    race_minutes: data["raceMinutes"].as_f64().unwrap_or(0.0),
+                    entries: serde_json::from_value(data["boats"].clone()).unwrap_or_default(),
+                    start_ms: None,
+                };
+                if let Err(e) = pursuit::compute_offsets(&mut pursuit) {
+                    warn!("configure-pursuit rejected: {e}");
+                    let _ = s.emit("pursuit-error", &json!({ "error": e.to_string() }));
+                    return;
+                }
 
+                let summary = format!("Pursuit start armed: {} boats, last start +{}s",
+                    pursuit.entries.len(), pursuit.entries.last().map(|e| e.offset_secs).unwrap_or(0.0));
                 {
                     let mut state = shared.write().await;
-                    state.status = status;
-                    state.current_procedure = Some(graph);
-                    state.ocs_boats.clear();
-                    state.prep_flag = match prep_flag_str {
-                        "I" => PrepFlag::I,
-                        "Z" => PrepFlag::Z,
-                        "U" => PrepFlag::U,
-                        "BLACK" => PrepFlag::Black,
-                        _ => PrepFlag::P,
-                    };
-                    if let Some(upd) = &update {
-                        state.current_sequence = Some(upd.current_sequence.clone());
-                        state.sequence_time_remaining = Some(upd.sequence_time_remaining);
-                    }
+                    let _ = s.broadcast().emit("pursuit-schedule", &pursuit);
+                    let _ = s.emit("pursuit-schedule", &pursuit);
+                    state.pursuit = Some(pursuit);
+                    let _ = save_state(&state).await;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
                 }
 
-                if let Some(upd) = update {
-                    let _ = s.broadcast().emit("sequence-update", &upd);
-                    let _ = s.emit("sequence-update", &upd);
+                emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(), summary, None, false).await;
+            }
+        });
+    }
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("clear-pursuit", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "clear-pursuit").await {
+                    return;
                 }
 
-                let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
+                audit_command(&audit, &auth, &s, "clear-pursuit", &data).await;
+
+                {
+                    let mut state = shared.write().await;
+                    state.pursuit = None;
+                    let _ = save_state(&state).await;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                }
 
-                emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(), "Started sequence".to_string(), None, false).await;
+                emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
+                    "Pursuit start cleared".to_string(), None, false).await;
             }
         });
     }
 
-    // ── set-prep-flag ─────────────────────────────────────────────────────────
+    // ── finishes & scoring (RRS Appendix A low point) ────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("set-prep-flag", move |s: SocketRef, Data::<Value>(data)| {
+        socket.on("get-standings", move |s: SocketRef| {
             let shared = shared.clone();
             async move {
-                // Accept both bare string ("P") and object ({ flag: "P" })
-                let flag_str = data.as_str()
-                    .or_else(|| data["flag"].as_str())
-                    .unwrap_or("P");
-                let mut state = shared.write().await;
-                state.prep_flag = match flag_str {
-                    "I" => PrepFlag::I,
-                    "Z" => PrepFlag::Z,
-                    "U" => PrepFlag::U,
-                    "BLACK" => PrepFlag::Black,
-                    _ => PrepFlag::P,
-                };
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
+                let state = shared.read().await;
+                let _ = s.emit("standings", &json!({ "standings": state.standings, "results": state.results }));
             }
         });
     }
-
-    // ── procedure-action (RRS Race Management) ────────────────────────────────
-    {
+    for event in ["record-finish", "remove-finish"] {
         let socket = socket.clone();
         let shared = shared.clone();
-        let engine = engine.clone();
         let auth = auth.clone();
-        socket.on("procedure-action", move |s: SocketRef, Data::<Value>(data)| {
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
-            let engine = engine.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized procedure action attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, event).await {
                     return;
                 }
-                
-                let action = data["action"].as_str().unwrap_or("");
-                info!("Procedure action: {action}");
-
-                match action {
-                    // ── POSTPONE (AP flag + 2 sounds) ─────────────────────
-                    "POSTPONE" => {
-                        engine.write().await.stop();
-                        {
-                            let mut state = shared.write().await;
-                            state.status = RaceStatus::Postponed;
-                            state.current_sequence = Some(SequenceInfo {
-                                event: "Postponed".to_string(),
-                                flags: vec!["AP".to_string()],
-                            });
-                            state.sequence_time_remaining = None;
-                            state.waiting_for_trigger = false;
-                            state.action_label = None;
-
-                            let _ = s.broadcast().emit("state-update", &*state);
-                            let _ = s.emit("state-update", &*state);
-                        }
-
-                        emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                            "Race postponed — AP flag raised, 2 sounds".to_string(),
-                            Some(json!({ "signal": "AP", "sounds": 2 })), false).await;
-
-                        // Auto-resume: spawn a task that waits 60s then starts new Warning
-                        let shared_r = shared.clone();
-                        let engine_r = engine.clone();
-                        let s_r = s.clone();
-                        tokio::spawn(async move {
-                            tokio::time::sleep(Duration::from_secs(60)).await;
-                            
-                            // Only resume if still postponed (RC may have manually changed)
-                            let is_still_postponed = shared_r.read().await.status == RaceStatus::Postponed;
-                            if !is_still_postponed { return; }
-
-                            info!("AP lowered — resuming with new Warning in 1 min");
-                            
-                            // Restart the engine
-                            let mut eng = engine_r.write().await;
-                            let update = eng.start();
-                            let status = eng.current_race_status();
-                            drop(eng);
-
-                            {
-                                let mut state = shared_r.write().await;
-                                state.status = status;
-                                if let Some(upd) = &update {
-                                    state.current_sequence = Some(upd.current_sequence.clone());
-                                    state.sequence_time_remaining = Some(upd.sequence_time_remaining);
-                                }
-                            }
-
-                            if let Some(upd) = update {
-                                let _ = s_r.broadcast().emit("sequence-update", &upd);
-                                let _ = s_r.emit("sequence-update", &upd);
-                            }
 
-                            let state = shared_r.read().await;
-                            let _ = s_r.broadcast().emit("state-update", &*state);
-                            let _ = s_r.emit("state-update", &*state);
+                audit_command(&audit, &auth, &s, event, &data).await;
 
-                            emit_log(&shared_r, &s_r, LogCategory::Procedure, "Director".to_string(),
-                                "AP lowered — new Warning signal, 1 sound".to_string(),
-                                Some(json!({ "signal": "AP_DOWN", "sounds": 1 })), false).await;
-                        });
+                let Some(key) = data["boatId"].as_str() else { return };
+                let boat_id = entries::canonical_id(&*shared.read().await, key);
+                let message = {
+                    let mut state = shared.write().await;
+                    let changed = if event == "record-finish" {
+                        let finish_ms = data["timestamp"].as_i64().unwrap_or_else(now_ms);
+                        scoring::record_finish(&mut state, &boat_id, finish_ms, "manual")
+                    } else {
+                        let before = state.finishes.len();
+                        state.finishes.retain(|f| f.boat_id != boat_id);
+                        state.finishes.len() != before
+                    };
+                    if !changed {
+                        return;
                     }
-
-                    // ── INDIVIDUAL RECALL (X flag + 1 sound) ──────────────
-                    "INDIVIDUAL_RECALL" => {
-                        // Don't stop the engine — racing continues
-                        let ocs_boats: Vec<String> = data["boats"].as_array()
-                            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
-                            .unwrap_or_default();
-                        
-                        {
-                            let mut state = shared.write().await;
-                            state.status = RaceStatus::IndividualRecall;
-                            state.ocs_boats = ocs_boats.clone();
-                            state.current_sequence = Some(SequenceInfo {
-                                event: "Individual Recall".to_string(),
-                                flags: vec!["X".to_string()],
-                            });
-
-                            let _ = s.broadcast().emit("state-update", &*state);
-                            let _ = s.emit("state-update", &*state);
-                        }
-
-                        emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                            format!("Individual Recall — X flag raised, OCS: {}", if ocs_boats.is_empty() { "none identified".to_string() } else { ocs_boats.join(", ") }),
-                            Some(json!({ "signal": "X", "sounds": 1, "ocsBoats": ocs_boats })), false).await;
-
-                        // Auto-clear X flag after 5 minutes (DNS default)
-                        let shared_r = shared.clone();
-                        let s_r = s.clone();
-                        tokio::spawn(async move {
-                            tokio::time::sleep(Duration::from_secs(300)).await; // 5 min
-                            
-                            let is_still_recall = shared_r.read().await.status == RaceStatus::IndividualRecall;
-                            if !is_still_recall { return; }
-
-                            info!("X flag auto-lowered after 5 minutes");
-                            {
-                                let mut state = shared_r.write().await;
-                                state.status = RaceStatus::Racing;
-                                state.current_sequence = Some(SequenceInfo {
-                                    event: "Racing".to_string(),
-                                    flags: vec![],
-                                });
-
-                                // Issue DNS to OCS boats
-                                let ocs_list = state.ocs_boats.clone();
-                                for boat_id in &ocs_list {
-                                    state.penalties.push(Penalty {
-                                        boat_id: boat_id.clone(),
-                                        penalty_type: PenaltyType::Dns,
-                                        timestamp: now_ms(),
-                                    });
-                                }
-                                state.ocs_boats.clear();
-
-                                let _ = s_r.broadcast().emit("state-update", &*state);
-                                let _ = s_r.emit("state-update", &*state);
-                            }
-
-                            emit_log(&shared_r, &s_r, LogCategory::Procedure, "Director".to_string(),
-                                "X flag lowered — DNS applied to OCS boats".to_string(), None, false).await;
-                        });
+                    let _ = s.broadcast().except(SCOPED_ROOM).emit("finishes-update", &state.finishes);
+                    let _ = s.emit("finishes-update", &state.finishes);
+                    match state.finishes.iter().position(|f| f.boat_id == boat_id) {
+                        Some(i) => format!("Finish recorded: {boat_id} ({})", i + 1),
+                        None => format!("Finish removed: {boat_id}"),
                     }
+                };
 
-                    // ── GENERAL RECALL (1st Substitute + 2 sounds) ────────
-                    "GENERAL_RECALL" => {
-                        engine.write().await.stop();
-                        {
-                            let mut state = shared.write().await;
-                            state.status = RaceStatus::GeneralRecall;
-                            state.current_sequence = Some(SequenceInfo {
-                                event: "General Recall".to_string(),
-                                flags: vec!["FIRST_SUB".to_string()],
-                            });
-                            state.sequence_time_remaining = None;
-                            state.waiting_for_trigger = false;
-                            state.action_label = None;
+                emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(), message,
+                    Some(json!({ "boatId": boat_id })), false).await;
+            }
+        });
+    }
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("score-race", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "score-race").await {
+                    return;
+                }
 
-                            let _ = s.broadcast().emit("state-update", &*state);
-                            let _ = s.emit("state-update", &*state);
-                        }
+                audit_command(&audit, &auth, &s, "score-race", &data).await;
 
-                        emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                            "General Recall — 1st Substitute raised, 2 sounds".to_string(),
-                            Some(json!({ "signal": "FIRST_SUB", "sounds": 2 })), false).await;
-
-                        // Auto: 1st Sub down + 1 sound, new Warning 1 min later
-                        let shared_r = shared.clone();
-                        let engine_r = engine.clone();
-                        let s_r = s.clone();
-                        tokio::spawn(async move {
-                            tokio::time::sleep(Duration::from_secs(60)).await;
-
-                            let is_still_recall = shared_r.read().await.status == RaceStatus::GeneralRecall;
-                            if !is_still_recall { return; }
-
-                            info!("1st Substitute lowered — new Warning sequence starting");
-
-                            let mut eng = engine_r.write().await;
-                            let update = eng.start();
-                            let status = eng.current_race_status();
-                            drop(eng);
-
-                            {
-                                let mut state = shared_r.write().await;
-                                state.status = status;
-                                if let Some(upd) = &update {
-                                    state.current_sequence = Some(upd.current_sequence.clone());
-                                    state.sequence_time_remaining = Some(upd.sequence_time_remaining);
-                                }
-                            }
+                let summary = {
+                    let mut state = shared.write().await;
+                    let race = scoring::close_race(&mut state);
+                    let summary = format!("Race {} scored: {} finishers, {} entries", race.race_number, race.finishes.len(), race.entries.len());
+                    let _ = save_state(&state).await;
+                    let payload = json!({ "standings": state.standings, "results": state.results });
+                    let _ = s.broadcast().emit("standings", &payload);
+                    let _ = s.emit("standings", &payload);
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                    summary
+                };
 
-                            if let Some(upd) = update {
-                                let _ = s_r.broadcast().emit("sequence-update", &upd);
-                                let _ = s_r.emit("sequence-update", &upd);
-                            }
+                emit_log(&shared, &s, LogCategory::Jury, "Director".to_string(), summary, None, false).await;
+            }
+        });
+    }
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("set-scoring", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "set-scoring").await {
+                    return;
+                }
 
-                            let state = shared_r.read().await;
-                            let _ = s_r.broadcast().emit("state-update", &*state);
-                            let _ = s_r.emit("state-update", &*state);
+                audit_command(&audit, &auth, &s, "set-scoring", &data).await;
 
-                            emit_log(&shared_r, &s_r, LogCategory::Procedure, "Director".to_string(),
-                                "1st Substitute lowered — new Warning signal, 1 sound".to_string(),
-                                Some(json!({ "signal": "FIRST_SUB_DOWN", "sounds": 1 })), false).await;
-                        });
+                let settings: ScoringSettings = match serde_json::from_value(data) {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        warn!("set-scoring rejected: {e}");
+                        return;
                     }
+                };
+                let mut state = shared.write().await;
+                state.scoring = settings;
+                scoring::rescore(&mut state);
+                let _ = save_state(&state).await;
+                let payload = json!({ "standings": state.standings, "results": state.results });
+                let _ = s.broadcast().emit("standings", &payload);
+                let _ = s.emit("standings", &payload);
+            }
+        });
+    }
+    // ── director-config ───────────────────────────────────────────────────────
+    // Auto-director weights; no payload reads them back (see `auto_director`)
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("director-config", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if data.as_object().is_none_or(|patch| patch.is_empty()) {
+                    let _ = s.emit("director-config", &shared.read().await.auto_director);
+                    return;
+                }
+                if !authorize_command(&audit, &auth, &s, "director-config").await {
+                    return;
+                }
 
-                    // ── ABANDON (N flag + 3 sounds) ───────────────────────
-                    "ABANDON" => {
-                        engine.write().await.stop();
-                        {
-                            let mut state = shared.write().await;
-                            state.status = RaceStatus::Abandoned;
-                            state.current_sequence = Some(SequenceInfo {
-                                event: "Abandoned".to_string(),
-                                flags: vec!["N".to_string()],
-                            });
-                            state.sequence_time_remaining = None;
-                            state.waiting_for_trigger = false;
-                            state.action_label = None;
-                            state.ocs_boats.clear();
-
-                            let _ = s.broadcast().emit("state-update", &*state);
-                            let _ = s.emit("state-update", &*state);
-                        }
-
-                        emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                            "Race abandoned — N flag raised, 3 sounds".to_string(),
-                            Some(json!({ "signal": "N", "sounds": 3 })), false).await;
-                    }
+                audit_command(&audit, &auth, &s, "director-config", &data).await;
 
-                    // ── SHORTEN COURSE (S flag + 2 sounds) ────────────────
-                    "SHORTEN_COURSE" => {
-                        emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                            "Shorten Course — S flag raised, 2 sounds".to_string(),
-                            Some(json!({ "signal": "S", "sounds": 2 })), false).await;
+                let mut state = shared.write().await;
+                match auto_director::patch_config(&state.auto_director, &data) {
+                    Ok(config) => {
+                        state.auto_director = config;
+                        let _ = save_state(&state).await;
+                        let _ = s.broadcast().emit("director-config", &state.auto_director);
+                        let _ = s.emit("director-config", &state.auto_director);
                     }
-
-                    // ── COURSE CHANGE (C flag + repetitive sounds) ────────
-                    "COURSE_CHANGE" => {
-                        emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                            "Course Change — C flag raised, repetitive sounds".to_string(),
-                            Some(json!({ "signal": "C", "sounds": "repetitive" })), false).await;
+                    Err(e) => {
+                        warn!("director-config rejected: {e}");
+                        let _ = s.emit("director-config-error", &json!({ "error": e.to_string() }));
                     }
+                }
+            }
+        });
+    }
+    // ── director-override ─────────────────────────────────────────────────────
+    // Pin, exclude, suspend; no payload reads the overrides back (see `auto_director`)
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("director-override", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if data.as_object().is_none_or(|o| o.is_empty()) {
+                    let _ = s.emit("director-overrides", &shared.read().await.director_overrides);
+                    return;
+                }
+                if !authorize_command(&audit, &auth, &s, "director-override").await {
+                    return;
+                }
 
-                    // ── RESET TO IDLE ──────────────────────────────────────
-                    "RESET" => {
-                        engine.write().await.stop();
-                        {
-                            let mut state = shared.write().await;
-                            state.status = RaceStatus::Idle;
-                            state.current_sequence = None;
-                            state.sequence_time_remaining = None;
-                            state.start_time = None;
-                            state.waiting_for_trigger = false;
-                            state.action_label = None;
-                            state.is_post_trigger = false;
-                            state.ocs_boats.clear();
-
-                            let _ = s.broadcast().emit("state-update", &*state);
-                            let _ = s.emit("state-update", &*state);
-                        }
+                audit_command(&audit, &auth, &s, "director-override", &data).await;
 
-                        emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                            "Race reset to Idle".to_string(), None, false).await;
+                let mut state = shared.write().await;
+                match auto_director::apply_override(&state.director_overrides, &data, now_ms()) {
+                    Ok(overrides) => {
+                        info!("🎬 Auto-Director override by {}: {data}", s.id);
+                        state.director_overrides = overrides;
+                        let _ = save_state(&state).await;
+                        let _ = s.broadcast().emit("director-overrides", &state.director_overrides);
+                        let _ = s.emit("director-overrides", &state.director_overrides);
                     }
-
-                    _ => {
-                        warn!("Unknown procedure action: {action}");
+                    Err(e) => {
+                        warn!("director-override rejected: {e}");
+                        let _ = s.emit("director-override-error", &json!({ "error": e.to_string() }));
                     }
                 }
             }
         });
     }
-
-    // ── save-procedure ────────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        let engine = engine.clone();
         let auth = auth.clone();
-        socket.on("save-procedure", move |s: SocketRef, Data::<Value>(data)| {
+        let audit = audit.clone();
+        socket.on("set-race-handicap", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
-            let engine = engine.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized save-procedure attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, "set-race-handicap").await {
                     return;
                 }
-                
-                match serde_json::from_value::<ProcedureGraph>(data) {
-                    Ok(graph) => {
-                        let mut eng = engine.write().await;
-                        eng.load_procedure(graph.clone());
-                        let update = eng.start();
-                        let status = eng.current_race_status();
-                        drop(eng);
-
-                        {
-                            let mut state = shared.write().await;
-                            state.status = status;
-                            state.current_procedure = Some(graph);
-                            if let Some(upd) = &update {
-                                state.current_sequence = Some(upd.current_sequence.clone());
-                                state.sequence_time_remaining = Some(upd.sequence_time_remaining);
-                            }
-                            let _ = save_state(&state).await;
-                        }
 
-                        if let Some(upd) = update {
-                            let _ = s.broadcast().emit("sequence-update", &upd);
-                            let _ = s.emit("sequence-update", &upd);
-                        }
-                        let state = shared.read().await;
-                        let _ = s.broadcast().emit("state-update", &*state);
-                        let _ = s.emit("state-update", &*state);
+                audit_command(&audit, &auth, &s, "set-race-handicap", &data).await;
 
-                        emit_log(&shared, &s, LogCategory::Procedure, "Architect".to_string(),
-                            "Custom procedure deployed and started".to_string(), None, false).await;
+                let Some(race_number) = data["raceNumber"].as_u64() else { return };
+                let handicap: Handicap = match serde_json::from_value(data["handicap"].clone()) {
+                    Ok(handicap) => handicap,
+                    Err(e) => {
+                        warn!("set-race-handicap rejected: {e}");
+                        return;
                     }
-                    Err(e) => warn!("Failed to parse procedure: {e}"),
-                }
+                };
+                let mut state = shared.write().await;
+                let Some(race) = state.results.iter_mut().find(|r| r.race_number as u64 == race_number) else {
+                    warn!("set-race-handicap: race {race_number} has not been scored");
+                    return;
+                };
+                race.handicap = handicap;
+                scoring::rescore(&mut state);
+                let _ = save_state(&state).await;
+                let payload = json!({ "standings": state.standings, "results": state.results });
+                let _ = s.broadcast().emit("standings", &payload);
+                let _ = s.emit("standings", &payload);
             }
         });
     }
 
-    // ── trigger-node ──────────────────────────────────────────────────────────
+    // ── entries (sail numbers, classes, ratings, crews, UWB tags) ────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        let engine = engine.clone();
+        socket.on("list-entries", move |s: SocketRef| {
+            let shared = shared.clone();
+            async move {
+                let _ = s.emit("entries-update", &shared.read().await.entries);
+            }
+        });
+    }
+    for event in ["upsert-entry", "delete-entry"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
         let auth = auth.clone();
-        socket.on("trigger-node", move |s: SocketRef, Data::<Value>(data)| {
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
-            let engine = engine.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized node trigger attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &crew::without_contacts(&data)).await;
+
+                let mut contacts = None;
+                let (result, contacts_key) = {
+                    let mut state = shared.write().await;
+                    let result = if event == "upsert-entry" {
+                        match serde_json::from_value::<Entry>(data.clone()) {
+                            Ok(mut entry) => {
+                                contacts = Some(crew::take_contacts(&mut entry));
+                                entries::upsert(&mut state, entry)
+                            }
+                            Err(e) => {
+                                let _ = s.emit("entry-error", &json!({ "error": format!("Invalid entry: {e}") }));
+                                return;
+                            }
+                        }
+                    } else {
+                        entries::remove(&mut state, data["boatId"].as_str().unwrap_or_default())
+                    };
+                    if result.is_ok() {
+                        // Ratings feed corrected times
+                        scoring::rescore(&mut state);
+                        let _ = save_state(&state).await;
+                        let _ = s.broadcast().emit("entries-update", &state.entries);
+                        let _ = s.emit("entries-update", &state.entries);
+                        let payload = json!({ "standings": state.standings, "results": state.results });
+                        let _ = s.broadcast().emit("standings", &payload);
+                        let _ = s.emit("standings", &payload);
+                    }
+                    (result, crew::key(&state))
+                };
+
+                match result {
+                    Ok(entry) => {
+                        let names: Vec<String> = entry.crew.iter().map(|m| m.name.clone()).collect();
+                        if let Err(e) = crew::save(&contacts_key, &entry.boat_id, &names, contacts).await {
+                            warn!("Crew contacts of {} not saved: {e}", entry.boat_id);
+                        }
+                        let verb = if event == "upsert-entry" { "Entry saved" } else { "Entry removed" };
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            format!("{verb}: {} {}", entry.sail_number, entry.boat_name).trim_end().to_string(),
+                            Some(json!({ "boatId": entry.boat_id })), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("entry-error", &json!({ "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── crew emergency contacts (director / jury) ─────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("get-crew-contacts", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "get-crew-contacts").await {
+                    return;
+                }
+                audit_command(&audit, &auth, &s, "get-crew-contacts", &data).await;
+
+                let (key, boat_id) = {
+                    let state = shared.read().await;
+                    (crew::key(&state), data["boatId"].as_str().map(|b| entries::canonical_id(&state, b)))
+                };
+                match crew::load(&key).await {
+                    Ok(mut contacts) => {
+                        if let Some(boat_id) = boat_id {
+                            contacts.retain(|b, _| *b == boat_id);
+                        }
+                        let _ = s.emit("crew-contacts", &contacts);
+                    }
+                    Err(e) => {
+                        let _ = s.emit("entry-error", &json!({ "error": format!("Crew contacts unavailable: {e}") }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── boat class registry (polars, angles, handicap system) ────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        socket.on("list-boat-classes", move |s: SocketRef| {
+            let shared = shared.clone();
+            async move {
+                let _ = s.emit("boat-classes-update", &shared.read().await.boat_classes);
+            }
+        });
+    }
+    for event in ["upsert-boat-class", "delete-boat-class"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let id = data["id"].as_str().unwrap_or_default().to_string();
+                let result = {
+                    let mut state = shared.write().await;
+                    let result = if event == "upsert-boat-class" {
+                        match serde_json::from_value::<BoatClass>(data.clone()) {
+                            Ok(class) => boat_classes::upsert(&mut state, class),
+                            Err(e) => {
+                                let _ = s.emit("class-error", &json!({ "error": format!("Invalid class: {e}") }));
+                                return;
+                            }
+                        }
+                    } else {
+                        boat_classes::delete(&mut state, &id)
+                    };
+                    if result.is_ok() {
+                        let laylines_changed = laylines::refresh(&mut state);
+                        let _ = save_state(&state).await;
+                        let _ = s.broadcast().emit("boat-classes-update", &state.boat_classes);
+                        let _ = s.emit("boat-classes-update", &state.boat_classes);
+                        if laylines_changed {
+                            let _ = s.broadcast().emit("course-updated", &state.course);
+                            let _ = s.emit("course-updated", &state.course);
+                        }
+                    }
+                    result
+                };
+
+                match result {
+                    Ok(()) => {
+                        let verb = if event == "upsert-boat-class" { "Class saved" } else { "Class removed" };
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            format!("{verb}: {id}"), Some(json!({ "classId": id })), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("class-error", &json!({ "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── races of the session (create-race / select-race) ─────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        socket.on("list-races", move |s: SocketRef| {
+            let shared = shared.clone();
+            async move {
+                let state = shared.read().await;
+                let _ = s.emit("races-update", &json!({ "races": state.races, "activeRaceId": state.active_race_id }));
+            }
+        });
+    }
+    for event in ["create-race", "select-race"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
                     return;
                 }
-                
-                if let Some(node_id) = data["nodeId"].as_str() {
-                    let update = engine.write().await.jump_to_node(node_id);
-                    if let Some(upd) = update {
-                        let mut state = shared.write().await;
-                        state.current_sequence = Some(upd.current_sequence.clone());
-                        state.sequence_time_remaining = Some(upd.sequence_time_remaining);
-                        let _ = s.broadcast().emit("sequence-update", &upd);
-                        let _ = s.emit("sequence-update", &upd);
 
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let mut eng = engine.write().await;
+                let result = if eng.is_running() {
+                    Err(race_session::SessionError::SequenceRunning)
+                } else {
+                    let mut state = shared.write().await;
+                    let now = now_ms();
+                    let result = if event == "create-race" {
+                        let procedure = data.get("procedure").and_then(|p| serde_json::from_value::<ProcedureGraph>(p.clone()).ok());
+                        let name = data["name"].as_str().map(str::to_string);
+                        Ok(race_session::create(&mut state, name, procedure, now))
+                    } else {
+                        race_session::select(&mut state, data["raceId"].as_str().unwrap_or_default(), now)
+                    };
+                    if result.is_ok() {
+                        if let Some(graph) = &state.current_procedure {
+                            eng.load_procedure(graph.clone());
+                        }
+                        let _ = save_state(&state).await;
+                        let payload = json!({ "races": state.races, "activeRaceId": state.active_race_id });
+                        let _ = s.broadcast().emit("races-update", &payload);
+                        let _ = s.emit("races-update", &payload);
+                        let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                        boat_scope::emit_to_sender(&s, &state);
+                    }
+                    result
+                };
+                drop(eng);
+
+                // The race's own chain from here on
+                if result.is_ok() {
+                    audit.set_session(race_session::audit_session(&*shared.read().await)).await;
+                }
+
+                match result {
+                    Ok(race) => {
+                        let verb = if event == "create-race" { "Created" } else { "Selected" };
                         emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                            format!("Triggered procedure node: {node_id}"), None, false).await;
+                            format!("{verb} race: {}", race.name), Some(json!({ "raceId": race.id })), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("race-session-error", &json!({ "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── regattas hosted by this backend (create-event / select-event) ─────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        socket.on("list-events", move |s: SocketRef| {
+            let shared = shared.clone();
+            async move {
+                let state = shared.read().await;
+                let _ = s.emit("events-update", &json!({ "events": state.events, "activeEventId": state.active_event_id }));
+            }
+        });
+    }
+    for event in ["create-event", "select-event"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let class_engines = class_engines.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let class_engines = class_engines.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let text = |key: &str| data[key].as_str().filter(|v| !v.trim().is_empty()).map(str::to_string);
+                let mut eng = engine.write().await;
+                let running = eng.is_running() || !class_engines.read().await.is_empty();
+                let result = {
+                    let mut state = shared.write().await;
+                    let now = now_ms();
+                    let result = if event == "create-event" {
+                        Ok(regatta::create(&mut state, text("name"), text("venue"), text("startDate"), text("endDate"), now))
+                    } else if running {
+                        Err(regatta::EventError::SequenceRunning)
+                    } else {
+                        regatta::select(&mut state, data["eventId"].as_str().unwrap_or_default(), now).await
+                    };
+                    if result.is_ok() {
+                        if event == "select-event" {
+                            if let Some(graph) = &state.current_procedure {
+                                eng.load_procedure(graph.clone());
+                            }
+                            scoring::rescore(&mut state);
+                            Outlet::Socket(s.clone()).emit_state(&state);
+                        }
+                        let _ = save_state(&state).await;
+                        let payload = json!({ "events": state.events, "activeEventId": state.active_event_id });
+                        let _ = s.broadcast().emit("events-update", &payload);
+                        let _ = s.emit("events-update", &payload);
+                    }
+                    result
+                };
+                drop(eng);
+
+                // The incoming event brings its own custom roles and audit chain
+                if event == "select-event" && result.is_ok() {
+                    audit.set_session(race_session::audit_session(&*shared.read().await)).await;
+                    let key = roles::key(&*shared.read().await);
+                    match roles::load(&key).await {
+                        Ok(custom) => auth.set_custom_roles(custom).await,
+                        Err(e) => warn!("Roles: failed to load {key}: {e}"),
+                    }
+                }
+
+                match result {
+                    Ok(regatta) => {
+                        let verb = if event == "create-event" { "Created" } else { "Selected" };
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            format!("{verb} event: {}", regatta.name), Some(json!({ "eventId": regatta.id })), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("event-error", &json!({ "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── role administration ───────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("list-clients", move |s: SocketRef| {
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "list-clients").await {
+                    return;
+                }
+                let _ = s.emit("clients-update", &json!({ "clients": auth.clients().await, "customRoles": auth.custom_roles().await }));
+            }
+        });
+    }
+
+    for event in ["set-client-role", "define-role", "delete-role"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let name = data[if event == "set-client-role" { "role" } else { "name" }].as_str().unwrap_or_default().trim().to_string();
+                let result = match event {
+                    "set-client-role" => {
+                        let target = data["socketId"].as_str().unwrap_or_default();
+                        let previous_boat = auth.get_tracker_boat(target).await;
+                        let changed = if target == s.id.to_string() {
+                            Err(roles::RoleError::OwnRole)
+                        } else {
+                            auth.change_role(target, &name, data["boatId"].as_str()).await
+                        };
+                        changed.map(|previous| {
+                            let room = roles::client_room(target);
+                            let _ = s.within(room.clone()).leave(previous.clone());
+                            let _ = s.within(room.clone()).join(name.clone());
+                            if let Some(bid) = previous_boat {
+                                let _ = s.within(room.clone()).leave(boat_scope::boat_room(&bid));
+                            }
+                            if name == "tracker" {
+                                if let Some(bid) = data["boatId"].as_str() {
+                                    let _ = s.within(room.clone()).join(boat_scope::boat_room(bid));
+                                }
+                            }
+                            let _ = s.within(room).emit("role-changed", &json!({ "role": name }));
+                            format!("Client {target}: role {previous} → {name}")
+                        })
+                    }
+                    "define-role" => {
+                        let role = roles::CustomRole {
+                            name: name.clone(),
+                            events: serde_json::from_value(data["events"].clone()).unwrap_or_default(),
+                            description: data["description"].as_str().map(str::to_string),
+                        };
+                        let key = roles::key(&*shared.read().await);
+                        match auth.define_role(role).await {
+                            Ok(custom) => {
+                                if let Err(e) = roles::save(&key, &custom).await {
+                                    warn!("Roles: failed to save {key}: {e}");
+                                }
+                                Ok(format!("Defined role {name}"))
+                            }
+                            Err(e) => Err(e),
+                        }
+                    }
+                    _ => {
+                        let key = roles::key(&*shared.read().await);
+                        match auth.delete_role(&name).await {
+                            Ok(custom) => {
+                                if let Err(e) = roles::save(&key, &custom).await {
+                                    warn!("Roles: failed to save {key}: {e}");
+                                }
+                                Ok(format!("Deleted role {name}"))
+                            }
+                            Err(e) => Err(e),
+                        }
+                    }
+                };
+
+                match result {
+                    Ok(message) => {
+                        let payload = json!({ "clients": auth.clients().await, "customRoles": auth.custom_roles().await });
+                        let _ = s.to("director").emit("clients-update", &payload);
+                        let _ = s.emit("clients-update", &payload);
+                        if event != "set-client-role" {
+                            let payload = json!({ "customRoles": payload["customRoles"] });
+                            let _ = s.to("director").emit("roles-update", &payload);
+                            let _ = s.emit("roles-update", &payload);
+                        }
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(), message, Some(data), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("role-error", &json!({ "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── start-class-sequence (concurrent per-class starts) ────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let class_engines = class_engines.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("start-class-sequence", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let class_engines = class_engines.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "start-class-sequence").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "start-class-sequence", &data).await;
+
+                let class_id = match data["classId"].as_str() {
+                    Some(id) if !id.is_empty() => id.to_string(),
+                    _ => {
+                        warn!("start-class-sequence without classId");
+                        return;
+                    }
+                };
+
+                // Custom graph for this class, then a rule-pack preset, otherwise the standard RRS 26 sequence
+                let minutes = data["minutes"].as_u64().unwrap_or(5);
+                let prep_flag = data["prepFlag"].as_str().unwrap_or("P");
+                let graph = serde_json::from_value::<ProcedureGraph>(data["procedure"].clone())
+                    .unwrap_or_else(|_| match data["preset"].as_str().and_then(RulePack::parse) {
+                        Some(pack) => {
+                            let sounds = data["sounds"].as_str().map(SoundConvention::parse).unwrap_or_default();
+                            rule_packs::procedure(pack, minutes, prep_flag, sounds)
+                        }
+                        None => standard_procedure(minutes, prep_flag),
+                    });
+                let issues = procedure_validator::validate(&graph);
+                if !issues.is_empty() {
+                    warn!("Rejected procedure {} for class {class_id}: {} validation issue(s)", graph.id, issues.len());
+                    let _ = s.emit("procedure-validation", &json!({ "valid": false, "procedureId": graph.id, "classId": class_id, "issues": issues }));
+                    return;
+                }
+
+                let mut engines = class_engines.write().await;
+                let eng = engines.entry(class_id.clone()).or_insert_with(ProcedureEngine::new);
+                eng.load_procedure(graph);
+                let update = eng.start();
+                let status = eng.current_race_status();
+                drop(engines);
+
+                let status_before = shared.read().await.class_sequences.get(&class_id)
+                    .map(|c| c.status.clone())
+                    .unwrap_or_default();
+                audit_status_change(&audit, &status_before, &status, &format!("start-class-sequence {class_id}")).await;
+
+                {
+                    let mut state = shared.write().await;
+                    let class_state = state.class_sequences.entry(class_id.clone()).or_default();
+                    class_state.status = status;
+                    class_state.start_time = None;
+                    if let Some(upd) = &update {
+                        class_state.apply(upd);
                     }
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                }
+
+                if let Some(upd) = update {
+                    let payload = ClassSequenceUpdate { class_id: class_id.clone(), update: upd };
+                    let _ = s.broadcast().emit("class-sequence-update", &payload);
+                    let _ = s.emit("class-sequence-update", &payload);
+                }
+
+                emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
+                    format!("Started sequence for class {class_id}"), Some(json!({ "classId": class_id })), false).await;
+            }
+        });
+    }
+
+    // ── class-procedure-action (postpone / recall / resume / abandon a class) ─
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let class_engines = class_engines.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("class-procedure-action", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let class_engines = class_engines.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "class-procedure-action").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "class-procedure-action", &data).await;
+
+                let (Some(class_id), Some(action)) = (data["classId"].as_str(), data["action"].as_str()) else {
+                    warn!("class-procedure-action requires classId and action");
+                    return;
+                };
+                let out = Outlet::Socket(s.clone());
+                if action == "RESUME" {
+                    let held = shared.read().await.class_sequences.get(class_id).map(|c| c.status.clone());
+                    match held {
+                        Some(RaceStatus::Postponed) => resume_class(&shared, &class_engines, &audit, &out, class_id, RaceStatus::Postponed, "AP lowered").await,
+                        Some(RaceStatus::GeneralRecall) => resume_class(&shared, &class_engines, &audit, &out, class_id, RaceStatus::GeneralRecall, "1st Substitute lowered").await,
+                        _ => warn!("Class {class_id}: RESUME without a postponement or general recall"),
+                    }
+                    return;
+                }
+                let (new_status, flags) = match action {
+                    "POSTPONE" => (RaceStatus::Postponed, vec!["AP".to_string()]),
+                    "GENERAL_RECALL" => (RaceStatus::GeneralRecall, vec!["FIRST_SUB".to_string()]),
+                    "ABANDON" => (RaceStatus::Abandoned, vec!["N".to_string()]),
+                    "RESET" => (RaceStatus::Idle, vec![]),
+                    _ => {
+                        warn!("Unknown class procedure action: {action}");
+                        return;
+                    }
+                };
+
+                let status_before = {
+                    // Engines before state, as the tick loop takes them, so it sees
+                    // the stopped engine and the held status together
+                    let mut engines = class_engines.write().await;
+                    if let Some(eng) = engines.get_mut(class_id) {
+                        eng.stop();
+                    }
+                    let mut state = shared.write().await;
+                    let before = state.class_sequences.get(class_id).map(|c| c.status.clone()).unwrap_or_default();
+                    if new_status == RaceStatus::Idle {
+                        state.class_sequences.remove(class_id);
+                    } else {
+                        let class_state = state.class_sequences.entry(class_id.to_string()).or_default();
+                        class_state.status = new_status.clone();
+                        class_state.current_sequence = Some(SequenceInfo { event: action.to_string(), flags });
+                        class_state.sequence_time_remaining = None;
+                        class_state.waiting_for_trigger = false;
+                    }
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                    before
+                };
+                audit_status_change(&audit, &status_before, &new_status, &format!("{action} {class_id}")).await;
+
+                emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
+                    format!("Class {class_id}: {action}"), Some(json!({ "classId": class_id, "action": action })), false).await;
+
+                // The stopped engine is kept (see `run_class_engine_tick`); like the
+                // main sequence, the signal comes down after a minute unless changed
+                let reason = match new_status {
+                    RaceStatus::Postponed => "AP lowered",
+                    RaceStatus::GeneralRecall => "1st Substitute lowered",
+                    _ => return,
+                };
+                let (shared, class_engines, audit, class_id) = (shared.clone(), class_engines.clone(), audit.clone(), class_id.to_string());
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    resume_class(&shared, &class_engines, &audit, &out, &class_id, new_status, reason).await;
+                });
+            }
+        });
+    }
+
+    // ── set-prep-flag ─────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("set-prep-flag", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                audit_command(&audit, &auth, &s, "set-prep-flag", &data).await;
+
+                // Accept both bare string ("P") and object ({ flag: "P" })
+                let flag_str = data.as_str()
+                    .or_else(|| data["flag"].as_str())
+                    .unwrap_or("P");
+                let mut state = shared.write().await;
+                state.prep_flag = match flag_str {
+                    "I" => PrepFlag::I,
+                    "Z" => PrepFlag::Z,
+                    "U" => PrepFlag::U,
+                    "BLACK" => PrepFlag::Black,
+                    _ => PrepFlag::P,
+                };
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
+            }
+        });
+    }
+
+    // ── procedure-action (RRS Race Management) ────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("procedure-action", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "procedure-action").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "procedure-action", &data).await;
+                procedure_action(&shared, &engine, &audit, &Outlet::Socket(s.clone()), &data).await;
+            }
+        });
+    }
+
+    // ── save-procedure ────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("save-procedure", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "save-procedure").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "save-procedure", &data).await;
+                
+                match serde_json::from_value::<ProcedureGraph>(data) {
+                    Ok(graph) => {
+                        let issues = procedure_validator::validate(&graph);
+                        if !issues.is_empty() {
+                            warn!("Rejected procedure {}: {} validation issue(s)", graph.id, issues.len());
+                            let _ = s.emit("procedure-validation", &json!({ "valid": false, "procedureId": graph.id, "issues": issues }));
+                            return;
+                        }
+
+                        let mut eng = engine.write().await;
+                        eng.load_procedure(graph.clone());
+                        let update = eng.start();
+                        let status = eng.current_race_status();
+                        drop(eng);
+
+                        {
+                            let mut state = shared.write().await;
+                            state.status = status;
+                            state.current_procedure = Some(graph);
+                            if let Some(upd) = &update {
+                                state.current_sequence = Some(upd.current_sequence.clone());
+                                state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+                            }
+                            let _ = save_state(&state).await;
+                        }
+
+                        if let Some(upd) = update {
+                            let _ = s.broadcast().emit("sequence-update", &upd);
+                            let _ = s.emit("sequence-update", &upd);
+                        }
+                        let state = shared.read().await;
+                        let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                        boat_scope::emit_to_sender(&s, &state);
+
+                        emit_log(&shared, &s, LogCategory::Procedure, "Architect".to_string(),
+                            "Custom procedure deployed and started".to_string(), None, false).await;
+                    }
+                    Err(e) => warn!("Failed to parse procedure: {e}"),
+                }
+            }
+        });
+    }
+
+    // ── validate-procedure (Architect dry run, nothing is deployed) ──────────
+    {
+        let socket = socket.clone();
+        socket.on("validate-procedure", move |s: SocketRef, Data::<Value>(data)| {
+            async move {
+                let report = match serde_json::from_value::<ProcedureGraph>(data) {
+                    Ok(graph) => {
+                        let issues = procedure_validator::validate(&graph);
+                        json!({ "valid": issues.is_empty(), "procedureId": graph.id, "issues": issues })
+                    }
+                    Err(e) => json!({ "valid": false, "error": format!("Failed to parse procedure: {e}"), "issues": [] }),
+                };
+                let _ = s.emit("procedure-validation", &report);
+            }
+        });
+    }
+
+    // ── clock-sync (round trip for client countdown offset estimation) ───────
+    {
+        let socket = socket.clone();
+        socket.on("clock-sync", move |s: SocketRef, Data::<Value>(data)| {
+            async move {
+                let _ = s.emit("clock-sync", &json!({
+                    "clientTime": data["clientTime"],
+                    "serverTime": now_ms(),
+                }));
+            }
+        });
+    }
+
+    // ── subscribe-state-delta (patches instead of full state-update) ─────────
+    {
+        let socket = socket.clone();
+        let delta = delta.clone();
+        socket.on("subscribe-state-delta", move |s: SocketRef| {
+            let delta = delta.clone();
+            async move {
+                // The delta stream is the whole state
+                if boat_scope::is_scoped(&s) {
+                    return;
+                }
+                let _ = s.join(DELTA_ROOM);
+                let _ = s.emit("state-keyframe", &delta.keyframe().await);
+            }
+        });
+    }
+    {
+        let socket = socket.clone();
+        socket.on("unsubscribe-state-delta", move |s: SocketRef| async move {
+            let _ = s.leave(DELTA_ROOM);
+        });
+    }
+
+    // ── rehearsal (accelerated dry run on a private engine) ──────────────────
+    {
+        let socket = socket.clone();
+        let engine = engine.clone();
+        let rehearsals = rehearsals.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("start-rehearsal", move |s: SocketRef, Data::<Value>(data)| {
+            let engine = engine.clone();
+            let rehearsals = rehearsals.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                // Directors only; every rehearsal is a private engine ticking at 50 Hz
+                if !authorize_command(&audit, &auth, &s, "start-rehearsal").await {
+                    return;
+                }
+
+                // Custom graph, then a rule-pack preset, then the loaded procedure, otherwise the standard sequence
+                let minutes = data["minutes"].as_u64().unwrap_or(5);
+                let prep_flag = data["prepFlag"].as_str().unwrap_or("P");
+                let loaded = engine.read().await.get_graph().cloned();
+                let graph = match serde_json::from_value::<ProcedureGraph>(data["procedure"].clone()) {
+                    Ok(graph) => graph,
+                    Err(_) => match data["preset"].as_str().and_then(RulePack::parse) {
+                        Some(pack) => {
+                            let sounds = data["sounds"].as_str().map(SoundConvention::parse).unwrap_or_default();
+                            rule_packs::procedure(pack, minutes, prep_flag, sounds)
+                        }
+                        None => loaded.unwrap_or_else(|| standard_procedure(minutes, prep_flag)),
+                    },
+                };
+                let issues = procedure_validator::validate(&graph);
+                if !issues.is_empty() {
+                    let _ = s.emit("procedure-validation", &json!({ "valid": false, "procedureId": graph.id, "issues": issues }));
+                    return;
+                }
+
+                let speed = data["speed"].as_f64().unwrap_or(rehearsal::DEFAULT_SPEED).clamp(1.0, rehearsal::MAX_SPEED);
+                info!("Client {}: rehearsing {} at {speed}×", s.id, graph.id);
+                let mut eng = ProcedureEngine::new();
+                eng.set_speed(speed);
+                eng.load_procedure(graph);
+                let update = eng.start();
+                let events = eng.drain_events();
+                rehearsals.write().await.insert(s.id.to_string(), eng);
+
+                let _ = s.join(rehearsal::room(&s.id.to_string()));
+                let _ = s.emit("rehearsal-update", &RehearsalUpdate { speed, update, events, complete: false });
+            }
+        });
+    }
+    {
+        let socket = socket.clone();
+        let rehearsals = rehearsals.clone();
+        socket.on("resume-rehearsal", move |s: SocketRef| {
+            let rehearsals = rehearsals.clone();
+            async move {
+                let mut engines = rehearsals.write().await;
+                let Some(eng) = engines.get_mut(&s.id.to_string()) else { return };
+                let update = eng.resume_sequence();
+                let payload = RehearsalUpdate { speed: eng.speed(), update, events: eng.drain_events(), complete: false };
+                drop(engines);
+                let _ = s.emit("rehearsal-update", &payload);
+            }
+        });
+    }
+    {
+        let socket = socket.clone();
+        let rehearsals = rehearsals.clone();
+        socket.on("stop-rehearsal", move |s: SocketRef| {
+            let rehearsals = rehearsals.clone();
+            async move {
+                let Some(eng) = rehearsals.write().await.remove(&s.id.to_string()) else { return };
+                let _ = s.leave(rehearsal::room(&s.id.to_string()));
+                let _ = s.emit("rehearsal-update", &RehearsalUpdate { speed: eng.speed(), update: None, events: vec![], complete: true });
+            }
+        });
+    }
+
+    // ── procedure template library ────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        socket.on("list-procedure-templates", move |s: SocketRef| {
+            let shared = shared.clone();
+            async move {
+                let state = shared.read().await;
+                let _ = s.emit("procedure-templates", &procedure_templates::list(&state.procedure_templates));
+            }
+        });
+    }
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        socket.on("export-procedure-template", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            async move {
+                let id = data["id"].as_str().or_else(|| data.as_str()).unwrap_or_default();
+                let state = shared.read().await;
+                match procedure_templates::find(&state.procedure_templates, id) {
+                    Some(template) => { let _ = s.emit("procedure-template-export", &procedure_templates::export_document(&template)); }
+                    None => { let _ = s.emit("procedure-template-error", &json!({ "error": format!("Unknown procedure template: {id}") })); }
+                }
+            }
+        });
+    }
+    for event in ["save-procedure-template", "duplicate-procedure-template", "delete-procedure-template", "import-procedure-template"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let id = data["id"].as_str().unwrap_or_default();
+                let name = data["name"].as_str();
+                let mut state = shared.write().await;
+                let saved = &mut state.procedure_templates;
+                let result = match event {
+                    "save-procedure-template" => serde_json::from_value::<ProcedureTemplate>(data.clone())
+                        .map_err(|e| procedure_templates::TemplateError::InvalidDocument(e.to_string()))
+                        .and_then(|t| procedure_templates::save(saved, t)),
+                    "duplicate-procedure-template" => procedure_templates::duplicate(saved, id, name),
+                    "delete-procedure-template" => procedure_templates::delete(saved, id),
+                    _ => procedure_templates::import_document(saved, &data["document"], name),
+                };
+
+                let template = match result {
+                    Ok(t) => t,
+                    Err(e) => {
+                        warn!("{event} failed: {e}");
+                        let _ = s.emit("procedure-template-error", &json!({ "event": event, "error": e.to_string() }));
+                        return;
+                    }
+                };
+                let _ = save_state(&state).await;
+                let templates = procedure_templates::list(&state.procedure_templates);
+                let _ = s.broadcast().emit("procedure-templates", &templates);
+                let _ = s.emit("procedure-templates", &templates);
+                drop(state);
+
+                let verb = match event {
+                    "save-procedure-template" => "saved",
+                    "duplicate-procedure-template" => "created (duplicate)",
+                    "delete-procedure-template" => "deleted",
+                    _ => "imported",
+                };
+                emit_log(&shared, &s, LogCategory::Procedure, "Architect".to_string(),
+                    format!("Procedure template '{}' {verb}", template.name), Some(json!({ "id": template.id })), false).await;
+            }
+        });
+    }
+
+    // ── trigger-node ──────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("trigger-node", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "trigger-node").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "trigger-node", &data).await;
+                
+                if let Some(node_id) = data["nodeId"].as_str() {
+                    let update = engine.write().await.jump_to_node(node_id);
+                    if let Some(upd) = update {
+                        let mut state = shared.write().await;
+                        state.current_sequence = Some(upd.current_sequence.clone());
+                        state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+                        let _ = s.broadcast().emit("sequence-update", &upd);
+                        let _ = s.emit("sequence-update", &upd);
+
+                        emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
+                            format!("Triggered procedure node: {node_id}"), None, false).await;
+                    }
+                }
+            }
+        });
+    }
+
+    // ── mutate-future-node ───────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("mutate-future-node", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "mutate-future-node").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "mutate-future-node", &data).await;
+                
+                let node_id = match data["nodeId"].as_str() {
+                    Some(id) => id,
+                    None => return,
+                };
+                let new_duration = match data["duration"].as_f64() {
+                    Some(d) => d,
+                    None => return,
+                };
+
+                let mut eng = engine.write().await;
+                eng.update_node_duration(node_id, new_duration);
+                let update = eng.build_update();
+                let graph = eng.graph.clone();
+                drop(eng);
+
+                {
+                    let mut state = shared.write().await;
+                    if let Some(g) = graph {
+                        state.current_procedure = Some(g);
+                    }
+                    if let Some(upd) = &update {
+                        state.current_sequence = Some(upd.current_sequence.clone());
+                        state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+                    }
+                    let _ = save_state(&state).await;
+                }
+
+                if let Some(upd) = update {
+                    let _ = s.broadcast().emit("sequence-update", &upd);
+                    let _ = s.emit("sequence-update", &upd);
+                }
+                let state = shared.read().await;
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
+            }
+        });
+    }
+
+    // ── resume-sequence ───────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("resume-sequence", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "resume-sequence").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "resume-sequence", &data).await;
+                
+                let mut eng = engine.write().await;
+                let update = eng.resume_sequence();
+                let status = eng.current_race_status();
+                drop(eng);
+
+                if let Some(upd) = update {
+                    let status_before = shared.read().await.status.clone();
+                    audit_status_change(&audit, &status_before, &status, "resume-sequence").await;
+                    let mut state = shared.write().await;
+                    state.status = status;
+                    state.current_sequence = Some(upd.current_sequence.clone());
+                    state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+                    let _ = s.broadcast().emit("sequence-update", &upd);
+                    let _ = s.emit("sequence-update", &upd);
+
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+
+                    emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
+                        "Resumed sequence manually".to_string(), None, false).await;
+                }
+            }
+        });
+    }
+
+    // ── undo-transition (step back over the last manual transition) ──────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("undo-transition", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "undo-transition").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "undo-transition", &data).await;
+
+                let mut eng = engine.write().await;
+                let undone = eng.undo_transition();
+                let status = eng.current_race_status();
+                drop(eng);
+
+                let Some((from_node, upd)) = undone else {
+                    let _ = s.emit("undo-transition-error", &json!({ "error": "Nothing to undo" }));
+                    return;
+                };
+
+                let status_before = shared.read().await.status.clone();
+                audit_status_change(&audit, &status_before, &status, "undo-transition").await;
+                {
+                    let mut state = shared.write().await;
+                    state.status = status;
+                    state.current_sequence = Some(upd.current_sequence.clone());
+                    state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+                    state.current_node_id = Some(upd.current_node_id.clone());
+                    state.waiting_for_trigger = upd.waiting_for_trigger;
+                    state.action_label = upd.action_label.clone();
+                    state.is_post_trigger = upd.is_post_trigger;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                }
+                let _ = s.broadcast().emit("sequence-update", &upd);
+                let _ = s.emit("sequence-update", &upd);
+
+                emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
+                    format!("Undid transition: back from node {from_node} to {}", upd.current_node_id),
+                    Some(json!({ "fromNodeId": from_node, "toNodeId": upd.current_node_id })), false).await;
+            }
+        });
+    }
+
+    // ── pause-sequence / unpause-sequence (hold countdown, keep node timing) ─
+    for (event, pause) in [("pause-sequence", true), ("unpause-sequence", false)] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let engine = engine.clone();
+        let class_engines = class_engines.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let engine = engine.clone();
+            let class_engines = class_engines.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let toggle = |eng: &mut ProcedureEngine| if pause { eng.pause() } else { eng.unpause() };
+                let class_id = data["classId"].as_str().map(str::to_string);
+                let update = match &class_id {
+                    Some(class_id) => class_engines.write().await.get_mut(class_id).and_then(toggle),
+                    None => toggle(&mut *engine.write().await),
+                };
+                let Some(upd) = update else {
+                    warn!("{event}: no running sequence to {}", if pause { "pause" } else { "resume" });
+                    return;
+                };
+
+                let mut state = shared.write().await;
+                match &class_id {
+                    Some(class_id) => {
+                        state.class_sequences.entry(class_id.clone()).or_default().apply(&upd);
+                        let payload = ClassSequenceUpdate { class_id: class_id.clone(), update: upd };
+                        let _ = s.broadcast().emit("class-sequence-update", &payload);
+                        let _ = s.emit("class-sequence-update", &payload);
+                    }
+                    None => {
+                        state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+                        let _ = s.broadcast().emit("sequence-update", &upd);
+                        let _ = s.emit("sequence-update", &upd);
+                    }
+                }
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
+                drop(state);
+
+                let scope = class_id.as_deref().map(|c| format!(" (class {c})")).unwrap_or_default();
+                emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
+                    format!("Sequence {}{scope}", if pause { "paused" } else { "resumed" }), None, false).await;
+            }
+        });
+    }
+
+    // ── update-course ─────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("update-course", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "update-course").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "update-course", &data).await;
+                
+                match serde_json::from_value::<CourseState>(data.clone()) {
+                    Ok(course) => {
+                        let mut state = shared.write().await;
+                        state.course = course;
+                        laylines::refresh(&mut state);
+                        let bias_changed = line_bias::refresh(&mut state);
+                        let _ = save_state(&state).await;
+                        let _ = s.broadcast().emit("course-updated", &state.course);
+                        let _ = s.emit("course-updated", &state.course);
+                        if bias_changed {
+                            let _ = s.broadcast().emit("line-bias", &state.line_bias);
+                            let _ = s.emit("line-bias", &state.line_bias);
+                        }
+
+                        drop(state);
+                        emit_log(&shared, &s, LogCategory::Course, "Director".to_string(),
+                            "Course layout updated".to_string(), None, false).await;
+                    }
+                    Err(e) => error!("Failed to parse course payload from frontend! Error: {e} | Raw Data: {}", data),
+                }
+            }
+        });
+    }
+
+    // ── generate-course ───────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("generate-course", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "generate-course").await {
+                    return;
+                }
+                audit_command(&audit, &auth, &s, "generate-course", &data).await;
+
+                let spec = match serde_json::from_value::<CourseSpec>(data.clone()) {
+                    Ok(spec) => spec,
+                    Err(e) => {
+                        let _ = s.emit("course-error", &json!({ "error": format!("Invalid course spec: {e}") }));
+                        return;
+                    }
+                };
+                let mut state = shared.write().await;
+                if let Err(e) = course_templates::generate(&mut state, &spec) {
+                    let _ = s.emit("course-error", &json!({ "error": e.to_string() }));
+                    return;
+                }
+                laylines::refresh(&mut state);
+                line_bias::refresh(&mut state);
+                let _ = save_state(&state).await;
+                let _ = s.broadcast().emit("course-updated", &state.course);
+                let _ = s.emit("course-updated", &state.course);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
+
+                let marks = state.course.marks.len();
+                drop(state);
+                emit_log(&shared, &s, LogCategory::Course, "Director".to_string(),
+                    format!("{:?} course generated ({marks} marks)", spec.course_type),
+                    Some(data), false).await;
+            }
+        });
+    }
+
+    // ── update-course-boundary ────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("update-course-boundary", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "update-course-boundary").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "update-course-boundary", &data).await;
+                
+                if data.is_null() {
+                    let mut state = shared.write().await;
+                    state.course.course_boundary = None;
+                    let _ = save_state(&state).await;
+                    let _ = s.broadcast().emit("course-updated", &state.course);
+                    let _ = s.emit("course-updated", &state.course);
+
+                    drop(state);
+                    emit_log(&shared, &s, LogCategory::Course, "Director".to_string(),
+                        "Course boundary cleared".to_string(), None, false).await;
+                } else if let Ok(boundary) = serde_json::from_value::<Vec<LatLon>>(data.clone()) {
+                    let mut state = shared.write().await;
+                    state.course.course_boundary = Some(boundary);
+                    let _ = save_state(&state).await;
+                    let _ = s.broadcast().emit("course-updated", &state.course);
+                    let _ = s.emit("course-updated", &state.course);
+
+                    drop(state);
+                    emit_log(&shared, &s, LogCategory::Course, "Director".to_string(),
+                        "Course boundary redefined".to_string(), None, false).await;
+                } else {
+                    error!("Failed to parse course boundary from frontend! Raw Data: {}", data);
+                }
+            }
+        });
+    }
+
+    // ── update-wind ───────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("update-wind", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                audit_command(&audit, &auth, &s, "update-wind", &data).await;
+
+                match serde_json::from_value::<WindState>(data.clone()) {
+                    Ok(wind) => {
+                        let mut state = shared.write().await;
+                        let report = WeatherReport {
+                            timestamp: now_ms(),
+                            wind_direction: wind.direction,
+                            wind_speed: wind.speed,
+                            gusts: None,
+                            temperature: None,
+                            provider: WeatherProvider::Manual,
+                        };
+                        weather::record(&mut state, report);
+                        state.wind = wind;
+                        let laylines_changed = laylines::refresh(&mut state);
+                        let bias_changed = line_bias::refresh(&mut state);
+                        let _ = save_state(&state).await;
+                        // Broadcast both specific wind update and full state update for reliability
+                        let _ = s.broadcast().emit("wind-updated", &state.wind);
+                        let _ = s.emit("wind-updated", &state.wind);
+                        let _ = s.broadcast().emit("weather-updated", &state.weather);
+                        let _ = s.emit("weather-updated", &state.weather);
+                        if laylines_changed {
+                            let _ = s.broadcast().emit("course-updated", &state.course);
+                            let _ = s.emit("course-updated", &state.course);
+                        }
+                        if bias_changed {
+                            let _ = s.broadcast().emit("line-bias", &state.line_bias);
+                            let _ = s.emit("line-bias", &state.line_bias);
+                        }
+                        let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    }
+                    Err(e) => error!("Failed to parse wind payload from frontend! Error: {e} | Raw Data: {}", data),
+                }
+            }
+        });
+    }
+
+    // ── update-current ────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("update-current", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "update-current").await {
+                    return;
+                }
+                audit_command(&audit, &auth, &s, "update-current", &data).await;
+
+                let current = if data.is_null() {
+                    None
+                } else {
+                    match (data["set"].as_f64(), data["drift"].as_f64()) {
+                        (Some(set), Some(drift)) if drift >= 0.0 => Some(CurrentState {
+                            set: set.rem_euclid(360.0),
+                            drift,
+                            source: CurrentSource::Manual,
+                            updated_ms: now_ms(),
+                        }),
+                        _ => {
+                            let _ = s.emit("x-error", &json!({ "error": "update-current needs { set, drift } or null" }));
+                            return;
+                        }
+                    }
+                };
+                let message = match &current {
+                    Some(c) => format!("Current set to {:.0}° at {:.1} kn", c.set, c.drift),
+                    None => "Current cleared".to_string(),
+                };
+
+                let mut state = shared.write().await;
+                tide::set_current(&mut state, current);
+                let _ = save_state(&state).await;
+                let _ = s.broadcast().emit("current-updated", &state.current);
+                let _ = s.emit("current-updated", &state.current);
+                let _ = s.broadcast().emit("course-updated", &state.course);
+                let _ = s.emit("course-updated", &state.course);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                drop(state);
+
+                emit_log(&shared, &s, LogCategory::Course, "Director".to_string(), message, Some(data), false).await;
+            }
+        });
+    }
+
+    // ── update-default-location ───────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("update-default-location", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                audit_command(&audit, &auth, &s, "update-default-location", &data).await;
+
+                match serde_json::from_value::<DefaultLocation>(data.clone()) {
+                    Ok(loc) => {
+                        let mut state = shared.write().await;
+                        state.default_location = Some(loc);
+                        let _ = save_state(&state).await;
+                        info!("Default location saved");
+                    }
+                    Err(e) => error!("Failed to parse location payload from frontend! Error: {e} | Raw Data: {}", data),
+                }
+            }
+        });
+    }
+
+    // ── set-race-status ───────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("set-race-status", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                audit_command(&audit, &auth, &s, "set-race-status", &data).await;
+
+                if let Some(status_str) = data["status"].as_str() {
+                    let new_status = match status_str {
+                        "WARNING" => RaceStatus::Warning,
+                        "PREPARATORY" => RaceStatus::Preparatory,
+                        "ONE_MINUTE" => RaceStatus::OneMinute,
+                        "RACING" => RaceStatus::Racing,
+                        "FINISHED" => RaceStatus::Finished,
+                        "POSTPONED" => RaceStatus::Postponed,
+                        "INDIVIDUAL_RECALL" => RaceStatus::IndividualRecall,
+                        "GENERAL_RECALL" => RaceStatus::GeneralRecall,
+                        "ABANDONED" => RaceStatus::Abandoned,
+                        _ => RaceStatus::Idle,
+                    };
+                    let status_before = shared.read().await.status.clone();
+                    audit_status_change(&audit, &status_before, &new_status, "set-race-status").await;
+                    let mut state = shared.write().await;
+                    state.status = new_status;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                }
+            }
+        });
+    }
+
+    // ── issue-penalty (RRS + Appendix UF umpire signals) ──────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("issue-penalty", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "issue-penalty").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "issue-penalty", &data).await;
+                issue_penalty(&shared, &Outlet::Socket(s.clone()), &data).await;
+            }
+        });
+    }
+
+    // ── penalty lifecycle (update / withdraw) ─────────────────────────────────
+    for event in ["update-penalty", "withdraw-penalty"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+                let sid = s.id.to_string();
+                // Trackers act for their own boat only; every other permitted role is an official
+                let tracker_boat = match auth.get_role(&sid).await.as_deref() {
+                    Some("tracker") => auth.get_tracker_boat(&sid).await,
+                    _ => None,
+                };
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let edit = match serde_json::from_value::<penalties::PenaltyEdit>(data) {
+                    Ok(edit) => edit,
+                    Err(e) => {
+                        let _ = s.emit("penalty-error", &json!({ "error": format!("Invalid penalty edit: {e}") }));
+                        return;
+                    }
+                };
+                let now = now_ms();
+                let result = {
+                    let mut state = shared.write().await;
+                    let result = match event {
+                        "update-penalty" => penalties::update(&mut state, edit, tracker_boat.as_deref(), now),
+                        _ if tracker_boat.is_some() => Err(penalties::PenaltyError::NotAllowed),
+                        _ => penalties::withdraw(&mut state, &edit.penalty_id, edit.note, now),
+                    };
+                    if let Ok(update) = &result {
+                        let _ = save_state(&state).await;
+                        // Trackers get their penalties from the scoped feed
+                        let _ = s.broadcast().except(SCOPED_ROOM).emit("penalty-updated", update);
+                        let _ = s.emit("penalty-updated", update);
+                        if update.race_number.is_some() {
+                            let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                            boat_scope::emit_to_sender(&s, &state);
+                        }
+                    }
+                    result
+                };
+
+                match result {
+                    Ok(update) => {
+                        let p = &update.penalty;
+                        emit_log(&shared, &s, LogCategory::Jury, "Chief Umpire".to_string(),
+                            format!("Penalty {:?} on {} now {:?}", p.penalty_type, p.boat_id, p.status),
+                            Some(serde_json::to_value(&update).unwrap_or_default()), false).await;
+                    }
+                    Err(e) => {
+                        warn!("{event} rejected: {e}");
+                        let _ = s.emit("penalty-error", &json!({ "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── protests (file / schedule hearing / decide / withdraw) ───────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        socket.on("list-protests", move |s: SocketRef| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            async move {
+                let state = shared.read().await;
+                if !boat_scope::is_scoped(&s) {
+                    let _ = s.emit("protests-update", &state.protests);
+                    return;
+                }
+                let protests = match auth.get_tracker_boat(&s.id.to_string()).await {
+                    Some(boat_id) => boat_scope::protests(&state, &boat_id),
+                    None => Vec::new(),
+                };
+                let _ = s.emit("protests-update", &protests);
+            }
+        });
+    }
+    for event in ["file-protest", "schedule-hearing", "decide-protest", "withdraw-protest"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+                let sid = s.id.to_string();
+                let role = auth.get_role(&sid).await;
+                // Trackers act for their own boat only; every other permitted role is an official
+                let tracker_boat = match role.as_deref() {
+                    Some("tracker") => auth.get_tracker_boat(&sid).await,
+                    _ => None,
+                };
+                let official = tracker_boat.is_none();
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let id = data["protestId"].as_str().unwrap_or_default().to_string();
+                let now = now_ms();
+                let result = {
+                    let mut state = shared.write().await;
+                    let result = match event {
+                        "file-protest" => match serde_json::from_value::<protests::ProtestFiling>(data.clone()) {
+                            Ok(filing) => {
+                                // Competitors protest as their own boat
+                                let protestor = tracker_boat.clone()
+                                    .or_else(|| filing.protestor.clone())
+                                    .unwrap_or_else(|| "PC".to_string());
+                                protests::file(&mut state, filing, protestor, role.as_deref().unwrap_or("unknown"), now)
+                            }
+                            Err(e) => {
+                                let _ = s.emit("protest-error", &json!({ "error": format!("Invalid protest: {e}") }));
+                                return;
+                            }
+                        },
+                        "schedule-hearing" => protests::schedule(&mut state, &id, Hearing {
+                            at_ms: data["atMs"].as_i64().unwrap_or(now),
+                            location: data["location"].as_str().map(str::to_string),
+                        }),
+                        "decide-protest" => {
+                            let penalties = data["penalties"].as_array().map(|list| list.iter().filter_map(|p| Some(Penalty::new(
+                                p["boatId"].as_str()?.to_string(),
+                                serde_json::from_value::<PenaltyType>(p["type"].clone()).ok()?,
+                                now,
+                            ))).collect()).unwrap_or_default();
+                            protests::decide(&mut state, &id, ProtestDecision {
+                                outcome: data["outcome"].as_str().unwrap_or("Decided").to_string(),
+                                penalties,
+                                facts_found: data["factsFound"].as_str().map(str::to_string),
+                                decided_ms: now,
+                            })
+                        }
+                        _ => {
+                            // Competitors may only withdraw their own protests
+                            let own = state.protests.iter().any(|p| p.id == id && Some(&p.protestor) == tracker_boat.as_ref());
+                            if !official && !own {
+                                warn!("Unauthorized withdraw-protest of {id} by: {}", s.id);
+                                return;
+                            }
+                            protests::withdraw(&mut state, &id)
+                        }
+                    };
+                    if result.is_ok() {
+                        let _ = save_state(&state).await;
+                        // Trackers only see the protests they are party to
+                        let _ = s.broadcast().except(SCOPED_ROOM).emit("protests-update", &state.protests);
+                        match &tracker_boat {
+                            Some(boat_id) => { let _ = s.emit("protests-update", &boat_scope::protests(&state, boat_id)); }
+                            None => { let _ = s.emit("protests-update", &state.protests); }
+                        }
+                        if event == "decide-protest" {
+                            let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                            boat_scope::emit_to_sender(&s, &state);
+                        }
+                    }
+                    result
+                };
+
+                let protest = match result {
+                    Ok(protest) => protest,
+                    Err(e) => {
+                        warn!("{event} rejected: {e}");
+                        let _ = s.emit("protest-error", &json!({ "error": e.to_string() }));
+                        return;
+                    }
+                };
+                let message = match protest.status {
+                    ProtestStatus::Filed => format!("Protest {} filed: {} v {} (rules {})",
+                        protest.id, protest.protestor, protest.protestees.join(", "), protest.rules.join(", ")),
+                    ProtestStatus::Scheduled => format!("Hearing scheduled for protest {}", protest.id),
+                    ProtestStatus::Decided => format!("Protest {} decided: {}", protest.id,
+                        protest.decision.as_ref().map(|d| d.outcome.as_str()).unwrap_or_default()),
+                    ProtestStatus::Withdrawn => format!("Protest {} withdrawn", protest.id),
+                };
+                emit_log(&shared, &s, LogCategory::Jury, "Jury".to_string(), message,
+                    Some(serde_json::to_value(&protest).unwrap_or_default()), false).await;
+            }
+        });
+    }
+
+    // ── messaging (RC ↔ jury ↔ boats) ────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        socket.on("list-messages", move |s: SocketRef| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            async move {
+                let sid = s.id.to_string();
+                let state = shared.read().await;
+                let messages = match auth.get_role(&sid).await.as_deref() {
+                    Some("director") | Some("jury") => state.messages.clone(),
+                    Some("tracker") => match auth.get_tracker_boat(&sid).await {
+                        Some(boat_id) => messaging::visible_to_boat(&state, &boat_id),
+                        None => Vec::new(),
+                    },
+                    _ => Vec::new(),
+                };
+                let _ = s.emit("messages-update", &messages);
+            }
+        });
+    }
+    for event in ["send-message", "ack-message"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let sid = s.id.to_string();
+                let role = auth.get_role(&sid).await.unwrap_or_default();
+                let boat = match role.as_str() {
+                    "tracker" => auth.get_tracker_boat(&sid).await,
+                    _ => None,
+                };
+                let now = now_ms();
+                let result = {
+                    let mut state = shared.write().await;
+                    let result = if event == "send-message" {
+                        let to = match serde_json::from_value::<MessageTarget>(data["to"].clone()) {
+                            Ok(to) => to,
+                            Err(e) => {
+                                let _ = s.emit("message-error", &json!({ "error": format!("Invalid recipient: {e}") }));
+                                return;
+                            }
+                        };
+                        let from = match &boat {
+                            Some(boat_id) => MessageTarget::Boat(boat_id.clone()),
+                            None => MessageTarget::Role(role.clone()),
+                        };
+                        messaging::send(&mut state, from, to, data["body"].as_str().unwrap_or_default(), now)
+                    } else {
+                        messaging::ack(&mut state, data["messageId"].as_str().unwrap_or_default(), &role, boat.as_deref(), now)
+                    };
+                    if result.is_ok() {
+                        let _ = save_state(&state).await;
+                    }
+                    result
+                };
+
+                match result {
+                    Ok(message) if event == "send-message" => {
+                        let _ = s.to(messaging::room(&message.to)).emit("message", &message);
+                        let _ = s.emit("message-sent", &message);
+                        let source = boat.unwrap_or(role);
+                        emit_log(&shared, &s, LogCategory::System, source,
+                            format!("Message to {}: {}", messaging::room(&message.to), message.body),
+                            Some(json!({ "messageId": message.id })), false).await;
+                    }
+                    Ok(message) => {
+                        let rooms = vec!["director".to_string(), "jury".to_string(), messaging::room(&message.from)];
+                        let _ = s.to(rooms).emit("message-acked", &message);
+                        let _ = s.emit("message-acked", &message);
+                    }
+                    Err(e) => {
+                        let _ = s.emit("message-error", &json!({ "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── update-log (Jury/Director Annotations) ────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("update-log", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                audit_command(&audit, &auth, &s, "update-log", &data).await;
+
+                if let Ok(updated_log) = serde_json::from_value::<crate::state::LogEntry>(data) {
+                    let mut state = shared.write().await;
+                    if let Some(log) = state.logs.iter_mut().find(|l| l.id == updated_log.id) {
+                        log.protest_flagged = updated_log.protest_flagged;
+                        log.jury_notes = updated_log.jury_notes.clone();
+                        info!("Log {} updated with Protest/Notes", log.id);
+                        
+                        let _ = s.broadcast().except(SCOPED_ROOM).emit("log-updated", &log);
+                        let _ = s.emit("log-updated", &log);
+                        let log = log.clone();
+                        drop(state);
+                        log_store::append(&log).await;
+                    }
+                }
+            }
+        });
+    }
+
+    // ── get-logs (paged history beyond the in-memory window) ─────────────────
+    {
+        let socket = socket.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("get-logs", move |s: SocketRef, Data::<Value>(data)| {
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "get-logs").await {
+                    return;
+                }
+                let query = if data.is_null() {
+                    LogQuery::default()
+                } else {
+                    match serde_json::from_value::<LogQuery>(data) {
+                        Ok(q) => q,
+                        Err(e) => {
+                            let _ = s.emit("x-error", &json!({ "error": format!("Invalid log query: {e}") }));
+                            return;
+                        }
+                    }
+                };
+                let _ = s.emit("logs-page", &log_store::query(&query).await);
+            }
+        });
+    }
+
+    // ── get-track (a boat's stored track for a time range) ───────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("get-track", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "get-track").await {
+                    return;
+                }
+                let query = match serde_json::from_value::<TrackQuery>(data) {
+                    Ok(q) => q,
+                    Err(e) => {
+                        let _ = s.emit("x-error", &json!({ "error": format!("Invalid track query: {e}") }));
+                        return;
+                    }
+                };
+                let race_id = match &query.race_id {
+                    Some(id) => id.clone(),
+                    None => track_store::race_key(&*shared.read().await),
+                };
+                let _ = s.emit("track", &track_store::query(&query, race_id).await);
+            }
+        });
+    }
+
+    // ── set-mark-designation ──────────────────────────────────────────────────
+    // Signed command to a UWB node (see `control_plane`)
+    {
+        let socket = socket.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        let downlink = downlink.clone();
+        socket.on("set-mark-designation", move |s: SocketRef, Data::<Value>(data)| {
+            let auth = auth.clone();
+            let audit = audit.clone();
+            let downlink = downlink.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "set-mark-designation").await {
+                    return;
+                }
+                let node_id = data["nodeId"].as_u64().and_then(|id| u32::try_from(id).ok());
+                let designation = data["designation"].as_u64().filter(|d| *d <= 3);
+                let (Some(node_id), Some(designation)) = (node_id, designation) else {
+                    let _ = s.emit("designation-error", &json!({ "error": "nodeId and designation (0-3) are required" }));
+                    return;
+                };
+
+                audit_command(&audit, &auth, &s, "set-mark-designation", &data).await;
+
+                let command = ControlCommand::SetDesignation { designation: NodeDesignation::from_u8(designation as u8) };
+                match downlink.send(node_id, command).await {
+                    Ok(seq) => {
+                        let _ = s.emit("designation-sent", &json!({ "nodeId": node_id, "designation": designation, "seq": seq }));
+                    }
+                    Err(e) => {
+                        warn!("set-mark-designation: {e}");
+                        let _ = s.emit("designation-error", &json!({ "nodeId": node_id, "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── kill-tracker ──────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("kill-tracker", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "kill-tracker").await {
+                    return;
+                }
+                audit_command(&audit, &auth, &s, "kill-tracker", &data).await;
+
+                let id = match data.as_str() {
+                    Some(id) => id.to_string(),
+                    None => match data["id"].as_str() {
+                        Some(id) => id.to_string(),
+                        None => return,
+                    },
+                };
+                let permanent = data["permanent"].as_bool().unwrap_or(false);
+                info!("Killing tracker: {id}{}", if permanent { " (permanent)" } else { "" });
+                audit.log_auth_event(AuthEventKind::TrackerKilled, &auth.identity(&s.id.to_string()).await, json!({
+                    "boatId": id,
+                    "permanent": permanent,
+                    "reason": data["reason"],
+                })).await;
+
+                let duration_ms = (!permanent).then(blacklist::kill_duration_ms);
+                {
+                    let mut state = shared.write().await;
+                    let reason = data["reason"].as_str().map(str::to_string);
+                    if blacklist::add(&mut state, BlacklistKind::Boat, &id, reason, duration_ms, now_ms()).is_err() {
+                        return;
+                    }
+                    state.boats.remove(&id);
+                    let _ = save_state(&state).await;
+                }
+
+                let _ = s.broadcast().emit("kill-simulation", &json!({ "id": id }));
+                let _ = s.emit("kill-simulation", &json!({ "id": id }));
+
+                let state = shared.read().await;
+                let _ = s.broadcast().emit("blacklist-update", &state.blacklist);
+                let _ = s.emit("blacklist-update", &state.blacklist);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
+            }
+        });
+    }
+
+    // ── list-blacklist ────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("list-blacklist", move |s: SocketRef| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "list-blacklist").await {
+                    return;
+                }
+                let mut state = shared.write().await;
+                blacklist::prune(&mut state, now_ms());
+                let _ = s.emit("blacklist-update", &state.blacklist);
+            }
+        });
+    }
+
+    // ── blacklist-add / blacklist-remove ──────────────────────────────────────
+    for event in ["blacklist-add", "blacklist-remove"] {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let kind = match data["kind"].as_str() {
+                    Some("DEVICE") => BlacklistKind::Device,
+                    _ => BlacklistKind::Boat,
+                };
+                let id = data["id"].as_str().unwrap_or_default();
+                let now = now_ms();
+                let result = {
+                    let mut state = shared.write().await;
+                    let result = if event == "blacklist-add" {
+                        let duration_ms = match data["permanent"].as_bool().unwrap_or(false) {
+                            true => None,
+                            false => Some(data["durationSecs"].as_i64().filter(|d| *d > 0)
+                                .map(|d| d * 1000)
+                                .unwrap_or_else(blacklist::kill_duration_ms)),
+                        };
+                        let reason = data["reason"].as_str().map(str::to_string);
+                        blacklist::add(&mut state, kind, id, reason, duration_ms, now).map(|_| ())
+                    } else {
+                        blacklist::remove(&mut state, kind, id, now)
+                    };
+                    if result.is_ok() {
+                        if event == "blacklist-add" && kind == BlacklistKind::Boat {
+                            state.boats.remove(id);
+                        }
+                        let _ = save_state(&state).await;
+                    }
+                    result
+                };
+                if let Err(e) = result {
+                    let _ = s.emit("blacklist-error", &json!({ "error": e.to_string() }));
+                    return;
+                }
+
+                if event == "blacklist-add" && kind == BlacklistKind::Boat {
+                    let _ = s.broadcast().emit("kill-simulation", &json!({ "id": id }));
+                    let _ = s.emit("kill-simulation", &json!({ "id": id }));
                 }
+
+                let state = shared.read().await;
+                let _ = s.broadcast().emit("blacklist-update", &state.blacklist);
+                let _ = s.emit("blacklist-update", &state.blacklist);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
+                drop(state);
+
+                let verb = if event == "blacklist-add" { "blacklisted" } else { "removed from blacklist" };
+                emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                    format!("{kind:?} {id} {verb}"), Some(json!({ "kind": kind, "id": id })), false).await;
             }
         });
     }
 
-    // ── mutate-future-node ───────────────────────────────────────────────────
+    // ── clear-fleet ───────────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        let engine = engine.clone();
         let auth = auth.clone();
-        socket.on("mutate-future-node", move |s: SocketRef, Data::<Value>(data)| {
+        let audit = audit.clone();
+        socket.on("clear-fleet", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
-            let engine = engine.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized mutate-future-node attempt by: {}", s.id);
-                    return;
-                }
-                
-                let node_id = match data["nodeId"].as_str() {
-                    Some(id) => id,
-                    None => return,
-                };
-                let new_duration = match data["duration"].as_f64() {
-                    Some(d) => d,
-                    None => return,
-                };
-
-                let mut eng = engine.write().await;
-                eng.update_node_duration(node_id, new_duration);
-                let update = eng.build_update();
-                let graph = eng.graph.clone();
-                drop(eng);
+                audit_command(&audit, &auth, &s, "clear-fleet", &data).await;
 
+                info!("Clearing all fleet trackers");
                 {
                     let mut state = shared.write().await;
-                    if let Some(g) = graph {
-                        state.current_procedure = Some(g);
-                    }
-                    if let Some(upd) = &update {
-                        state.current_sequence = Some(upd.current_sequence.clone());
-                        state.sequence_time_remaining = Some(upd.sequence_time_remaining);
-                    }
+                    state.boats.clear();
                     let _ = save_state(&state).await;
                 }
+                let _ = s.broadcast().emit("kill-simulation", &json!({ "id": "all" }));
+                let _ = s.emit("kill-simulation", &json!({ "id": "all" }));
 
-                if let Some(upd) = update {
-                    let _ = s.broadcast().emit("sequence-update", &upd);
-                    let _ = s.emit("sequence-update", &upd);
-                }
                 let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
             }
         });
     }
 
-    // ── resume-sequence ───────────────────────────────────────────────────────
-    {
+    // ── state snapshots (take / list / restore) ──────────────────────────────
+    for event in ["take-snapshot", "list-snapshots", "restore-snapshot"] {
         let socket = socket.clone();
         let shared = shared.clone();
-        let engine = engine.clone();
         let auth = auth.clone();
-        socket.on("resume-sequence", move |s: SocketRef, Data::<Value>(_data)| {
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
-            let engine = engine.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized sequence resume attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, event).await {
                     return;
                 }
-                
-                let mut eng = engine.write().await;
-                let update = eng.resume_sequence();
-                let status = eng.current_race_status();
-                drop(eng);
-
-                if let Some(upd) = update {
-                    let mut state = shared.write().await;
-                    state.status = status;
-                    state.current_sequence = Some(upd.current_sequence.clone());
-                    state.sequence_time_remaining = Some(upd.sequence_time_remaining);
-                    let _ = s.broadcast().emit("sequence-update", &upd);
-                    let _ = s.emit("sequence-update", &upd);
-
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
-
-                    emit_log(&shared, &s, LogCategory::Procedure, "Director".to_string(),
-                        "Resumed sequence manually".to_string(), None, false).await;
+                let keep = SnapshotConfig::default().keep;
+
+                match event {
+                    "list-snapshots" => {}
+                    "take-snapshot" => {
+                        audit_command(&audit, &auth, &s, event, &data).await;
+                        let name = data["name"].as_str().filter(|n| !n.trim().is_empty()).unwrap_or("manual");
+                        let taken = snapshots::take(&*shared.read().await, name, false, keep).await;
+                        match taken {
+                            Ok(info) => emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                                format!("Snapshot taken: {}", info.name), Some(json!({ "snapshotId": info.id })), false).await,
+                            Err(e) => {
+                                let _ = s.emit("snapshot-error", &json!({ "error": e.to_string() }));
+                                return;
+                            }
+                        }
+                    }
+                    _ => {
+                        audit_command(&audit, &auth, &s, event, &data).await;
+                        let id = data["id"].as_str().unwrap_or_default();
+                        let snapshot = match snapshots::load(id).await {
+                            Ok(snapshot) => snapshot,
+                            Err(e) => {
+                                let _ = s.emit("snapshot-error", &json!({ "error": e.to_string() }));
+                                return;
+                            }
+                        };
+                        {
+                            let mut state = shared.write().await;
+                            if let Err(e) = snapshots::take(&state, "pre-restore", false, keep).await {
+                                warn!("Failed to snapshot before restore: {e}");
+                                let _ = s.emit("snapshot-error", &json!({ "error": format!("Could not save the current state first: {e}") }));
+                                return;
+                            }
+                            snapshots::restore_into(&mut state, snapshot);
+                            scoring::rescore(&mut state);
+                            let _ = save_state(&state).await;
+                            Outlet::Socket(s.clone()).emit_state(&state);
+                        }
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            format!("Restored snapshot {id}"), Some(json!({ "snapshotId": id })), true).await;
+                    }
                 }
+                let _ = s.emit("snapshots", &snapshots::list().await);
             }
         });
     }
 
-    // ── update-course ─────────────────────────────────────────────────────────
+    // ── register-team ─────────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
-        socket.on("update-course", move |s: SocketRef, Data::<Value>(data)| {
+        let audit = audit.clone();
+        socket.on("register-team", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized course update attempt by: {}", s.id);
-                    return;
-                }
-                
-                match serde_json::from_value::<CourseState>(data.clone()) {
-                    Ok(course) => {
+                audit_command(&audit, &auth, &s, "register-team", &data).await;
+
+                if let Ok(team) = serde_json::from_value::<crate::state::Team>(data.clone()) {
+                    {
                         let mut state = shared.write().await;
-                        state.course = course;
+                        state.teams.insert(team.id.clone(), team);
                         let _ = save_state(&state).await;
-                        let _ = s.broadcast().emit("course-updated", &state.course);
-                        let _ = s.emit("course-updated", &state.course);
-
-                        drop(state);
-                        emit_log(&shared, &s, LogCategory::Course, "Director".to_string(),
-                            "Course layout updated".to_string(), None, false).await;
                     }
-                    Err(e) => error!("Failed to parse course payload from frontend! Error: {e} | Raw Data: {}", data),
+                    let state = shared.read().await;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                } else {
+                    warn!("Failed to parse register-team payload: {}", data);
                 }
             }
         });
     }
 
-    // ── update-course-boundary ────────────────────────────────────────────────
+    // ── delete-team ───────────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
-        socket.on("update-course-boundary", move |s: SocketRef, Data::<Value>(data)| {
+        let audit = audit.clone();
+        socket.on("delete-team", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized boundary update attempt by: {}", s.id);
-                    return;
-                }
-                
-                if data.is_null() {
-                    let mut state = shared.write().await;
-                    state.course.course_boundary = None;
-                    let _ = save_state(&state).await;
-                    let _ = s.broadcast().emit("course-updated", &state.course);
-                    let _ = s.emit("course-updated", &state.course);
-
-                    drop(state);
-                    emit_log(&shared, &s, LogCategory::Course, "Director".to_string(),
-                        "Course boundary cleared".to_string(), None, false).await;
-                } else if let Ok(boundary) = serde_json::from_value::<Vec<LatLon>>(data.clone()) {
-                    let mut state = shared.write().await;
-                    state.course.course_boundary = Some(boundary);
-                    let _ = save_state(&state).await;
-                    let _ = s.broadcast().emit("course-updated", &state.course);
-                    let _ = s.emit("course-updated", &state.course);
+                audit_command(&audit, &auth, &s, "delete-team", &data).await;
 
-                    drop(state);
-                    emit_log(&shared, &s, LogCategory::Course, "Director".to_string(),
-                        "Course boundary redefined".to_string(), None, false).await;
-                } else {
-                    error!("Failed to parse course boundary from frontend! Raw Data: {}", data);
+                if let Some(team_id) = data.as_str() {
+                    {
+                        let mut state = shared.write().await;
+                        state.teams.remove(team_id);
+                        // Also remove pairings for that team
+                        state.pairings.retain(|p| p.team_id != team_id);
+                        let _ = save_state(&state).await;
+                    }
+                    let state = shared.read().await;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
                 }
             }
         });
     }
 
-    // ── update-wind ───────────────────────────────────────────────────────────
+    // ── register-flight ───────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("update-wind", move |s: SocketRef, Data::<Value>(data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("register-flight", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                match serde_json::from_value::<WindState>(data.clone()) {
-                    Ok(wind) => {
+                audit_command(&audit, &auth, &s, "register-flight", &data).await;
+
+                if let Ok(flight) = serde_json::from_value::<crate::state::Flight>(data.clone()) {
+                    {
                         let mut state = shared.write().await;
-                        state.wind = wind;
+                        state.flights.insert(flight.id.clone(), flight);
                         let _ = save_state(&state).await;
-                        // Broadcast both specific wind update and full state update for reliability
-                        let _ = s.broadcast().emit("wind-updated", &state.wind);
-                        let _ = s.emit("wind-updated", &state.wind);
-                        let _ = s.broadcast().emit("state-update", &*state);
                     }
-                    Err(e) => error!("Failed to parse wind payload from frontend! Error: {e} | Raw Data: {}", data),
+                    let state = shared.read().await;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                } else {
+                    warn!("Failed to parse register-flight payload: {}", data);
                 }
             }
         });
     }
 
-    // ── update-default-location ───────────────────────────────────────────────
+    // ── update-pairings ───────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("update-default-location", move |_s: SocketRef, Data::<Value>(data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("update-pairings", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                match serde_json::from_value::<DefaultLocation>(data.clone()) {
-                    Ok(loc) => {
+                audit_command(&audit, &auth, &s, "update-pairings", &data).await;
+
+                if let Ok(pairings) = serde_json::from_value::<Vec<crate::state::Pairing>>(data.clone()) {
+                    {
                         let mut state = shared.write().await;
-                        state.default_location = Some(loc);
+                        state.pairings = pairings;
                         let _ = save_state(&state).await;
-                        info!("Default location saved");
                     }
-                    Err(e) => error!("Failed to parse location payload from frontend! Error: {e} | Raw Data: {}", data),
+                    let state = shared.read().await;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                } else {
+                    warn!("Failed to parse update-pairings payload: {}", data);
                 }
             }
         });
     }
 
-    // ── set-race-status ───────────────────────────────────────────────────────
-    {
+    // ── flight edits (delete / swap boats / status / resails / breakdowns) ───
+    for event in [
+        "delete-flight", "swap-boats", "set-flight-status", "insert-resail", "create-resail",
+        "substitute-boat", "decide-redress",
+    ] {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("set-race-status", move |s: SocketRef, Data::<Value>(data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if let Some(status_str) = data["status"].as_str() {
-                    let new_status = match status_str {
-                        "WARNING" => RaceStatus::Warning,
-                        "PREPARATORY" => RaceStatus::Preparatory,
-                        "ONE_MINUTE" => RaceStatus::OneMinute,
-                        "RACING" => RaceStatus::Racing,
-                        "FINISHED" => RaceStatus::Finished,
-                        "POSTPONED" => RaceStatus::Postponed,
-                        "INDIVIDUAL_RECALL" => RaceStatus::IndividualRecall,
-                        "GENERAL_RECALL" => RaceStatus::GeneralRecall,
-                        "ABANDONED" => RaceStatus::Abandoned,
-                        _ => RaceStatus::Idle,
-                    };
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let flight_id = data["flightId"].as_str().unwrap_or_default();
+                let result = {
                     let mut state = shared.write().await;
-                    state.status = new_status;
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
+                    let result = match event {
+                        "delete-flight" => flight_schedule::delete_flight(&mut state, flight_id),
+                        "swap-boats" => flight_schedule::swap_boats(
+                            &mut state,
+                            flight_id,
+                            data["teamA"].as_str().unwrap_or_default(),
+                            data["teamB"].as_str().unwrap_or_default(),
+                        ),
+                        "set-flight-status" => match serde_json::from_value::<FlightStatus>(data["status"].clone()) {
+                            Ok(status) => flight_schedule::set_status(&mut state, flight_id, status),
+                            Err(e) => {
+                                let _ = s.emit("flight-error", &json!({ "error": format!("Invalid flight status: {e}") }));
+                                return;
+                            }
+                        },
+                        "create-resail" => {
+                            let flight_number = match &data["flightNumber"] {
+                                Value::Null => None,
+                                n => match n.as_u64().and_then(|n| u32::try_from(n).ok()) {
+                                    Some(n) => Some(n),
+                                    None => {
+                                        let _ = s.emit("flight-error", &json!({ "error": format!("Invalid flight number: {n}") }));
+                                        return;
+                                    }
+                                },
+                            };
+                            flight_schedule::create_resail(&mut state, flight_id, flight_number)
+                        }
+                        "substitute-boat" => flight_schedule::substitute_boat(
+                            &mut state,
+                            data["boatId"].as_str().unwrap_or_default(),
+                            data["substituteBoatId"].as_str().unwrap_or_default(),
+                            Some(flight_id).filter(|f| !f.is_empty()),
+                            data["reason"].as_str().unwrap_or("Boat breakdown"),
+                            now_ms(),
+                        ),
+                        "decide-redress" => match serde_json::from_value::<RedressStatus>(data["status"].clone()) {
+                            Ok(status) if status != RedressStatus::Pending => flight_schedule::decide_redress(
+                                &mut state,
+                                data["redressId"].as_str().unwrap_or_default(),
+                                status,
+                            ),
+                            _ => {
+                                let _ = s.emit("flight-error", &json!({ "error": "Redress status must be GRANTED or DENIED" }));
+                                return;
+                            }
+                        },
+                        _ => flight_schedule::insert_resail(&mut state, flight_id),
+                    };
+                    // Abandoned flights and redress change the league table and the knockout
+                    let result = result.map(|delta| {
+                        league::rescore(&mut state);
+                        let stages = knockout::update(&mut state).then(|| json!({ "stages": state.knockout }));
+                        (delta, json!({ "standings": state.league_standings, "results": state.league_results }), stages)
+                    });
+                    if result.is_ok() {
+                        let _ = save_state(&state).await;
+                    }
+                    result
+                };
+
+                match result {
+                    Ok((delta, standings, stages)) => {
+                        let _ = s.broadcast().emit("flight-delta", &delta);
+                        let _ = s.emit("flight-delta", &delta);
+                        let _ = s.broadcast().emit("league-standings", &standings);
+                        let _ = s.emit("league-standings", &standings);
+                        if let Some(stages) = stages {
+                            let _ = s.broadcast().emit("knockout", &stages);
+                            let _ = s.emit("knockout", &stages);
+                        }
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            format!("Schedule edited: {event}"), Some(data), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("flight-error", &json!({ "error": e.to_string() }));
+                    }
                 }
             }
         });
     }
 
-    // ── issue-penalty (RRS + Appendix UF umpire signals) ──────────────────────
+    // ── set-active-flight ─────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("issue-penalty", move |s: SocketRef, Data::<Value>(data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("set-active-flight", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                let boat_id = data["boatId"].as_str().unwrap_or("").to_string();
-                let penalty_type_str = data["type"].as_str().unwrap_or("UMPIRE_PENALTY");
-                let penalty_type = match penalty_type_str {
-                    "OCS" => PenaltyType::Ocs,
-                    "DSQ" => PenaltyType::Dsq,
-                    "DNF" => PenaltyType::Dnf,
-                    "DNS" => PenaltyType::Dns,
-                    "TLE" => PenaltyType::Tle,
-                    "TURN_360" => PenaltyType::Turn360,
-                    "UMPIRE_NO_ACTION" => PenaltyType::UmpireNoAction,
-                    "UMPIRE_DSQ" => PenaltyType::UmpireDsq,
-                    _ => PenaltyType::UmpirePenalty,
-                };
-
-                let penalty = Penalty {
-                    boat_id: boat_id.clone(),
-                    penalty_type: penalty_type.clone(),
-                    timestamp: data["timestamp"].as_i64().unwrap_or_else(now_ms),
-                };
-                info!("Penalty: {:?} on {}", penalty.penalty_type, penalty.boat_id);
+                audit_command(&audit, &auth, &s, "set-active-flight", &data).await;
 
-                // Determine umpire signal flags + sounds
-                let (signal, flag, sounds) = match &penalty.penalty_type {
-                    PenaltyType::UmpireNoAction => ("Umpire: No penalty", "GREEN_WHITE", "1 long"),
-                    PenaltyType::UmpirePenalty | PenaltyType::Turn360 => ("Umpire: Penalty imposed", "RED", "1 long"),
-                    PenaltyType::UmpireDsq => ("Umpire: DSQ — leave course", "BLACK_UMPIRE", "1 long"),
-                    _ => ("Penalty", "", ""),
+                // No strict role check — any authenticated director can set the active flight
+                // Accept both bare string (flight id) and null/empty (to clear)
+                let flight_id = if data.is_null() || data.as_str().map(|s| s.is_empty()).unwrap_or(false) {
+                    None
+                } else {
+                    data.as_str().map(|s| s.to_string())
                 };
-
+                
                 {
                     let mut state = shared.write().await;
-                    state.penalties.push(penalty.clone());
+                    state.active_flight_id = flight_id;
+                    let _ = save_state(&state).await;
                 }
-                let _ = s.broadcast().emit("penalty-issued", &penalty);
-                let _ = s.emit("penalty-issued", &penalty);
-
-                emit_log(&shared, &s, LogCategory::Jury, "Chief Umpire".to_string(),
-                    format!("{}: {} on {}", signal, penalty_type_str, boat_id),
-                    Some(json!({ "boatId": boat_id, "type": penalty_type_str, "flag": flag, "sounds": sounds })),
-                    false).await;
+                
+                let state = shared.read().await;
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
             }
         });
     }
 
-    // ── update-log (Jury/Director Annotations) ────────────────────────────────
+    // ── set-teams ─────────────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("update-log", move |s: SocketRef, Data::<Value>(data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("set-teams", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if let Ok(updated_log) = serde_json::from_value::<crate::state::LogEntry>(data) {
-                    let mut state = shared.write().await;
-                    if let Some(log) = state.logs.iter_mut().find(|l| l.id == updated_log.id) {
-                        log.protest_flagged = updated_log.protest_flagged;
-                        log.jury_notes = updated_log.jury_notes.clone();
-                        info!("Log {} updated with Protest/Notes", log.id);
-                        
-                        let _ = s.broadcast().emit("log-updated", &log);
-                        let _ = s.emit("log-updated", &log);
+                audit_command(&audit, &auth, &s, "set-teams", &data).await;
+
+                info!("📥 Received set-teams event with data: {:?}", data);
+                if let Ok(teams_vec) = serde_json::from_value::<Vec<crate::state::Team>>(data.clone()) {
+                    info!("✅ Successfully parsed {} teams", teams_vec.len());
+                    {
+                        let mut state = shared.write().await;
+                        state.teams.clear();
+                        for team in teams_vec {
+                            state.teams.insert(team.id.clone(), team);
+                        }
+                        let _ = save_state(&state).await;
                     }
+                    let state = shared.read().await;
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
+                    info!("📡 Broadcasted state-update with new teams.");
+                } else {
+                    warn!("❌ Failed to parse set-teams payload: {}", data);
                 }
             }
         });
     }
 
-    // ── kill-tracker ──────────────────────────────────────────────────────────
+    // ── league results & standings ───────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        let dead_boats = dead_boats.clone();
-        socket.on("kill-tracker", move |s: SocketRef, Data::<Value>(data)| {
+        socket.on("get-league-standings", move |s: SocketRef| {
             let shared = shared.clone();
-            let dead_boats = dead_boats.clone();
             async move {
-                let id = match data.as_str() {
-                    Some(id) => id.to_string(),
-                    None => match data["id"].as_str() {
-                        Some(id) => id.to_string(),
-                        None => return,
-                    },
-                };
-                info!("Killing tracker: {id}");
-
-                dead_boats.write().await.insert(id.clone());
-
-                {
-                    let mut state = shared.write().await;
-                    state.boats.remove(&id);
-                }
-
-                let _ = s.broadcast().emit("kill-simulation", &json!({ "id": id }));
-                let _ = s.emit("kill-simulation", &json!({ "id": id }));
-
                 let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
-
-                // Auto-expire blacklist entry after 30s
-                let dead_boats_clone = dead_boats.clone();
-                let id_clone = id.clone();
-                tokio::spawn(async move {
-                    tokio::time::sleep(Duration::from_secs(30)).await;
-                    dead_boats_clone.write().await.remove(&id_clone);
-                });
+                let _ = s.emit("league-standings", &json!({ "standings": state.league_standings, "results": state.league_results }));
             }
         });
     }
-
-    // ── clear-fleet ───────────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("clear-fleet", move |s: SocketRef, Data::<Value>(_data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("record-flight-results", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                info!("Clearing all fleet trackers");
-                {
-                    let mut state = shared.write().await;
-                    state.boats.clear();
-                    let _ = save_state(&state).await;
+                if !authorize_command(&audit, &auth, &s, "record-flight-results").await {
+                    return;
                 }
-                let _ = s.broadcast().emit("kill-simulation", &json!({ "id": "all" }));
-                let _ = s.emit("kill-simulation", &json!({ "id": "all" }));
 
-                let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
+                audit_command(&audit, &auth, &s, "record-flight-results", &data).await;
+
+                let order: Vec<String> = serde_json::from_value(data["order"].clone()).unwrap_or_default();
+                let codes: HashMap<String, crate::state::ScoreCode> = match data.get("codes") {
+                    None | Some(Value::Null) => HashMap::new(),
+                    Some(codes) => match serde_json::from_value(codes.clone()) {
+                        Ok(codes) => codes,
+                        Err(e) => {
+                            let _ = s.emit("league-error", &json!({ "error": format!("Invalid scoring codes: {e}") }));
+                            return;
+                        }
+                    },
+                };
+                let flight_id = data["flightId"].as_str().unwrap_or_default();
+                let race_index = data["raceIndex"].as_u64().unwrap_or(0) as u32;
+
+                let result = {
+                    let mut state = shared.write().await;
+                    let result = league::record_results(&mut state, flight_id, race_index, &order, &codes, now_ms());
+                    if result.is_ok() {
+                        let advanced = knockout::update(&mut state);
+                        let _ = save_state(&state).await;
+                        let payload = json!({ "standings": state.league_standings, "results": state.league_results });
+                        let _ = s.broadcast().emit("league-standings", &payload);
+                        let _ = s.emit("league-standings", &payload);
+                        if advanced {
+                            let stages = json!({ "stages": state.knockout });
+                            let _ = s.broadcast().emit("knockout", &stages);
+                            let _ = s.emit("knockout", &stages);
+                        }
+                    }
+                    result
+                };
+
+                match result {
+                    Ok(results) => {
+                        emit_log(&shared, &s, LogCategory::Jury, "Director".to_string(),
+                            format!("League results entered: {} teams, race {}", results.len(), race_index + 1),
+                            Some(data), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("league-error", &json!({ "error": e.to_string() }));
+                    }
+                }
             }
         });
     }
 
-    // ── register-team ─────────────────────────────────────────────────────────
+    // ── knockout rounds ──────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("register-team", move |s: SocketRef, Data::<Value>(data)| {
+        socket.on("get-knockout", move |s: SocketRef| {
             let shared = shared.clone();
             async move {
-                if let Ok(team) = serde_json::from_value::<crate::state::Team>(data.clone()) {
-                    {
-                        let mut state = shared.write().await;
-                        state.teams.insert(team.id.clone(), team);
-                        let _ = save_state(&state).await;
-                    }
-                    let state = shared.read().await;
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
-                } else {
-                    warn!("Failed to parse register-team payload: {}", data);
-                }
+                let state = shared.read().await;
+                let _ = s.emit("knockout", &json!({ "stages": state.knockout }));
             }
         });
     }
-
-    // ── delete-team ───────────────────────────────────────────────────────────
-    {
+    for event in ["generate-knockout", "reset-knockout"] {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("delete-team", move |s: SocketRef, Data::<Value>(data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on(event, move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if let Some(team_id) = data.as_str() {
-                    {
-                        let mut state = shared.write().await;
-                        state.teams.remove(team_id);
-                        // Also remove pairings for that team
-                        state.pairings.retain(|p| p.team_id != team_id);
+                if !authorize_command(&audit, &auth, &s, event).await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, event, &data).await;
+
+                let result = {
+                    let mut state = shared.write().await;
+                    let result = match event {
+                        "generate-knockout" => match serde_json::from_value::<KnockoutRound>(data["round"].clone()) {
+                            Ok(round) => {
+                                let boats = data["boats"].as_u64()
+                                    .or_else(|| state.fleet_settings.as_ref().map(|f| f.provided_boats_count as u64).filter(|n| *n > 0))
+                                    .unwrap_or(6) as usize;
+                                knockout::generate(
+                                    &mut state,
+                                    round,
+                                    data["teamsPerMatch"].as_u64().map(|n| n as usize),
+                                    data["advancePerMatch"].as_u64().map(|n| n as usize),
+                                    boats,
+                                    now_ms(),
+                                )
+                            }
+                            Err(e) => {
+                                let _ = s.emit("knockout-error", &json!({ "error": format!("Invalid knockout round: {e}") }));
+                                return;
+                            }
+                        },
+                        _ => {
+                            let delta = knockout::reset(&mut state);
+                            league::rescore(&mut state);
+                            Ok(delta)
+                        }
+                    };
+                    if result.is_ok() {
                         let _ = save_state(&state).await;
                     }
-                    let state = shared.read().await;
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
+                    result.map(|delta| (delta, json!({ "stages": state.knockout })))
+                };
+
+                match result {
+                    Ok((delta, stages)) => {
+                        let _ = s.broadcast().emit("flight-delta", &delta);
+                        let _ = s.emit("flight-delta", &delta);
+                        let _ = s.broadcast().emit("knockout", &stages);
+                        let _ = s.emit("knockout", &stages);
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            format!("Knockout edited: {event}"), Some(data), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("knockout-error", &json!({ "error": e.to_string() }));
+                    }
                 }
             }
         });
     }
 
-    // ── register-flight ───────────────────────────────────────────────────────
+    // ── umpires & support boats ──────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("register-flight", move |s: SocketRef, Data::<Value>(data)| {
+        socket.on("get-officials", move |s: SocketRef| {
             let shared = shared.clone();
             async move {
-                if let Ok(flight) = serde_json::from_value::<crate::state::Flight>(data.clone()) {
-                    {
-                        let mut state = shared.write().await;
-                        state.flights.insert(flight.id.clone(), flight);
-                        let _ = save_state(&state).await;
-                    }
-                    let state = shared.read().await;
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
-                } else {
-                    warn!("Failed to parse register-flight payload: {}", data);
-                }
+                let state = shared.read().await;
+                let _ = s.emit("officials", &json!({ "officials": state.officials }));
             }
         });
     }
-
-    // ── update-pairings ───────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("update-pairings", move |s: SocketRef, Data::<Value>(data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("set-officials", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if let Ok(pairings) = serde_json::from_value::<Vec<crate::state::Pairing>>(data.clone()) {
-                    {
-                        let mut state = shared.write().await;
-                        state.pairings = pairings;
-                        let _ = save_state(&state).await;
+                if !authorize_command(&audit, &auth, &s, "set-officials").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "set-officials", &data).await;
+
+                let roster: Vec<crate::state::Official> = match serde_json::from_value(data["officials"].clone()) {
+                    Ok(roster) => roster,
+                    Err(e) => {
+                        let _ = s.emit("officials-error", &json!({ "error": format!("Invalid officials: {e}") }));
+                        return;
                     }
-                    let state = shared.read().await;
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
-                } else {
-                    warn!("Failed to parse update-pairings payload: {}", data);
+                };
+                let mut state = shared.write().await;
+                if let Err(e) = officials::set_roster(&mut state, roster) {
+                    let _ = s.emit("officials-error", &json!({ "error": e.to_string() }));
+                    return;
                 }
+                let _ = save_state(&state).await;
+                let payload = json!({ "officials": state.officials });
+                let _ = s.broadcast().emit("officials", &payload);
+                let _ = s.emit("officials", &payload);
             }
         });
     }
-
-    // ── set-active-flight ─────────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
-        socket.on("set-active-flight", move |s: SocketRef, Data::<Value>(data)| {
+        let audit = audit.clone();
+        socket.on("assign-officials", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                // No strict role check — any authenticated director can set the active flight
-                // Accept both bare string (flight id) and null/empty (to clear)
-                let flight_id = if data.is_null() || data.as_str().map(|s| s.is_empty()).unwrap_or(false) {
-                    None
-                } else {
-                    data.as_str().map(|s| s.to_string())
-                };
-                
-                {
+                if !authorize_command(&audit, &auth, &s, "assign-officials").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "assign-officials", &data).await;
+
+                let umpire_pairs = data["umpirePairs"].as_u64().unwrap_or(1) as usize;
+                let support_boats = data["supportBoats"].as_u64().unwrap_or(1) as usize;
+                let result = {
                     let mut state = shared.write().await;
-                    state.active_flight_id = flight_id;
-                    let _ = save_state(&state).await;
+                    let result = officials::assign(&mut state, umpire_pairs, support_boats);
+                    if result.is_ok() {
+                        let _ = save_state(&state).await;
+                    }
+                    result
+                };
+
+                match result {
+                    Ok(delta) => {
+                        let _ = s.broadcast().emit("flight-delta", &delta);
+                        let _ = s.emit("flight-delta", &delta);
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            format!("Officials assigned to {} flights", delta.flights.len()), Some(data), false).await;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("officials-error", &json!({ "error": e.to_string() }));
+                    }
                 }
-                
-                let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
             }
         });
     }
 
-    // ── set-teams ─────────────────────────────────────────────────────────────
+    // ── schedule constraints ─────────────────────────────────────────────────
     {
         let socket = socket.clone();
         let shared = shared.clone();
-        socket.on("set-teams", move |s: SocketRef, Data::<Value>(data)| {
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("set-schedule-constraints", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                info!("📥 Received set-teams event with data: {:?}", data);
-                if let Ok(teams_vec) = serde_json::from_value::<Vec<crate::state::Team>>(data.clone()) {
-                    info!("✅ Successfully parsed {} teams", teams_vec.len());
-                    {
-                        let mut state = shared.write().await;
-                        state.teams.clear();
-                        for team in teams_vec {
-                            state.teams.insert(team.id.clone(), team);
-                        }
-                        let _ = save_state(&state).await;
+                if !authorize_command(&audit, &auth, &s, "set-schedule-constraints").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "set-schedule-constraints", &data).await;
+
+                let constraints: crate::state::ScheduleConstraints = match serde_json::from_value(data.clone()) {
+                    Ok(constraints) => constraints,
+                    Err(e) => {
+                        let _ = s.emit("flight-error", &json!({ "error": format!("Invalid schedule constraints: {e}") }));
+                        return;
                     }
-                    let state = shared.read().await;
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
-                    info!("📡 Broadcasted state-update with new teams.");
-                } else {
-                    warn!("❌ Failed to parse set-teams payload: {}", data);
+                };
+                if let Some(u) = constraints.unavailable.iter().find(|u| u.from_flight == 0 || u.from_flight > u.to_flight) {
+                    let _ = s.emit("flight-error", &json!({
+                        "error": format!("Invalid unavailability for team {}: flights {} to {}", u.team_id, u.from_flight, u.to_flight),
+                    }));
+                    return;
                 }
+
+                let mut state = shared.write().await;
+                state.schedule_constraints = constraints;
+                let _ = save_state(&state).await;
+                let payload = json!({ "constraints": state.schedule_constraints });
+                let _ = s.broadcast().emit("schedule-constraints", &payload);
+                let _ = s.emit("schedule-constraints", &payload);
             }
         });
     }
@@ -1471,19 +4334,47 @@ pub async fn on_connect(
     {
         let socket = socket.clone();
         let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("generate-flights", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
+                audit_command(&audit, &auth, &s, "generate-flights", &data).await;
+
                 let flight_count = data["flightCount"].as_u64().unwrap_or(15) as u32;
                 let boats = data["boats"].as_u64().unwrap_or(6) as u32;
+                // Opponent/boat balancing unless the plain rotation is asked for
+                let balanced = data["balanced"].as_bool().unwrap_or(true);
                 
                 info!("📥 Received generate-flights event: {} flights, {} boats", flight_count, boats);
                 
-                let (flights, pairings) = {
+                let generated = {
+                    use crate::flight_engine::FlightEngine;
                     let state = shared.read().await;
-                    let teams: Vec<crate::state::Team> = state.teams.values().cloned().collect();
+                    let mut teams: Vec<crate::state::Team> = state.teams.values().cloned().collect();
+                    teams.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
                     info!("📊 Engine generating from {} teams", teams.len());
-                    crate::flight_engine::FlightEngine::generate_rotation_schedule(teams, boats, flight_count)
+                    let constraints = &state.schedule_constraints;
+                    if balanced {
+                        FlightEngine::generate_balanced_schedule(teams, boats, flight_count, constraints)
+                    } else {
+                        FlightEngine::generate_rotation_schedule(teams.clone(), boats, flight_count, constraints)
+                            .map(|(flights, pairings)| {
+                                let fairness = FlightEngine::fairness_report(&teams, boats, &pairings);
+                                (flights, pairings, fairness)
+                            })
+                    }
+                };
+                let (flights, pairings, fairness) = match generated {
+                    Ok(generated) => generated,
+                    Err(e) => {
+                        warn!("❌ Flight generation aborted: {e}");
+                        let crate::flight_engine::ScheduleError::Infeasible(conflicts) = &e;
+                        let _ = s.emit("flight-error", &json!({ "error": e.to_string(), "conflicts": conflicts }));
+                        return;
+                    }
                 };
                 
                 if flights.is_empty() {
@@ -1504,15 +4395,25 @@ pub async fn on_connect(
                         state.flights.insert(f.id.clone(), f);
                     }
                     state.pairings = pairings;
+                    // Knockout rounds sail flights of the old schedule
+                    state.knockout.clear();
+                    league::rescore(&mut state);
                     
                     let _ = save_state(&state).await;
                 }
                 
                 let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
+                let _ = s.emit("flight-fairness", &fairness);
                 
-                info!("Generated new fair rotation schedule spanning {} flights.", state.flights.len());
+                info!(
+                    "Generated new {} schedule spanning {} flights (pair meetings {}–{}, boat usage {}–{}).",
+                    if balanced { "balanced" } else { "rotation" },
+                    state.flights.len(),
+                    fairness.pair_meetings.min, fairness.pair_meetings.max,
+                    fairness.boat_usage.min, fairness.boat_usage.max,
+                );
             }
         });
     }
@@ -1522,15 +4423,18 @@ pub async fn on_connect(
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
-        socket.on("commit-race-results", move |s: SocketRef, _data: Data<Value>| {
+        let audit = audit.clone();
+        socket.on("commit-race-results", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized commit-race-results attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, "commit-race-results").await {
                     return;
                 }
 
+                audit_command(&audit, &auth, &s, "commit-race-results", &data).await;
+
                 {
                     let mut state = shared.write().await;
                     
@@ -1585,8 +4489,8 @@ pub async fn on_connect(
                         }
                     }
 
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
                 }
 
                 emit_log(&shared, &s, LogCategory::System, "Director".to_string(), "Committed race results".to_string(), None, false).await;
@@ -1598,9 +4502,15 @@ pub async fn on_connect(
     {
         let socket = socket.clone();
         let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("update-fleet-settings", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
             async move {
+                audit_command(&audit, &auth, &s, "update-fleet-settings", &data).await;
+
                 if let Ok(settings) = serde_json::from_value::<crate::state::FleetSettings>(data.clone()) {
                     {
                         let mut state = shared.write().await;
@@ -1608,8 +4518,8 @@ pub async fn on_connect(
                         let _ = save_state(&state).await;
                     }
                     let state = shared.read().await;
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
                 } else {
                     warn!("Failed to parse update-fleet-settings payload: {}", data);
                 }
@@ -1622,14 +4532,17 @@ pub async fn on_connect(
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("move-buoy", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized move-buoy attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, "move-buoy").await {
                     return;
                 }
+
+                audit_command(&audit, &auth, &s, "move-buoy", &data).await;
                 
                 let id = data["id"].as_str().unwrap_or("");
                 let lat = data["lat"].as_f64().unwrap_or(0.0);
@@ -1658,11 +4571,13 @@ pub async fn on_connect(
                             layline_direction: 0.0,
                         });
                     }
+                    laylines::refresh(&mut state);
+                    line_bias::refresh(&mut state);
                 }
                 
                 let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
                 let _ = save_state(&state).await;
             }
         });
@@ -1673,14 +4588,17 @@ pub async fn on_connect(
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("override-marks", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized override-marks attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, "override-marks").await {
                     return;
                 }
+
+                audit_command(&audit, &auth, &s, "override-marks", &data).await;
                 
                 if let Some(marks_array) = data.as_array() {
                     let mut parsed_marks = Vec::new();
@@ -1726,13 +4644,15 @@ pub async fn on_connect(
                     {
                         let mut state = shared.write().await;
                         state.course.marks = parsed_marks;
+                        laylines::refresh(&mut state);
+                        line_bias::refresh(&mut state);
                     }
                     
                     let state = shared.read().await;
                     let _ = s.broadcast().emit("course-updated", &state.course);
                     let _ = s.emit("course-updated", &state.course);
-                    let _ = s.broadcast().emit("state-update", &*state);
-                    let _ = s.emit("state-update", &*state);
+                    let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                    boat_scope::emit_to_sender(&s, &state);
                     let _ = save_state(&state).await;
                 }
             }
@@ -1744,14 +4664,17 @@ pub async fn on_connect(
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("update-buoy-config", move |s: SocketRef, Data::<Value>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized update-buoy-config attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, "update-buoy-config").await {
                     return;
                 }
+
+                audit_command(&audit, &auth, &s, "update-buoy-config", &data).await;
                 
                 let id = data["id"].as_str().unwrap_or("");
                 {
@@ -1787,8 +4710,8 @@ pub async fn on_connect(
                 }
                 
                 let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
                 let _ = save_state(&state).await;
             }
         });
@@ -1799,14 +4722,17 @@ pub async fn on_connect(
         let socket = socket.clone();
         let shared = shared.clone();
         let auth = auth.clone();
+        let audit = audit.clone();
         socket.on("set-boundary", move |s: SocketRef, Data::<Vec<Value>>(data)| {
             let shared = shared.clone();
             let auth = auth.clone();
+            let audit = audit.clone();
             async move {
-                if auth.get_role(&s.id.to_string()).await.as_deref() != Some("director") {
-                    warn!("Unauthorized set-boundary attempt by: {}", s.id);
+                if !authorize_command(&audit, &auth, &s, "set-boundary").await {
                     return;
                 }
+
+                audit_command(&audit, &auth, &s, "set-boundary", &json!(data)).await;
                 
                 let points = data.iter().filter_map(|v| {
                     Some(crate::state::LatLon {
@@ -1821,13 +4747,144 @@ pub async fn on_connect(
                 }
                 
                 let state = shared.read().await;
-                let _ = s.broadcast().emit("state-update", &*state);
-                let _ = s.emit("state-update", &*state);
+                let _ = s.broadcast().except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                boat_scope::emit_to_sender(&s, &state);
                 let _ = save_state(&state).await;
             }
         });
     }
 
+    // ── verify-audit ──────────────────────────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("verify-audit", move |s: SocketRef, Data::<Value>(_data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "verify-audit").await {
+                    return;
+                }
+
+                match audit.verify_file().await {
+                    Ok(reports) => {
+                        let blocks: u64 = reports.iter().map(|r| r.blocks_checked).sum();
+                        let broken: Vec<String> = reports.iter()
+                            .filter(|r| !r.valid)
+                            .map(|r| r.session_id.clone().unwrap_or_else(|| "legacy".to_string()))
+                            .collect();
+                        let message = if broken.is_empty() {
+                            format!("Audit chains verified — {} sessions, {} blocks intact", reports.len(), blocks)
+                        } else {
+                            format!("Audit chain BROKEN in session(s): {}", broken.join(", "))
+                        };
+                        let _ = s.emit("audit-verification", &reports);
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            message, serde_json::to_value(&reports).ok(), false).await;
+                    }
+                    Err(e) => {
+                        error!("Audit verification failed to read log: {e}");
+                        let _ = s.emit("audit-verification", &json!({ "valid": false, "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── merge-node-chains (microSD import) ────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let shared = shared.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("merge-node-chains", move |s: SocketRef, Data::<Value>(data)| {
+            let shared = shared.clone();
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "merge-node-chains").await {
+                    return;
+                }
+
+                audit_command(&audit, &auth, &s, "merge-node-chains", &data).await;
+
+                // Only card copies in the audit uploads dir, never an arbitrary path
+                let audit_dir = audit.dir().await;
+                let mut node_files = Vec::new();
+                for name in data["files"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+                    match crate::node_chains::upload_file(&audit_dir, name).await {
+                        Some(path) => node_files.push(path),
+                        None => {
+                            warn!("merge-node-chains: rejected file {name:?}, not in the uploads dir");
+                            let _ = s.emit("node-chain-report", &json!({ "consistent": false, "error": format!("{name} is not a file in the uploads dir") }));
+                            return;
+                        }
+                    }
+                }
+                let hub_files = audit.files_for(data["sessionId"].as_str()).await;
+
+                match crate::node_chains::merge_node_chains(&hub_files, &node_files, &audit_dir).await {
+                    Ok(report) => {
+                        let message = if report.consistent {
+                            format!("Node chains consistent with hub — {} node(s) checked", report.nodes.len())
+                        } else {
+                            "Node chains DISAGREE with hub chain — see report".to_string()
+                        };
+                        let _ = s.emit("node-chain-report", &report);
+                        emit_log(&shared, &s, LogCategory::System, "Director".to_string(),
+                            message, serde_json::to_value(&report).ok(), false).await;
+                    }
+                    Err(e) => {
+                        error!("Node chain merge failed: {e}");
+                        let _ = s.emit("node-chain-report", &json!({ "consistent": false, "error": e.to_string() }));
+                    }
+                }
+            }
+        });
+    }
+
+    // ── protest-replay (jury scrubbing) ───────────────────────────────────────
+    {
+        let socket = socket.clone();
+        let auth = auth.clone();
+        let audit = audit.clone();
+        socket.on("protest-replay", move |s: SocketRef, Data::<Value>(data)| {
+            let auth = auth.clone();
+            let audit = audit.clone();
+            async move {
+                if !authorize_command(&audit, &auth, &s, "protest-replay").await {
+                    return;
+                }
+                let role = auth.get_role(&s.id.to_string()).await;
+
+                let query = match serde_json::from_value::<ReplayQuery>(data.clone()) {
+                    Ok(q) => q,
+                    Err(e) => {
+                        warn!("Failed to parse protest-replay query: {e}");
+                        return;
+                    }
+                };
+                audit.log_protest_replay(role.as_deref().unwrap_or("unknown"), &data).await;
+
+                let engine = ProtestReplayEngine::new(audit.files_for(query.session_id.as_deref()).await);
+                match engine.replay(&query).await {
+                    Ok(report) => {
+                        // A frame request returns only the instant being scrubbed to
+                        if let Some(at_ms) = data["atMs"].as_u64() {
+                            let _ = s.emit("protest-replay-frame", &report.frame_at(at_ms));
+                        } else {
+                            let _ = s.emit("protest-replay-result", &report);
+                        }
+                    }
+                    Err(e) => error!("Protest replay failed to read audit log: {e}"),
+                }
+            }
+        });
+    }
+
     // ── signal (WebRTC relay) ─────────────────────────────────────────────────
     {
         let socket = socket.clone();
@@ -0,0 +1,156 @@
+//! # highlights
+//!
+//! Timestamped markers of the moments worth a replay, so post-race editors can
+//! jump straight to them in the recording. The auto-director adds them as it
+//! sees them:
+//!
+//! - `GUN` — the start signal
+//! - `OCS` — a boat called over the line at the start
+//! - `LEAD_CHANGE` — a new boat ranked first while racing
+//! - `MARK_ROUNDING` — a boat rounding a mark (correctly)
+//! - `INCIDENT` — a new crossing, mark overlap or penalty (see `incidents`)
+//!
+//! Each is `highlight { id, kind, timestamp, boats, label, raceId? }` to everyone
+//! and kept in `state.highlights` (the newest `MAX_HIGHLIGHTS`), with the active
+//! race at the time.
+//!
+//! - `GET /highlights?race=<raceId>` — the list as JSON, oldest first
+//! - `GET /highlights.csv?race=<raceId>` — `Time, Epoch ms, Race, Kind, Boats,
+//!   Label`, times in UTC as the recordings use them
+
+use std::collections::HashSet;
+
+use chrono::{TimeZone, Utc};
+
+use crate::entries;
+use crate::incidents::{Incident, IncidentKind};
+use crate::state::{Highlight, HighlightKind, RaceState, RaceStatus};
+
+/// Highlights kept; the oldest go first
+pub const MAX_HIGHLIGHTS: usize = 5000;
+
+/// Sail number of a boat where it has an entry, else its id.
+fn sail(state: &RaceState, boat_id: &str) -> String {
+    entries::resolve(state, boat_id).map_or_else(|| boat_id.to_string(), |e| e.sail_number.clone())
+}
+
+fn highlight(state: &RaceState, kind: HighlightKind, boats: Vec<String>, label: String, timestamp: i64) -> Highlight {
+    Highlight {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind,
+        timestamp,
+        boats,
+        label,
+        race_id: state.active_race_id.clone(),
+    }
+}
+
+pub fn gun(state: &RaceState, timestamp: i64) -> Highlight {
+    highlight(state, HighlightKind::Gun, Vec::new(), "Start gun".to_string(), timestamp)
+}
+
+pub fn incident(state: &RaceState, incident: &Incident, timestamp: i64) -> Highlight {
+    let sails = incident.boats.iter().map(|b| sail(state, b)).collect::<Vec<_>>().join(" / ");
+    let label = match incident.kind {
+        IncidentKind::Crossing => format!("Close crossing {sails}"),
+        IncidentKind::MarkOverlap => format!("Overlap at {} {sails}", incident.mark_id.as_deref().unwrap_or("mark")),
+        IncidentKind::Penalty => format!("Penalty {sails}"),
+    };
+    highlight(state, HighlightKind::Incident, incident.boats.clone(), label, timestamp)
+}
+
+/// What was already seen, so each OCS call, leader and rounding is marked once.
+#[derive(Default)]
+pub struct HighlightWatch {
+    ocs: HashSet<String>,
+    leader: Option<String>,
+    roundings: usize,
+    primed: bool,
+}
+
+impl HighlightWatch {
+    /// New OCS calls, lead changes and mark roundings since the last scan. The
+    /// first scan only takes note, so a restart does not mark them again.
+    pub fn scan(&mut self, state: &RaceState, timestamp: i64) -> Vec<Highlight> {
+        let mut found = Vec::new();
+        let primed = std::mem::replace(&mut self.primed, true);
+
+        for boat_id in &state.ocs_boats {
+            if self.ocs.insert(boat_id.clone()) && primed {
+                found.push(highlight(state, HighlightKind::Ocs, vec![boat_id.clone()], format!("OCS {}", sail(state, boat_id)), timestamp));
+            }
+        }
+        // A new start clears the calls
+        self.ocs.retain(|b| state.ocs_boats.contains(b));
+
+        let leader = state.boats.values().find(|b| b.rank == 1).map(|b| b.boat_id.clone());
+        if state.status == RaceStatus::Racing && primed && self.leader.is_some() && leader.is_some() && leader != self.leader {
+            if let Some(boat_id) = &leader {
+                found.push(highlight(state, HighlightKind::LeadChange, vec![boat_id.clone()], format!("{} takes the lead", sail(state, boat_id)), timestamp));
+            }
+        }
+        if leader.is_some() {
+            self.leader = leader;
+        }
+
+        // Roundings are only appended; a shorter list is a new race
+        if state.mark_roundings.len() < self.roundings {
+            self.roundings = 0;
+        }
+        if primed {
+            for rounding in state.mark_roundings[self.roundings..].iter().filter(|r| r.correct) {
+                let label = format!("{} rounds {}", sail(state, &rounding.boat_id), rounding.element_name);
+                found.push(highlight(state, HighlightKind::MarkRounding, vec![rounding.boat_id.clone()], label, rounding.timestamp));
+            }
+        }
+        self.roundings = state.mark_roundings.len();
+        found
+    }
+}
+
+/// Keep new highlights, dropping the oldest past `MAX_HIGHLIGHTS`.
+pub fn record(state: &mut RaceState, highlights: &[Highlight]) {
+    state.highlights.extend_from_slice(highlights);
+    let excess = state.highlights.len().saturating_sub(MAX_HIGHLIGHTS);
+    state.highlights.drain(..excess);
+}
+
+/// Highlights of one race, or all.
+pub fn list<'a>(state: &'a RaceState, race: Option<&str>) -> Vec<&'a Highlight> {
+    state.highlights.iter()
+        .filter(|h| race.is_none_or(|race| h.race_id.as_deref() == Some(race)))
+        .collect()
+}
+
+fn kind_label(kind: HighlightKind) -> &'static str {
+    match kind {
+        HighlightKind::Gun => "GUN",
+        HighlightKind::Ocs => "OCS",
+        HighlightKind::LeadChange => "LEAD_CHANGE",
+        HighlightKind::MarkRounding => "MARK_ROUNDING",
+        HighlightKind::Incident => "INCIDENT",
+    }
+}
+
+/// Quote a CSV field when it needs it (RFC 4180).
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn csv(state: &RaceState, race: Option<&str>) -> String {
+    let mut out = String::from("Time,Epoch ms,Race,Kind,Boats,Label\r\n");
+    for h in list(state, race) {
+        let time = Utc.timestamp_millis_opt(h.timestamp).single()
+            .map(|t| t.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+            .unwrap_or_default();
+        let boats = h.boats.iter().map(|b| sail(state, b)).collect::<Vec<_>>().join(" ");
+        let fields = [time, h.timestamp.to_string(), h.race_id.clone().unwrap_or_default(), kind_label(h.kind).to_string(), boats, h.label.clone()];
+        out.push_str(&fields.iter().map(|f| field(f)).collect::<Vec<_>>().join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
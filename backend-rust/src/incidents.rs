@@ -0,0 +1,250 @@
+//! # incidents
+//!
+//! Incident detection for the auto-director, so it cuts to boats in each other's
+//! way rather than just the fastest one. Each tick the live boats are checked for:
+//!
+//! - **crossing** — two boats on converging courses (more than 30° apart) whose
+//!   closest approach within the next 10 s is under 10 m
+//! - **mark overlap** — two boats within 12 m of each other, both inside the
+//!   30 m zone of the same mark
+//! - **penalty** — an on-water penalty issued (OCS, umpire flags, 360°) or a
+//!   penalty turn reported as taken
+//!
+//! Confidence (0–1) grows as the boats get closer; a penalty is certain. The
+//! priority is the kind's weight (penalty 40, mark overlap 30, crossing 25)
+//! times the confidence, and is what the involved boats add to their focus
+//! score (scaled by `incidentWeight`). Boats in an incident may also displace a
+//! focused boat before its dwell is up.
+//!
+//! An incident stays active for 15 s after it was last seen. A new one is
+//! announced to everyone as
+//! `incident { id, kind, boats, confidence, priority, markId?, timestamp }`.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::ranking_engine::haversine_distance;
+use crate::state::{BoatState, BoatStatus, LatLon, PenaltyStatus, PenaltyType, RaceState};
+
+const MPS_PER_KNOT: f64 = 0.514_444;
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Closest approach that makes a crossing
+const CROSSING_CPA_M: f64 = 10.0;
+/// How far ahead a crossing is looked for
+const CROSSING_HORIZON_S: f64 = 10.0;
+/// Courses closer than this are sailing together, not crossing
+const MIN_CROSSING_ANGLE_DEG: f64 = 30.0;
+/// Boats further apart than this are not checked for a crossing
+const CROSSING_RANGE_M: f64 = 100.0;
+/// Zone around a mark
+const MARK_ZONE_M: f64 = 30.0;
+/// Gap between two boats that counts as an overlap
+const OVERLAP_M: f64 = 12.0;
+/// Incidents are kept this long after they were last seen
+const HOLD: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IncidentKind {
+    Crossing,
+    MarkOverlap,
+    Penalty,
+}
+
+impl IncidentKind {
+    fn weight(self) -> f64 {
+        match self {
+            Self::Penalty => 40.0,
+            Self::MarkOverlap => 30.0,
+            Self::Crossing => 25.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Incident {
+    pub id: String,
+    pub kind: IncidentKind,
+    pub boats: Vec<String>,
+    pub confidence: f64,
+    pub priority: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark_id: Option<String>,
+}
+
+impl Incident {
+    fn new(kind: IncidentKind, mut boats: Vec<String>, confidence: f64, mark_id: Option<String>) -> Self {
+        boats.sort();
+        let confidence = (confidence.clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            boats,
+            confidence,
+            priority: (kind.weight() * confidence * 10.0).round() / 10.0,
+            mark_id,
+        }
+    }
+
+    /// Same incident across ticks: same kind, same boats.
+    fn key(&self) -> String {
+        format!("{:?}:{}", self.kind, self.boats.join(","))
+    }
+}
+
+struct Active {
+    incident: Incident,
+    seen: Instant,
+}
+
+/// Incidents found so far, and the penalties already reported.
+#[derive(Default)]
+pub struct IncidentDetector {
+    active: HashMap<String, Active>,
+    /// Penalty id → status last seen; None until the first tick, so penalties
+    /// from before a restart are not announced again
+    penalties: Option<HashMap<String, PenaltyStatus>>,
+}
+
+/// Local east/north metres of `p` relative to `origin`.
+fn local(origin: &LatLon, p: &LatLon) -> (f64, f64) {
+    let e = (p.lon - origin.lon).to_radians() * origin.lat.to_radians().cos() * EARTH_RADIUS_M;
+    let n = (p.lat - origin.lat).to_radians() * EARTH_RADIUS_M;
+    (e, n)
+}
+
+/// Course over ground (heading if the tracker sends none) and speed in m/s.
+fn course(boat: &BoatState) -> (f64, f64) {
+    (boat.velocity.dir.unwrap_or(boat.imu.heading), boat.velocity.speed * MPS_PER_KNOT)
+}
+
+fn crossing(a: &BoatState, b: &BoatState) -> Option<Incident> {
+    let (cog_a, speed_a) = course(a);
+    let (cog_b, speed_b) = course(b);
+    let angle = ((cog_a - cog_b).rem_euclid(360.0) + 180.0).rem_euclid(360.0) - 180.0;
+    if angle.abs() < MIN_CROSSING_ANGLE_DEG {
+        return None;
+    }
+    // Relative position and velocity of b seen from a
+    let (pe, pn) = local(&a.pos, &b.pos);
+    let (ve, vn) = (
+        speed_b * cog_b.to_radians().sin() - speed_a * cog_a.to_radians().sin(),
+        speed_b * cog_b.to_radians().cos() - speed_a * cog_a.to_radians().cos(),
+    );
+    let closing = ve * ve + vn * vn;
+    if closing < 1e-6 {
+        return None;
+    }
+    let t = -(pe * ve + pn * vn) / closing;
+    if !(0.0..=CROSSING_HORIZON_S).contains(&t) {
+        return None;
+    }
+    let cpa = (pe + ve * t).hypot(pn + vn * t);
+    if cpa >= CROSSING_CPA_M {
+        return None;
+    }
+    // Closer and sooner is more certain; a right-angle crossing more than a glancing one
+    let confidence = (1.0 - cpa / CROSSING_CPA_M) * 0.6
+        + (1.0 - t / CROSSING_HORIZON_S) * 0.2
+        + angle.to_radians().sin().abs() * 0.2;
+    Some(Incident::new(IncidentKind::Crossing, vec![a.boat_id.clone(), b.boat_id.clone()], confidence, None))
+}
+
+fn penalty_on_water(penalty_type: &PenaltyType) -> bool {
+    matches!(
+        penalty_type,
+        PenaltyType::Ocs
+            | PenaltyType::Turn360
+            | PenaltyType::UmpireNoAction
+            | PenaltyType::UmpirePenalty
+            | PenaltyType::UmpireDsq
+    )
+}
+
+impl IncidentDetector {
+    /// Check the fleet; returns the incidents seen for the first time.
+    pub fn detect(&mut self, state: &RaceState, now: Instant) -> Vec<Incident> {
+        let boats: Vec<&BoatState> = state.boats.values().filter(|b| b.status == BoatStatus::Live).collect();
+        let mut found = Vec::new();
+
+        for (i, a) in boats.iter().enumerate() {
+            for b in &boats[i + 1..] {
+                if haversine_distance(&a.pos, &b.pos) > CROSSING_RANGE_M {
+                    continue;
+                }
+                found.extend(crossing(a, b));
+            }
+        }
+
+        for mark in &state.course.marks {
+            let in_zone: Vec<&BoatState> = boats.iter().copied()
+                .filter(|b| haversine_distance(&b.pos, &mark.pos) <= MARK_ZONE_M)
+                .collect();
+            for (i, a) in in_zone.iter().enumerate() {
+                for b in &in_zone[i + 1..] {
+                    let gap = haversine_distance(&a.pos, &b.pos);
+                    if gap < OVERLAP_M {
+                        let confidence = 0.5 + 0.5 * (1.0 - gap / OVERLAP_M);
+                        found.push(Incident::new(
+                            IncidentKind::MarkOverlap,
+                            vec![a.boat_id.clone(), b.boat_id.clone()],
+                            confidence,
+                            Some(mark.id.clone()),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let first_tick = self.penalties.is_none();
+        let seen = self.penalties.get_or_insert_with(HashMap::new);
+        for penalty in &state.penalties {
+            let before = seen.insert(penalty.id.clone(), penalty.status);
+            if first_tick {
+                continue;
+            }
+            let issued = before.is_none() && penalty_on_water(&penalty.penalty_type) && penalty.status == PenaltyStatus::Pending;
+            let turn_taken = penalty.status == PenaltyStatus::TakenOnWater && before.is_some_and(|s| s != PenaltyStatus::TakenOnWater);
+            if (issued || turn_taken) && state.boats.contains_key(&penalty.boat_id) {
+                found.push(Incident::new(IncidentKind::Penalty, vec![penalty.boat_id.clone()], 1.0, None));
+            }
+        }
+        seen.retain(|id, _| state.penalties.iter().any(|p| &p.id == id));
+
+        // Known incidents keep their id and are refreshed; only new ones are returned
+        let mut new = Vec::new();
+        for incident in found {
+            match self.active.get_mut(&incident.key()) {
+                Some(active) => {
+                    active.seen = now;
+                    active.incident.confidence = incident.confidence;
+                    active.incident.priority = incident.priority;
+                    active.incident.mark_id = incident.mark_id;
+                }
+                None => {
+                    self.active.insert(incident.key(), Active { incident: incident.clone(), seen: now });
+                    new.push(incident);
+                }
+            }
+        }
+        self.active.retain(|_, a| now.saturating_duration_since(a.seen) < HOLD);
+        new.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap_or(std::cmp::Ordering::Equal));
+        new
+    }
+
+    /// Highest incident priority each boat is involved in.
+    pub fn boat_priorities(&self) -> HashMap<String, f64> {
+        let mut priorities: HashMap<String, f64> = HashMap::new();
+        for active in self.active.values() {
+            for boat_id in &active.incident.boats {
+                let entry = priorities.entry(boat_id.clone()).or_default();
+                *entry = entry.max(active.incident.priority);
+            }
+        }
+        priorities
+    }
+}
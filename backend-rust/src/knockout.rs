@@ -0,0 +1,208 @@
+//! # knockout
+//!
+//! Knockout rounds after the round robin — quarter-finals, semi-finals and the
+//! final — each sailed as one flight with a race per match.
+//!
+//! - `generate-knockout { round, teamsPerMatch?, advancePerMatch?, boats? }` —
+//!   `QUARTER_FINAL`, `SEMI_FINAL` or `FINAL`. The first round is seeded from the
+//!   league standings (`league`): two teams a match meet 1 v 8, 2 v 7 …, larger
+//!   matches take the seeds in snake order. A later round takes the teams
+//!   advancing from the round before, which must be complete; the winners of
+//!   matches i and n−1−i meet, so the top two seeds can only meet in the final.
+//! - `reset-knockout` — drops every round with its flights and results
+//! - `get-knockout` — the rounds, to the sender
+//!
+//! Seeds sail boats 1, 2 … of the match. Results are entered like any flight
+//! (`record-flight-results`); the first `advancePerMatch` teams of each match
+//! (default half of it) go through. A resailed round (`create-resail`) keeps its
+//! round, and the latest of its flights not abandoned counts.
+//!
+//! New flights go out as `flight-delta`, the rounds as `knockout { stages }`
+//! whenever they or who advances change.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::flight_schedule::FlightDelta;
+use crate::state::{Flight, FlightOfficials, FlightStatus, KnockoutMatch, KnockoutRound, KnockoutStage, Pairing, RaceState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum KnockoutError {
+    #[error("The {0} has already been generated")]
+    Exists(&'static str),
+    #[error("The {0} must follow the {1}")]
+    OutOfOrder(&'static str, &'static str),
+    #[error("The {0} is not complete")]
+    Incomplete(&'static str),
+    #[error("The {0} needs {1} teams in the standings, there are {2}")]
+    NotEnoughTeams(&'static str, usize, usize),
+    #[error("Matches of {0} teams cannot be sailed with {1} boats")]
+    MatchSize(usize, usize),
+    #[error("Between 1 and {0} teams of a match can advance")]
+    Advance(usize),
+}
+
+/// The flight a round counts on: its latest one not abandoned.
+fn flight_of(state: &RaceState, round: KnockoutRound) -> Option<&Flight> {
+    state.flights.values()
+        .filter(|f| f.stage == Some(round) && f.status != FlightStatus::Abandoned)
+        .max_by_key(|f| f.flight_number)
+}
+
+fn complete(stage: &KnockoutStage) -> bool {
+    stage.matches.iter().all(|m| !m.finish.is_empty())
+}
+
+/// Seeds 0.. into `matches` matches: 1 v n, 2 v n−1 …, snaking for larger matches.
+fn seed(teams: &[String], matches: usize) -> Vec<Vec<String>> {
+    let mut seeded = vec![Vec::new(); matches];
+    for (k, team) in teams.iter().enumerate() {
+        let (pass, pos) = (k / matches, k % matches);
+        let i = if pass % 2 == 0 { pos } else { matches - 1 - pos };
+        seeded[i].push(team.clone());
+    }
+    seeded
+}
+
+pub fn generate(
+    state: &mut RaceState,
+    round: KnockoutRound,
+    teams_per_match: Option<usize>,
+    advance_per_match: Option<usize>,
+    boats: usize,
+    now_ms: i64,
+) -> Result<FlightDelta, KnockoutError> {
+    if state.knockout.iter().any(|s| s.round == round) {
+        return Err(KnockoutError::Exists(round.label()));
+    }
+    // League rank is the seed throughout
+    let rank: HashMap<&str, u32> = state.league_standings.iter().map(|s| (s.team_id.as_str(), s.rank)).collect();
+    let by_seed = |teams: &mut Vec<String>| teams.sort_by_key(|t| rank.get(t.as_str()).copied().unwrap_or(u32::MAX));
+
+    let matches: Vec<Vec<String>> = match state.knockout.last() {
+        Some(previous) => {
+            if previous.round.next() != Some(round) {
+                return Err(KnockoutError::OutOfOrder(round.label(), previous.round.label()));
+            }
+            if !complete(previous) {
+                return Err(KnockoutError::Incomplete(previous.round.label()));
+            }
+            let n = previous.matches.len();
+            (0..round.matches())
+                .map(|i| {
+                    let mut teams = previous.matches[i].advancing.clone();
+                    teams.extend(previous.matches[n - 1 - i].advancing.iter().cloned());
+                    by_seed(&mut teams);
+                    teams
+                })
+                .collect()
+        }
+        None => {
+            let size = teams_per_match.unwrap_or(2);
+            let needed = size * round.matches();
+            if state.league_standings.len() < needed {
+                return Err(KnockoutError::NotEnoughTeams(round.label(), needed, state.league_standings.len()));
+            }
+            let teams: Vec<String> = state.league_standings.iter().take(needed).map(|s| s.team_id.clone()).collect();
+            seed(&teams, round.matches())
+        }
+    };
+
+    let size = matches.iter().map(Vec::len).max().unwrap_or(0);
+    if size < 2 || size > boats {
+        return Err(KnockoutError::MatchSize(size, boats));
+    }
+    let advance = match round {
+        KnockoutRound::Final => 1,
+        _ => advance_per_match.unwrap_or(size / 2),
+    };
+    if advance == 0 || advance >= size {
+        return Err(KnockoutError::Advance(size - 1));
+    }
+
+    let number = state.flights.values().map(|f| f.flight_number).max().unwrap_or(0) + 1;
+    let flight = Flight {
+        id: Uuid::new_v4().to_string(),
+        flight_number: number,
+        group_label: round.label().to_string(),
+        status: FlightStatus::Scheduled,
+        stage: Some(round),
+        officials: FlightOfficials::default(),
+    };
+    let pairings: Vec<Pairing> = matches.iter().enumerate()
+        .flat_map(|(i, teams)| teams.iter().enumerate().map(move |(k, team)| (i, k, team)))
+        .map(|(i, k, team)| Pairing {
+            id: Uuid::new_v4().to_string(),
+            flight_id: flight.id.clone(),
+            team_id: team.clone(),
+            boat_id: (k + 1).to_string(),
+            race_index: i as u32,
+        })
+        .collect();
+
+    state.knockout.push(KnockoutStage {
+        round,
+        teams_per_match: size,
+        advance_per_match: advance,
+        matches: matches.into_iter().enumerate()
+            .map(|(i, teams)| KnockoutMatch { race_index: i as u32, teams, finish: Vec::new(), advancing: Vec::new() })
+            .collect(),
+        created_ms: now_ms,
+    });
+    state.pairings.extend(pairings.iter().cloned());
+    state.flights.insert(flight.id.clone(), flight.clone());
+    Ok(FlightDelta { flights: vec![flight], pairings, ..Default::default() })
+}
+
+/// Refresh each match's finish and who advances from the entered results.
+/// Returns whether anything changed.
+pub fn update(state: &mut RaceState) -> bool {
+    let mut changed = false;
+    for i in 0..state.knockout.len() {
+        let flight_id = flight_of(state, state.knockout[i].round).map(|f| f.id.clone());
+        let finishes: Vec<Vec<String>> = state.knockout[i].matches.iter()
+            .map(|m| {
+                let mut results: Vec<_> = state.league_results.iter()
+                    .filter(|r| Some(&r.flight_id) == flight_id.as_ref() && r.race_index == m.race_index)
+                    .collect();
+                if results.len() < m.teams.len() {
+                    return Vec::new();
+                }
+                // Places first, then codes
+                results.sort_by_key(|r| (r.code.is_some() || r.place.is_none(), r.place.unwrap_or(u32::MAX), r.code));
+                results.into_iter().map(|r| r.team_id.clone()).collect()
+            })
+            .collect();
+        let stage = &mut state.knockout[i];
+        for (m, finish) in stage.matches.iter_mut().zip(finishes) {
+            if m.finish != finish {
+                m.advancing = finish.iter().take(stage.advance_per_match).cloned().collect();
+                m.finish = finish;
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Drop the rounds, their flights and results.
+pub fn reset(state: &mut RaceState) -> FlightDelta {
+    let flight_ids: Vec<String> = state.flights.values().filter(|f| f.stage.is_some()).map(|f| f.id.clone()).collect();
+    let mut delta = FlightDelta::default();
+    for id in &flight_ids {
+        state.flights.remove(id);
+        if state.active_flight_id.as_ref() == Some(id) {
+            state.active_flight_id = None;
+        }
+    }
+    delta.removed_pairing_ids = state.pairings.iter()
+        .filter(|p| flight_ids.contains(&p.flight_id))
+        .map(|p| p.id.clone())
+        .collect();
+    state.pairings.retain(|p| !flight_ids.contains(&p.flight_id));
+    state.league_results.retain(|r| !flight_ids.contains(&r.flight_id));
+    state.knockout.clear();
+    delta.removed_flight_ids = flight_ids;
+    delta
+}
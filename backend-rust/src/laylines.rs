@@ -0,0 +1,165 @@
+//! # laylines
+//!
+//! Port and starboard laylines for every rounding mark, computed once on the
+//! server so all clients draw the same lines.
+//!
+//! A mark is approached upwind when the leg into it (from the previous element of
+//! `activeCourseOrder`, or else from the centre of the course) points within 90°
+//! of the wind; otherwise downwind. Laylines are sailed at:
+//!
+//! - `LAYLINE_UPWIND_TWA` (default 45) / `LAYLINE_DOWNWIND_TWA` (default 150)
+//! - per class, `LAYLINE_CLASS_ANGLES` (`"J70=42/145,ILCA7=45/160"`): an extra set
+//!   tagged with the class id for each listed class that has entries
+//! - a class in the registry (`boat_classes`) with `upwindTwa` / `downwindTwa`
+//!   replaces its `LAYLINE_CLASS_ANGLES` entry, and sails at its polar speed
+//!
+//! With `state.current` set, each line follows the ground track of a boat on that
+//! heading at `LAYLINE_UPWIND_KN` / `LAYLINE_DOWNWIND_KN` (default 6 / 7) through
+//! the water (see `tide`).
+//!
+//! Each line runs `LAYLINE_LENGTH_M` (default 1000) from the mark. They are kept
+//! in `course.laylines`, so they go out with every `course-updated` and
+//! `state-update`, and are recomputed when the course, the wind or the current
+//! changes.
+
+use std::collections::HashMap;
+
+use crate::boat_classes;
+use crate::ranking_engine::{bearing, destination};
+use crate::state::{BuoyType, LatLon, LaylineLeg, MarkLaylines, RaceState};
+use crate::tide;
+
+pub struct LaylineConfig {
+    pub upwind_twa: f64,
+    pub downwind_twa: f64,
+    /// class id → (upwind, downwind) true wind angles
+    pub class_angles: HashMap<String, (f64, f64)>,
+    pub length_m: f64,
+    /// Boat speed through the water, knots, for the current correction
+    pub upwind_kn: f64,
+    pub downwind_kn: f64,
+}
+
+impl Default for LaylineConfig {
+    fn default() -> Self {
+        let num = |key: &str, default: f64| std::env::var(key).ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .unwrap_or(default);
+        let class_angles = std::env::var("LAYLINE_CLASS_ANGLES").unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| {
+                let (class, angles) = pair.split_once('=')?;
+                let (up, down) = angles.split_once('/')?;
+                Some((class.trim().to_string(), (up.trim().parse().ok()?, down.trim().parse().ok()?)))
+            })
+            .collect();
+        Self {
+            upwind_twa: num("LAYLINE_UPWIND_TWA", 45.0),
+            downwind_twa: num("LAYLINE_DOWNWIND_TWA", 150.0),
+            class_angles,
+            length_m: num("LAYLINE_LENGTH_M", 1000.0),
+            upwind_kn: num("LAYLINE_UPWIND_KN", 6.0),
+            downwind_kn: num("LAYLINE_DOWNWIND_KN", 7.0),
+        }
+    }
+}
+
+fn centroid(points: &[&LatLon]) -> Option<LatLon> {
+    if points.is_empty() {
+        return None;
+    }
+    let n = points.len() as f64;
+    Some(LatLon {
+        lat: points.iter().map(|p| p.lat).sum::<f64>() / n,
+        lon: points.iter().map(|p| p.lon).sum::<f64>() / n,
+    })
+}
+
+/// Where boats come from on the way to `mark_id`.
+fn approach_from(state: &RaceState, mark_id: &str) -> Option<LatLon> {
+    let element_centroid = |ids: &[String]| {
+        centroid(&state.course.marks.iter().filter(|m| ids.contains(&m.id)).map(|m| &m.pos).collect::<Vec<_>>())
+    };
+    if let Some(order) = &state.active_course_order {
+        if let Some(i) = order.iter().position(|el| el.marks.iter().any(|m| m == mark_id)) {
+            if i > 0 {
+                return element_centroid(&order[i - 1].marks);
+            }
+        }
+    }
+    centroid(&state.course.marks.iter().map(|m| &m.pos).collect::<Vec<_>>())
+}
+
+fn leg(state: &RaceState, mark: &LatLon, mark_id: &str) -> LaylineLeg {
+    let twd = state.wind.direction;
+    match approach_from(state, mark_id) {
+        Some(from) if from != *mark => {
+            let off_wind = (bearing(&from, mark) - twd).rem_euclid(360.0);
+            if off_wind <= 90.0 || off_wind >= 270.0 { LaylineLeg::Upwind } else { LaylineLeg::Downwind }
+        }
+        _ => LaylineLeg::Upwind,
+    }
+}
+
+pub fn compute(config: &LaylineConfig, state: &RaceState) -> Vec<MarkLaylines> {
+    let twd = state.wind.direction;
+    // Default angles, then every class with angles (registry first) that is racing
+    let mut sets: Vec<(Option<String>, (f64, f64))> = vec![(None, (config.upwind_twa, config.downwind_twa))];
+    let mut classes: Vec<(String, (f64, f64))> = state.entries.iter()
+        .filter_map(|e| e.class_id.clone())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|c| {
+            let fallback = config.class_angles.get(&c).copied();
+            let registered = boat_classes::get(state, &c).and_then(|class| {
+                let (up, down) = fallback.unzip();
+                Some((class.upwind_twa.or(up)?, class.downwind_twa.or(down)?))
+            });
+            registered.or(fallback).map(|angles| (c, angles))
+        })
+        .collect();
+    classes.sort_by(|a, b| a.0.cmp(&b.0));
+    sets.extend(classes.into_iter().map(|(c, angles)| (Some(c), angles)));
+
+    let mut laylines = Vec::new();
+    for mark in state.course.marks.iter().filter(|m| matches!(m.buoy_type, BuoyType::Mark | BuoyType::Gate)) {
+        let leg = leg(state, &mark.pos, &mark.id);
+        for (class_id, (upwind, downwind)) in &sets {
+            let default_kn = if leg == LaylineLeg::Upwind { config.upwind_kn } else { config.downwind_kn };
+            let knots = class_id.as_deref()
+                .and_then(|c| boat_classes::get(state, c))
+                .and_then(|class| boat_classes::leg_speed(class, leg, state.wind.speed))
+                .unwrap_or(default_kn);
+            let twa = if leg == LaylineLeg::Upwind { *upwind } else { *downwind };
+            // A layline runs back from the mark along the reciprocal of the track that fetches it
+            let track = |heading: f64| match state.current {
+                Some(_) => tide::ground_track(state, heading, knots),
+                None => heading,
+            };
+            let port_bearing = (track(twd + twa) + 180.0).rem_euclid(360.0);
+            let starboard_bearing = (track(twd - twa) + 180.0).rem_euclid(360.0);
+            laylines.push(MarkLaylines {
+                mark_id: mark.id.clone(),
+                class_id: class_id.clone(),
+                leg,
+                twa,
+                port_bearing,
+                port_end: destination(&mark.pos, port_bearing, config.length_m),
+                starboard_bearing,
+                starboard_end: destination(&mark.pos, starboard_bearing, config.length_m),
+            });
+        }
+    }
+    laylines
+}
+
+/// Recompute `course.laylines`. Returns whether they changed.
+pub fn refresh(state: &mut RaceState) -> bool {
+    let laylines = compute(&LaylineConfig::default(), state);
+    if laylines == state.course.laylines {
+        return false;
+    }
+    state.course.laylines = laylines;
+    true
+}
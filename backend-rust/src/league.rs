@@ -0,0 +1,235 @@
+//! # league
+//!
+//! Team standings for league racing, from the finish order of each race of each
+//! flight (`flight_engine`, `flight_schedule`).
+//!
+//! - `record-flight-results { flightId, raceIndex, order: [teamId…], codes? }` —
+//!   the finish order of one race; `codes` gives the teams that did not take a
+//!   place their scoring code, e.g. `{ "team-3": "DNF" }`. Every team in the race
+//!   must be in one or the other. Entering a race again replaces it.
+//! - `get-league-standings` — the current table, to the sender
+//!
+//! Each entry broadcasts `league-standings { standings, results }`, and so do
+//! schedule edits and redress decisions.
+//!
+//! Scoring is low point: a team scores its place, a code scores the number of
+//! teams in the race + 1. Granted redress (`scoring::flag_redress`) scores the
+//! average of the team's other races to one decimal (half the race size + 1
+//! when it has none). Flights marked `ABANDONED` and knockout flights
+//! (`knockout`) do not count. Ties break on the best-to-worst list of scores
+//! (most wins, then most seconds, …), then on the last race sailed.
+//!
+//! - `GET /league/standings.csv` — `Rank, Team, Club, F1 … Fn, Total`, scores
+//!   written as in the fleet exports, e.g. `7 DNF`, `2.5 RDG`
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::results_export::code_label;
+use crate::state::{
+    FlightStatus, LeagueRaceScore, LeagueStanding, PairingResult, RaceState, RedressStatus, ScoreCode,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LeagueError {
+    #[error("Unknown flight: {0}")]
+    UnknownFlight(String),
+    #[error("Flight {0} has no race {1}")]
+    UnknownRace(u32, u32),
+    #[error("Team {0} does not sail in this race")]
+    NotInRace(String),
+    #[error("Team {0} is entered more than once")]
+    Duplicate(String),
+    #[error("No finish or code for team {0}")]
+    Missing(String),
+}
+
+/// Enter the finish order (and codes) of one race of a flight, then rescore.
+pub fn record_results(
+    state: &mut RaceState,
+    flight_id: &str,
+    race_index: u32,
+    order: &[String],
+    codes: &HashMap<String, ScoreCode>,
+    now_ms: i64,
+) -> Result<Vec<PairingResult>, LeagueError> {
+    let flight = state.flights.get(flight_id).ok_or_else(|| LeagueError::UnknownFlight(flight_id.to_string()))?;
+    let pairings: Vec<_> = state.pairings.iter()
+        .filter(|p| p.flight_id == flight_id && p.race_index == race_index)
+        .collect();
+    if pairings.is_empty() {
+        return Err(LeagueError::UnknownRace(flight.flight_number, race_index + 1));
+    }
+
+    let mut seen = HashSet::new();
+    for team in order.iter().chain(codes.keys()) {
+        if !pairings.iter().any(|p| &p.team_id == team) {
+            return Err(LeagueError::NotInRace(team.clone()));
+        }
+        if !seen.insert(team.as_str()) {
+            return Err(LeagueError::Duplicate(team.clone()));
+        }
+    }
+    if let Some(p) = pairings.iter().find(|p| !seen.contains(p.team_id.as_str())) {
+        return Err(LeagueError::Missing(p.team_id.clone()));
+    }
+
+    let results: Vec<PairingResult> = pairings.iter()
+        .map(|p| PairingResult {
+            pairing_id: p.id.clone(),
+            flight_id: flight_id.to_string(),
+            race_index,
+            team_id: p.team_id.clone(),
+            place: order.iter().position(|t| t == &p.team_id).map(|i| i as u32 + 1),
+            code: codes.get(&p.team_id).copied(),
+            recorded_ms: now_ms,
+        })
+        .collect();
+    state.league_results.retain(|r| !(r.flight_id == flight_id && r.race_index == race_index));
+    state.league_results.extend(results.iter().cloned());
+    rescore(state);
+    Ok(results)
+}
+
+pub fn rescore(state: &mut RaceState) {
+    state.league_standings = standings(state);
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+/// Team standings over every counted flight.
+pub fn standings(state: &RaceState) -> Vec<LeagueStanding> {
+    let counted = |flight_id: &str| state.flights.get(flight_id)
+        .filter(|f| f.status != FlightStatus::Abandoned && f.stage.is_none());
+    let race_size = |flight_id: &str, race_index: u32| state.pairings.iter()
+        .filter(|p| p.flight_id == flight_id && p.race_index == race_index)
+        .count();
+
+    let mut races: HashMap<&str, Vec<LeagueRaceScore>> = HashMap::new();
+    for result in &state.league_results {
+        let Some(flight) = counted(&result.flight_id) else { continue };
+        let points = match (result.place, result.code) {
+            (Some(place), None) => place as f64,
+            _ => race_size(&result.flight_id, result.race_index) as f64 + 1.0,
+        };
+        races.entry(result.team_id.as_str()).or_default().push(LeagueRaceScore {
+            flight_id: result.flight_id.clone(),
+            flight_number: flight.flight_number,
+            race_index: result.race_index,
+            place: result.place,
+            code: result.code,
+            points,
+            redress: false,
+        });
+    }
+
+    // Granted redress: the average of the team's other races, whether or not the
+    // spoiled race was entered
+    let granted: Vec<_> = state.redress.iter()
+        .filter(|r| r.status == RedressStatus::Granted && counted(&r.flight_id).is_some())
+        .collect();
+    for redress in &granted {
+        let scores = races.entry(redress.team_id.as_str()).or_default();
+        if !scores.iter().any(|s| s.flight_id == redress.flight_id) {
+            scores.push(LeagueRaceScore {
+                flight_id: redress.flight_id.clone(),
+                flight_number: counted(&redress.flight_id).map_or(0, |f| f.flight_number),
+                race_index: redress.race_index,
+                place: None,
+                code: None,
+                points: 0.0,
+                redress: false,
+            });
+        }
+    }
+    for (team_id, scores) in races.iter_mut() {
+        let given: HashSet<&str> = granted.iter().filter(|r| r.team_id == *team_id).map(|r| r.flight_id.as_str()).collect();
+        let others: Vec<f64> = scores.iter().filter(|s| !given.contains(s.flight_id.as_str())).map(|s| s.points).collect();
+        for score in scores.iter_mut().filter(|s| given.contains(s.flight_id.as_str())) {
+            score.redress = true;
+            score.points = if others.is_empty() {
+                (race_size(&score.flight_id, score.race_index) as f64 + 1.0) / 2.0
+            } else {
+                round1(others.iter().sum::<f64>() / others.len() as f64)
+            };
+        }
+        scores.sort_by_key(|s| s.flight_number);
+    }
+
+    let mut table: Vec<LeagueStanding> = races.into_iter()
+        .map(|(team_id, scores)| LeagueStanding {
+            rank: 0,
+            team_id: team_id.to_string(),
+            team_name: state.teams.get(team_id).map_or_else(|| team_id.to_string(), |t| t.name.clone()),
+            total: scores.iter().map(|s| s.points).sum(),
+            races: scores,
+        })
+        .collect();
+    table.sort_by(|a, b| a.total.total_cmp(&b.total).then_with(|| tie_break(a, b)).then(a.team_name.cmp(&b.team_name)));
+    for (i, row) in table.iter_mut().enumerate() {
+        row.rank = i as u32 + 1;
+    }
+    table
+}
+
+/// Best-to-worst scores, then the last race backwards.
+fn tie_break(a: &LeagueStanding, b: &LeagueStanding) -> std::cmp::Ordering {
+    let sorted = |s: &LeagueStanding| {
+        let mut pts: Vec<f64> = s.races.iter().map(|r| r.points).collect();
+        pts.sort_by(f64::total_cmp);
+        pts
+    };
+    for (x, y) in sorted(a).iter().zip(&sorted(b)) {
+        if x != y {
+            return x.total_cmp(y);
+        }
+    }
+    for (x, y) in a.races.iter().rev().zip(b.races.iter().rev()) {
+        if x.points != y.points {
+            return x.points.total_cmp(&y.points);
+        }
+    }
+    std::cmp::Ordering::Equal
+}
+
+/// Quote a CSV field when it needs it (RFC 4180).
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn score_cell(score: &LeagueRaceScore) -> String {
+    match (score.redress, score.code) {
+        (true, _) => format!("{} RDG", score.points),
+        (false, Some(code)) => format!("{} {}", score.points, code_label(code)),
+        (false, None) => score.points.to_string(),
+    }
+}
+
+pub fn csv(state: &RaceState) -> String {
+    let flights: BTreeSet<u32> = state.league_standings.iter()
+        .flat_map(|s| s.races.iter().map(|r| r.flight_number))
+        .collect();
+    let mut header = vec!["Rank".to_string(), "Team".to_string(), "Club".to_string()];
+    header.extend(flights.iter().map(|n| format!("F{n}")));
+    header.push("Total".to_string());
+
+    let mut out = String::new();
+    for fields in std::iter::once(header).chain(state.league_standings.iter().map(|s| {
+        let club = state.teams.get(&s.team_id).map(|t| t.club.clone()).unwrap_or_default();
+        let mut fields = vec![s.rank.to_string(), s.team_name.clone(), club];
+        fields.extend(flights.iter().map(|n| {
+            s.races.iter().filter(|r| r.flight_number == *n).map(score_cell).collect::<Vec<_>>().join(" ")
+        }));
+        fields.push(s.total.to_string());
+        fields
+    })) {
+        out.push_str(&fields.iter().map(|f| field(f)).collect::<Vec<_>>().join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
@@ -0,0 +1,164 @@
+//! # line_bias
+//!
+//! Start-line bias for the committee: how far the line is off square to the wind
+//! and which end is favored, so it can be squared before the sequence.
+//!
+//! Line geometry, first available of:
+//!
+//! - UWB line anchors (`UWB_LINE_ANCHORS`, default `"1,2"`) reporting their GPS fix
+//!   through the hub — source `UWB`
+//! - `course.startLine` p1/p2, then the course's two `START` marks — source `GPS`
+//!
+//! Ends are named as seen looking upwind: pin to port, committee boat to
+//! starboard. `biasDeg` is positive when the committee end is upwind; the
+//! advantage is line length × sin(bias), also given in boat lengths of the first
+//! boat profile. Within `LINE_SQUARE_TOLERANCE_DEG` (default 0.5) no end is
+//! favored.
+//!
+//! `state.lineBias` is recomputed on `update-wind`, course and mark edits and
+//! anchor moves; `update-wind`, `update-course` and anchor moves emit each change
+//! as `line-bias`, the mark commands carry it in their `state-update`.
+
+use serde_json::json;
+use socketioxide::SocketIo;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::handlers::SharedState;
+use crate::ranking_engine::{bearing, haversine_distance};
+use crate::state::{BuoyType, CourseLine, LatLon, LineBias, LineEnd, LineSource, RaceState};
+
+/// Fallback when no boat profile is configured
+const DEFAULT_BOAT_LENGTH_M: f64 = 7.0;
+
+/// GPS fix of a UWB node, as carried in its measurement envelope.
+#[derive(Debug, Clone)]
+pub struct AnchorFix {
+    pub node_id: u32,
+    pub pos: LatLon,
+}
+
+pub struct LineBiasConfig {
+    /// UWB nodes at the two ends of the start line
+    pub anchor_nodes: [u32; 2],
+}
+
+impl Default for LineBiasConfig {
+    fn default() -> Self {
+        let nodes: Vec<u32> = std::env::var("UWB_LINE_ANCHORS").unwrap_or_default()
+            .split(',')
+            .filter_map(|n| n.trim().parse().ok())
+            .collect();
+        Self {
+            anchor_nodes: match nodes[..] {
+                [a, b] => [a, b],
+                _ => [1, 2],
+            },
+        }
+    }
+}
+
+fn square_tolerance_deg() -> f64 {
+    std::env::var("LINE_SQUARE_TOLERANCE_DEG").ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v: &f64| *v >= 0.0)
+        .unwrap_or(0.5)
+}
+
+fn ends(line: &Option<CourseLine>) -> Option<(LatLon, LatLon)> {
+    let line = line.as_ref()?;
+    Some((line.p1.clone()?, line.p2.clone()?))
+}
+
+fn start_line(state: &RaceState) -> Option<(LatLon, LatLon, LineSource)> {
+    if let Some((p1, p2)) = ends(&state.uwb_start_line) {
+        return Some((p1, p2, LineSource::Uwb));
+    }
+    if let Some((p1, p2)) = ends(&state.course.start_line) {
+        return Some((p1, p2, LineSource::Gps));
+    }
+    let mut marks = state.course.marks.iter().filter(|m| m.buoy_type == BuoyType::Start);
+    match (marks.next(), marks.next()) {
+        (Some(a), Some(b)) => Some((a.pos.clone(), b.pos.clone(), LineSource::Gps)),
+        _ => None,
+    }
+}
+
+/// Signed difference `to - from` in (-180, 180].
+fn angle_diff(from: f64, to: f64) -> f64 {
+    let d = (to - from).rem_euclid(360.0);
+    if d > 180.0 { d - 360.0 } else { d }
+}
+
+pub fn compute(state: &RaceState) -> Option<LineBias> {
+    let (p1, p2, source) = start_line(state)?;
+    let length = haversine_distance(&p1, &p2);
+    if length < 1.0 {
+        return None;
+    }
+    let twd = state.wind.direction;
+    // Looking upwind, starboard is TWD + 90: the end lying that way is the committee end
+    let square = (twd + 90.0).rem_euclid(360.0);
+    let (pin, committee) = if angle_diff(square, bearing(&p1, &p2)).abs() <= 90.0 { (p1, p2) } else { (p2, p1) };
+    let line_bearing = bearing(&pin, &committee);
+    let bias_deg = angle_diff(line_bearing, square);
+
+    let advantage_m = length * bias_deg.to_radians().sin().abs();
+    let boat_length = state.boat_profiles.first()
+        .map(|p| p.max_length_hull)
+        .filter(|l| *l > 0.0)
+        .unwrap_or(DEFAULT_BOAT_LENGTH_M);
+    let favored_end = if bias_deg.abs() < square_tolerance_deg() {
+        None
+    } else if bias_deg > 0.0 {
+        Some(LineEnd::Committee)
+    } else {
+        Some(LineEnd::Pin)
+    };
+    let round = |v: f64, places: i32| (v * 10f64.powi(places)).round() / 10f64.powi(places);
+    Some(LineBias {
+        source,
+        bias_deg: round(bias_deg, 1),
+        favored_end,
+        advantage_m: round(advantage_m, 1),
+        advantage_boat_lengths: round(advantage_m / boat_length, 1),
+        line_length_m: round(length, 1),
+        line_bearing: round(line_bearing, 1),
+        wind_direction: twd,
+        pin,
+        committee,
+    })
+}
+
+/// Recompute `state.line_bias`. Returns whether it changed.
+pub fn refresh(state: &mut RaceState) -> bool {
+    let bias = compute(state);
+    if bias == state.line_bias {
+        return false;
+    }
+    state.line_bias = bias;
+    true
+}
+
+/// Track the UWB line anchors' GPS fixes and keep the bias current as they move.
+pub async fn run_line_bias(config: LineBiasConfig, mut anchor_rx: mpsc::Receiver<AnchorFix>, shared: SharedState, io: SocketIo) {
+    info!("LineBias: UWB line anchors {:?}", config.anchor_nodes);
+    while let Some(fix) = anchor_rx.recv().await {
+        let Some(end) = config.anchor_nodes.iter().position(|n| *n == fix.node_id) else { continue };
+        let bias = {
+            let mut state = shared.write().await;
+            let line = state.uwb_start_line.get_or_insert_with(CourseLine::default);
+            let slot = if end == 0 { &mut line.p1 } else { &mut line.p2 };
+            if slot.as_ref() == Some(&fix.pos) {
+                continue;
+            }
+            *slot = Some(fix.pos);
+            if !refresh(&mut state) {
+                continue;
+            }
+            state.line_bias.clone()
+        };
+        let _ = io.emit("line-bias", &json!(bias));
+    }
+    warn!("LineBias: anchor channel closed");
+}
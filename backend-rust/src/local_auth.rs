@@ -0,0 +1,225 @@
+//! # local_auth
+//!
+//! Authentication that works with no internet on the committee boat.
+//!
+//! Two forms, both configured before leaving the dock in `LOCAL_AUTH_FILE`
+//! (default `/data/local_auth.json`):
+//!
+//! ```json
+//! { "secret": "<hex, ≥ 32 bytes>",
+//!   "users": [{ "name": "pro", "role": "director", "salt": "<hex>", "hash": "<hex>", "rounds": 210000 }] }
+//! ```
+//!
+//! - **Offline tokens** — HS256 JWTs signed with `secret` (`LOCAL_AUTH_SECRET`
+//!   overrides), issuer `regatta-local`, carrying `sub`, `role`, `boatId?` and
+//!   `exp`. They are passed as `token` exactly like a Supabase token.
+//! - **Credentials** — `register { username, password }` against `users`,
+//!   PBKDF2-HMAC-SHA256 with a per-user salt. A successful login gets an
+//!   offline token back (`local-token { token, expiresMs }`) for reconnects and
+//!   `refresh-token`.
+//!
+//! Both are made with the offline tool:
+//!
+//! ```text
+//! regatta-backend local-auth hash <name> <role> <password>       → user entry
+//! regatta-backend local-auth issue <sub> <role> [boatId] [hours] → token
+//! ```
+//!
+//! The old fixed role strings (`director123`, `tracker`, …) are refused unless
+//! `LEGACY_ROLE_TOKENS=true`, meant for local development only.
+
+use std::path::PathBuf;
+
+use hmac::Hmac;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation, Algorithm};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::{info, warn};
+
+pub const ISSUER: &str = "regatta-local";
+const DEFAULT_ROUNDS: u32 = 210_000;
+const DEFAULT_TOKEN_HOURS: u64 = 24;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LocalAuthError {
+    #[error("No local auth secret configured")]
+    NoSecret,
+    #[error("Token signing failed: {0}")]
+    Sign(#[from] jsonwebtoken::errors::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalUser {
+    pub name: String,
+    pub role: String,
+    /// Hex salt
+    pub salt: String,
+    /// Hex PBKDF2-HMAC-SHA256 of the password
+    pub hash: String,
+    #[serde(default = "default_rounds")]
+    pub rounds: u32,
+    #[serde(default, rename = "boatId", skip_serializing_if = "Option::is_none")]
+    pub boat_id: Option<String>,
+}
+
+fn default_rounds() -> u32 {
+    DEFAULT_ROUNDS
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineClaims {
+    pub sub: String,
+    pub role: String,
+    #[serde(rename = "boatId", skip_serializing_if = "Option::is_none")]
+    pub boat_id: Option<String>,
+    pub iss: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct LocalAuthFile {
+    secret: Option<String>,
+    #[serde(default)]
+    users: Vec<LocalUser>,
+}
+
+pub struct LocalAuth {
+    secret: Option<Vec<u8>>,
+    users: Vec<LocalUser>,
+    legacy_roles: bool,
+    token_hours: u64,
+}
+
+impl LocalAuth {
+    pub fn load() -> Self {
+        let path = PathBuf::from(std::env::var("LOCAL_AUTH_FILE").unwrap_or_else(|_| "/data/local_auth.json".to_string()));
+        let file = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str::<LocalAuthFile>(&text).unwrap_or_else(|e| {
+                warn!("LocalAuth: ignoring {}: {e}", path.display());
+                LocalAuthFile::default()
+            }),
+            Err(_) => LocalAuthFile::default(),
+        };
+        let secret = std::env::var("LOCAL_AUTH_SECRET").ok().or(file.secret)
+            .and_then(|hex_secret| match hex::decode(hex_secret.trim()) {
+                Ok(secret) if secret.len() >= 32 => Some(secret),
+                _ => {
+                    warn!("LocalAuth: secret must be at least 32 bytes of hex, offline tokens disabled");
+                    None
+                }
+            });
+        let legacy_roles = std::env::var("LEGACY_ROLE_TOKENS").ok().is_some_and(|v| v == "true");
+        if legacy_roles {
+            warn!("LocalAuth: LEGACY_ROLE_TOKENS=true — fixed role strings are accepted, do not race like this");
+        }
+        info!("LocalAuth: {} local user(s), offline tokens {}", file.users.len(), if secret.is_some() { "enabled" } else { "disabled" });
+        Self {
+            secret,
+            users: file.users,
+            legacy_roles,
+            token_hours: std::env::var("LOCAL_TOKEN_HOURS").ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_TOKEN_HOURS),
+        }
+    }
+
+    pub fn verify_token(&self, token: &str) -> Option<OfflineClaims> {
+        let secret = self.secret.as_ref()?;
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[ISSUER]);
+        validation.set_required_spec_claims(&["exp", "sub", "iss"]);
+        validation.validate_aud = false;
+        decode::<OfflineClaims>(token, &DecodingKey::from_secret(secret), &validation)
+            .map(|data| data.claims)
+            .ok()
+    }
+
+    pub fn issue(&self, sub: &str, role: &str, boat_id: Option<&str>, hours: Option<u64>) -> Result<(String, OfflineClaims), LocalAuthError> {
+        let secret = self.secret.as_ref().ok_or(LocalAuthError::NoSecret)?;
+        let iat = crate::time_discipline::now_ms() / 1000;
+        let claims = OfflineClaims {
+            sub: sub.to_string(),
+            role: role.to_string(),
+            boat_id: boat_id.map(str::to_string),
+            iss: ISSUER.to_string(),
+            iat,
+            exp: iat + hours.unwrap_or(self.token_hours) * 3600,
+        };
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(secret))?;
+        Ok((token, claims))
+    }
+
+    /// The user whose password matches.
+    pub fn login(&self, name: &str, password: &str) -> Option<&LocalUser> {
+        let user = self.users.iter().find(|u| u.name == name)?;
+        let salt = hex::decode(&user.salt).ok()?;
+        let expected = hex::decode(&user.hash).ok()?;
+        let actual = derive(password, &salt, user.rounds);
+        // Constant-time comparison
+        let same = expected.len() == actual.len()
+            && expected.iter().zip(&actual).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+        same.then_some(user)
+    }
+
+    /// Role of a fixed legacy role string, only with `LEGACY_ROLE_TOKENS=true`.
+    pub fn legacy_role(&self, token: &str) -> Option<String> {
+        if !self.legacy_roles {
+            return None;
+        }
+        let role = match token {
+            "director123" | "director" => "director",
+            "jury123" | "jury" => "jury",
+            "media123" | "media" => "media",
+            "tracker123" | "tracker" => "tracker",
+            _ => return None,
+        };
+        Some(role.to_string())
+    }
+}
+
+fn derive(password: &str, salt: &[u8], rounds: u32) -> Vec<u8> {
+    let mut out = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds, &mut out).ok();
+    out.to_vec()
+}
+
+/// A `users` entry for `password` with a fresh salt.
+pub fn hash_user(name: &str, role: &str, password: &str) -> LocalUser {
+    let salt: [u8; 16] = *uuid::Uuid::new_v4().as_bytes();
+    LocalUser {
+        name: name.to_string(),
+        role: role.to_string(),
+        salt: hex::encode(salt),
+        hash: hex::encode(derive(password, &salt, DEFAULT_ROUNDS)),
+        rounds: DEFAULT_ROUNDS,
+        boat_id: None,
+    }
+}
+
+/// `regatta-backend local-auth …`; returns the process exit code.
+pub fn run_tool(args: &[String]) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("hash") if args.len() == 4 => {
+            let user = hash_user(&args[1], &args[2], &args[3]);
+            println!("{}", serde_json::to_string_pretty(&user).unwrap_or_default());
+            0
+        }
+        Some("issue") if (3..=5).contains(&args.len()) => {
+            let hours = args.get(4).and_then(|h| h.parse().ok());
+            match LocalAuth::load().issue(&args[1], &args[2], args.get(3).map(String::as_str).filter(|b| !b.is_empty()), hours) {
+                Ok((token, _)) => {
+                    println!("{token}");
+                    0
+                }
+                Err(e) => {
+                    eprintln!("local-auth issue failed: {e}");
+                    2
+                }
+            }
+        }
+        _ => {
+            eprintln!("usage: regatta-backend local-auth hash <name> <role> <password>");
+            eprintln!("       regatta-backend local-auth issue <sub> <role> [boatId] [hours]");
+            1
+        }
+    }
+}
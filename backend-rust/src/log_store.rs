@@ -0,0 +1,190 @@
+//! # log_store
+//!
+//! Race log beyond the in-memory window: every `LogEntry` is appended to a JSONL
+//! file so it can be queried after it rolls off `state.logs`, e.g. for jury review
+//! after the race.
+//!
+//! - `LOG_STORE_PATH` (default `/data/logs.jsonl`)
+//! - `LOG_MEMORY_LIMIT` (default 100) — entries kept in `state.logs`
+//! - `LOG_RETENTION_DAYS` (default 30, 0 = keep forever) — older entries are
+//!   dropped from the file at startup and hourly
+//!
+//! Annotations (`update-log`) append the entry again; the last line for an id wins.
+//!
+//! `get-logs` pages through the file, newest first:
+//!
+//! ```json
+//! { "category": "JURY", "fromMs": 0, "toMs": 0, "offset": 0, "limit": 100 }
+//! ```
+//!
+//! answered with `logs-page { logs, total, offset, nextOffset }`.
+//!
+//! ## Invariants
+//! - Core Invariant #8: file I/O runs after the state lock is released
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::handlers::now_ms;
+use crate::state::{LogCategory, LogEntry, RaceState};
+
+const DEFAULT_PAGE: usize = 100;
+const MAX_PAGE: usize = 1000;
+const RETENTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+pub struct LogStoreConfig {
+    pub path: PathBuf,
+    pub memory_limit: usize,
+    /// 0 = keep forever
+    pub retention_ms: i64,
+}
+
+impl Default for LogStoreConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        Self {
+            path: var("LOG_STORE_PATH").unwrap_or_else(|| "/data/logs.jsonl".to_string()).into(),
+            memory_limit: var("LOG_MEMORY_LIMIT").and_then(|v| v.parse().ok()).filter(|n| *n > 0).unwrap_or(100),
+            retention_ms: var("LOG_RETENTION_DAYS").and_then(|v| v.parse::<i64>().ok()).unwrap_or(30) * 86_400_000,
+        }
+    }
+}
+
+static CONFIG: LazyLock<LogStoreConfig> = LazyLock::new(LogStoreConfig::default);
+/// Serialises appends against the retention rewrite
+static FILE: Mutex<()> = Mutex::const_new(());
+static SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Unique log id; several entries can share a millisecond.
+pub fn next_id() -> String {
+    format!("log-{}-{}", now_ms(), SEQ.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Add `log` to the in-memory window, dropping the oldest past `LOG_MEMORY_LIMIT`.
+pub fn push(state: &mut RaceState, log: LogEntry) {
+    state.logs.push(log);
+    let excess = state.logs.len().saturating_sub(CONFIG.memory_limit);
+    state.logs.drain(..excess);
+}
+
+/// Append `log` to the store file.
+pub async fn append(log: &LogEntry) {
+    let Ok(mut line) = serde_json::to_string(log) else { return };
+    line.push('\n');
+    let _guard = FILE.lock().await;
+    let written = async {
+        let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(&CONFIG.path).await?;
+        file.write_all(line.as_bytes()).await
+    }.await;
+    if let Err(e) = written {
+        debug!("LogStore: append to {} failed: {e}", CONFIG.path.display());
+    }
+}
+
+/// Every stored entry, annotations applied, oldest first. Callers hold `FILE`.
+async fn read_all() -> Vec<LogEntry> {
+    let data = tokio::fs::read_to_string(&CONFIG.path).await.unwrap_or_default();
+    let mut order: Vec<String> = Vec::new();
+    let mut by_id: HashMap<String, LogEntry> = HashMap::new();
+    for line in data.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<LogEntry>(line) {
+            Ok(log) => {
+                if !by_id.contains_key(&log.id) {
+                    order.push(log.id.clone());
+                }
+                by_id.insert(log.id.clone(), log);
+            }
+            Err(e) => warn!("LogStore: skipping unreadable line: {e}"),
+        }
+    }
+    let mut logs: Vec<LogEntry> = order.into_iter().filter_map(|id| by_id.remove(&id)).collect();
+    logs.sort_by_key(|l| l.timestamp);
+    logs
+}
+
+/// Every stored entry, oldest first (event bundles).
+pub async fn all() -> Vec<LogEntry> {
+    let _guard = FILE.lock().await;
+    read_all().await
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogQuery {
+    pub category: Option<LogCategory>,
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogPage {
+    pub logs: Vec<LogEntry>,
+    pub total: usize,
+    pub offset: usize,
+    pub next_offset: Option<usize>,
+}
+
+/// One page of stored entries matching `query`, newest first.
+pub async fn query(query: &LogQuery) -> LogPage {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE);
+    let stored = {
+        let _guard = FILE.lock().await;
+        read_all().await
+    };
+    let matching: Vec<LogEntry> = stored.into_iter().rev()
+        .filter(|l| query.category.as_ref().is_none_or(|c| &l.category == c))
+        .filter(|l| query.from_ms.is_none_or(|from| l.timestamp >= from))
+        .filter(|l| query.to_ms.is_none_or(|to| l.timestamp <= to))
+        .collect();
+    let total = matching.len();
+    let logs: Vec<LogEntry> = matching.into_iter().skip(query.offset).take(limit).collect();
+    let end = query.offset + logs.len();
+    LogPage { logs, total, offset: query.offset, next_offset: (end < total).then_some(end) }
+}
+
+/// Rewrite the store without entries older than the retention period.
+async fn apply_retention() {
+    if CONFIG.retention_ms <= 0 {
+        return;
+    }
+    let cutoff = now_ms() - CONFIG.retention_ms;
+    let _guard = FILE.lock().await;
+    let logs = read_all().await;
+    let kept: Vec<&LogEntry> = logs.iter().filter(|l| l.timestamp >= cutoff).collect();
+    if kept.len() == logs.len() {
+        return;
+    }
+    let data: String = kept.iter()
+        .filter_map(|l| serde_json::to_string(l).ok())
+        .map(|l| l + "\n")
+        .collect();
+    let tmp = CONFIG.path.with_extension("jsonl.tmp");
+    let rewritten = async {
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, &CONFIG.path).await
+    }.await;
+    match rewritten {
+        Ok(()) => info!("LogStore: dropped {} entries past retention", logs.len() - kept.len()),
+        Err(e) => warn!("LogStore: retention rewrite of {} failed: {e}", CONFIG.path.display()),
+    }
+}
+
+pub async fn run_log_retention() {
+    let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+    loop {
+        interval.tick().await;
+        apply_retention().await;
+    }
+}
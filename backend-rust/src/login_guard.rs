@@ -0,0 +1,209 @@
+//! # login_guard
+//!
+//! Throttles `register`, so tokens and passwords cannot be guessed at socket speed.
+//!
+//! Attempts are tracked per socket and per client address (`client_address`):
+//! the TCP peer, or the address a proxy listed in `TRUSTED_PROXIES`
+//! (comma-separated IPs, e.g. Fly's edge) forwarded for it. Forwarding headers
+//! from any other peer are ignored, so a client cannot pick its own address:
+//!
+//! - **Rate limit** — at most `LOGIN_ATTEMPTS_PER_MIN` attempts a minute
+//!   (default 10), as a token bucket of that size; a successful attempt gives
+//!   its token back.
+//! - **Backoff** — after `n` consecutive failures the next attempt is refused for
+//!   `LOGIN_BACKOFF_BASE_MS × 2^(n-1)` (default 500 ms), capped at a minute.
+//! - **Lockout** — `LOGIN_LOCKOUT_FAILURES` consecutive failures (default 10)
+//!   lock the address or socket out for `LOGIN_LOCKOUT_SECS` (default 900). The
+//!   directors get `auth-alert` and the chain an `AUTH_EVENT` `LOCKED_OUT`.
+//!
+//! A successful login clears the failures. Refused attempts are answered with
+//! `auth-error { error, retryAfterMs }` and never reach token verification.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use serde::Serialize;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Entries idle this long are forgotten
+const FORGET_AFTER: Duration = Duration::from_secs(3600);
+
+pub struct LoginGuardConfig {
+    pub attempts_per_min: f64,
+    pub backoff_base: Duration,
+    pub lockout_failures: u32,
+    pub lockout: Duration,
+}
+
+impl Default for LoginGuardConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<u64>().ok()).filter(|v| *v > 0);
+        Self {
+            attempts_per_min: var("LOGIN_ATTEMPTS_PER_MIN").unwrap_or(10) as f64,
+            backoff_base: Duration::from_millis(var("LOGIN_BACKOFF_BASE_MS").unwrap_or(500)),
+            lockout_failures: var("LOGIN_LOCKOUT_FAILURES").unwrap_or(10) as u32,
+            lockout: Duration::from_secs(var("LOGIN_LOCKOUT_SECS").unwrap_or(900)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RefusalReason {
+    RateLimited,
+    Backoff,
+    LockedOut,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Refusal {
+    pub reason: RefusalReason,
+    /// `ip:<address>` or `socket:<id>`
+    pub key: String,
+    pub retry_after_ms: u64,
+}
+
+/// A lockout that a failure has just started.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lockout {
+    pub key: String,
+    pub failures: u32,
+    pub lockout_secs: u64,
+}
+
+struct Attempts {
+    tokens: f64,
+    refilled: Instant,
+    failures: u32,
+    blocked_until: Option<Instant>,
+    locked: bool,
+}
+
+pub struct LoginGuard {
+    config: LoginGuardConfig,
+    attempts: Mutex<HashMap<String, Attempts>>,
+}
+
+/// Peers whose forwarding headers name the client (`TRUSTED_PROXIES`)
+static TRUSTED_PROXIES: LazyLock<Vec<IpAddr>> = LazyLock::new(|| {
+    std::env::var("TRUSTED_PROXIES").unwrap_or_default()
+        .split(',')
+        .filter_map(|a| a.trim().parse().ok())
+        .collect()
+});
+
+/// Client address of a connection: the TCP peer, unless the peer is a trusted
+/// proxy, in which case the address it forwarded (`Fly-Client-IP`, the hop it
+/// appended to `X-Forwarded-For`, or `X-Real-IP`).
+pub fn client_address(peer: Option<IpAddr>, headers: &HeaderMap) -> Option<String> {
+    let peer = peer?;
+    if !TRUSTED_PROXIES.contains(&peer) {
+        return Some(peer.to_string());
+    }
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim).filter(|v| !v.is_empty());
+    header("fly-client-ip")
+        .or_else(|| header("x-forwarded-for").and_then(|v| v.rsplit(',').next()).map(str::trim))
+        .or_else(|| header("x-real-ip"))
+        .map(str::to_string)
+        .or_else(|| Some(peer.to_string()))
+}
+
+/// Keys an attempt counts against.
+pub fn keys(socket_id: &str, address: Option<&str>) -> Vec<String> {
+    let mut keys = vec![format!("socket:{socket_id}")];
+    if let Some(address) = address {
+        keys.push(format!("ip:{address}"));
+    }
+    keys
+}
+
+impl LoginGuard {
+    pub fn new(config: LoginGuardConfig) -> Self {
+        Self { config, attempts: Mutex::new(HashMap::new()) }
+    }
+
+    /// Take an attempt, or say why not and for how long.
+    pub fn check(&self, keys: &[String], now: Instant) -> Result<(), Refusal> {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        attempts.retain(|_, a| now.saturating_duration_since(a.refilled) < FORGET_AFTER || a.blocked_until.is_some_and(|t| t > now));
+
+        let burst = self.config.attempts_per_min;
+        for key in keys {
+            let entry = attempts.entry(key.clone()).or_insert(Attempts {
+                tokens: burst,
+                refilled: now,
+                failures: 0,
+                blocked_until: None,
+                locked: false,
+            });
+            if let Some(until) = entry.blocked_until.filter(|t| *t > now) {
+                return Err(Refusal {
+                    reason: if entry.locked { RefusalReason::LockedOut } else { RefusalReason::Backoff },
+                    key: key.clone(),
+                    retry_after_ms: until.saturating_duration_since(now).as_millis() as u64,
+                });
+            }
+            if entry.locked {
+                // Lockout over: a fresh start
+                entry.locked = false;
+                entry.failures = 0;
+            }
+            let elapsed = now.saturating_duration_since(entry.refilled).as_secs_f64();
+            entry.tokens = (entry.tokens + elapsed * burst / 60.0).min(burst);
+            entry.refilled = now;
+            if entry.tokens < 1.0 {
+                return Err(Refusal {
+                    reason: RefusalReason::RateLimited,
+                    key: key.clone(),
+                    retry_after_ms: ((1.0 - entry.tokens) * 60_000.0 / burst) as u64,
+                });
+            }
+        }
+        for key in keys {
+            if let Some(entry) = attempts.get_mut(key) {
+                entry.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Count a failed attempt; returns the lockouts it started.
+    pub fn record_failure(&self, keys: &[String], now: Instant) -> Vec<Lockout> {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        let mut lockouts = Vec::new();
+        for key in keys {
+            let Some(entry) = attempts.get_mut(key) else { continue };
+            entry.failures += 1;
+            if entry.failures >= self.config.lockout_failures {
+                entry.locked = true;
+                entry.blocked_until = Some(now + self.config.lockout);
+                lockouts.push(Lockout {
+                    key: key.clone(),
+                    failures: entry.failures,
+                    lockout_secs: self.config.lockout.as_secs(),
+                });
+            } else {
+                let backoff = self.config.backoff_base.saturating_mul(1 << (entry.failures - 1).min(16)).min(MAX_BACKOFF);
+                entry.blocked_until = Some(now + backoff);
+            }
+        }
+        lockouts
+    }
+
+    pub fn record_success(&self, keys: &[String]) {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        for key in keys {
+            if let Some(entry) = attempts.get_mut(key) {
+                entry.failures = 0;
+                entry.blocked_until = None;
+                // Only failures use up the rate, a club behind one address reconnecting at once is fine
+                entry.tokens = (entry.tokens + 1.0).min(self.config.attempts_per_min);
+            }
+        }
+    }
+}
@@ -1,22 +1,96 @@
 mod handlers;
 mod persistence;
+mod state_store;
+mod state_schema;
+mod snapshots;
 mod auth;
+mod local_auth;
+mod login_guard;
+mod oidc;
 mod procedure_engine;
 mod state;
 mod flight_engine;
+mod flight_schedule;
+mod league;
+mod knockout;
+mod officials;
+mod schedule_export;
 mod audit;
 mod uwb_hub;
+mod node_auth;
+mod control_plane;
 mod trilateration;
 mod auto_director;
+mod incidents;
+mod highlights;
 mod ranking_engine;
+mod protest_replay;
+mod audit_uploader;
+mod audit_store;
+mod audit_checkpoint;
+mod audit_anchor;
+mod node_chains;
+mod measurement_recorder;
+mod procedure_templates;
+mod procedure_validator;
+mod rule_packs;
+mod time_discipline;
+mod pursuit;
+mod ocs_recall;
+mod time_limits;
+mod signal_outputs;
+mod engine_bus;
+mod rehearsal;
+mod mark_rounding;
+mod scoring;
+mod results_export;
+mod protests;
+mod penalties;
+mod race_session;
+mod regatta;
+mod roles;
+mod sessions;
+mod permissions;
+mod rest_api;
+mod rate_limit;
+mod state_delta;
+mod boat_scope;
+mod entries;
+mod event_bundle;
+mod handicap;
+mod boat_liveness;
+mod spectator;
+mod messaging;
+mod blacklist;
+mod boat_classes;
+mod line_bias;
+mod laylines;
+mod time_sync;
+mod course_templates;
+mod crew;
+mod marksetbot;
+mod weather;
+mod tide;
+mod track_store;
+mod log_store;
+mod recording;
+mod playback;
+mod telemetry;
+mod broadcast_feed;
+mod camera_assignment;
+mod camera_pointing;
+mod vision_switcher;
+mod config;
 pub mod cloud_sync;
 pub mod edge_network;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use axum::extract::{Path, Query};
+use axum::http::{HeaderMap, StatusCode};
 use axum::routing::get;
 use axum::Router;
 use serde_json::json;
@@ -27,11 +101,13 @@ use axum::http::HeaderValue;
 use tracing::{info, warn};
 
 use auth::AuthEngine;
+use engine_bus::{EngineBus, MAIN_ENGINE};
 use audit::AuditLogger;
-use handlers::{on_connect, DeadBoats, SharedEngine, SharedState};
-use persistence::load_state;
+use handlers::{audit_status_change, on_connect, ClassEngines, SharedEngine, SharedState};
+use persistence::{load_class_engine_states, load_engine_state, load_state, save_class_engine_states, save_engine_state};
 use procedure_engine::{ProcedureEngine, TickResult};
-use state::{RaceStatus, SequenceInfo};
+use boat_scope::FULL_STATE_EXCEPT;
+use state::{ClassSequenceUpdate, Countdown, RaceStatus, SequenceInfo, UpcomingSignal};
 use uwb_hub::{start_uwb_hub, UwbHubConfig};
 use auto_director::start_auto_director;
 use ranking_engine::start_ranking_engine;
@@ -41,12 +117,16 @@ static STARTUP_MS: AtomicU64 = AtomicU64::new(0);
 
 // ─── Time Sync Endpoint ───────────────────────────────────────────────────────
 
-async fn time_sync() -> axum::Json<serde_json::Value> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis();
-    axum::Json(json!({ "serverTime": now }))
+// GET /sync?t0=<client send ms> → { t0, t1, t2, serverTime, clock } (see `time_sync`)
+
+#[derive(serde::Deserialize)]
+struct SyncQuery {
+    t0: Option<f64>,
+}
+
+async fn time_sync(Query(query): Query<SyncQuery>) -> axum::Json<serde_json::Value> {
+    let t1 = time_discipline::now_ms();
+    axum::Json(time_sync::reply(query.t0, t1))
 }
 
 // ─── Health Endpoint (required by Fly.io + cloud deployment) ─────────────────
@@ -68,33 +148,235 @@ async fn health_check() -> axum::Json<serde_json::Value> {
     }))
 }
 
+// ─── Protest Replay Endpoint ──────────────────────────────────────────────────
+// GET /replay?sessionId=..&fromMs=..&toMs=..  (Authorization: Bearer <jury/director JWT>)
+async fn protest_replay(
+    headers: HeaderMap,
+    Query(query): Query<protest_replay::ReplayQuery>,
+    auth: Arc<AuthEngine>,
+    audit: AuditLogger,
+) -> Result<axum::Json<protest_replay::ReplayReport>, StatusCode> {
+    let token = headers.get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let role = rest_api::token_role(&auth, token).await.ok_or(StatusCode::UNAUTHORIZED)?;
+    if role != "jury" && role != "director" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    audit.log_protest_replay(&role, &json!(query)).await;
+    protest_replay::ProtestReplayEngine::new(audit.files_for(query.session_id.as_deref()).await)
+        .replay(&query)
+        .await
+        .map(axum::Json)
+        .map_err(|e| {
+            warn!("Protest replay failed to read audit log: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// ─── Procedure Template Library (read-only REST mirror) ──────────────────────
+
+async fn list_procedure_templates(shared: SharedState) -> axum::Json<Vec<state::ProcedureTemplate>> {
+    axum::Json(procedure_templates::list(&shared.read().await.procedure_templates))
+}
+
+async fn export_procedure_template(
+    Path(id): Path<String>,
+    shared: SharedState,
+) -> Result<axum::Json<serde_json::Value>, StatusCode> {
+    procedure_templates::find(&shared.read().await.procedure_templates, &id)
+        .map(|t| axum::Json(procedure_templates::export_document(&t)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// ─── Results Export (CSV / Sailwave) ─────────────────────────────────────────
+
+#[derive(serde::Deserialize)]
+struct ResultsQuery {
+    race: Option<u32>,
+}
+
+fn csv_download(filename: &str, body: String) -> impl axum::response::IntoResponse {
+    (
+        [
+            (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        body,
+    )
+}
+
+async fn export_results_csv(Query(query): Query<ResultsQuery>, shared: SharedState) -> impl axum::response::IntoResponse {
+    let body = results_export::csv(&*shared.read().await, query.race);
+    csv_download("results.csv", body)
+}
+
+async fn export_results_sailwave(shared: SharedState) -> impl axum::response::IntoResponse {
+    csv_download("results-sailwave.csv", results_export::sailwave(&*shared.read().await))
+}
+
+// ─── League Standings Export ─────────────────────────────────────────────────
+// GET /league/standings.csv → team standings across flights (see `league`)
+
+async fn export_league_standings_csv(shared: SharedState) -> impl axum::response::IntoResponse {
+    csv_download("league-standings.csv", league::csv(&*shared.read().await))
+}
+
+// ─── Schedule Export ─────────────────────────────────────────────────────────
+// GET /schedule.csv, /schedule.html → flights, pairings and planned starts (see `schedule_export`)
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScheduleQuery {
+    first: Option<String>,
+    race_minutes: Option<f64>,
+}
+
+async fn export_schedule_csv(Query(query): Query<ScheduleQuery>, shared: SharedState) -> impl axum::response::IntoResponse {
+    let first = query.first.as_deref().and_then(schedule_export::parse_time);
+    csv_download("schedule.csv", schedule_export::csv(&*shared.read().await, first, query.race_minutes))
+}
+
+async fn export_schedule_html(Query(query): Query<ScheduleQuery>, shared: SharedState) -> axum::response::Html<String> {
+    let first = query.first.as_deref().and_then(schedule_export::parse_time);
+    axum::response::Html(schedule_export::html(&*shared.read().await, first, query.race_minutes))
+}
+
+// ─── Highlights Export ───────────────────────────────────────────────────────
+// GET /highlights, /highlights.csv → marked moments, optionally of one race (see `highlights`)
+
+#[derive(serde::Deserialize)]
+struct HighlightsQuery {
+    race: Option<String>,
+}
+
+async fn export_highlights(Query(query): Query<HighlightsQuery>, shared: SharedState) -> axum::Json<serde_json::Value> {
+    let state = shared.read().await;
+    axum::Json(serde_json::json!(highlights::list(&state, query.race.as_deref())))
+}
+
+async fn export_highlights_csv(Query(query): Query<HighlightsQuery>, shared: SharedState) -> impl axum::response::IntoResponse {
+    csv_download("highlights.csv", highlights::csv(&*shared.read().await, query.race.as_deref()))
+}
+
+// ─── Broadcast Graphics Feed ─────────────────────────────────────────────────
+// GET /broadcast-feed → the latest `broadcast-feed` payload (see `broadcast_feed`)
+
+async fn broadcast_feed_snapshot(shared: SharedState) -> axum::Json<serde_json::Value> {
+    axum::Json(broadcast_feed::feed(&*shared.read().await))
+}
+
+// ─── Configuration Readback ──────────────────────────────────────────────────
+// GET /config → the effective `config::BackendConfig`, secrets redacted (director only)
+
+async fn config_readback(headers: HeaderMap, auth: Arc<AuthEngine>) -> Result<axum::Json<config::BackendConfig>, StatusCode> {
+    let token = headers.get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let role = rest_api::token_role(&auth, token).await.ok_or(StatusCode::UNAUTHORIZED)?;
+    if role != "director" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(axum::Json(config::effective()))
+}
+
+// ─── UWB Node Authentication ─────────────────────────────────────────────────
+// GET /uwb/auth-rejections → rejected UWB packets per source address (director only, see `node_auth`)
+
+async fn uwb_auth_rejections(
+    headers: HeaderMap,
+    auth: Arc<AuthEngine>,
+    node_auth: Arc<node_auth::NodeAuth>,
+) -> Result<axum::Json<HashMap<String, node_auth::SourceRejections>>, StatusCode> {
+    let token = headers.get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let role = rest_api::token_role(&auth, token).await.ok_or(StatusCode::UNAUTHORIZED)?;
+    if role != "director" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(axum::Json(node_auth.rejections()))
+}
+
+// GET /uwb/hub-key → the hub's Ed25519 public key for verifying multicast (any role, see `control_plane`)
+
+async fn uwb_hub_key(
+    headers: HeaderMap,
+    auth: Arc<AuthEngine>,
+    signer: Arc<control_plane::HubSigner>,
+) -> Result<axum::Json<serde_json::Value>, StatusCode> {
+    let token = headers.get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    rest_api::token_role(&auth, token).await.ok_or(StatusCode::UNAUTHORIZED)?;
+    Ok(axum::Json(serde_json::json!({ "algorithm": "Ed25519", "publicKey": signer.public_hex() })))
+}
+
 // ─── Procedure Engine Tick Task ───────────────────────────────────────────────
 
 async fn run_engine_tick(
     engine: SharedEngine,
     shared: SharedState,
     io: SocketIo,
-    ocs_tx: tokio::sync::mpsc::Sender<uwb_hub::OcsEvent>,
+    audit: AuditLogger,
+    bus: EngineBus,
 ) {
     let mut interval = tokio::time::interval(Duration::from_millis(200)); // 5Hz
+    let mut saved_revision = engine.read().await.revision();
+    let mut countdown_sent: Option<(u64, u64)> = None;
     loop {
         interval.tick().await;
 
         let mut eng = engine.write().await;
         if !eng.is_running() {
+            // A stop still reports its status change
+            bus.publish(MAIN_ENGINE, eng.drain_events());
+            let snapshot_due = eng.revision() != saved_revision;
+            saved_revision = eng.revision();
+            drop(eng);
+            if snapshot_due {
+                if let Err(e) = save_engine_state(None).await {
+                    warn!("Failed to clear engine state: {e}");
+                }
+            }
             continue;
         }
 
         let result = eng.tick();
+        if let Some(signal) = eng.upcoming_signal() {
+            let _ = io.emit("upcoming-signal", &signal);
+        }
+        // Published once RaceState reflects the tick, so subscribers (OCS recall
+        // judging reports against the gun time) read the state the events describe
+        let events = eng.drain_events();
+        // High-resolution countdown: once per second, and immediately whenever the engine moved
+        let countdown_key = (eng.revision(), time_discipline::now_ms() / 1000);
+        if countdown_sent != Some(countdown_key) {
+            countdown_sent = Some(countdown_key);
+            if let Some(countdown) = eng.countdown() {
+                let _ = io.emit("countdown", &countdown);
+            }
+        }
+        // Persist runtime state whenever the engine moved (start, transition, gun, trigger…)
+        let snapshot = (eng.revision() != saved_revision).then(|| eng.snapshot());
+        saved_revision = eng.revision();
         drop(eng);
+        if let Some(snapshot) = snapshot {
+            if let Err(e) = save_engine_state(snapshot.as_ref()).await {
+                warn!("Failed to persist engine state: {e}");
+            }
+        }
 
         match result {
             TickResult::Idle => {}
             TickResult::Update(upd) => {
-                // Sync race status from engine's node-level mapping
-                let eng = engine.read().await;
-                let engine_status = eng.current_race_status();
-                drop(eng);
+                // Sync race status from engine's node-level mapping (audited from the bus)
+                let engine_status = engine.read().await.current_race_status();
 
                 {
                     let mut state = shared.write().await;
@@ -112,10 +394,8 @@ async fn run_engine_tick(
                 info!("🏁 T-0 GUN FIRED: Transition to RACING state!");
 
                 // Sync race status
-                let eng = engine.read().await;
-                let engine_status = eng.current_race_status();
-                drop(eng);
-                
+                let engine_status = engine.read().await.current_race_status();
+
                 {
                     let mut state = shared.write().await;
                     state.status = engine_status;
@@ -123,19 +403,22 @@ async fn run_engine_tick(
                     state.sequence_time_remaining = Some(upd.sequence_time_remaining);
                     state.current_node_id = Some(upd.current_node_id.clone());
                     state.action_label = upd.action_label.clone();
+                    // Gun time — OCS reports are judged relative to it
+                    let gun_ms = handlers::now_ms();
+                    state.start_time = Some(gun_ms);
+                    if let Some(p) = state.pursuit.as_mut() {
+                        pursuit::arm_at_gun(p, gun_ms);
+                    }
                 }
-                
-                // Trigger the UWB Concurrent Batch Solve for sub-cm OCS Detection
-                uwb_hub::trigger_batch_solve(&ocs_tx).await;
-                
+
                 let _ = io.emit("sequence-update", &upd);
             }
             TickResult::SequenceComplete => {
                 info!("Sequence complete — race finished");
-                let finish_time = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis() as i64;
+                let finish_time = time_discipline::now_ms() as i64;
+
+                let status_before = shared.read().await.status.clone();
+                audit_status_change(&audit, &status_before, &RaceStatus::Finished, "sequence complete").await;
 
                 {
                     let mut state = shared.write().await;
@@ -145,48 +428,118 @@ async fn run_engine_tick(
                         flags: vec![],
                     });
                     state.sequence_time_remaining = Some(0.0);
+                    // Close the race into the series; a later score-race for the same start adds to it
+                    let race_number = scoring::close_race(&mut state).race_number;
+                    info!("Race {race_number} scored");
+                    if let Err(e) = persistence::save_state(&state).await {
+                        warn!("Failed to persist results: {e}");
+                    }
+                    let _ = io.emit("standings", &json!({ "standings": state.standings, "results": state.results }));
                 }
 
                 let state = shared.read().await;
                 let _ = io.emit("race-finished", &json!({ "finishTime": finish_time }));
-                let _ = io.emit("state-update", &*state);
+                let _ = io.except(FULL_STATE_EXCEPT).emit("state-update", &*state);
             }
         }
+        bus.publish(MAIN_ENGINE, events);
     }
 }
 
-// ─── Tracker Disconnection Reaper Task ────────────────────────────────────────
+// ─── Class Sequence Tick Task ────────────────────────────────────────────────
 
-async fn run_tracker_reaper_tick(shared: SharedState, io: SocketIo) {
-    let mut interval = tokio::time::interval(Duration::from_secs(2));
+/// Ticks every concurrent per-class engine at 5Hz, mirroring `run_engine_tick`
+/// but publishing `class-sequence-update` and per-class status in `RaceState`.
+async fn run_class_engine_tick(
+    class_engines: ClassEngines,
+    shared: SharedState,
+    io: SocketIo,
+    bus: EngineBus,
+) {
+    let mut interval = tokio::time::interval(Duration::from_millis(200)); // 5Hz
+    let mut saved_revisions: HashMap<String, u64> = HashMap::new();
+    let mut countdowns_sent: HashMap<String, (u64, u64)> = HashMap::new();
     loop {
         interval.tick().await;
 
-        let now_ms = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as i64;
-
-        let mut dropped_boats = Vec::new();
+        let mut results = Vec::new();
+        let mut events = Vec::new();
+        let mut snapshots = None;
         {
-            let mut state = shared.write().await;
-            
-            // Remove boats that haven't sent a telemetry ping in over 15 seconds!
-            state.boats.retain(|id, boat| {
-                let diff = now_ms - boat.timestamp;
-                if diff > 15_000 {
-                    dropped_boats.push(id.clone());
-                    false // Remove
-                } else {
-                    true // Retain
+            let mut engines = class_engines.write().await;
+            // Postponed or recalled classes keep their stopped engine for the resume
+            let held: HashSet<String> = shared.read().await.class_sequences.iter()
+                .filter(|(_, c)| matches!(c.status, RaceStatus::Postponed | RaceStatus::GeneralRecall))
+                .map(|(id, _)| id.clone())
+                .collect();
+            for (class_id, eng) in engines.iter_mut() {
+                if eng.is_running() {
+                    let result = eng.tick();
+                    if let Some(signal) = eng.upcoming_signal() {
+                        let _ = io.emit("upcoming-signal", &UpcomingSignal { class_id: Some(class_id.clone()), ..signal });
+                    }
+                    results.push((class_id.clone(), result, eng.current_race_status()));
+
+                    let countdown_key = (eng.revision(), time_discipline::now_ms() / 1000);
+                    if countdowns_sent.insert(class_id.clone(), countdown_key) != Some(countdown_key) {
+                        if let Some(countdown) = eng.countdown() {
+                            let _ = io.emit("countdown", &Countdown { class_id: Some(class_id.clone()), ..countdown });
+                        }
+                    }
                 }
-            });
+                events.push((class_id.clone(), eng.drain_events()));
+            }
+            engines.retain(|id, eng| eng.is_running() || held.contains(id));
+            countdowns_sent.retain(|id, _| engines.contains_key(id));
+
+            let revisions: HashMap<String, u64> = engines.iter().map(|(id, eng)| (id.clone(), eng.revision())).collect();
+            if revisions != saved_revisions {
+                snapshots = Some(engines.iter()
+                    .filter_map(|(id, eng)| eng.snapshot().map(|snap| (id.clone(), snap)))
+                    .collect::<HashMap<_, _>>());
+                saved_revisions = revisions;
+            }
         }
+        if let Some(snapshots) = snapshots {
+            if let Err(e) = save_class_engine_states(&snapshots).await {
+                warn!("Failed to persist class engine state: {e}");
+            }
+        }
+
+        for (class_id, result, engine_status) in results {
+            let (update, status) = match result {
+                TickResult::Idle => continue,
+                TickResult::Update(upd) => (Some(upd), engine_status),
+                TickResult::GunFired(upd) => {
+                    info!("🏁 Class {class_id}: starting signal");
+                    (Some(upd), engine_status)
+                }
+                TickResult::SequenceComplete => (None, RaceStatus::Racing),
+            };
+            {
+                let mut state = shared.write().await;
+                let class_state = state.class_sequences.entry(class_id.clone()).or_default();
+                class_state.status = status.clone();
+                if status == RaceStatus::Racing && class_state.start_time.is_none() {
+                    class_state.start_time = Some(time_discipline::now_ms() as i64);
+                }
+                if let Some(upd) = &update {
+                    class_state.apply(upd);
+                }
+            }
 
-        if !dropped_boats.is_empty() {
-            tracing::info!("Reaper dropped dead trackers: {:?}", dropped_boats);
-            let state = shared.read().await;
-            let _ = io.emit("state-update", &*state);
+            match update {
+                Some(upd) => {
+                    let _ = io.emit("class-sequence-update", &ClassSequenceUpdate { class_id, update: upd });
+                }
+                None => {
+                    let state = shared.read().await;
+                    let _ = io.except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+                }
+            }
+        }
+        for (class_id, events) in events {
+            bus.publish(&class_id, events);
         }
     }
 }
@@ -210,6 +563,37 @@ async fn main() {
         )
         .init();
 
+    // config.toml under the environment (see `config`)
+    config::load();
+
+    // Offline tool: `regatta-backend merge-node-chains <node.jsonl>...`
+    // Cross-checks node microSD chains against the hub chain, prints the JSON report and exits
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("merge-node-chains") {
+        let store = audit_store::AuditStore::open(audit_store::AuditStoreConfig::default()).await;
+        let node_files: Vec<std::path::PathBuf> = args[2..].iter().map(Into::into).collect();
+        match node_chains::merge_node_chains(&store.all_files(), &node_files, store.dir()).await {
+            Ok(report) => {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+                std::process::exit(if report.consistent { 0 } else { 1 });
+            }
+            Err(e) => {
+                eprintln!("merge-node-chains failed: {e}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Offline tool: `regatta-backend local-auth hash|issue …` (see `local_auth`)
+    if args.get(1).map(String::as_str) == Some("local-auth") {
+        std::process::exit(local_auth::run_tool(&args[2..]));
+    }
+
+    // Offline tool: `regatta-backend uwb-keys provision|hub-public …` (see `control_plane`)
+    if args.get(1).map(String::as_str) == Some("uwb-keys") {
+        std::process::exit(control_plane::run_tool(&args[2..]));
+    }
+
     // Log backend mode (local file persistence vs cloud Supabase)
     let backend_mode = std::env::var("BACKEND_MODE").unwrap_or_else(|_| "local".into());
     info!("🏁 Regatta Pro Backend (Rust) v{} starting — mode: {backend_mode}",
@@ -225,76 +609,243 @@ async fn main() {
         }
     }
 
-    // Load persisted state
-    let race_state = load_state().await;
+    // Disciplined wall clock (NTP / GPS) for gun and status-change times
+    time_discipline::spawn(time_discipline::TimeConfig::default());
+
+    // Load persisted state (file, SQLite or Postgres — see `state_store`)
+    state_store::init().await;
+    let mut race_state = load_state().await;
+    track_store::restore(&mut race_state).await;
+
+    // Resume a start sequence interrupted by a crash/restart (wall-clock based)
+    let mut procedure_engine = ProcedureEngine::new();
+    if let Some(snapshot) = load_engine_state().await {
+        procedure_engine.restore(snapshot);
+        race_state.status = procedure_engine.current_race_status();
+        race_state.current_procedure = procedure_engine.graph.clone();
+        if let Some(upd) = procedure_engine.build_update() {
+            race_state.current_sequence = Some(upd.current_sequence);
+            race_state.sequence_time_remaining = Some(upd.sequence_time_remaining);
+            race_state.current_node_id = Some(upd.current_node_id);
+        }
+        info!("⏱️ Resumed procedure sequence (status: {:?})", race_state.status);
+    }
+
+    // Resume any per-class sequences the same way
+    let mut engines = HashMap::new();
+    for (class_id, snapshot) in load_class_engine_states().await {
+        let mut eng = ProcedureEngine::new();
+        eng.restore(snapshot);
+        let class_state = race_state.class_sequences.entry(class_id.clone()).or_default();
+        class_state.status = eng.current_race_status();
+        if let Some(upd) = eng.build_update() {
+            class_state.apply(&upd);
+        }
+        engines.insert(class_id, eng);
+    }
+    let class_engines: ClassEngines = Arc::new(RwLock::new(engines));
+
     let shared: SharedState = Arc::new(RwLock::new(race_state));
-    let engine: SharedEngine = Arc::new(RwLock::new(ProcedureEngine::new()));
-    let dead_boats: DeadBoats = Arc::new(RwLock::new(HashSet::new()));
+    let engine: SharedEngine = Arc::new(RwLock::new(procedure_engine));
+    let rehearsals: rehearsal::Rehearsals = Arc::new(RwLock::new(HashMap::new()));
     
     // Auth Engine
     let auth_engine = AuthEngine::new();
+    let roles_key = roles::key(&*shared.read().await);
+    match roles::load(&roles_key).await {
+        Ok(custom) => auth_engine.set_custom_roles(custom).await,
+        Err(e) => warn!("Roles: failed to load {roles_key}: {e}"),
+    }
+    let auth_clone = auth_engine.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(oidc::refresh_interval());
+        loop {
+            interval.tick().await;
+            auth_clone.refresh_oidc_keys().await;
+        }
+    });
     let auth_clone = auth_engine.clone();
     tokio::spawn(async move {
-        auth_clone.refresh_apple_keys().await;
-        let mut interval = tokio::time::interval(Duration::from_secs(86400));
+        let mut interval = tokio::time::interval(auth_clone.supabase_refresh_interval());
         loop {
             interval.tick().await;
-            auth_clone.refresh_apple_keys().await;
+            auth_clone.refresh_supabase_keys().await;
         }
     });
 
     // Audit Logger (SHA-256 chained, satisfies Invariant #2)
-    let audit_logger = AuditLogger::new();
+    let audit_logger = AuditLogger::open(audit_store::AuditStoreConfig::default()).await;
+    audit_logger.set_session(race_session::audit_session(&*shared.read().await)).await;
+    match audit_logger.verify_file().await {
+        Ok(reports) => {
+            for report in reports {
+                let session = report.session_id.as_deref().unwrap_or("legacy");
+                if report.valid {
+                    info!("🔗 Audit chain [{session}] verified: {} blocks across {} segment(s)", report.blocks_checked, report.segments);
+                } else {
+                    warn!("⚠️ Audit chain [{session}] BROKEN after {} valid blocks: {:?}", report.blocks_checked, report.first_broken);
+                }
+            }
+        }
+        Err(e) => warn!("Audit chain verification could not read log: {e}"),
+    }
     audit_logger.log_session_event("server_start", Some(serde_json::json!({
         "version": env!("CARGO_PKG_VERSION"),
         "mode": backend_mode,
     }))).await;
+    audit_anchor::spawn(audit_anchor::AnchorConfig::default(), audit_logger.clone(), audit_logger.dir().await);
 
     // UWB Hub (UDP listener on :5555, satisfies Invariant #1 path)
-    let (ocs_tx, _ocs_rx) = tokio::sync::mpsc::channel::<uwb_hub::OcsEvent>(64);
+    let (ocs_tx, ocs_rx) = tokio::sync::mpsc::channel::<uwb_hub::OcsEvent>(64);
+    let (anchor_tx, anchor_rx) = tokio::sync::mpsc::channel::<line_bias::AnchorFix>(64);
     let uwb_config = UwbHubConfig::default();
     
-    // We clone the sender so the engine tick can use it too
-    let ocs_tx_tick = ocs_tx.clone();
-    tokio::spawn(start_uwb_hub(uwb_config, ocs_tx));
+    // The main starting signal on the engine bus triggers the hub's gun batch solve
+    let (gun_tx, gun_rx) = tokio::sync::mpsc::channel::<uwb_hub::GunSignal>(4);
+    let recorder = measurement_recorder::MeasurementRecorder::spawn(
+        measurement_recorder::RecorderConfig::default(),
+        audit_logger.clone(),
+    );
+    let node_auth = Arc::new(node_auth::NodeAuth::load());
+    let hub_signer = Arc::new(control_plane::HubSigner::load());
+    let (downlink, downlink_rx) = control_plane::ControlDownlink::channel();
+    tokio::spawn(start_uwb_hub(
+        uwb_config,
+        ocs_tx,
+        anchor_tx,
+        recorder,
+        node_auth.clone(),
+        control_plane::ControlPlaneConfig::default(),
+        hub_signer.clone(),
+        downlink_rx,
+        audit_logger.clone(),
+        gun_rx,
+    ));
 
     // Build Socket.IO layer with massively expanded payload capacity for Base64 Video
     let (socket_layer, io) = SocketIo::builder()
         .max_payload(10_000_000) // 10MB
         .build_layer();
 
+    // Merkle-root checkpoints over the audit chain, broadcast to clients (+ optional witness URL)
+    audit_logger.attach_checkpoints(audit_checkpoint::CheckpointPublisher::spawn(
+        audit_checkpoint::CheckpointConfig::default(),
+        io.clone(),
+    )).await;
+
     // Clone refs for socket handler
     let shared_sock = shared.clone();
     let engine_sock = engine.clone();
-    let dead_sock = dead_boats.clone();
+    let class_engines_sock = class_engines.clone();
+    let rehearsals_sock = rehearsals.clone();
+    let delta = state_delta::StateDelta::new(serde_json::to_value(&*shared.read().await).unwrap_or_default());
+    let delta_sock = delta.clone();
     let auth_sock = auth_engine.clone();
+    let audit_sock = audit_logger.clone();
+    let downlink_sock = downlink.clone();
 
     io.ns("/", move |socket: socketioxide::extract::SocketRef| {
         let shared = shared_sock.clone();
         let engine = engine_sock.clone();
-        let dead_boats = dead_sock.clone();
+        let class_engines = class_engines_sock.clone();
+        let rehearsals = rehearsals_sock.clone();
+        let delta = delta_sock.clone();
         let auth_engine = auth_sock.clone();
+        let audit = audit_sock.clone();
+        let downlink = downlink_sock.clone();
         async move {
-            on_connect(socket, shared, engine, dead_boats, auth_engine).await;
+            on_connect(socket, shared, engine, class_engines, rehearsals, delta, auth_engine, audit, downlink).await;
+        }
+    });
+
+    // Public tracking, no token required
+    let shared_spectate = shared.clone();
+    io.ns(spectator::NAMESPACE, move |socket: socketioxide::extract::SocketRef| {
+        let shared = shared_spectate.clone();
+        async move {
+            spectator::on_connect(socket, shared).await;
+        }
+    });
+
+    // Recorded races, scrubbed through by jury and media
+    let auth_playback = auth_engine.clone();
+    let audit_playback = audit_logger.clone();
+    io.ns(playback::NAMESPACE, move |socket: socketioxide::extract::SocketRef, socketioxide::extract::Data::<serde_json::Value>(data)| {
+        let auth = auth_playback.clone();
+        let audit = audit_playback.clone();
+        async move {
+            playback::on_connect(socket, data, auth, audit).await;
         }
     });
 
     // Start execution task loops
-    tokio::spawn(run_engine_tick(engine.clone(), shared.clone(), io.clone(), ocs_tx_tick));
-    tokio::spawn(start_auto_director(shared.clone(), io.clone()));
+    let bus = EngineBus::new();
+    // Subscribers first, so nothing published by the tick loops is missed
+    signal_outputs::spawn(signal_outputs::SignalOutputConfig::default(), audit_logger.clone(), bus.subscribe());
+    tokio::spawn(engine_bus::run_status_audit(bus.subscribe(), audit_logger.clone()));
+    tokio::spawn(engine_bus::run_gun_solve(bus.subscribe(), gun_tx));
+    tokio::spawn(engine_bus::run_client_forwarder(bus.subscribe(), io.clone()));
+    let (focus_tx, focus_rx) = tokio::sync::watch::channel(auto_director::DirectorFocus::default());
+    tokio::spawn(start_auto_director(shared.clone(), io.clone(), bus.subscribe(), focus_tx));
+    tokio::spawn(camera_pointing::run_camera_pointing(camera_pointing::CameraPointingConfig::default(), shared.clone(), focus_rx.clone(), io.clone()));
+    tokio::spawn(vision_switcher::run_vision_switcher(vision_switcher::VisionSwitcherConfig::default(), focus_rx, io.clone()));
+    tokio::spawn(run_engine_tick(engine.clone(), shared.clone(), io.clone(), audit_logger.clone(), bus.clone()));
+    tokio::spawn(run_class_engine_tick(class_engines.clone(), shared.clone(), io.clone(), bus));
     tokio::spawn(start_ranking_engine(shared.clone(), io.clone()));
-    tokio::spawn(run_tracker_reaper_tick(shared.clone(), io.clone()));
+    tokio::spawn(spectator::run_spectator_feed(spectator::SpectatorConfig::default(), shared.clone(), io.clone()));
+    tokio::spawn(broadcast_feed::run_broadcast_feed(broadcast_feed::BroadcastFeedConfig::default(), shared.clone(), io.clone()));
+    tokio::spawn(boat_liveness::run_liveness_sweep(boat_liveness::LivenessConfig::default(), shared.clone(), io.clone()));
+    tokio::spawn(pursuit::run_pursuit_tick(shared.clone(), io.clone()));
+    tokio::spawn(rehearsal::run_rehearsal_tick(rehearsals, io.clone()));
+    tokio::spawn(boat_scope::run_scoped_feed(boat_scope::ScopeConfig::default(), shared.clone(), auth_engine.clone(), io.clone()));
+    tokio::spawn(state_delta::run_delta_broadcaster(state_delta::DeltaConfig::default(), delta, shared.clone(), io.clone()));
+    tokio::spawn(ocs_recall::run_ocs_recall(
+        ocs_recall::OcsRecallConfig::default(),
+        ocs_rx,
+        shared.clone(),
+        io.clone(),
+        audit_logger.clone(),
+    ));
+    tokio::spawn(sessions::run_session_expiry(
+        sessions::SessionConfig::default(),
+        auth_engine.clone(),
+        engine.clone(),
+        class_engines.clone(),
+        io.clone(),
+        audit_logger.clone(),
+    ));
+    tokio::spawn(time_limits::run_time_limits(
+        time_limits::TimeLimitConfig::default(),
+        shared.clone(),
+        engine.clone(),
+        audit_logger.clone(),
+        io.clone(),
+    ));
+    tokio::spawn(line_bias::run_line_bias(line_bias::LineBiasConfig::default(), anchor_rx, shared.clone(), io.clone()));
+    tokio::spawn(weather::run_weather_poll(weather::WeatherConfig::default(), shared.clone(), io.clone()));
+    tokio::spawn(tide::run_current_poll(tide::CurrentConfig::default(), shared.clone(), io.clone()));
+    tokio::spawn(log_store::run_log_retention());
+    tokio::spawn(persistence::run_state_saver(shared.clone(), io.clone()));
+    tokio::spawn(snapshots::run_snapshots(snapshots::SnapshotConfig::default(), shared.clone()));
+    tokio::spawn(recording::run_recorder(recording::RecordingConfig::default(), shared.clone()));
+    tokio::spawn(marksetbot::run_marksetbot(marksetbot::MarkSetBotConfig::default(), shared.clone(), io.clone()));
 
     // Phase 1: AWS Aurora Cloud Sync (Heartbeat & State Mirroring)
     if let Ok(db_url) = std::env::var("AURORA_DB_URL") {
         info!("AWS Setup: Activating CloudSyncManager for Aurora integration.");
         let redis_url = std::env::var("REDIS_URL").ok();
-        let session_id = "default".to_string(); // In a full app, generate/fetch this dynamically
+        // UUID of the race_sessions row registered for this regatta
+        let session_id = std::env::var("CLOUD_SESSION_ID").unwrap_or_else(|_| "default".to_string());
         if let Ok(cloud_sync) = cloud_sync::CloudSyncManager::connect(&db_url, redis_url, session_id).await {
             let sync_arc = Arc::new(cloud_sync);
             
-            // Wire AuditLogger to Aurora DB
-            audit_logger.attach_cloud_sync(sync_arc.clone()).await;
+            // Mirror the audit chain to Aurora `audit_log` (batched, outbox-backed)
+            match sync_arc.session_uuid() {
+                Some(session) => audit_logger.attach_uploader(
+                    audit_uploader::AuditUploader::spawn(sync_arc.pool(), audit_uploader::AuditTable::Aurora, session),
+                ).await,
+                None => warn!("CLOUD_SESSION_ID is not a registered session UUID, audit chain stays local-only"),
+            }
 
             // Spawn cloud sync background tasks
             tokio::spawn(sync_arc.clone().run_heartbeat_loop());
@@ -304,6 +855,27 @@ async fn main() {
         }
     }
 
+    // Supabase audit mirror — used when no Aurora uplink is configured
+    if std::env::var("AURORA_DB_URL").is_err() {
+        if let Ok(db_url) = std::env::var("SUPABASE_DB_URL") {
+            let session = std::env::var("CLOUD_SESSION_ID").ok().and_then(|id| uuid::Uuid::parse_str(&id).ok());
+            match (session, sqlx::postgres::PgPoolOptions::new()
+                .max_connections(2)
+                .acquire_timeout(Duration::from_secs(5))
+                .connect_lazy(&db_url))
+            {
+                (Some(session), Ok(pool)) => {
+                    info!("☁️ Mirroring audit chain to Supabase audit_log (session {session})");
+                    audit_logger.attach_uploader(
+                        audit_uploader::AuditUploader::spawn(pool, audit_uploader::AuditTable::Supabase, session),
+                    ).await;
+                }
+                (None, _) => warn!("CLOUD_SESSION_ID is not a registered session UUID, audit chain stays local-only"),
+                (_, Err(e)) => warn!("Invalid SUPABASE_DB_URL, audit chain stays local-only: {e}"),
+            }
+        }
+    }
+
     // CORS — local dev: http://localhost:3000; cloud: set CORS_ORIGINS=*
     // Fly.io env sets CORS_ORIGINS=* so native Mac apps, iOS apps, and
     // browsers from any origin can connect (secure via JWT auth layer).
@@ -327,9 +899,48 @@ async fn main() {
     };
 
     // Build Axum router
+    let audit_http = audit_logger.clone();
+    let auth_http = auth_engine.clone();
+    let config_auth = auth_engine.clone();
+    let uwb_auth = auth_engine.clone();
+    let hub_key_auth = auth_engine.clone();
+    let templates_http = shared.clone();
+    let template_http = shared.clone();
+    let results_http = shared.clone();
+    let sailwave_http = shared.clone();
+    let league_http = shared.clone();
+    let schedule_csv_http = shared.clone();
+    let schedule_html_http = shared.clone();
+    let telemetry_http = shared.clone();
+    let feed_http = shared.clone();
+    let highlights_http = shared.clone();
+    let highlights_csv_http = shared.clone();
+    let api = rest_api::router(rest_api::ApiContext {
+        shared: shared.clone(),
+        engine: engine.clone(),
+        auth: auth_engine.clone(),
+        audit: audit_logger.clone(),
+        io: io.clone(),
+    });
     let app = Router::new()
         .route("/health", get(health_check))   // Fly.io health check
         .route("/sync", get(time_sync))
+        .route("/replay", get(move |headers, query| protest_replay(headers, query, auth_http.clone(), audit_http.clone())))
+        .route("/procedure-templates", get(move || list_procedure_templates(templates_http.clone())))
+        .route("/procedure-templates/:id", get(move |path| export_procedure_template(path, template_http.clone())))
+        .route("/results.csv", get(move |query| export_results_csv(query, results_http.clone())))
+        .route("/results/sailwave.csv", get(move || export_results_sailwave(sailwave_http.clone())))
+        .route("/league/standings.csv", get(move || export_league_standings_csv(league_http.clone())))
+        .route("/schedule.csv", get(move |query| export_schedule_csv(query, schedule_csv_http.clone())))
+        .route("/schedule.html", get(move |query| export_schedule_html(query, schedule_html_http.clone())))
+        .route("/highlights", get(move |query| export_highlights(query, highlights_http.clone())))
+        .route("/highlights.csv", get(move |query| export_highlights_csv(query, highlights_csv_http.clone())))
+        .route("/broadcast-feed", get(move || broadcast_feed_snapshot(feed_http.clone())))
+        .route("/config", get(move |headers| config_readback(headers, config_auth.clone())))
+        .route("/uwb/auth-rejections", get(move |headers| uwb_auth_rejections(headers, uwb_auth.clone(), node_auth.clone())))
+        .route("/uwb/hub-key", get(move |headers| uwb_hub_key(headers, hub_key_auth.clone(), hub_signer.clone())))
+        .route("/telemetry", get(move |ws, query| telemetry::upgrade(ws, query, telemetry_http.clone())))
+        .nest("/api/v1", api)
         .layer(socket_layer)
         .layer(cors);
 
@@ -338,6 +949,7 @@ async fn main() {
     info!("🚀 Listening on {addr}");
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // Peer addresses reach socket handshakes as `ConnectInfo` (login throttling)
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap();
 
 }
@@ -0,0 +1,129 @@
+//! # mark_rounding
+//!
+//! Mark rounding detection for the ranking engine.
+//!
+//! For each boat the detector watches the next element of `active_course_order`:
+//!
+//! - **Mark** — while the boat is inside `ROUNDING_ZONE_M` (default 50 m) of the mark,
+//!   the bearing from the mark to the boat is integrated. On leaving the zone, a sweep of
+//!   at least `ROUNDING_SWEEP_DEG` (default 90°) is a rounding: a decreasing bearing
+//!   (counter-clockwise) leaves the mark to port, an increasing one to starboard. A
+//!   rounding against the buoy's `rounding` side is reported with `correct: false` and
+//!   does not advance the boat (RRS 28 — it must unwind and sail the course).
+//! - **Gate / start line / finish line** — the track segment since the last sample
+//!   crosses the line between the element's first two marks.
+//!
+//! Laps count the roundings of the course's first mark after the start.
+//!
+//! ## Invariants
+//! - Core Invariant #8: tracks are task-local to the ranking loop; no extra locking
+
+use std::collections::HashMap;
+
+use crate::ranking_engine::{bearing, haversine_distance, segments_intersect};
+use crate::state::{BoatState, CourseElement, CourseElementType, CourseState, LatLon, RaceState, Rounding};
+
+pub struct MarkRoundingConfig {
+    /// Radius around a mark inside which the bearing sweep is integrated (m)
+    pub zone_m: f64,
+    /// Minimum sweep around the mark that counts as a rounding (degrees)
+    pub min_sweep_deg: f64,
+}
+
+impl Default for MarkRoundingConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<f64>().ok()).filter(|v| *v > 0.0);
+        Self {
+            zone_m: var("ROUNDING_ZONE_M").unwrap_or(50.0),
+            min_sweep_deg: var("ROUNDING_SWEEP_DEG").unwrap_or(90.0),
+        }
+    }
+}
+
+/// Result of observing one boat against its next element.
+pub struct Detection {
+    pub direction: Option<Rounding>,
+    pub correct: bool,
+}
+
+#[derive(Default)]
+struct Track {
+    target: usize,
+    prev_pos: Option<LatLon>,
+    /// Bearing mark → boat at the last sample inside the zone
+    last_bearing: Option<f64>,
+    /// Signed bearing change accumulated inside the zone (degrees, + = clockwise)
+    sweep: f64,
+}
+
+pub struct MarkRoundingDetector {
+    config: MarkRoundingConfig,
+    tracks: HashMap<String, Track>,
+}
+
+impl MarkRoundingDetector {
+    pub fn new(config: MarkRoundingConfig) -> Self {
+        Self { config, tracks: HashMap::new() }
+    }
+
+    /// Feed the boat's latest position; `target` is the index of the element it sails to.
+    pub fn observe(&mut self, boat_id: &str, pos: &LatLon, target: usize, element: &CourseElement, course: &CourseState) -> Option<Detection> {
+        let track = self.tracks.entry(boat_id.to_string()).or_default();
+        if track.target != target {
+            *track = Track { target, ..Track::default() };
+        }
+        let prev = track.prev_pos.replace(pos.clone());
+
+        let mark_pos = |i: usize| element.marks.get(i)
+            .and_then(|id| course.marks.iter().find(|m| &m.id == id))
+            .map(|m| m.pos.clone());
+
+        let is_line = !matches!(element.element_type, CourseElementType::Mark) && element.marks.len() >= 2;
+        if is_line {
+            let (p1, p2) = (mark_pos(0)?, mark_pos(1)?);
+            return segments_intersect(&prev?, pos, &p1, &p2).then_some(Detection { direction: None, correct: true });
+        }
+
+        let mark = mark_pos(0)?;
+        if haversine_distance(pos, &mark) <= self.config.zone_m {
+            let b = bearing(&mark, pos);
+            if let Some(last) = track.last_bearing {
+                track.sweep += (b - last + 540.0) % 360.0 - 180.0;
+            }
+            track.last_bearing = Some(b);
+            return None;
+        }
+
+        // Left the zone — judge the pass
+        track.last_bearing.take()?;
+        let sweep = std::mem::take(&mut track.sweep);
+        if sweep.abs() < self.config.min_sweep_deg {
+            return None;
+        }
+        let direction = if sweep < 0.0 { Rounding::Port } else { Rounding::Starboard };
+        let required = element.marks.first()
+            .and_then(|id| course.marks.iter().find(|m| &m.id == id))
+            .and_then(|m| m.rounding.clone());
+        Some(Detection { correct: required.is_none_or(|r| r == direction), direction: Some(direction) })
+    }
+
+    /// Drop tracks of boats that left the fleet.
+    pub fn retain_boats(&mut self, boats: &HashMap<String, BoatState>) {
+        self.tracks.retain(|id, _| boats.contains_key(id));
+    }
+}
+
+/// Laps completed after reaching `leg_index`: roundings of the first mark after the start.
+pub fn laps(course_order: &[CourseElement], leg_index: usize) -> u32 {
+    let Some(first) = course_order.get(1) else { return 0 };
+    course_order.iter().skip(1).take(leg_index).filter(|e| e.marks == first.marks).count() as u32
+}
+
+/// Back to leg 0 for a new race.
+pub fn reset(state: &mut RaceState) {
+    state.mark_roundings.clear();
+    for boat in state.boats.values_mut() {
+        boat.leg_index = 0;
+        boat.lap = 0;
+    }
+}
@@ -0,0 +1,231 @@
+//! # marksetbot
+//!
+//! Drives MarkSetBot robotic marks: every course mark with design `MARKSETBOT`
+//! is held by a unit, sent to the mark's position whenever the course moves it.
+//!
+//! - `MARKSETBOT_API_URL` — fleet API base; unset disables the integration.
+//!   Targets go out as `POST {url}/units/{botId}/target { lat, lon }`, positions
+//!   come from `GET {url}/units` (`[{ id, lat, lon }]`), with
+//!   `MARKSETBOT_API_KEY` as a bearer token when set
+//! - `MARKSETBOT_UNITS` — `"mark-1=MSB-07,gate-4s=MSB-03"`; unmapped marks use
+//!   the buoy's label, then its id, as the unit id
+//! - `MARKSETBOT_POLL_MS` (default 2000)
+//! - `MARKSETBOT_ARRIVAL_M` (default 10) — a unit this close to its target is on
+//!   station; its reported position is then written into `CourseState`
+//! - `MARKSETBOT_HOLD_ALARM_M` (default 25) — an on-station unit further off
+//!   raises `marksetbot-alarm` to directors
+//!
+//! Unit status is kept in `state.markBots`; each change is emitted as
+//! `marksetbot-status`.
+//!
+//! ## Invariants
+//! - Core Invariant #8: network calls outside the state lock; one write lock per poll
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+use socketioxide::SocketIo;
+use tracing::{info, warn};
+
+use crate::handlers::{log_to, now_ms, Outlet, SharedState};
+use crate::laylines;
+use crate::line_bias;
+use crate::persistence::save_state;
+use crate::ranking_engine::haversine_distance;
+use crate::state::{Buoy, BuoyDesign, LatLon, LogCategory, MarkBotState, MarkBotStatus};
+
+/// A fleet API call that hangs longer is dropped; the next poll retries
+const API_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct MarkSetBotConfig {
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    /// mark id → unit id
+    pub units: HashMap<String, String>,
+    pub poll: Duration,
+    pub arrival_m: f64,
+    pub hold_alarm_m: f64,
+}
+
+impl Default for MarkSetBotConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        let num = |key: &str, default: f64| var(key).and_then(|v| v.parse::<f64>().ok()).filter(|v| *v > 0.0).unwrap_or(default);
+        Self {
+            api_url: var("MARKSETBOT_API_URL").map(|u| u.trim_end_matches('/').to_string()),
+            api_key: var("MARKSETBOT_API_KEY"),
+            units: var("MARKSETBOT_UNITS").unwrap_or_default()
+                .split(',')
+                .filter_map(|pair| pair.split_once('=').map(|(m, u)| (m.trim().to_string(), u.trim().to_string())))
+                .collect(),
+            poll: Duration::from_millis(num("MARKSETBOT_POLL_MS", 2000.0) as u64),
+            arrival_m: num("MARKSETBOT_ARRIVAL_M", 10.0),
+            hold_alarm_m: num("MARKSETBOT_HOLD_ALARM_M", 25.0),
+        }
+    }
+}
+
+impl MarkSetBotConfig {
+    fn unit_id(&self, buoy: &Buoy) -> String {
+        self.units.get(&buoy.id).cloned()
+            .or_else(|| buoy.label.clone().filter(|l| !l.is_empty()))
+            .unwrap_or_else(|| buoy.id.clone())
+    }
+
+    fn request(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => builder.bearer_auth(key),
+            None => builder,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UnitReport {
+    id: String,
+    lat: f64,
+    lon: f64,
+}
+
+async fn send_target(client: &reqwest::Client, config: &MarkSetBotConfig, api: &str, bot_id: &str, target: &LatLon) -> anyhow::Result<()> {
+    config.request(client.post(format!("{api}/units/{bot_id}/target")))
+        .json(&json!({ "lat": target.lat, "lon": target.lon }))
+        .send().await?
+        .error_for_status()?;
+    Ok(())
+}
+
+async fn fetch_units(client: &reqwest::Client, config: &MarkSetBotConfig, api: &str) -> anyhow::Result<HashMap<String, LatLon>> {
+    let units: Vec<UnitReport> = config.request(client.get(format!("{api}/units")))
+        .send().await?
+        .error_for_status()?
+        .json().await?;
+    Ok(units.into_iter().map(|u| (u.id, LatLon { lat: u.lat, lon: u.lon })).collect())
+}
+
+fn next_state(config: &MarkSetBotConfig, current: MarkBotState, error_m: f64) -> MarkBotState {
+    match current {
+        MarkBotState::OnStation if error_m > config.hold_alarm_m => MarkBotState::HoldAlarm,
+        MarkBotState::OnStation => MarkBotState::OnStation,
+        _ if error_m <= config.arrival_m => MarkBotState::OnStation,
+        MarkBotState::HoldAlarm => MarkBotState::HoldAlarm,
+        _ => MarkBotState::Transit,
+    }
+}
+
+pub async fn run_marksetbot(config: MarkSetBotConfig, shared: SharedState, io: SocketIo) {
+    let Some(api) = config.api_url.clone() else { return };
+    info!("MarkSetBot: fleet API {api}, arrival {} m, hold alarm {} m", config.arrival_m, config.hold_alarm_m);
+    let client = reqwest::Client::builder().timeout(API_TIMEOUT).build().unwrap_or_default();
+    let out = Outlet::Io(io.clone());
+    let mut interval = tokio::time::interval(config.poll);
+    // Positions this task wrote into the course, so they are not taken for new targets
+    let mut written: HashMap<String, LatLon> = HashMap::new();
+
+    loop {
+        interval.tick().await;
+
+        // Marks the course moved since their unit was last sent
+        let moved: Vec<(String, String, LatLon)> = {
+            let state = shared.read().await;
+            state.course.marks.iter()
+                .filter(|b| b.design == Some(BuoyDesign::Marksetbot))
+                .filter(|b| written.get(&b.id) != Some(&b.pos))
+                .filter(|b| state.mark_bots.iter().find(|s| s.mark_id == b.id).map(|s| &s.target) != Some(&b.pos))
+                .map(|b| (b.id.clone(), config.unit_id(b), b.pos.clone()))
+                .collect()
+        };
+        let mut sent = Vec::new();
+        for (mark_id, bot_id, target) in moved {
+            match send_target(&client, &config, &api, &bot_id, &target).await {
+                Ok(()) => {
+                    info!("MarkSetBot: {bot_id} sent to {mark_id} ({:.6}, {:.6})", target.lat, target.lon);
+                    sent.push((mark_id, bot_id, target));
+                }
+                Err(e) => warn!("MarkSetBot: target for {bot_id} failed: {e}"),
+            }
+        }
+
+        let units = match fetch_units(&client, &config, &api).await {
+            Ok(units) => Some(units),
+            Err(e) => {
+                warn!("MarkSetBot: position poll failed: {e}");
+                None
+            }
+        };
+
+        let now = now_ms();
+        let mut changed = Vec::new();
+        let mut arrived = Vec::new();
+        {
+            let mut state = shared.write().await;
+            for (mark_id, bot_id, target) in sent {
+                let status = MarkBotStatus { mark_id: mark_id.clone(), bot_id, target, actual: None, error_m: None, state: MarkBotState::Transit, updated_ms: now };
+                match state.mark_bots.iter_mut().find(|s| s.mark_id == mark_id) {
+                    Some(existing) => *existing = MarkBotStatus { actual: existing.actual.take(), ..status },
+                    None => state.mark_bots.push(status),
+                }
+            }
+            let bot_marks: Vec<String> = state.course.marks.iter()
+                .filter(|b| b.design == Some(BuoyDesign::Marksetbot))
+                .map(|b| b.id.clone())
+                .collect();
+            state.mark_bots.retain(|s| bot_marks.contains(&s.mark_id));
+
+            if let Some(units) = &units {
+                for status in state.mark_bots.iter_mut() {
+                    let before = status.state;
+                    match units.get(&status.bot_id) {
+                        Some(actual) => {
+                            let error_m = haversine_distance(actual, &status.target);
+                            status.state = next_state(&config, before, error_m);
+                            status.actual = Some(actual.clone());
+                            status.error_m = Some((error_m * 10.0).round() / 10.0);
+                            if status.state == MarkBotState::OnStation && matches!(before, MarkBotState::Transit | MarkBotState::Offline) {
+                                arrived.push((status.mark_id.clone(), actual.clone()));
+                            }
+                        }
+                        None => status.state = MarkBotState::Offline,
+                    }
+                    status.updated_ms = now;
+                    if status.state != before {
+                        changed.push(status.clone());
+                    }
+                }
+            }
+
+            for (mark_id, pos) in &arrived {
+                if let Some(buoy) = state.course.marks.iter_mut().find(|b| &b.id == mark_id) {
+                    buoy.pos = pos.clone();
+                    written.insert(mark_id.clone(), pos.clone());
+                }
+            }
+            if !arrived.is_empty() {
+                laylines::refresh(&mut state);
+                line_bias::refresh(&mut state);
+                let _ = save_state(&state).await;
+                let _ = io.emit("course-updated", &state.course);
+            }
+        }
+
+        for status in &changed {
+            let _ = io.emit("marksetbot-status", status);
+            let (message, alarm) = match status.state {
+                MarkBotState::OnStation => (format!("MarkSetBot {} on station at {}", status.bot_id, status.mark_id), false),
+                MarkBotState::HoldAlarm => (format!(
+                    "MarkSetBot {} off station at {} by {:.0} m",
+                    status.bot_id, status.mark_id, status.error_m.unwrap_or_default(),
+                ), true),
+                MarkBotState::Offline => (format!("MarkSetBot {} ({}) not reporting", status.bot_id, status.mark_id), true),
+                MarkBotState::Transit => continue,
+            };
+            if alarm {
+                let _ = io.to("director").emit("marksetbot-alarm", status);
+            }
+            log_to(&shared, &out, LogCategory::Course, "MarkSetBot".to_string(), message,
+                Some(json!(status)), alarm).await;
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! # measurement_recorder
+//!
+//! Records the full raw UWB packet stream to the audit chain.
+//!
+//! The hub hands every accepted `MeasurementPacket` to `MeasurementRecorder::record`.
+//! The recorder task buffers them and, every `window_ms` (default 5 s), serializes
+//! the batch, compresses it with zstd and appends it as one `UWB_MEASUREMENT_BATCH`
+//! block. At ~20 Hz × 24 peers per node this keeps the chain to a handful of blocks
+//! per minute while still logging every range the solver could have used.
+//!
+//! ## Payload (compressed form)
+//! ```json
+//! { "encoding": "zstd+base64", "windowStartMs": 0, "windowEndMs": 0,
+//!   "packetCount": 0, "rawBytes": 0, "data": "<base64 zstd JSON [MeasurementPacket]>" }
+//! ```
+//! `decode_batch` reverses this for replay and export.
+//!
+//! ## Invariants
+//! - Core Invariant #2: full raw packet stream logged to the SHA-256 chained audit log
+//! - Core Invariant #8: `record` never blocks the UDP receive loop
+
+use std::time::Duration;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+use uwb_types::MeasurementPacket;
+
+use crate::audit::AuditLogger;
+
+pub const BATCH_ENCODING: &str = "zstd+base64";
+const CHANNEL_CAPACITY: usize = 8192;
+const ZSTD_LEVEL: i32 = 3;
+
+// ── Configuration ─────────────────────────────────────────────────────────────
+
+pub struct RecorderConfig {
+    /// Batch window in ms (default 5000)
+    pub window_ms: u64,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: std::env::var("UWB_AUDIT_BATCH_MS")
+                .ok().and_then(|v| v.parse().ok()).filter(|&ms| ms > 0).unwrap_or(5000),
+        }
+    }
+}
+
+// ── Recorder ──────────────────────────────────────────────────────────────────
+
+/// Handle used by the UWB hub to queue raw packets for audit.
+#[derive(Clone)]
+pub struct MeasurementRecorder {
+    tx: mpsc::Sender<MeasurementPacket>,
+}
+
+impl MeasurementRecorder {
+    pub fn spawn(config: RecorderConfig, audit: AuditLogger) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        info!("MeasurementRecorder: raw packets batched every {} ms", config.window_ms);
+        tokio::spawn(run_recorder(config.window_ms, audit, rx));
+        Self { tx }
+    }
+
+    /// Queue a packet. Never blocks.
+    pub fn record(&self, packet: MeasurementPacket) {
+        if let Err(e) = self.tx.try_send(packet) {
+            warn!("MeasurementRecorder: queue full, raw packet not audited: {e}");
+        }
+    }
+}
+
+async fn run_recorder(window_ms: u64, audit: AuditLogger, mut rx: mpsc::Receiver<MeasurementPacket>) {
+    let mut interval = tokio::time::interval(Duration::from_millis(window_ms));
+    let mut buffer: Vec<MeasurementPacket> = Vec::new();
+    let mut window_start_ms = now_ms();
+
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Some(packet) => buffer.push(packet),
+                None => {
+                    // Hub gone — flush what we have and stop
+                    flush(&audit, &mut buffer, window_start_ms).await;
+                    return;
+                }
+            },
+            _ = interval.tick() => {
+                flush(&audit, &mut buffer, window_start_ms).await;
+                window_start_ms = now_ms();
+            }
+        }
+    }
+}
+
+async fn flush(audit: &AuditLogger, buffer: &mut Vec<MeasurementPacket>, window_start_ms: u64) {
+    if buffer.is_empty() {
+        return;
+    }
+    let packets = std::mem::take(buffer);
+    match encode_batch(&packets, window_start_ms, now_ms()) {
+        Ok(payload) => audit.log_measurement_batch(payload).await,
+        Err(e) => warn!("MeasurementRecorder: failed to encode {} packets: {e}", packets.len()),
+    }
+}
+
+// ── Encoding ──────────────────────────────────────────────────────────────────
+
+/// Build the compressed `UWB_MEASUREMENT_BATCH` payload for a window of packets.
+pub fn encode_batch(packets: &[MeasurementPacket], window_start_ms: u64, window_end_ms: u64) -> std::io::Result<serde_json::Value> {
+    let raw = serde_json::to_vec(packets).map_err(std::io::Error::other)?;
+    let compressed = zstd::encode_all(raw.as_slice(), ZSTD_LEVEL)?;
+    Ok(json!({
+        "encoding": BATCH_ENCODING,
+        "windowStartMs": window_start_ms,
+        "windowEndMs": window_end_ms,
+        "packetCount": packets.len(),
+        "rawBytes": raw.len(),
+        "data": BASE64.encode(compressed),
+    }))
+}
+
+/// Decode a compressed batch payload back into its raw packets.
+/// Returns None if the payload is not a compressed batch or is corrupt.
+pub fn decode_batch(payload: &serde_json::Value) -> Option<Vec<MeasurementPacket>> {
+    if payload["encoding"].as_str() != Some(BATCH_ENCODING) {
+        return None;
+    }
+    let compressed = BASE64.decode(payload["data"].as_str()?).ok()?;
+    let raw = zstd::decode_all(compressed.as_slice()).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
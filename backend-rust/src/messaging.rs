@@ -0,0 +1,90 @@
+//! # messaging
+//!
+//! Structured on-water messages between the race committee, the jury and boats
+//! ("move MarkB 30 m upwind"), kept with the race record instead of on VHF only.
+//!
+//! - `send-message { to: { role } | { boat }, body }` — delivered as `message` to the
+//!   role's room or the boat's tracker (`boat:<id>`); the sender gets `message-sent`
+//! - `ack-message { messageId }` — a recipient confirms; `message-acked` goes to the
+//!   director and jury and to the sender
+//! - `list-messages` — officials see every message, a tracker those to or from its boat
+//!
+//! The last `MESSAGE_LOG_LIMIT` messages (default 500) are kept in state; every
+//! send and ack is an audited command.
+//!
+//! ## Invariants
+//! - Core Invariant #2: sends and acks arrive as audited commands
+
+use crate::state::{Message, MessageAck, MessageTarget, RaceState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MessageError {
+    #[error("A message needs a body")]
+    EmptyBody,
+    #[error("Unknown message: {0}")]
+    NotFound(String),
+    #[error("Message {0} is not addressed to {1}")]
+    NotRecipient(String, String),
+}
+
+fn log_limit() -> usize {
+    std::env::var("MESSAGE_LOG_LIMIT").ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(500)
+}
+
+/// Socket.IO room the target's clients are in.
+pub fn room(target: &MessageTarget) -> String {
+    match target {
+        MessageTarget::Role(role) => role.clone(),
+        MessageTarget::Boat(boat_id) => format!("boat:{boat_id}"),
+    }
+}
+
+pub fn send(state: &mut RaceState, from: MessageTarget, to: MessageTarget, body: &str, now: i64) -> Result<Message, MessageError> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(MessageError::EmptyBody);
+    }
+    let message = Message {
+        id: format!("msg-{now}-{}", state.messages.len() + 1),
+        from,
+        to,
+        body: body.to_string(),
+        sent_ms: now,
+        acks: Vec::new(),
+    };
+    state.messages.push(message.clone());
+    let excess = state.messages.len().saturating_sub(log_limit());
+    state.messages.drain(..excess);
+    Ok(message)
+}
+
+/// Acknowledge as a client with `role` (and `boat`, for a tracker). Only a
+/// recipient may: a member of the addressed role, or the addressed boat.
+pub fn ack(state: &mut RaceState, id: &str, role: &str, boat: Option<&str>, now: i64) -> Result<Message, MessageError> {
+    let message = state.messages.iter_mut().find(|m| m.id == id).ok_or_else(|| MessageError::NotFound(id.to_string()))?;
+    let by = boat.unwrap_or(role);
+    let addressed = match &message.to {
+        MessageTarget::Role(r) => r == role,
+        MessageTarget::Boat(b) => Some(b.as_str()) == boat,
+    };
+    if !addressed {
+        return Err(MessageError::NotRecipient(id.to_string(), by.to_string()));
+    }
+    if !message.acks.iter().any(|a| a.by == by) {
+        message.acks.push(MessageAck { by: by.to_string(), at_ms: now });
+    }
+    Ok(message.clone())
+}
+
+/// Messages a tracker may see: to or from its boat, or to all trackers.
+pub fn visible_to_boat(state: &RaceState, boat_id: &str) -> Vec<Message> {
+    let boat = MessageTarget::Boat(boat_id.to_string());
+    let trackers = MessageTarget::Role("tracker".to_string());
+    state.messages.iter()
+        .filter(|m| m.from == boat || m.to == boat || m.to == trackers)
+        .cloned()
+        .collect()
+}
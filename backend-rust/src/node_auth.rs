@@ -0,0 +1,187 @@
+//! # node_auth
+//!
+//! Per-node shared keys for UWB packets, so a laptop on the race network cannot
+//! speak for a node.
+//!
+//! A node signs the JSON envelope by appending a trailer to the datagram: `\n`
+//! and the hex HMAC-SHA256, under the node's key, of the JSON bytes before it.
+//! The hub checks the tag over the bytes exactly as received, before parsing
+//! them, so no two serializers ever have to agree. A binary `MeasurementPacket` is instead sealed with
+//! AES-128-CCM under the first 16 bytes of HMAC-SHA256(node key, "uwb-ccm").
+//!
+//! Keys are provisioned in `UWB_NODE_KEYS_FILE` (default `/data/uwb_node_keys.json`),
+//! `{ "<node id>": "<hex key>" }`; the simulator reads the same file. With any key
+//! provisioned every packet must be signed by its node (`UWB_REQUIRE_NODE_AUTH`
+//! overrides: `false` accepts unsigned packets while nodes are being re-flashed,
+//! but a wrong tag is always rejected).
+//!
+//! The same keys sign the hub's commands to a node (`control_plane`), so a node
+//! only obeys its own hub.
+//!
+//! Rejections are counted per source address, with the node ids it claimed, and
+//! read back by directors at `GET /uwb/auth-rejections` — a source claiming
+//! another source's node is an impersonation attempt.
+
+use std::collections::{BTreeSet, HashMap};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use tracing::{info, warn};
+
+const TAG_FIELD: &str = "hmac";
+/// `\n` + 64 hex chars of HMAC-SHA256 closing a signed JSON datagram
+const TRAILER_LEN: usize = 1 + 64;
+/// After the first, every n-th rejection from a source is logged
+const WARN_EVERY: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AuthReject {
+    #[error("unsigned packet")]
+    MissingTag,
+    #[error("tag does not match the node key")]
+    BadTag,
+    #[error("no key provisioned for the node")]
+    UnknownNode,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceRejections {
+    pub missing_tag: u64,
+    pub bad_tag: u64,
+    pub unknown_node: u64,
+    /// Node ids the source claimed in rejected packets
+    pub claimed_nodes: BTreeSet<u32>,
+    pub last_ms: u64,
+}
+
+impl SourceRejections {
+    fn total(&self) -> u64 {
+        self.missing_tag + self.bad_tag + self.unknown_node
+    }
+}
+
+pub struct NodeAuth {
+    keys: HashMap<u32, Vec<u8>>,
+    required: bool,
+    rejections: Mutex<HashMap<String, SourceRejections>>,
+}
+
+impl NodeAuth {
+    pub fn load() -> Self {
+        let path = std::env::var("UWB_NODE_KEYS_FILE").unwrap_or_else(|_| "/data/uwb_node_keys.json".to_string());
+        let keys = match std::fs::read_to_string(&path) {
+            Ok(text) => parse_keys(&text).unwrap_or_else(|e| {
+                warn!("NodeAuth: ignoring {path}: {e}");
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        let required = std::env::var("UWB_REQUIRE_NODE_AUTH").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(!keys.is_empty());
+        info!("NodeAuth: {} node key(s), signatures {}", keys.len(), if required { "required" } else { "optional" });
+        Self { keys, required, rejections: Mutex::new(HashMap::new()) }
+    }
+
+    /// Check the tag split off a JSON datagram against its body bytes.
+    pub fn verify(&self, body: &[u8], tag: Option<&[u8]>, node_id: u32) -> Result<(), AuthReject> {
+        match (tag, self.keys.get(&node_id)) {
+            (Some(tag), Some(key)) => {
+                let expected = hex::decode(tag).map_err(|_| AuthReject::BadTag)?;
+                let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|_| AuthReject::BadTag)?;
+                mac.update(body);
+                mac.verify_slice(&expected).map_err(|_| AuthReject::BadTag)
+            }
+            (_, _) if !self.required => Ok(()),
+            (None, _) => Err(AuthReject::MissingTag),
+            (Some(_), None) => Err(AuthReject::UnknownNode),
+        }
+    }
+
+    /// Key to open a binary packet from `node_id`; None for an unsealed packet
+    /// the policy lets through.
+    pub fn packet_key(&self, node_id: u32, sealed: bool) -> Result<Option<[u8; 16]>, AuthReject> {
+        match (sealed, self.keys.get(&node_id)) {
+            (true, Some(key)) => ccm_key(key).map(Some).ok_or(AuthReject::BadTag),
+            (true, None) => Err(AuthReject::UnknownNode),
+            (false, _) if !self.required => Ok(None),
+            (false, _) => Err(AuthReject::MissingTag),
+        }
+    }
+
+    /// Tag a command for `node_id` with its key (the `hmac` field; the hub
+    /// serializes the command itself, so it signs its own bytes).
+    pub fn sign(&self, packet: &mut Map<String, Value>, node_id: u32) -> Result<(), AuthReject> {
+        let key = self.keys.get(&node_id).ok_or(AuthReject::UnknownNode)?;
+        packet.remove(TAG_FIELD);
+        let tag = mac(key, packet).ok_or(AuthReject::UnknownNode)?.finalize().into_bytes();
+        packet.insert(TAG_FIELD.to_string(), Value::from(hex::encode(tag)));
+        Ok(())
+    }
+
+    pub fn record_rejection(&self, src: SocketAddr, node_id: u32, reason: AuthReject) {
+        let source = src.ip().to_string();
+        let mut rejections = self.rejections.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = rejections.entry(source.clone()).or_default();
+        match reason {
+            AuthReject::MissingTag => entry.missing_tag += 1,
+            AuthReject::BadTag => entry.bad_tag += 1,
+            AuthReject::UnknownNode => entry.unknown_node += 1,
+        }
+        entry.claimed_nodes.insert(node_id);
+        entry.last_ms = crate::time_discipline::now_ms();
+        let total = entry.total();
+        if total == 1 || total.is_multiple_of(WARN_EVERY) {
+            warn!("UWB: rejected packet from {source} claiming node {node_id}: {reason} ({total} rejected from this source)");
+        }
+    }
+
+    /// Source address → rejection counts.
+    pub fn rejections(&self) -> HashMap<String, SourceRejections> {
+        self.rejections.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Split a JSON datagram into its body and, when signed, the hex tag trailer.
+pub fn split_tag(datagram: &[u8]) -> (&[u8], Option<&[u8]>) {
+    if datagram.len() > TRAILER_LEN {
+        let (body, trailer) = datagram.split_at(datagram.len() - TRAILER_LEN);
+        if trailer[0] == b'\n' && trailer[1..].iter().all(u8::is_ascii_hexdigit) {
+            return (body, Some(&trailer[1..]));
+        }
+    }
+    (datagram, None)
+}
+
+/// HMAC-SHA256 over a command, serialized compact with sorted keys.
+fn mac(key: &[u8], packet: &Map<String, Value>) -> Option<Hmac<Sha256>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).ok()?;
+    mac.update(&serde_json::to_vec(packet).ok()?);
+    Some(mac)
+}
+
+/// AES-128-CCM key of a node's binary packets, derived from its shared key.
+fn ccm_key(key: &[u8]) -> Option<[u8; 16]> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).ok()?;
+    mac.update(b"uwb-ccm");
+    mac.finalize().into_bytes()[..16].try_into().ok()
+}
+
+pub fn parse_keys(text: &str) -> Result<HashMap<u32, Vec<u8>>, String> {
+    let raw: HashMap<String, String> = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    raw.into_iter()
+        .map(|(node, key)| {
+            let id = node.trim().parse().map_err(|_| format!("node id {node:?}"))?;
+            let key = hex::decode(key.trim()).map_err(|_| format!("key of node {node}"))?;
+            if key.len() < 16 {
+                return Err(format!("key of node {node} is shorter than 16 bytes"));
+            }
+            Ok((id, key))
+        })
+        .collect()
+}
@@ -0,0 +1,263 @@
+//! # node_chains
+//!
+//! Import per-node microSD audit chains and cross-check them against the hub chain.
+//!
+//! Every UWB node keeps its own append-only SHA-256 chain on microSD (firmware
+//! `sd_logger`), independent of the hub. After racing the cards are copied to the
+//! hub and fed to `merge_node_chains`, which:
+//!   1. Verifies each node chain (hash + prev_hash linkage + seq continuity)
+//!   2. Copies it into `<audit dir>/nodes/` alongside the hub chain
+//!   3. Compares every raw packet the node says it transmitted with the packets the
+//!      hub recorded in its `UWB_MEASUREMENT_BATCH` blocks, over the time span both cover
+//!
+//! A compromised hub can rewrite its own chain, but not the chains sitting on every
+//! node's card — any packet that differs, disappears or appears only on one side is
+//! reported as a `Discrepancy`.
+//!
+//! Over the socket (`merge-node-chains`) the card copies must sit in
+//! `<audit dir>/uploads/` and are named relative to it (`upload_file`); the
+//! offline `regatta-backend merge-node-chains` tool takes any local path.
+//!
+//! ## Node block format
+//! `uwb_types::AuditBlock` JSON lines, hashed with the same `chain_hash` as the hub.
+//! `MEASUREMENT_BATCH` payloads are either a JSON array of `MeasurementPacket`s,
+//! `{ "packets": [...] }`, or the compressed `measurement_recorder` form.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use tracing::{info, warn};
+use uwb_types::{AuditBlock as NodeBlock, AuditEventType as NodeEventType, MeasurementPacket};
+
+use crate::audit::{chain_hash, AuditBlock, AuditEventType, GENESIS_HASH};
+use crate::measurement_recorder::decode_batch;
+
+const NODES_DIR: &str = "nodes";
+/// Card copies for the socket command, under the audit dir
+const UPLOADS_DIR: &str = "uploads";
+
+// ── Report ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DiscrepancyKind {
+    /// Node logged the packet, hub has no record of it
+    MissingFromHub,
+    /// Hub recorded a packet attributed to this node that the node never logged
+    NotInNodeLog,
+    /// Both recorded the packet but its contents differ
+    ContentMismatch,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Discrepancy {
+    pub kind: DiscrepancyKind,
+    pub node_id: u32,
+    pub seq_num: u32,
+    pub tx_timestamp_ns: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeChainReport {
+    pub file: String,
+    /// Node the chain belongs to (most frequent packet node_id), if any packets were found
+    pub node_id: Option<u32>,
+    pub blocks: usize,
+    pub chain_valid: bool,
+    /// block_seq of the first block that failed verification
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_broken_seq: Option<u64>,
+    pub node_packets: usize,
+    pub hub_packets: usize,
+    /// Overlapping tx-time span compared, ns
+    pub overlap_ns: Option<(u64, u64)>,
+    pub matched: usize,
+    pub discrepancies: Vec<Discrepancy>,
+    /// Path the chain was imported to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imported_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeReport {
+    pub hub_blocks: usize,
+    pub nodes: Vec<NodeChainReport>,
+    /// True if every node chain verified and no discrepancies were found
+    pub consistent: bool,
+}
+
+// ── Merge ─────────────────────────────────────────────────────────────────────
+
+/// `name` resolved inside `<audit dir>/uploads/`. None for an absolute path, a
+/// `..` or `.` component, a missing file, or a symlink leading out of the dir.
+pub async fn upload_file(audit_dir: &Path, name: &str) -> Option<PathBuf> {
+    let rel = Path::new(name);
+    if rel.as_os_str().is_empty() || !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let root = tokio::fs::canonicalize(audit_dir.join(UPLOADS_DIR)).await.ok()?;
+    let path = tokio::fs::canonicalize(root.join(rel)).await.ok()?;
+    path.starts_with(&root).then_some(path)
+}
+
+/// Verify, import and cross-check node chain files against the hub chain in `hub_files`.
+pub async fn merge_node_chains(hub_files: &[PathBuf], node_files: &[PathBuf], audit_dir: &Path) -> std::io::Result<MergeReport> {
+    let hub_blocks = crate::audit::read_blocks(hub_files).await?;
+    let hub_packets = hub_packets_by_node(&hub_blocks);
+
+    let import_dir = audit_dir.join(NODES_DIR);
+    if let Err(e) = tokio::fs::create_dir_all(&import_dir).await {
+        warn!("NodeChains: cannot create {}: {e} — node chains will not be imported", import_dir.display());
+    }
+
+    let mut nodes = Vec::new();
+    for file in node_files {
+        let data = tokio::fs::read_to_string(file).await?;
+        let blocks: Vec<NodeBlock> = data.lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect();
+
+        let first_broken_seq = first_broken(&blocks);
+        let packets: Vec<MeasurementPacket> = blocks.iter()
+            .filter(|b| matches!(b.event_type, NodeEventType::MeasurementBatch))
+            .flat_map(|b| node_batch_packets(&b.payload_json))
+            .collect();
+        let node_id = majority_node(&packets);
+
+        let mut report = NodeChainReport {
+            file: file.display().to_string(),
+            node_id,
+            blocks: blocks.len(),
+            chain_valid: first_broken_seq.is_none(),
+            first_broken_seq,
+            node_packets: 0,
+            hub_packets: 0,
+            overlap_ns: None,
+            matched: 0,
+            discrepancies: Vec::new(),
+            imported_to: None,
+        };
+
+        if let Some(node_id) = node_id {
+            let own: Vec<&MeasurementPacket> = packets.iter().filter(|p| p.node_id == node_id).collect();
+            let hub = hub_packets.get(&node_id).map(Vec::as_slice).unwrap_or_default();
+            cross_check(&mut report, node_id, &own, hub);
+
+            let file_name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let target = import_dir.join(format!("node-{node_id}-{file_name}"));
+            match tokio::fs::copy(file, &target).await {
+                Ok(_) => report.imported_to = Some(target.display().to_string()),
+                Err(e) => warn!("NodeChains: import of {} failed: {e}", file.display()),
+            }
+        }
+
+        info!(
+            "NodeChains: {} node={:?} valid={} matched={} discrepancies={}",
+            report.file, report.node_id, report.chain_valid, report.matched, report.discrepancies.len()
+        );
+        nodes.push(report);
+    }
+
+    let consistent = nodes.iter().all(|n| n.chain_valid && n.discrepancies.is_empty());
+    Ok(MergeReport { hub_blocks: hub_blocks.len(), nodes, consistent })
+}
+
+/// Compare a node's own packets with what the hub recorded for that node,
+/// restricted to the tx-time span both logs cover.
+fn cross_check(report: &mut NodeChainReport, node_id: u32, own: &[&MeasurementPacket], hub: &[MeasurementPacket]) {
+    report.node_packets = own.len();
+    report.hub_packets = hub.len();
+
+    let span = |ts: &mut dyn Iterator<Item = u64>| ts.fold(None, |acc: Option<(u64, u64)>, t| {
+        Some(acc.map_or((t, t), |(lo, hi)| (lo.min(t), hi.max(t))))
+    });
+    let (Some((node_lo, node_hi)), Some((hub_lo, hub_hi))) = (
+        span(&mut own.iter().map(|p| p.tx_timestamp_ns)),
+        span(&mut hub.iter().map(|p| p.tx_timestamp_ns)),
+    ) else {
+        return;
+    };
+    let (lo, hi) = (node_lo.max(hub_lo), node_hi.min(hub_hi));
+    if lo > hi {
+        return;
+    }
+    report.overlap_ns = Some((lo, hi));
+
+    let in_span = |p: &&MeasurementPacket| (lo..=hi).contains(&p.tx_timestamp_ns);
+    let node_by_seq: BTreeMap<u32, &MeasurementPacket> = own.iter().copied().filter(in_span).map(|p| (p.seq_num, p)).collect();
+    let hub_by_seq: BTreeMap<u32, &MeasurementPacket> = hub.iter().filter(in_span).map(|p| (p.seq_num, p)).collect();
+
+    let discrepancy = |kind, p: &MeasurementPacket| Discrepancy { kind, node_id, seq_num: p.seq_num, tx_timestamp_ns: p.tx_timestamp_ns };
+
+    for (seq, node_pkt) in &node_by_seq {
+        match hub_by_seq.get(seq) {
+            Some(hub_pkt) if same_packet(node_pkt, hub_pkt) => report.matched += 1,
+            Some(_) => report.discrepancies.push(discrepancy(DiscrepancyKind::ContentMismatch, node_pkt)),
+            None => report.discrepancies.push(discrepancy(DiscrepancyKind::MissingFromHub, node_pkt)),
+        }
+    }
+    for (seq, hub_pkt) in &hub_by_seq {
+        if !node_by_seq.contains_key(seq) {
+            report.discrepancies.push(discrepancy(DiscrepancyKind::NotInNodeLog, hub_pkt));
+        }
+    }
+    report.discrepancies.sort_by_key(|d| (d.tx_timestamp_ns, d.seq_num));
+}
+
+/// Packets are equal if their serialized forms match (ranges, flags, orientation, CRC).
+fn same_packet(a: &MeasurementPacket, b: &MeasurementPacket) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+fn hub_packets_by_node(blocks: &[AuditBlock]) -> HashMap<u32, Vec<MeasurementPacket>> {
+    let mut by_node: HashMap<u32, Vec<MeasurementPacket>> = HashMap::new();
+    for block in blocks.iter().filter(|b| matches!(b.event_type, AuditEventType::UwbMeasurementBatch)) {
+        let Ok(payload) = serde_json::from_str::<serde_json::Value>(&block.payload_json) else { continue };
+        for packet in decode_batch(&payload).unwrap_or_default() {
+            by_node.entry(packet.node_id).or_default().push(packet);
+        }
+    }
+    by_node
+}
+
+fn node_batch_packets(payload_json: &str) -> Vec<MeasurementPacket> {
+    let Ok(payload) = serde_json::from_str::<serde_json::Value>(payload_json) else { return Vec::new() };
+    if let Some(packets) = decode_batch(&payload) {
+        return packets;
+    }
+    let list = if payload.is_array() { payload } else { payload["packets"].clone() };
+    serde_json::from_value(list).unwrap_or_default()
+}
+
+fn majority_node(packets: &[MeasurementPacket]) -> Option<u32> {
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for p in packets {
+        *counts.entry(p.node_id).or_default() += 1;
+    }
+    counts.into_iter().max_by_key(|&(id, n)| (n, std::cmp::Reverse(id))).map(|(id, _)| id)
+}
+
+/// block_seq of the first node block whose hash, linkage or sequence is wrong.
+fn first_broken(blocks: &[NodeBlock]) -> Option<u64> {
+    let mut prev: Option<&NodeBlock> = None;
+    for block in blocks {
+        let event_type = serde_json::to_value(&block.event_type).ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let hash_ok = chain_hash(&block.prev_hash, block.timestamp_ms, &event_type, &block.payload_json) == block.block_hash;
+        let link_ok = match prev {
+            Some(p) => block.block_seq == p.block_seq + 1 && block.prev_hash == p.block_hash,
+            None => block.block_seq == 0 && block.prev_hash == GENESIS_HASH,
+        };
+        if !hash_ok || !link_ok {
+            return Some(block.block_seq);
+        }
+        prev = Some(block);
+    }
+    None
+}
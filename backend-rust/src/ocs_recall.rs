@@ -0,0 +1,246 @@
+//! # ocs_recall
+//!
+//! Closes the loop between the UWB gun solve and the race procedure.
+//!
+//! `uwb_hub` reports OCS boats on its `OcsEvent` channel; the gun batch solve runs
+//! at T-0 and real-time fused positions keep flowing afterwards. Any OCS boat
+//! reported within `OCS_GUN_WINDOW_MS` (default 3000) of the start signal is an
+//! individual-recall candidate (RRS 29.1). Depending on `OCS_AUTO_RECALL`:
+//!
+//! - `auto`    — raise INDIVIDUAL_RECALL (X flag, 1 sound) with the boats populated,
+//!   lowering X and scoring OCS boats DNS after 5 minutes, as the manual command does
+//! - `confirm` (default) — emit `ocs-recall-proposed` to directors; they confirm with
+//!   `procedure-action { action: "INDIVIDUAL_RECALL", boats }`
+//! - `off`     — only audit the detection
+//!
+//! UWB node ids map to boat ids via the entry list's `nodeId`, then
+//! `UWB_NODE_BOATS` (`"12=GBR-1,13=USA-7"`); unmapped nodes use the node id as boat id.
+//!
+//! Detections are kept in `state.ocs_detections` (first report per boat) until the
+//! next start. A manual `INDIVIDUAL_RECALL` without a `boats` list takes its OCS
+//! list from them; every recall event carries `ocs: [{ boatId, dtlCm, ... }]` with
+//! the measured distance to line (null for boats the director added by eye).
+//!
+//! ## Invariants
+//! - Core Invariant #2: every OCS detection is written to the audit chain before acting on it
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use socketioxide::SocketIo;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::audit::AuditLogger;
+use crate::handlers::{audit_status_change, now_ms, SharedState};
+use crate::entries;
+use crate::log_store;
+use crate::state::{
+    Entry, LogCategory, LogEntry, OcsDetection, Penalty, PenaltyType, RaceState, RaceStatus, SequenceInfo,
+};
+use crate::boat_scope::{FULL_STATE_EXCEPT, SCOPED_ROOM};
+use crate::uwb_hub::OcsEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecallMode {
+    Auto,
+    Confirm,
+    Off,
+}
+
+pub struct OcsRecallConfig {
+    pub mode: RecallMode,
+    /// How long after the gun an OCS report still counts (default 3000 ms)
+    pub gun_window_ms: i64,
+    pub node_boats: HashMap<u32, String>,
+}
+
+impl Default for OcsRecallConfig {
+    fn default() -> Self {
+        let mode = match std::env::var("OCS_AUTO_RECALL").unwrap_or_default().to_lowercase().as_str() {
+            "auto" => RecallMode::Auto,
+            "off" | "false" => RecallMode::Off,
+            _ => RecallMode::Confirm,
+        };
+        let node_boats = std::env::var("UWB_NODE_BOATS").unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| {
+                let (node, boat) = pair.split_once('=')?;
+                Some((node.trim().parse().ok()?, boat.trim().to_string()))
+            })
+            .collect();
+        Self {
+            mode,
+            gun_window_ms: std::env::var("OCS_GUN_WINDOW_MS")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(3000),
+            node_boats,
+        }
+    }
+}
+
+impl OcsRecallConfig {
+    fn boat_id(&self, entries: &[Entry], node_id: u32) -> String {
+        entries::for_node(entries, node_id).map(|e| e.boat_id.clone())
+            .or_else(|| self.node_boats.get(&node_id).cloned())
+            .unwrap_or_else(|| node_id.to_string())
+    }
+}
+
+/// Boats the hub measured OCS at this start, in detection order.
+pub fn detected_boats(state: &RaceState) -> Vec<String> {
+    state.ocs_detections.iter().map(|d| d.boat_id.clone()).collect()
+}
+
+/// Per-boat OCS detail for recall events: the measured DTL where UWB saw the boat.
+pub fn detail(state: &RaceState, boats: &[String]) -> Vec<Value> {
+    boats.iter().map(|boat_id| {
+        match state.ocs_detections.iter().find(|d| &d.boat_id == boat_id) {
+            Some(d) => json!({
+                "boatId": boat_id,
+                "dtlCm": d.dtl_cm,
+                "nodeId": d.node_id,
+                "fixQuality": d.fix_quality,
+                "epochMs": d.epoch_ms,
+            }),
+            None => json!({ "boatId": boat_id, "dtlCm": null }),
+        }
+    }).collect()
+}
+
+pub async fn run_ocs_recall(
+    config: OcsRecallConfig,
+    mut ocs_rx: mpsc::Receiver<OcsEvent>,
+    shared: SharedState,
+    io: SocketIo,
+    audit: AuditLogger,
+) {
+    info!("OcsRecall: mode {:?}, gun window {} ms", config.mode, config.gun_window_ms);
+    while let Some(event) = ocs_rx.recv().await {
+        let ocs: Vec<_> = event.boats.iter().filter(|b| b.is_ocs).collect();
+        if ocs.is_empty() {
+            continue;
+        }
+
+        let (status, gun_ms, entries) = {
+            let state = shared.read().await;
+            (state.status.clone(), state.start_time, state.entries.clone())
+        };
+        let Some(gun_ms) = gun_ms else { continue };
+        let since_gun = event.epoch_ms as i64 - gun_ms;
+        if !(0..=config.gun_window_ms).contains(&since_gun)
+            || !matches!(status, RaceStatus::Racing | RaceStatus::IndividualRecall)
+        {
+            continue;
+        }
+
+        let boats: Vec<String> = ocs.iter().map(|b| config.boat_id(&entries, b.node_id)).collect();
+        audit.log_ocs_detected(&ocs.iter().map(|b| json!({
+            "nodeId": b.node_id,
+            "boatId": config.boat_id(&entries, b.node_id),
+            "dtlCm": b.dtl_cm,
+            "fixQuality": b.fix_quality,
+            "epochMs": event.epoch_ms,
+            "msAfterGun": since_gun,
+        })).collect::<Vec<_>>()).await;
+        let detail = {
+            let mut state = shared.write().await;
+            for (b, boat_id) in ocs.iter().zip(&boats) {
+                if !state.ocs_detections.iter().any(|d| &d.boat_id == boat_id) {
+                    state.ocs_detections.push(OcsDetection {
+                        boat_id: boat_id.clone(),
+                        node_id: b.node_id,
+                        dtl_cm: b.dtl_cm,
+                        fix_quality: b.fix_quality,
+                        epoch_ms: event.epoch_ms,
+                    });
+                }
+            }
+            detail(&state, &boats)
+        };
+        info!("OcsRecall: OCS at gun +{since_gun} ms: {}", boats.join(", "));
+
+        match config.mode {
+            RecallMode::Off => {}
+            RecallMode::Confirm => {
+                let _ = io.to("director").emit("ocs-recall-proposed", &json!({
+                    "boats": boats,
+                    "ocs": detail,
+                    "epochMs": event.epoch_ms,
+                    "msAfterGun": since_gun,
+                }));
+            }
+            RecallMode::Auto => raise_individual_recall(&shared, &io, &audit, boats).await,
+        }
+    }
+    warn!("OcsRecall: OCS channel closed");
+}
+
+/// X flag + 1 sound with the OCS boats populated; racing continues.
+async fn raise_individual_recall(shared: &SharedState, io: &SocketIo, audit: &AuditLogger, boats: Vec<String>) {
+    let status_before = {
+        let mut state = shared.write().await;
+        let before = std::mem::replace(&mut state.status, RaceStatus::IndividualRecall);
+        for boat in boats {
+            if !state.ocs_boats.contains(&boat) {
+                state.ocs_boats.push(boat);
+            }
+        }
+        state.current_sequence = Some(SequenceInfo {
+            event: "Individual Recall".to_string(),
+            flags: vec!["X".to_string()],
+        });
+        let _ = io.except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+        before
+    };
+    if status_before == RaceStatus::IndividualRecall {
+        // Already recalling — the new boats were added to the list
+        return;
+    }
+    audit_status_change(audit, &status_before, &RaceStatus::IndividualRecall, "auto OCS recall").await;
+
+    let (ocs_boats, ocs_detail) = {
+        let state = shared.read().await;
+        (state.ocs_boats.clone(), detail(&state, &state.ocs_boats))
+    };
+    push_log(shared, io, format!("Individual Recall — X flag raised automatically, OCS: {}", ocs_boats.join(", ")),
+        json!({ "signal": "X", "sounds": 1, "ocsBoats": ocs_boats, "ocs": ocs_detail, "auto": true })).await;
+
+    // Auto-clear X flag after 5 minutes (DNS default), as the manual recall does
+    let (shared, io, audit) = (shared.clone(), io.clone(), audit.clone());
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(300)).await;
+        {
+            let mut state = shared.write().await;
+            if state.status != RaceStatus::IndividualRecall {
+                return;
+            }
+            state.status = RaceStatus::Racing;
+            state.current_sequence = Some(SequenceInfo { event: "Racing".to_string(), flags: vec![] });
+            let ocs_list = std::mem::take(&mut state.ocs_boats);
+            for boat_id in ocs_list {
+                state.penalties.push(Penalty::new(boat_id, PenaltyType::Dns, now_ms()));
+            }
+            let _ = io.except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+        }
+        audit_status_change(&audit, &RaceStatus::IndividualRecall, &RaceStatus::Racing, "X flag lowered").await;
+        push_log(&shared, &io, "X flag lowered — DNS applied to OCS boats".to_string(), json!({ "auto": true })).await;
+    });
+}
+
+async fn push_log(shared: &SharedState, io: &SocketIo, message: String, data: serde_json::Value) {
+    let log = LogEntry {
+        id: log_store::next_id(),
+        timestamp: now_ms(),
+        category: LogCategory::Procedure,
+        source: "UWB".to_string(),
+        message,
+        data: Some(data),
+        is_active: false,
+        protest_flagged: None,
+        jury_notes: None,
+    };
+    log_store::push(&mut *shared.write().await, log.clone());
+    log_store::append(&log).await;
+    let _ = io.except(SCOPED_ROOM).emit("new-log", &log);
+}
@@ -0,0 +1,134 @@
+//! # officials
+//!
+//! Umpire pairs and support boats for each flight, so umpires find their
+//! assignments in the app with the pairings (`Flight::officials`).
+//!
+//! - `set-officials { officials: [{ id, name, kind, unavailableFlights? }] }` —
+//!   the roster; `kind` is `UMPIRE` or `SUPPORT_BOAT`, `unavailableFlights` the
+//!   flight numbers they cannot do
+//! - `assign-officials { umpirePairs?, supportBoats? }` — per flight (default 1
+//!   each), for every flight still `SCHEDULED`; flights under way or done keep
+//!   theirs
+//! - `get-officials` — the roster, to the sender
+//!
+//! Assignment goes flight by flight: the available officials with the fewest
+//! flights so far go first, then the one off the water longest; the second
+//! umpire of a pair is, among those, the one who has sat with the first the
+//! least. If a flight cannot be staffed nothing is assigned and the flight is
+//! named in the error.
+//!
+//! The roster goes out as `officials { officials }`, assignments as `flight-delta`.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::flight_schedule::FlightDelta;
+use crate::state::{FlightOfficials, FlightStatus, Official, OfficialKind, RaceState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum OfficialsError {
+    #[error("Official ids must be unique and non-empty: {0:?}")]
+    InvalidId(String),
+    #[error("Flight {0} needs {1} umpires, {2} are available")]
+    NotEnoughUmpires(u32, usize, usize),
+    #[error("Flight {0} needs {1} support boats, {2} are available")]
+    NotEnoughSupportBoats(u32, usize, usize),
+}
+
+pub fn set_roster(state: &mut RaceState, officials: Vec<Official>) -> Result<(), OfficialsError> {
+    let mut ids = HashSet::new();
+    if let Some(o) = officials.iter().find(|o| o.id.trim().is_empty() || !ids.insert(o.id.as_str())) {
+        return Err(OfficialsError::InvalidId(o.id.clone()));
+    }
+    state.officials = officials;
+    Ok(())
+}
+
+/// Flights so far and the last flight number of each official.
+#[derive(Default)]
+struct Load {
+    flights: HashMap<String, (u32, u32)>,
+    partners: HashMap<(String, String), u32>,
+}
+
+impl Load {
+    fn key(&self, id: &str) -> (u32, u32) {
+        // Fewest flights, then longest off the water
+        self.flights.get(id).copied().unwrap_or((0, 0))
+    }
+
+    fn partnered(&self, a: &str, b: &str) -> u32 {
+        let key = if a < b { (a.to_string(), b.to_string()) } else { (b.to_string(), a.to_string()) };
+        self.partners.get(&key).copied().unwrap_or(0)
+    }
+
+    fn add(&mut self, officials: &FlightOfficials, number: u32) {
+        for id in officials.umpire_pairs.iter().flatten().chain(&officials.support_boats) {
+            let entry = self.flights.entry(id.clone()).or_default();
+            entry.0 += 1;
+            entry.1 = entry.1.max(number);
+        }
+        for [a, b] in &officials.umpire_pairs {
+            let key = if a < b { (a.clone(), b.clone()) } else { (b.clone(), a.clone()) };
+            *self.partners.entry(key).or_default() += 1;
+        }
+    }
+}
+
+/// Staff every scheduled flight. Returns the flights that changed.
+pub fn assign(state: &mut RaceState, umpire_pairs: usize, support_boats: usize) -> Result<FlightDelta, OfficialsError> {
+    let mut flights: Vec<(String, u32, bool)> = state.flights.values()
+        .map(|f| (f.id.clone(), f.flight_number, f.status == FlightStatus::Scheduled))
+        .collect();
+    flights.sort_by_key(|(_, number, _)| *number);
+
+    let mut load = Load::default();
+    for (id, number, _) in flights.iter().filter(|(_, _, open)| !open) {
+        load.add(&state.flights[id].officials, *number);
+    }
+
+    let mut planned: Vec<(String, FlightOfficials)> = Vec::new();
+    for (id, number, _) in flights.iter().filter(|(_, _, open)| *open) {
+        let available = |kind: OfficialKind| -> Vec<&Official> {
+            state.officials.iter()
+                .filter(|o| o.kind == kind && !o.unavailable_flights.contains(number))
+                .collect()
+        };
+        let mut umpires = available(OfficialKind::Umpire);
+        let mut boats = available(OfficialKind::SupportBoat);
+        if umpires.len() < umpire_pairs * 2 {
+            return Err(OfficialsError::NotEnoughUmpires(*number, umpire_pairs * 2, umpires.len()));
+        }
+        if boats.len() < support_boats {
+            return Err(OfficialsError::NotEnoughSupportBoats(*number, support_boats, boats.len()));
+        }
+
+        let mut officials = FlightOfficials::default();
+        umpires.sort_by_key(|o| (load.key(&o.id), o.id.clone()));
+        for _ in 0..umpire_pairs {
+            let first = umpires.remove(0);
+            let Some((i, _)) = umpires.iter().enumerate()
+                .min_by_key(|(_, o)| (load.key(&o.id).0, load.partnered(&first.id, &o.id), load.key(&o.id).1, o.id.clone()))
+            else {
+                break;
+            };
+            let second = umpires.remove(i);
+            officials.umpire_pairs.push([first.id.clone(), second.id.clone()]);
+        }
+        boats.sort_by_key(|o| (load.key(&o.id), o.id.clone()));
+        officials.support_boats = boats.iter().take(support_boats).map(|o| o.id.clone()).collect();
+
+        load.add(&officials, *number);
+        planned.push((id.clone(), officials));
+    }
+
+    let mut delta = FlightDelta::default();
+    for (id, officials) in planned {
+        if let Some(flight) = state.flights.get_mut(&id) {
+            if flight.officials != officials {
+                flight.officials = officials;
+                delta.flights.push(flight.clone());
+            }
+        }
+    }
+    Ok(delta)
+}
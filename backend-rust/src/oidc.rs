@@ -0,0 +1,268 @@
+//! # oidc
+//!
+//! Sign-in with an OpenID Connect identity provider: Apple, Google or any
+//! generic issuer (a club's Microsoft, Keycloak or Auth0 tenant).
+//!
+//! A provider verifies ID tokens against its JWKS (cached, refreshed every
+//! `OIDC_JWKS_REFRESH_SECS`, default 3600, and refetched when an unknown `kid`
+//! shows up), its issuer and its audiences (the app's client ids), with the
+//! clock skew of `SUPABASE_JWT_LEEWAY_SECS`.
+//!
+//! - Apple — `APPLE_CLIENT_ID`
+//! - Google — `GOOGLE_CLIENT_ID`
+//! - generic — `[[oidc]]` tables in `config.toml` (exported as `OIDC_PROVIDERS`
+//!   JSON); `jwksUrl` may be left out, it is then read from the issuer's
+//!   `/.well-known/openid-configuration`
+//!
+//! ```toml
+//! [[oidc]]
+//! name = "club"
+//! issuer = "https://login.microsoftonline.com/<tenant>/v2.0"
+//! audiences = ["<client id>"]
+//! role_claim = "roles"
+//! ```
+//!
+//! These providers know who someone is, not what they do on the water. The role
+//! comes from `roleClaim` if the provider issues one, else from the officials
+//! list `AUTH_OFFICIALS` (`"pro@club.org=director,jury@club.org=jury"`, by
+//! verified email), else the provider's `defaultRole` (`AUTH_DEFAULT_ROLE` for
+//! Apple and Google). Without any of them the token is refused.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL;
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{error, info, warn};
+
+/// An unknown `kid` refetches the JWKS (key rotation), at most this often.
+pub const MISS_REFETCH: Duration = Duration::from_secs(30);
+
+/// Signing keys by `kid`.
+pub type KeySet = HashMap<String, (DecodingKey, Algorithm)>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OidcProviderConfig {
+    pub name: String,
+    pub issuer: String,
+    /// Accepted `aud` values (client ids)
+    pub audiences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "jwks_url")]
+    pub jwks_url: Option<String>,
+    /// Claim holding the role (a string, or a list whose first entry is used)
+    #[serde(skip_serializing_if = "Option::is_none", alias = "role_claim")]
+    pub role_claim: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "default_role")]
+    pub default_role: Option<String>,
+}
+
+pub fn refresh_interval() -> Duration {
+    Duration::from_secs(std::env::var("OIDC_JWKS_REFRESH_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(3600))
+}
+
+/// Apple, Google and the configured generic issuers.
+pub fn providers() -> Vec<OidcProviderConfig> {
+    let env = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let default_role = env("AUTH_DEFAULT_ROLE");
+    let mut providers = Vec::new();
+    if let Some(client_id) = env("APPLE_CLIENT_ID") {
+        providers.push(OidcProviderConfig {
+            name: "apple".to_string(),
+            issuer: "https://appleid.apple.com".to_string(),
+            audiences: client_id.split(',').map(|c| c.trim().to_string()).collect(),
+            jwks_url: Some("https://appleid.apple.com/auth/keys".to_string()),
+            role_claim: None,
+            default_role: default_role.clone(),
+        });
+    }
+    if let Some(client_id) = env("GOOGLE_CLIENT_ID") {
+        providers.push(OidcProviderConfig {
+            name: "google".to_string(),
+            issuer: "https://accounts.google.com".to_string(),
+            audiences: client_id.split(',').map(|c| c.trim().to_string()).collect(),
+            jwks_url: Some("https://www.googleapis.com/oauth2/v3/certs".to_string()),
+            role_claim: None,
+            default_role: default_role.clone(),
+        });
+    }
+    if let Some(json) = env("OIDC_PROVIDERS") {
+        match serde_json::from_str::<Vec<OidcProviderConfig>>(&json) {
+            Ok(generic) => providers.extend(generic.into_iter().filter(|p| !p.issuer.is_empty() && !p.audiences.is_empty())),
+            Err(e) => warn!("OIDC: ignoring OIDC_PROVIDERS: {e}"),
+        }
+    }
+    providers
+}
+
+/// Verified email → role, from `AUTH_OFFICIALS`.
+pub fn officials() -> HashMap<String, String> {
+    std::env::var("AUTH_OFFICIALS").unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| {
+            let (email, role) = pair.split_once('=')?;
+            Some((email.trim().to_lowercase(), role.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Google issues `accounts.google.com` as well as the URL form.
+pub fn issuers(provider: &OidcProviderConfig) -> Vec<String> {
+    let mut issuers = vec![provider.issuer.clone()];
+    if let Some(host) = provider.issuer.strip_prefix("https://").filter(|_| provider.name == "google") {
+        issuers.push(host.to_string());
+    }
+    issuers
+}
+
+/// Role for the verified claims of a provider's token.
+pub fn role(provider: &OidcProviderConfig, claims: &Value, officials: &HashMap<String, String>) -> Option<String> {
+    if let Some(claim) = provider.role_claim.as_deref().and_then(|c| claims.get(c)) {
+        let role = match claim {
+            Value::String(role) => Some(role.clone()),
+            Value::Array(roles) => roles.iter().find_map(|r| r.as_str().map(str::to_string)),
+            _ => None,
+        };
+        if role.is_some() {
+            return role;
+        }
+    }
+    // Apple sends email_verified as a string
+    let verified = matches!(claims.get("email_verified"), Some(Value::Bool(true))) || claims.get("email_verified").and_then(Value::as_str) == Some("true");
+    let by_email = claims.get("email").and_then(Value::as_str)
+        .filter(|_| verified)
+        .and_then(|email| officials.get(&email.to_lowercase()).cloned());
+    by_email.or_else(|| provider.default_role.clone())
+}
+
+/// The provider's JWKS URL, from its discovery document when not configured.
+async fn jwks_url(provider: &OidcProviderConfig) -> Option<String> {
+    if let Some(url) = &provider.jwks_url {
+        return Some(url.clone());
+    }
+    let discovery = format!("{}/.well-known/openid-configuration", provider.issuer.trim_end_matches('/'));
+    match reqwest::get(&discovery).await {
+        Ok(res) => res.json::<Value>().await.ok()?.get("jwks_uri")?.as_str().map(str::to_string),
+        Err(e) => {
+            error!("OIDC {}: discovery failed: {e}", provider.name);
+            None
+        }
+    }
+}
+
+/// Fetch a JWKS and keep its RSA and EC signing keys.
+pub async fn fetch_jwks(label: &str, url: &str) -> Option<KeySet> {
+    info!("Fetching {label} signing keys from {url}...");
+    let jwks = match reqwest::get(url).await {
+        Ok(res) => match res.json::<JwkSet>().await {
+            Ok(jwks) => jwks,
+            Err(e) => {
+                error!("Failed to parse {label} JWKS payload: {e}");
+                return None;
+            }
+        },
+        Err(e) => {
+            error!("Network failure pulling {label} JWKS: {e}");
+            return None;
+        }
+    };
+    let mut keys = KeySet::new();
+    for jwk in &jwks.keys {
+        let Some(kid) = jwk.common.key_id.clone() else { continue };
+        let algorithm = match jwk.algorithm {
+            AlgorithmParameters::RSA(_) => Algorithm::RS256,
+            AlgorithmParameters::EllipticCurve(_) => Algorithm::ES256,
+            _ => continue,
+        };
+        match DecodingKey::from_jwk(jwk) {
+            Ok(key) => {
+                keys.insert(kid, (key, algorithm));
+            }
+            Err(e) => warn!("Skipping {label} key {kid}: {e}"),
+        }
+    }
+    // An empty or unusable set keeps the previous keys
+    if keys.is_empty() {
+        warn!("{label} JWKS held no usable keys");
+        return None;
+    }
+    info!("Successfully cached {} {label} signing keys.", keys.len());
+    Some(keys)
+}
+
+/// The `iss` of a token, read without verifying it — only to pick the provider.
+pub fn unverified_issuer(token: &str) -> Option<String> {
+    let payload = BASE64_URL.decode(token.split('.').nth(1)?).ok()?;
+    serde_json::from_slice::<Value>(&payload).ok()?.get("iss")?.as_str().map(str::to_string)
+}
+
+/// A configured provider with its cached signing keys.
+pub struct OidcProvider {
+    pub config: OidcProviderConfig,
+    issuers: Vec<String>,
+    keys: RwLock<KeySet>,
+    fetched: Mutex<Option<Instant>>,
+}
+
+impl OidcProvider {
+    pub fn new(config: OidcProviderConfig) -> Self {
+        Self {
+            issuers: issuers(&config),
+            config,
+            keys: RwLock::new(KeySet::new()),
+            fetched: Mutex::new(None),
+        }
+    }
+
+    pub fn issues(&self, iss: &str) -> bool {
+        self.issuers.iter().any(|i| i == iss)
+    }
+
+    pub async fn refresh_keys(&self) {
+        *self.fetched.lock().await = Some(Instant::now());
+        let Some(url) = jwks_url(&self.config).await else { return };
+        if let Some(keys) = fetch_jwks(&self.config.name, &url).await {
+            *self.keys.write().await = keys;
+        }
+    }
+
+    /// Cached key for `kid`, refetched once if it is unknown.
+    async fn key(&self, alg: Algorithm, kid: &str) -> Option<DecodingKey> {
+        let cached = |keys: &KeySet| keys.get(kid).filter(|(_, a)| *a == alg).map(|(k, _)| k.clone());
+        if let Some(key) = cached(&*self.keys.read().await) {
+            return Some(key);
+        }
+        let stale = self.fetched.lock().await.is_none_or(|at| at.elapsed() >= MISS_REFETCH);
+        if !stale {
+            return None;
+        }
+        self.refresh_keys().await;
+        cached(&*self.keys.read().await)
+    }
+
+    /// Verifies an ID token (signature, `exp`, `aud`, `iss`) and returns its claims.
+    pub async fn verify(&self, token: &str, leeway_secs: u64) -> Option<Value> {
+        let header = decode_header(token).ok()?;
+        let Some(key) = self.key(header.alg, header.kid.as_deref()?).await else {
+            warn!("OIDC {}: token rejected, no {:?} key for kid {:?}", self.config.name, header.alg, header.kid);
+            return None;
+        };
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&self.config.audiences);
+        validation.set_issuer(&self.issuers);
+        validation.set_required_spec_claims(&["exp", "sub", "aud", "iss"]);
+        validation.leeway = leeway_secs;
+        match decode::<Value>(token, &key, &validation) {
+            Ok(data) => Some(data.claims),
+            Err(e) => {
+                warn!("OIDC {}: token validation failed: {e}", self.config.name);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! # penalties
+//!
+//! Penalty lifecycle after `issue-penalty`:
+//!
+//! - `update-penalty { penaltyId, status?, type?, note? }` — `PENDING`,
+//!   `TAKEN_ON_WATER`, `CLEARED` or `UNDER_PROTEST`, a corrected type, a note
+//! - `withdraw-penalty { penaltyId, note? }` — status `WITHDRAWN`
+//!
+//! The penalty is looked up in the race in progress, then in the closed races;
+//! an edit to a closed race rescores the series. Only penalties still on the
+//! record count in scoring (`Penalty::counts`), so a turn taken on the water
+//! clears the boat's score. Each change is broadcast as `penalty-updated`.
+//!
+//! Officials may edit any penalty. A tracker may only report its own boat's
+//! pending penalty as taken on the water.
+//!
+//! ## Invariants
+//! - Core Invariant #2: every change arrives as an audited command
+
+use serde::{Deserialize, Serialize};
+
+use crate::scoring;
+use crate::state::{Penalty, PenaltyStatus, PenaltyType, RaceState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PenaltyError {
+    #[error("Unknown penalty: {0}")]
+    NotFound(String),
+    #[error("Penalty {0} was withdrawn")]
+    Withdrawn(String),
+    #[error("Competitors may only report their own pending penalty as taken")]
+    NotAllowed,
+}
+
+/// `update-penalty` / `withdraw-penalty` payload.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PenaltyEdit {
+    pub penalty_id: String,
+    #[serde(default)]
+    pub status: Option<PenaltyStatus>,
+    #[serde(default, rename = "type")]
+    pub penalty_type: Option<PenaltyType>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// `penalty-updated` payload: the penalty and the closed race it belongs to, if any.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PenaltyUpdate {
+    pub penalty: Penalty,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub race_number: Option<u32>,
+}
+
+fn find<'a>(state: &'a mut RaceState, id: &str) -> Option<(&'a mut Penalty, Option<u32>)> {
+    if let Some(i) = state.penalties.iter().position(|p| p.id == id) {
+        return Some((&mut state.penalties[i], None));
+    }
+    state.results.iter_mut()
+        .find_map(|r| {
+            let number = r.race_number;
+            r.penalties.iter_mut().find(|p| p.id == id).map(|p| (p, Some(number)))
+        })
+}
+
+/// Apply `edit`. `tracker_boat` is the acting competitor's boat (None for officials).
+pub fn update(state: &mut RaceState, edit: PenaltyEdit, tracker_boat: Option<&str>, now: i64) -> Result<PenaltyUpdate, PenaltyError> {
+    let (penalty, race_number) = find(state, &edit.penalty_id).ok_or_else(|| PenaltyError::NotFound(edit.penalty_id.clone()))?;
+    if penalty.status == PenaltyStatus::Withdrawn {
+        return Err(PenaltyError::Withdrawn(penalty.id.clone()));
+    }
+    if let Some(boat) = tracker_boat {
+        let own_turn = penalty.boat_id == boat
+            && penalty.status == PenaltyStatus::Pending
+            && edit.status == Some(PenaltyStatus::TakenOnWater)
+            && edit.penalty_type.is_none();
+        if !own_turn {
+            return Err(PenaltyError::NotAllowed);
+        }
+    }
+
+    if let Some(status) = edit.status {
+        penalty.status = status;
+    }
+    if let Some(penalty_type) = edit.penalty_type {
+        penalty.penalty_type = penalty_type;
+    }
+    if edit.note.is_some() {
+        penalty.note = edit.note;
+    }
+    penalty.updated_ms = Some(now);
+    let penalty = penalty.clone();
+
+    if race_number.is_some() {
+        scoring::rescore(state);
+    }
+    Ok(PenaltyUpdate { penalty, race_number })
+}
+
+pub fn withdraw(state: &mut RaceState, penalty_id: &str, note: Option<String>, now: i64) -> Result<PenaltyUpdate, PenaltyError> {
+    update(state, PenaltyEdit {
+        penalty_id: penalty_id.to_string(),
+        status: Some(PenaltyStatus::Withdrawn),
+        penalty_type: None,
+        note,
+    }, None, now)
+}
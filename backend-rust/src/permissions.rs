@@ -0,0 +1,105 @@
+//! # permissions
+//!
+//! Role → allowed socket events, checked by `handlers::authorize_command` before any
+//! guarded command runs.
+//!
+//! The built-in matrix reproduces the long-standing rules: the director may do
+//! everything, the jury handles penalties, protests and redress and may read crew
+//! emergency contacts, a tracker may file and withdraw its own protests and
+//! report its own penalty turn as taken; jury and trackers can also send and
+//! acknowledge messages, and jury and media may open race playback and stored
+//! boat tracks; media also tunes and overrides the auto-director. Banning boats
+//! (`kill-tracker`, `blacklist-add`/`-remove`) and reading the ban list
+//! (`list-blacklist`) stay with the director. `PERMISSIONS_FILE` points at
+//! a JSON object that overrides it per role, e.g.
+//!
+//! ```json
+//! { "jury": ["issue-penalty", "decide-protest", "protest-replay"], "media": [] }
+//! ```
+//!
+//! A role listed in the file gets exactly those events (`"*"` = all); roles not
+//! listed keep their built-in entry. A missing or unreadable file leaves the
+//! built-in matrix in place.
+//!
+//! Directors can add roles per event at runtime (see `roles`); those are checked
+//! by `AuthEngine::authorize` after this matrix and cannot redefine its roles.
+//!
+//! ## Invariants
+//! - Core Invariant #2: denied attempts are written to the audit chain
+
+use std::collections::{HashMap, HashSet};
+
+use tracing::{info, warn};
+
+pub const ALL_EVENTS: &str = "*";
+
+const JURY_EVENTS: &[&str] = &[
+    "issue-penalty",
+    "update-penalty",
+    "withdraw-penalty",
+    "file-protest",
+    "schedule-hearing",
+    "decide-protest",
+    "withdraw-protest",
+    "protest-replay",
+    "decide-redress",
+    "playback",
+    "get-logs",
+    "get-track",
+    "get-crew-contacts",
+    "send-message",
+    "ack-message",
+];
+
+const TRACKER_EVENTS: &[&str] = &["file-protest", "withdraw-protest", "update-penalty", "send-message", "ack-message"];
+
+const MEDIA_EVENTS: &[&str] = &["playback", "get-track", "director-config", "director-override"];
+
+#[derive(Debug, Clone)]
+pub struct PermissionMatrix {
+    roles: HashMap<String, HashSet<String>>,
+}
+
+impl Default for PermissionMatrix {
+    fn default() -> Self {
+        let set = |events: &[&str]| events.iter().map(|e| e.to_string()).collect::<HashSet<_>>();
+        Self {
+            roles: HashMap::from([
+                ("director".to_string(), set(&[ALL_EVENTS])),
+                ("jury".to_string(), set(JURY_EVENTS)),
+                ("tracker".to_string(), set(TRACKER_EVENTS)),
+                ("media".to_string(), set(MEDIA_EVENTS)),
+            ]),
+        }
+    }
+}
+
+impl PermissionMatrix {
+    /// Built-in matrix with the roles from `PERMISSIONS_FILE` (if set) layered on top.
+    pub fn load() -> Self {
+        let mut matrix = Self::default();
+        let Some(path) = std::env::var("PERMISSIONS_FILE").ok().filter(|p| !p.is_empty()) else {
+            return matrix;
+        };
+        let overrides = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_str::<HashMap<String, HashSet<String>>>(&data).map_err(|e| e.to_string()));
+        match overrides {
+            Ok(overrides) => {
+                info!("Permissions: {} role(s) loaded from {path}", overrides.len());
+                matrix.roles.extend(overrides);
+            }
+            Err(e) => warn!("Permissions: failed to load {path}: {e}, using built-in matrix"),
+        }
+        matrix
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.contains_key(role)
+    }
+
+    pub fn authorize(&self, event: &str, role: Option<&str>) -> bool {
+        role.and_then(|r| self.roles.get(r))
+            .is_some_and(|events| events.contains(ALL_EVENTS) || events.contains(event))
+    }
+}
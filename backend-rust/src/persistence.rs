@@ -1,21 +1,30 @@
 use anyhow::Result;
-use std::path::Path;
-use tokio::fs;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use socketioxide::SocketIo;
+use tokio::sync::Notify;
 use tracing::{info, warn};
 
-use crate::state::RaceState;
+use crate::handlers::{log_to, Outlet, SharedState};
+use crate::procedure_engine::EngineSnapshot;
+use crate::state::{LogCategory, RaceState};
+use crate::state_schema;
+use crate::state_store::store;
 
-const STATE_FILE: &str = "state.json";
+const STATE_KEY: &str = "state";
+const ENGINE_STATE_KEY: &str = "engine_state";
+const CLASS_ENGINE_STATE_KEY: &str = "class_engine_state";
 
-/// Load persisted state from disk. Returns default if file missing or corrupt.
+/// Load persisted state from the store. Returns default if missing or corrupt.
 pub async fn load_state() -> RaceState {
-    if !Path::new(STATE_FILE).exists() {
-        info!("No state.json found, using default state");
-        return RaceState::default();
-    }
-
-    match fs::read_to_string(STATE_FILE).await {
-        Ok(data) => match serde_json::from_str::<RaceState>(&data) {
+    match store().load(STATE_KEY).await {
+        Ok(None) => {
+            info!("No saved state in {} store, using default state", store().name());
+            RaceState::default()
+        }
+        Ok(Some(data)) => match serde_json::from_str(&data).and_then(|doc| state_schema::upgrade(doc, "Saved state")) {
             Ok(mut state) => {
                 // Reset ephemeral runtime fields on load
                 state.boats.clear();
@@ -23,6 +32,11 @@ pub async fn load_state() -> RaceState {
                 state.current_sequence = None;
                 state.sequence_time_remaining = None;
                 state.start_time = None;
+                state.class_sequences.clear();
+                if let Some(p) = state.pursuit.as_mut() {
+                    crate::pursuit::reset(p);
+                }
+                state.mark_roundings.clear();
                 info!("Loaded state from disk (course: {} marks, wind: {}kn {}°)",
                     state.course.marks.len(),
                     state.wind.speed,
@@ -31,31 +45,139 @@ pub async fn load_state() -> RaceState {
                 state
             }
             Err(e) => {
-                warn!("Failed to parse state.json: {e}, using default state");
+                warn!("Failed to parse saved state: {e}, using default state");
                 RaceState::default()
             }
         },
         Err(e) => {
-            warn!("Failed to read state.json: {e}, using default state");
+            warn!("Failed to read saved state: {e}, using default state");
             RaceState::default()
         }
     }
 }
 
-/// Save the persistent parts of state to disk. Strips ephemeral fields.
+/// Request a save of the persistent state. Once `run_state_saver` is running the
+/// write happens there, coalesced with other saves; before that it is immediate.
 pub async fn save_state(state: &RaceState) -> Result<()> {
-    // Build a saveable copy — omit ephemeral boat telemetry
-    let save = RaceState {
+    if let Some(saver) = SAVER.get() {
+        saver.dirty.store(true, Ordering::Release);
+        saver.notify.notify_one();
+        return Ok(());
+    }
+    write_state(state).await
+}
+
+/// Save the persistent parts of state to the store now.
+pub async fn write_state(state: &RaceState) -> Result<()> {
+    write_saveable(&saveable(state)).await
+}
+
+/// A saveable copy — omits ephemeral fields and boat telemetry (trails live in `track_store`).
+/// Recorded finishes are kept, so a restart before `scoring::close_race` still scores them.
+pub fn saveable(state: &RaceState) -> RaceState {
+    RaceState {
+        schema_version: state_schema::SCHEMA_VERSION,
         status: crate::state::RaceStatus::Idle,
         current_sequence: None,
         sequence_time_remaining: None,
         start_time: None,
         boats: std::collections::HashMap::new(),
         penalties: Vec::new(),
+        class_sequences: std::collections::HashMap::new(),
+        fleet_history: std::collections::HashMap::new(),
         ..state.clone()
+    }
+}
+
+async fn write_saveable(save: &RaceState) -> Result<()> {
+    let json = serde_json::to_string_pretty(save)?;
+    store().save(STATE_KEY, &json).await
+}
+
+struct Saver {
+    dirty: AtomicBool,
+    notify: Notify,
+}
+
+static SAVER: OnceLock<Saver> = OnceLock::new();
+
+fn debounce() -> Duration {
+    let ms = std::env::var("STATE_SAVE_DEBOUNCE_MS").ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(2000);
+    Duration::from_millis(ms)
+}
+
+/// Background saver: every `save_state` within `STATE_SAVE_DEBOUNCE_MS` (default
+/// 2000) of the first becomes one write. A failed save raises an active system
+/// log entry, once per run of failures, and is retried one debounce later.
+pub async fn run_state_saver(shared: SharedState, io: SocketIo) {
+    let saver = SAVER.get_or_init(|| Saver { dirty: AtomicBool::new(false), notify: Notify::new() });
+    let delay = debounce();
+    let mut failing = false;
+    loop {
+        saver.notify.notified().await;
+        tokio::time::sleep(delay).await;
+        if !saver.dirty.swap(false, Ordering::AcqRel) {
+            continue;
+        }
+        // Copy under the lock, write without it
+        let snapshot = saveable(&*shared.read().await);
+        match write_saveable(&snapshot).await {
+            Ok(()) if failing => {
+                failing = false;
+                info!("State save recovered");
+                log_to(&shared, &Outlet::Io(io.clone()), LogCategory::System, "Persistence".to_string(),
+                    "State saves recovered".to_string(), None, false).await;
+            }
+            Ok(()) => {}
+            Err(e) => {
+                warn!("State save failed: {e}");
+                saver.dirty.store(true, Ordering::Release);
+                saver.notify.notify_one();
+                if !failing {
+                    failing = true;
+                    log_to(&shared, &Outlet::Io(io.clone()), LogCategory::System, "Persistence".to_string(),
+                        format!("State save failed: {e}"), Some(serde_json::json!({ "store": store().name() })), true).await;
+                }
+            }
+        }
+    }
+}
+
+/// Load the running procedure snapshot left by a previous process, if any.
+pub async fn load_engine_state() -> Option<EngineSnapshot> {
+    let data = store().load(ENGINE_STATE_KEY).await.ok()??;
+    match serde_json::from_str::<EngineSnapshot>(&data) {
+        Ok(snapshot) => Some(snapshot),
+        Err(e) => {
+            warn!("Failed to parse {ENGINE_STATE_KEY}: {e}, not resuming sequence");
+            None
+        }
+    }
+}
+
+/// Persist the running procedure (or clear it when the engine is idle).
+/// The file store writes via a temp file + rename, so a crash mid-write never leaves a torn snapshot.
+pub async fn save_engine_state(snapshot: Option<&EngineSnapshot>) -> Result<()> {
+    match snapshot {
+        Some(snapshot) => store().save(ENGINE_STATE_KEY, &serde_json::to_string(snapshot)?).await,
+        None => store().remove(ENGINE_STATE_KEY).await,
+    }
+}
+
+/// Load the per-class procedure snapshots left by a previous process.
+pub async fn load_class_engine_states() -> HashMap<String, EngineSnapshot> {
+    let Ok(Some(data)) = store().load(CLASS_ENGINE_STATE_KEY).await else {
+        return HashMap::new();
     };
+    serde_json::from_str(&data).unwrap_or_else(|e| {
+        warn!("Failed to parse {CLASS_ENGINE_STATE_KEY}: {e}, not resuming class sequences");
+        HashMap::new()
+    })
+}
 
-    let json = serde_json::to_string_pretty(&save)?;
-    fs::write(STATE_FILE, json).await?;
-    Ok(())
+/// Persist every running class sequence.
+pub async fn save_class_engine_states(snapshots: &HashMap<String, EngineSnapshot>) -> Result<()> {
+    store().save(CLASS_ENGINE_STATE_KEY, &serde_json::to_string(snapshots)?).await
 }
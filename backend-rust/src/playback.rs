@@ -0,0 +1,188 @@
+//! # playback
+//!
+//! Time-scrubbing playback of `recording` sessions on the `/playback` namespace,
+//! so media and jury can move through a race like a video timeline.
+//!
+//! - Connect with `auth: { token }`; the token's role needs `playback` in the
+//!   permission matrix (director, jury, media by default)
+//! - `list-recordings` → `recordings`
+//! - `playback-seek { recordingId?, t }` — open a recording (first seek) and jump to
+//!   wall-clock ms `t`; the state as it was then comes back as `playback-frame`
+//! - `playback-rate { rate }` — 0 pauses, 1 is real time, up to 16× fast-forward
+//!
+//! Every socket has its own cursor. While playing, `playback-frame`
+//! `{ recordingId, t, startMs, endMs, rate, state }` is emitted whenever the
+//! reconstructed state moved; reaching the end pauses.
+//!
+//! ## Invariants
+//! - Core Invariant #2: refused connections are written to the audit chain
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use socketioxide::extract::{Data, SocketRef};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::audit::AuditLogger;
+use crate::auth::AuthEngine;
+use crate::recording::{self, Frame};
+use crate::rest_api::token_role;
+use crate::state_delta;
+
+pub const NAMESPACE: &str = "/playback";
+const TICK: Duration = Duration::from_millis(200);
+const MAX_RATE: f64 = 16.0;
+
+struct Player {
+    id: String,
+    frames: Vec<Frame>,
+    /// Index of the last frame applied to `state`
+    cursor: usize,
+    state: Value,
+    t: f64,
+    rate: f64,
+}
+
+impl Player {
+    fn new(id: String, frames: Vec<Frame>) -> Self {
+        let state = frames[0].keyframe.clone().unwrap_or_default();
+        let t = frames[0].t as f64;
+        Self { id, frames, cursor: 0, state, t, rate: 0.0 }
+    }
+
+    fn start_ms(&self) -> i64 {
+        self.frames[0].t
+    }
+
+    fn end_ms(&self) -> i64 {
+        self.frames.last().map(|f| f.t).unwrap_or_default()
+    }
+
+    /// Apply frames up to `t`; true if the state moved.
+    fn advance_to(&mut self, t: i64) -> bool {
+        let mut moved = false;
+        while let Some(frame) = self.frames.get(self.cursor + 1).filter(|f| f.t <= t) {
+            match &frame.keyframe {
+                Some(state) => self.state = state.clone(),
+                None => state_delta::apply(&mut self.state, &frame.ops),
+            }
+            self.cursor += 1;
+            moved = true;
+        }
+        moved
+    }
+
+    /// Rebuild the state at `t` from the nearest keyframe at or before it.
+    fn seek(&mut self, t: i64) {
+        let t = t.clamp(self.start_ms(), self.end_ms());
+        let upto = self.frames.partition_point(|f| f.t <= t);
+        let keyframe = self.frames[..upto].iter().rposition(|f| f.keyframe.is_some()).unwrap_or(0);
+        self.cursor = keyframe;
+        self.state = self.frames[keyframe].keyframe.clone().unwrap_or_default();
+        self.advance_to(t);
+        self.t = t as f64;
+    }
+
+    fn frame(&self) -> Value {
+        json!({
+            "recordingId": self.id,
+            "t": self.t as i64,
+            "startMs": self.start_ms(),
+            "endMs": self.end_ms(),
+            "rate": self.rate,
+            "state": self.state,
+        })
+    }
+}
+
+type SharedPlayer = Arc<Mutex<Option<Player>>>;
+
+pub async fn on_connect(socket: SocketRef, auth_data: Value, auth: Arc<AuthEngine>, audit: AuditLogger) {
+    let socket_id = socket.id.to_string();
+    let role = match auth_data["token"].as_str() {
+        Some(token) => token_role(&auth, token).await,
+        None => None,
+    };
+    if !auth.authorize("playback", role.as_deref()).await {
+        warn!("Playback: refused {socket_id} (role {})", role.as_deref().unwrap_or("none"));
+        audit.log_permission_denied("playback", &socket_id, role.as_deref()).await;
+        let _ = socket.disconnect();
+        return;
+    }
+    info!("Playback: {socket_id} connected as {}", role.as_deref().unwrap_or("unknown"));
+
+    let player: SharedPlayer = Arc::new(Mutex::new(None));
+    let ticker = tokio::spawn(run_player(socket.clone(), player.clone())).abort_handle();
+    socket.on_disconnect(move |_: SocketRef| {
+        let ticker = ticker.clone();
+        async move { ticker.abort() }
+    });
+
+    socket.on("list-recordings", |s: SocketRef| async move {
+        let _ = s.emit("recordings", &recording::list().await);
+    });
+
+    {
+        let player = player.clone();
+        socket.on("playback-seek", move |s: SocketRef, Data::<Value>(data)| {
+            let player = player.clone();
+            async move {
+                let mut player = player.lock().await;
+                let requested = data["recordingId"].as_str();
+                if let Some(id) = requested.filter(|id| player.as_ref().is_none_or(|p| p.id != *id)) {
+                    match recording::load(id).await {
+                        Ok(frames) => *player = Some(Player::new(id.to_string(), frames)),
+                        Err(e) => {
+                            let _ = s.emit("x-error", &json!({ "error": e.to_string() }));
+                            return;
+                        }
+                    }
+                }
+                let Some(p) = player.as_mut() else {
+                    let _ = s.emit("x-error", &json!({ "error": "playback-seek needs a recordingId first" }));
+                    return;
+                };
+                let t = data["t"].as_i64().unwrap_or_else(|| p.start_ms());
+                p.seek(t);
+                let _ = s.emit("playback-frame", &p.frame());
+            }
+        });
+    }
+
+    socket.on("playback-rate", move |s: SocketRef, Data::<Value>(data)| {
+        let player = player.clone();
+        async move {
+            let mut player = player.lock().await;
+            let Some(p) = player.as_mut() else {
+                let _ = s.emit("x-error", &json!({ "error": "No recording open" }));
+                return;
+            };
+            p.rate = data["rate"].as_f64().unwrap_or(1.0).clamp(0.0, MAX_RATE);
+            let _ = s.emit("playback-frame", &p.frame());
+        }
+    });
+}
+
+async fn run_player(socket: SocketRef, player: SharedPlayer) {
+    let mut ticker = tokio::time::interval(TICK);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last = Instant::now();
+    loop {
+        ticker.tick().await;
+        let elapsed = last.elapsed().as_secs_f64() * 1000.0;
+        last = Instant::now();
+
+        let mut player = player.lock().await;
+        let Some(p) = player.as_mut().filter(|p| p.rate > 0.0) else { continue };
+        p.t = (p.t + elapsed * p.rate).min(p.end_ms() as f64);
+        let moved = p.advance_to(p.t as i64);
+        if p.t as i64 >= p.end_ms() {
+            p.rate = 0.0;
+        } else if !moved {
+            continue;
+        }
+        let _ = socket.emit("playback-frame", &p.frame());
+    }
+}
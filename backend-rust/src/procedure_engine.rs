@@ -1,7 +1,57 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
 use tracing::info;
 
-use crate::state::{ProcedureGraph, ProcedureNode, RaceStatus, SequenceInfo, SequenceUpdate, SoundSignal};
+use crate::state::{Countdown, ProcedureGraph, ProcedureNode, RaceStatus, SequenceInfo, SequenceUpdate, SoundSignal, UpcomingSignal};
+
+/// Wall-clock snapshot of the engine's runtime state, persisted so a backend
+/// restart mid-sequence resumes with the correct countdown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineSnapshot {
+    pub graph: ProcedureGraph,
+    pub current_node_id: String,
+    /// Unix ms at which the current node started
+    pub node_started_ms: u64,
+    pub sequence_started_ms: Option<u64>,
+    pub is_post_trigger: bool,
+    pub post_trigger_started_ms: Option<u64>,
+    pub has_fired_gun: bool,
+    /// Unix ms at which the sequence was paused, if it is paused
+    #[serde(default)]
+    pub paused_ms: Option<u64>,
+}
+
+/// Typed transition events, drained by the tick loops and published on the `EngineBus`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EngineEvent {
+    #[serde(rename_all = "camelCase")]
+    NodeEntered { node_id: String, label: String },
+    #[serde(rename_all = "camelCase")]
+    SoundFired { node_id: String, sound: SoundSignal },
+    /// `by_sequence` is false when a command (start, stop, undo…) moved the status;
+    /// whoever issued the command records that change itself
+    #[serde(rename_all = "camelCase")]
+    StatusChanged { from: RaceStatus, to: RaceStatus, by_sequence: bool },
+    /// Flags displayed now (node flags, or post-trigger flags once triggered)
+    #[serde(rename_all = "camelCase")]
+    FlagsChanged { node_id: String, flags: Vec<String> },
+    #[serde(rename_all = "camelCase")]
+    GunFired { node_id: String },
+    SequenceComplete,
+}
+
+/// Runtime position before the last manual transition, for `undo_transition`.
+#[derive(Debug, Clone)]
+struct UndoPoint {
+    node_id: String,
+    node_started_at: Option<Instant>,
+    sequence_started_at: Option<Instant>,
+    is_post_trigger: bool,
+    post_trigger_started_at: Option<Instant>,
+    has_fired_gun: bool,
+}
 
 /// Tick-based procedure sequencer — RRS-compliant state machine
 pub struct ProcedureEngine {
@@ -12,6 +62,24 @@ pub struct ProcedureEngine {
     pub is_post_trigger: bool,
     pub post_trigger_started_at: Option<Instant>,
     pub has_fired_gun: bool,
+    /// Set while the sequence is paused; all node/sequence clocks are frozen at this instant
+    paused_at: Option<Instant>,
+    /// Start of the phase (node or post-trigger) whose closing signal was already announced
+    announced_phase: Option<Instant>,
+    /// Bumped on every runtime state change; the tick task persists a snapshot when it moves
+    revision: u64,
+    /// Events since the last `drain_events`
+    events: Vec<EngineEvent>,
+    /// Race status as of the last `drain_events`, for StatusChanged
+    reported_status: RaceStatus,
+    /// `tick` moved the race status since the last `drain_events`
+    status_ticked: bool,
+    /// Displayed flags as of the last `drain_events`, for FlagsChanged
+    reported_flags: Vec<String>,
+    /// Clock multiplier — 1.0 for live engines, >1 for rehearsal runs
+    speed: f64,
+    /// Position before the last manual transition; one step only
+    undo: Option<UndoPoint>,
 }
 
 impl ProcedureEngine {
@@ -21,6 +89,7 @@ impl ProcedureEngine {
                 node.data.duration = new_duration;
             }
         }
+        self.revision += 1;
     }
     pub fn new() -> Self {
         Self {
@@ -31,6 +100,122 @@ impl ProcedureEngine {
             is_post_trigger: false,
             post_trigger_started_at: None,
             has_fired_gun: false,
+            paused_at: None,
+            announced_phase: None,
+            revision: 0,
+            events: Vec::new(),
+            reported_status: RaceStatus::Idle,
+            status_ticked: false,
+            reported_flags: Vec::new(),
+            speed: 1.0,
+            undo: None,
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Wall-clock snapshot of the running sequence (None when idle).
+    pub fn snapshot(&self) -> Option<EngineSnapshot> {
+        let now = Instant::now();
+        let now_ms = unix_ms();
+        let to_ms = |t: Instant| now_ms.saturating_sub(now.saturating_duration_since(t).as_millis() as u64);
+        Some(EngineSnapshot {
+            graph: self.graph.clone()?,
+            current_node_id: self.current_node_id.clone()?,
+            node_started_ms: to_ms(self.node_started_at?),
+            sequence_started_ms: self.sequence_started_at.map(to_ms),
+            is_post_trigger: self.is_post_trigger,
+            post_trigger_started_ms: self.post_trigger_started_at.map(to_ms),
+            has_fired_gun: self.has_fired_gun,
+            paused_ms: self.paused_at.map(to_ms),
+        })
+    }
+
+    /// Restore a persisted snapshot, then fast-forward through any nodes whose time
+    /// ran out while the backend was down so the countdown is correct on resume.
+    /// Fast-forwarding runs on the snapshot's wall-clock times; only the resulting
+    /// position is turned back into `Instant`s.
+    pub fn restore(&mut self, mut snapshot: EngineSnapshot) {
+        let now = Instant::now();
+        let now_ms = unix_ms();
+
+        info!("Restoring procedure {} at node {}", snapshot.graph.id, snapshot.current_node_id);
+        self.graph = Some(snapshot.graph.clone());
+        if snapshot.paused_ms.is_none() {
+            self.fast_forward(&mut snapshot, now_ms);
+        }
+
+        // A paused engine's clocks are relative to the moment it froze
+        let (anchor, anchor_ms) = match snapshot.paused_ms {
+            Some(ms) => (instant_before(now, now_ms.saturating_sub(ms)), ms),
+            None => (now, now_ms),
+        };
+        let to_instant = |ms: u64| instant_before(anchor, anchor_ms.saturating_sub(ms));
+        self.current_node_id = Some(snapshot.current_node_id);
+        self.node_started_at = Some(to_instant(snapshot.node_started_ms));
+        self.sequence_started_at = snapshot.sequence_started_ms.map(to_instant);
+        self.is_post_trigger = snapshot.is_post_trigger;
+        self.post_trigger_started_at = snapshot.post_trigger_started_ms.map(to_instant);
+        self.has_fired_gun = snapshot.has_fired_gun;
+        self.paused_at = snapshot.paused_ms.map(|_| anchor);
+        self.revision += 1;
+    }
+
+    /// Advance `snapshot` past expired nodes, starting each next node exactly when the
+    /// previous one ended (rather than "now", which `tick` would do). Stops at
+    /// user-trigger nodes and at the end of the graph, which `tick` then handles normally.
+    /// A start signal that fell in the downtime counts as made, so it never fires late.
+    fn fast_forward(&mut self, snapshot: &mut EngineSnapshot, now_ms: u64) {
+        let to_ms = |secs: f64| (secs.max(0.0) * 1000.0) as u64;
+        loop {
+            let Some(node) = self.graph.as_ref()
+                .and_then(|g| g.nodes.iter().find(|n| n.id == snapshot.current_node_id))
+            else {
+                return;
+            };
+            let data = &node.data;
+
+            let node_ended_ms = if snapshot.is_post_trigger {
+                let Some(post_started) = snapshot.post_trigger_started_ms else { return };
+                let end = post_started + to_ms(data.post_trigger_duration);
+                if end > now_ms {
+                    return;
+                }
+                end
+            } else {
+                if data.wait_for_user_trigger || data.duration <= 0.0 {
+                    return;
+                }
+                let end = snapshot.node_started_ms + to_ms(data.duration);
+                if end > now_ms {
+                    return;
+                }
+                if data.post_trigger_duration > 0.0 {
+                    snapshot.is_post_trigger = true;
+                    snapshot.post_trigger_started_ms = Some(end);
+                    continue;
+                }
+                end
+            };
+
+            let Some(next_id) = self.get_next_node_id(&snapshot.current_node_id) else { return };
+            let next_racing = self.graph.as_ref()
+                .and_then(|g| g.nodes.iter().find(|n| n.id == next_id))
+                .is_some_and(|n| node_race_status(n) == RaceStatus::Racing);
+            if next_racing || node_race_status(node) == RaceStatus::Racing {
+                snapshot.has_fired_gun = true;
+            }
+
+            info!("Procedure: fast-forwarding to node {next_id}");
+            snapshot.current_node_id = next_id.clone();
+            snapshot.node_started_ms = node_ended_ms;
+            snapshot.is_post_trigger = false;
+            snapshot.post_trigger_started_ms = None;
+            // Signals of skipped nodes are in the past — report the entry, not the sound
+            self.current_node_id = Some(next_id);
+            self.node_entered(false);
         }
     }
 
@@ -38,6 +223,7 @@ impl ProcedureEngine {
         info!("Loading procedure: {} ({} nodes, {} edges)", graph.id, graph.nodes.len(), graph.edges.len());
         self.graph = Some(graph);
         self.current_node_id = None;
+        self.revision += 1;
     }
 
     pub fn get_graph(&self) -> Option<&ProcedureGraph> {
@@ -48,12 +234,17 @@ impl ProcedureEngine {
     pub fn jump_to_node(&mut self, node_id: &str) -> Option<SequenceUpdate> {
         let graph = self.graph.as_ref()?;
         if graph.nodes.iter().any(|n| n.id == node_id) {
+            let undo = self.undo_point();
             self.current_node_id = Some(node_id.to_string());
             self.node_started_at = Some(Instant::now());
             self.sequence_started_at = Some(Instant::now());
             self.is_post_trigger = false;
             self.post_trigger_started_at = None;
             self.has_fired_gun = false;
+            self.paused_at = None;
+            self.revision += 1;
+            self.node_entered(true);
+            self.undo = undo;
             info!("Jumped to node: {node_id}");
             self.build_update()
         } else {
@@ -66,11 +257,13 @@ impl ProcedureEngine {
         let current_id = self.current_node_id.clone()?;
         let graph = self.graph.as_ref()?;
         let current_node = graph.nodes.iter().find(|n| n.id == current_id)?;
+        let undo = self.undo_point();
 
         // If it has post-trigger logic and we are not in it yet, transition to it
-        if !self.is_post_trigger && current_node.data.post_trigger_duration > 0.0 {
+        let update = if !self.is_post_trigger && current_node.data.post_trigger_duration > 0.0 {
             self.is_post_trigger = true;
             self.post_trigger_started_at = Some(Instant::now());
+            self.revision += 1;
             self.build_update()
         } else {
             // Otherwise, jump to the next node
@@ -78,7 +271,47 @@ impl ProcedureEngine {
                 TickResult::Update(u) => Some(u),
                 _ => None,
             }
+        };
+        if update.is_some() {
+            self.undo = undo;
+        }
+        update
+    }
+
+    fn undo_point(&self) -> Option<UndoPoint> {
+        Some(UndoPoint {
+            node_id: self.current_node_id.clone()?,
+            node_started_at: self.node_started_at,
+            sequence_started_at: self.sequence_started_at,
+            is_post_trigger: self.is_post_trigger,
+            post_trigger_started_at: self.post_trigger_started_at,
+            has_fired_gun: self.has_fired_gun,
+        })
+    }
+
+    /// Step back over the last manual transition (`jump_to_node` / `resume_sequence`).
+    /// The previous node's original clocks come back, so its remaining time is what it
+    /// would have been had the transition never happened — if that has run out since,
+    /// the next tick moves on as normal. Only one step is kept, and any automatic
+    /// transition since clears it. Returns the node stepped back from and the update.
+    pub fn undo_transition(&mut self) -> Option<(String, SequenceUpdate)> {
+        if self.paused_at.is_some() {
+            return None;
         }
+        let undo = self.undo.take()?;
+        let from = self.current_node_id.replace(undo.node_id);
+        self.node_started_at = undo.node_started_at;
+        self.sequence_started_at = undo.sequence_started_at;
+        self.is_post_trigger = undo.is_post_trigger;
+        self.post_trigger_started_at = undo.post_trigger_started_at;
+        self.has_fired_gun = undo.has_fired_gun;
+        self.announced_phase = None;
+        self.revision += 1;
+        // The signal was already made once — don't fire it again
+        self.node_entered(false);
+        info!("Procedure: undid transition from {} back to {}", from.as_deref().unwrap_or("-"),
+            self.current_node_id.as_deref().unwrap_or("-"));
+        Some((from.unwrap_or_default(), self.build_update()?))
     }
 
     /// Start the procedure from the first node.
@@ -99,6 +332,10 @@ impl ProcedureEngine {
         self.is_post_trigger = false;
         self.post_trigger_started_at = None;
         self.has_fired_gun = false;
+        self.paused_at = None;
+        self.undo = None;
+        self.revision += 1;
+        self.node_entered(true);
 
         self.build_update()
     }
@@ -110,6 +347,53 @@ impl ProcedureEngine {
         self.is_post_trigger = false;
         self.post_trigger_started_at = None;
         self.has_fired_gun = false;
+        self.paused_at = None;
+        self.undo = None;
+        self.revision += 1;
+    }
+
+    /// Freeze the running sequence in place. Unlike `stop`, all timing is kept so
+    /// `unpause` resumes exactly where the countdown left off.
+    pub fn pause(&mut self) -> Option<SequenceUpdate> {
+        if !self.is_running() || self.paused_at.is_some() {
+            return None;
+        }
+        self.paused_at = Some(Instant::now());
+        self.revision += 1;
+        info!("Procedure: paused at node {}", self.current_node_id.as_deref().unwrap_or("-"));
+        self.build_update()
+    }
+
+    /// Resume a paused sequence, shifting every clock forward by the time spent paused.
+    pub fn unpause(&mut self) -> Option<SequenceUpdate> {
+        let paused_for = self.paused_at.take()?.elapsed();
+        let shift = |t: &mut Option<Instant>| {
+            if let Some(t) = t {
+                *t += paused_for;
+            }
+        };
+        shift(&mut self.node_started_at);
+        shift(&mut self.sequence_started_at);
+        shift(&mut self.post_trigger_started_at);
+        shift(&mut self.announced_phase);
+        self.revision += 1;
+        info!("Procedure: resumed after {:.1}s paused", paused_for.as_secs_f64());
+        self.build_update()
+    }
+
+    /// Seconds since `t` on the engine clock, which stands still while paused.
+    fn elapsed_since(&self, t: Instant) -> f64 {
+        self.paused_at.unwrap_or_else(Instant::now).saturating_duration_since(t).as_secs_f64() * self.speed
+    }
+
+    /// Run the procedure clock `speed` times faster than real time. Only for
+    /// rehearsal engines — snapshots and restore assume a 1× clock.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
     }
 
     pub fn is_running(&self) -> bool {
@@ -131,46 +415,20 @@ impl ProcedureEngine {
             None => return RaceStatus::Idle,
         };
 
-        // Check for explicit raceStatus override on the node
-        if let Some(ref status_str) = node.data.race_status {
-            return match status_str.as_str() {
-                "IDLE" => RaceStatus::Idle,
-                "WARNING" => RaceStatus::Warning,
-                "PREPARATORY" => RaceStatus::Preparatory,
-                "ONE_MINUTE" => RaceStatus::OneMinute,
-                "RACING" => RaceStatus::Racing,
-                "FINISHED" => RaceStatus::Finished,
-                "POSTPONED" => RaceStatus::Postponed,
-                "INDIVIDUAL_RECALL" => RaceStatus::IndividualRecall,
-                "GENERAL_RECALL" => RaceStatus::GeneralRecall,
-                "ABANDONED" => RaceStatus::Abandoned,
-                _ => RaceStatus::Idle,
-            };
-        }
-
-        // Auto-detect from label if no override
-        let label_lower = node.data.label.to_lowercase();
-        if label_lower.contains("warning") {
-            RaceStatus::Warning
-        } else if label_lower.contains("preparatory") || label_lower.contains("prep") {
-            RaceStatus::Preparatory
-        } else if label_lower.contains("one-minute") || label_lower.contains("one minute") || label_lower.contains("1-minute") {
-            RaceStatus::OneMinute
-        } else if label_lower.contains("start") {
-            // Starting signal node — still part of the pre-start sequence
-            RaceStatus::OneMinute
-        } else if label_lower.contains("racing") || label_lower.contains("race") {
-            RaceStatus::Racing
-        } else if label_lower.contains("idle") {
-            RaceStatus::Idle
-        } else {
-            // Default: if engine is running, it's in the pre-start zone
-            RaceStatus::Warning
-        }
+        node_race_status(node)
     }
 
     /// Called at 5Hz. Returns Some(update) whenever state needs to be broadcast.
     pub fn tick(&mut self) -> TickResult {
+        let status_before = self.current_race_status();
+        let result = self.advance();
+        if self.current_race_status() != status_before {
+            self.status_ticked = true;
+        }
+        result
+    }
+
+    fn advance(&mut self) -> TickResult {
         let graph = match &self.graph {
             Some(g) => g,
             None => return TickResult::Idle,
@@ -189,7 +447,15 @@ impl ProcedureEngine {
             None => return TickResult::Idle,
         };
 
-        let elapsed = started_at.elapsed().as_secs_f64();
+        if self.paused_at.is_some() {
+            // Clocks are frozen — keep clients showing the held countdown, no transitions
+            return match self.build_update() {
+                Some(update) => TickResult::Update(update),
+                None => TickResult::Idle,
+            };
+        }
+
+        let elapsed = self.elapsed_since(started_at);
         let duration = current_node.data.duration;
 
         if self.is_post_trigger {
@@ -197,7 +463,7 @@ impl ProcedureEngine {
                 Some(t) => t,
                 None => return TickResult::Idle,
             };
-            let post_elapsed = post_started_at.elapsed().as_secs_f64();
+            let post_elapsed = self.elapsed_since(post_started_at);
             let post_dur = current_node.data.post_trigger_duration;
 
             if post_elapsed >= post_dur {
@@ -216,6 +482,8 @@ impl ProcedureEngine {
                 if current_node.data.post_trigger_duration > 0.0 {
                     self.is_post_trigger = true;
                     self.post_trigger_started_at = Some(Instant::now());
+                    self.undo = None;
+                    self.revision += 1;
                     match self.build_update() {
                         Some(update) => TickResult::Update(update),
                         None => TickResult::Idle,
@@ -230,6 +498,8 @@ impl ProcedureEngine {
                 let status = self.current_race_status();
                 if status == RaceStatus::Racing && !self.has_fired_gun {
                     self.has_fired_gun = true;
+                    self.revision += 1;
+                    self.events.push(EngineEvent::GunFired { node_id: current_id.clone() });
                     // Return the special GunFired tick immediately
                     return match self.build_update() {
                         Some(update) => TickResult::GunFired(update),
@@ -246,7 +516,96 @@ impl ProcedureEngine {
         }
     }
 
+    /// Pre-announce the next node's signal once the current phase is within that node's
+    /// `announce_lead` of ending. Only timed phases are announced — a node waiting for the
+    /// user trigger has no known end. Call after `tick`; returns each announcement once.
+    pub fn upcoming_signal(&mut self) -> Option<UpcomingSignal> {
+        if self.paused_at.is_some() {
+            return None;
+        }
+        let graph = self.graph.as_ref()?;
+        let current_id = self.current_node_id.as_ref()?;
+        let current = graph.nodes.iter().find(|n| &n.id == current_id)?;
+
+        let (phase_start, phase_duration) = if self.is_post_trigger {
+            (self.post_trigger_started_at?, current.data.post_trigger_duration)
+        } else if current.data.wait_for_user_trigger || current.data.post_trigger_duration > 0.0 {
+            return None;
+        } else {
+            (self.node_started_at?, current.data.duration)
+        };
+        if phase_duration <= 0.0 || self.announced_phase == Some(phase_start) {
+            return None;
+        }
+
+        let next_id = self.get_next_node_id(current_id)?;
+        let next = graph.nodes.iter().find(|n| n.id == next_id)?;
+        let remaining = phase_duration - self.elapsed_since(phase_start);
+        if next.data.announce_lead <= 0.0 || remaining > next.data.announce_lead || remaining <= 0.0 {
+            return None;
+        }
+
+        self.announced_phase = Some(phase_start);
+        Some(UpcomingSignal {
+            class_id: None,
+            node_id: next.id.clone(),
+            event: next.data.label.clone(),
+            flags: next.data.flags.clone(),
+            sound: next.data.sound.clone(),
+            seconds_until: remaining.ceil(),
+            at_ms: unix_ms() + (remaining * 1000.0) as u64,
+        })
+    }
+
+    /// Events since the last call, plus a StatusChanged if the mapped race status moved
+    /// and a FlagsChanged if the displayed flags did.
+    pub fn drain_events(&mut self) -> Vec<EngineEvent> {
+        let status = self.current_race_status();
+        let by_sequence = std::mem::take(&mut self.status_ticked);
+        if status != self.reported_status {
+            let from = std::mem::replace(&mut self.reported_status, status.clone());
+            self.events.push(EngineEvent::StatusChanged { from, to: status, by_sequence });
+        }
+        let flags = self.active_flags();
+        if flags != self.reported_flags {
+            self.reported_flags = flags.clone();
+            let node_id = self.current_node_id.clone().unwrap_or_default();
+            self.events.push(EngineEvent::FlagsChanged { node_id, flags });
+        }
+        std::mem::take(&mut self.events)
+    }
+
+    /// Flags on display: post-trigger flags once triggered (if the node has any), else the node's.
+    fn active_flags(&self) -> Vec<String> {
+        let Some(node) = self.current_node_id.as_ref()
+            .and_then(|id| self.graph.as_ref()?.nodes.iter().find(|n| &n.id == id))
+        else {
+            return Vec::new();
+        };
+        if self.is_post_trigger && !node.data.post_trigger_flags.is_empty() {
+            node.data.post_trigger_flags.clone()
+        } else {
+            node.data.flags.clone()
+        }
+    }
+
+    fn node_entered(&mut self, with_sound: bool) {
+        let Some(node) = self.current_node_id.as_ref()
+            .and_then(|id| self.graph.as_ref()?.nodes.iter().find(|n| &n.id == id))
+        else {
+            return;
+        };
+        let (node_id, label, sound) = (node.id.clone(), node.data.label.clone(), node.data.sound.clone());
+        if with_sound && sound != SoundSignal::None {
+            self.events.push(EngineEvent::NodeEntered { node_id: node_id.clone(), label });
+            self.events.push(EngineEvent::SoundFired { node_id, sound });
+        } else {
+            self.events.push(EngineEvent::NodeEntered { node_id, label });
+        }
+    }
+
     fn transition_next(&mut self) -> TickResult {
+        self.undo = None;
         let current_id = match &self.current_node_id {
             Some(id) => id.clone(),
             None => return TickResult::Idle,
@@ -263,6 +622,8 @@ impl ProcedureEngine {
                 self.node_started_at = Some(Instant::now());
                 self.is_post_trigger = false;
                 self.post_trigger_started_at = None;
+                self.revision += 1;
+                self.node_entered(true);
                 match self.build_update() {
                     Some(upd) => TickResult::Update(upd),
                     None => TickResult::Idle,
@@ -279,6 +640,8 @@ impl ProcedureEngine {
                         self.node_started_at = Some(Instant::now());
                         self.is_post_trigger = false;
                         self.post_trigger_started_at = None;
+                        self.revision += 1;
+                        self.node_entered(true);
                         return match self.build_update() {
                             Some(upd) => TickResult::Update(upd),
                             None => TickResult::Idle,
@@ -292,6 +655,8 @@ impl ProcedureEngine {
                 self.node_started_at = None;
                 self.is_post_trigger = false;
                 self.post_trigger_started_at = None;
+                self.revision += 1;
+                self.events.push(EngineEvent::SequenceComplete);
                 TickResult::SequenceComplete
             }
         }
@@ -304,13 +669,13 @@ impl ProcedureEngine {
 
         let current_node = graph.nodes.iter().find(|n| &n.id == current_id)?;
 
-        let elapsed = started_at.elapsed().as_secs_f64();
+        let elapsed = self.elapsed_since(started_at);
         let duration = current_node.data.duration;
 
         let is_waiting = !self.is_post_trigger && current_node.data.wait_for_user_trigger && (duration == 0.0 || elapsed >= duration);
         
         let node_remaining = if self.is_post_trigger {
-            let p_elapsed = self.post_trigger_started_at?.elapsed().as_secs_f64();
+            let p_elapsed = self.elapsed_since(self.post_trigger_started_at?);
             let p_dur = current_node.data.post_trigger_duration;
             (p_dur - p_elapsed).max(0.0).ceil()
         } else {
@@ -321,14 +686,9 @@ impl ProcedureEngine {
             }
         };
 
-        let total_remaining = self.calculate_total_remaining(current_node, elapsed);
+        let total_remaining = self.calculate_total_remaining(current_node, elapsed).ceil();
         
-        // Use post trigger flags if we are in that phase and they exist, otherwise use standard flags
-        let active_flags = if self.is_post_trigger && !current_node.data.post_trigger_flags.is_empty() {
-            current_node.data.post_trigger_flags.clone()
-        } else {
-            current_node.data.flags.clone()
-        };
+        let active_flags = self.active_flags();
 
         // Determine the correct RaceStatus for this node
         let status = self.current_race_status();
@@ -345,8 +705,8 @@ impl ProcedureEngine {
             RaceStatus::Abandoned => "ABANDONED",
         };
 
-        // Only emit sound on the first tick of a node (< 0.3s elapsed)
-        let sound = if elapsed < 0.3 && !self.is_post_trigger {
+        // Only emit sound on the first tick of a node (< 0.3s of real time elapsed)
+        let sound = if elapsed / self.speed < 0.3 && !self.is_post_trigger && self.paused_at.is_none() {
             current_node.data.sound.clone()
         } else {
             SoundSignal::None
@@ -365,6 +725,40 @@ impl ProcedureEngine {
             action_label: current_node.data.action_label.clone(),
             is_post_trigger: self.is_post_trigger,
             sound,
+            paused: self.paused_at.is_some(),
+            metadata: current_node.data.metadata.clone(),
+        })
+    }
+
+    /// Unrounded remaining times with absolute end times, for smooth client countdowns.
+    pub fn countdown(&self) -> Option<Countdown> {
+        let graph = self.graph.as_ref()?;
+        let current_id = self.current_node_id.as_ref()?;
+        let current_node = graph.nodes.iter().find(|n| &n.id == current_id)?;
+        let elapsed = self.elapsed_since(self.node_started_at?);
+        let duration = current_node.data.duration;
+
+        let waiting = !self.is_post_trigger && current_node.data.wait_for_user_trigger && (duration == 0.0 || elapsed >= duration);
+        let node_remaining = if self.is_post_trigger {
+            current_node.data.post_trigger_duration - self.elapsed_since(self.post_trigger_started_at?)
+        } else {
+            duration - elapsed
+        }.max(0.0);
+        let sequence_remaining = self.calculate_total_remaining(current_node, elapsed);
+
+        // Engine seconds → real milliseconds (rehearsal engines run faster than the wall clock)
+        let real_ms = |secs: f64| (secs / self.speed * 1000.0).round() as u64;
+        let now = unix_ms();
+        let paused = self.paused_at.is_some();
+        Some(Countdown {
+            class_id: None,
+            node_id: current_id.clone(),
+            node_ends_at_ms: (!paused && !waiting).then(|| now + real_ms(node_remaining)),
+            sequence_ends_at_ms: (!paused).then(|| now + real_ms(sequence_remaining)),
+            node_remaining_ms: real_ms(node_remaining),
+            sequence_remaining_ms: real_ms(sequence_remaining),
+            paused,
+            server_time_ms: now,
         })
     }
 
@@ -392,7 +786,7 @@ impl ProcedureEngine {
             }
         }
 
-        total.max(0.0).ceil()
+        total.max(0.0)
     }
 
     fn get_next_node_id(&self, node_id: &str) -> Option<String> {
@@ -408,3 +802,93 @@ pub enum TickResult {
     GunFired(SequenceUpdate),
     SequenceComplete,
 }
+
+/// Race status a node stands for. Explicit raceStatus override on the node, otherwise
+/// auto-detect from the label. Unknown overrides fall back to Idle; undetectable labels
+/// to the pre-start zone.
+fn node_race_status(node: &ProcedureNode) -> RaceStatus {
+    match &node.data.race_status {
+        Some(status_str) => parse_race_status(status_str).unwrap_or(RaceStatus::Idle),
+        None => label_race_status(&node.data.label).unwrap_or(RaceStatus::Warning),
+    }
+}
+
+/// Planned seconds from starting the procedure to its starting signal: the node
+/// durations (and post-trigger durations) along the graph from the first node up
+/// to the first racing node. A wait for a trigger counts as no time. None if the
+/// procedure never reaches racing.
+pub fn planned_gun_offset(graph: &ProcedureGraph) -> Option<f64> {
+    let mut node = graph.nodes.iter().find(|n| n.id == "1").or_else(|| graph.nodes.first())?;
+    let mut visited = std::collections::HashSet::new();
+    let mut total = 0.0;
+    while visited.insert(node.id.as_str()) {
+        if node_race_status(node) == RaceStatus::Racing {
+            return Some(total);
+        }
+        total += node.data.duration + node.data.post_trigger_duration;
+        let next = graph.edges.iter().find(|e| e.source == node.id)?;
+        node = graph.nodes.iter().find(|n| n.id == next.target)?;
+    }
+    None
+}
+
+/// Map a node `raceStatus` override to a RaceStatus.
+pub fn parse_race_status(status: &str) -> Option<RaceStatus> {
+    Some(match status {
+        "IDLE" => RaceStatus::Idle,
+        "WARNING" => RaceStatus::Warning,
+        "PREPARATORY" => RaceStatus::Preparatory,
+        "ONE_MINUTE" => RaceStatus::OneMinute,
+        "RACING" => RaceStatus::Racing,
+        "FINISHED" => RaceStatus::Finished,
+        "POSTPONED" => RaceStatus::Postponed,
+        "INDIVIDUAL_RECALL" => RaceStatus::IndividualRecall,
+        "GENERAL_RECALL" => RaceStatus::GeneralRecall,
+        "ABANDONED" => RaceStatus::Abandoned,
+        _ => return None,
+    })
+}
+
+/// Auto-detect a RaceStatus from a node label (None if nothing matches).
+pub fn label_race_status(label: &str) -> Option<RaceStatus> {
+    let label_lower = label.to_lowercase();
+    if label_lower.contains("warning") {
+        Some(RaceStatus::Warning)
+    } else if label_lower.contains("preparatory") || label_lower.contains("prep") {
+        Some(RaceStatus::Preparatory)
+    } else if label_lower.contains("one-minute") || label_lower.contains("one minute") || label_lower.contains("1-minute") {
+        Some(RaceStatus::OneMinute)
+    } else if label_lower.contains("start") {
+        // Starting signal node — still part of the pre-start sequence
+        Some(RaceStatus::OneMinute)
+    } else if label_lower.contains("racing") || label_lower.contains("race") {
+        Some(RaceStatus::Racing)
+    } else if label_lower.contains("idle") {
+        Some(RaceStatus::Idle)
+    } else {
+        None
+    }
+}
+
+fn unix_ms() -> u64 {
+    crate::time_discipline::now_ms()
+}
+
+/// `now - age`. An age reaching back before the monotonic clock's origin (the host
+/// rebooted during the downtime) is clamped to the earliest instant it can represent.
+fn instant_before(now: Instant, age_ms: u64) -> Instant {
+    if let Some(t) = now.checked_sub(Duration::from_millis(age_ms)) {
+        return t;
+    }
+    // Largest representable age, by bisection
+    let (mut ok, mut too_far) = (0u64, age_ms);
+    while too_far - ok > 1 {
+        let mid = ok + (too_far - ok) / 2;
+        if now.checked_sub(Duration::from_millis(mid)).is_some() {
+            ok = mid;
+        } else {
+            too_far = mid;
+        }
+    }
+    now - Duration::from_millis(ok)
+}
@@ -0,0 +1,171 @@
+//! # procedure_templates
+//!
+//! Named procedure template library.
+//!
+//! Built-in templates (the RRS 26 five- and three-minute sequences from
+//! `standard_procedure`, plus the `rule_packs` presets) are generated on demand;
+//! everything a club saves, duplicates or imports lives in
+//! `RaceState::procedure_templates` and is persisted with the rest of the state.
+//!
+//! Templates are shared as a small JSON document:
+//! ```json
+//! { "format": "regatta-procedure-template", "version": 1, "template": { ... } }
+//! ```
+//! `import_document` also accepts a bare `ProcedureTemplate` or a bare
+//! `ProcedureGraph`, so graphs exported from the Architect can be imported as-is.
+
+use serde_json::{json, Value};
+
+use crate::handlers::standard_procedure;
+use crate::rule_packs::{self, RulePack, SoundConvention};
+use crate::state::{ProcedureGraph, ProcedureTemplate};
+
+pub const EXPORT_FORMAT: &str = "regatta-procedure-template";
+pub const EXPORT_VERSION: u64 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("Unknown procedure template: {0}")]
+    NotFound(String),
+    #[error("Built-in template {0} cannot be modified")]
+    BuiltIn(String),
+    #[error("Template name must not be empty")]
+    EmptyName,
+    #[error("Unsupported template document: {0}")]
+    InvalidDocument(String),
+}
+
+/// Templates shipped with the backend.
+pub fn built_in_templates() -> Vec<ProcedureTemplate> {
+    let standard = [(5, "RRS 26 — 5 minute"), (3, "RRS 26 — 3 minute")]
+        .into_iter()
+        .map(|(minutes, name)| {
+            let graph = standard_procedure(minutes, "P");
+            ProcedureTemplate {
+                id: graph.id.clone(),
+                name: name.to_string(),
+                description: Some(format!("Warning, preparatory (P), one-minute and start signals over {minutes} minutes")),
+                category: Some("fleet".to_string()),
+                built_in: true,
+                updated_ms: 0,
+                graph,
+            }
+        });
+    let presets = RulePack::ALL.into_iter()
+        .filter(|&pack| pack != RulePack::Fleet)
+        .map(|pack| {
+            let graph = rule_packs::procedure(pack, 5, "P", SoundConvention::Rrs);
+            ProcedureTemplate {
+                id: graph.id.clone(),
+                name: pack.name().to_string(),
+                description: None,
+                category: Some(pack.category().to_string()),
+                built_in: true,
+                updated_ms: 0,
+                graph,
+            }
+        });
+    standard.chain(presets).collect()
+}
+
+/// Built-ins followed by saved templates.
+pub fn list(saved: &[ProcedureTemplate]) -> Vec<ProcedureTemplate> {
+    let mut all = built_in_templates();
+    all.extend(saved.iter().cloned());
+    all
+}
+
+pub fn find(saved: &[ProcedureTemplate], id: &str) -> Option<ProcedureTemplate> {
+    list(saved).into_iter().find(|t| t.id == id)
+}
+
+/// Insert or replace a saved template. A missing id gets a fresh one.
+pub fn save(saved: &mut Vec<ProcedureTemplate>, mut template: ProcedureTemplate) -> Result<ProcedureTemplate, TemplateError> {
+    if template.name.trim().is_empty() {
+        return Err(TemplateError::EmptyName);
+    }
+    if template.id.is_empty() {
+        template.id = new_id();
+    }
+    if built_in_templates().iter().any(|t| t.id == template.id) {
+        return Err(TemplateError::BuiltIn(template.id));
+    }
+    template.built_in = false;
+    template.updated_ms = now_ms();
+
+    match saved.iter_mut().find(|t| t.id == template.id) {
+        Some(existing) => *existing = template.clone(),
+        None => saved.push(template.clone()),
+    }
+    Ok(template)
+}
+
+/// Copy any template (built-in or saved) under a new id.
+pub fn duplicate(saved: &mut Vec<ProcedureTemplate>, id: &str, name: Option<&str>) -> Result<ProcedureTemplate, TemplateError> {
+    let source = find(saved, id).ok_or_else(|| TemplateError::NotFound(id.to_string()))?;
+    let copy = ProcedureTemplate {
+        id: String::new(),
+        name: name.map(str::to_string).unwrap_or_else(|| format!("{} (copy)", source.name)),
+        ..source
+    };
+    save(saved, copy)
+}
+
+pub fn delete(saved: &mut Vec<ProcedureTemplate>, id: &str) -> Result<ProcedureTemplate, TemplateError> {
+    if built_in_templates().iter().any(|t| t.id == id) {
+        return Err(TemplateError::BuiltIn(id.to_string()));
+    }
+    let pos = saved.iter().position(|t| t.id == id).ok_or_else(|| TemplateError::NotFound(id.to_string()))?;
+    Ok(saved.remove(pos))
+}
+
+pub fn export_document(template: &ProcedureTemplate) -> Value {
+    json!({ "format": EXPORT_FORMAT, "version": EXPORT_VERSION, "template": template })
+}
+
+/// Import a shared document as a new saved template. The imported copy always
+/// gets a fresh id so it can never overwrite an existing template.
+pub fn import_document(saved: &mut Vec<ProcedureTemplate>, doc: &Value, name: Option<&str>) -> Result<ProcedureTemplate, TemplateError> {
+    let invalid = |e: serde_json::Error| TemplateError::InvalidDocument(e.to_string());
+
+    let template = match doc["format"].as_str() {
+        Some(EXPORT_FORMAT) => {
+            let version = doc["version"].as_u64().unwrap_or(0);
+            if version > EXPORT_VERSION {
+                return Err(TemplateError::InvalidDocument(format!("version {version} is newer than {EXPORT_VERSION}")));
+            }
+            serde_json::from_value::<ProcedureTemplate>(doc["template"].clone()).map_err(invalid)?
+        }
+        Some(other) => return Err(TemplateError::InvalidDocument(format!("unknown format {other}"))),
+        None if doc.get("graph").is_some() => serde_json::from_value::<ProcedureTemplate>(doc.clone()).map_err(invalid)?,
+        None => {
+            let graph = serde_json::from_value::<ProcedureGraph>(doc.clone()).map_err(invalid)?;
+            ProcedureTemplate {
+                id: String::new(),
+                name: graph.id.clone(),
+                description: None,
+                category: None,
+                built_in: false,
+                updated_ms: 0,
+                graph,
+            }
+        }
+    };
+
+    save(saved, ProcedureTemplate {
+        id: String::new(),
+        name: name.map(str::to_string).unwrap_or(template.name),
+        ..template
+    })
+}
+
+fn new_id() -> String {
+    format!("tpl-{}", uuid::Uuid::new_v4().simple())
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,187 @@
+//! # procedure_validator
+//!
+//! Structural checks run on a procedure graph before it is deployed to an engine.
+//!
+//! The engine itself will happily run any graph that parses, which means a typo
+//! in the Architect can ship a sequence that loops forever, never reaches the
+//! start signal, or silently maps a node to the wrong race status. `validate`
+//! reports every problem at once as a list of `ValidationIssue`s the Architect
+//! UI can attach to the offending node or edge.
+//!
+//! ## Checks
+//! - `EMPTY_GRAPH`, `DUPLICATE_NODE_ID`, `DANGLING_EDGE` — basic integrity
+//! - `AMBIGUOUS_EDGE` — more than one outgoing edge (the engine only follows the first)
+//! - `CYCLE` — a loop in the graph while `autoRestart` is off
+//! - `UNREACHABLE_NODE` — no path from the start node or any other entry point
+//! - `DEAD_END` — a zero-duration node with no trigger gate and no successor
+//! - `INVALID_RACE_STATUS` / `MISSING_RACE_STATUS` — unknown override, or no
+//!   override and a label the engine cannot map to a race status
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::procedure_engine::{label_race_status, parse_race_status};
+use crate::state::ProcedureGraph;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IssueCode {
+    EmptyGraph,
+    DuplicateNodeId,
+    DanglingEdge,
+    AmbiguousEdge,
+    Cycle,
+    UnreachableNode,
+    DeadEnd,
+    InvalidRaceStatus,
+    MissingRaceStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub code: IssueCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edge_id: Option<String>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn node(code: IssueCode, node_id: &str, message: String) -> Self {
+        Self { code, node_id: Some(node_id.to_string()), edge_id: None, message }
+    }
+
+    fn edge(code: IssueCode, edge_id: &str, message: String) -> Self {
+        Self { code, node_id: None, edge_id: Some(edge_id.to_string()), message }
+    }
+}
+
+/// Validate a graph. Returns every issue found; an empty list means it is safe to deploy.
+pub fn validate(graph: &ProcedureGraph) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    if graph.nodes.is_empty() {
+        issues.push(ValidationIssue {
+            code: IssueCode::EmptyGraph,
+            node_id: None,
+            edge_id: None,
+            message: "Procedure has no nodes".to_string(),
+        });
+        return issues;
+    }
+
+    let mut ids = HashSet::new();
+    for node in &graph.nodes {
+        if !ids.insert(node.id.as_str()) {
+            issues.push(ValidationIssue::node(IssueCode::DuplicateNodeId, &node.id,
+                format!("Node id {} is used more than once", node.id)));
+        }
+    }
+
+    // Adjacency over valid edges only
+    let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut has_incoming: HashSet<&str> = HashSet::new();
+    for edge in &graph.edges {
+        let missing: Vec<&str> = [edge.source.as_str(), edge.target.as_str()].into_iter()
+            .filter(|id| !ids.contains(id))
+            .collect();
+        if !missing.is_empty() {
+            issues.push(ValidationIssue::edge(IssueCode::DanglingEdge, &edge.id,
+                format!("Edge {} references unknown node(s): {}", edge.id, missing.join(", "))));
+            continue;
+        }
+        successors.entry(edge.source.as_str()).or_default().push(edge.target.as_str());
+        has_incoming.insert(edge.target.as_str());
+    }
+    for (source, targets) in &successors {
+        if targets.len() > 1 {
+            issues.push(ValidationIssue::node(IssueCode::AmbiguousEdge, source,
+                format!("Node {source} has {} outgoing edges; only the first ({}) is followed", targets.len(), targets[0])));
+        }
+    }
+
+    if !graph.auto_restart {
+        if let Some(node_id) = find_cycle(&graph.nodes.iter().map(|n| n.id.as_str()).collect::<Vec<_>>(), &successors) {
+            issues.push(ValidationIssue::node(IssueCode::Cycle, node_id,
+                format!("Node {node_id} is part of a loop but autoRestart is off")));
+        }
+    }
+
+    // Entry points: the node the engine starts at, plus any node nothing leads to
+    // that leads somewhere (e.g. an Idle node in front of the warning signal).
+    let start = graph.nodes.iter().find(|n| n.id == "1").unwrap_or(&graph.nodes[0]).id.as_str();
+    let mut reachable: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = std::iter::once(start)
+        .chain(graph.nodes.iter()
+            .map(|n| n.id.as_str())
+            .filter(|id| !has_incoming.contains(id) && successors.contains_key(id)))
+        .collect();
+    while let Some(id) = stack.pop() {
+        if reachable.insert(id) {
+            stack.extend(successors.get(id).into_iter().flatten());
+        }
+    }
+
+    for node in &graph.nodes {
+        let data = &node.data;
+        if !reachable.contains(node.id.as_str()) {
+            issues.push(ValidationIssue::node(IssueCode::UnreachableNode, &node.id,
+                format!("Node {} ({}) can never be reached", node.id, data.label)));
+        }
+        if data.duration <= 0.0 && !data.wait_for_user_trigger && !successors.contains_key(node.id.as_str()) && graph.nodes.len() > 1 {
+            issues.push(ValidationIssue::node(IssueCode::DeadEnd, &node.id,
+                format!("Node {} ({}) has no duration, no trigger gate and no next node", node.id, data.label)));
+        }
+        match &data.race_status {
+            Some(status) if parse_race_status(status).is_none() => {
+                issues.push(ValidationIssue::node(IssueCode::InvalidRaceStatus, &node.id,
+                    format!("Node {} has unknown raceStatus {status}", node.id)));
+            }
+            None if label_race_status(&data.label).is_none() => {
+                issues.push(ValidationIssue::node(IssueCode::MissingRaceStatus, &node.id,
+                    format!("Node {} ({}) needs a raceStatus; its label does not map to one", node.id, data.label)));
+            }
+            _ => {}
+        }
+    }
+
+    issues
+}
+
+/// Iterative DFS; returns a node on the first cycle found.
+fn find_cycle<'a>(nodes: &[&'a str], successors: &HashMap<&'a str, Vec<&'a str>>) -> Option<&'a str> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark { Visiting, Done }
+
+    let mut marks: HashMap<&str, Mark> = HashMap::new();
+    for &root in nodes {
+        if marks.contains_key(root) {
+            continue;
+        }
+        let mut stack: Vec<(&str, usize)> = vec![(root, 0)];
+        marks.insert(root, Mark::Visiting);
+        while let Some((node, next)) = stack.pop() {
+            let targets = successors.get(node).map(Vec::as_slice).unwrap_or_default();
+            match targets.get(next) {
+                Some(&target) => {
+                    stack.push((node, next + 1));
+                    match marks.get(target) {
+                        Some(Mark::Visiting) => return Some(target),
+                        Some(Mark::Done) => {}
+                        None => {
+                            marks.insert(target, Mark::Visiting);
+                            stack.push((target, 0));
+                        }
+                    }
+                }
+                None => {
+                    marks.insert(node, Mark::Done);
+                }
+            }
+        }
+    }
+    None
+}
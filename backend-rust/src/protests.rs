@@ -0,0 +1,137 @@
+//! # protests
+//!
+//! Protest lifecycle (RRS Part 5): filed → hearing scheduled → decided, or withdrawn.
+//!
+//! - Competitors file from their tracker (the protestor is the tracker's boat); the
+//!   jury and director can file for any boat, or as "RC" / "PC"
+//! - Filing flags the referenced log lines and records an audit window around the
+//!   incident (`PROTEST_AUDIT_WINDOW_SECS` either side, default 60) that the jury
+//!   can pass straight to `protest-replay`
+//! - A decision's penalties go to scoring: into the closed race the protest names
+//!   (and the standings are recomputed), or into the race in progress
+//!
+//! ## Invariants
+//! - Core Invariant #2: every lifecycle step arrives as an audited command
+
+use serde::Deserialize;
+
+use crate::scoring;
+use crate::state::{Hearing, Penalty, Protest, ProtestDecision, ProtestStatus, RaceState};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProtestError {
+    #[error("Unknown protest: {0}")]
+    NotFound(String),
+    #[error("Protest {0} is already {1:?}")]
+    Closed(String, ProtestStatus),
+    #[error("A protest must name at least one protestee")]
+    NoProtestee,
+    #[error("Race {0} has not been scored")]
+    UnknownRace(u32),
+}
+
+/// `file-protest` payload.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtestFiling {
+    #[serde(default)]
+    pub race_number: Option<u32>,
+    #[serde(default)]
+    pub protestor: Option<String>,
+    pub protestees: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub incident_ms: Option<i64>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub log_ids: Vec<String>,
+}
+
+fn audit_window_ms() -> i64 {
+    std::env::var("PROTEST_AUDIT_WINDOW_SECS").ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(60) * 1000
+}
+
+fn find<'a>(state: &'a mut RaceState, id: &str) -> Result<&'a mut Protest, ProtestError> {
+    state.protests.iter_mut().find(|p| p.id == id).ok_or_else(|| ProtestError::NotFound(id.to_string()))
+}
+
+fn open<'a>(state: &'a mut RaceState, id: &str) -> Result<&'a mut Protest, ProtestError> {
+    let protest = find(state, id)?;
+    match protest.status {
+        ProtestStatus::Decided | ProtestStatus::Withdrawn => Err(ProtestError::Closed(protest.id.clone(), protest.status)),
+        _ => Ok(protest),
+    }
+}
+
+pub fn file(state: &mut RaceState, filing: ProtestFiling, protestor: String, filed_by: &str, now: i64) -> Result<Protest, ProtestError> {
+    if filing.protestees.is_empty() {
+        return Err(ProtestError::NoProtestee);
+    }
+    if let Some(n) = filing.race_number {
+        if !state.results.iter().any(|r| r.race_number == n) {
+            return Err(ProtestError::UnknownRace(n));
+        }
+    }
+    let incident_ms = filing.incident_ms.unwrap_or(now);
+    let window = audit_window_ms();
+    for log in state.logs.iter_mut().filter(|l| filing.log_ids.contains(&l.id)) {
+        log.protest_flagged = Some(true);
+    }
+
+    let protest = Protest {
+        id: format!("protest-{}", state.protests.len() + 1),
+        race_number: filing.race_number,
+        protestor,
+        protestees: filing.protestees,
+        rules: filing.rules,
+        incident_ms,
+        description: filing.description,
+        log_ids: filing.log_ids,
+        audit_from_ms: (incident_ms - window).max(0) as u64,
+        audit_to_ms: (incident_ms + window).max(0) as u64,
+        filed_ms: now,
+        filed_by: filed_by.to_string(),
+        status: ProtestStatus::Filed,
+        hearing: None,
+        decision: None,
+    };
+    state.protests.push(protest.clone());
+    Ok(protest)
+}
+
+pub fn schedule(state: &mut RaceState, id: &str, hearing: Hearing) -> Result<Protest, ProtestError> {
+    let protest = open(state, id)?;
+    protest.hearing = Some(hearing);
+    protest.status = ProtestStatus::Scheduled;
+    Ok(protest.clone())
+}
+
+pub fn withdraw(state: &mut RaceState, id: &str) -> Result<Protest, ProtestError> {
+    let protest = open(state, id)?;
+    protest.status = ProtestStatus::Withdrawn;
+    Ok(protest.clone())
+}
+
+/// Record the decision and apply its penalties to scoring.
+pub fn decide(state: &mut RaceState, id: &str, decision: ProtestDecision) -> Result<Protest, ProtestError> {
+    let race_number = open(state, id)?.race_number;
+    let penalties: Vec<Penalty> = decision.penalties.clone();
+
+    match race_number {
+        Some(n) => {
+            let race = state.results.iter_mut().find(|r| r.race_number == n).ok_or(ProtestError::UnknownRace(n))?;
+            race.penalties.extend(penalties);
+            scoring::rescore(state);
+        }
+        None => state.penalties.extend(penalties),
+    }
+
+    let protest = find(state, id)?;
+    protest.decision = Some(decision);
+    protest.status = ProtestStatus::Decided;
+    Ok(protest.clone())
+}
@@ -0,0 +1,182 @@
+//! # pursuit
+//!
+//! Pursuit (handicap) starts.
+//!
+//! The normal start sequence runs once; its starting signal is the gun for the
+//! slowest-rated boat. Every other boat starts `offset_secs` later, chosen so
+//! that on handicap the whole fleet should arrive at the finish together and the
+//! first boat home wins:
+//!
+//! - `PY`  — elapsed ∝ yardstick: `offset = T × (1 − PY / PY_max)`
+//! - `TCF` — elapsed ∝ 1 / TCF:   `offset = T × (1 − TCF_min / TCF)`
+//! - `OFFSET` — offsets supplied directly
+//!
+//! where `T` is the expected elapsed time of the slowest boat (`raceMinutes`).
+//!
+//! `run_pursuit_tick` drives the individual countdowns: once per second each
+//! tracker gets `your-start-in` (in its `boat:<id>` room), the RC gets the whole
+//! `pursuit-update` table, and `pursuit-start` is broadcast with a sound signal
+//! as each boat's start time arrives.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::json;
+use socketioxide::SocketIo;
+use tracing::info;
+
+use crate::handlers::{now_ms, SharedState};
+use crate::state::{HandicapSystem, PursuitEntry, PursuitStart, RaceStatus, SoundSignal};
+
+/// Offsets are rounded to this many seconds so the RC can call them by hand
+const OFFSET_ROUNDING_SECS: f64 = 1.0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PursuitError {
+    #[error("Pursuit start needs at least one boat")]
+    NoBoats,
+    #[error("Rating for {0} must be positive")]
+    InvalidRating(String),
+    #[error("raceMinutes must be positive")]
+    InvalidRaceTime,
+}
+
+/// Compute start offsets for every entry in place and sort by start order.
+pub fn compute_offsets(pursuit: &mut PursuitStart) -> Result<(), PursuitError> {
+    if pursuit.entries.is_empty() {
+        return Err(PursuitError::NoBoats);
+    }
+    if pursuit.system != HandicapSystem::Offset {
+        if pursuit.race_minutes <= 0.0 {
+            return Err(PursuitError::InvalidRaceTime);
+        }
+        if let Some(e) = pursuit.entries.iter().find(|e| e.rating <= 0.0 || !e.rating.is_finite()) {
+            return Err(PursuitError::InvalidRating(e.boat_id.clone()));
+        }
+    }
+
+    let race_secs = pursuit.race_minutes * 60.0;
+    let ratings = pursuit.entries.iter().map(|e| e.rating);
+    match pursuit.system {
+        HandicapSystem::Py => {
+            let slowest = ratings.fold(f64::MIN, f64::max);
+            for e in &mut pursuit.entries {
+                e.offset_secs = race_secs * (1.0 - e.rating / slowest);
+            }
+        }
+        HandicapSystem::Tcf => {
+            let slowest = ratings.fold(f64::MAX, f64::min);
+            for e in &mut pursuit.entries {
+                e.offset_secs = race_secs * (1.0 - slowest / e.rating);
+            }
+        }
+        HandicapSystem::Offset => {}
+    }
+
+    for e in &mut pursuit.entries {
+        e.offset_secs = ((e.offset_secs.max(0.0)) / OFFSET_ROUNDING_SECS).round() * OFFSET_ROUNDING_SECS;
+        e.started = false;
+    }
+    pursuit.entries.sort_by(|a, b| a.offset_secs.total_cmp(&b.offset_secs));
+    pursuit.start_ms = None;
+    Ok(())
+}
+
+/// Forget any previous run so the schedule restarts from the next gun.
+pub fn reset(pursuit: &mut PursuitStart) {
+    pursuit.start_ms = None;
+    for e in &mut pursuit.entries {
+        e.started = false;
+    }
+}
+
+/// Called when the sequence fires its starting signal.
+pub fn arm_at_gun(pursuit: &mut PursuitStart, gun_ms: i64) {
+    if pursuit.start_ms.is_none() {
+        info!("Pursuit: first gun at {gun_ms}, {} boats on individual countdowns", pursuit.entries.len());
+        pursuit.start_ms = Some(gun_ms);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PursuitCountdown {
+    pub boat_id: String,
+    pub offset_secs: f64,
+    /// Whole seconds until this boat's start (negative once started)
+    pub seconds_to_start: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at_ms: Option<i64>,
+    pub started: bool,
+}
+
+/// Seconds until the first gun, if it is known yet.
+fn seconds_to_first_gun(pursuit: &PursuitStart, status: &RaceStatus, sequence_remaining: Option<f64>, now: i64) -> Option<f64> {
+    match pursuit.start_ms {
+        Some(start) => Some((start - now) as f64 / 1000.0),
+        None => match status {
+            RaceStatus::Warning | RaceStatus::Preparatory | RaceStatus::OneMinute => sequence_remaining,
+            _ => None,
+        },
+    }
+}
+
+pub async fn run_pursuit_tick(shared: SharedState, io: SocketIo) {
+    let mut interval = tokio::time::interval(Duration::from_millis(200)); // 5Hz
+    let mut last_second: Option<i64> = None;
+    loop {
+        interval.tick().await;
+
+        let now = now_ms();
+        let mut started_now: Vec<PursuitEntry> = Vec::new();
+        let countdowns = {
+            let mut state = shared.write().await;
+            let status = state.status.clone();
+            let remaining = state.sequence_time_remaining;
+            let Some(pursuit) = state.pursuit.as_mut() else {
+                last_second = None;
+                continue;
+            };
+            let Some(to_gun) = seconds_to_first_gun(pursuit, &status, remaining, now) else { continue };
+
+            let start_ms = pursuit.start_ms;
+            let countdowns: Vec<PursuitCountdown> = pursuit.entries.iter_mut().map(|e| {
+                let to_start = to_gun + e.offset_secs;
+                if start_ms.is_some() && to_start <= 0.0 && !e.started {
+                    e.started = true;
+                    started_now.push(e.clone());
+                }
+                PursuitCountdown {
+                    boat_id: e.boat_id.clone(),
+                    offset_secs: e.offset_secs,
+                    seconds_to_start: to_start.ceil() as i64,
+                    start_at_ms: start_ms.map(|s| s + (e.offset_secs * 1000.0) as i64),
+                    started: e.started,
+                }
+            }).collect();
+            countdowns
+        };
+
+        for entry in &started_now {
+            info!("Pursuit: {} starts (+{}s)", entry.boat_id, entry.offset_secs);
+            let _ = io.emit("pursuit-start", &json!({
+                "boatId": entry.boat_id,
+                "offsetSecs": entry.offset_secs,
+                "startedAtMs": now,
+                "sound": SoundSignal::OneShort,
+            }));
+        }
+
+        // Individual countdowns once per second (and immediately on any start)
+        let second = now / 1000;
+        if last_second == Some(second) && started_now.is_empty() {
+            continue;
+        }
+        last_second = Some(second);
+
+        for c in &countdowns {
+            let _ = io.to(format!("boat:{}", c.boat_id)).emit("your-start-in", c);
+        }
+        let _ = io.emit("pursuit-update", &countdowns);
+    }
+}
@@ -0,0 +1,122 @@
+//! # race_session
+//!
+//! Several races in one session.
+//!
+//! The top-level race fields of `RaceState` (status, procedure, start time,
+//! penalties, OCS list, finishes) always describe the *active* race, so every
+//! existing handler and tick loop keeps working unchanged. The other races of the
+//! day are kept as `RaceRecord`s in `RaceState::races`; `create` and `select`
+//! stash the active race into its record and swap another one in.
+//!
+//! A state without `active_race_id` is the classic single race — it becomes
+//! "Race 1" the first time another race is created.
+//!
+//! Switching is refused while a sequence is running (the handlers check the engine).
+//!
+//! Each race of each event writes its own audit chain (`audit_session`, see
+//! `audit_store`); the handlers switch the logger along with the race.
+
+use crate::mark_rounding;
+use crate::scoring;
+use crate::state::{ProcedureGraph, RaceRecord, RaceState, RaceStatus};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("Unknown race: {0}")]
+    NotFound(String),
+    #[error("Stop the running sequence before switching races")]
+    SequenceRunning,
+}
+
+/// Audit session of the active race: `<eventId>_<raceId>`, or `default` for the
+/// classic single race.
+pub fn audit_session(state: &RaceState) -> String {
+    match (&state.active_event_id, &state.active_race_id) {
+        (Some(event), Some(race)) => format!("{event}_{race}"),
+        (None, Some(race)) => race.clone(),
+        (_, None) => "default".to_string(),
+    }
+}
+
+/// Write the active race's fields back into its record (creating it if needed).
+pub fn stash_active(state: &mut RaceState, now: i64) {
+    let id = state.active_race_id.clone().unwrap_or_else(|| {
+        let id = format!("race-{}", state.races.len() + 1);
+        state.active_race_id = Some(id.clone());
+        id
+    });
+    let existing = state.races.iter().position(|r| r.id == id);
+    let scored = state.start_time
+        .and_then(|s| state.results.iter().find(|r| r.start_ms == Some(s)))
+        .map(|r| r.race_number);
+
+    let record = RaceRecord {
+        name: existing.map(|i| state.races[i].name.clone()).unwrap_or_else(|| format!("Race {}", state.races.len() + 1)),
+        created_ms: existing.map(|i| state.races[i].created_ms).unwrap_or(now),
+        race_number: scored.or_else(|| existing.and_then(|i| state.races[i].race_number)),
+        id,
+        status: state.status.clone(),
+        procedure: state.current_procedure.clone(),
+        start_time: state.start_time,
+        penalties: state.penalties.clone(),
+        ocs_boats: state.ocs_boats.clone(),
+        ocs_detections: state.ocs_detections.clone(),
+        finishes: state.finishes.clone(),
+    };
+    match existing {
+        Some(i) => state.races[i] = record,
+        None => state.races.push(record),
+    }
+}
+
+/// Make `record` the active race.
+fn activate(state: &mut RaceState, record: RaceRecord) {
+    state.active_race_id = Some(record.id);
+    state.status = record.status;
+    state.current_procedure = record.procedure;
+    state.start_time = record.start_time;
+    state.penalties = record.penalties;
+    state.ocs_boats = record.ocs_boats;
+    state.ocs_detections = record.ocs_detections;
+    state.finishes = record.finishes;
+    state.current_sequence = None;
+    state.sequence_time_remaining = None;
+    state.current_node_id = None;
+    state.waiting_for_trigger = false;
+    state.action_label = None;
+    state.is_post_trigger = false;
+    mark_rounding::reset(state);
+}
+
+/// Start a new race (keeping the current procedure unless one is given) and make it active.
+pub fn create(state: &mut RaceState, name: Option<String>, procedure: Option<ProcedureGraph>, now: i64) -> RaceRecord {
+    stash_active(state, now);
+    let number = state.races.len() + 1;
+    let record = RaceRecord {
+        id: format!("race-{number}"),
+        name: name.unwrap_or_else(|| format!("Race {number}")),
+        created_ms: now,
+        status: RaceStatus::Idle,
+        procedure: procedure.or_else(|| state.current_procedure.clone()),
+        start_time: None,
+        penalties: Vec::new(),
+        ocs_boats: Vec::new(),
+        ocs_detections: Vec::new(),
+        finishes: Vec::new(),
+        race_number: None,
+    };
+    state.races.push(record.clone());
+    activate(state, record.clone());
+    record
+}
+
+pub fn select(state: &mut RaceState, id: &str, now: i64) -> Result<RaceRecord, SessionError> {
+    if !state.races.iter().any(|r| r.id == id) {
+        return Err(SessionError::NotFound(id.to_string()));
+    }
+    stash_active(state, now);
+    let record = state.races.iter().find(|r| r.id == id).cloned().ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+    activate(state, record.clone());
+    scoring::rescore(state);
+    Ok(record)
+}
@@ -4,7 +4,11 @@ use tokio::time::interval;
 use tracing::{debug, info, warn};
 use serde_json::json;
 
+use crate::boat_scope::SCOPED_ROOM;
 use crate::handlers::SharedState;
+use crate::mark_rounding::{self, MarkRoundingConfig, MarkRoundingDetector};
+use crate::scoring;
+use crate::state::MarkRounding;
 use crate::state::{BoatState, CourseElement, CourseElementType, LatLon};
 
 // Earth radius in meters
@@ -38,6 +42,17 @@ pub fn bearing(p1: &LatLon, p2: &LatLon) -> f64 {
     (brng + 360.0) % 360.0
 }
 
+/// Point `distance_m` from `from` along `bearing_deg` (great circle)
+pub fn destination(from: &LatLon, bearing_deg: f64, distance_m: f64) -> LatLon {
+    let d = distance_m / R;
+    let b = bearing_deg.to_radians();
+    let lat1 = from.lat.to_radians();
+    let lon1 = from.lon.to_radians();
+    let lat2 = (lat1.sin() * d.cos() + lat1.cos() * d.sin() * b.cos()).asin();
+    let lon2 = lon1 + (b.sin() * d.sin() * lat1.cos()).atan2(d.cos() - lat1.sin() * lat2.sin());
+    LatLon { lat: lat2.to_degrees(), lon: lon2.to_degrees() }
+}
+
 /// Helper: find centroid of a list of marks (useful for gates/lines)
 fn get_centroid(mark_ids: &[String], state: &crate::state::RaceState) -> Option<LatLon> {
     if mark_ids.is_empty() { return None; }
@@ -62,7 +77,7 @@ fn get_centroid(mark_ids: &[String], state: &crate::state::RaceState) -> Option<
 
 // Check if line segment A-B intersects C-D.
 // Used for detecting when a boat (A=prev, B=curr) crosses a line/gate (C, D).
-fn segments_intersect(a: &LatLon, b: &LatLon, c: &LatLon, d: &LatLon) -> bool {
+pub fn segments_intersect(a: &LatLon, b: &LatLon, c: &LatLon, d: &LatLon) -> bool {
     let ccw = |p1: &LatLon, p2: &LatLon, p3: &LatLon| {
         (p3.lat - p1.lat) * (p2.lon - p1.lon) > (p2.lat - p1.lat) * (p3.lon - p1.lon)
     };
@@ -72,6 +87,7 @@ fn segments_intersect(a: &LatLon, b: &LatLon, c: &LatLon, d: &LatLon) -> bool {
 /// The main ranking algorithm
 pub async fn start_ranking_engine(shared: SharedState, io: SocketIo) {
     let mut ticker = interval(Duration::from_millis(1000)); // Update once per second
+    let mut roundings = MarkRoundingDetector::new(MarkRoundingConfig::default());
     info!("🏆 Ranking Engine started (1Hz DTF / DMG loop).");
     
     loop {
@@ -112,43 +128,35 @@ pub async fn start_ranking_engine(shared: SharedState, io: SocketIo) {
         
         // Create an array to collect scores for ranking
         let mut fleet_scores = Vec::new();
+        let mut rounded: Vec<MarkRounding> = Vec::new();
+        let mut finished: Vec<(String, i64)> = Vec::new();
+        let course = state.course.clone();
+        roundings.retain_boats(&state.boats);
         
         // Evaluate each boat
         for (boat_id, boat) in state.boats.iter_mut() {
-            // 1. Advance Leg if they crossed the next mark/element
+            // 1. Advance Leg when the boat rounds / crosses the next element
             let current_leg = boat.leg_index as usize;
-            
-            // Check if they advanced (passed element current_leg + 1)
-            let mut just_advanced = false;
             if current_leg + 1 < n_elements {
                 let target_element = &course_order[current_leg + 1];
-                let is_passed = match target_element.element_type {
-                    CourseElementType::StartLine | CourseElementType::FinishLine | CourseElementType::Gate => {
-                        // Check segment intersection
-                        if target_element.marks.len() >= 2 {
-                            // We need the boat's previous position to do a segment intersect. 
-                            // We will approximate passing by simply checking if they are within a radius and moving away, 
-                            // Alternatively, if they have simulation_path, use the last 2 points.
-                            // For now, simple proximity check for the gate center.
-                            if let Some(c) = &element_centroids[current_leg + 1] {
-                                if haversine_distance(&boat.pos, c) < 20.0 {
-                                    just_advanced = true;
-                                }
-                            }
-                        }
-                    },
-                    CourseElementType::Mark => {
-                        // Simple proximity check for roundings (e.g. within 15 meters)
-                        if let Some(c) = &element_centroids[current_leg + 1] {
-                            if haversine_distance(&boat.pos, c) < 15.0 {
-                                just_advanced = true;
-                            }
+                if let Some(detection) = roundings.observe(boat_id, &boat.pos, current_leg + 1, target_element, &course) {
+                    if detection.correct {
+                        boat.leg_index += 1;
+                        boat.lap = mark_rounding::laps(&course_order, boat.leg_index as usize);
+                        if boat.leg_index as usize == n_elements - 1 {
+                            finished.push((boat_id.clone(), boat.timestamp));
                         }
                     }
-                };
-                
-                if just_advanced {
-                    boat.leg_index += 1;
+                    rounded.push(MarkRounding {
+                        boat_id: boat_id.clone(),
+                        element_id: target_element.id.clone(),
+                        element_name: target_element.name.clone(),
+                        direction: detection.direction,
+                        correct: detection.correct,
+                        leg_index: boat.leg_index,
+                        lap: boat.lap,
+                        timestamp: boat.timestamp,
+                    });
                 }
             }
             
@@ -216,6 +224,18 @@ pub async fn start_ranking_engine(shared: SharedState, io: SocketIo) {
             b.1.cmp(&a.1).then_with(|| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
         });
         
+        for rounding in &rounded {
+            info!("Boat {} rounded {} ({:?}, correct: {})", rounding.boat_id, rounding.element_name, rounding.direction, rounding.correct);
+            io.emit("mark-rounded", rounding).ok();
+        }
+        state.mark_roundings.extend(rounded);
+        for (boat_id, finish_ms) in finished {
+            if scoring::record_finish(&mut state, &boat_id, finish_ms, "tracking") {
+                info!("Boat {boat_id} finished");
+                io.except(SCOPED_ROOM).emit("finishes-update", &state.finishes).ok();
+            }
+        }
+        
         let mut telemetry_update_map = serde_json::Map::new();
         
         for (i, (boat_id, _, _)) in fleet_scores.iter().enumerate() {
@@ -232,6 +252,7 @@ pub async fn start_ranking_engine(shared: SharedState, io: SocketIo) {
                     "rank": boat.rank,
                     "dtf_m": boat.dtf_m,
                     "leg_index": boat.leg_index,
+                    "lap": boat.lap,
                 });
                 telemetry_update_map.insert(boat_id.clone(), payload);
             }
@@ -0,0 +1,102 @@
+//! # rate_limit
+//!
+//! Per-socket limiter for `track-update`, so one misbehaving tracker cannot flood
+//! the shared state lock, the logs and every other client's socket.
+//!
+//! - **Rate limit** — a token bucket of `TRACK_UPDATE_BURST` updates (default 20)
+//!   refilled at `TRACK_UPDATE_MAX_HZ` (default 20 Hz). Updates beyond it are dropped
+//!   and the client gets a `rate-limited` warning, at most once per second.
+//! - **Downsampling** — of the updates within the limit, at most
+//!   `TRACK_UPDATE_DOWNSAMPLE_HZ` per second (default 5 Hz) are applied and
+//!   rebroadcast; the ones in between are dropped silently. 0 disables it.
+//!
+//! ## Invariants
+//! - Core Invariant #8: each limiter belongs to one socket's handler; no shared locking
+
+use std::time::{Duration, Instant};
+
+const WARN_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct RateLimitConfig {
+    pub max_hz: f64,
+    pub burst: f64,
+    pub downsample_hz: f64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<f64>().ok()).filter(|v| *v >= 0.0);
+        Self {
+            max_hz: var("TRACK_UPDATE_MAX_HZ").filter(|v| *v > 0.0).unwrap_or(20.0),
+            burst: var("TRACK_UPDATE_BURST").filter(|v| *v >= 1.0).unwrap_or(20.0),
+            downsample_hz: var("TRACK_UPDATE_DOWNSAMPLE_HZ").unwrap_or(5.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Apply the update
+    Accept,
+    /// Within the rate limit, but inside the downsampling interval
+    Downsampled,
+    /// Over the rate limit
+    Limited,
+}
+
+pub struct TrackLimiter {
+    config: RateLimitConfig,
+    tokens: f64,
+    refilled: Instant,
+    last_accepted: Option<Instant>,
+    /// Updates dropped by the rate limit since the last warning
+    dropped: u64,
+    last_warning: Option<Instant>,
+}
+
+impl TrackLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            tokens: config.burst,
+            config,
+            refilled: Instant::now(),
+            last_accepted: None,
+            dropped: 0,
+            last_warning: None,
+        }
+    }
+
+    pub fn check(&mut self, now: Instant) -> Verdict {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.config.max_hz).min(self.config.burst);
+        self.refilled = now;
+
+        if self.tokens < 1.0 {
+            self.dropped += 1;
+            return Verdict::Limited;
+        }
+        self.tokens -= 1.0;
+
+        if self.config.downsample_hz > 0.0 {
+            let interval = Duration::from_secs_f64(1.0 / self.config.downsample_hz);
+            if self.last_accepted.is_some_and(|t| now.saturating_duration_since(t) < interval) {
+                return Verdict::Downsampled;
+            }
+        }
+        self.last_accepted = Some(now);
+        Verdict::Accept
+    }
+
+    /// Updates dropped since the last warning, if a warning is due.
+    pub fn take_warning(&mut self, now: Instant) -> Option<u64> {
+        if self.dropped == 0 || self.last_warning.is_some_and(|t| now.saturating_duration_since(t) < WARN_INTERVAL) {
+            return None;
+        }
+        self.last_warning = Some(now);
+        Some(std::mem::take(&mut self.dropped))
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+}
@@ -0,0 +1,172 @@
+//! # recording
+//!
+//! Race recorder: samples the whole race state, positions included, into a
+//! session file that `playback` can scrub through like a video timeline.
+//!
+//! - `RECORDING_DIR` (default `/data/recordings`); unavailable = no recording
+//! - `RECORDING_INTERVAL_MS` (default 250) — sample rate
+//! - `RECORDING_KEYFRAME_SECS` (default 30) — full state this often
+//!
+//! A recording starts with the server and again whenever the active race
+//! changes, as `rec-<startMs>.jsonl`. Each line is a frame: the first and every
+//! keyframe interval `{ t, keyframe: <state> }`, in between `{ t, ops }` with the
+//! `state_delta` JSON Patch from the previous frame. Unchanged samples are not
+//! written.
+//!
+//! ## Invariants
+//! - Core Invariant #8: the diff and the write run in the recorder task on a snapshot
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::handlers::{now_ms, SharedState};
+use crate::state_delta::{self, PatchOp};
+
+pub struct RecordingConfig {
+    pub dir: PathBuf,
+    pub interval: Duration,
+    pub keyframe_every: Duration,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        let num = |key: &str| var(key).and_then(|v| v.parse::<u64>().ok()).filter(|v| *v > 0);
+        Self {
+            dir: var("RECORDING_DIR").unwrap_or_else(|| "/data/recordings".to_string()).into(),
+            interval: Duration::from_millis(num("RECORDING_INTERVAL_MS").unwrap_or(250)),
+            keyframe_every: Duration::from_secs(num("RECORDING_KEYFRAME_SECS").unwrap_or(30)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Frame {
+    pub t: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyframe: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ops: Vec<PatchOp>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub id: String,
+    pub start_ms: i64,
+    pub bytes: u64,
+}
+
+pub fn dir() -> PathBuf {
+    RecordingConfig::default().dir
+}
+
+/// Path of recording `id`, or None for ids that are not recorder file names.
+fn path(id: &str) -> Option<PathBuf> {
+    let ms = id.strip_prefix("rec-")?;
+    ms.parse::<i64>().ok()?;
+    Some(dir().join(format!("{id}.jsonl")))
+}
+
+/// Recordings on disk, newest first.
+pub async fn list() -> Vec<RecordingInfo> {
+    let mut recordings = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir()).await else { return recordings };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = name.strip_suffix(".jsonl") else { continue };
+        let Some(start_ms) = id.strip_prefix("rec-").and_then(|ms| ms.parse().ok()) else { continue };
+        let bytes = entry.metadata().await.map(|m| m.len()).unwrap_or(0);
+        recordings.push(RecordingInfo { id: id.to_string(), start_ms, bytes });
+    }
+    recordings.sort_by_key(|r| std::cmp::Reverse(r.start_ms));
+    recordings
+}
+
+/// Every frame of recording `id`, in time order.
+pub async fn load(id: &str) -> anyhow::Result<Vec<Frame>> {
+    let path = path(id).ok_or_else(|| anyhow::anyhow!("Unknown recording: {id}"))?;
+    let data = tokio::fs::read_to_string(&path).await?;
+    let mut frames: Vec<Frame> = data.lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect();
+    // A recording cut short by a crash may end mid-line; everything before it still plays
+    frames.sort_by_key(|f| f.t);
+    if frames.first().is_none_or(|f| f.keyframe.is_none()) {
+        anyhow::bail!("Recording {id} has no keyframe");
+    }
+    Ok(frames)
+}
+
+struct Session {
+    file: tokio::fs::File,
+    race_id: Option<String>,
+    last: Value,
+    last_keyframe: Instant,
+}
+
+async fn open_session(dir: &std::path::Path, race_id: Option<String>, state: Value, t: i64) -> std::io::Result<Session> {
+    let path = dir.join(format!("rec-{t}.jsonl"));
+    let mut file = tokio::fs::File::create(&path).await?;
+    let frame = Frame { t, keyframe: Some(state.clone()), ops: Vec::new() };
+    file.write_all(format!("{}\n", serde_json::to_string(&frame)?).as_bytes()).await?;
+    info!("Recording: {} (race {})", path.display(), race_id.as_deref().unwrap_or("-"));
+    Ok(Session { file, race_id, last: state, last_keyframe: Instant::now() })
+}
+
+pub async fn run_recorder(config: RecordingConfig, shared: SharedState) {
+    if let Err(e) = tokio::fs::create_dir_all(&config.dir).await {
+        info!("Recording: {} unavailable ({e}) — race not recorded", config.dir.display());
+        return;
+    }
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut session: Option<Session> = None;
+    loop {
+        ticker.tick().await;
+        let (race_id, next) = {
+            let state = shared.read().await;
+            (state.active_race_id.clone(), serde_json::to_value(&*state))
+        };
+        let Ok(next) = next else { continue };
+        let t = now_ms();
+
+        let current = match session.as_mut() {
+            Some(s) if s.race_id == race_id => s,
+            _ => match open_session(&config.dir, race_id, next, t).await {
+                Ok(s) => {
+                    session = Some(s);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Recording: failed to open session file: {e}");
+                    continue;
+                }
+            },
+        };
+
+        let frame = if current.last_keyframe.elapsed() >= config.keyframe_every {
+            current.last_keyframe = Instant::now();
+            Frame { t, keyframe: Some(next.clone()), ops: Vec::new() }
+        } else {
+            let mut ops = Vec::new();
+            state_delta::diff(&current.last, &next, "", &mut ops);
+            if ops.is_empty() {
+                continue;
+            }
+            Frame { t, keyframe: None, ops }
+        };
+        current.last = next;
+        let Ok(line) = serde_json::to_string(&frame) else { continue };
+        if let Err(e) = current.file.write_all(format!("{line}\n").as_bytes()).await {
+            warn!("Recording: write failed: {e}");
+        }
+    }
+}
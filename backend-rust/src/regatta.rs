@@ -0,0 +1,103 @@
+//! # regatta
+//!
+//! Several regattas (events) on one backend.
+//!
+//! Like `race_session` one level up: the top-level persistent fields of
+//! `RaceState` (course, entries, races, results, procedures, league schedule —
+//! everything `snapshots::restore_into` covers) always belong to the *active*
+//! event, so handlers and tick loops are unchanged. Every other event is a
+//! document `event-<id>` in the `state_store`, written when it is switched out.
+//!
+//! - `create-event { name, venue?, startDate?, endDate? }` adds an empty event
+//! - `select-event { eventId }` makes it the live one (refused while a sequence runs)
+//! - `register { …, eventId }` joins the room `event:<id>`; a client registering for
+//!   an event that is not live gets that event's stored state as `init-state`
+//!
+//! A state without `active_event_id` is the classic single regatta — it becomes
+//! "Event 1" the first time another event is created.
+
+use crate::persistence::saveable;
+use crate::snapshots;
+use crate::state::{RaceState, RegattaEvent};
+use crate::state_schema;
+use crate::state_store::store;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EventError {
+    #[error("Unknown event: {0}")]
+    NotFound(String),
+    #[error("Stop the running sequence before switching events")]
+    SequenceRunning,
+    #[error("Event storage failed: {0}")]
+    Store(#[from] anyhow::Error),
+}
+
+fn key(id: &str) -> String {
+    format!("event-{id}")
+}
+
+pub fn room(id: &str) -> String {
+    format!("event:{id}")
+}
+
+/// Name the classic single regatta the first time it matters.
+fn ensure_active(state: &mut RaceState, now: i64) -> String {
+    if let Some(id) = &state.active_event_id {
+        return id.clone();
+    }
+    let id = format!("event-{}", state.events.len() + 1);
+    state.events.push(RegattaEvent {
+        id: id.clone(),
+        name: format!("Event {}", state.events.len() + 1),
+        venue: None,
+        start_date: None,
+        end_date: None,
+        created_ms: now,
+    });
+    state.active_event_id = Some(id.clone());
+    id
+}
+
+pub fn create(
+    state: &mut RaceState,
+    name: Option<String>,
+    venue: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    now: i64,
+) -> RegattaEvent {
+    ensure_active(state, now);
+    let number = state.events.len() + 1;
+    let event = RegattaEvent {
+        id: format!("event-{now}"),
+        name: name.unwrap_or_else(|| format!("Event {number}")),
+        venue,
+        start_date,
+        end_date,
+        created_ms: now,
+    };
+    state.events.push(event.clone());
+    event
+}
+
+/// Store the active event and swap `id` in. A never-selected event starts empty.
+pub async fn select(state: &mut RaceState, id: &str, now: i64) -> Result<RegattaEvent, EventError> {
+    let event = state.events.iter().find(|e| e.id == id).cloned().ok_or_else(|| EventError::NotFound(id.to_string()))?;
+    let active = ensure_active(state, now);
+    if active == id {
+        return Ok(event);
+    }
+    store().save(&key(&active), &serde_json::to_string(&saveable(state)).map_err(anyhow::Error::from)?).await?;
+    let incoming = load(id).await?.unwrap_or_default();
+    snapshots::restore_into(state, incoming);
+    state.active_event_id = Some(id.to_string());
+    Ok(event)
+}
+
+/// The stored state of an event that is not live (None if it was never switched out).
+pub async fn load(id: &str) -> anyhow::Result<Option<RaceState>> {
+    match store().load(&key(id)).await? {
+        Some(data) => Ok(Some(state_schema::upgrade(serde_json::from_str(&data)?, &format!("Event {id}"))?)),
+        None => Ok(None),
+    }
+}
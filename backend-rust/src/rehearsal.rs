@@ -0,0 +1,86 @@
+//! # rehearsal
+//!
+//! Procedure rehearsal (dry-run) mode.
+//!
+//! A race officer can run any graph — a custom Architect graph, a rule-pack
+//! preset or the procedure currently loaded — on a private engine whose clock
+//! runs `speed`× real time (10× by default, up to 60×). Rehearsal engines are
+//! keyed by the requesting socket and never touch `RaceState`, the live engines,
+//! the audit chain or the signal outputs; their updates go only to that socket
+//! as `rehearsal-update`, with the engine events (node entered, sound fired…)
+//! attached so timing and sounds can be checked before race day.
+//!
+//! The tick runs at 50Hz so that even at 60× a transition lands within ~1 s of
+//! simulated time of where it would on the water.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use socketioxide::SocketIo;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::procedure_engine::{EngineEvent, ProcedureEngine, TickResult};
+use crate::state::SequenceUpdate;
+
+/// Rehearsal engines by socket id
+pub type Rehearsals = Arc<RwLock<HashMap<String, ProcedureEngine>>>;
+
+pub const DEFAULT_SPEED: f64 = 10.0;
+pub const MAX_SPEED: f64 = 60.0;
+
+/// Room a socket joins to receive its own rehearsal stream
+pub fn room(socket_id: &str) -> String {
+    format!("rehearsal:{socket_id}")
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RehearsalUpdate {
+    pub speed: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<SequenceUpdate>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EngineEvent>,
+    pub complete: bool,
+}
+
+pub async fn run_rehearsal_tick(rehearsals: Rehearsals, io: SocketIo) {
+    let mut interval = tokio::time::interval(Duration::from_millis(20)); // 50Hz
+    loop {
+        interval.tick().await;
+
+        let mut outgoing = Vec::new();
+        {
+            let mut engines = rehearsals.write().await;
+            if engines.is_empty() {
+                continue;
+            }
+            for (socket_id, eng) in engines.iter_mut() {
+                let result = eng.tick();
+                let events = eng.drain_events();
+                let (update, complete) = match result {
+                    TickResult::Idle => (None, false),
+                    TickResult::Update(upd) | TickResult::GunFired(upd) => (Some(upd), false),
+                    TickResult::SequenceComplete => (None, true),
+                };
+                if update.is_some() || complete || !events.is_empty() {
+                    outgoing.push((socket_id.clone(), RehearsalUpdate { speed: eng.speed(), update, events, complete }));
+                }
+            }
+            engines.retain(|socket_id, eng| {
+                let done = !eng.is_running() || outgoing.iter().any(|(id, u)| id == socket_id && u.complete);
+                if done {
+                    info!("Rehearsal for {socket_id} finished");
+                }
+                !done
+            });
+        }
+
+        for (socket_id, update) in outgoing {
+            let _ = io.to(room(&socket_id)).emit("rehearsal-update", &update);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! # rest_api
+//!
+//! REST mirror of the key Socket.IO commands, for automations, scripts and clients
+//! on connections too flaky to hold a Socket.IO session.
+//!
+//! | Method | Path                           | Socket.IO equivalent |
+//! |--------|--------------------------------|----------------------|
+//! | GET    | `/api/v1/state`                | `init-state`         |
+//! | GET    | `/api/v1/logs?limit=&category=`| `new-log`            |
+//! | GET    | `/api/v1/track?boatId=&raceId=&fromMs=&toMs=` | `get-track` |
+//! | POST   | `/api/v1/sequence/start`       | `start-sequence`     |
+//! | POST   | `/api/v1/procedure-action`     | `procedure-action`   |
+//! | POST   | `/api/v1/penalties`            | `issue-penalty`      |
+//! | GET    | `/api/v1/bundle`               | `export-bundle`      |
+//! | POST   | `/api/v1/bundle`               | `import-bundle`      |
+//!
+//! Requests carry `Authorization: Bearer <Supabase JWT>`. Commands take the same JSON
+//! body as their socket event, are checked against the permission matrix under the
+//! socket event name and run the same code as the socket handlers, so connected
+//! clients see the same updates whichever way a command arrived. A command answers
+//! with the resulting race state; the event bundle routes (see `event_bundle`) send
+//! and take the zstd archive as the raw body.
+//!
+//! A tracker token reads only its boat's view of the state (see `boat_scope`) and
+//! no logs.
+//!
+//! ## Invariants
+//! - Core Invariant #2: commands and denied attempts are audited like their socket counterparts
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use socketioxide::SocketIo;
+use tracing::warn;
+
+use crate::audit::AuditLogger;
+use crate::auth::{AuthEngine, ResolvedToken};
+use crate::boat_scope::{self, SCOPED_ROOM};
+use crate::event_bundle::{self, ImportSummary};
+use crate::handlers::{self, now_ms, Outlet, SharedEngine, SharedState};
+use crate::persistence::{save_state, saveable};
+use crate::scoring;
+use crate::snapshots::{self, SnapshotConfig};
+use crate::state::{LogCategory, LogEntry, RaceState};
+use crate::track_store::{self, Track, TrackQuery};
+
+/// Client id recorded in audit blocks for commands that arrived over REST
+const REST_CLIENT: &str = "rest-api";
+const DEFAULT_LOG_LIMIT: usize = 100;
+/// Event bundles carry the audit chain and can be far larger than a command body
+const BUNDLE_LIMIT: usize = 256 * 1024 * 1024;
+
+#[derive(Clone)]
+pub struct ApiContext {
+    pub shared: SharedState,
+    pub engine: SharedEngine,
+    pub auth: Arc<AuthEngine>,
+    pub audit: AuditLogger,
+    pub io: SocketIo,
+}
+
+pub fn router(ctx: ApiContext) -> Router {
+    Router::new()
+        .route("/state", get(get_state))
+        .route("/logs", get(get_logs))
+        .route("/track", get(get_track))
+        .route("/sequence/start", post(start_sequence))
+        .route("/procedure-action", post(procedure_action))
+        .route("/penalties", post(issue_penalty))
+        .route("/bundle", get(export_bundle).post(import_bundle).layer(DefaultBodyLimit::max(BUNDLE_LIMIT)))
+        .with_state(ctx)
+}
+
+/// Role carried by a token, resolved the way `register` resolves it.
+pub async fn token_role(auth: &AuthEngine, token: &str) -> Option<String> {
+    auth.resolve_token(token).await.map(|resolved| resolved.role)
+}
+
+/// The bearer token, resolved.
+async fn bearer_token(auth: &AuthEngine, headers: &HeaderMap) -> Result<ResolvedToken, StatusCode> {
+    let token = headers.get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    auth.resolve_token(token).await.ok_or(StatusCode::UNAUTHORIZED)
+}
+
+/// Role of the bearer token.
+async fn bearer_role(auth: &AuthEngine, headers: &HeaderMap) -> Result<String, StatusCode> {
+    bearer_token(auth, headers).await.map(|resolved| resolved.role)
+}
+
+/// Check the permission matrix and audit the command (or the refusal).
+async fn authorize(ctx: &ApiContext, headers: &HeaderMap, event: &str, data: &Value) -> Result<(), StatusCode> {
+    let role = bearer_role(&ctx.auth, headers).await?;
+    if !ctx.auth.authorize(event, Some(&role)).await {
+        warn!("Unauthorized REST {event} attempt (role {role})");
+        ctx.audit.log_permission_denied(event, REST_CLIENT, Some(&role)).await;
+        return Err(StatusCode::FORBIDDEN);
+    }
+    ctx.audit.log_director_command(event, REST_CLIENT, Some(&role), data).await;
+    Ok(())
+}
+
+async fn get_state(State(ctx): State<ApiContext>, headers: HeaderMap) -> Result<Json<RaceState>, StatusCode> {
+    let token = bearer_token(&ctx.auth, &headers).await?;
+    let state = ctx.shared.read().await;
+    if token.role != SCOPED_ROOM {
+        return Ok(Json(state.clone()));
+    }
+    match token.boat_id {
+        Some(boat_id) => Ok(Json(boat_scope::view(&state, &boat_id))),
+        None => {
+            ctx.audit.log_permission_denied("get-state", REST_CLIENT, Some(&token.role)).await;
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LogsQuery {
+    limit: Option<usize>,
+    category: Option<LogCategory>,
+}
+
+/// Most recent log entries, oldest first.
+async fn get_logs(
+    State(ctx): State<ApiContext>,
+    headers: HeaderMap,
+    Query(query): Query<LogsQuery>,
+) -> Result<Json<Vec<LogEntry>>, StatusCode> {
+    let role = bearer_role(&ctx.auth, &headers).await?;
+    // Logs are left out of a tracker's view
+    if role == SCOPED_ROOM {
+        ctx.audit.log_permission_denied("get-logs", REST_CLIENT, Some(&role)).await;
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let state = ctx.shared.read().await;
+    let mut logs: Vec<LogEntry> = state.logs.iter().rev()
+        .filter(|l| query.category.as_ref().is_none_or(|c| &l.category == c))
+        .take(query.limit.unwrap_or(DEFAULT_LOG_LIMIT))
+        .cloned()
+        .collect();
+    logs.reverse();
+    Ok(Json(logs))
+}
+
+/// A boat's stored track; a read, so only a refusal is audited.
+async fn get_track(
+    State(ctx): State<ApiContext>,
+    headers: HeaderMap,
+    Query(query): Query<TrackQuery>,
+) -> Result<Json<Track>, StatusCode> {
+    let role = bearer_role(&ctx.auth, &headers).await?;
+    if !ctx.auth.authorize("get-track", Some(&role)).await {
+        ctx.audit.log_permission_denied("get-track", REST_CLIENT, Some(&role)).await;
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let race_id = match &query.race_id {
+        Some(id) => id.clone(),
+        None => track_store::race_key(&*ctx.shared.read().await),
+    };
+    Ok(Json(track_store::query(&query, race_id).await))
+}
+
+async fn start_sequence(State(ctx): State<ApiContext>, headers: HeaderMap, Json(data): Json<Value>) -> Result<Json<RaceState>, StatusCode> {
+    authorize(&ctx, &headers, "start-sequence", &data).await?;
+    handlers::start_sequence(&ctx.shared, &ctx.engine, &ctx.audit, &Outlet::Io(ctx.io.clone()), &data).await;
+    Ok(Json(ctx.shared.read().await.clone()))
+}
+
+async fn procedure_action(State(ctx): State<ApiContext>, headers: HeaderMap, Json(data): Json<Value>) -> Result<Json<RaceState>, StatusCode> {
+    authorize(&ctx, &headers, "procedure-action", &data).await?;
+    handlers::procedure_action(&ctx.shared, &ctx.engine, &ctx.audit, &Outlet::Io(ctx.io.clone()), &data).await;
+    Ok(Json(ctx.shared.read().await.clone()))
+}
+
+async fn issue_penalty(State(ctx): State<ApiContext>, headers: HeaderMap, Json(data): Json<Value>) -> Result<Json<RaceState>, StatusCode> {
+    authorize(&ctx, &headers, "issue-penalty", &data).await?;
+    handlers::issue_penalty(&ctx.shared, &Outlet::Io(ctx.io.clone()), &data).await;
+    Ok(Json(ctx.shared.read().await.clone()))
+}
+
+async fn export_bundle(State(ctx): State<ApiContext>, headers: HeaderMap) -> Result<impl axum::response::IntoResponse, StatusCode> {
+    authorize(&ctx, &headers, "export-bundle", &Value::Null).await?;
+    let state = ctx.shared.read().await.clone();
+    let bytes = event_bundle::export(state, &ctx.audit, now_ms()).await.map_err(|e| {
+        warn!("Event bundle export failed: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zstd"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"event-bundle.json.zst\""),
+        ],
+        bytes,
+    ))
+}
+
+async fn import_bundle(State(ctx): State<ApiContext>, headers: HeaderMap, body: Bytes) -> Result<Json<ImportSummary>, StatusCode> {
+    authorize(&ctx, &headers, "import-bundle", &json!({ "bytes": body.len() })).await?;
+    let bundle = event_bundle::decode(&body).map_err(|e| {
+        warn!("Event bundle import rejected: {e}");
+        StatusCode::BAD_REQUEST
+    })?;
+    if ctx.engine.read().await.is_running() {
+        warn!("Event bundle import rejected: {}", event_bundle::BundleError::SequenceRunning);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let current = saveable(&*ctx.shared.read().await);
+    if let Err(e) = snapshots::take(&current, "pre-import", false, SnapshotConfig::default().keep).await {
+        warn!("Event bundle import: pre-import snapshot failed: {e}");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    {
+        let mut state = ctx.shared.write().await;
+        snapshots::restore_into(&mut state, bundle.state.clone());
+        scoring::rescore(&mut state);
+        let _ = save_state(&state).await;
+        Outlet::Io(ctx.io.clone()).emit_state(&state);
+    }
+
+    let summary = event_bundle::store_files(&bundle, &ctx.audit.dir().await).await.map_err(|e| {
+        warn!("Event bundle import: storing files failed: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    ctx.audit.log_session_event("EVENT_BUNDLE_IMPORTED", Some(json!(summary))).await;
+    Ok(Json(summary))
+}
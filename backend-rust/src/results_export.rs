@@ -0,0 +1,139 @@
+//! # results_export
+//!
+//! Results downloads for publishing without retyping the log.
+//!
+//! - `GET /results.csv` — one row per boat per closed race: sail number and name,
+//!   place, scoring code, points, start/finish/elapsed/corrected times and the
+//!   penalties raised
+//! - `GET /results/sailwave.csv` — the series table in the column layout of
+//!   Sailwave's "Import results" wizard (`Rank, Sail No, R1 … Rn, Total, Nett`),
+//!   scores written the way Sailwave prints them: discards in parentheses and
+//!   codes after the points, e.g. `(13.0 DNC)`
+//!
+//! Sail numbers and names come from the entry list; unregistered boats show their
+//! boat id. Corrected times follow the race's handicap (`handicap`).
+
+use chrono::{TimeZone, Utc};
+
+use crate::entries;
+use crate::state::{RaceResult, RaceScore, RaceState, ScoreCode};
+
+/// Quote a CSV field when it needs it (RFC 4180).
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn row(fields: &[String]) -> String {
+    let mut line = fields.iter().map(|f| field(f)).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn iso_time(ms: i64) -> String {
+    Utc.timestamp_millis_opt(ms).single().map(|t| t.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()).unwrap_or_default()
+}
+
+/// `h:mm:ss` of a duration in ms.
+fn duration(ms: i64) -> String {
+    let secs = ms.max(0) / 1000;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+pub fn code_label(code: ScoreCode) -> &'static str {
+    match code {
+        ScoreCode::Dnc => "DNC",
+        ScoreCode::Dns => "DNS",
+        ScoreCode::Ocs => "OCS",
+        ScoreCode::Dnf => "DNF",
+        ScoreCode::Tle => "TLE",
+        ScoreCode::Dsq => "DSQ",
+    }
+}
+
+fn score_for<'a>(state: &'a RaceState, boat_id: &str, race_number: u32) -> Option<&'a RaceScore> {
+    state.standings.iter()
+        .find(|s| s.boat_id == boat_id)?
+        .races.iter()
+        .find(|r| r.race_number == race_number)
+}
+
+/// Plain CSV, optionally restricted to one race.
+pub fn csv(state: &RaceState, race_number: Option<u32>) -> String {
+    let mut out = row(&[
+        "Race", "Boat", "Sail No", "Name", "Place", "Code", "Points", "Discarded",
+        "Start", "Finish", "Elapsed", "Corrected", "Penalties",
+    ].map(String::from));
+
+    let races = state.results.iter().filter(|r| race_number.is_none_or(|n| r.race_number == n));
+    for race in races {
+        for boat_id in boats_in_order(state, race) {
+            let score = score_for(state, &boat_id, race.race_number);
+            let finish = race.finishes.iter().find(|f| f.boat_id == boat_id).map(|f| f.finish_ms);
+            let times = race.corrected.iter().find(|c| c.boat_id == boat_id);
+            let elapsed = times.map(|t| duration(t.elapsed_ms)).unwrap_or_default();
+            let corrected = times.map(|t| duration(t.corrected_ms)).unwrap_or_default();
+            let penalties = race.penalties.iter()
+                .filter(|p| p.boat_id == boat_id && p.counts())
+                .map(|p| serde_json::to_value(&p.penalty_type).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let entry = entries::resolve(state, &boat_id);
+            out.push_str(&row(&[
+                race.race_number.to_string(),
+                boat_id.clone(),
+                entry.map(|e| e.sail_number.clone()).unwrap_or_default(),
+                entry.map(|e| e.boat_name.clone()).unwrap_or_default(),
+                score.and_then(|s| s.place).map(|p| p.to_string()).unwrap_or_default(),
+                score.and_then(|s| s.code).map(code_label).unwrap_or_default().to_string(),
+                score.map(|s| format!("{:.1}", s.points)).unwrap_or_default(),
+                score.map(|s| s.discarded.to_string()).unwrap_or_default(),
+                race.start_ms.map(iso_time).unwrap_or_default(),
+                finish.map(iso_time).unwrap_or_default(),
+                elapsed,
+                corrected,
+                penalties,
+            ]));
+        }
+    }
+    out
+}
+
+/// Boats of a race by points, then id.
+fn boats_in_order(state: &RaceState, race: &RaceResult) -> Vec<String> {
+    let mut boats: Vec<(f64, String)> = state.standings.iter()
+        .filter_map(|s| Some((score_for(state, &s.boat_id, race.race_number)?.points, s.boat_id.clone())))
+        .collect();
+    boats.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    boats.into_iter().map(|(_, id)| id).collect()
+}
+
+/// Series table for Sailwave's results import.
+pub fn sailwave(state: &RaceState) -> String {
+    let mut header = vec!["Rank".to_string(), "Sail No".to_string()];
+    header.extend(state.results.iter().map(|r| format!("R{}", r.race_number)));
+    header.extend(["Total".to_string(), "Nett".to_string()]);
+    let mut out = row(&header);
+
+    for standing in &state.standings {
+        let sail_number = entries::resolve(state, &standing.boat_id).map(|e| e.sail_number.clone());
+        let mut fields = vec![standing.rank.to_string(), sail_number.unwrap_or_else(|| standing.boat_id.clone())];
+        for race in &state.results {
+            let cell = standing.races.iter().find(|r| r.race_number == race.race_number).map(|r| {
+                let score = match r.code {
+                    Some(code) => format!("{:.1} {}", r.points, code_label(code)),
+                    None => format!("{:.1}", r.points),
+                };
+                if r.discarded { format!("({score})") } else { score }
+            });
+            fields.push(cell.unwrap_or_default());
+        }
+        fields.push(format!("{:.1}", standing.total));
+        fields.push(format!("{:.1}", standing.net));
+        out.push_str(&row(&fields));
+    }
+    out
+}
@@ -0,0 +1,87 @@
+//! # roles
+//!
+//! Role administration for directors.
+//!
+//! A client's role is resolved from its token at `register`; a director can
+//! then change it for that connection, and define roles of their own on top of
+//! the permission matrix's `director` / `jury` / `tracker` / `media` (see
+//! `permissions`), which custom roles cannot redefine.
+//!
+//! - `list-clients` → `clients-update { clients: [{ socketId, role, boatId? }], customRoles }`
+//! - `set-client-role { socketId, role, boatId? }` — promote/demote a connection;
+//!   `boatId` is needed to make it a tracker. The client gets `role-changed { role }`
+//! - `define-role { name, events, description? }` / `delete-role { name }` →
+//!   `roles-update { customRoles }`; a role still held by a client is not deleted
+//!
+//! Errors come back as `role-error`. Custom roles are kept per event in a
+//! `state_store` document (`custom_roles`, `custom_roles-<eventId>`) and swapped
+//! in with `select-event`; role changes of connections last until they disconnect.
+//!
+//! ## Invariants
+//! - Core Invariant #2: every change is a `DIRECTOR_COMMAND` block in the audit chain
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::RaceState;
+use crate::state_store::store;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomRole {
+    pub name: String,
+    /// Allowed socket events (`"*"` = all)
+    pub events: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectedClient {
+    pub socket_id: String,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boat_id: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RoleError {
+    #[error("Unknown role: {0}")]
+    UnknownRole(String),
+    #[error("{0} is defined by the permission matrix")]
+    BuiltIn(String),
+    #[error("A role needs a name")]
+    Unnamed,
+    #[error("No connected client {0}")]
+    NoSuchClient(String),
+    #[error("You cannot change your own role")]
+    OwnRole,
+    #[error("A tracker needs a boatId")]
+    NeedsBoat,
+    #[error("Role {0} is held by {1} connected client(s)")]
+    InUse(String, usize),
+}
+
+/// Per-connection room, for reaching one client from another's command.
+pub fn client_room(socket_id: &str) -> String {
+    format!("client:{socket_id}")
+}
+
+/// Store key of the active event's custom roles.
+pub fn key(state: &RaceState) -> String {
+    match &state.active_event_id {
+        Some(id) => format!("custom_roles-{id}"),
+        None => "custom_roles".to_string(),
+    }
+}
+
+pub async fn load(key: &str) -> anyhow::Result<Vec<CustomRole>> {
+    match store().load(key).await? {
+        Some(data) => Ok(serde_json::from_str(&data)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub async fn save(key: &str, roles: &[CustomRole]) -> anyhow::Result<()> {
+    store().save(key, &serde_json::to_string(roles)?).await
+}
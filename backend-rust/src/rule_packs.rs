@@ -0,0 +1,174 @@
+//! # rule_packs
+//!
+//! Built-in RRS starting-procedure presets selectable from `start-sequence`
+//! (`{ preset, minutes, prepFlag, sounds }`), so directors do not rebuild the
+//! standard sequences node by node in the Architect.
+//!
+//! | preset          | sequence                                                        |
+//! |-----------------|-----------------------------------------------------------------|
+//! | `FLEET`         | Rule 26 — warning, preparatory (P/I/Z/U/black), one-minute, start |
+//! | `FLEET_U`       | Rule 26 with the U flag as preparatory signal (rule 30.3)       |
+//! | `FLEET_BLACK`   | Rule 26 with the black flag as preparatory signal (rule 30.4)   |
+//! | `MATCH_RACE`    | Appendix C C3.1 — flag F attention signal at 10 min, then 5-4-1-0 |
+//! | `TEAM_RACE`     | Appendix D — rule 26 run as the customary 3-minute sequence      |
+//!
+//! Sound conventions: `RRS` (default, rule 26 / C3.1 sounds) or `NONE` (flags only,
+//! for venues with noise restrictions — flags govern timing under RRS 26 anyway).
+
+use serde::Serialize;
+
+use crate::handlers::standard_procedure;
+use crate::state::{ProcedureEdge, ProcedureGraph, ProcedureNode, ProcedureNodeData, SoundSignal};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RulePack {
+    Fleet,
+    FleetU,
+    FleetBlack,
+    MatchRace,
+    TeamRace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SoundConvention {
+    #[default]
+    Rrs,
+    None,
+}
+
+impl RulePack {
+    pub const ALL: [RulePack; 5] = [Self::Fleet, Self::FleetU, Self::FleetBlack, Self::MatchRace, Self::TeamRace];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_uppercase().replace('-', "_").as_str() {
+            "FLEET" | "RRS26" | "STANDARD" => Some(Self::Fleet),
+            "FLEET_U" | "U" => Some(Self::FleetU),
+            "FLEET_BLACK" | "BLACK" => Some(Self::FleetBlack),
+            "MATCH_RACE" | "APPENDIX_C" => Some(Self::MatchRace),
+            "TEAM_RACE" | "APPENDIX_D" => Some(Self::TeamRace),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Fleet => "preset-fleet",
+            Self::FleetU => "preset-fleet-u",
+            Self::FleetBlack => "preset-fleet-black",
+            Self::MatchRace => "preset-match-race",
+            Self::TeamRace => "preset-team-race",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fleet => "Fleet race (rule 26)",
+            Self::FleetU => "Fleet race — U flag start (rule 30.3)",
+            Self::FleetBlack => "Fleet race — black flag start (rule 30.4)",
+            Self::MatchRace => "Match race (Appendix C)",
+            Self::TeamRace => "Team race (Appendix D)",
+        }
+    }
+
+    pub fn category(self) -> &'static str {
+        match self {
+            Self::Fleet | Self::FleetU | Self::FleetBlack => "fleet",
+            Self::MatchRace => "match-race",
+            Self::TeamRace => "team-race",
+        }
+    }
+
+    /// Preparatory flag the preset forces, if any (displayed in `state.prep_flag`).
+    pub fn prep_flag(self) -> Option<&'static str> {
+        match self {
+            Self::FleetU => Some("U"),
+            Self::FleetBlack => Some("BLACK"),
+            Self::MatchRace => Some("P"),
+            _ => None,
+        }
+    }
+}
+
+impl SoundConvention {
+    pub fn parse(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "NONE" | "SILENT" | "FLAGS_ONLY" => Self::None,
+            _ => Self::Rrs,
+        }
+    }
+}
+
+/// Build the procedure graph for a preset. `minutes` applies to the fleet packs
+/// (5 or 3); match and team racing use their appendix timings.
+pub fn procedure(pack: RulePack, minutes: u64, prep_flag: &str, sounds: SoundConvention) -> ProcedureGraph {
+    let mut graph = match pack {
+        RulePack::Fleet => standard_procedure(minutes, prep_flag),
+        RulePack::FleetU | RulePack::FleetBlack => standard_procedure(minutes, pack.prep_flag().unwrap_or(prep_flag)),
+        RulePack::TeamRace => standard_procedure(3, prep_flag),
+        RulePack::MatchRace => match_race_procedure(),
+    };
+    graph.id = match pack {
+        RulePack::Fleet | RulePack::FleetU | RulePack::FleetBlack => format!("{}-{minutes}min", pack.id()),
+        _ => pack.id().to_string(),
+    };
+
+    if sounds == SoundConvention::None {
+        for node in &mut graph.nodes {
+            node.data.sound = SoundSignal::None;
+            node.data.sound_on_remove = SoundSignal::None;
+        }
+    }
+    graph
+}
+
+/// Appendix C, C3.1: attention (F) 10 min, warning (class) 5, preparatory (P) 4,
+/// one-minute (P removed, long sound) 1, start (class removed) 0.
+fn match_race_procedure() -> ProcedureGraph {
+    let nodes = vec![
+        node("0", "Idle", &[], 0.0, SoundSignal::None, "IDLE", false),
+        node("1", "Attention Signal", &["F"], 300.0, SoundSignal::OneShort, "WARNING", false),
+        node("2", "Warning Signal", &["CLASS"], 60.0, SoundSignal::OneShort, "WARNING", false),
+        node("3", "Preparatory Signal", &["CLASS", "P"], 180.0, SoundSignal::OneShort, "PREPARATORY", false),
+        node("4", "One-Minute", &["CLASS"], 60.0, SoundSignal::OneLong, "ONE_MINUTE", false),
+        node("5", "Start", &[], 0.0, SoundSignal::OneShort, "RACING", false),
+        node("6", "Racing", &[], 0.0, SoundSignal::None, "RACING", true),
+    ];
+    let edges = (0..nodes.len() - 1)
+        .map(|i| ProcedureEdge {
+            id: format!("e{i}-{}", i + 1),
+            source: i.to_string(),
+            target: (i + 1).to_string(),
+            animated: Some(true),
+        })
+        .collect();
+
+    ProcedureGraph {
+        id: RulePack::MatchRace.id().to_string(),
+        nodes,
+        edges,
+        auto_restart: false,
+    }
+}
+
+fn node(id: &str, label: &str, flags: &[&str], duration: f64, sound: SoundSignal, race_status: &str, wait: bool) -> ProcedureNode {
+    ProcedureNode {
+        id: id.into(),
+        node_type: "state".into(),
+        position: None,
+        data: ProcedureNodeData {
+            label: label.into(),
+            flags: flags.iter().map(|f| f.to_string()).collect(),
+            duration,
+            announce_lead: if sound == SoundSignal::None { 0.0 } else { 10.0 },
+            sound,
+            sound_on_remove: SoundSignal::None,
+            wait_for_user_trigger: wait,
+            action_label: wait.then(|| "FINISH RACE — End racing".to_string()),
+            post_trigger_duration: 0.0,
+            post_trigger_flags: vec![],
+            race_status: Some(race_status.into()),
+            metadata: None,
+        },
+    }
+}
@@ -0,0 +1,179 @@
+//! # schedule_export
+//!
+//! The league schedule for publishing: every flight not abandoned, race by race,
+//! with the boat each team sails, the planned start and the officials
+//! (`officials`).
+//!
+//! - `GET /schedule.csv` — `Flight, Race, Planned start, Team, Club, Boat,
+//!   Umpires, Support boats`, one row per pairing
+//! - `GET /schedule.html` — a sheet per flight (races down, boats across) laid
+//!   out for A4 so it can be printed or saved as PDF from the browser
+//!
+//! Both take `?first=` (the first start, Unix ms or RFC 3339; default as if the
+//! procedure were started now) and `?raceMinutes=` (default 15). Races follow
+//! each other in flight order: the next start is the race's length plus the
+//! loaded procedure's run up to its starting signal
+//! (`procedure_engine::planned_gun_offset`) after the one before. Times are in UTC.
+
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::handlers::now_ms;
+use crate::procedure_engine::planned_gun_offset;
+use crate::state::{Flight, FlightStatus, Pairing, RaceState};
+
+const DEFAULT_RACE_MINUTES: f64 = 15.0;
+
+/// One race of the schedule with its planned start.
+struct PlannedRace<'a> {
+    flight: &'a Flight,
+    race_index: u32,
+    start_ms: i64,
+    pairings: Vec<&'a Pairing>,
+}
+
+/// `first` as Unix ms or an RFC 3339 time.
+pub fn parse_time(value: &str) -> Option<i64> {
+    value.trim().parse::<i64>().ok()
+        .or_else(|| DateTime::parse_from_rfc3339(value.trim()).ok().map(|t| t.timestamp_millis()))
+}
+
+fn plan(state: &RaceState, first_ms: Option<i64>, race_minutes: Option<f64>) -> Vec<PlannedRace<'_>> {
+    let gun_offset_ms = state.current_procedure.as_ref().and_then(planned_gun_offset).unwrap_or(0.0) * 1000.0;
+    let race_ms = race_minutes.filter(|m| *m > 0.0).unwrap_or(DEFAULT_RACE_MINUTES) * 60_000.0;
+    let mut start_ms = first_ms.unwrap_or_else(|| now_ms() + gun_offset_ms as i64);
+
+    let mut flights: Vec<&Flight> = state.flights.values().filter(|f| f.status != FlightStatus::Abandoned).collect();
+    flights.sort_by_key(|f| f.flight_number);
+    let mut races = Vec::new();
+    for flight in flights {
+        let indices: BTreeSet<u32> = state.pairings.iter().filter(|p| p.flight_id == flight.id).map(|p| p.race_index).collect();
+        for race_index in indices {
+            let mut pairings: Vec<&Pairing> = state.pairings.iter()
+                .filter(|p| p.flight_id == flight.id && p.race_index == race_index)
+                .collect();
+            pairings.sort_by_key(|p| boat_order(&p.boat_id));
+            races.push(PlannedRace { flight, race_index, start_ms, pairings });
+            start_ms += (race_ms + gun_offset_ms) as i64;
+        }
+    }
+    races
+}
+
+/// Numeric boat ids in number order, others after them by name.
+fn boat_order(boat_id: &str) -> (u64, String) {
+    (boat_id.parse().unwrap_or(u64::MAX), boat_id.to_string())
+}
+
+fn team_name(state: &RaceState, team_id: &str) -> String {
+    state.teams.get(team_id).map_or_else(|| team_id.to_string(), |t| t.name.clone())
+}
+
+fn official_names(state: &RaceState, ids: impl Iterator<Item = String>) -> Vec<String> {
+    ids.map(|id| state.officials.iter().find(|o| o.id == id).map_or(id, |o| o.name.clone())).collect()
+}
+
+fn umpires(state: &RaceState, flight: &Flight) -> String {
+    flight.officials.umpire_pairs.iter()
+        .map(|pair| official_names(state, pair.iter().cloned()).join(" & "))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn support_boats(state: &RaceState, flight: &Flight) -> String {
+    official_names(state, flight.officials.support_boats.iter().cloned()).join("; ")
+}
+
+fn iso_time(ms: i64) -> String {
+    Utc.timestamp_millis_opt(ms).single().map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string()).unwrap_or_default()
+}
+
+/// Quote a CSV field when it needs it (RFC 4180).
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn csv(state: &RaceState, first_ms: Option<i64>, race_minutes: Option<f64>) -> String {
+    let mut out = String::from("Flight,Race,Planned start,Team,Club,Boat,Umpires,Support boats\r\n");
+    for race in plan(state, first_ms, race_minutes) {
+        for p in &race.pairings {
+            let club = state.teams.get(&p.team_id).map(|t| t.club.clone()).unwrap_or_default();
+            let fields = [
+                race.flight.group_label.clone(),
+                (race.race_index + 1).to_string(),
+                iso_time(race.start_ms),
+                team_name(state, &p.team_id),
+                club,
+                p.boat_id.clone(),
+                umpires(state, race.flight),
+                support_boats(state, race.flight),
+            ];
+            out.push_str(&fields.iter().map(|f| field(f)).collect::<Vec<_>>().join(","));
+            out.push_str("\r\n");
+        }
+    }
+    out
+}
+
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+const STYLE: &str = "\
+@page { size: A4; margin: 12mm; }
+body { font-family: Helvetica, Arial, sans-serif; font-size: 10pt; color: #111; }
+h1 { font-size: 16pt; margin: 0 0 4mm; }
+section { page-break-inside: avoid; margin-bottom: 8mm; }
+h2 { font-size: 12pt; margin: 0 0 1mm; }
+p.officials { margin: 0 0 2mm; color: #444; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #999; padding: 1.5mm 2mm; text-align: left; }
+th { background: #eee; }
+";
+
+pub fn html(state: &RaceState, first_ms: Option<i64>, race_minutes: Option<f64>) -> String {
+    let races = plan(state, first_ms, race_minutes);
+    let mut boats: Vec<&str> = races.iter().flat_map(|r| r.pairings.iter().map(|p| p.boat_id.as_str())).collect::<BTreeSet<_>>().into_iter().collect();
+    boats.sort_by_key(|b| boat_order(b));
+
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Schedule</title><style>{STYLE}</style></head><body>\n<h1>Schedule</h1>\n"
+    );
+    let mut i = 0;
+    while i < races.len() {
+        let flight = races[i].flight;
+        out.push_str(&format!("<section>\n<h2>{}</h2>\n", escape(&flight.group_label)));
+        let (umpires, support) = (umpires(state, flight), support_boats(state, flight));
+        if !umpires.is_empty() || !support.is_empty() {
+            out.push_str(&format!(
+                "<p class=\"officials\">Umpires: {} &middot; Support: {}</p>\n",
+                escape(if umpires.is_empty() { "–" } else { &umpires }),
+                escape(if support.is_empty() { "–" } else { &support }),
+            ));
+        }
+        out.push_str("<table>\n<tr><th>Race</th><th>Start (UTC)</th>");
+        for boat in &boats {
+            out.push_str(&format!("<th>Boat {}</th>", escape(boat)));
+        }
+        out.push_str("</tr>\n");
+        while i < races.len() && races[i].flight.id == flight.id {
+            let race = &races[i];
+            let start = Utc.timestamp_millis_opt(race.start_ms).single().map(|t| t.format("%H:%M").to_string()).unwrap_or_default();
+            out.push_str(&format!("<tr><td>{}</td><td>{start}</td>", race.race_index + 1));
+            for boat in &boats {
+                let team = race.pairings.iter().find(|p| p.boat_id == *boat).map(|p| team_name(state, &p.team_id)).unwrap_or_default();
+                out.push_str(&format!("<td>{}</td>", escape(&team)));
+            }
+            out.push_str("</tr>\n");
+            i += 1;
+        }
+        out.push_str("</table>\n</section>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
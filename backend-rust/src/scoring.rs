@@ -0,0 +1,239 @@
+//! # scoring
+//!
+//! Low-point scoring (RRS Appendix A) and series standings.
+//!
+//! A race is closed by `close_race`: the current finishes and penalties are moved
+//! into a `RaceResult` and the standings are recomputed from every closed race.
+//!
+//! - A4.1 — a finisher scores its finishing place; boats with a scoring code do not
+//!   take a place, so boats behind an OCS/DSQ boat move up (A6.1)
+//! - A5.2 — DNC, DNS, OCS, DNF and DSQ score the number of series entries + 1
+//! - TLE (rule 35) scores per `TimeLimits::tle_scoring` (`"LAST+N"`: the last
+//!   finisher's place + N, default N = 1), never more than DNF
+//! - A2.1 — the worst scores are discarded per `ScoringSettings::discard_thresholds`
+//! - A8 — ties break on the best-to-worst list of counted scores, then on the last race
+//! - Handicap races place finishers in corrected-time order (`handicap`)
+//!
+//! Only penalties still on the record count (`Penalty::counts`): one taken on the
+//! water, cleared or withdrawn no longer scores.
+//!
+//! A tracked entry with neither a finish nor a penalty scores DNF; a series entry
+//! missing from a race altogether scores DNC.
+//!
+//! League teams whose boat broke down are flagged for redress (`flag_redress`,
+//! one flag per team and flight) and stay `PENDING` until the jury grants or
+//! denies it with `decide-redress`.
+
+use std::collections::BTreeSet;
+
+use crate::handicap;
+use crate::state::{
+    FinishRecord, Handicap, PenaltyType, RaceResult, RaceScore, RaceState, Redress, RedressStatus, ScoreCode,
+    ScoringSettings, SeriesStanding,
+};
+
+fn code_for(penalty: &PenaltyType) -> Option<ScoreCode> {
+    match penalty {
+        PenaltyType::Ocs => Some(ScoreCode::Ocs),
+        PenaltyType::Dsq | PenaltyType::UmpireDsq => Some(ScoreCode::Dsq),
+        PenaltyType::Dnf => Some(ScoreCode::Dnf),
+        PenaltyType::Dns => Some(ScoreCode::Dns),
+        PenaltyType::Tle => Some(ScoreCode::Tle),
+        PenaltyType::Turn360 | PenaltyType::UmpireNoAction | PenaltyType::UmpirePenalty => None,
+    }
+}
+
+/// N in `"LAST+N"`.
+fn tle_offset(tle_scoring: Option<&str>) -> u32 {
+    tle_scoring
+        .and_then(|s| s.trim().to_uppercase().strip_prefix("LAST+").and_then(|n| n.trim().parse().ok()))
+        .unwrap_or(1)
+}
+
+/// Record a finish for the current race. Returns false if the boat already finished.
+pub fn record_finish(state: &mut RaceState, boat_id: &str, finish_ms: i64, source: &str) -> bool {
+    if state.finishes.iter().any(|f| f.boat_id == boat_id) {
+        return false;
+    }
+    state.finishes.push(FinishRecord { boat_id: boat_id.to_string(), finish_ms, source: source.to_string() });
+    state.finishes.sort_by_key(|f| f.finish_ms);
+    true
+}
+
+/// Move the current race's finishes and penalties into the series and rescore.
+/// Closing again for the same start time (a late finish or penalty after the race
+/// was closed) adds to that race instead of creating a new one.
+pub fn close_race(state: &mut RaceState) -> &RaceResult {
+    let mut entries: BTreeSet<String> = state.boats.keys().cloned().collect();
+    entries.extend(state.finishes.iter().map(|f| f.boat_id.clone()));
+    entries.extend(state.penalties.iter().map(|p| p.boat_id.clone()));
+    let finishes = std::mem::take(&mut state.finishes);
+    let penalties = std::mem::take(&mut state.penalties);
+
+    let start_ms = state.start_time;
+    let existing = start_ms.and_then(|s| state.results.iter().position(|r| r.start_ms == Some(s)));
+    let index = match existing {
+        Some(i) => {
+            let race = &mut state.results[i];
+            entries.extend(race.entries.drain(..));
+            race.entries = entries.into_iter().collect();
+            for finish in finishes {
+                if !race.finishes.iter().any(|f| f.boat_id == finish.boat_id) {
+                    race.finishes.push(finish);
+                }
+            }
+            race.finishes.sort_by_key(|f| f.finish_ms);
+            race.penalties.extend(penalties);
+            i
+        }
+        None => {
+            state.results.push(RaceResult {
+                race_number: state.results.len() as u32 + 1,
+                start_ms,
+                entries: entries.into_iter().collect(),
+                finishes,
+                penalties,
+                handicap: state.scoring.handicap.clone(),
+                corrected: Vec::new(),
+            });
+            state.results.len() - 1
+        }
+    };
+    // Protests about the race in progress now belong to this race
+    let race_number = state.results[index].race_number;
+    for protest in state.protests.iter_mut().filter(|p| p.race_number.is_none()) {
+        protest.race_number = Some(race_number);
+    }
+    rescore(state);
+    &state.results[index]
+}
+
+/// Flag a team for redress in a flight; a team already flagged there keeps its flag.
+pub fn flag_redress(state: &mut RaceState, team_id: &str, flight_id: &str, race_index: u32, boat_id: &str, reason: &str, now_ms: i64) -> Redress {
+    if let Some(existing) = state.redress.iter().find(|r| r.team_id == team_id && r.flight_id == flight_id) {
+        return existing.clone();
+    }
+    let redress = Redress {
+        id: uuid::Uuid::new_v4().to_string(),
+        team_id: team_id.to_string(),
+        flight_id: flight_id.to_string(),
+        race_index,
+        boat_id: boat_id.to_string(),
+        reason: reason.to_string(),
+        flagged_ms: now_ms,
+        status: RedressStatus::Pending,
+    };
+    state.redress.push(redress.clone());
+    redress
+}
+
+/// Record the jury's decision on a redress flag. None if there is no such flag.
+pub fn decide_redress(state: &mut RaceState, redress_id: &str, status: RedressStatus) -> Option<Redress> {
+    let redress = state.redress.iter_mut().find(|r| r.id == redress_id)?;
+    redress.status = status;
+    Some(redress.clone())
+}
+
+pub fn rescore(state: &mut RaceState) {
+    for race in &mut state.results {
+        race.corrected = handicap::correct(race, &state.entries);
+    }
+    state.standings = standings(&state.results, &state.scoring, state.time_limits.tle_scoring.as_deref());
+}
+
+/// Score one race for the given series entries.
+fn score_race(race: &RaceResult, series_entries: &[String], tle_scoring: Option<&str>) -> Vec<(String, RaceScore)> {
+    let penalty_points = series_entries.len() as f64 + 1.0;
+    // Worst code wins when a boat has several
+    let code = |boat: &str| race.penalties.iter()
+        .filter(|p| p.boat_id == boat && p.counts())
+        .filter_map(|p| code_for(&p.penalty_type))
+        .max();
+
+    let finish_order: Vec<&str> = match race.handicap {
+        Handicap::Scratch => race.finishes.iter().map(|f| f.boat_id.as_str()).collect(),
+        _ => race.corrected.iter().map(|c| c.boat_id.as_str()).collect(),
+    };
+    let places: Vec<&str> = finish_order.into_iter()
+        .filter(|b| code(b).is_none())
+        .collect();
+    let tle_points = (places.len() as u32 + tle_offset(tle_scoring)) as f64;
+
+    series_entries.iter().map(|boat| {
+        let place = places.iter().position(|b| b == boat).map(|i| i as u32 + 1);
+        let code = code(boat).or_else(|| match place {
+            Some(_) => None,
+            None if race.entries.contains(boat) => Some(ScoreCode::Dnf),
+            None => Some(ScoreCode::Dnc),
+        });
+        let points = match (place, code) {
+            (Some(p), None) => p as f64,
+            (_, Some(ScoreCode::Tle)) => tle_points.min(penalty_points),
+            _ => penalty_points,
+        };
+        (boat.clone(), RaceScore { race_number: race.race_number, place, code, points, discarded: false })
+    }).collect()
+}
+
+/// Series standings over all closed races.
+pub fn standings(results: &[RaceResult], settings: &ScoringSettings, tle_scoring: Option<&str>) -> Vec<SeriesStanding> {
+    let series_entries: Vec<String> = results.iter()
+        .flat_map(|r| r.entries.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let discards = settings.discard_thresholds.iter().filter(|&&t| results.len() as u32 >= t).count();
+
+    let mut table: Vec<SeriesStanding> = series_entries.iter().map(|boat| SeriesStanding {
+        rank: 0,
+        boat_id: boat.clone(),
+        total: 0.0,
+        net: 0.0,
+        races: Vec::with_capacity(results.len()),
+    }).collect();
+    for race in results {
+        for (boat, score) in score_race(race, &series_entries, tle_scoring) {
+            if let Some(row) = table.iter_mut().find(|r| r.boat_id == boat) {
+                row.races.push(score);
+            }
+        }
+    }
+
+    for row in &mut table {
+        // Discard the worst scores; on equal points the earlier race is kept
+        let mut worst: Vec<usize> = (0..row.races.len()).collect();
+        worst.sort_by(|&a, &b| row.races[b].points.total_cmp(&row.races[a].points).then(b.cmp(&a)));
+        for &i in worst.iter().take(discards.min(row.races.len().saturating_sub(1))) {
+            row.races[i].discarded = true;
+        }
+        row.total = row.races.iter().map(|r| r.points).sum();
+        row.net = row.races.iter().filter(|r| !r.discarded).map(|r| r.points).sum();
+    }
+
+    table.sort_by(|a, b| a.net.total_cmp(&b.net).then_with(|| tie_break(a, b)));
+    for (i, row) in table.iter_mut().enumerate() {
+        row.rank = i as u32 + 1;
+    }
+    table
+}
+
+/// A8.1 best-to-worst counted scores, then A8.2 last race backwards.
+fn tie_break(a: &SeriesStanding, b: &SeriesStanding) -> std::cmp::Ordering {
+    let counted = |s: &SeriesStanding| {
+        let mut pts: Vec<f64> = s.races.iter().filter(|r| !r.discarded).map(|r| r.points).collect();
+        pts.sort_by(f64::total_cmp);
+        pts
+    };
+    let (ca, cb) = (counted(a), counted(b));
+    for (x, y) in ca.iter().zip(&cb) {
+        if x != y {
+            return x.total_cmp(y);
+        }
+    }
+    for (x, y) in a.races.iter().rev().zip(b.races.iter().rev()) {
+        if x.points != y.points {
+            return x.points.total_cmp(&y.points);
+        }
+    }
+    std::cmp::Ordering::Equal
+}
@@ -0,0 +1,130 @@
+//! # sessions
+//!
+//! Expiry of a socket's authentication.
+//!
+//! A client registered with a Supabase token holds its role only until the
+//! token's `exp`. Before that it sends a fresh token:
+//!
+//! - `refresh-token { token }` → `token-refreshed { expiresMs }`, or
+//!   `auth-error { error }` (invalid token, or one for another user)
+//!
+//! `SESSION_WARN_SECS` (default 60) before expiry the client gets
+//! `session-expiring { expiresMs }`. Once expired it gets `session-expired` and is
+//! disconnected, and has to `register` again. While a start sequence runs
+//! (fleet or class), expiry is held off for up to `SESSION_GRACE_SECS` (default
+//! 300) so no one loses the start — the client is told with
+//! `session-expiring { expiresMs, graceUntilMs }`.
+//!
+//! Legacy role strings carry no expiry and are not affected.
+//!
+//! ## Invariants
+//! - Core Invariant #2: every forced expiry is a session event in the audit chain
+
+use std::time::Duration;
+
+use serde_json::json;
+use socketioxide::SocketIo;
+use tracing::info;
+
+use crate::audit::AuditLogger;
+use crate::auth::AuthEngine;
+use crate::handlers::{now_ms, ClassEngines, SharedEngine};
+use crate::roles;
+
+const TICK: Duration = Duration::from_secs(5);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("Invalid or expired token")]
+    InvalidToken,
+    #[error("Token belongs to another user")]
+    OtherUser,
+    #[error("Not registered with a token")]
+    NoSession,
+}
+
+/// A token-backed registration.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Token subject; a refresh must be for the same user
+    pub sub: String,
+    pub expires_ms: i64,
+    /// `session-expiring` already sent for this expiry
+    pub warned: bool,
+    /// Expiry held off until then by a running sequence
+    pub grace_until_ms: Option<i64>,
+}
+
+impl Session {
+    pub fn new(sub: String, exp_secs: u64) -> Self {
+        Self { sub, expires_ms: exp_secs as i64 * 1000, warned: false, grace_until_ms: None }
+    }
+}
+
+pub struct SessionConfig {
+    pub warn_ms: i64,
+    pub grace_ms: i64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        let secs = |name: &str, default: i64| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            warn_ms: secs("SESSION_WARN_SECS", 60) * 1000,
+            grace_ms: secs("SESSION_GRACE_SECS", 300) * 1000,
+        }
+    }
+}
+
+pub async fn run_session_expiry(
+    config: SessionConfig,
+    auth: std::sync::Arc<AuthEngine>,
+    engine: SharedEngine,
+    class_engines: ClassEngines,
+    io: SocketIo,
+    audit: AuditLogger,
+) {
+    let mut ticker = tokio::time::interval(TICK);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        let now = now_ms();
+        let sequence_running = engine.read().await.is_running() || !class_engines.read().await.is_empty();
+
+        for (sid, session) in auth.sessions().await {
+            let room = roles::client_room(&sid);
+            if now < session.expires_ms {
+                if !session.warned && session.expires_ms - now <= config.warn_ms {
+                    auth.update_session(&sid, |s| s.warned = true).await;
+                    let _ = io.to(room).emit("session-expiring", &json!({ "expiresMs": session.expires_ms }));
+                }
+                continue;
+            }
+
+            if sequence_running {
+                let grace_until = session.expires_ms + config.grace_ms;
+                if now < grace_until {
+                    if session.grace_until_ms.is_none() {
+                        auth.update_session(&sid, |s| s.grace_until_ms = Some(grace_until)).await;
+                        let _ = io.to(room).emit("session-expiring", &json!({ "expiresMs": session.expires_ms, "graceUntilMs": grace_until }));
+                        info!("Session {sid}: token expired during a sequence, grace until {grace_until}");
+                    }
+                    continue;
+                }
+            }
+
+            let role = auth.get_role(&sid).await;
+            info!("Session {sid}: token expired, disconnecting ({})", role.as_deref().unwrap_or("no role"));
+            let _ = io.to(room.clone()).emit("session-expired", &json!({ "expiresMs": session.expires_ms }));
+            let _ = io.to(room).disconnect();
+            auth.end_session(&sid).await;
+            auth.remove_role(&sid).await;
+            audit.log_session_event("session_expired", Some(json!({
+                "socketId": sid,
+                "sub": session.sub,
+                "role": role,
+                "expiresMs": session.expires_ms,
+            }))).await;
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! # signal_outputs
+//!
+//! Drives external horn and flag actuators from procedure signals.
+//!
+//! The output task subscribes to the `EngineBus`. Every `SoundFired` and
+//! `FlagsChanged` event becomes a `SignalCommand` (the node's sound, or the flags
+//! raised/lowered since the previous change for that sequence) and is fanned out
+//! to every configured adapter:
+//!
+//! - `SIGNAL_WEBHOOK_URL` — POST the command as JSON to a horn controller
+//! - `SIGNAL_MQTT_BROKER` (`host:port`) + `SIGNAL_MQTT_TOPIC` (default `regatta/signals`) —
+//!   publish the command JSON (MQTT 3.1.1, QoS 0)
+//! - `SIGNAL_GPIO_HORN` — sysfs GPIO value file (e.g. `/sys/class/gpio/gpio17/value`)
+//!   pulsed for the sound pattern (short 1 s, long 3 s, 1 s gaps)
+//! - `SIGNAL_GPIO_FLAGS` — `"P=/sys/class/gpio/gpio22/value,X=..."` relays held
+//!   high while the flag is displayed
+//!
+//! Each adapter is retried up to `SIGNAL_RETRIES` times (default 3); webhook calls
+//! and MQTT publishes time out after 5 s. A command's adapters run detached, so a
+//! hung endpoint never holds back the next horn or flag. Every attempt outcome is
+//! written to the audit chain as `SIGNAL_ACTUATION` with the commanded time, the
+//! actual actuation time and the delay between them.
+//!
+//! ## Invariants
+//! - Core Invariant #2: the actual horn time is on the chain next to the commanded one
+//! - Core Invariant #8: the tick loops never wait on an actuator; a lagging output
+//!   task loses the oldest events (logged), never a tick
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::audit::AuditLogger;
+use crate::engine_bus::{self, EngineEventEnvelope};
+use crate::procedure_engine::EngineEvent;
+use crate::state::SoundSignal;
+
+const SHORT_SOUND: Duration = Duration::from_secs(1);
+const LONG_SOUND: Duration = Duration::from_secs(3);
+const SOUND_GAP: Duration = Duration::from_secs(1);
+const WEBHOOK_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+const MQTT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ── Configuration ─────────────────────────────────────────────────────────────
+
+pub struct SignalOutputConfig {
+    pub webhook_url: Option<String>,
+    pub mqtt_broker: Option<String>,
+    pub mqtt_topic: String,
+    pub gpio_horn: Option<String>,
+    pub gpio_flags: HashMap<String, String>,
+    pub retries: u32,
+}
+
+impl Default for SignalOutputConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        Self {
+            webhook_url: var("SIGNAL_WEBHOOK_URL"),
+            mqtt_broker: var("SIGNAL_MQTT_BROKER"),
+            mqtt_topic: var("SIGNAL_MQTT_TOPIC").unwrap_or_else(|| "regatta/signals".to_string()),
+            gpio_horn: var("SIGNAL_GPIO_HORN"),
+            gpio_flags: var("SIGNAL_GPIO_FLAGS").unwrap_or_default()
+                .split(',')
+                .filter_map(|pair| pair.split_once('=').map(|(f, p)| (f.trim().to_string(), p.trim().to_string())))
+                .collect(),
+            retries: var("SIGNAL_RETRIES").and_then(|v| v.parse().ok()).filter(|&n| n > 0).unwrap_or(3),
+        }
+    }
+}
+
+impl SignalOutputConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.webhook_url.is_some() || self.mqtt_broker.is_some() || self.gpio_horn.is_some() || !self.gpio_flags.is_empty()
+    }
+}
+
+// ── Commands ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalCommand {
+    /// "main" or the class id of a concurrent class sequence
+    pub source: String,
+    pub node_id: String,
+    pub event: String,
+    pub sound: SoundSignal,
+    pub flags_raised: Vec<String>,
+    pub flags_lowered: Vec<String>,
+    pub commanded_ms: u64,
+}
+
+/// Start the output task on the engine bus. Does nothing when no adapter is configured.
+pub fn spawn(config: SignalOutputConfig, audit: AuditLogger, rx: broadcast::Receiver<EngineEventEnvelope>) {
+    if !config.is_enabled() {
+        return;
+    }
+    info!(
+        "SignalOutputs: webhook={} mqtt={} horn={} flags={:?}",
+        config.webhook_url.as_deref().unwrap_or("-"),
+        config.mqtt_broker.as_deref().unwrap_or("-"),
+        config.gpio_horn.as_deref().unwrap_or("-"),
+        config.gpio_flags.keys().collect::<Vec<_>>(),
+    );
+    tokio::spawn(run_outputs(config, audit, rx));
+}
+
+async fn run_outputs(config: SignalOutputConfig, audit: AuditLogger, mut rx: broadcast::Receiver<EngineEventEnvelope>) {
+    let client = reqwest::Client::builder()
+        .connect_timeout(WEBHOOK_CONNECT_TIMEOUT)
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    // source → node label of the current node
+    let mut labels: HashMap<String, String> = HashMap::new();
+    // source → displayed flags
+    let mut displayed: HashMap<String, Vec<String>> = HashMap::new();
+
+    while let Some(envelope) = engine_bus::next_event(&mut rx, "signal outputs").await {
+        let source = envelope.source;
+        let command = match envelope.event {
+            EngineEvent::NodeEntered { label, .. } => {
+                labels.insert(source, label);
+                continue;
+            }
+            EngineEvent::SoundFired { node_id, sound } => SignalCommand {
+                event: labels.get(&source).cloned().unwrap_or_default(),
+                source,
+                node_id,
+                sound,
+                flags_raised: Vec::new(),
+                flags_lowered: Vec::new(),
+                commanded_ms: envelope.at_ms,
+            },
+            EngineEvent::FlagsChanged { node_id, flags } => {
+                let prev_flags = displayed.insert(source.clone(), flags.clone()).unwrap_or_default();
+                SignalCommand {
+                    event: labels.get(&source).cloned().unwrap_or_default(),
+                    source,
+                    node_id,
+                    sound: SoundSignal::None,
+                    flags_raised: flags.iter().filter(|f| !prev_flags.contains(f)).cloned().collect(),
+                    flags_lowered: prev_flags.iter().filter(|f| !flags.contains(f)).cloned().collect(),
+                    commanded_ms: envelope.at_ms,
+                }
+            }
+            _ => continue,
+        };
+        if command.sound == SoundSignal::None && command.flags_raised.is_empty() && command.flags_lowered.is_empty() {
+            continue;
+        }
+
+        // Actuators run concurrently and detached: a slow webhook never delays the horn
+        // relay, nor the next command
+        if let Some(url) = config.webhook_url.clone() {
+            let (client, cmd, audit, retries) = (client.clone(), command.clone(), audit.clone(), config.retries);
+            tokio::spawn(async move {
+                actuate(&audit, &cmd, "webhook", retries, || async {
+                    client.post(&url).json(&cmd).send().await
+                        .and_then(|r| r.error_for_status())
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                }).await
+            });
+        }
+        if let Some(broker) = config.mqtt_broker.clone() {
+            let (topic, cmd, audit, retries) = (config.mqtt_topic.clone(), command.clone(), audit.clone(), config.retries);
+            tokio::spawn(async move {
+                let payload = serde_json::to_vec(&cmd).unwrap_or_default();
+                actuate(&audit, &cmd, "mqtt", retries, || async {
+                    tokio::time::timeout(MQTT_TIMEOUT, mqtt_publish(&broker, &topic, &payload)).await
+                        .map_err(|_| "publish timed out".to_string())?
+                }).await
+            });
+        }
+        if let Some(path) = config.gpio_horn.clone() {
+            if command.sound != SoundSignal::None {
+                let (cmd, audit, retries) = (command.clone(), audit.clone(), config.retries);
+                tokio::spawn(async move {
+                    actuate(&audit, &cmd, "gpio-horn", retries, || sound_horn(&path, &cmd.sound)).await
+                });
+            }
+        }
+        for (flag, raised) in command.flags_raised.iter().map(|f| (f, true)).chain(command.flags_lowered.iter().map(|f| (f, false))) {
+            if let Some(path) = config.gpio_flags.get(flag).cloned() {
+                let (cmd, audit, retries) = (command.clone(), audit.clone(), config.retries);
+                let adapter = format!("gpio-flag-{flag}");
+                tokio::spawn(async move {
+                    actuate(&audit, &cmd, &adapter, retries, || gpio_write(&path, raised)).await
+                });
+            }
+        }
+    }
+}
+
+/// Run `attempt` up to `retries` times and audit the outcome.
+async fn actuate<F, Fut>(audit: &AuditLogger, command: &SignalCommand, adapter: &str, retries: u32, mut attempt: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    let mut last_error = None;
+    for n in 1..=retries {
+        let started_ms = crate::time_discipline::now_ms();
+        match attempt().await {
+            Ok(()) => {
+                audit.log_signal_actuation(json!({
+                    "adapter": adapter,
+                    "command": command,
+                    "actuatedMs": started_ms,
+                    "delayMs": started_ms as i64 - command.commanded_ms as i64,
+                    "attempts": n,
+                    "ok": true,
+                })).await;
+                return;
+            }
+            Err(e) => {
+                warn!("SignalOutputs: {adapter} attempt {n}/{retries} failed: {e}");
+                last_error = Some(e);
+                tokio::time::sleep(Duration::from_millis(200 * n as u64)).await;
+            }
+        }
+    }
+    audit.log_signal_actuation(json!({
+        "adapter": adapter,
+        "command": command,
+        "attempts": retries,
+        "ok": false,
+        "error": last_error,
+    })).await;
+}
+
+// ── Adapters ──────────────────────────────────────────────────────────────────
+
+async fn gpio_write(path: &str, high: bool) -> Result<(), String> {
+    tokio::fs::write(path, if high { "1" } else { "0" }).await.map_err(|e| e.to_string())
+}
+
+/// Pulse the horn relay for the sound pattern. Only the first edge counts as the
+/// actuation time, so a failure there is retried; later pulses are best-effort.
+async fn sound_horn(path: &str, sound: &SoundSignal) -> Result<(), String> {
+    let pulses: &[Duration] = match sound {
+        SoundSignal::None => return Ok(()),
+        SoundSignal::OneShort => &[SHORT_SOUND],
+        SoundSignal::OneLong => &[LONG_SOUND],
+        SoundSignal::TwoShort => &[SHORT_SOUND, SHORT_SOUND],
+        SoundSignal::ThreeShort => &[SHORT_SOUND, SHORT_SOUND, SHORT_SOUND],
+    };
+    gpio_write(path, true).await?;
+    let path = path.to_string();
+    let pulses = pulses.to_vec();
+    tokio::spawn(async move {
+        for (i, on) in pulses.iter().enumerate() {
+            if i > 0 {
+                let _ = gpio_write(&path, true).await;
+            }
+            tokio::time::sleep(*on).await;
+            let _ = gpio_write(&path, false).await;
+            tokio::time::sleep(SOUND_GAP).await;
+        }
+    });
+    Ok(())
+}
+
+/// Minimal MQTT 3.1.1 CONNECT → PUBLISH (QoS 0) → DISCONNECT.
+async fn mqtt_publish(broker: &str, topic: &str, payload: &[u8]) -> Result<(), String> {
+    let io_err = |e: std::io::Error| e.to_string();
+    let mut stream = tokio::time::timeout(Duration::from_secs(3), tokio::net::TcpStream::connect(broker)).await
+        .map_err(|_| "connect timed out".to_string())?
+        .map_err(io_err)?;
+
+    let client_id = format!("regatta-{}", uuid::Uuid::new_v4().simple());
+    let mut connect = vec![0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 0x3c];
+    connect.extend(mqtt_string(&client_id));
+    stream.write_all(&mqtt_packet(0x10, &connect)).await.map_err(io_err)?;
+
+    let mut connack = [0u8; 4];
+    tokio::time::timeout(Duration::from_secs(3), stream.read_exact(&mut connack)).await
+        .map_err(|_| "CONNACK timed out".to_string())?
+        .map_err(io_err)?;
+    if connack[0] != 0x20 || connack[3] != 0 {
+        return Err(format!("broker refused connection (code {})", connack[3]));
+    }
+
+    let mut publish = mqtt_string(topic);
+    publish.extend_from_slice(payload);
+    stream.write_all(&mqtt_packet(0x30, &publish)).await.map_err(io_err)?;
+    stream.write_all(&[0xe0, 0x00]).await.map_err(io_err)?;
+    Ok(())
+}
+
+fn mqtt_string(s: &str) -> Vec<u8> {
+    let mut out = (s.len() as u16).to_be_bytes().to_vec();
+    out.extend_from_slice(s.as_bytes());
+    out
+}
+
+fn mqtt_packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(body);
+    out
+}
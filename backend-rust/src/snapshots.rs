@@ -0,0 +1,167 @@
+//! # snapshots
+//!
+//! Point-in-time copies of the persistent state, so an accidental `clear-fleet`
+//! or a bad course edit minutes before a start can be rolled back.
+//!
+//! - Automatic: every `SNAPSHOT_INTERVAL_SECS` (default 300) when anything
+//!   persistent changed; the newest `SNAPSHOT_KEEP` (default 48) are kept
+//! - Manual: `take-snapshot { name? }`, never pruned
+//! - `list-snapshots` → `snapshots` (newest first, without the state)
+//! - `restore-snapshot { id }` puts back the course, entries, procedures, results,
+//!   league schedule and the other fields in `restore_into`. Live race data
+//!   (boats, running sequence, penalties, logs) stays as it is. A `pre-restore`
+//!   snapshot is taken first, so a restore can itself be undone
+//!
+//! Snapshots live in the `state_store` next to the state: an index document plus
+//! one document per snapshot.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::handlers::{now_ms, SharedState};
+use crate::persistence::saveable;
+use crate::state::RaceState;
+use crate::state_schema;
+use crate::state_store::store;
+
+const INDEX_KEY: &str = "snapshot_index";
+
+pub struct SnapshotConfig {
+    pub interval: Duration,
+    pub keep: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        let num = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<u64>().ok()).filter(|v| *v > 0);
+        Self {
+            interval: Duration::from_secs(num("SNAPSHOT_INTERVAL_SECS").unwrap_or(300)),
+            keep: num("SNAPSHOT_KEEP").unwrap_or(48) as usize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub name: String,
+    pub created_ms: i64,
+    /// Taken by the periodic task (prunable) rather than on request
+    pub auto: bool,
+}
+
+fn key(id: &str) -> String {
+    format!("snapshot-{id}")
+}
+
+/// Every snapshot, newest first.
+pub async fn list() -> Vec<SnapshotInfo> {
+    let mut index: Vec<SnapshotInfo> = match store().load(INDEX_KEY).await {
+        Ok(Some(data)) => serde_json::from_str(&data).unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    index.sort_by_key(|s| std::cmp::Reverse(s.created_ms));
+    index
+}
+
+async fn save_index(index: &[SnapshotInfo]) -> anyhow::Result<()> {
+    store().save(INDEX_KEY, &serde_json::to_string(index)?).await
+}
+
+/// Store a snapshot of `state` and prune old automatic ones beyond `keep`.
+pub async fn take(state: &RaceState, name: &str, auto: bool, keep: usize) -> anyhow::Result<SnapshotInfo> {
+    let created_ms = now_ms();
+    let info = SnapshotInfo { id: created_ms.to_string(), name: name.to_string(), created_ms, auto };
+    store().save(&key(&info.id), &serde_json::to_string(&saveable(state))?).await?;
+
+    let mut index = list().await;
+    index.retain(|s| s.id != info.id);
+    index.insert(0, info.clone());
+    let mut autos = 0;
+    let mut pruned = Vec::new();
+    index.retain(|s| {
+        if !s.auto {
+            return true;
+        }
+        autos += 1;
+        if autos <= keep {
+            return true;
+        }
+        pruned.push(s.id.clone());
+        false
+    });
+    save_index(&index).await?;
+    for id in pruned {
+        let _ = store().remove(&key(&id)).await;
+    }
+    Ok(info)
+}
+
+pub async fn load(id: &str) -> anyhow::Result<RaceState> {
+    let data = store().load(&key(id)).await?.ok_or_else(|| anyhow::anyhow!("Unknown snapshot: {id}"))?;
+    Ok(state_schema::upgrade(serde_json::from_str(&data)?, &format!("Snapshot {id}"))?)
+}
+
+/// Put the persistent fields of `snapshot` back into `state`.
+pub fn restore_into(state: &mut RaceState, snapshot: RaceState) {
+    state.course = snapshot.course;
+    state.active_course_order = snapshot.active_course_order;
+    state.default_location = snapshot.default_location;
+    state.time_limits = snapshot.time_limits;
+    state.procedure_templates = snapshot.procedure_templates;
+    state.pursuit = snapshot.pursuit;
+    state.entries = snapshot.entries;
+    state.boat_classes = snapshot.boat_classes;
+    state.results = snapshot.results;
+    state.scoring = snapshot.scoring;
+    state.standings = snapshot.standings;
+    state.auto_director = snapshot.auto_director;
+    state.director_overrides = snapshot.director_overrides;
+    state.highlights = snapshot.highlights;
+    state.protests = snapshot.protests;
+    state.races = snapshot.races;
+    state.active_race_id = snapshot.active_race_id;
+    state.fleet_settings = snapshot.fleet_settings;
+    state.teams = snapshot.teams;
+    state.boat_profiles = snapshot.boat_profiles;
+    state.flights = snapshot.flights;
+    state.pairings = snapshot.pairings;
+    state.active_flight_id = snapshot.active_flight_id;
+    state.redress = snapshot.redress;
+    state.league_results = snapshot.league_results;
+    state.league_standings = snapshot.league_standings;
+    state.schedule_constraints = snapshot.schedule_constraints;
+    state.officials = snapshot.officials;
+    state.knockout = snapshot.knockout;
+    state.blacklist = snapshot.blacklist;
+    // The running procedure keeps its graph; only an idle race gets the saved one back
+    if state.current_sequence.is_none() {
+        state.current_procedure = snapshot.current_procedure;
+    }
+}
+
+pub async fn run_snapshots(config: SnapshotConfig, shared: SharedState) {
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    ticker.tick().await;
+    let mut last = None;
+    loop {
+        ticker.tick().await;
+        let state = saveable(&*shared.read().await);
+        // Logs and weather move on their own; only a real edit is worth a snapshot
+        let fingerprint = serde_json::to_string(&RaceState { logs: Vec::new(), weather: None, weather_history: Vec::new(), ..state.clone() }).ok();
+        if fingerprint == last {
+            continue;
+        }
+        match take(&state, "auto", true, config.keep).await {
+            Ok(info) => {
+                info!("Snapshot {} taken", info.id);
+                last = fingerprint;
+            }
+            Err(e) => warn!("Snapshot failed: {e}"),
+        }
+    }
+}
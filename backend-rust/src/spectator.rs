@@ -0,0 +1,103 @@
+//! # spectator
+//!
+//! Public tracking on its own Socket.IO namespace, `/spectate`, apart from the
+//! director's channel.
+//!
+//! - No token: any client may connect; nothing it emits is handled
+//! - `spectator-init` on connect, then `spectator-update` every
+//!   `SPECTATE_INTERVAL_MS` (default 1000): race status, countdown, wind, course and
+//!   boat positions with their sail numbers and names
+//! - `spectator-results` whenever the standings change
+//!
+//! Payloads are built from an allow-list, so logs, jury notes, protests, audit data
+//! and tracker simulation settings never reach the namespace.
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use socketioxide::extract::SocketRef;
+use socketioxide::SocketIo;
+
+use crate::entries;
+use crate::handlers::SharedState;
+use crate::state::RaceState;
+
+pub const NAMESPACE: &str = "/spectate";
+
+pub struct SpectatorConfig {
+    pub interval: Duration,
+}
+
+impl Default for SpectatorConfig {
+    fn default() -> Self {
+        let ms = std::env::var("SPECTATE_INTERVAL_MS").ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v >= 100)
+            .unwrap_or(1000);
+        Self { interval: Duration::from_millis(ms) }
+    }
+}
+
+fn snapshot(state: &RaceState) -> Value {
+    let boats: Vec<Value> = state.boats.values().map(|b| {
+        let entry = entries::resolve(state, &b.boat_id);
+        json!({
+            "boatId": b.boat_id,
+            "sailNumber": entry.map(|e| e.sail_number.as_str()),
+            "boatName": entry.map(|e| e.boat_name.as_str()),
+            "classId": entry.and_then(|e| e.class_id.as_deref()),
+            "pos": b.pos,
+            "heading": b.imu.heading,
+            "speed": b.velocity.speed,
+            "rank": b.rank,
+            "legIndex": b.leg_index,
+            "lap": b.lap,
+            "status": b.status,
+        })
+    }).collect();
+    json!({
+        "status": state.status,
+        "startTime": state.start_time,
+        "sequenceTimeRemaining": state.sequence_time_remaining,
+        "prepFlag": state.prep_flag,
+        "wind": state.wind,
+        "course": state.course,
+        "courseOrder": state.active_course_order,
+        "boats": boats,
+    })
+}
+
+fn results(state: &RaceState) -> Value {
+    let races: Vec<Value> = state.results.iter().map(|r| json!({
+        "raceNumber": r.race_number,
+        "startMs": r.start_ms,
+        "corrected": r.corrected,
+    })).collect();
+    json!({ "standings": state.standings, "races": races })
+}
+
+pub async fn on_connect(socket: SocketRef, shared: SharedState) {
+    let state = shared.read().await;
+    let _ = socket.emit("spectator-init", &json!({ "state": snapshot(&state), "results": results(&state) }));
+}
+
+pub async fn run_spectator_feed(config: SpectatorConfig, shared: SharedState, io: SocketIo) {
+    let mut interval = tokio::time::interval(config.interval);
+    let mut last_results = Value::Null;
+    loop {
+        interval.tick().await;
+        let (update, results) = {
+            let state = shared.read().await;
+            (snapshot(&state), results(&state))
+        };
+        if let Some(ns) = io.of(NAMESPACE) {
+            let _ = ns.emit("spectator-update", &update);
+        }
+        if results != last_results {
+            if let Some(ns) = io.of(NAMESPACE) {
+                let _ = ns.emit("spectator-results", &results);
+            }
+            last_results = results;
+        }
+    }
+}
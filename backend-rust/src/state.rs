@@ -3,7 +3,7 @@ use std::collections::HashMap;
 
 // ─── Geographic Types ────────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct LatLon {
     pub lat: f64,
     pub lon: f64,
@@ -171,6 +171,34 @@ pub struct CourseState {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub course_boundary: Option<Vec<LatLon>>,
     pub settings: CourseSettings,
+    /// Computed server-side from the wind (see `laylines`); ignored on input
+    #[serde(default)]
+    pub laylines: Vec<MarkLaylines>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LaylineLeg {
+    Upwind,
+    Downwind,
+}
+
+/// Port and starboard laylines into one rounding mark, each running from the
+/// mark to the given end point.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkLaylines {
+    pub mark_id: String,
+    /// Set when the class sails its own angles
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_id: Option<String>,
+    pub leg: LaylineLeg,
+    /// True wind angle the laylines are sailed at
+    pub twa: f64,
+    pub port_bearing: f64,
+    pub port_end: LatLon,
+    pub starboard_bearing: f64,
+    pub starboard_end: LatLon,
 }
 impl Default for CourseSettings {
     fn default() -> Self {
@@ -210,6 +238,25 @@ pub struct WeatherReport {
     pub provider: WeatherProvider,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CurrentSource {
+    Manual,
+    Openmeteo,
+}
+
+/// Tidal stream on the course.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentState {
+    /// Direction the water flows towards, degrees true
+    pub set: f64,
+    /// Knots
+    pub drift: f64,
+    pub source: CurrentSource,
+    pub updated_ms: i64,
+}
+
 // ─── Boat Telemetry ───────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -253,6 +300,63 @@ pub struct BoatState {
     pub dtf_m: f64,
     #[serde(default)]
     pub rank: u32,
+    #[serde(default)]
+    pub lap: u32,
+    // Liveness (server receive time, not the tracker's clock)
+    #[serde(default)]
+    pub last_seen_ms: i64,
+    #[serde(default)]
+    pub status: BoatStatus,
+    // Start-line prediction while a gun is pending (see `tide`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_prediction: Option<LinePrediction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LinePrediction {
+    /// Signed distance to the start line, positive = course side (m)
+    pub dtl_m: f64,
+    /// Seconds to the line at the current velocity over ground; None when not closing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_line_s: Option<f64>,
+    /// Distance to the line at the gun, holding the current velocity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dtl_at_gun_m: Option<f64>,
+    /// Distance to the line at the gun if the boat stops sailing and only drifts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift_dtl_at_gun_m: Option<f64>,
+    /// Seconds to the line sailing close-hauled at the class's target speed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_time_to_line_s: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BoatStatus {
+    #[default]
+    Live,
+    /// Silent for longer than `BOAT_STALE_SECS`; position is the last known one
+    Stale,
+    /// Silent for longer than `BOAT_OFFLINE_SECS`
+    Offline,
+}
+
+/// `mark-rounded` payload, also kept in `RaceState::mark_roundings`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkRounding {
+    pub boat_id: String,
+    pub element_id: String,
+    pub element_name: String,
+    /// Side the mark was left on; None for lines and gates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Rounding>,
+    pub correct: bool,
+    /// Leg the boat is on after this rounding (unchanged if incorrect)
+    pub leg_index: u32,
+    pub lap: u32,
+    pub timestamp: i64,
 }
 
 // ─── Penalty (RRS + Appendix UF) ─────────────────────────────────────────────
@@ -271,16 +375,525 @@ pub enum PenaltyType {
     UmpireDsq,          // Umpire: Black flag DSQ
 }
 
+/// Where a penalty stands. Only `Pending` and `UnderProtest` count in scoring.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PenaltyStatus {
+    #[default]
+    Pending,
+    TakenOnWater,   // Turn(s) done on the water (RRS 44.2 / UF)
+    Cleared,
+    UnderProtest,
+    Withdrawn,
+}
+
+fn penalty_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Penalty {
+    /// Penalties stored before ids existed get one on load
+    #[serde(default = "penalty_id")]
+    pub id: String,
     pub boat_id: String,
     #[serde(rename = "type")]
     pub penalty_type: PenaltyType,
     pub timestamp: i64,
+    #[serde(default)]
+    pub status: PenaltyStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_ms: Option<i64>,
+}
+
+impl Penalty {
+    pub fn new(boat_id: String, penalty_type: PenaltyType, timestamp: i64) -> Self {
+        Self {
+            id: penalty_id(),
+            boat_id,
+            penalty_type,
+            timestamp,
+            status: PenaltyStatus::Pending,
+            note: None,
+            updated_ms: None,
+        }
+    }
+
+    /// Still on the record: scored and exported
+    pub fn counts(&self) -> bool {
+        matches!(self.status, PenaltyStatus::Pending | PenaltyStatus::UnderProtest)
+    }
+}
+
+// ─── Finishes & Scoring (RRS Appendix A) ─────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishRecord {
+    pub boat_id: String,
+    pub finish_ms: i64,
+    /// "tracking" (finish line crossing) or "manual" (RC spotted)
+    pub source: String,
+}
+
+/// Scoring abbreviations (RRS A10) that replace a finishing place.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ScoreCode {
+    Dnc,
+    Dns,
+    Ocs,
+    Dnf,
+    Tle,
+    Dsq,
+}
+
+/// One closed race: raw finishes and penalties, scored by `scoring::standings`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaceResult {
+    pub race_number: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_ms: Option<i64>,
+    /// Boats that took part (tracked, finished or penalised)
+    pub entries: Vec<String>,
+    pub finishes: Vec<FinishRecord>,
+    pub penalties: Vec<Penalty>,
+    #[serde(default)]
+    pub handicap: Handicap,
+    /// Elapsed and corrected time per finisher, fastest corrected first (derived)
+    #[serde(default)]
+    pub corrected: Vec<CorrectedTime>,
+}
+
+/// How a race's elapsed times are corrected with the entries' ratings.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(tag = "method", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Handicap {
+    /// One-design: corrected = elapsed
+    #[default]
+    Scratch,
+    /// Rating is a time correction factor (ORC ToT, IRC TCC): corrected = elapsed × rating
+    TimeOnTime,
+    /// PHRF time-on-time: corrected = elapsed × a / (b + rating)
+    #[serde(rename_all = "camelCase")]
+    PhrfTimeOnTime {
+        #[serde(default = "default_phrf_a")]
+        a: f64,
+        #[serde(default = "default_phrf_b")]
+        b: f64,
+    },
+    /// Rating in s/nm (PHRF, ORC ToD): corrected = elapsed − rating × distance
+    #[serde(rename_all = "camelCase")]
+    TimeOnDistance { distance_nm: f64 },
+}
+
+fn default_phrf_a() -> f64 { 650.0 }
+fn default_phrf_b() -> f64 { 550.0 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectedTime {
+    pub boat_id: String,
+    pub elapsed_ms: i64,
+    pub corrected_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoringSettings {
+    /// Race counts at which another discard applies, e.g. [4, 8]: one discard from
+    /// four races, two from eight (RRS A2.1 — none unless the SIs say so)
+    #[serde(default)]
+    pub discard_thresholds: Vec<u32>,
+    /// Handicap of races closed from now on (each race can be changed afterwards)
+    #[serde(default)]
+    pub handicap: Handicap,
+}
+
+/// What the auto-director finds exciting (see `auto_director`). Each weight
+/// scales one heuristic; 0 turns it off.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoDirectorConfig {
+    /// Speed, against the class target where one is known
+    pub speed_weight: f64,
+    /// Closeness to a mark or the start line
+    pub proximity_weight: f64,
+    /// Places gained or lost in the last half minute
+    pub rank_change_weight: f64,
+    /// Predicted to be over the line at the gun
+    pub ocs_risk_weight: f64,
+    /// In a crossing, mark overlap or penalty (see `incidents`)
+    pub incident_weight: f64,
+    /// Boats in focus at once
+    pub focus_count: usize,
+    /// Score a challenger needs above a focused boat to displace it
+    pub hysteresis: f64,
+    /// A boat stays in focus at least this long
+    pub min_dwell_secs: f64,
+    /// Final seconds before the gun scored on the start line alone; 0 = off
+    pub gun_window_secs: f64,
+    /// Cameras the pointing feed aims at the focus (see `camera_pointing`)
+    pub cameras: Vec<CameraConfig>,
+}
+
+impl Default for AutoDirectorConfig {
+    fn default() -> Self {
+        Self {
+            speed_weight: 1.0,
+            proximity_weight: 1.0,
+            rank_change_weight: 1.0,
+            ocs_risk_weight: 1.0,
+            incident_weight: 1.0,
+            focus_count: 4,
+            hysteresis: 5.0,
+            min_dwell_secs: 10.0,
+            gun_window_secs: 60.0,
+            cameras: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HighlightKind {
+    Gun,
+    Ocs,
+    LeadChange,
+    MarkRounding,
+    Incident,
+}
+
+/// A timestamped moment for post-race editors (see `highlights`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Highlight {
+    pub id: String,
+    pub kind: HighlightKind,
+    pub timestamp: i64,
+    #[serde(default)]
+    pub boats: Vec<String>,
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub race_id: Option<String>,
+}
+
+/// Manual overrides of the auto-director (see `director-override`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DirectorOverrides {
+    /// Boats kept in focus, in the order they were pinned
+    pub pinned: Vec<String>,
+    /// Boats never put in focus
+    pub excluded: Vec<String>,
+    /// Automation paused until then; the focus stays as it is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suspended_until_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CameraPlatform {
+    #[default]
+    CommitteeBoat,
+    Drone,
+    Shore,
+}
+
+/// What a camera is for (see `camera_assignment`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CameraRole {
+    /// The focus boats together, else the fleet
+    Wide,
+    /// One focus boat
+    #[default]
+    Chase,
+    /// A mark and the boats rounding it
+    Mark,
+}
+
+/// A camera on a gimbal. It stands at `pos`, or moves with the boat or mark
+/// `followId` (committee boat, a drone carrying a tracker).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CameraConfig {
+    pub id: String,
+    pub name: String,
+    pub platform: CameraPlatform,
+    pub role: CameraRole,
+    /// Mark a mark camera covers; the busiest mark when None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark_id: Option<String>,
+    /// Least time on one target; the focus `minDwellSecs` when None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dwell_secs: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pos: Option<LatLon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow_id: Option<String>,
+    /// Lens above the water
+    pub height_m: f64,
+    /// True bearing of pan 0; the followed boat's heading when None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading_deg: Option<f64>,
+    /// Horizontal field of view at zoom 1
+    pub wide_fov_deg: f64,
+    pub max_zoom: f64,
+    /// Width of water to fill the frame with around a target
+    pub frame_m: f64,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            platform: CameraPlatform::default(),
+            role: CameraRole::default(),
+            mark_id: None,
+            dwell_secs: None,
+            pos: None,
+            follow_id: None,
+            height_m: 3.0,
+            heading_deg: None,
+            wide_fov_deg: 60.0,
+            max_zoom: 30.0,
+            frame_m: 20.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaceScore {
+    pub race_number: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<ScoreCode>,
+    pub points: f64,
+    pub discarded: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesStanding {
+    pub rank: u32,
+    pub boat_id: String,
+    pub total: f64,
+    pub net: f64,
+    pub races: Vec<RaceScore>,
+}
+
+// ─── Protests (RRS Part 5) ───────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProtestStatus {
+    #[default]
+    Filed,
+    Scheduled,
+    Decided,
+    Withdrawn,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hearing {
+    pub at_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtestDecision {
+    /// e.g. "Protest upheld", "Protest dismissed", "Invalid"
+    pub outcome: String,
+    /// Penalties applied to scoring as a result of the hearing
+    #[serde(default)]
+    pub penalties: Vec<Penalty>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facts_found: Option<String>,
+    pub decided_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Protest {
+    pub id: String,
+    /// Closed race the incident belongs to; None = the race in progress
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub race_number: Option<u32>,
+    /// Protesting boat, or "RC" / "PC" for race or protest committee protests
+    pub protestor: String,
+    pub protestees: Vec<String>,
+    /// Rules alleged to be broken, e.g. ["10", "14"]
+    #[serde(default)]
+    pub rules: Vec<String>,
+    pub incident_ms: i64,
+    #[serde(default)]
+    pub description: String,
+    /// Log lines the protest refers to (flagged on filing)
+    #[serde(default)]
+    pub log_ids: Vec<String>,
+    /// Audit window for `protest-replay`
+    pub audit_from_ms: u64,
+    pub audit_to_ms: u64,
+    pub filed_ms: i64,
+    pub filed_by: String,
+    #[serde(default)]
+    pub status: ProtestStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hearing: Option<Hearing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decision: Option<ProtestDecision>,
+}
+
+// ─── Messaging ────────────────────────────────────────────────────────────────
+
+/// Sender or recipient of an on-water message: everyone with a role, or one boat's tracker.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum MessageTarget {
+    Role(String),
+    Boat(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAck {
+    /// Role or boat id that acknowledged
+    pub by: String,
+    pub at_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    pub id: String,
+    /// Sender's role, or its boat for a tracker
+    pub from: MessageTarget,
+    pub to: MessageTarget,
+    pub body: String,
+    pub sent_ms: i64,
+    #[serde(default)]
+    pub acks: Vec<MessageAck>,
+}
+
+// ─── Tracker Blacklist ────────────────────────────────────────────────────────
+
+/// What a blacklist entry matches: a boat id, or a tracker's hardware id.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BlacklistKind {
+    Boat,
+    Device,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlacklistEntry {
+    pub kind: BlacklistKind,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub added_ms: i64,
+    /// None = permanent ban
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_ms: Option<i64>,
+}
+
+// ─── Robotic Marks ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MarkBotState {
+    /// Driving to a new target
+    Transit,
+    OnStation,
+    /// On station but drifted beyond the holding limit
+    HoldAlarm,
+    /// Missing from the last position report
+    Offline,
+}
+
+/// A MarkSetBot unit holding a course mark (see `marksetbot`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkBotStatus {
+    pub mark_id: String,
+    pub bot_id: String,
+    pub target: LatLon,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<LatLon>,
+    /// Distance from target, metres
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_m: Option<f64>,
+    pub state: MarkBotState,
+    pub updated_ms: i64,
+}
+
+// ─── Start Line Bias ──────────────────────────────────────────────────────────
+
+/// End of the start line as seen looking upwind: pin to port, committee boat to starboard.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LineEnd {
+    Pin,
+    Committee,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LineSource {
+    Uwb,
+    Gps,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineBias {
+    pub source: LineSource,
+    /// Degrees off square to the wind, positive = committee end favored
+    pub bias_deg: f64,
+    /// None while the line is square within tolerance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favored_end: Option<LineEnd>,
+    /// How much further upwind the favored end is
+    pub advantage_m: f64,
+    pub advantage_boat_lengths: f64,
+    pub line_length_m: f64,
+    /// Bearing from pin to committee end
+    pub line_bearing: f64,
+    pub wind_direction: f64,
+    pub pin: LatLon,
+    pub committee: LatLon,
+}
+
+// ─── OCS Detection ────────────────────────────────────────────────────────────
+
+/// A boat the UWB hub measured on the course side of the line at the gun.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcsDetection {
+    pub boat_id: String,
+    pub node_id: u32,
+    /// Distance to line, positive = over
+    pub dtl_cm: f32,
+    pub fix_quality: u8,
+    pub epoch_ms: u64,
 }
 
 // ─── Time Limits ──────────────────────────────────────────────────────────────
+// Enforced by `time_limits`.
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -323,6 +936,12 @@ pub struct ProcedureNodeData {
     // Map this node to a specific RaceStatus (optional override)
     #[serde(rename = "raceStatus", skip_serializing_if = "Option::is_none")]
     pub race_status: Option<String>,
+    // Seconds before this node's flags/sound fire to emit an `upcoming-signal` (0 = off)
+    #[serde(rename = "announceLead", default)]
+    pub announce_lead: f64,
+    // Free-form frontend data (flag image ids, announcer text, locale strings), passed through untouched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -353,6 +972,25 @@ pub struct ProcedureGraph {
     pub auto_restart: bool,
 }
 
+/// Named, shareable procedure graph in the template library.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcedureTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // Free-form grouping, e.g. "fleet", "match-race", "team-race"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    // Built-in templates are generated at runtime and cannot be modified or deleted
+    #[serde(default)]
+    pub built_in: bool,
+    #[serde(default)]
+    pub updated_ms: u64,
+    pub graph: ProcedureGraph,
+}
+
 // ─── Sequence Update (broadcast payload) ─────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -371,6 +1009,123 @@ pub struct SequenceUpdate {
     pub is_post_trigger: bool,
     #[serde(default)]
     pub sound: SoundSignal,
+    /// True while the sequence is paused (countdown held)
+    #[serde(default)]
+    pub paused: bool,
+    /// The current node's `metadata`, verbatim
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// `countdown` payload: absolute end times on the disciplined server clock. Clients
+/// estimate their offset from `serverTimeMs` (or the `clock-sync` round trip) and
+/// render `endsAtMs - (localNow + offset)` every frame instead of stepping on each
+/// 5Hz `sequence-update`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Countdown {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_id: Option<String>,
+    pub node_id: String,
+    /// When the current node (or its post-trigger phase) ends; None while waiting or paused
+    pub node_ends_at_ms: Option<u64>,
+    /// When the whole sequence ends; None while paused
+    pub sequence_ends_at_ms: Option<u64>,
+    pub node_remaining_ms: u64,
+    pub sequence_remaining_ms: u64,
+    pub paused: bool,
+    pub server_time_ms: u64,
+}
+
+/// `upcoming-signal` payload: the next node's flags/sound, announced `announceLead`
+/// seconds before they fire so the RC can arm the horn and the announcer can count down.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpcomingSignal {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_id: Option<String>,
+    pub node_id: String,
+    pub event: String,
+    pub flags: Vec<String>,
+    pub sound: SoundSignal,
+    pub seconds_until: f64,
+    /// Expected unix ms of the signal
+    pub at_ms: u64,
+}
+
+/// `class-sequence-update` payload: a SequenceUpdate tagged with its class/fleet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassSequenceUpdate {
+    pub class_id: String,
+    #[serde(flatten)]
+    pub update: SequenceUpdate,
+}
+
+/// Handicap system used to derive pursuit start offsets.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HandicapSystem {
+    /// Portsmouth Yardstick — higher number is slower
+    #[default]
+    Py,
+    /// Time correction factor (IRC/ORC style) — higher number is faster
+    Tcf,
+    /// Offsets given directly in seconds
+    Offset,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PursuitEntry {
+    pub boat_id: String,
+    #[serde(default)]
+    pub rating: f64,
+    /// Seconds after the first (slowest boat's) gun this boat starts
+    #[serde(default)]
+    pub offset_secs: f64,
+    #[serde(default)]
+    pub started: bool,
+}
+
+/// Pursuit start: one start sequence, then each boat starts on its own handicap offset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PursuitStart {
+    pub system: HandicapSystem,
+    /// Expected elapsed time of the slowest boat, minutes
+    pub race_minutes: f64,
+    pub entries: Vec<PursuitEntry>,
+    /// Unix ms of the first gun; None until the sequence reaches its start signal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_ms: Option<i64>,
+}
+
+/// Live state of one class/fleet start sequence running alongside the others.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassSequenceState {
+    pub status: RaceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_sequence: Option<SequenceInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence_time_remaining: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_node_id: Option<String>,
+    #[serde(default)]
+    pub waiting_for_trigger: bool,
+    /// Unix ms of this class's starting signal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<i64>,
+}
+
+impl ClassSequenceState {
+    pub fn apply(&mut self, update: &SequenceUpdate) {
+        self.current_sequence = Some(update.current_sequence.clone());
+        self.sequence_time_remaining = Some(update.sequence_time_remaining);
+        self.current_node_id = Some(update.current_node_id.clone());
+        self.waiting_for_trigger = update.waiting_for_trigger;
+    }
 }
 
 // ─── Logging ─────────────────────────────────────────────────────────────────
@@ -422,6 +1177,92 @@ pub struct BoatProfile {
     pub mount: TrackerMount,
 }
 
+/// A registered boat. `boat_id` is the id trackers, penalties, finishes and
+/// results use; the other fields say which boat that actually is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    #[serde(default)]
+    pub boat_id: String,
+    pub sail_number: String,
+    #[serde(default)]
+    pub boat_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_id: Option<String>,
+    /// Handicap rating (e.g. PY, IRC TCC); None for one-design
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<f64>,
+    #[serde(default)]
+    pub crew: Vec<CrewMember>,
+    /// UWB tag on the boat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CrewRole {
+    Skipper,
+    #[default]
+    Crew,
+}
+
+/// Who to call if something happens to a crew member on the water.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EmergencyContact {
+    pub name: String,
+    pub phone: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrewMember {
+    pub name: String,
+    #[serde(default)]
+    pub role: CrewRole,
+    /// IOC / ISO 3166 country code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nationality: Option<String>,
+    /// Accepted with the entry but never sent with the state; kept by `crew`
+    #[serde(default, skip_serializing)]
+    pub emergency_contact: Option<EmergencyContact>,
+}
+
+/// Target boat speed at one true wind speed and angle.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PolarPoint {
+    pub tws_kn: f64,
+    pub twa_deg: f64,
+    pub bsp_kn: f64,
+}
+
+/// A boat class in the registry; entries refer to it by `Entry::class_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoatClass {
+    pub id: String,
+    pub name: String,
+    /// Target speeds (see `boat_classes::target_speed`)
+    #[serde(default)]
+    pub polar: Vec<PolarPoint>,
+    /// Optimum true wind angles sailed up- and downwind (tacking / gybing angles)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upwind_twa: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downwind_twa: Option<f64>,
+    /// Target speeds when the polar does not cover the wind
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upwind_kn: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downwind_kn: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handicap_system: Option<HandicapSystem>,
+}
+
 // ─── Fleet & League Management ────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -455,6 +1296,8 @@ pub enum FlightStatus {
     Scheduled,
     InProgress,
     Completed,
+    /// Sailed but not counted (breakdown, general recall); see `insert-resail`
+    Abandoned,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -464,6 +1307,76 @@ pub struct Flight {
     pub flight_number: u32,
     pub group_label: String,
     pub status: FlightStatus,
+    /// Knockout round this flight sails; None = round robin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stage: Option<KnockoutRound>,
+    /// Umpires and support boats on the water for this flight (see `officials`)
+    #[serde(default, skip_serializing_if = "FlightOfficials::is_empty")]
+    pub officials: FlightOfficials,
+}
+
+/// Flights `fromFlight`..=`toFlight` a team cannot sail.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamUnavailability {
+    pub team_id: String,
+    pub from_flight: u32,
+    pub to_flight: u32,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// What `generate-flights` must honour (see `flight_engine`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleConstraints {
+    /// Other races a team sits out between two of its own
+    #[serde(default)]
+    pub min_rest_races: u32,
+    #[serde(default)]
+    pub unavailable: Vec<TeamUnavailability>,
+}
+
+impl ScheduleConstraints {
+    pub fn is_unavailable(&self, team_id: &str, flight_number: u32) -> bool {
+        self.unavailable.iter()
+            .any(|u| u.team_id == team_id && (u.from_flight..=u.to_flight).contains(&flight_number))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OfficialKind {
+    Umpire,
+    SupportBoat,
+}
+
+/// An umpire or support boat of the event's roster.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Official {
+    pub id: String,
+    pub name: String,
+    pub kind: OfficialKind,
+    /// Flight numbers they cannot do
+    #[serde(default)]
+    pub unavailable_flights: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightOfficials {
+    /// Official ids, two per umpire boat
+    #[serde(default)]
+    pub umpire_pairs: Vec<[String; 2]>,
+    #[serde(default)]
+    pub support_boats: Vec<String>,
+}
+
+impl FlightOfficials {
+    pub fn is_empty(&self) -> bool {
+        self.umpire_pairs.is_empty() && self.support_boats.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -476,6 +1389,136 @@ pub struct Pairing {
     pub race_index: u32,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RedressStatus {
+    #[default]
+    Pending,
+    Granted,
+    Denied,
+}
+
+/// A team whose league race was spoiled through no fault of its own (a boat
+/// breakdown), up for redress; see `substitute-boat` and `decide-redress`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Redress {
+    pub id: String,
+    pub team_id: String,
+    pub flight_id: String,
+    pub race_index: u32,
+    /// The boat that broke down
+    pub boat_id: String,
+    #[serde(default)]
+    pub reason: String,
+    pub flagged_ms: i64,
+    #[serde(default)]
+    pub status: RedressStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum KnockoutRound {
+    QuarterFinal,
+    SemiFinal,
+    Final,
+}
+
+impl KnockoutRound {
+    /// Matches in the round
+    pub fn matches(self) -> usize {
+        match self {
+            Self::QuarterFinal => 4,
+            Self::SemiFinal => 2,
+            Self::Final => 1,
+        }
+    }
+
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Self::QuarterFinal => Some(Self::SemiFinal),
+            Self::SemiFinal => Some(Self::Final),
+            Self::Final => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::QuarterFinal => "Quarter-final",
+            Self::SemiFinal => "Semi-final",
+            Self::Final => "Final",
+        }
+    }
+}
+
+/// One race of a knockout round (see `knockout`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnockoutMatch {
+    pub race_index: u32,
+    /// Seed order
+    pub teams: Vec<String>,
+    /// Finish order once the race is entered
+    #[serde(default)]
+    pub finish: Vec<String>,
+    #[serde(default)]
+    pub advancing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnockoutStage {
+    pub round: KnockoutRound,
+    pub teams_per_match: usize,
+    /// Teams of each match going on to the next round
+    pub advance_per_match: usize,
+    /// Bracket order: the winners of matches i and n−1−i meet in the next round
+    pub matches: Vec<KnockoutMatch>,
+    pub created_ms: i64,
+}
+
+/// One team's finish in a league race, entered per flight (see `league`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingResult {
+    pub pairing_id: String,
+    pub flight_id: String,
+    pub race_index: u32,
+    pub team_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<ScoreCode>,
+    pub recorded_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeagueRaceScore {
+    pub flight_id: String,
+    pub flight_number: u32,
+    pub race_index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<ScoreCode>,
+    pub points: f64,
+    /// Points given as granted redress
+    #[serde(default)]
+    pub redress: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeagueStanding {
+    pub rank: u32,
+    pub team_id: String,
+    pub team_name: String,
+    pub total: f64,
+    /// Flight order
+    pub races: Vec<LeagueRaceScore>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum FleetMode {
@@ -496,6 +1539,9 @@ pub struct FleetSettings {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RaceState {
+    /// Persisted layout version, see `state_schema` (0 = written before versioning)
+    #[serde(default)]
+    pub schema_version: u32,
     pub status: RaceStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub global_override: Option<String>, // "AP", "N", "GENERAL_RECALL", "INDIVIDUAL_RECALL"
@@ -509,6 +1555,10 @@ pub struct RaceState {
     pub wind: WindState,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub weather: Option<WeatherReport>,
+    #[serde(default)]
+    pub weather_history: Vec<WeatherReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<CurrentState>,
     pub course: CourseState,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub current_procedure: Option<ProcedureGraph>,
@@ -530,6 +1580,16 @@ pub struct RaceState {
     // OCS boat list (boats on course side at start)
     #[serde(default)]
     pub ocs_boats: Vec<String>,
+    /// UWB gun-solve OCS detections of the current start (see `ocs_recall`)
+    #[serde(default)]
+    pub ocs_detections: Vec<OcsDetection>,
+    /// Start line ends as reported by the UWB line anchors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uwb_start_line: Option<CourseLine>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_bias: Option<LineBias>,
+    #[serde(default)]
+    pub mark_bots: Vec<MarkBotStatus>,
     // Ephemeral — populated at runtime, not persisted
     #[serde(default)]
     pub boats: HashMap<String, BoatState>,
@@ -552,11 +1612,121 @@ pub struct RaceState {
     pub pairings: Vec<Pairing>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub active_flight_id: Option<String>,
+    /// League teams flagged for redress, oldest first
+    #[serde(default)]
+    pub redress: Vec<Redress>,
+    /// League finishes per pairing and the team standings computed from them
+    #[serde(default)]
+    pub league_results: Vec<PairingResult>,
+    #[serde(default)]
+    pub league_standings: Vec<LeagueStanding>,
+    #[serde(default)]
+    pub schedule_constraints: ScheduleConstraints,
+    /// Umpires and support boats to assign to flights
+    #[serde(default)]
+    pub officials: Vec<Official>,
+    /// Knockout rounds after the round robin, in order
+    #[serde(default)]
+    pub knockout: Vec<KnockoutStage>,
+    // Concurrent per-class start sequences (class_id → live sequence state)
+    #[serde(default)]
+    pub class_sequences: HashMap<String, ClassSequenceState>,
+    // Saved procedure templates (built-ins are not stored)
+    #[serde(default)]
+    pub procedure_templates: Vec<ProcedureTemplate>,
+    // Armed pursuit (handicap) start, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pursuit: Option<PursuitStart>,
+    // Mark roundings of the current race, in detection order
+    #[serde(default)]
+    pub mark_roundings: Vec<MarkRounding>,
+    // Finishes of the current race, in finishing order
+    #[serde(default)]
+    pub finishes: Vec<FinishRecord>,
+    // Closed races of the series and the standings computed from them
+    #[serde(default)]
+    pub results: Vec<RaceResult>,
+    #[serde(default)]
+    pub scoring: ScoringSettings,
+    #[serde(default)]
+    pub standings: Vec<SeriesStanding>,
+    #[serde(default)]
+    pub auto_director: AutoDirectorConfig,
+    #[serde(default)]
+    pub director_overrides: DirectorOverrides,
+    /// Moments worth a replay, newest last (see `highlights`)
+    #[serde(default)]
+    pub highlights: Vec<Highlight>,
+    #[serde(default)]
+    pub protests: Vec<Protest>,
+    // Races of the session; the active one lives in the top-level race fields
+    #[serde(default)]
+    pub races: Vec<RaceRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_race_id: Option<String>,
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+    #[serde(default)]
+    pub boat_classes: Vec<BoatClass>,
+    #[serde(default)]
+    pub messages: Vec<Message>,
+    #[serde(default)]
+    pub blacklist: Vec<BlacklistEntry>,
+    // Regattas hosted by this backend; the active one lives in the top-level fields
+    #[serde(default)]
+    pub events: Vec<RegattaEvent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_event_id: Option<String>,
+}
+
+/// A regatta (event) hosted by this backend. Its course, entries, races and
+/// results are the top-level fields while it is active, a stored document
+/// otherwise (see `regatta`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegattaEvent {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub venue: Option<String>,
+    /// ISO dates, e.g. `2026-10-15`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    pub created_ms: i64,
+}
+
+/// A race of the session while it is not the active one. `race_session` swaps
+/// these fields in and out of the top-level `RaceState` race fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaceRecord {
+    pub id: String,
+    pub name: String,
+    pub created_ms: i64,
+    pub status: RaceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub procedure: Option<ProcedureGraph>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<i64>,
+    #[serde(default)]
+    pub penalties: Vec<Penalty>,
+    #[serde(default)]
+    pub ocs_boats: Vec<String>,
+    #[serde(default)]
+    pub ocs_detections: Vec<OcsDetection>,
+    #[serde(default)]
+    pub finishes: Vec<FinishRecord>,
+    /// Series race number once scored (see `results`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub race_number: Option<u32>,
 }
 
 impl Default for RaceState {
     fn default() -> Self {
         Self {
+            schema_version: crate::state_schema::SCHEMA_VERSION,
             status: RaceStatus::Idle,
             global_override: None,
             current_sequence: None,
@@ -568,6 +1738,8 @@ impl Default for RaceState {
                 speed: 12.0,
             },
             weather: None,
+            weather_history: Vec::new(),
+            current: None,
             course: CourseState::default(),
             current_procedure: None,
             active_course_order: None,
@@ -578,6 +1750,10 @@ impl Default for RaceState {
             is_post_trigger: false,
             time_limits: TimeLimits::default(),
             ocs_boats: Vec::new(),
+            ocs_detections: Vec::new(),
+            uwb_start_line: None,
+            line_bias: None,
+            mark_bots: Vec::new(),
             boats: HashMap::new(),
             penalties: Vec::new(),
             logs: Vec::new(),
@@ -601,6 +1777,32 @@ impl Default for RaceState {
             flights: HashMap::new(),
             pairings: Vec::new(),
             active_flight_id: None,
+            redress: Vec::new(),
+            league_results: Vec::new(),
+            league_standings: Vec::new(),
+            schedule_constraints: ScheduleConstraints::default(),
+            officials: Vec::new(),
+            knockout: Vec::new(),
+            class_sequences: HashMap::new(),
+            procedure_templates: Vec::new(),
+            pursuit: None,
+            mark_roundings: Vec::new(),
+            finishes: Vec::new(),
+            results: Vec::new(),
+            scoring: ScoringSettings::default(),
+            standings: Vec::new(),
+            auto_director: AutoDirectorConfig::default(),
+            director_overrides: DirectorOverrides::default(),
+            highlights: Vec::new(),
+            protests: Vec::new(),
+            races: Vec::new(),
+            active_race_id: None,
+            entries: Vec::new(),
+            boat_classes: Vec::new(),
+            messages: Vec::new(),
+            blacklist: Vec::new(),
+            events: Vec::new(),
+            active_event_id: None,
         }
     }
 }
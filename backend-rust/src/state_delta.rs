@@ -0,0 +1,209 @@
+//! # state_delta
+//!
+//! Delta state stream for clients that opt out of full `state-update` broadcasts.
+//!
+//! A client emits `subscribe-state-delta` and joins the `state-delta` room. It gets
+//! a `state-keyframe` (`{ seq, state }`) straight away and from then on:
+//!
+//! - `state-patch` — `{ seq, ops }`, the RFC 6902 JSON Patch from the previous
+//!   `seq` to this one, sent every `STATE_DELTA_INTERVAL_MS` (default 100) when
+//!   something changed
+//! - `state-keyframe` — the whole state every `STATE_KEYFRAME_SECS` (default 10)
+//!
+//! A client that sees a gap in `seq` re-emits `subscribe-state-delta` for a fresh
+//! keyframe. Full `state-update` broadcasts skip the room; `init-state` on register
+//! is still the full state. Trackers only see their boat (`boat_scope`) and
+//! cannot subscribe.
+//!
+//! ## Invariants
+//! - Core Invariant #8: the diff runs in its own task on a snapshot; the state lock
+//!   is held only for serialization
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use socketioxide::SocketIo;
+use tokio::sync::RwLock;
+
+use crate::handlers::SharedState;
+
+pub const DELTA_ROOM: &str = "state-delta";
+
+pub struct DeltaConfig {
+    pub interval: Duration,
+    pub keyframe_every: Duration,
+}
+
+impl Default for DeltaConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<u64>().ok()).filter(|v| *v > 0);
+        Self {
+            interval: Duration::from_millis(var("STATE_DELTA_INTERVAL_MS").unwrap_or(100)),
+            keyframe_every: Duration::from_secs(var("STATE_KEYFRAME_SECS").unwrap_or(10)),
+        }
+    }
+}
+
+/// One RFC 6902 operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+struct Baseline {
+    seq: u64,
+    state: Value,
+}
+
+/// The state every subscriber holds after applying the patches up to `seq`.
+pub struct StateDelta {
+    baseline: RwLock<Baseline>,
+}
+
+pub type SharedDelta = Arc<StateDelta>;
+
+impl StateDelta {
+    pub fn new(state: Value) -> SharedDelta {
+        Arc::new(Self { baseline: RwLock::new(Baseline { seq: 0, state }) })
+    }
+
+    pub async fn keyframe(&self) -> Value {
+        let base = self.baseline.read().await;
+        json!({ "seq": base.seq, "state": base.state })
+    }
+}
+
+/// RFC 6901 reference token.
+fn token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn diff_objects(prev: &Map<String, Value>, next: &Map<String, Value>, path: &str, ops: &mut Vec<PatchOp>) {
+    for key in prev.keys().filter(|k| !next.contains_key(*k)) {
+        ops.push(PatchOp::Remove { path: format!("{path}/{}", token(key)) });
+    }
+    for (key, value) in next {
+        let child = format!("{path}/{}", token(key));
+        match prev.get(key) {
+            Some(old) => diff(old, value, &child, ops),
+            None => ops.push(PatchOp::Add { path: child, value: value.clone() }),
+        }
+    }
+}
+
+/// Smallest `k` such that `next` is `prev` with its first `k` entries dropped
+/// and `k` new ones pushed (a full ring buffer such as `logs`).
+fn shifted_by(prev: &[Value], next: &[Value]) -> Option<usize> {
+    (1..prev.len()).find(|&k| prev[k..] == next[..next.len() - k])
+}
+
+/// `id` of every entry, when they all have one.
+fn ids(arr: &[Value]) -> Option<Vec<&Value>> {
+    arr.iter().map(|v| v.get("id")).collect()
+}
+
+/// Append the operations turning `prev` into `next` at `path`.
+pub fn diff(prev: &Value, next: &Value, path: &str, ops: &mut Vec<PatchOp>) {
+    if prev == next {
+        return;
+    }
+    match (prev, next) {
+        (Value::Object(a), Value::Object(b)) => diff_objects(a, b, path, ops),
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            if let Some(k) = shifted_by(a, b) {
+                ops.extend((0..k).map(|_| PatchOp::Remove { path: format!("{path}/0") }));
+                for value in &b[b.len() - k..] {
+                    ops.push(PatchOp::Add { path: format!("{path}/-"), value: value.clone() });
+                }
+            } else if ids(a).zip(ids(b)).is_some_and(|(x, y)| x != y) {
+                // Entries moved or were swapped out; matching by index would rewrite them all
+                ops.push(PatchOp::Replace { path: path.to_string(), value: next.clone() });
+            } else {
+                for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                    diff(x, y, &format!("{path}/{i}"), ops);
+                }
+            }
+        }
+        // Appended to (logs, roundings, penalties)
+        (Value::Array(a), Value::Array(b)) if b.len() > a.len() && b.starts_with(a) => {
+            for value in &b[a.len()..] {
+                ops.push(PatchOp::Add { path: format!("{path}/-"), value: value.clone() });
+            }
+        }
+        _ => ops.push(PatchOp::Replace { path: path.to_string(), value: next.clone() }),
+    }
+}
+
+/// Parent of the value at `path` and the last reference token, unescaped.
+fn parent<'a>(doc: &'a mut Value, path: &str) -> Option<(&'a mut Value, String)> {
+    let (parent, last) = path.rsplit_once('/')?;
+    Some((doc.pointer_mut(parent)?, last.replace("~1", "/").replace("~0", "~")))
+}
+
+/// Apply operations produced by `diff`; operations that no longer fit are skipped.
+pub fn apply(doc: &mut Value, ops: &[PatchOp]) {
+    for op in ops {
+        match op {
+            PatchOp::Replace { path, value } if path.is_empty() => *doc = value.clone(),
+            PatchOp::Replace { path, value } => {
+                if let Some(target) = doc.pointer_mut(path) {
+                    *target = value.clone();
+                }
+            }
+            PatchOp::Add { path, value } => match parent(doc, path) {
+                Some((Value::Object(map), key)) => {
+                    map.insert(key, value.clone());
+                }
+                Some((Value::Array(arr), key)) if key == "-" => arr.push(value.clone()),
+                Some((Value::Array(arr), key)) => {
+                    if let Some(i) = key.parse::<usize>().ok().filter(|i| *i <= arr.len()) {
+                        arr.insert(i, value.clone());
+                    }
+                }
+                _ => {}
+            },
+            PatchOp::Remove { path } => match parent(doc, path) {
+                Some((Value::Object(map), key)) => {
+                    map.remove(&key);
+                }
+                Some((Value::Array(arr), key)) => {
+                    if let Some(i) = key.parse::<usize>().ok().filter(|i| *i < arr.len()) {
+                        arr.remove(i);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+pub async fn run_delta_broadcaster(config: DeltaConfig, delta: SharedDelta, shared: SharedState, io: SocketIo) {
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last_keyframe = Instant::now();
+    loop {
+        ticker.tick().await;
+        let next = match serde_json::to_value(&*shared.read().await) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        let mut base = delta.baseline.write().await;
+        let mut ops = Vec::new();
+        diff(&base.state, &next, "", &mut ops);
+        if !ops.is_empty() {
+            base.seq += 1;
+            base.state = next;
+            let _ = io.to(DELTA_ROOM).emit("state-patch", &json!({ "seq": base.seq, "ops": ops }));
+        }
+        if last_keyframe.elapsed() >= config.keyframe_every {
+            last_keyframe = Instant::now();
+            let _ = io.to(DELTA_ROOM).emit("state-keyframe", &json!({ "seq": base.seq, "state": base.state }));
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! # state_schema
+//!
+//! Versioned layout of the persisted `RaceState`.
+//!
+//! Every saved state carries `schemaVersion`. On load (state, snapshots, stored
+//! events) the JSON is upgraded one step at a time through `MIGRATIONS` before it
+//! is deserialized, so a field that was renamed or moved is carried over instead
+//! of silently falling back to its default. Afterwards `upgrade` warns about
+//! fields that were defaulted (missing from the document) and fields that were
+//! dropped (unknown to this build, e.g. a state written by a newer backend).
+//!
+//! Changing the layout of a persisted field: bump `SCHEMA_VERSION` and append a
+//! migration from the previous version; never edit one that has shipped. A new
+//! field with `#[serde(default)]` needs no migration.
+
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+
+use crate::state::RaceState;
+
+pub const SCHEMA_VERSION: u32 = 3;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades version `n` to `n + 1`.
+const MIGRATIONS: &[(&str, Migration)] = &[
+    ("penalty ids and lifecycle status", v0_penalty_status),
+    ("boat trails moved to the track store", v1_drop_fleet_history),
+    ("structured crew members", v2_crew_members),
+];
+
+/// v0 → v1: every penalty stood, so it is `PENDING`; ids are assigned on deserialize.
+fn v0_penalty_status(doc: &mut Map<String, Value>) {
+    let mark = |list: Option<&mut Vec<Value>>| {
+        for penalty in list.into_iter().flatten().filter_map(Value::as_object_mut) {
+            penalty.entry("status").or_insert_with(|| Value::from("PENDING"));
+        }
+    };
+    mark(doc.get_mut("penalties").and_then(Value::as_array_mut));
+    for race in doc.get_mut("results").and_then(Value::as_array_mut).into_iter().flatten() {
+        mark(race.get_mut("penalties").and_then(Value::as_array_mut));
+    }
+}
+
+/// v1 → v2: `fleetHistory` is rebuilt from `track_store`, no longer saved with the state.
+fn v1_drop_fleet_history(doc: &mut Map<String, Value>) {
+    doc.remove("fleetHistory");
+}
+
+/// v2 → v3: an entry's crew was a list of names.
+fn v2_crew_members(doc: &mut Map<String, Value>) {
+    let entries = doc.get_mut("entries").and_then(Value::as_array_mut).into_iter().flatten();
+    for crew in entries.filter_map(|e| e.get_mut("crew").and_then(Value::as_array_mut)) {
+        for member in crew.iter_mut() {
+            if let Some(name) = member.as_str() {
+                *member = serde_json::json!({ "name": name, "role": "CREW" });
+            }
+        }
+    }
+}
+
+/// Upgrade a persisted state document to `SCHEMA_VERSION` and deserialize it.
+/// `source` names the document in warnings.
+pub fn upgrade(mut doc: Value, source: &str) -> Result<RaceState, serde_json::Error> {
+    if let Some(map) = doc.as_object_mut() {
+        let from = map.get("schemaVersion").and_then(Value::as_u64).unwrap_or(0) as u32;
+        if from > SCHEMA_VERSION {
+            warn!("{source}: schema version {from} is newer than this backend ({SCHEMA_VERSION}), unknown fields will be dropped");
+        }
+        for (version, (name, migrate)) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+            migrate(map);
+            info!("{source}: migrated schema {version} → {} ({name})", version + 1);
+        }
+        map.insert("schemaVersion".to_string(), Value::from(SCHEMA_VERSION.max(from)));
+    }
+
+    let state: RaceState = serde_json::from_value(doc.clone())?;
+    report_fields(&doc, &state, source);
+    Ok(state)
+}
+
+/// Warn about top-level fields that did not survive the round trip.
+fn report_fields(doc: &Value, state: &RaceState, source: &str) {
+    let (Some(input), Ok(Value::Object(output))) = (doc.as_object(), serde_json::to_value(state)) else {
+        return;
+    };
+    let dropped: Vec<&str> = input.iter()
+        .filter(|(key, value)| !value.is_null() && !output.contains_key(*key))
+        .map(|(key, _)| key.as_str())
+        .collect();
+    let defaulted: Vec<&str> = output.keys()
+        .filter(|key| !input.contains_key(*key))
+        .map(String::as_str)
+        .collect();
+    if !dropped.is_empty() {
+        warn!("{source}: dropped unknown field(s): {}", dropped.join(", "));
+    }
+    if !defaulted.is_empty() {
+        warn!("{source}: defaulted missing field(s): {}", defaulted.join(", "));
+    }
+}
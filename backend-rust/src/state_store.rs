@@ -0,0 +1,289 @@
+//! # state_store
+//!
+//! Where `persistence` keeps its documents (race state, engine snapshots).
+//!
+//! `STATE_STORE` picks the backend:
+//! - `file` — one JSON file per document in the working directory (`state.json`, …)
+//! - `sqlite` — `STATE_SQLITE_PATH` (default `state.db`)
+//! - `postgres` — `STATE_DB_URL`, else `SUPABASE_DB_URL`; a JSONB row per document
+//!
+//! Unset, `BACKEND_MODE=cloud` with a database URL means `postgres`, anything
+//! else `file`, so cloud deploys keep their state across redeploys. A database
+//! that cannot be reached at startup falls back to `file`.
+//!
+//! SQL backends apply `SQLITE_MIGRATIONS` / `POSTGRES_MIGRATIONS` in order on
+//! connect, recording each in `state_store_migrations`; add a migration by
+//! appending to the list, never by editing one that has shipped.
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use sqlx::{PgPool, Row, SqlitePool};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Key → JSON document storage.
+pub trait StateStore: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn load<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>>;
+    fn save<'a>(&'a self, key: &'a str, json: &'a str) -> StoreFuture<'a, ()>;
+    fn remove<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()>;
+}
+
+// ── File ──────────────────────────────────────────────────────────────────────
+
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+impl StateStore for FileStore {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn load<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        Box::pin(async move {
+            match fs::read_to_string(self.path(key)).await {
+                Ok(data) => Ok(Some(data)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    /// Written via a temp file + rename so a crash mid-write never leaves a torn document.
+    fn save<'a>(&'a self, key: &'a str, json: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.path(key);
+            let tmp = path.with_extension("json.tmp");
+            let mut file = fs::File::create(&tmp).await?;
+            file.write_all(json.as_bytes()).await?;
+            file.sync_all().await?;
+            fs::rename(&tmp, &path).await?;
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            match fs::remove_file(self.path(key)).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+// ── SQLite ────────────────────────────────────────────────────────────────────
+
+const SQLITE_MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS backend_state (
+        key TEXT PRIMARY KEY,
+        data TEXT NOT NULL,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )",
+];
+
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    pub async fn connect(path: &str) -> Result<Self> {
+        let options = sqlx::sqlite::SqliteConnectOptions::new().filename(path).create_if_missing(true);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect_with(options).await?;
+        sqlx::query("CREATE TABLE IF NOT EXISTS state_store_migrations (version INTEGER PRIMARY KEY, applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP)")
+            .execute(&pool).await?;
+        let applied: i64 = sqlx::query("SELECT COUNT(*) AS n FROM state_store_migrations")
+            .fetch_one(&pool).await?
+            .try_get("n")?;
+        for (version, sql) in SQLITE_MIGRATIONS.iter().enumerate().skip(applied as usize) {
+            let mut tx = pool.begin().await?;
+            sqlx::query(sql).execute(&mut *tx).await?;
+            sqlx::query("INSERT INTO state_store_migrations (version) VALUES (?)")
+                .bind(version as i64 + 1)
+                .execute(&mut *tx).await?;
+            tx.commit().await?;
+            info!("State store: sqlite migration {} applied", version + 1);
+        }
+        Ok(Self { pool })
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn load<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let row = sqlx::query("SELECT data FROM backend_state WHERE key = ?")
+                .bind(key)
+                .fetch_optional(&self.pool).await?;
+            Ok(row.map(|r| r.try_get("data")).transpose()?)
+        })
+    }
+
+    fn save<'a>(&'a self, key: &'a str, json: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO backend_state (key, data, updated_at) VALUES (?, ?, CURRENT_TIMESTAMP)
+                 ON CONFLICT (key) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+            )
+            .bind(key)
+            .bind(json)
+            .execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM backend_state WHERE key = ?").bind(key).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+}
+
+// ── Postgres / Supabase ───────────────────────────────────────────────────────
+
+const POSTGRES_MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS backend_state (
+        key TEXT PRIMARY KEY,
+        data JSONB NOT NULL,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    )",
+];
+
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .acquire_timeout(std::time::Duration::from_secs(5))
+            .connect(url).await?;
+        sqlx::query("CREATE TABLE IF NOT EXISTS state_store_migrations (version INTEGER PRIMARY KEY, applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW())")
+            .execute(&pool).await?;
+        let applied: i64 = sqlx::query("SELECT COUNT(*) AS n FROM state_store_migrations")
+            .fetch_one(&pool).await?
+            .try_get("n")?;
+        for (version, sql) in POSTGRES_MIGRATIONS.iter().enumerate().skip(applied as usize) {
+            let mut tx = pool.begin().await?;
+            sqlx::query(sql).execute(&mut *tx).await?;
+            sqlx::query("INSERT INTO state_store_migrations (version) VALUES ($1)")
+                .bind(version as i32 + 1)
+                .execute(&mut *tx).await?;
+            tx.commit().await?;
+            info!("State store: postgres migration {} applied", version + 1);
+        }
+        Ok(Self { pool })
+    }
+}
+
+impl StateStore for PostgresStore {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn load<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let row = sqlx::query("SELECT data::text AS data FROM backend_state WHERE key = $1")
+                .bind(key)
+                .fetch_optional(&self.pool).await?;
+            Ok(row.map(|r| r.try_get("data")).transpose()?)
+        })
+    }
+
+    fn save<'a>(&'a self, key: &'a str, json: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO backend_state (key, data, updated_at) VALUES ($1, $2::jsonb, NOW())
+                 ON CONFLICT (key) DO UPDATE SET data = EXCLUDED.data, updated_at = EXCLUDED.updated_at",
+            )
+            .bind(key)
+            .bind(json)
+            .execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM backend_state WHERE key = $1").bind(key).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+}
+
+// ── Selection ─────────────────────────────────────────────────────────────────
+
+static STORE: OnceLock<Box<dyn StateStore>> = OnceLock::new();
+
+fn file_store() -> Box<dyn StateStore> {
+    Box::new(FileStore { dir: PathBuf::from(".") })
+}
+
+/// The configured store; the file store until `init` has run.
+pub fn store() -> &'static dyn StateStore {
+    STORE.get_or_init(file_store).as_ref()
+}
+
+/// Connect the backend chosen by `STATE_STORE` / `BACKEND_MODE`. Call once, before loading state.
+pub async fn init() {
+    let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+    let db_url = var("STATE_DB_URL").or_else(|| var("SUPABASE_DB_URL"));
+    let kind = var("STATE_STORE").unwrap_or_else(|| {
+        let cloud = var("BACKEND_MODE").as_deref() == Some("cloud");
+        if cloud && db_url.is_some() { "postgres" } else { "file" }.to_string()
+    });
+
+    let store: Box<dyn StateStore> = match kind.as_str() {
+        "sqlite" => {
+            let path = var("STATE_SQLITE_PATH").unwrap_or_else(|| "state.db".to_string());
+            match SqliteStore::connect(&path).await {
+                Ok(s) => Box::new(s),
+                Err(e) => {
+                    warn!("State store: sqlite {path} unavailable ({e}), using files");
+                    file_store()
+                }
+            }
+        }
+        "postgres" => match db_url {
+            Some(url) => match PostgresStore::connect(&url).await {
+                Ok(s) => Box::new(s),
+                Err(e) => {
+                    warn!("State store: postgres unavailable ({e}), using files");
+                    file_store()
+                }
+            },
+            None => {
+                warn!("State store: postgres needs STATE_DB_URL or SUPABASE_DB_URL, using files");
+                file_store()
+            }
+        },
+        "file" => file_store(),
+        other => {
+            warn!("State store: unknown STATE_STORE {other}, using files");
+            file_store()
+        }
+    };
+    info!("State store: {}", store.name());
+    if STORE.set(store).is_err() {
+        warn!("State store: already initialised, keeping {}", self::store().name());
+    }
+}
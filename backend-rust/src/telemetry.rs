@@ -0,0 +1,88 @@
+//! # telemetry
+//!
+//! Opt-in binary position channel for the media suite and iOS apps, where JSON
+//! `boat-update` broadcasts stop scaling at 50 boats and 5–20 Hz.
+//!
+//! - WebSocket at `GET /telemetry?hz=<rate>` (default 10, capped at `TELEMETRY_MAX_HZ`,
+//!   default 20)
+//! - Each binary message is a `uwb_types::TelemetryFrame` (12-byte header, 36 bytes
+//!   per boat; layout in `uwb_types.h`) with every boat in the fleet
+//! - A frame is only sent when a boat moved or changed status since the last one
+//!
+//! Like `/spectate`, no token is needed: a frame carries positions only.
+
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::Query;
+use axum::response::Response;
+use serde::Deserialize;
+use tracing::info;
+use uwb_types::{TelemetryBoat, TelemetryFrame};
+
+use crate::handlers::{now_ms, SharedState};
+use crate::state::{BoatStatus, RaceState};
+
+#[derive(Deserialize)]
+pub struct TelemetryQuery {
+    hz: Option<u32>,
+}
+
+fn max_hz() -> u32 {
+    std::env::var("TELEMETRY_MAX_HZ").ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(20)
+}
+
+pub fn frame(state: &RaceState) -> TelemetryFrame {
+    let mut boats: Vec<TelemetryBoat> = state.boats.values().map(|b| TelemetryBoat {
+        boat_id: b.boat_id.clone(),
+        lat_e7: (b.pos.lat * 1e7).round() as i32,
+        lon_e7: (b.pos.lon * 1e7).round() as i32,
+        heading_cdeg: (b.imu.heading.rem_euclid(360.0) * 100.0).round() as u16 % 36000,
+        speed_ckn: (b.velocity.speed * 100.0).round().clamp(0.0, u16::MAX as f64) as u16,
+        dtl_cm: (b.dtl * 100.0).round() as i32,
+        rank: b.rank.min(u16::MAX as u32) as u16,
+        leg_index: b.leg_index.min(u8::MAX as u32) as u8,
+        status: match b.status {
+            BoatStatus::Live => 0,
+            BoatStatus::Stale => 1,
+            BoatStatus::Offline => 2,
+        },
+    }).collect();
+    boats.sort_by(|a, b| a.boat_id.cmp(&b.boat_id));
+    TelemetryFrame { epoch_ms: now_ms() as u64, flags: 0, boats }
+}
+
+pub async fn upgrade(ws: WebSocketUpgrade, Query(query): Query<TelemetryQuery>, shared: SharedState) -> Response {
+    let hz = query.hz.unwrap_or(10).clamp(1, max_hz());
+    ws.on_upgrade(move |socket| stream(socket, hz, shared))
+}
+
+async fn stream(mut socket: WebSocket, hz: u32, shared: SharedState) {
+    info!("Telemetry: binary client connected at {hz} Hz");
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / hz as f64));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last_boats = Vec::new();
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                // Nothing the client sends is handled; only a close ends the stream
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = ticker.tick() => {
+                let frame = frame(&*shared.read().await);
+                if frame.boats == last_boats {
+                    continue;
+                }
+                if socket.send(Message::Binary(frame.encode())).await.is_err() {
+                    break;
+                }
+                last_boats = frame.boats;
+            }
+        }
+    }
+    info!("Telemetry: binary client disconnected");
+}
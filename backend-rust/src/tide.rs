@@ -0,0 +1,181 @@
+//! # tide
+//!
+//! Tidal stream on the course and the predictions it changes.
+//!
+//! The current is set by the committee (`update-current { set, drift }`, set =
+//! direction the water flows towards, drift in knots; `null` clears it) or
+//! fetched by `CURRENT_PROVIDER=openmeteo` (Open-Meteo marine API, every
+//! `CURRENT_POLL_SECS`, default 900) for the course area. It is kept as
+//! `state.current` and emitted as `current-updated`.
+//!
+//! With a current set:
+//!
+//! - laylines follow the ground track of a boat sailing the layline angle at
+//!   `LAYLINE_UPWIND_KN` / `LAYLINE_DOWNWIND_KN` (default 6 / 7) through the water
+//! - each boat's `linePrediction` (while a gun is pending) adds the current to a
+//!   velocity measured through the water — a tracker sending speed without a
+//!   course over ground `dir` — and predicts where a boat that stops sailing
+//!   drifts to by the gun
+//!
+//! For a boat whose class (`boat_classes`) has an upwind angle, `targetTimeToLineS`
+//! is the time to the line from behind it, close-hauled at the class target speed.
+//!
+//! The start line is the one `line_bias` resolved; distances are positive on the
+//! course side.
+
+use std::time::Duration;
+
+use serde_json::Value;
+use socketioxide::SocketIo;
+use tracing::{info, warn};
+
+use crate::boat_classes;
+use crate::handlers::{now_ms, SharedState};
+use crate::laylines;
+use crate::state::{BoatState, CurrentSource, CurrentState, LatLon, LaylineLeg, LinePrediction, RaceState};
+use crate::weather;
+
+const MPS_PER_KNOT: f64 = 0.514_444;
+const KMH_PER_KNOT: f64 = 1.852;
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+/// A provider that hangs longer is skipped until the next poll
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct CurrentConfig {
+    pub provider: Option<CurrentSource>,
+    pub poll: Duration,
+}
+
+impl Default for CurrentConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        Self {
+            provider: match var("CURRENT_PROVIDER").unwrap_or_default().to_lowercase().as_str() {
+                "openmeteo" | "open-meteo" => Some(CurrentSource::Openmeteo),
+                _ => None,
+            },
+            poll: Duration::from_secs(var("CURRENT_POLL_SECS").and_then(|v| v.parse().ok()).filter(|&s| s >= 60).unwrap_or(900)),
+        }
+    }
+}
+
+/// (east, north) in m/s for a speed in knots along `bearing_deg`.
+fn vector(bearing_deg: f64, knots: f64) -> (f64, f64) {
+    let b = bearing_deg.to_radians();
+    (knots * MPS_PER_KNOT * b.sin(), knots * MPS_PER_KNOT * b.cos())
+}
+
+/// Current as (east, north) m/s; zero when none is set.
+pub fn current_vector(state: &RaceState) -> (f64, f64) {
+    state.current.as_ref().map_or((0.0, 0.0), |c| vector(c.set, c.drift))
+}
+
+/// Bearing made good by a boat heading `heading_deg` at `knots` through the water.
+pub fn ground_track(state: &RaceState, heading_deg: f64, knots: f64) -> f64 {
+    let (we, wn) = vector(heading_deg, knots);
+    let (ce, cn) = current_vector(state);
+    (we + ce).atan2(wn + cn).to_degrees().rem_euclid(360.0)
+}
+
+/// Replace the current and re-lay the laylines around it.
+pub fn set_current(state: &mut RaceState, current: Option<CurrentState>) {
+    state.current = current;
+    laylines::refresh(state);
+}
+
+/// Local east/north metres of `p` relative to `origin`.
+fn local(origin: &LatLon, p: &LatLon) -> (f64, f64) {
+    let e = (p.lon - origin.lon).to_radians() * origin.lat.to_radians().cos() * EARTH_RADIUS_M;
+    let n = (p.lat - origin.lat).to_radians() * EARTH_RADIUS_M;
+    (e, n)
+}
+
+/// Start-line prediction for `boat`, or None without a start line or pending gun.
+pub fn predict(state: &RaceState, boat: &BoatState, now: i64) -> Option<LinePrediction> {
+    let line = state.line_bias.as_ref()?;
+    let secs_to_gun = state.start_time.map(|gun| (gun - now) as f64 / 1000.0).filter(|s| *s > 0.0)?;
+
+    // Unit normal to the line pointing upwind (course side)
+    let (le, ln) = local(&line.pin, &line.committee);
+    let len = le.hypot(ln);
+    if len < 1.0 {
+        return None;
+    }
+    let (mut ne, mut nn) = (-ln / len, le / len);
+    let (ue, un) = vector(state.wind.direction, 1.0);
+    if ne * ue + nn * un < 0.0 {
+        (ne, nn) = (-ne, -nn);
+    }
+
+    let (pe, pn) = local(&line.pin, &boat.pos);
+    let dtl_m = pe * ne + pn * nn;
+
+    // A course over ground already contains the current; a heading-based speed does not
+    let (ce, cn) = current_vector(state);
+    let (ve, vn) = match boat.velocity.dir {
+        Some(cog) => vector(cog, boat.velocity.speed),
+        None => {
+            let (we, wn) = vector(boat.imu.heading, boat.velocity.speed);
+            (we + ce, wn + cn)
+        }
+    };
+    let closing = ve * ne + vn * nn;
+    let drift = ce * ne + cn * nn;
+
+    // Close-hauled at the class target, only the upwind component closes the line
+    let target_vmg = boat_classes::for_boat(state, &boat.boat_id).and_then(|class| {
+        let knots = boat_classes::leg_speed(class, LaylineLeg::Upwind, state.wind.speed)?;
+        Some(knots * MPS_PER_KNOT * class.upwind_twa?.to_radians().cos())
+    }).filter(|v| *v > 0.0);
+
+    let round = |v: f64| (v * 10.0).round() / 10.0;
+    Some(LinePrediction {
+        dtl_m: round(dtl_m),
+        time_to_line_s: (closing != 0.0 && -dtl_m / closing > 0.0).then(|| round(-dtl_m / closing)),
+        dtl_at_gun_m: Some(round(dtl_m + closing * secs_to_gun)),
+        drift_dtl_at_gun_m: state.current.is_some().then(|| round(dtl_m + drift * secs_to_gun)),
+        target_time_to_line_s: target_vmg.filter(|_| dtl_m < 0.0).map(|vmg| round(-dtl_m / vmg)),
+    })
+}
+
+async fn fetch_openmeteo(client: &reqwest::Client, at: &LatLon) -> anyhow::Result<CurrentState> {
+    let url = format!(
+        "https://marine-api.open-meteo.com/v1/marine?latitude={}&longitude={}\
+         &current=ocean_current_velocity,ocean_current_direction",
+        at.lat, at.lon,
+    );
+    let body: Value = client.get(url).send().await?.error_for_status()?.json().await?;
+    let current = &body["current"];
+    Ok(CurrentState {
+        set: current["ocean_current_direction"].as_f64().ok_or_else(|| anyhow::anyhow!("no current direction"))?,
+        drift: current["ocean_current_velocity"].as_f64().ok_or_else(|| anyhow::anyhow!("no current velocity"))? / KMH_PER_KNOT,
+        source: CurrentSource::Openmeteo,
+        updated_ms: now_ms(),
+    })
+}
+
+pub async fn run_current_poll(config: CurrentConfig, shared: SharedState, io: SocketIo) {
+    let Some(provider) = config.provider else { return };
+    info!("Current: polling {:?} every {}s", provider, config.poll.as_secs());
+    let client = reqwest::Client::builder().timeout(PROVIDER_TIMEOUT).build().unwrap_or_default();
+    let mut interval = tokio::time::interval(config.poll);
+    loop {
+        interval.tick().await;
+        let Some(at) = weather::location(&*shared.read().await) else { continue };
+        let current = match fetch_openmeteo(&client, &at).await {
+            Ok(current) => current,
+            Err(e) => {
+                warn!("Current: {provider:?} fetch failed: {e}");
+                continue;
+            }
+        };
+        let mut state = shared.write().await;
+        // A committee entry wins over the model
+        if state.current.as_ref().is_some_and(|c| c.source == CurrentSource::Manual) {
+            continue;
+        }
+        set_current(&mut state, Some(current));
+        let _ = io.emit("current-updated", &state.current);
+        let _ = io.emit("course-updated", &state.course);
+    }
+}
@@ -0,0 +1,240 @@
+//! # time_discipline
+//!
+//! Disciplined wall clock for gun and status-change times.
+//!
+//! The procedure engine measures intervals with monotonic `Instant`s, which is
+//! correct for countdowns but says nothing about *when* the gun fired. Every
+//! wall-clock time the backend states (start time, finish time, `/sync`,
+//! persisted sequence snapshots) goes through `now_ms()`, which applies the
+//! offset measured against an external reference:
+//!
+//! - `TIME_SOURCE=ntp` (default) — SNTP against `NTP_SERVER` (default `pool.ntp.org:123`)
+//! - `TIME_SOURCE=gps` — NMEA `RMC` sentences from `GPS_SERIAL_PORT` (a serial device
+//!   already configured for the receiver's baud rate, e.g. `/dev/ttyAMA0`). Sentences
+//!   trail the PPS edge they label, so the offset is the maximum over recent samples
+//!   (the one with the least transport delay), with `GPS_UNCERTAINTY_MS` (default 250)
+//!   reported as its bound.
+//! - `TIME_SOURCE=system` — no discipline; offset stays 0 and `synced` false
+//!
+//! `status()` is recorded in every race status change audit block so a stated gun
+//! time can be traced back to the reference it was disciplined against.
+//!
+//! ## Invariants
+//! - Core Invariant #2: gun times in the audit chain carry their clock provenance
+//! - Core Invariant #8: sync runs in its own task; a lost reference keeps the last offset
+
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::UdpSocket;
+use tracing::{info, warn};
+
+/// Seconds between the NTP era (1900) and the Unix epoch
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+const GPS_SAMPLE_WINDOW: usize = 16;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockStatus {
+    /// "ntp", "gps" or "system"
+    pub source: &'static str,
+    /// Reference minus local system clock, ms
+    pub offset_ms: i64,
+    /// Bound on the offset error (NTP: half the round trip), ms
+    pub uncertainty_ms: u64,
+    /// Unix ms of the last successful sync (0 = never)
+    pub last_sync_ms: u64,
+    pub synced: bool,
+}
+
+static STATUS: RwLock<ClockStatus> = RwLock::new(ClockStatus {
+    source: "system",
+    offset_ms: 0,
+    uncertainty_ms: 0,
+    last_sync_ms: 0,
+    synced: false,
+});
+
+/// Disciplined unix ms.
+pub fn now_ms() -> u64 {
+    let offset = STATUS.read().map(|s| s.offset_ms).unwrap_or(0);
+    system_ms().saturating_add_signed(offset)
+}
+
+pub fn status() -> ClockStatus {
+    STATUS.read().map(|s| s.clone()).unwrap_or(ClockStatus {
+        source: "system",
+        offset_ms: 0,
+        uncertainty_ms: 0,
+        last_sync_ms: 0,
+        synced: false,
+    })
+}
+
+fn set_status(source: &'static str, offset_ms: i64, uncertainty_ms: u64) {
+    if let Ok(mut s) = STATUS.write() {
+        *s = ClockStatus { source, offset_ms, uncertainty_ms, last_sync_ms: system_ms(), synced: true };
+    }
+}
+
+// ── Configuration ─────────────────────────────────────────────────────────────
+
+pub enum TimeSource {
+    System,
+    Ntp { server: String },
+    Gps { port: String, uncertainty_ms: u64 },
+}
+
+pub struct TimeConfig {
+    pub source: TimeSource,
+    /// NTP poll interval (default 64 s)
+    pub interval: Duration,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        let source = match var("TIME_SOURCE").as_deref().map(str::to_lowercase).as_deref() {
+            Some("system") | Some("none") => TimeSource::System,
+            Some("gps") => match var("GPS_SERIAL_PORT") {
+                Some(port) => TimeSource::Gps {
+                    port,
+                    uncertainty_ms: var("GPS_UNCERTAINTY_MS").and_then(|v| v.parse().ok()).unwrap_or(250),
+                },
+                None => {
+                    warn!("TimeDiscipline: TIME_SOURCE=gps without GPS_SERIAL_PORT, using system clock");
+                    TimeSource::System
+                }
+            },
+            _ => TimeSource::Ntp { server: var("NTP_SERVER").unwrap_or_else(|| "pool.ntp.org:123".to_string()) },
+        };
+        Self {
+            source,
+            interval: Duration::from_secs(
+                var("TIME_SYNC_INTERVAL_SECS").and_then(|v| v.parse().ok()).filter(|&s| s > 0).unwrap_or(64),
+            ),
+        }
+    }
+}
+
+// ── Sync task ─────────────────────────────────────────────────────────────────
+
+pub fn spawn(config: TimeConfig) {
+    match config.source {
+        TimeSource::System => info!("TimeDiscipline: using undisciplined system clock"),
+        TimeSource::Ntp { server } => {
+            info!("TimeDiscipline: NTP against {server} every {:?}", config.interval);
+            tokio::spawn(run_ntp(server, config.interval));
+        }
+        TimeSource::Gps { port, uncertainty_ms } => {
+            info!("TimeDiscipline: GPS NMEA from {port}");
+            tokio::spawn(run_gps(port, uncertainty_ms));
+        }
+    }
+}
+
+async fn run_ntp(server: String, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        match tokio::time::timeout(Duration::from_secs(5), ntp_query(&server)).await {
+            Ok(Ok((offset_ms, rtt_ms))) => {
+                let was_synced = status().synced;
+                set_status("ntp", offset_ms, rtt_ms / 2);
+                if !was_synced {
+                    info!("TimeDiscipline: synced to {server}, offset {offset_ms} ms (±{} ms)", rtt_ms / 2);
+                }
+            }
+            Ok(Err(e)) => warn!("TimeDiscipline: NTP query to {server} failed: {e}"),
+            Err(_) => warn!("TimeDiscipline: NTP query to {server} timed out"),
+        }
+    }
+}
+
+/// One SNTP exchange. Returns (offset ms, round trip ms).
+async fn ntp_query(server: &str) -> std::io::Result<(i64, u64)> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+
+    let mut request = [0u8; 48];
+    request[0] = 0x23; // LI 0, version 4, mode 3 (client)
+    let t1 = system_ms() as i64;
+    socket.send(&request).await?;
+
+    let mut response = [0u8; 48];
+    let n = socket.recv(&mut response).await?;
+    let t4 = system_ms() as i64;
+    if n < 48 || response[0] & 0x07 != 4 {
+        return Err(std::io::Error::other("not an NTP server response"));
+    }
+
+    let t2 = ntp_timestamp_ms(&response[32..40]);
+    let t3 = ntp_timestamp_ms(&response[40..48]);
+    let offset = ((t2 - t1) + (t3 - t4)) / 2;
+    let rtt = ((t4 - t1) - (t3 - t2)).max(0) as u64;
+    Ok((offset, rtt))
+}
+
+fn ntp_timestamp_ms(bytes: &[u8]) -> i64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64;
+    let frac = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as u64;
+    let unix_secs = secs.saturating_sub(NTP_UNIX_OFFSET_SECS);
+    (unix_secs * 1000 + ((frac * 1000) >> 32)) as i64
+}
+
+async fn run_gps(port: String, uncertainty_ms: u64) {
+    loop {
+        match tokio::fs::File::open(&port).await {
+            Ok(file) => {
+                let mut lines = BufReader::new(file).lines();
+                let mut samples: Vec<i64> = Vec::with_capacity(GPS_SAMPLE_WINDOW);
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let received_ms = system_ms() as i64;
+                    let Some(gps_ms) = parse_rmc(&line) else { continue };
+                    if samples.len() == GPS_SAMPLE_WINDOW {
+                        samples.remove(0);
+                    }
+                    samples.push(gps_ms - received_ms);
+                    if let Some(&offset) = samples.iter().max() {
+                        set_status("gps", offset, uncertainty_ms);
+                    }
+                }
+                warn!("TimeDiscipline: GPS stream {port} ended, reopening");
+            }
+            Err(e) => warn!("TimeDiscipline: cannot open {port}: {e}"),
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+/// Unix ms from a valid `$--RMC` sentence (`hhmmss.ss,A,...,ddmmyy`).
+fn parse_rmc(line: &str) -> Option<i64> {
+    let body = line.trim().strip_prefix('$')?;
+    let body = body.split('*').next()?;
+    let fields: Vec<&str> = body.split(',').collect();
+    if !fields.first()?.ends_with("RMC") || fields.get(2) != Some(&"A") {
+        return None;
+    }
+    let time = fields.get(1)?;
+    let date = fields.get(9)?;
+    if time.len() < 6 || date.len() != 6 {
+        return None;
+    }
+    let num = |s: &str| s.parse::<u32>().ok();
+    let (hh, mm) = (num(&time[0..2])?, num(&time[2..4])?);
+    let secs: f64 = time[4..].parse().ok()?;
+    let (day, month, year) = (num(&date[0..2])?, num(&date[2..4])?, 2000 + num(&date[4..6])? as i32);
+
+    let date = chrono::NaiveDate::from_ymd_opt(year, month, day)?;
+    let midnight_ms = date.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis();
+    Some(midnight_ms + ((hh * 3600 + mm * 60) as i64) * 1000 + (secs * 1000.0).round() as i64)
+}
+
+fn system_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
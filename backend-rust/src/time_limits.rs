@@ -0,0 +1,159 @@
+//! # time_limits
+//!
+//! Enforces `state.time_limits` (RRS 35 and the sailing instructions).
+//!
+//! Clocks run from the gun (`start_time`) while the race is sailing:
+//!
+//! - `mark1LimitSecs` — if no boat has rounded a mark by then, the race is
+//!   abandoned (`procedure-action ABANDON`, N flag)
+//! - `finishWindowSecs` — counted from the first finish; when it expires every
+//!   tracked boat without a finish is scored TLE (see `scoring` for
+//!   `tleScoring`), the race is closed and marked finished
+//!
+//! Once a second the directors (`director` room) get `time-limit-clock
+//! { mark1RemainingSecs?, finishRemainingSecs? }`, and `time-limit-warning
+//! { limit: "MARK1" | "FINISH_WINDOW", remainingSecs }` as each of
+//! `TIME_LIMIT_WARNINGS_SECS` (default `300,60`) is crossed.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use serde_json::json;
+use socketioxide::SocketIo;
+use tracing::info;
+
+use crate::audit::AuditLogger;
+use crate::handlers::{audit_status_change, log_to, now_ms, procedure_action, Outlet, SharedEngine, SharedState};
+use crate::persistence::save_state;
+use crate::scoring;
+use crate::state::{LogCategory, Penalty, PenaltyType, RaceState, RaceStatus};
+
+const TICK: Duration = Duration::from_secs(1);
+const DIRECTOR_ROOM: &str = "director";
+
+pub struct TimeLimitConfig {
+    /// Seconds before expiry at which a warning goes out, largest first
+    pub warnings: Vec<i64>,
+}
+
+impl Default for TimeLimitConfig {
+    fn default() -> Self {
+        let mut warnings: Vec<i64> = std::env::var("TIME_LIMIT_WARNINGS_SECS").ok()
+            .map(|v| v.split(',').filter_map(|s| s.trim().parse().ok()).filter(|s| *s > 0).collect())
+            .unwrap_or_else(|| vec![300, 60]);
+        warnings.sort_unstable_by(|a, b| b.cmp(a));
+        Self { warnings }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Limit {
+    Mark1,
+    FinishWindow,
+}
+
+impl Limit {
+    fn name(self) -> &'static str {
+        match self {
+            Limit::Mark1 => "MARK1",
+            Limit::FinishWindow => "FINISH_WINDOW",
+        }
+    }
+}
+
+/// Milliseconds left on each running clock.
+fn remaining(state: &RaceState, now: i64) -> (Option<i64>, Option<i64>) {
+    if !matches!(state.status, RaceStatus::Racing | RaceStatus::IndividualRecall) {
+        return (None, None);
+    }
+    let Some(gun) = state.start_time else { return (None, None) };
+    let limits = &state.time_limits;
+    let mark1 = limits.mark1_limit_secs
+        .filter(|_| !state.mark_roundings.iter().any(|r| r.correct))
+        .map(|secs| gun + (secs * 1000.0) as i64 - now);
+    let finish = limits.finish_window_secs
+        .zip(state.finishes.iter().map(|f| f.finish_ms).min())
+        .map(|(secs, first)| first + (secs * 1000.0) as i64 - now);
+    (mark1, finish)
+}
+
+/// Score every tracked boat still racing TLE and close the race.
+fn expire_finish_window(state: &mut RaceState, now: i64) -> Vec<String> {
+    let mut late: Vec<String> = state.boats.keys()
+        .filter(|b| !state.finishes.iter().any(|f| &f.boat_id == *b))
+        .filter(|b| !state.penalties.iter().any(|p| &p.boat_id == *b && p.counts()))
+        .cloned()
+        .collect();
+    late.sort();
+    for boat_id in &late {
+        state.penalties.push(Penalty::new(boat_id.clone(), PenaltyType::Tle, now));
+    }
+    scoring::close_race(state);
+    state.status = RaceStatus::Finished;
+    late
+}
+
+pub async fn run_time_limits(config: TimeLimitConfig, shared: SharedState, engine: SharedEngine, audit: AuditLogger, io: SocketIo) {
+    let mut ticker = tokio::time::interval(TICK);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let out = Outlet::Io(io.clone());
+    // Warnings already sent, per gun
+    let mut warned: HashSet<(Limit, i64)> = HashSet::new();
+    let mut gun = None;
+    loop {
+        ticker.tick().await;
+        let now = now_ms();
+        let (start_time, (mark1, finish)) = {
+            let state = shared.read().await;
+            (state.start_time, remaining(&state, now))
+        };
+        if start_time != gun {
+            gun = start_time;
+            warned.clear();
+        }
+        if mark1.is_none() && finish.is_none() {
+            continue;
+        }
+
+        let secs = |ms: Option<i64>| ms.map(|ms| (ms.max(0) as f64 / 1000.0).ceil());
+        let _ = io.to(DIRECTOR_ROOM).emit("time-limit-clock", &json!({
+            "mark1RemainingSecs": secs(mark1),
+            "finishRemainingSecs": secs(finish),
+        }));
+        for (limit, left) in [(Limit::Mark1, mark1), (Limit::FinishWindow, finish)] {
+            let Some(left) = left.filter(|l| *l > 0) else { continue };
+            for &threshold in &config.warnings {
+                if left <= threshold * 1000 && warned.insert((limit, threshold)) {
+                    let _ = io.to(DIRECTOR_ROOM).emit("time-limit-warning", &json!({
+                        "limit": limit.name(),
+                        "remainingSecs": threshold,
+                    }));
+                }
+            }
+        }
+
+        if mark1.is_some_and(|l| l <= 0) {
+            info!("TimeLimits: mark 1 limit expired, abandoning");
+            procedure_action(&shared, &engine, &audit, &out, &json!({ "action": "ABANDON" })).await;
+            log_to(&shared, &out, LogCategory::Procedure, "Race Committee".to_string(),
+                "Mark 1 time limit expired — race abandoned".to_string(),
+                Some(json!({ "limit": Limit::Mark1.name() })), true).await;
+        } else if finish.is_some_and(|l| l <= 0) {
+            let (late, race_number, status_before) = {
+                let mut state = shared.write().await;
+                let status_before = state.status.clone();
+                let late = expire_finish_window(&mut state, now);
+                let race_number = state.results.last().map(|r| r.race_number);
+                let _ = save_state(&state).await;
+                out.emit("standings", &json!({ "standings": state.standings, "results": state.results }));
+                out.emit_state(&state);
+                (late, race_number, status_before)
+            };
+            audit_status_change(&audit, &status_before, &RaceStatus::Finished, "finish window expired").await;
+            info!("TimeLimits: finish window expired, {} boat(s) TLE", late.len());
+            log_to(&shared, &out, LogCategory::Procedure, "Race Committee".to_string(),
+                format!("Finish window expired — {} boat(s) scored TLE", late.len()),
+                Some(json!({ "limit": Limit::FinishWindow.name(), "boats": late, "raceNumber": race_number })), false).await;
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! # time_sync
+//!
+//! NTP-style clock sync for clients, so countdowns on every device agree to tens
+//! of milliseconds.
+//!
+//! One exchange: the client stamps `t0` and sends it; the server stamps `t1` on
+//! receipt and `t2` on reply; the client stamps `t3` on arrival. Then
+//!
+//! - offset = ((t1 − t0) + (t2 − t3)) / 2   (server clock minus client clock)
+//! - rtt    = (t3 − t0) − (t2 − t1)
+//!
+//! Clients run several exchanges and keep the offset of the one with the lowest
+//! rtt. Both transports answer the same way:
+//!
+//! - `GET /sync?t0=<ms>` — JSON `{ t0, t1, t2, serverTime, clock }`
+//! - socket `time-sync { t0 }` — answered with `time-sync` carrying the same fields
+//!
+//! `time-sync-report { offsetMs, rttMs, samples }` records the client's result;
+//! directors get each report as `client-clock-offset` and the full list from
+//! `list-clock-offsets` (`clock-offsets`). Server times are the disciplined clock
+//! (`time_discipline::now_ms`).
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::time_discipline;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientOffset {
+    pub socket_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boat_id: Option<String>,
+    /// Server clock minus client clock, ms
+    pub offset_ms: f64,
+    pub rtt_ms: f64,
+    pub samples: u32,
+    pub reported_ms: u64,
+}
+
+static CLIENTS: LazyLock<RwLock<HashMap<String, ClientOffset>>> = LazyLock::new(Default::default);
+
+/// Reply to one exchange. `t1` is the receive stamp taken when the request arrived.
+pub fn reply(t0: Option<f64>, t1: u64) -> Value {
+    let t2 = time_discipline::now_ms();
+    json!({
+        "t0": t0,
+        "t1": t1,
+        "t2": t2,
+        "serverTime": t2,
+        "clock": time_discipline::status(),
+    })
+}
+
+pub fn record(offset: ClientOffset) {
+    CLIENTS.write().unwrap_or_else(|e| e.into_inner()).insert(offset.socket_id.clone(), offset);
+}
+
+pub fn remove(socket_id: &str) {
+    CLIENTS.write().unwrap_or_else(|e| e.into_inner()).remove(socket_id);
+}
+
+/// Every connected client's last report, largest offset first.
+pub fn list() -> Vec<ClientOffset> {
+    let mut clients: Vec<ClientOffset> = CLIENTS.read().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+    clients.sort_by(|a, b| b.offset_ms.abs().total_cmp(&a.offset_ms.abs()));
+    clients
+}
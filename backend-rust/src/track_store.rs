@@ -0,0 +1,154 @@
+//! # track_store
+//!
+//! Boat tracks beyond the live trail. `state.fleet_history` (the trail drawn on
+//! the map) keeps a time window per boat; every sampled point is also appended
+//! to a JSONL file per race, so a boat's full track can be fetched afterwards.
+//!
+//! - `TRACK_SAMPLE_MS` (default 5000) — minimum spacing of points per boat
+//! - `TRACK_WINDOW_SECS` (default 1800) — age of the oldest point kept in
+//!   `fleet_history`
+//! - `TRACK_STORE_DIR` (default `/data/tracks`) — `<raceId>.jsonl` per race
+//!
+//! `get-track { boatId, raceId?, fromMs?, toMs? }` (default: the active race, all
+//! of it) is answered with `track { boatId, raceId, points }`; the same query is
+//! served as `GET /api/v1/track`.
+//!
+//! ## Invariants
+//! - Core Invariant #8: file I/O runs after the state lock is released
+
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::state::{HistoricalPing, RaceState};
+
+pub struct TrackConfig {
+    pub sample_ms: i64,
+    pub window_ms: i64,
+    pub dir: PathBuf,
+}
+
+impl Default for TrackConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        let num = |key: &str| var(key).and_then(|v| v.parse::<i64>().ok()).filter(|v| *v > 0);
+        Self {
+            sample_ms: num("TRACK_SAMPLE_MS").unwrap_or(5000),
+            window_ms: num("TRACK_WINDOW_SECS").unwrap_or(1800) * 1000,
+            dir: var("TRACK_STORE_DIR").unwrap_or_else(|| "/data/tracks".to_string()).into(),
+        }
+    }
+}
+
+static CONFIG: LazyLock<TrackConfig> = LazyLock::new(TrackConfig::default);
+/// Serialises appends so concurrent boats never interleave partial lines
+static FILE: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredPing {
+    boat_id: String,
+    #[serde(flatten)]
+    ping: HistoricalPing,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackQuery {
+    pub boat_id: String,
+    pub race_id: Option<String>,
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Track {
+    pub boat_id: String,
+    pub race_id: String,
+    pub points: Vec<HistoricalPing>,
+}
+
+/// The race a point belongs to; the classic single race is `race-1`, as in `race_session`.
+pub fn race_key(state: &RaceState) -> String {
+    state.active_race_id.clone().unwrap_or_else(|| "race-1".to_string())
+}
+
+fn path(race_id: &str) -> PathBuf {
+    // Ids are generated server-side, but never let one escape the directory
+    let name: String = race_id.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_').collect();
+    CONFIG.dir.join(format!("{name}.jsonl"))
+}
+
+/// Add a position to the boat's trail if it is due, dropping points older than
+/// the window. Returns the point to `append` once the lock is released.
+pub fn sample(state: &mut RaceState, boat_id: &str, timestamp: i64, lat: f64, lon: f64) -> Option<HistoricalPing> {
+    let hist = state.fleet_history.entry(boat_id.to_string()).or_default();
+    if hist.last().is_some_and(|p| timestamp - p.timestamp < CONFIG.sample_ms) {
+        return None;
+    }
+    let ping = HistoricalPing { timestamp, lat, lon };
+    hist.push(ping.clone());
+    let cutoff = timestamp - CONFIG.window_ms;
+    let stale = hist.iter().take_while(|p| p.timestamp < cutoff).count();
+    hist.drain(..stale);
+    Some(ping)
+}
+
+/// Append a sampled point to the race's track file.
+pub async fn append(race_id: &str, boat_id: &str, ping: HistoricalPing) {
+    let Ok(mut line) = serde_json::to_string(&StoredPing { boat_id: boat_id.to_string(), ping }) else { return };
+    line.push('\n');
+    let _guard = FILE.lock().await;
+    let written = async {
+        tokio::fs::create_dir_all(&CONFIG.dir).await?;
+        let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(path(race_id)).await?;
+        file.write_all(line.as_bytes()).await
+    }.await;
+    if let Err(e) = written {
+        debug!("TrackStore: append for {race_id} failed: {e}");
+    }
+}
+
+async fn read_race(race_id: &str) -> Vec<StoredPing> {
+    let data = {
+        let _guard = FILE.lock().await;
+        tokio::fs::read_to_string(path(race_id)).await.unwrap_or_default()
+    };
+    data.lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| serde_json::from_str::<StoredPing>(l).map_err(|e| warn!("TrackStore: skipping unreadable line: {e}")).ok())
+        .collect()
+}
+
+/// A boat's stored track in `race_id`, oldest first, limited to the query's time range.
+pub async fn query(query: &TrackQuery, race_id: String) -> Track {
+    let mut points: Vec<HistoricalPing> = read_race(&race_id).await.into_iter()
+        .filter(|p| p.boat_id == query.boat_id)
+        .map(|p| p.ping)
+        .filter(|p| query.from_ms.is_none_or(|from| p.timestamp >= from))
+        .filter(|p| query.to_ms.is_none_or(|to| p.timestamp <= to))
+        .collect();
+    points.sort_by_key(|p| p.timestamp);
+    Track { boat_id: query.boat_id.clone(), race_id, points }
+}
+
+/// Refill `fleet_history` with the active race's recent points after a restart.
+pub async fn restore(state: &mut RaceState) {
+    let race_id = race_key(state);
+    let stored = read_race(&race_id).await;
+    let Some(latest) = stored.iter().map(|p| p.ping.timestamp).max() else { return };
+    let cutoff = latest - CONFIG.window_ms;
+    state.fleet_history.clear();
+    for p in stored.into_iter().filter(|p| p.ping.timestamp >= cutoff) {
+        state.fleet_history.entry(p.boat_id).or_default().push(p.ping);
+    }
+    for hist in state.fleet_history.values_mut() {
+        hist.sort_by_key(|p| p.timestamp);
+    }
+    info!("TrackStore: restored trails of {} boat(s) for {race_id}", state.fleet_history.len());
+}
@@ -11,8 +11,14 @@
 //! validation_protocol.json:
 //! - Invariant #1: this solver is the path to ≤1 cm batch accuracy
 //! - Invariant #2: batch solve result is audit-logged (AuditEventType::UwbGunSolve)
+//!
+//! Replay determinism:
+//!   `SolveMode::Deterministic` canonicalizes measurement order before solving so
+//!   that replaying audit-logged measurements yields bit-identical positions.
+//!   `SolveMode::DeterministicFixedPoint` additionally accumulates the normal
+//!   equations in integer fixed-point, making the sums order-independent.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use serde::{Deserialize, Serialize};
 
 use uwb_types::PeerReport;
@@ -20,7 +26,7 @@ use uwb_types::PeerReport;
 // ── Types ─────────────────────────────────────────────────────────────────────
 
 /// A range measurement between two nodes
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RangeMeasurement {
     pub node_i: u32,
     pub node_j: u32,
@@ -29,6 +35,19 @@ pub struct RangeMeasurement {
     pub nlos: bool,
 }
 
+impl RangeMeasurement {
+    /// Range edge from `node_id` to the peer in one of its DS-TWR reports.
+    pub fn from_peer_report(node_id: u32, report: &PeerReport) -> Self {
+        Self {
+            node_i: node_id,
+            node_j: report.peer_id,
+            range_m: report.range_m(),
+            sigma_m: report.sigma_range_m(),
+            nlos: report.is_nlos(),
+        }
+    }
+}
+
 /// 2D position (line frame)
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Pos2D {
@@ -36,11 +55,34 @@ pub struct Pos2D {
     pub y: f32,   // perpendicular to line (North = OCS side)
 }
 
+/// Solver reproducibility mode.
+///
+/// `Live` is the default used during racing. The deterministic modes are used
+/// when re-solving audit-logged measurements for protest replay, where the
+/// result must be bit-identical across runs regardless of packet arrival order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SolveMode {
+    /// Measurements processed in arrival order, f64 accumulation
+    #[default]
+    Live,
+    /// Measurements sorted into canonical order before solving
+    Deterministic,
+    /// Canonical order + fixed-point (i128) accumulation of the normal equations
+    DeterministicFixedPoint,
+}
+
+impl SolveMode {
+    pub fn is_deterministic(&self) -> bool {
+        !matches!(self, SolveMode::Live)
+    }
+}
+
 /// Result from multilateration solve
 #[derive(Debug, Clone, Serialize)]
 pub struct MultilaterationResult {
-    /// Estimated 2D positions per node_id
-    pub positions: HashMap<u32, Pos2D>,
+    /// Estimated 2D positions per node_id (ordered by node_id for stable serialization)
+    pub positions: BTreeMap<u32, Pos2D>,
     /// RMS residual after convergence (meters) — target < 0.05m per epoch
     pub rms_residual_m: f32,
     /// Number of Gauss-Newton iterations taken
@@ -68,6 +110,110 @@ impl AnchorMap {
     pub fn is_anchor(&self, node_id: u32) -> bool { self.positions.contains_key(&node_id) }
 }
 
+impl FromIterator<(u32, [f32; 2])> for AnchorMap {
+    fn from_iter<I: IntoIterator<Item = (u32, [f32; 2])>>(iter: I) -> Self {
+        Self { positions: iter.into_iter().collect() }
+    }
+}
+
+// ── Deterministic accumulation helpers ────────────────────────────────────────
+
+/// Fixed-point scale for normal-equation accumulation (2^40 ≈ 1e-12 resolution).
+/// Each term is quantized independently, so integer addition makes the sum
+/// independent of measurement order.
+const FIXED_POINT_SCALE: f64 = (1u64 << 40) as f64;
+
+/// Normal-equation accumulator: AᵀWA (2x2), AᵀWr (2x1) and Σ residual².
+enum NormalAccumulator {
+    Float {
+        atwa: [[f64; 2]; 2],
+        atwb: [f64; 2],
+        sum_sq_res: f64,
+    },
+    Fixed {
+        atwa: [[i128; 2]; 2],
+        atwb: [i128; 2],
+        sum_sq_res: i128,
+    },
+}
+
+impl NormalAccumulator {
+    fn new(mode: SolveMode) -> Self {
+        match mode {
+            SolveMode::DeterministicFixedPoint => Self::Fixed {
+                atwa: [[0; 2]; 2],
+                atwb: [0; 2],
+                sum_sq_res: 0,
+            },
+            _ => Self::Float {
+                atwa: [[0.0; 2]; 2],
+                atwb: [0.0; 2],
+                sum_sq_res: 0.0,
+            },
+        }
+    }
+
+    fn quantize(v: f64) -> i128 {
+        (v * FIXED_POINT_SCALE).round() as i128
+    }
+
+    fn add(&mut self, w: f64, jx: f64, jy: f64, residual: f64) {
+        match self {
+            Self::Float { atwa, atwb, sum_sq_res } => {
+                atwa[0][0] += w * jx * jx;
+                atwa[0][1] += w * jx * jy;
+                atwa[1][0] += w * jy * jx;
+                atwa[1][1] += w * jy * jy;
+                atwb[0] += w * jx * residual;
+                atwb[1] += w * jy * residual;
+                *sum_sq_res += residual * residual;
+            }
+            Self::Fixed { atwa, atwb, sum_sq_res } => {
+                atwa[0][0] += Self::quantize(w * jx * jx);
+                atwa[0][1] += Self::quantize(w * jx * jy);
+                atwa[1][0] += Self::quantize(w * jy * jx);
+                atwa[1][1] += Self::quantize(w * jy * jy);
+                atwb[0] += Self::quantize(w * jx * residual);
+                atwb[1] += Self::quantize(w * jy * residual);
+                *sum_sq_res += Self::quantize(residual * residual);
+            }
+        }
+    }
+
+    /// Returns (AᵀWA, AᵀWr, Σ residual²) as f64.
+    fn finish(&self) -> ([[f64; 2]; 2], [f64; 2], f64) {
+        match self {
+            Self::Float { atwa, atwb, sum_sq_res } => (*atwa, *atwb, *sum_sq_res),
+            Self::Fixed { atwa, atwb, sum_sq_res } => {
+                let f = |v: i128| v as f64 / FIXED_POINT_SCALE;
+                (
+                    [[f(atwa[0][0]), f(atwa[0][1])], [f(atwa[1][0]), f(atwa[1][1])]],
+                    [f(atwb[0]), f(atwb[1])],
+                    f(*sum_sq_res),
+                )
+            }
+        }
+    }
+}
+
+/// Sort measurements into a canonical order independent of packet arrival.
+/// Pairs are keyed by (min node, max node) then by the exact bit patterns of
+/// range and sigma, so duplicates collapse to a stable relative order.
+fn canonical_order(measurements: &[RangeMeasurement]) -> Vec<RangeMeasurement> {
+    let mut sorted = measurements.to_vec();
+    sorted.sort_by_key(|m| {
+        (
+            m.node_i.min(m.node_j),
+            m.node_i.max(m.node_j),
+            m.node_i,
+            m.range_m.to_bits(),
+            m.sigma_m.to_bits(),
+            m.nlos,
+        )
+    });
+    sorted
+}
+
 // ── WLS Multilateration ───────────────────────────────────────────────────────
 
 /// Huber loss weight: down-weight large residuals (robust to NLOS outliers)
@@ -99,6 +245,28 @@ pub fn solve(
     max_iter: u32,
     converge_threshold: f32,
 ) -> Option<MultilaterationResult> {
+    solve_with_mode(measurements, anchors, initial_guess, antenna_offsets, max_iter, converge_threshold, SolveMode::Live)
+}
+
+/// WLS multilateration solve with an explicit reproducibility mode.
+/// See [`SolveMode`] — deterministic modes are used for audit replay.
+pub fn solve_with_mode(
+    measurements: &[RangeMeasurement],
+    anchors: &AnchorMap,
+    initial_guess: &HashMap<u32, Pos2D>,
+    antenna_offsets: &HashMap<u32, Pos2D>,
+    max_iter: u32,
+    converge_threshold: f32,
+    mode: SolveMode,
+) -> Option<MultilaterationResult> {
+    let canonical;
+    let measurements = if mode.is_deterministic() {
+        canonical = canonical_order(measurements);
+        &canonical[..]
+    } else {
+        measurements
+    };
+
     // Collect all unique unknown node IDs
     let unknown_ids: Vec<u32> = {
         let mut ids = std::collections::BTreeSet::new();
@@ -112,7 +280,7 @@ pub fn solve(
     if unknown_ids.is_empty() { return None; }
 
     // Initialize position estimates
-    let mut positions: HashMap<u32, [f32; 2]> = HashMap::new();
+    let mut positions: BTreeMap<u32, [f32; 2]> = BTreeMap::new();
     for &id in &unknown_ids {
         let guess = initial_guess.get(&id).copied()
             .unwrap_or(Pos2D { x: 0.0, y: -50.0 });  // default: 50m under line
@@ -129,15 +297,14 @@ pub fn solve(
         final_iter = iter + 1;
         let mut max_update = 0.0f32;
         n_rejected = 0;
-        let mut sum_sq_res = 0.0f32;
+        let mut sum_sq_res = 0.0f64;
         let mut n_used = 0u32;
 
         // For each unknown node: solve its position given all measurements to other nodes
         for &id_i in &unknown_ids {
             let pi = positions[&id_i];
-            // Gather measurements involving this node
-            let mut atwa = [[0.0f64; 2]; 2];  // 2x2 normal matrix
-            let mut atwb = [0.0f64; 2];       // 2x1 RHS
+            // Gather measurements involving this node (AᵀWA 2x2 normal matrix, AᵀWr 2x1 RHS)
+            let mut acc = NormalAccumulator::new(mode);
 
             for m in measurements {
                 // Is this measurement relevant to node id_i?
@@ -165,7 +332,8 @@ pub fn solve(
                 let residual = m.range_m - dist;
 
                 // Mahalanobis gate (reject egregious outliers)
-                let mahal = (residual / m.sigma_m).powi(2);
+                let normalized = residual / m.sigma_m;
+                let mahal = normalized * normalized;
                 if mahal > MAHAL_GATE {
                     n_rejected += 1;
                     continue;
@@ -173,7 +341,6 @@ pub fn solve(
 
                 // Huber weight
                 let w = huber_weight(residual, m.sigma_m, 0.15) as f64;
-                sum_sq_res += residual * residual;
                 n_used += 1;
 
                 // Jacobian: ∂f/∂x = (pi-pj)/||pi-pj||
@@ -181,14 +348,12 @@ pub fn solve(
                 let jy = (dy / dist) as f64;
 
                 // Normal equations: AᵀWA * δp = AᵀWr
-                atwa[0][0] += w * jx * jx;
-                atwa[0][1] += w * jx * jy;
-                atwa[1][0] += w * jy * jx;
-                atwa[1][1] += w * jy * jy;
-                atwb[0] += w * jx * residual as f64;
-                atwb[1] += w * jy * residual as f64;
+                acc.add(w, jx, jy, residual as f64);
             }
 
+            let (atwa, atwb, node_sq_res) = acc.finish();
+            sum_sq_res += node_sq_res;
+
             // Solve 2x2 system (Cramer's rule — fast for 2D)
             let det = atwa[0][0] * atwa[1][1] - atwa[0][1] * atwa[1][0];
             if det.abs() < 1e-10 { continue; }  // singular — not enough measurements
@@ -204,7 +369,7 @@ pub fn solve(
             ]);
         }
 
-        final_rms = if n_used > 0 { (sum_sq_res / n_used as f32).sqrt() } else { 0.0 };
+        final_rms = if n_used > 0 { (sum_sq_res / n_used as f64).sqrt() as f32 } else { 0.0 };
 
         if max_update < converge_threshold {
             converged = true;
@@ -212,7 +377,7 @@ pub fn solve(
         }
     }
 
-    let result_positions: HashMap<u32, Pos2D> = positions.iter()
+    let result_positions: BTreeMap<u32, Pos2D> = positions.iter()
         .map(|(&id, &p)| (id, Pos2D { x: p[0], y: p[1] }))
         .collect();
 
@@ -229,17 +394,20 @@ pub fn solve(
 /// Batch solve: accumulate measurements from multiple epochs, solve jointly.
 /// This is the 2-second high-density solve triggered at gun (T-0).
 /// Invariant #1: batch accuracy target ≤ 1 cm absolute.
+///
+/// Pass a deterministic `mode` when re-solving audit-logged epochs for protest replay.
 pub fn batch_solve(
     epochs: &[Vec<RangeMeasurement>],
     anchors: &AnchorMap,
     initial_guess: &HashMap<u32, Pos2D>,
     antenna_offsets: &HashMap<u32, Pos2D>,
+    mode: SolveMode,
 ) -> Option<MultilaterationResult> {
     // Flatten all epoch measurements
     let all: Vec<RangeMeasurement> = epochs.iter().flat_map(|e| e.iter().cloned()).collect();
     // More measurements → better convergence and accuracy
     // With 40 epochs × 15 boats × 5 measurements = ~3000 ranges, expect σ_batch ≈ 1cm
-    solve(&all, anchors, initial_guess, antenna_offsets, 20, 0.001)
+    solve_with_mode(&all, anchors, initial_guess, antenna_offsets, 20, 0.001, mode)
 }
 
 // ── OCS determination from solve result ───────────────────────────────────────
@@ -248,6 +416,7 @@ pub fn batch_solve(
 /// OCS = y_line_m > ocs_threshold AND fix_quality >= min_quality
 /// invariant_ref: #1 (≤1 cm accuracy means OCS call is reliable)
 /// invariant_ref: #2 (all OCS detections logged via AuditLogger)
+#[derive(Debug, Clone, Serialize)]
 pub struct OcsDetection {
     pub node_id: u32,
     pub y_line_m: f32,   // positive = OCS side
@@ -7,10 +7,19 @@
 //! This module runs as a separate Tokio task (tokio::spawn) alongside the
 //! Socket.IO handler. It:
 //!   1. Binds UDP socket on port 5555 (configurable via UWB_UDP_PORT env)
-//!   2. Receives MeasurementPackets (JSON envelope for now, binary wire later)
-//!   3. Validates sequence numbers (replay detection)
+//!   2. Receives MeasurementPackets: the JSON envelope, or the binary wire
+//!      format of uwb-types (first byte not `{`)
+//!   3. Checks the node's HMAC tag or opens its AES-CCM seal (`node_auth`),
+//!      validates sequence numbers
+//!      (replay detection) and hands raw packets to the `MeasurementRecorder`
+//!      for compressed audit batches
 //!   4. Extracts fused position data for integration with RaceState
-//!   5. Broadcasts updated boat positions via Socket.IO state-update
+//!   5. Multicasts the fused positions, signed by the hub, to on-water clients
+//!      and sends signed commands down to nodes (`control_plane`)
+//!   6. Keeps the last `GUN_WINDOW_MS` of raw ranges; at the main starting signal
+//!      (`GunSignal`) batch-solves them, appends the inputs as a `UWB_GUN_SOLVE`
+//!      block (`GunSolvePayload`, re-run by `protest_replay`) and reports the
+//!      solved OCS boats
 //!
 //! ## Phase progression
 //! - Phase 2 (now): JSON envelope, software-simulated positions, basic OCS detection
@@ -21,7 +30,7 @@
 //! - Core Invariant #2: all OCS detections logged to audit chain
 //! - Core Invariant #8: zero race interruption — UDP errors never crash the server
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::net::SocketAddr;
 use std::sync::Arc;
 
@@ -29,13 +38,28 @@ use serde::{Deserialize, Serialize};
 use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
+use uwb_types::{MeasurementPacket, PeerReport};
+
+use crate::audit::AuditLogger;
+use crate::control_plane::{self, ControlError, ControlPlaneConfig, Downlink, HubSigner};
+use crate::line_bias::AnchorFix;
+use crate::measurement_recorder::MeasurementRecorder;
+use crate::node_auth::{split_tag, AuthReject, NodeAuth};
+use crate::protest_replay::GunSolvePayload;
+use crate::state::LatLon;
+use crate::trilateration::{self, AnchorMap, Pos2D, RangeMeasurement, SolveMode};
+
+/// Raw ranges kept for the gun batch solve
+const GUN_WINDOW_MS: u64 = 2000;
+/// Ranges are grouped into epochs of one superframe
+const EPOCH_MS: u64 = 50;
 
 // ── Configuration ─────────────────────────────────────────────────────────────
 
 pub struct UwbHubConfig {
     /// UDP port to listen on (default 5555)
     pub udp_port: u16,
-    /// Multicast group (default 239.255.0.1)
+    /// Multicast group for signed fused positions (default 239.255.0.1)
     pub multicast_group: String,
     /// OCS threshold in meters (default 0.10 = 10 cm)
     pub ocs_threshold_m: f32,
@@ -81,14 +105,37 @@ pub struct UwbMeasurementEnvelope {
     /// Optional: anchor GPS pos (for TacticalMap integration)
     pub lat: Option<f64>,
     pub lon: Option<f64>,
+    /// Optional raw DS-TWR ranges behind the fused position (gun batch solve)
+    #[serde(default)]
+    pub peers: Vec<EnvelopePeer>,
 }
 
-/// Fused position packet broadcast back to all clients via UDP multicast.
-#[derive(Debug, Serialize)]
-pub struct FusedPositionBroadcast {
-    pub epoch_ms: u64,
-    pub nodes: Vec<FusedNode>,
-    pub batch_mode: bool,
+/// One raw range in the JSON envelope
+#[derive(Debug, Deserialize)]
+pub struct EnvelopePeer {
+    pub peer_id: u32,
+    pub range_m: f32,
+    #[serde(default)]
+    pub snr_db10: i32,
+    #[serde(default)]
+    pub fp_index: u8,
+    #[serde(default)]
+    pub nlos: bool,
+}
+
+impl EnvelopePeer {
+    /// As the node would report it in a binary packet
+    fn to_report(&self) -> PeerReport {
+        PeerReport {
+            peer_id: self.peer_id,
+            range_mm: (self.range_m * 1000.0).round() as i32,
+            azimuth_deg10: 0,
+            elevation_deg10: 0,
+            cir_snr_db10: self.snr_db10.max(0) as u16,
+            fp_index: self.fp_index,
+            quality_flags: self.nlos as u8,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -146,6 +193,123 @@ impl SeqTracker {
     }
 }
 
+// ── Gun batch solve ───────────────────────────────────────────────────────────
+
+/// The main sequence fired its starting signal (sent by `engine_bus::run_gun_solve`).
+#[derive(Debug, Clone, Copy)]
+pub struct GunSignal {
+    pub gun_ms: u64,
+}
+
+/// What the gun batch solve runs on: the last `GUN_WINDOW_MS` of raw ranges in
+/// `EPOCH_MS` epochs, and every node's latest fused position and role.
+#[derive(Default)]
+struct GunWindow {
+    epochs: VecDeque<(u64, Vec<RangeMeasurement>)>,
+    positions: HashMap<u32, (Pos2D, u8)>,
+    designations: HashMap<u32, u8>,
+}
+
+impl GunWindow {
+    fn push_ranges(&mut self, now_ms: u64, ranges: impl IntoIterator<Item = RangeMeasurement>) {
+        let epoch = now_ms - now_ms % EPOCH_MS;
+        match self.epochs.back_mut() {
+            Some((start, epoch_ranges)) if *start == epoch => epoch_ranges.extend(ranges),
+            _ => self.epochs.push_back((epoch, ranges.into_iter().collect())),
+        }
+        while self.epochs.front().is_some_and(|(start, _)| start + GUN_WINDOW_MS < now_ms) {
+            self.epochs.pop_front();
+        }
+    }
+
+    fn note_node(&mut self, node_id: u32, designation: u8, position: Option<(Pos2D, u8)>) {
+        self.designations.insert(node_id, designation);
+        if let Some(position) = position {
+            self.positions.insert(node_id, position);
+        }
+    }
+
+    /// Solve inputs at the gun. Anchors are the marks and committee boat at their
+    /// last fused position; boats start from theirs. Antenna and bow offsets are
+    /// not known in the line frame here and are left out.
+    fn payload(&self, gun_ms: u64, config: &UwbHubConfig) -> GunSolvePayload {
+        let is_anchor = |id: u32| self.designations.get(&id).is_some_and(|d| *d != 0);
+        GunSolvePayload {
+            epoch_ms: gun_ms,
+            anchors: self.positions.iter().filter(|(id, _)| is_anchor(**id)).map(|(&id, (p, _))| (id, [p.x, p.y])).collect(),
+            epochs: self.epochs.iter().map(|(_, ranges)| ranges.clone()).collect(),
+            initial_guess: self.positions.iter().filter(|(id, _)| !is_anchor(**id)).map(|(&id, (p, _))| (id, *p)).collect(),
+            antenna_offsets: HashMap::new(),
+            bow_offsets_m: HashMap::new(),
+            fix_qualities: self.positions.iter().map(|(&id, (_, q))| (id, *q)).collect(),
+            ocs_threshold_m: config.ocs_threshold_m,
+            min_fix_quality: config.min_fix_quality,
+            reported_ocs: Vec::new(),
+        }
+    }
+}
+
+/// Batch-solve at the gun, append the inputs and the call to the audit chain,
+/// then report the solved boats. Solved in the deterministic mode protest
+/// replay uses, so a replay reproduces the live call exactly.
+async fn run_gun_solve(mut payload: GunSolvePayload, audit: AuditLogger, ocs_tx: mpsc::Sender<OcsEvent>) {
+    let n_epochs = payload.epochs.len();
+    let solved = tokio::task::spawn_blocking(move || {
+        let anchors: AnchorMap = payload.anchors.iter().map(|(&id, &p)| (id, p)).collect();
+        let result = trilateration::batch_solve(
+            &payload.epochs,
+            &anchors,
+            &payload.initial_guess,
+            &payload.antenna_offsets,
+            SolveMode::DeterministicFixedPoint,
+        );
+        let detections = result.as_ref().map(|result| trilateration::detect_ocs(
+            result,
+            &payload.fix_qualities,
+            &payload.bow_offsets_m,
+            payload.ocs_threshold_m,
+            payload.min_fix_quality,
+        )).unwrap_or_default();
+        payload.reported_ocs = detections.iter().map(|d| d.node_id).collect();
+        payload.reported_ocs.sort_unstable();
+        (payload, result)
+    }).await;
+    let Ok((payload, result)) = solved else {
+        warn!("UWB: gun batch solve panicked");
+        return;
+    };
+
+    // Core Invariant #2: the inputs are on the chain before anything acts on them
+    match serde_json::to_value(&payload) {
+        Ok(block) => audit.log_uwb_gun_solve(block).await,
+        Err(e) => warn!("UWB: gun solve inputs not audited: {e}"),
+    }
+    let Some(result) = result else {
+        warn!("UWB: gun batch solve found no solution over {n_epochs} epochs");
+        return;
+    };
+    info!(
+        "🎯 UWB gun batch solve: {} nodes, {} ranges over {n_epochs} epochs, rms {:.3} m, OCS {:?}",
+        result.positions.len(), result.n_measurements, result.rms_residual_m, payload.reported_ocs,
+    );
+
+    let boats = result.positions.iter()
+        .filter(|(id, _)| payload.initial_guess.contains_key(*id))
+        .map(|(&node_id, pos)| FusedNode {
+            node_id,
+            x_line_m: pos.x,
+            y_line_m: pos.y,
+            vx_line_mps: 0.0,
+            vy_line_mps: 0.0,
+            heading_deg: 0.0,
+            fix_quality: payload.fix_qualities.get(&node_id).copied().unwrap_or(0),
+            is_ocs: payload.reported_ocs.contains(&node_id),
+            dtl_cm: pos.y * 100.0,
+        })
+        .collect();
+    let _ = ocs_tx.send(OcsEvent { epoch_ms: crate::time_discipline::now_ms(), boats }).await;
+}
+
 // ── OCS Event channel message ─────────────────────────────────────────────────
 
 pub struct OcsEvent {
@@ -161,6 +325,14 @@ pub struct OcsEvent {
 pub async fn start_uwb_hub(
     config: UwbHubConfig,
     ocs_tx: mpsc::Sender<OcsEvent>,
+    anchor_tx: mpsc::Sender<AnchorFix>,
+    recorder: MeasurementRecorder,
+    node_auth: Arc<NodeAuth>,
+    control: ControlPlaneConfig,
+    signer: Arc<HubSigner>,
+    mut downlink_rx: mpsc::Receiver<Downlink>,
+    audit: AuditLogger,
+    mut gun_rx: mpsc::Receiver<GunSignal>,
 ) {
     let addr = format!("0.0.0.0:{}", config.udp_port);
     let socket = match UdpSocket::bind(&addr).await {
@@ -175,20 +347,66 @@ pub async fn start_uwb_hub(
         }
     };
 
+    let multicast_addr = match format!("{}:{}", config.multicast_group, control.multicast_port).parse::<SocketAddr>() {
+        Ok(addr) => Some(addr),
+        Err(e) => {
+            warn!("UWB Hub: invalid multicast group {}: {e}, fused positions not multicast", config.multicast_group);
+            None
+        }
+    };
+
     let mut seq_tracker = SeqTracker::new();
     let mut buf = vec![0u8; 4096];
-    let ocs_threshold = config.ocs_threshold_m;
-    let min_quality = config.min_fix_quality;
+    // Where each node last sent an authenticated packet from (downlink target)
+    let mut node_addrs: HashMap<u32, SocketAddr> = HashMap::new();
+    // Nodes fused since the last multicast
+    let mut fused: HashMap<u32, FusedNode> = HashMap::new();
+    let mut batch_mode = false;
+    let mut gun_window = GunWindow::default();
+    let mut multicast_tick = tokio::time::interval(control.multicast_interval);
+    multicast_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
     loop {
-        match socket.recv_from(&mut buf).await {
-            Ok((len, src)) => {
-                process_packet(&buf[..len], src, &mut seq_tracker, ocs_threshold, min_quality, &ocs_tx).await;
-            }
-            Err(e) => {
-                // Never crash — log and continue
-                warn!("UWB Hub: UDP recv error: {e}");
-            }
+        tokio::select! {
+            received = socket.recv_from(&mut buf) => match received {
+                Ok((len, src)) => {
+                    let accepted = process_packet(&buf[..len], src, &mut seq_tracker, &config, &ocs_tx, &anchor_tx, &recorder, &node_auth, &mut node_addrs, &mut gun_window).await;
+                    if let Some((node, batch)) = accepted {
+                        batch_mode |= batch;
+                        fused.insert(node.node_id, node);
+                    }
+                }
+                Err(e) => {
+                    // Never crash — log and continue
+                    warn!("UWB Hub: UDP recv error: {e}");
+                }
+            },
+            Some(downlink) = downlink_rx.recv() => {
+                let node_id = downlink.node_id;
+                let result = match control_plane::control_packet(&signer, &node_auth, &node_addrs, node_id, downlink.command) {
+                    Ok((seq, addr, packet)) => socket.send_to(&packet, addr).await
+                        .map(|_| seq)
+                        .map_err(|e| ControlError::Send(node_id, e.to_string())),
+                    Err(e) => Err(e),
+                };
+                match &result {
+                    Ok(seq) => info!("UWB Hub: {:?} → node {node_id} (seq {seq})", downlink.command),
+                    Err(e) => warn!("UWB Hub: command to node {node_id} not sent: {e}"),
+                }
+                let _ = downlink.reply.send(result);
+            },
+            Some(gun) = gun_rx.recv() => {
+                let payload = gun_window.payload(gun.gun_ms, &config);
+                tokio::spawn(run_gun_solve(payload, audit.clone(), ocs_tx.clone()));
+            },
+            _ = multicast_tick.tick() => {
+                let Some(multicast_addr) = multicast_addr.filter(|_| !fused.is_empty()) else { continue };
+                let nodes: Vec<FusedNode> = fused.drain().map(|(_, node)| node).collect();
+                let packet = signer.fused_packet(nodes, std::mem::take(&mut batch_mode));
+                if let Err(e) = socket.send_to(&packet, multicast_addr).await {
+                    debug!("UWB Hub: multicast send failed: {e}");
+                }
+            },
         }
     }
 }
@@ -197,72 +415,135 @@ async fn process_packet(
     data: &[u8],
     src: SocketAddr,
     seq_tracker: &mut SeqTracker,
-    ocs_threshold: f32,
-    min_quality: u8,
+    config: &UwbHubConfig,
     ocs_tx: &mpsc::Sender<OcsEvent>,
-) {
-    // Phase 2: JSON envelope. Phase 6: switch to binary C struct parsing.
-    let env: UwbMeasurementEnvelope = match serde_json::from_slice(data) {
+    anchor_tx: &mpsc::Sender<AnchorFix>,
+    recorder: &MeasurementRecorder,
+    node_auth: &NodeAuth,
+    node_addrs: &mut HashMap<u32, SocketAddr>,
+    gun_window: &mut GunWindow,
+) -> Option<(FusedNode, bool)> {
+    if data.first() != Some(&b'{') {
+        process_binary(data, src, seq_tracker, recorder, node_auth, node_addrs, gun_window);
+        return None;
+    }
+    // The tag covers the body bytes as received; parse only what it covers
+    let (body, tag) = split_tag(data);
+    let doc = match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(doc)) => doc,
+        Ok(_) => {
+            debug!("UWB: malformed packet from {src}: not a JSON object");
+            return None;
+        }
+        Err(e) => {
+            debug!("UWB: malformed packet from {src}: {e}");
+            return None;
+        }
+    };
+    let Some(node_id) = doc.get("node_id").and_then(|v| v.as_u64()).map(|id| id as u32) else {
+        debug!("UWB: packet from {src} has no node_id");
+        return None;
+    };
+    if let Err(reason) = node_auth.verify(body, tag, node_id) {
+        node_auth.record_rejection(src, node_id, reason);
+        return None;
+    }
+    let data = serde_json::Value::Object(doc);
+
+    // Raw MeasurementPacket (ranges, no fused position) — audit only
+    if let Ok(packet) = MeasurementPacket::deserialize(&data) {
+        if seq_tracker.accept(packet.node_id, packet.seq_num) {
+            // Only fresh packets move the downlink target, a replay cannot redirect it
+            node_addrs.insert(node_id, src);
+            note_packet(gun_window, &packet);
+            recorder.record(packet);
+        }
+        return None;
+    }
+
+    // Phase 2 JSON envelope with a fused position
+    let env = match UwbMeasurementEnvelope::deserialize(&data) {
         Ok(e) => e,
         Err(e) => {
             debug!("UWB: malformed packet from {src}: {e}");
-            return;
+            return None;
         }
     };
 
     // Replay protection
     if !seq_tracker.accept(env.node_id, env.seq_num) {
-        return;
+        return None;
+    }
+    node_addrs.insert(node_id, src);
+
+    // Anchors report their GPS fix; `line_bias` picks out the start-line ends
+    if let (Some(lat), Some(lon)) = (env.lat, env.lon) {
+        let _ = anchor_tx.try_send(AnchorFix { node_id: env.node_id, pos: LatLon { lat, lon } });
     }
 
-    let node = FusedNode::from_envelope(&env, ocs_threshold, min_quality);
+    let node = FusedNode::from_envelope(&env, config.ocs_threshold_m, config.min_fix_quality);
+    gun_window.note_node(env.node_id, env.designation, Some((Pos2D { x: env.x_line_m, y: env.y_line_m }, env.fix_quality)));
+    gun_window.push_ranges(
+        crate::time_discipline::now_ms(),
+        env.peers.iter().map(|p| RangeMeasurement::from_peer_report(env.node_id, &p.to_report())),
+    );
     debug!("UWB: node {} → DTL={:.1}cm (OCS={})", env.node_id, node.dtl_cm, node.is_ocs);
 
     // If any OCS boats detected, forward to the event channel
     if node.is_ocs || env.batch_mode {
-        let epoch_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
+        let epoch_ms = crate::time_discipline::now_ms();
 
         // Collect into a single OCS event (Phase 6: aggregate all nodes in epoch)
         let _ = ocs_tx.try_send(OcsEvent {
             epoch_ms,
-            boats: vec![node],
+            boats: vec![node.clone()],
         });
     }
+    Some((node, env.batch_mode))
 }
 
-/// Triggers the 2-second concurrent batch solve algorithm.
-/// This is called explicitly by the ProcedureEngine at the exact moment of the Gun (T-0).
-/// In SNPN mode, this uses the Thunderbolt-connected raw UWB ranges. 
-pub async fn trigger_batch_solve(ocs_tx: &mpsc::Sender<OcsEvent>) {
-    info!("🎯 T-0 GUN FIRED: Executing UWB Concurrent Batch Solve for OCS Detection");
-    // In Phase 6, this will gather the last 2 seconds of buffered `MeasurementPackets`
-    // and feed them into `trilateration::batch_solve(epochs, anchors, guess)`.
-    
-    // For Phase 3 implementation, we simulate the output of the batch solve
-    // to strictly verify the pipeline from ProcedureEngine -> UWB Hub -> Audit Log -> IO Emit.
-    
-    let mock_ocs_node = FusedNode {
-        node_id: 99, // Represents a simulated boat
-        x_line_m: 10.5,
-        y_line_m: 0.25, // 25cm over the line
-        vx_line_mps: 2.1,
-        vy_line_mps: 0.0,
-        heading_deg: 90.0,
-        fix_quality: 95,
-        is_ocs: true,
-        dtl_cm: 25.0,
+/// Binary `MeasurementPacket` (raw ranges, no fused position) — audit only.
+fn process_binary(
+    data: &[u8],
+    src: SocketAddr,
+    seq_tracker: &mut SeqTracker,
+    recorder: &MeasurementRecorder,
+    node_auth: &NodeAuth,
+    node_addrs: &mut HashMap<u32, SocketAddr>,
+    gun_window: &mut GunWindow,
+) {
+    let Some((node_id, sealed)) = MeasurementPacket::wire_header(data) else {
+        debug!("UWB: malformed packet from {src}: unknown binary version");
+        return;
     };
-    
-    let epoch_ms = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64;
-
-    let _ = ocs_tx.try_send(OcsEvent {
-        epoch_ms,
-        boats: vec![mock_ocs_node], // Dispatching the batch result
-    });
+    let key = match node_auth.packet_key(node_id, sealed) {
+        Ok(key) => key,
+        Err(reason) => {
+            node_auth.record_rejection(src, node_id, reason);
+            return;
+        }
+    };
+    let Some(packet) = MeasurementPacket::decode(data, key.as_ref()) else {
+        // A CRC failure cannot be told from a forged seal; count it against the source
+        if sealed {
+            node_auth.record_rejection(src, node_id, AuthReject::BadTag);
+        } else {
+            debug!("UWB: malformed binary packet from {src} (node {node_id})");
+        }
+        return;
+    };
+    if seq_tracker.accept(packet.node_id, packet.seq_num) {
+        node_addrs.insert(node_id, src);
+        note_packet(gun_window, &packet);
+        recorder.record(packet);
+    }
+}
+
+/// A raw packet's ranges go into the gun window; it carries no position.
+fn note_packet(gun_window: &mut GunWindow, packet: &MeasurementPacket) {
+    gun_window.note_node(packet.node_id, packet.designation as u8, None);
+    gun_window.push_ranges(
+        crate::time_discipline::now_ms(),
+        packet.reports.iter().map(|r| RangeMeasurement::from_peer_report(packet.node_id, r)),
+    );
 }
@@ -0,0 +1,297 @@
+//! # vision_switcher
+//!
+//! Cuts the program feed from the auto-director's choices, so the
+//! "SRS Auto-Director" drives a real vision mixer rather than just emitting JSON.
+//!
+//! The program camera is the one on the focus boat that matters most (incident
+//! boats first), else a wide shot (see `camera_assignment`). When it changes — at
+//! most once every `SWITCHER_MIN_CUT_MS` (default 3000) — the cut goes to every
+//! configured adapter:
+//!
+//! - `SWITCHER_ATEM_HOST` (`host[:port]`, default port 9910) — runs the camera's
+//!   `atemMacro` on a Blackmagic ATEM (minimal UDP client: handshake, `MAct`,
+//!   wait for the ack)
+//! - `SWITCHER_COMPANION_URL` (e.g. `http://companion:8000`) — presses the
+//!   camera's `companion` button (`page/row/column`) through the Bitfocus
+//!   Companion HTTP API
+//!
+//! `SWITCHER_NDI_METADATA_URL` gets every change of program or camera shots as
+//! NDI metadata XML (`<srs_director program=… ><camera id=… target=… /></srs_director>`),
+//! POSTed to the NDI sender that attaches it to the program stream.
+//!
+//! `SWITCHER_CAMERA_MAP` maps camera ids to their switcher controls:
+//!
+//! ```json
+//! { "cam1": { "atemMacro": 0, "companion": "1/0/1" }, "cam2": { "atemMacro": 1, "companion": "1/0/2" } }
+//! ```
+//!
+//! Each adapter is retried up to `SWITCHER_RETRIES` times (default 2). Every cut
+//! is announced as `switcher-cut { cameraId, adapters: [{ adapter, ok, error? }],
+//! timestamp }`.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use socketioxide::SocketIo;
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::auto_director::DirectorFocus;
+use crate::camera_assignment::CameraTarget;
+
+const ATEM_PORT: u16 = 9910;
+const ATEM_TIMEOUT: Duration = Duration::from_secs(2);
+/// vMix / NDI calls share the loop with every cut; never wait longer than this
+const HTTP_TIMEOUT: Duration = Duration::from_secs(2);
+
+// ATEM packet header flags (top 5 bits of the first byte)
+const ATEM_RELIABLE: u8 = 0x08;
+const ATEM_HELLO: u8 = 0x10;
+const ATEM_ACK: u8 = 0x80;
+
+// ── Configuration ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraControls {
+    pub atem_macro: Option<u16>,
+    /// Companion button location, `page/row/column`
+    pub companion: Option<String>,
+}
+
+pub struct VisionSwitcherConfig {
+    pub atem_host: Option<String>,
+    pub companion_url: Option<String>,
+    pub ndi_metadata_url: Option<String>,
+    pub cameras: HashMap<String, CameraControls>,
+    pub min_cut: Duration,
+    pub retries: u32,
+}
+
+impl Default for VisionSwitcherConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        let cameras = match var("SWITCHER_CAMERA_MAP").map(|json| serde_json::from_str(&json)) {
+            Some(Ok(cameras)) => cameras,
+            Some(Err(e)) => {
+                warn!("VisionSwitcher: ignoring SWITCHER_CAMERA_MAP: {e}");
+                HashMap::new()
+            }
+            None => HashMap::new(),
+        };
+        Self {
+            atem_host: var("SWITCHER_ATEM_HOST"),
+            companion_url: var("SWITCHER_COMPANION_URL").map(|url| url.trim_end_matches('/').to_string()),
+            ndi_metadata_url: var("SWITCHER_NDI_METADATA_URL"),
+            cameras,
+            min_cut: Duration::from_millis(var("SWITCHER_MIN_CUT_MS").and_then(|v| v.parse().ok()).unwrap_or(3000)),
+            retries: var("SWITCHER_RETRIES").and_then(|v| v.parse().ok()).filter(|&n| n > 0).unwrap_or(2),
+        }
+    }
+}
+
+impl VisionSwitcherConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.atem_host.is_some() || self.companion_url.is_some() || self.ndi_metadata_url.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AdapterOutcome {
+    adapter: &'static str,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+// ── Task ──────────────────────────────────────────────────────────────────────
+
+pub async fn run_vision_switcher(config: VisionSwitcherConfig, mut focus: watch::Receiver<DirectorFocus>, io: SocketIo) {
+    if !config.is_enabled() {
+        return;
+    }
+    info!(
+        "VisionSwitcher: atem={} companion={} ndi={} cameras={:?}",
+        config.atem_host.as_deref().unwrap_or("-"),
+        config.companion_url.as_deref().unwrap_or("-"),
+        config.ndi_metadata_url.as_deref().unwrap_or("-"),
+        config.cameras.keys().collect::<Vec<_>>(),
+    );
+    let client = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build().unwrap_or_default();
+    let mut on_air: Option<String> = None;
+    let mut last_cut: Option<Instant> = None;
+    let mut last_metadata = String::new();
+
+    loop {
+        // A cut held back by the minimum interval is retried when it has passed
+        let wait = last_cut.map(|at| config.min_cut.saturating_sub(at.elapsed())).unwrap_or_default();
+        tokio::select! {
+            changed = focus.changed() => if changed.is_err() { return },
+            _ = tokio::time::sleep(wait), if !wait.is_zero() => {}
+        }
+        let current = focus.borrow_and_update().clone();
+
+        if let Some(url) = &config.ndi_metadata_url {
+            let metadata = ndi_metadata(&current);
+            if metadata != last_metadata {
+                let sent = with_retries(config.retries, "ndi", || async {
+                    client.post(url).header("content-type", "application/xml").body(metadata.clone()).send().await
+                        .and_then(|r| r.error_for_status())
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                }).await;
+                if sent.is_ok() {
+                    last_metadata = metadata;
+                }
+            }
+        }
+
+        let Some(program) = current.program.clone().filter(|p| on_air.as_ref() != Some(p)) else { continue };
+        if last_cut.is_some_and(|at| at.elapsed() < config.min_cut) {
+            continue;
+        }
+        let controls = config.cameras.get(&program).cloned().unwrap_or_default();
+        let mut outcomes = Vec::new();
+        if let (Some(host), Some(index)) = (&config.atem_host, controls.atem_macro) {
+            let result = with_retries(config.retries, "atem", || atem_run_macro(host, index)).await;
+            outcomes.push(AdapterOutcome { adapter: "atem", ok: result.is_ok(), error: result.err() });
+        }
+        if let (Some(base), Some(location)) = (&config.companion_url, &controls.companion) {
+            let url = format!("{base}/api/location/{}/press", location.trim_matches('/'));
+            let result = with_retries(config.retries, "companion", || async {
+                client.post(&url).send().await
+                    .and_then(|r| r.error_for_status())
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }).await;
+            outcomes.push(AdapterOutcome { adapter: "companion", ok: result.is_ok(), error: result.err() });
+        }
+        if outcomes.is_empty() {
+            warn!("VisionSwitcher: no switcher control mapped for camera {program}");
+        }
+        info!("VisionSwitcher: cut to {program}");
+        last_cut = Some(Instant::now());
+        on_air = Some(program.clone());
+        io.emit("switcher-cut", &json!({
+            "cameraId": program,
+            "adapters": outcomes,
+            "timestamp": crate::handlers::now_ms(),
+        })).ok();
+    }
+}
+
+/// Run `attempt` up to `retries` times.
+async fn with_retries<F, Fut>(retries: u32, adapter: &str, mut attempt: F) -> Result<(), String>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    let mut last_error = String::new();
+    for n in 1..=retries {
+        match attempt().await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                warn!("VisionSwitcher: {adapter} attempt {n}/{retries} failed: {e}");
+                last_error = e;
+                tokio::time::sleep(Duration::from_millis(200 * n as u64)).await;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+// ── NDI metadata ──────────────────────────────────────────────────────────────
+
+fn xml_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn ndi_metadata(focus: &DirectorFocus) -> String {
+    let mut xml = format!("<srs_director program=\"{}\">", xml_escape(focus.program.as_deref().unwrap_or("")));
+    for camera in &focus.cameras {
+        let target = match &camera.target {
+            Some(CameraTarget::Boat { boat_id }) => format!("boat:{boat_id}"),
+            Some(CameraTarget::Mark { mark_id }) => format!("mark:{mark_id}"),
+            Some(CameraTarget::Group { group, .. }) => format!("group:{group}"),
+            None => String::new(),
+        };
+        xml.push_str(&format!("<camera id=\"{}\" target=\"{}\"/>", xml_escape(&camera.camera_id), xml_escape(&target)));
+    }
+    xml.push_str("</srs_director>");
+    xml
+}
+
+// ── ATEM ──────────────────────────────────────────────────────────────────────
+
+/// 12-byte ATEM header: flags and length, session, acked packet, local packet.
+fn atem_header(flags: u8, len: usize, session: u16, ack_id: u16, packet_id: u16) -> Vec<u8> {
+    let mut header = vec![flags | ((len >> 8) as u8 & 0x07), len as u8];
+    header.extend_from_slice(&session.to_be_bytes());
+    header.extend_from_slice(&ack_id.to_be_bytes());
+    header.extend_from_slice(&[0, 0, 0, 0]);
+    header.extend_from_slice(&packet_id.to_be_bytes());
+    header
+}
+
+async fn atem_recv(socket: &UdpSocket, buf: &mut [u8]) -> Result<usize, String> {
+    tokio::time::timeout(ATEM_TIMEOUT, socket.recv(buf)).await
+        .map_err(|_| "ATEM did not answer".to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Minimal ATEM session: hello → ack → `MAct` (run macro) → wait for its ack.
+async fn atem_run_macro(host: &str, index: u16) -> Result<(), String> {
+    let addr = if host.contains(':') { host.to_string() } else { format!("{host}:{ATEM_PORT}") };
+    let socket = UdpSocket::bind("0.0.0.0:0").await.map_err(|e| e.to_string())?;
+    socket.connect(&addr).await.map_err(|e| e.to_string())?;
+
+    let mut hello = atem_header(ATEM_HELLO, 20, 0x53ab, 0, 0);
+    hello[9] = 0x3a;
+    hello.extend_from_slice(&[0x01, 0, 0, 0, 0, 0, 0, 0]);
+    socket.send(&hello).await.map_err(|e| e.to_string())?;
+
+    let mut buf = [0u8; 2048];
+    let n = atem_recv(&socket, &mut buf).await?;
+    if n < 12 || buf[0] & ATEM_HELLO == 0 {
+        return Err("unexpected ATEM handshake reply".to_string());
+    }
+    let hello_session = u16::from_be_bytes([buf[2], buf[3]]);
+    socket.send(&atem_header(ATEM_ACK, 12, hello_session, 0, 0)).await.map_err(|e| e.to_string())?;
+
+    // The switcher then dumps its state on the session it assigns; ack it and take the id
+    let n = atem_recv(&socket, &mut buf).await?;
+    if n < 12 {
+        return Err("short ATEM packet".to_string());
+    }
+    let session = u16::from_be_bytes([buf[2], buf[3]]);
+    let remote_id = u16::from_be_bytes([buf[10], buf[11]]);
+    socket.send(&atem_header(ATEM_ACK, 12, session, remote_id, 0)).await.map_err(|e| e.to_string())?;
+
+    // MAct: macro index, action 0 = run
+    let packet_id = 1;
+    let mut command = atem_header(ATEM_RELIABLE, 12 + 12, session, 0, packet_id);
+    command.extend_from_slice(&12u16.to_be_bytes());
+    command.extend_from_slice(&[0, 0]);
+    command.extend_from_slice(b"MAct");
+    command.extend_from_slice(&index.to_be_bytes());
+    command.extend_from_slice(&[0, 0]);
+    socket.send(&command).await.map_err(|e| e.to_string())?;
+
+    // State-dump packets keep coming; wait for the ack of ours
+    let deadline = Instant::now() + ATEM_TIMEOUT;
+    while Instant::now() < deadline {
+        let n = atem_recv(&socket, &mut buf).await?;
+        if n >= 12 && buf[0] & ATEM_ACK != 0 && u16::from_be_bytes([buf[4], buf[5]]) == packet_id {
+            return Ok(());
+        }
+        if n >= 12 && buf[0] & ATEM_RELIABLE != 0 {
+            let remote_id = u16::from_be_bytes([buf[10], buf[11]]);
+            let _ = socket.send(&atem_header(ATEM_ACK, 12, session, remote_id, 0)).await;
+        }
+    }
+    Err("ATEM did not acknowledge the macro".to_string())
+}
@@ -0,0 +1,168 @@
+//! # weather
+//!
+//! Polls a weather provider for the course area and keeps a rolling history of
+//! `WeatherReport`s next to the committee's manual wind entries.
+//!
+//! - `WEATHER_PROVIDER` — `openmeteo`, `noaa` (api.weather.gov, US waters) or
+//!   unset / `manual` for no polling
+//! - `WEATHER_POLL_SECS` (default 600)
+//! - `WEATHER_HISTORY_LIMIT` (default 144, a day at the default rate)
+//! - `WEATHER_SETS_WIND=true` — also apply fetched wind to `state.wind`;
+//!   by default only the committee sets the race wind
+//! - `NOAA_USER_AGENT` — contact string api.weather.gov asks for
+//!
+//! The location is the centre of the course marks, else the default location.
+//! Every report, fetched or from `update-wind`, is stored as `state.weather`,
+//! appended to `state.weatherHistory` and emitted as `weather-updated`. Speeds are
+//! knots.
+//!
+//! ## Invariants
+//! - Core Invariant #8: fetches run outside the state lock; a failed poll keeps the last report
+
+use std::time::Duration;
+
+use serde_json::Value;
+use socketioxide::SocketIo;
+use tracing::{info, warn};
+
+use crate::handlers::{now_ms, SharedState};
+use crate::laylines;
+use crate::line_bias;
+use crate::state::{LatLon, RaceState, WeatherProvider, WeatherReport, WindState};
+use crate::boat_scope::FULL_STATE_EXCEPT;
+
+const KMH_PER_KNOT: f64 = 1.852;
+/// A provider that hangs longer is skipped until the next poll
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct WeatherConfig {
+    pub provider: WeatherProvider,
+    pub poll: Duration,
+    pub sets_wind: bool,
+    pub user_agent: String,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.is_empty());
+        Self {
+            provider: match var("WEATHER_PROVIDER").unwrap_or_default().to_lowercase().as_str() {
+                "openmeteo" | "open-meteo" => WeatherProvider::Openmeteo,
+                "noaa" => WeatherProvider::Noaa,
+                _ => WeatherProvider::Manual,
+            },
+            poll: Duration::from_secs(var("WEATHER_POLL_SECS").and_then(|v| v.parse().ok()).filter(|&s| s >= 60).unwrap_or(600)),
+            sets_wind: var("WEATHER_SETS_WIND").is_some_and(|v| v == "true" || v == "1"),
+            user_agent: var("NOAA_USER_AGENT").unwrap_or_else(|| format!("regatta-backend/{}", env!("CARGO_PKG_VERSION"))),
+        }
+    }
+}
+
+fn history_limit() -> usize {
+    std::env::var("WEATHER_HISTORY_LIMIT").ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(144)
+}
+
+/// Make `report` the current weather and append it to the history.
+pub fn record(state: &mut RaceState, report: WeatherReport) {
+    state.weather = Some(report.clone());
+    state.weather_history.push(report);
+    let excess = state.weather_history.len().saturating_sub(history_limit());
+    state.weather_history.drain(..excess);
+}
+
+/// Centre of the course marks, else the default location.
+pub fn location(state: &RaceState) -> Option<LatLon> {
+    let marks = &state.course.marks;
+    if !marks.is_empty() {
+        let n = marks.len() as f64;
+        return Some(LatLon {
+            lat: marks.iter().map(|m| m.pos.lat).sum::<f64>() / n,
+            lon: marks.iter().map(|m| m.pos.lon).sum::<f64>() / n,
+        });
+    }
+    state.default_location.as_ref().map(|l| LatLon { lat: l.lat, lon: l.lon })
+}
+
+async fn fetch_openmeteo(client: &reqwest::Client, at: &LatLon) -> anyhow::Result<WeatherReport> {
+    let url = format!(
+        "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}\
+         &current=wind_speed_10m,wind_direction_10m,wind_gusts_10m,temperature_2m&wind_speed_unit=kn",
+        at.lat, at.lon,
+    );
+    let body: Value = client.get(url).send().await?.error_for_status()?.json().await?;
+    let current = &body["current"];
+    Ok(WeatherReport {
+        timestamp: now_ms(),
+        wind_direction: current["wind_direction_10m"].as_f64().ok_or_else(|| anyhow::anyhow!("no wind direction"))?,
+        wind_speed: current["wind_speed_10m"].as_f64().ok_or_else(|| anyhow::anyhow!("no wind speed"))?,
+        gusts: current["wind_gusts_10m"].as_f64(),
+        temperature: current["temperature_2m"].as_f64(),
+        provider: WeatherProvider::Openmeteo,
+    })
+}
+
+/// Latest observation from the station api.weather.gov lists first for the point.
+async fn fetch_noaa(client: &reqwest::Client, user_agent: &str, at: &LatLon) -> anyhow::Result<WeatherReport> {
+    let get = |url: String| async move {
+        client.get(url).header(reqwest::header::USER_AGENT, user_agent)
+            .send().await?.error_for_status()?.json::<Value>().await
+    };
+    let point = get(format!("https://api.weather.gov/points/{:.4},{:.4}", at.lat, at.lon)).await?;
+    let stations_url = point["properties"]["observationStations"].as_str()
+        .ok_or_else(|| anyhow::anyhow!("no observation stations for location"))?;
+    let stations = get(stations_url.to_string()).await?;
+    let station = stations["features"][0]["properties"]["stationIdentifier"].as_str()
+        .ok_or_else(|| anyhow::anyhow!("no observation station nearby"))?;
+    let obs = get(format!("https://api.weather.gov/stations/{station}/observations/latest")).await?;
+    let p = &obs["properties"];
+    let knots = |v: &Value| v["value"].as_f64().map(|kmh| kmh / KMH_PER_KNOT);
+    Ok(WeatherReport {
+        timestamp: now_ms(),
+        wind_direction: p["windDirection"]["value"].as_f64().ok_or_else(|| anyhow::anyhow!("no wind direction from {station}"))?,
+        wind_speed: knots(&p["windSpeed"]).ok_or_else(|| anyhow::anyhow!("no wind speed from {station}"))?,
+        gusts: knots(&p["windGust"]),
+        temperature: p["temperature"]["value"].as_f64(),
+        provider: WeatherProvider::Noaa,
+    })
+}
+
+pub async fn run_weather_poll(config: WeatherConfig, shared: SharedState, io: SocketIo) {
+    if config.provider == WeatherProvider::Manual {
+        return;
+    }
+    info!("Weather: polling {:?} every {}s (sets wind: {})", config.provider, config.poll.as_secs(), config.sets_wind);
+    let client = reqwest::Client::builder().timeout(PROVIDER_TIMEOUT).build().unwrap_or_default();
+    let mut interval = tokio::time::interval(config.poll);
+    loop {
+        interval.tick().await;
+        let Some(at) = location(&*shared.read().await) else { continue };
+        let report = match config.provider {
+            WeatherProvider::Openmeteo => fetch_openmeteo(&client, &at).await,
+            WeatherProvider::Noaa => fetch_noaa(&client, &config.user_agent, &at).await,
+            WeatherProvider::Manual => continue,
+        };
+        let report = match report {
+            Ok(report) => report,
+            Err(e) => {
+                warn!("Weather: {:?} fetch failed: {e}", config.provider);
+                continue;
+            }
+        };
+
+        let mut state = shared.write().await;
+        record(&mut state, report.clone());
+        let _ = io.emit("weather-updated", &report);
+        if config.sets_wind {
+            state.wind = WindState { direction: report.wind_direction, speed: report.wind_speed };
+            laylines::refresh(&mut state);
+            line_bias::refresh(&mut state);
+            let _ = io.emit("wind-updated", &state.wind);
+            let _ = io.emit("course-updated", &state.course);
+            let _ = io.emit("line-bias", &state.line_bias);
+            let _ = io.except(FULL_STATE_EXCEPT).emit("state-update", &*state);
+        }
+    }
+}
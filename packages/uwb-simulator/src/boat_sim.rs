@@ -7,13 +7,17 @@
 //! - Heel angle from boat speed (lever-arm source for UWB testing)
 //! - Pitch from wave model
 //! - Heading variation for realistic approach angles
+//! - Wind: persistent shifts + oscillation; boats beat up to their spot on the
+//!   line close-hauled, tacking at the edge of a corridor or on the layline,
+//!   and heel to leeward by how much they are powered up
 //!
 //! validation_protocol.json invariants served:
 //! - #1 (≤1 cm): lever-arm compensation tested by realistic heel variation
 //! - #5 (UWB Hive): mark buoys + committee boat are fixed anchors in this frame
 //! - #8 (zero interruption): pure math, no panics, no unwraps
 
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rand_distr::{Distribution, Normal, Uniform};
 use serde::{Deserialize, Serialize};
 
@@ -81,6 +85,80 @@ impl Quat {
     }
 }
 
+// ── Wind ──────────────────────────────────────────────────────────────────────
+
+/// How fast a persistent shift fills in (degrees per second)
+const SHIFT_FILL_DPS: f64 = 0.5;
+/// Helm turn rate (degrees per second), tacks included
+const TURN_RATE_DPS: f64 = 20.0;
+/// Speed kept through a tack
+const TACK_SPEED_KEPT: f64 = 0.7;
+/// Closer than this to the line boats hold their heading into the start
+const STEER_FREEZE_M: f64 = 10.0;
+
+/// Signed angle a − b wrapped to (−180, 180] degrees
+fn angle_diff(a: f64, b: f64) -> f64 {
+    let d = (a - b).rem_euclid(360.0);
+    if d > 180.0 { d - 360.0 } else { d }
+}
+
+/// True wind direction (where it blows from, degrees) over the sequence:
+/// the mean, a persistent shift that steps to a new level every so often and
+/// fills in gradually, and a regular oscillation on top.
+#[derive(Debug, Clone)]
+pub struct WindModel {
+    mean_deg: f64,
+    /// Current persistent shift and the level it is moving to
+    shift_deg: f64,
+    shift_target_deg: f64,
+    shift_sigma_deg: f64,
+    shift_interval_s: f64,
+    osc_amplitude_deg: f64,
+    osc_period_s: f64,
+    osc_phase: f64,
+    t: f64,
+}
+
+impl WindModel {
+    fn new(cfg: &SimConfig, rng: &mut StdRng) -> Self {
+        Self {
+            mean_deg: cfg.wind_direction_deg,
+            shift_deg: 0.0,
+            shift_target_deg: 0.0,
+            shift_sigma_deg: cfg.wind_shift_sigma_deg.max(0.0),
+            shift_interval_s: cfg.wind_shift_interval_s,
+            osc_amplitude_deg: cfg.wind_oscillation_deg,
+            osc_period_s: cfg.wind_oscillation_period_s,
+            osc_phase: rng.gen_range(0.0..std::f64::consts::TAU),
+            t: 0.0,
+        }
+    }
+
+    fn tick(&mut self, dt: f64, rng: &mut StdRng) {
+        self.t += dt;
+        // New persistent level: on average once per interval
+        if self.shift_interval_s > 0.0 && self.shift_sigma_deg > 0.0
+            && rng.gen_bool((dt / self.shift_interval_s).min(1.0))
+        {
+            self.shift_target_deg = Normal::new(0.0, self.shift_sigma_deg)
+                .map(|n| n.sample(rng))
+                .unwrap_or(0.0);
+        }
+        let step = SHIFT_FILL_DPS * dt;
+        self.shift_deg += (self.shift_target_deg - self.shift_deg).clamp(-step, step);
+    }
+
+    /// Current true wind direction (from), 0–360°
+    pub fn direction_deg(&self) -> f64 {
+        let osc = if self.osc_period_s > 0.0 {
+            self.osc_amplitude_deg * (std::f64::consts::TAU * self.t / self.osc_period_s + self.osc_phase).sin()
+        } else {
+            0.0
+        };
+        (self.mean_deg + self.shift_deg + osc).rem_euclid(360.0)
+    }
+}
+
 // ── Race world geometry (Invariant #5 — UWB Hive anchors) ────────────────────
 
 /// Fixed anchor positions in ENU frame
@@ -146,6 +224,10 @@ pub struct BoatState {
     pub is_ocs_scenario: bool,
     /// Wave phase offset (unique per boat)
     pub wave_phase: f64,
+    /// Where on the line (x) the boat is heading for
+    pub target_x: f64,
+    /// +1 = port tack (heading right of the wind), −1 = starboard
+    pub tack: f64,
 }
 
 impl BoatState {
@@ -180,6 +262,7 @@ pub struct BoatSim {
     pub t_elapsed: f64,           // seconds since sim start
     pub t_to_gun: f64,            // seconds until T-0
     pub batch_mode: bool,         // true during 2s batch solve at gun
+    pub wind: WindModel,
 
     // Config
     line_length: f64,
@@ -189,14 +272,25 @@ pub struct BoatSim {
     tactical_slowdown_y: f64,
     tactical_slowdown_factor: f64,
     max_heel_rad: f64,
+    tack_angle_deg: f64,
+    tack_width_m: f64,
     ocs_set: std::collections::HashSet<u32>,  // node_ids to force OCS
     ocs_offset: f64,
+
+    /// Single source of randomness for the run (spawn, then radio noise).
+    /// Seeded from `SimConfig::seed` so a run can be reproduced exactly.
+    pub rng: StdRng,
 }
 
 impl BoatSim {
     pub fn new(cfg: &SimConfig) -> Self {
         let anchors = Anchors::new(cfg.line_length_m, cfg.committee_offset_m);
-        let boats = Self::spawn_boats(cfg, &anchors);
+        let mut rng = match cfg.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let boats = Self::spawn_boats(cfg, &mut rng);
+        let wind = WindModel::new(cfg, &mut rng);
         let ocs_set = cfg.ocs_boat_ids.iter().cloned().collect();
         Self {
             boats,
@@ -204,6 +298,7 @@ impl BoatSim {
             t_elapsed: 0.0,
             t_to_gun: cfg.t_minus_seconds as f64,
             batch_mode: false,
+            wind,
             line_length: cfg.line_length_m,
             wave_amplitude: cfg.wave_amplitude_m
                 * if cfg.rough_sea { 2.0 } else { 1.0 },
@@ -212,13 +307,15 @@ impl BoatSim {
             tactical_slowdown_y: cfg.tactical_slowdown_y_m,
             tactical_slowdown_factor: cfg.tactical_slowdown_factor,
             max_heel_rad: cfg.max_heel_rad,
+            tack_angle_deg: cfg.tack_angle_deg.max(0.0),
+            tack_width_m: cfg.tack_width_m.max(0.0),
             ocs_set,
             ocs_offset: cfg.ocs_offset_m,
+            rng,
         }
     }
 
-    fn spawn_boats(cfg: &SimConfig, anchors: &Anchors) -> Vec<BoatState> {
-        let mut rng = rand::thread_rng();
+    fn spawn_boats(cfg: &SimConfig, rng: &mut StdRng) -> Vec<BoatState> {
         let speed_dist = Uniform::new(
             cfg.target_speed_mps - cfg.speed_variance / 2.0,
             cfg.target_speed_mps + cfg.speed_variance / 2.0,
@@ -226,15 +323,19 @@ impl BoatSim {
         let x_spread = cfg.line_length_m * 0.9;
 
         (0..cfg.n_boats).map(|i| {
-            let base_speed = speed_dist.sample(&mut rng);
+            let base_speed = speed_dist.sample(rng);
             let x = -x_spread/2.0 + (i as f64 / f64::max(cfg.n_boats as f64 - 1.0, 1.0)) * x_spread;
             let y = -cfg.approach_distance_m + rng.gen_range(-20.0..20.0);
+            let heading_deg: f64 = 360.0 + rng.gen_range(-10.0..10.0);   // roughly North
+            // Aim for where that first heading meets the line
+            let half = cfg.line_length_m * 0.45;
+            let target_x = (x - y * heading_deg.to_radians().tan()).clamp(-half, half);
             BoatState {
                 boat_number: i as u32 + 1,
                 node_id: 10 + i as u32,   // nodes 10..21 for boats
                 cog: Vec3::new(x, y, 0.0),
                 vel: Vec3::new(0.0, base_speed, 0.0),
-                heading_deg: 360.0 + rng.gen_range(-10.0..10.0),   // roughly North
+                heading_deg,
                 heel_rad: 0.0,
                 pitch_rad: 0.0,
                 boat_speed_mps: base_speed,
@@ -242,6 +343,8 @@ impl BoatSim {
                 battery_pct: rng.gen_range(70..=100),
                 is_ocs_scenario: false,
                 wave_phase: rng.gen_range(0.0..std::f64::consts::TAU),
+                target_x,
+                tack: if rng.gen_bool(0.5) { 1.0 } else { -1.0 },
             }
         }).collect()
     }
@@ -251,6 +354,8 @@ impl BoatSim {
     pub fn tick(&mut self, dt: f64) {
         self.t_elapsed += dt;
         self.t_to_gun = f64::max(self.t_to_gun - dt, -30.0);
+        self.wind.tick(dt, &mut self.rng);
+        let twd = self.wind.direction_deg();
 
         let angle = std::f64::consts::TAU / self.wave_period;
         let ocs_active = self.t_to_gun <= 0.0 && self.t_to_gun >= -5.0;
@@ -282,6 +387,13 @@ impl BoatSim {
             // Smooth speed transition (simple first-order lag)
             boat.boat_speed_mps += (actual_speed - boat.boat_speed_mps) * (dt * 2.0).min(1.0);
 
+            // Steer: straight for the target if it can be laid, else beat
+            if pos_override.is_none() && boat.cog.y < -STEER_FREEZE_M {
+                let desired = Self::course_to_target(boat, twd, self.tack_angle_deg, self.tack_width_m);
+                let turn = angle_diff(desired, boat.heading_deg).clamp(-TURN_RATE_DPS * dt, TURN_RATE_DPS * dt);
+                boat.heading_deg = (boat.heading_deg + turn).rem_euclid(360.0);
+            }
+
             // Position update
             if let Some(next_y) = pos_override {
                 boat.cog.y = next_y;
@@ -295,15 +407,41 @@ impl BoatSim {
                 boat.cog = boat.cog.add(&boat.vel.scale(dt));
             }
 
-            // Attitude
+            // Attitude: heel to leeward, full when powered up (close-hauled
+            // to a beam reach), easing off when luffing or bearing away
             let speed_ratio = boat.boat_speed_mps / boat.base_speed_mps;
-            boat.heel_rad  = speed_ratio * self.max_heel_rad;
+            let awa = angle_diff(twd, boat.heading_deg);   // + = wind from starboard
+            let twa = awa.abs();
+            let power = if twa < self.tack_angle_deg {
+                twa / self.tack_angle_deg
+            } else if twa <= 90.0 {
+                1.0
+            } else {
+                (twa - 90.0).to_radians().cos()
+            };
+            boat.heel_rad  = -awa.signum() * speed_ratio * self.max_heel_rad * power;
             boat.pitch_rad = 0.05 * (angle * self.t_elapsed * 0.7 + boat.wave_phase).sin();
         }
 
         // Batch mode activates at gun (2-second window per Invariant #1 batch solve)
         self.batch_mode = self.t_to_gun <= 0.0 && self.t_to_gun >= -2.0;
     }
+
+    /// Heading for a boat given the wind: its target bearing when that is
+    /// outside the no-go zone (on or past the layline), else close-hauled on
+    /// its tack, tacking when it reaches the edge of its corridor.
+    fn course_to_target(boat: &mut BoatState, twd: f64, tack_angle_deg: f64, tack_width_m: f64) -> f64 {
+        let bearing = (boat.target_x - boat.cog.x).atan2(-boat.cog.y).to_degrees();
+        if angle_diff(bearing, twd).abs() >= tack_angle_deg {
+            return bearing;
+        }
+        let off = boat.cog.x - boat.target_x;
+        if boat.tack * off > tack_width_m {
+            boat.tack = -boat.tack;
+            boat.boat_speed_mps *= TACK_SPEED_KEPT;
+        }
+        twd + boat.tack * tack_angle_deg
+    }
 }
 
 // ── Config struct (populated from config.toml) ────────────────────────────────
@@ -327,8 +465,28 @@ pub struct SimConfig {
     pub lever_arm_body: [f64; 3],
     pub max_heel_rad: f64,
 
+    // [boat_physics] wind (0 = off; tack_angle_deg 0 sails straight at the line)
+    #[serde(default)]
+    pub wind_direction_deg: f64,
+    #[serde(default)]
+    pub wind_shift_sigma_deg: f64,
+    #[serde(default)]
+    pub wind_shift_interval_s: f64,
+    #[serde(default)]
+    pub wind_oscillation_deg: f64,
+    #[serde(default)]
+    pub wind_oscillation_period_s: f64,
+    #[serde(default)]
+    pub tack_angle_deg: f64,
+    #[serde(default)]
+    pub tack_width_m: f64,
+
     // [scenarios]
     pub ocs_boat_ids: Vec<u32>,
     pub ocs_offset_m: f64,
     pub rough_sea: bool,
+
+    // --seed (None = fresh entropy every run)
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
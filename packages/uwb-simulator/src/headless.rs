@@ -0,0 +1,289 @@
+//! headless.rs — Monte Carlo accuracy validation (`--headless --runs N`)
+//!
+//! Runs the start sequence N times with no UDP, no control server and no
+//! wall clock, solving the positions in-process with `trilateration` exactly
+//! as the hub would, and measures the error against ground truth at the gun:
+//!   - live:  one single-epoch solve on the gun epoch (first with t_to_gun ≤ 0)
+//!   - batch: `batch_solve` over every epoch of the 2 s batch window, compared
+//!     to each boat's mean antenna position over that window
+//!
+//! Errors are split along the line (x) and across it (y = distance to line).
+//! The invariant is checked on the cross-line error, the one an OCS call
+//! rests on. Run i uses seed `base + i` (`--seed`, random otherwise), so a
+//! failing run is reproduced with `--headless --runs 1 --seed <its seed>`.
+//!
+//! The JSON report goes to `--report`. Exit status is 0 when every limit holds
+//! and every boat was solved in every run, 1 when an invariant is violated,
+//! 2 when the report cannot be written.
+//!
+//! validation_protocol.json:
+//! - Invariant #1: σ ≤ 1 cm batch, σ ≤ 5 cm live
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tracing::{debug, info};
+
+use crate::boat_sim::{BoatSim, Vec3};
+use crate::scenarios::ScenarioConfig;
+use crate::trilateration::{self, AnchorMap, Pos2D, RangeMeasurement};
+use crate::uwb_physics::{self, EpochMeasurement, RadioConfig};
+use crate::{sim_config_from, FullConfig};
+
+/// Invariant #1 limits on the cross-line error σ (meters)
+const LIVE_SIGMA_MAX_M: f64 = 0.05;
+const BATCH_SIGMA_MAX_M: f64 = 0.01;
+
+// ── Report ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct ErrorStats {
+    /// Boat positions compared
+    pub samples: usize,
+    /// Boats the solver gave no position for
+    pub unsolved: usize,
+    /// Cross-line (distance-to-line) error: estimate − truth, meters
+    pub mean_m: f64,
+    pub sigma_m: f64,
+    pub rms_m: f64,
+    pub max_abs_m: f64,
+    /// Along-line error σ, meters
+    pub sigma_along_m: f64,
+    /// Horizontal (2D) RMS error, meters
+    pub horizontal_rms_m: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub run: u32,
+    pub seed: u64,
+    pub live_sigma_m: f64,
+    pub batch_sigma_m: f64,
+    pub batch_epochs: usize,
+    pub unsolved: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Limits {
+    pub live_sigma_m: f64,
+    pub batch_sigma_m: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccuracyReport {
+    pub runs: u32,
+    pub base_seed: u64,
+    pub boats: usize,
+    pub live: ErrorStats,
+    pub batch: ErrorStats,
+    pub limits: Limits,
+    pub passed: bool,
+    pub per_run: Vec<RunSummary>,
+}
+
+// ── Error samples ─────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct Samples {
+    along: Vec<f64>,
+    across: Vec<f64>,
+    unsolved: usize,
+}
+
+impl Samples {
+    fn push(&mut self, estimate: Option<&Pos2D>, truth: Vec3) {
+        match estimate {
+            Some(p) => {
+                self.along.push(p.x as f64 - truth.x);
+                self.across.push(p.y as f64 - truth.y);
+            }
+            None => self.unsolved += 1,
+        }
+    }
+
+    fn extend(&mut self, other: &Samples) {
+        self.along.extend_from_slice(&other.along);
+        self.across.extend_from_slice(&other.across);
+        self.unsolved += other.unsolved;
+    }
+
+    fn stats(&self) -> ErrorStats {
+        let (mean, sigma) = mean_sigma(&self.across);
+        let (_, sigma_along) = mean_sigma(&self.along);
+        let n = self.across.len().max(1) as f64;
+        ErrorStats {
+            samples: self.across.len(),
+            unsolved: self.unsolved,
+            mean_m: mean,
+            sigma_m: sigma,
+            rms_m: (self.across.iter().map(|e| e * e).sum::<f64>() / n).sqrt(),
+            max_abs_m: self.across.iter().fold(0.0, |m, e| f64::max(m, e.abs())),
+            sigma_along_m: sigma_along,
+            horizontal_rms_m: (self.along.iter().zip(&self.across)
+                .map(|(a, c)| a * a + c * c).sum::<f64>() / n).sqrt(),
+        }
+    }
+}
+
+fn mean_sigma(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() { return (0.0, 0.0); }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, var.sqrt())
+}
+
+// ── Solver input ──────────────────────────────────────────────────────────────
+
+fn anchor_map(sim: &BoatSim) -> AnchorMap {
+    let mut map = AnchorMap::new();
+    map.insert(1, [sim.anchors.mark_a.x as f32, sim.anchors.mark_a.y as f32]);
+    map.insert(2, [sim.anchors.mark_b.x as f32, sim.anchors.mark_b.y as f32]);
+    map.insert(3, [sim.anchors.committee.x as f32, sim.anchors.committee.y as f32]);
+    map
+}
+
+/// Peer reports of one epoch as solver ranges (σ by the reported LOS/NLOS flag)
+fn ranges(measurements: &[EpochMeasurement], radio: &RadioConfig) -> Vec<RangeMeasurement> {
+    measurements.iter()
+        .flat_map(|m| m.peers.iter().map(move |p| RangeMeasurement {
+            node_i: m.node_id,
+            node_j: p.peer_id,
+            range_m: p.range_m,
+            sigma_m: if p.nlos { radio.sigma_nlos_m } else { radio.sigma_los_m } as f32,
+            nlos: p.nlos,
+        }))
+        .collect()
+}
+
+// ── Runs ──────────────────────────────────────────────────────────────────────
+
+struct RunResult {
+    live: Samples,
+    batch: Samples,
+    batch_epochs: usize,
+}
+
+fn run_once(cfg: &FullConfig, scenario: &ScenarioConfig, seed: u64) -> RunResult {
+    let mut sim = BoatSim::new(&sim_config_from(cfg, scenario, Some(seed)));
+    let anchors = anchor_map(&sim);
+    let lever_arm = cfg.boat_physics.lever_arm_body;
+    // Same epoch length as the live loop, so a seed behaves the same in both
+    let dt = (1000.0 / cfg.simulation.update_rate_hz) as u64 as f64 / 1000.0;
+    let mut seq_nums = HashMap::new();
+
+    let mut live = Samples::default();
+    let mut live_solution: HashMap<u32, Pos2D> = HashMap::new();
+    let mut batch_epochs: Vec<Vec<RangeMeasurement>> = Vec::new();
+    let mut batch_truth: HashMap<u32, Vec3> = HashMap::new();
+    let mut gun_seen = false;
+
+    loop {
+        sim.tick(dt);
+        // Every epoch is generated, solved or not, so the random stream is the
+        // one a networked run with the same seed draws
+        let measurements = uwb_physics::generate_epoch(
+            &sim.boats, &sim.anchors, lever_arm, &cfg.uwb_radio,
+            &mut seq_nums, sim.batch_mode, sim.t_to_gun, &mut sim.rng,
+        );
+        if !sim.batch_mode {
+            if sim.t_to_gun > 0.0 { continue; }
+            break;
+        }
+        let epoch = ranges(&measurements, &cfg.uwb_radio);
+
+        if !gun_seen {
+            gun_seen = true;
+            // Start from what the nodes report, like the hub's first solve
+            let guess: HashMap<u32, Pos2D> = measurements.iter()
+                .map(|m| (m.node_id, Pos2D { x: m.x_line_m, y: m.y_line_m }))
+                .collect();
+            if let Some(result) = trilateration::solve(&epoch, &anchors, &guess, 10, 0.001) {
+                live_solution = result.positions;
+            }
+            for boat in &sim.boats {
+                live.push(live_solution.get(&boat.node_id), boat.antenna_world_pos(lever_arm));
+            }
+        }
+
+        for boat in &sim.boats {
+            let truth = batch_truth.entry(boat.node_id).or_insert(Vec3::zero());
+            *truth = truth.add(&boat.antenna_world_pos(lever_arm));
+        }
+        batch_epochs.push(epoch);
+    }
+
+    let mut batch = Samples::default();
+    let n_epochs = batch_epochs.len();
+    let batch_solution = trilateration::batch_solve(&batch_epochs, &anchors, &live_solution)
+        .map(|r| r.positions)
+        .unwrap_or_default();
+    for boat in &sim.boats {
+        let truth = batch_truth.get(&boat.node_id).copied().unwrap_or(Vec3::zero())
+            .scale(1.0 / n_epochs.max(1) as f64);
+        batch.push(batch_solution.get(&boat.node_id), truth);
+    }
+
+    RunResult { live, batch, batch_epochs: n_epochs }
+}
+
+/// Run the Monte Carlo, write the report, and return whether it passed.
+pub(crate) fn run(
+    cfg: &FullConfig,
+    scenario: &ScenarioConfig,
+    runs: u32,
+    seed: Option<u64>,
+    report_path: &str,
+) -> Result<bool, std::io::Error> {
+    let base_seed = seed.unwrap_or_else(rand::random);
+    info!("🎲 Headless accuracy validation — {runs} runs, base seed {base_seed}");
+
+    let mut live = Samples::default();
+    let mut batch = Samples::default();
+    let mut per_run = Vec::with_capacity(runs as usize);
+    for run in 0..runs {
+        let seed = base_seed.wrapping_add(run as u64);
+        let result = run_once(cfg, scenario, seed);
+        let summary = RunSummary {
+            run,
+            seed,
+            live_sigma_m: mean_sigma(&result.live.across).1,
+            batch_sigma_m: mean_sigma(&result.batch.across).1,
+            batch_epochs: result.batch_epochs,
+            unsolved: result.live.unsolved + result.batch.unsolved,
+        };
+        debug!("run {run} seed {seed}: live σ={:.4}m batch σ={:.4}m over {} epochs",
+            summary.live_sigma_m, summary.batch_sigma_m, summary.batch_epochs);
+        live.extend(&result.live);
+        batch.extend(&result.batch);
+        per_run.push(summary);
+    }
+
+    let (live, batch) = (live.stats(), batch.stats());
+    let passed = live.sigma_m <= LIVE_SIGMA_MAX_M
+        && batch.sigma_m <= BATCH_SIGMA_MAX_M
+        && live.unsolved == 0
+        && batch.unsolved == 0;
+    info!(
+        "{} live σ={:.2}cm (≤{:.0}cm), batch σ={:.2}cm (≤{:.0}cm), unsolved {}",
+        if passed { "✅" } else { "❌" },
+        live.sigma_m * 100.0, LIVE_SIGMA_MAX_M * 100.0,
+        batch.sigma_m * 100.0, BATCH_SIGMA_MAX_M * 100.0,
+        live.unsolved + batch.unsolved,
+    );
+
+    let report = AccuracyReport {
+        runs,
+        base_seed,
+        boats: cfg.race.n_boats,
+        live,
+        batch,
+        limits: Limits { live_sigma_m: LIVE_SIGMA_MAX_M, batch_sigma_m: BATCH_SIGMA_MAX_M },
+        passed,
+        per_run,
+    };
+    let json = serde_json::to_string_pretty(&report).map_err(std::io::Error::other)?;
+    std::fs::write(report_path, json)?;
+    info!("📄 Report written to {report_path}");
+    Ok(passed)
+}
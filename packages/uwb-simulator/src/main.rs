@@ -16,12 +16,26 @@
 //! #7 Three products: sim feeds backend → frontend + iOS tracker simultaneously
 //! #8 Zero interruption: all errors logged, sim never crashes
 //! #9 Intuitive UX: web control panel shows real vs estimated positions live
+//!
+//! `--seed <u64>` makes a run reproducible: every random draw (spawn, ranging
+//! noise, NLOS, PDoA, CIR) comes from one RNG seeded with it. Without it each
+//! run is seeded from OS entropy.
+//!
+//! `--headless --runs N [--report file]` skips UDP and the control server and
+//! runs N seeded start sequences through the in-process solver instead,
+//! checking invariant #1 at the gun (headless.rs).
+//!
+//! `--record <file>` saves every epoch with ground truth; `--replay <file>`
+//! sends a recording to the hub again instead of simulating (recording.rs).
 
 mod boat_sim;
 mod uwb_physics;
 mod trilateration;
 mod udp_tx;
 mod scenarios;
+mod headless;
+mod recording;
+mod net_impair;
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -41,7 +55,7 @@ use tracing::{info, warn};
 
 use boat_sim::{BoatSim, SimConfig};
 use scenarios::ScenarioConfig;
-use udp_tx::UdpTransmitter;
+use udp_tx::{UdpTransmitter, WireFormat};
 
 // ── CLI ───────────────────────────────────────────────────────────────────────
 
@@ -57,6 +71,9 @@ struct Args {
     /// Enable UDP multicast (mirrors real Ubiquiti AP relay)
     #[arg(long)]
     multicast: bool,
+    /// Packet format: JSON envelope or binary MeasurementPacket (udp_tx.rs)
+    #[arg(long, value_enum, default_value = "json")]
+    wire: WireFormat,
     /// Simulation speed multiplier (1.0 = real-time)
     #[arg(long, default_value = "1.0")]
     speed: f64,
@@ -66,6 +83,29 @@ struct Args {
     /// Control panel WebSocket port
     #[arg(long, default_value = "9090")]
     ctrl_port: u16,
+    /// Seed for every random draw (boat spawn, radio noise, NLOS) — the same
+    /// seed, config and speed reproduce a run exactly
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Monte Carlo accuracy validation: no networking, exit non-zero if
+    /// invariant #1 is violated (see headless.rs)
+    #[arg(long)]
+    headless: bool,
+    /// Number of randomized runs in --headless mode
+    #[arg(long, default_value = "100")]
+    runs: u32,
+    /// JSON report path for --headless mode
+    #[arg(long, default_value = "accuracy_report.json")]
+    report: String,
+    /// Record every epoch (with ground truth) to this JSON-lines file
+    #[arg(long)]
+    record: Option<String>,
+    /// Re-transmit a recording instead of simulating (paced by --speed)
+    #[arg(long)]
+    replay: Option<String>,
+    /// Times to send the recording in --replay mode
+    #[arg(long, default_value = "1")]
+    replay_loops: u32,
 }
 
 // ── Shared state ──────────────────────────────────────────────────────────────
@@ -111,7 +151,19 @@ async fn main() {
         ScenarioConfig::default()
     };
 
-    let sim = BoatSim::new(&sim_config_from(&cfg, &scenario));
+    if args.headless {
+        let code = match headless::run(&cfg, &scenario, args.runs, args.seed, &args.report) {
+            Ok(true) => 0,
+            Ok(false) => 1,
+            Err(e) => { warn!("Could not write report to {}: {e}", args.report); 2 }
+        };
+        std::process::exit(code);
+    }
+
+    if let Some(seed) = args.seed {
+        info!("🎲 Deterministic run, seed {seed}");
+    }
+    let sim = BoatSim::new(&sim_config_from(&cfg, &scenario, args.seed));
 
     let shared: SharedState = Arc::new(RwLock::new(SimState {
         sim,
@@ -125,9 +177,24 @@ async fn main() {
     // UDP transmitter
     let mc_addr = if args.multicast { Some("239.255.0.1:5555") } else { None };
     let transmitter = UdpTransmitter::new(&args.hub_addr, mc_addr)
-        .expect("Failed to bind UDP socket");
+        .expect("Failed to bind UDP socket")
+        .with_wire(args.wire, cfg.boat_physics.lever_arm_body)
+        .with_impairment(&cfg.network, args.seed);
+
+    if let Some(path) = &args.replay {
+        if let Err(e) = recording::replay(path, &transmitter, args.speed, args.replay_loops).await {
+            warn!("Replay of {path} failed: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
     let transmitter = Arc::new(transmitter);
 
+    let recorder = args.record.as_deref().and_then(|path| match recording::Recorder::create(path) {
+        Ok(r) => Some(r),
+        Err(e) => { warn!("Could not record to {path}: {e}"); None }
+    });
+
     // Broadcast channel for telemetry (web UI)
     let (telem_tx, _) = broadcast::channel::<String>(64);
     let telem_tx = Arc::new(telem_tx);
@@ -138,7 +205,7 @@ async fn main() {
     let telem_tx_loop = telem_tx.clone();
     let update_rate = cfg.simulation.update_rate_hz;
     tokio::spawn(async move {
-        sim_loop(shared_loop, tx_loop, telem_tx_loop, update_rate, &cfg).await;
+        sim_loop(shared_loop, tx_loop, telem_tx_loop, update_rate, &cfg, recorder).await;
     });
 
     // Control WebSocket server
@@ -163,6 +230,7 @@ async fn sim_loop(
     telem: Arc<broadcast::Sender<String>>,
     update_rate_hz: f64,
     cfg: &FullConfig,
+    mut recorder: Option<recording::Recorder>,
 ) {
     let epoch_duration_ms = (1000.0 / update_rate_hz) as u64;
     let mut ticker = interval(Duration::from_millis(epoch_duration_ms));
@@ -184,7 +252,8 @@ async fn sim_loop(
         let dt = (epoch_duration_ms as f64 / 1000.0) * speed;
 
         let (measurements, t_to_gun, batch_mode, telemetry_json) = {
-            let mut s = state.write().await;
+            let mut guard = state.write().await;
+            let s = &mut *guard;
             s.sim.tick(dt);
             s.epoch_counter += 1;
 
@@ -200,8 +269,16 @@ async fn sim_loop(
                 &mut seq_nums,
                 batch_mode,
                 t_to_gun,
+                &mut s.sim.rng,
             );
 
+            if let Some(rec) = recorder.as_mut() {
+                if let Err(e) = rec.record(s.epoch_counter, &s.sim, cfg.boat_physics.lever_arm_body, &meas) {
+                    warn!("Recording to {} stopped after {} epochs: {e}", rec.path(), rec.epochs());
+                    recorder = None;
+                }
+            }
+
             // Ground truth telemetry for web UI
             let boats_json: Vec<_> = s.sim.boats.iter().map(|b| {
                 serde_json::json!({
@@ -242,6 +319,7 @@ async fn sim_loop(
                 "t_to_gun":  t_to_gun,
                 "epoch":     s.epoch_counter,
                 "batch_mode": batch_mode,
+                "wind_dir_deg": s.sim.wind.direction_deg(),
                 "boats":     boats_json,
                 "estimated": est_json,
                 "anchors": {
@@ -373,6 +451,8 @@ struct FullConfig {
     uwb_radio:     uwb_physics::RadioConfig,
     boat_physics:  BoatPhysicsConfig,
     scenarios:     ScenariosConfig,
+    #[serde(default)]
+    network:       net_impair::NetworkConfig,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -404,6 +484,18 @@ struct BoatPhysicsConfig {
     wave_period_s: f64,
     lever_arm_body: [f64; 3],
     max_heel_rad: f64,
+    #[serde(default)]
+    wind_shift_sigma_deg: f64,
+    #[serde(default)]
+    wind_shift_interval_s: f64,
+    #[serde(default)]
+    wind_oscillation_deg: f64,
+    #[serde(default)]
+    wind_oscillation_period_s: f64,
+    #[serde(default)]
+    tack_angle_deg: f64,
+    #[serde(default)]
+    tack_width_m: f64,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -413,7 +505,7 @@ struct ScenariosConfig {
     rough_sea: bool,
 }
 
-fn sim_config_from(cfg: &FullConfig, sc: &ScenarioConfig) -> SimConfig {
+fn sim_config_from(cfg: &FullConfig, sc: &ScenarioConfig, seed: Option<u64>) -> SimConfig {
     SimConfig {
         line_length_m: cfg.race.line_length_m,
         committee_offset_m: cfg.race.committee_offset_m,
@@ -428,9 +520,17 @@ fn sim_config_from(cfg: &FullConfig, sc: &ScenarioConfig) -> SimConfig {
         wave_period_s: cfg.boat_physics.wave_period_s,
         lever_arm_body: cfg.boat_physics.lever_arm_body,
         max_heel_rad: cfg.boat_physics.max_heel_rad,
+        wind_direction_deg: cfg.boat_physics.wind_direction_deg,
+        wind_shift_sigma_deg: cfg.boat_physics.wind_shift_sigma_deg,
+        wind_shift_interval_s: cfg.boat_physics.wind_shift_interval_s,
+        wind_oscillation_deg: cfg.boat_physics.wind_oscillation_deg,
+        wind_oscillation_period_s: cfg.boat_physics.wind_oscillation_period_s,
+        tack_angle_deg: cfg.boat_physics.tack_angle_deg,
+        tack_width_m: cfg.boat_physics.tack_width_m,
         ocs_boat_ids: sc.ocs_boat_ids.clone(),
         ocs_offset_m: sc.ocs_offset_m as f64,
         rough_sea: sc.has(&scenarios::ScenarioType::RoughSea),
+        seed,
     }
 }
 
@@ -0,0 +1,111 @@
+//! net_impair.rs — Network impairment model for the UDP path
+//!
+//! Degrades each node's packet stream the way the Ubiquiti relay does under
+//! load, so the hub's replay protection and epoch aggregation see real traffic:
+//!   - loss:        packet dropped with probability `loss_rate`
+//!   - jitter:      delivered `latency_ms` + U(0, `jitter_ms`) late
+//!   - reordering:  with probability `reorder_rate` held a further
+//!     `reorder_delay_ms`, so the node's next packets overtake it
+//!   - duplication: with probability `duplicate_rate` a second copy follows,
+//!     with its own jitter
+//!
+//! Configured in `[network]` of config.toml; `[network.nodes.<node id>]`
+//! replaces the defaults for one node (fields left out are 0). All zero (the
+//! default) sends every packet at once, as before. Draws come from their own
+//! RNG, seeded from `--seed` when given.
+//!
+//! validation_protocol.json:
+//! - Invariant #6: Ubiquiti 5 GHz WiFi backbone behavior under load
+//! - Invariant #8: hub must keep racing through degraded delivery
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+/// Impairment applied to one node's packets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ImpairmentProfile {
+    pub loss_rate: f64,
+    pub latency_ms: f64,
+    pub jitter_ms: f64,
+    pub reorder_rate: f64,
+    pub reorder_delay_ms: f64,
+    pub duplicate_rate: f64,
+}
+
+impl ImpairmentProfile {
+    fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+/// `[network]` section of config.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    #[serde(flatten)]
+    pub default: ImpairmentProfile,
+    /// Per-node overrides, keyed by node id
+    pub nodes: HashMap<String, ImpairmentProfile>,
+}
+
+fn chance(rng: &mut StdRng, rate: f64) -> bool {
+    rng.gen_bool(rate.clamp(0.0, 1.0))
+}
+
+/// Delivery delay in ms: base latency plus uniform jitter
+fn jittered(rng: &mut StdRng, p: &ImpairmentProfile) -> f64 {
+    p.latency_ms.max(0.0) + p.jitter_ms.max(0.0) * rng.gen::<f64>()
+}
+
+pub struct Impairment {
+    default: ImpairmentProfile,
+    nodes: HashMap<u32, ImpairmentProfile>,
+    rng: Mutex<StdRng>,
+}
+
+impl Impairment {
+    /// None when no node is impaired.
+    pub fn new(cfg: &NetworkConfig, seed: Option<u64>) -> Option<Self> {
+        let nodes: HashMap<u32, ImpairmentProfile> = cfg.nodes.iter()
+            .filter_map(|(id, p)| Some((id.trim().parse().ok()?, *p)))
+            .collect();
+        if !cfg.default.is_active() && !nodes.values().any(ImpairmentProfile::is_active) {
+            return None;
+        }
+        let rng = match seed {
+            // Its own stream, so impairment does not shift the radio noise
+            Some(seed) => StdRng::seed_from_u64(seed ^ 0x6e65_7477_6f72_6b00),
+            None => StdRng::from_entropy(),
+        };
+        Some(Self { default: cfg.default, nodes, rng: Mutex::new(rng) })
+    }
+
+    /// Delays of the copies of one packet to send; empty when it is lost.
+    pub fn plan(&self, node_id: u32) -> Vec<Duration> {
+        let p = self.nodes.get(&node_id).unwrap_or(&self.default);
+        if !p.is_active() {
+            return vec![Duration::ZERO];
+        }
+        let Ok(mut guard) = self.rng.lock() else { return vec![Duration::ZERO] };
+        let rng = &mut *guard;
+
+        if chance(rng, p.loss_rate) {
+            return Vec::new();
+        }
+        let mut first = jittered(rng, p);
+        if chance(rng, p.reorder_rate) {
+            first += p.reorder_delay_ms.max(0.0);
+        }
+        let mut copies = vec![first];
+        if chance(rng, p.duplicate_rate) {
+            copies.push(jittered(rng, p));
+        }
+        copies.into_iter().map(|ms| Duration::from_secs_f64(ms / 1000.0)).collect()
+    }
+}
@@ -0,0 +1,154 @@
+//! recording.rs — Measurement stream record (`--record`) and replay (`--replay`)
+//!
+//! `--record <file>` writes every generated epoch as one JSON line:
+//!   { epoch, sent_ms, t_to_gun, batch_mode, measurements: [EpochMeasurement], truth: [BoatTruth] }
+//! `sent_ms` is wall-clock ms since recording started, `truth` the ground truth
+//! of every boat at that epoch (CoG, antenna, attitude). Each line is flushed
+//! as it is written, so stopping the sim keeps everything sent so far.
+//!
+//! `--replay <file>` re-transmits a recording through the normal UDP path
+//! (same hub address, multicast and node signing) without running the sim,
+//! paced by `sent_ms` divided by `--speed` (1.0 = as recorded). With
+//! `--replay-loops N` the file is sent N times back to back; each pass carries
+//! on the sequence numbers of the one before so the hub's replay protection
+//! keeps accepting them.
+//!
+//! validation_protocol.json:
+//! - Invariant #8: a failing write stops recording with a warning, never the sim
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::boat_sim::{BoatSim, Vec3};
+use crate::udp_tx::UdpTransmitter;
+use crate::uwb_physics::EpochMeasurement;
+
+/// Ground truth of one boat at one epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoatTruth {
+    pub node_id: u32,
+    pub cog: Vec3,
+    /// Antenna position after lever arm + attitude (what the ranges measure)
+    pub antenna: Vec3,
+    pub heading_deg: f64,
+    pub heel_rad: f64,
+    pub pitch_rad: f64,
+    pub speed_mps: f64,
+    /// Signed distance to line, + = OCS side
+    pub dtl_m: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEpoch {
+    pub epoch: u32,
+    pub sent_ms: u64,
+    pub t_to_gun: f64,
+    pub batch_mode: bool,
+    pub measurements: Vec<EpochMeasurement>,
+    pub truth: Vec<BoatTruth>,
+}
+
+// ── Record ────────────────────────────────────────────────────────────────────
+
+pub struct Recorder {
+    out: BufWriter<File>,
+    path: String,
+    started: Instant,
+    epochs: u64,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> Result<Self, std::io::Error> {
+        let out = BufWriter::new(File::create(path)?);
+        info!("⏺ Recording measurements to {path}");
+        Ok(Self { out, path: path.to_string(), started: Instant::now(), epochs: 0 })
+    }
+
+    pub fn record(
+        &mut self,
+        epoch: u32,
+        sim: &BoatSim,
+        lever_arm: [f64; 3],
+        measurements: &[EpochMeasurement],
+    ) -> Result<(), std::io::Error> {
+        let line = RecordedEpoch {
+            epoch,
+            sent_ms: self.started.elapsed().as_millis() as u64,
+            t_to_gun: sim.t_to_gun,
+            batch_mode: sim.batch_mode,
+            measurements: measurements.to_vec(),
+            truth: sim.boats.iter().map(|b| BoatTruth {
+                node_id: b.node_id,
+                cog: b.cog,
+                antenna: b.antenna_world_pos(lever_arm),
+                heading_deg: b.heading_deg,
+                heel_rad: b.heel_rad,
+                pitch_rad: b.pitch_rad,
+                speed_mps: b.boat_speed_mps,
+                dtl_m: b.dtl_m(&sim.anchors),
+            }).collect(),
+        };
+        serde_json::to_writer(&mut self.out, &line)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()?;
+        self.epochs += 1;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &str { &self.path }
+    pub fn epochs(&self) -> u64 { self.epochs }
+}
+
+// ── Replay ────────────────────────────────────────────────────────────────────
+
+/// Send a recording to the hub `loops` times at `speed`× the recorded pace.
+pub async fn replay(
+    path: &str,
+    tx: &UdpTransmitter,
+    speed: f64,
+    loops: u32,
+) -> Result<(), std::io::Error> {
+    let speed = speed.clamp(0.1, 20.0);
+    let mut seq_offset = 0u32;
+    info!("⏵ Replaying {path} ×{loops} at {speed}× speed");
+
+    for pass in 0..loops.max(1) {
+        let reader = BufReader::new(File::open(path)?);
+        let started = tokio::time::Instant::now();
+        let (mut sent, mut max_seq) = (0u64, 0u32);
+        let mut last_t_to_gun = None;
+
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() { continue; }
+            let mut epoch: RecordedEpoch = match serde_json::from_str(&line) {
+                Ok(e) => e,
+                Err(e) => { warn!("Replay: skipping line {}: {e}", n + 1); continue; }
+            };
+
+            let due = Duration::from_secs_f64(epoch.sent_ms as f64 / 1000.0 / speed);
+            tokio::time::sleep_until(started + due).await;
+
+            for m in &mut epoch.measurements {
+                max_seq = max_seq.max(m.seq_num);
+                m.seq_num = m.seq_num.wrapping_add(seq_offset);
+            }
+            tx.send_epoch(&epoch.measurements);
+            sent += 1;
+            last_t_to_gun = Some(epoch.t_to_gun);
+        }
+
+        // Next pass starts one past this one's last seq, not on it (a duplicate)
+        seq_offset = seq_offset.wrapping_add(max_seq.wrapping_add(1));
+        info!(
+            "⏵ Pass {}/{} done — {sent} epochs, ended at T{:+.1}s",
+            pass + 1, loops.max(1), -last_t_to_gun.unwrap_or(0.0)
+        );
+    }
+    Ok(())
+}
+
@@ -7,16 +7,95 @@
 //! validation_protocol.json:
 //! - Invariant #6: Ubiquiti 5 GHz WiFi backbone — multicast target matches real network
 //! - Invariant #8: send errors are logged but never crash the sim
+//!
+//! Packets are signed like real nodes (backend `node_auth`) when the node has a
+//! key in `UWB_NODE_KEYS_FILE` (`{ "<node id>": "<hex key>" }`): the datagram is
+//! the JSON envelope followed by `\n` and the hex HMAC-SHA256 of the JSON bytes.
+//!
+//! `--wire binary` sends the Phase 6 wire format instead: one `MeasurementPacket`
+//! from uwb-types per node (`MeasurementPacket::encode` — header, attitude,
+//! antenna offset, peer reports, CRC-32). It carries raw ranges only, no fused
+//! position. A node with a key is sealed with AES-128-CCM rather than signed;
+//! its AES key is the first 16 bytes of HMAC-SHA256(node key, "uwb-ccm").
+//!
+//! With a `[network]` impairment configured (net_impair.rs) each packet may be
+//! dropped, delayed, held back or duplicated per node; delayed copies are sent
+//! from a Tokio task when they fall due.
 
+use std::collections::HashMap;
 use std::net::UdpSocket;
-use tracing::{debug, warn};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::{debug, info, warn};
+use uwb_types::{MeasurementPacket, NodeDesignation};
+
+use crate::boat_sim::Quat;
+use crate::net_impair::{Impairment, NetworkConfig};
 use crate::uwb_physics::EpochMeasurement;
 
+/// What goes in each UDP datagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum WireFormat {
+    /// Phase 2 JSON envelope (uwb_hub.rs UwbMeasurementEnvelope)
+    Json,
+    /// Phase 6 binary MeasurementPacket (uwb-types)
+    Binary,
+}
+
 pub struct UdpTransmitter {
-    socket: UdpSocket,
+    socket: Arc<UdpSocket>,
     unicast_addr: String,
     multicast_addr: Option<String>,
+    node_keys: HashMap<u32, Vec<u8>>,
+    wire: WireFormat,
+    /// Boat antenna offset from CoG, body frame (binary packets only)
+    ant_offset_body: [f64; 3],
+    impairment: Option<Impairment>,
+}
+
+/// Per-node keys shared with the hub; none means packets go out unsigned.
+fn load_node_keys() -> HashMap<u32, Vec<u8>> {
+    let Ok(path) = std::env::var("UWB_NODE_KEYS_FILE") else { return HashMap::new() };
+    let raw: HashMap<String, String> = match std::fs::read_to_string(&path).map(|t| serde_json::from_str(&t)) {
+        Ok(Ok(raw)) => raw,
+        _ => {
+            warn!("UDP: could not read node keys from {path}, sending unsigned");
+            return HashMap::new();
+        }
+    };
+    let keys: HashMap<u32, Vec<u8>> = raw.into_iter()
+        .filter_map(|(node, key)| Some((node.trim().parse().ok()?, hex::decode(key.trim()).ok()?)))
+        .collect();
+    info!("UDP: signing packets for {} node(s)", keys.len());
+    keys
+}
+
+/// AES-128-CCM key of a node, derived from its shared key
+fn ccm_key(node_key: &[u8]) -> Option<[u8; 16]> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(node_key).ok()?;
+    mac.update(b"uwb-ccm");
+    mac.finalize().into_bytes()[..16].try_into().ok()
+}
+
+/// Battery percentage as a single Li-ion cell voltage (3.3 V empty, 4.2 V full)
+fn battery_mv(pct: u8) -> u16 {
+    3300 + pct.min(100) as u16 * 9
+}
+
+/// Unicast to the hub, plus multicast when enabled.
+fn send_to_hub(socket: &UdpSocket, bytes: &[u8], unicast: &str, multicast: Option<&str>) {
+    if let Err(e) = socket.send_to(bytes, unicast) {
+        warn!("UDP: unicast send failed: {e}");
+    }
+    // Optional multicast (mirrors real Ubiquiti AP relay behavior)
+    if let Some(mc) = multicast {
+        if let Err(e) = socket.send_to(bytes, mc) {
+            warn!("UDP: multicast send failed: {e}");
+        }
+    }
 }
 
 impl UdpTransmitter {
@@ -27,12 +106,36 @@ impl UdpTransmitter {
         let socket = UdpSocket::bind("0.0.0.0:0")?;
         socket.set_nonblocking(false)?;
         Ok(Self {
-            socket,
+            socket: Arc::new(socket),
             unicast_addr: unicast_addr.to_string(),
             multicast_addr: multicast_addr.map(|s| s.to_string()),
+            node_keys: load_node_keys(),
+            wire: WireFormat::Json,
+            ant_offset_body: [0.0; 3],
+            impairment: None,
         })
     }
 
+    /// Degrade delivery per node as configured in `[network]`.
+    pub fn with_impairment(mut self, cfg: &NetworkConfig, seed: Option<u64>) -> Self {
+        self.impairment = Impairment::new(cfg, seed);
+        if self.impairment.is_some() {
+            info!("UDP: network impairment on (loss/jitter/reorder/duplication)");
+        }
+        self
+    }
+
+    /// Switch the wire format; `lever_arm_body` goes into binary packets as
+    /// the boats' antenna offset.
+    pub fn with_wire(mut self, wire: WireFormat, lever_arm_body: [f64; 3]) -> Self {
+        if wire == WireFormat::Binary {
+            info!("UDP: sending binary MeasurementPackets");
+        }
+        self.wire = wire;
+        self.ant_offset_body = lever_arm_body;
+        self
+    }
+
     /// Send all measurements from one epoch to the hub.
     /// invariant_ref: #8 — errors logged, never panic
     pub fn send_epoch(&self, measurements: &[EpochMeasurement]) {
@@ -42,6 +145,50 @@ impl UdpTransmitter {
     }
 
     fn send_measurement(&self, m: &EpochMeasurement) {
+        let bytes = match self.wire {
+            WireFormat::Json => self.json_envelope(m),
+            WireFormat::Binary => self.binary_packet(m),
+        };
+        let Some(bytes) = bytes else { return };
+
+        let delays = match &self.impairment {
+            Some(impairment) => impairment.plan(m.node_id),
+            None => vec![Duration::ZERO],
+        };
+        if delays.is_empty() {
+            debug!("UDP ✗ node_id={} seq={} dropped", m.node_id, m.seq_num);
+            return;
+        }
+        for delay in delays {
+            if delay.is_zero() {
+                self.deliver(&bytes);
+                debug!("UDP → {} node_id={} y={:.2}m", self.unicast_addr, m.node_id, m.y_line_m);
+                continue;
+            }
+            // Outside a runtime there is nothing to schedule on; send now
+            let Ok(handle) = tokio::runtime::Handle::try_current() else {
+                self.deliver(&bytes);
+                continue;
+            };
+            let (socket, unicast, multicast, bytes) = (
+                self.socket.clone(),
+                self.unicast_addr.clone(),
+                self.multicast_addr.clone(),
+                bytes.clone(),
+            );
+            debug!("UDP ⏳ node_id={} seq={} delayed {}ms", m.node_id, m.seq_num, delay.as_millis());
+            handle.spawn(async move {
+                tokio::time::sleep(delay).await;
+                send_to_hub(&socket, &bytes, &unicast, multicast.as_deref());
+            });
+        }
+    }
+
+    fn deliver(&self, bytes: &[u8]) {
+        send_to_hub(&self.socket, bytes, &self.unicast_addr, self.multicast_addr.as_deref());
+    }
+
+    fn json_envelope(&self, m: &EpochMeasurement) -> Option<Vec<u8>> {
         // Build JSON envelope matching uwb_hub.rs UwbMeasurementEnvelope
         let payload = serde_json::json!({
             "node_id":     m.node_id,
@@ -69,23 +216,56 @@ impl UdpTransmitter {
             })).collect::<Vec<_>>(),
         });
 
-        let bytes = match serde_json::to_vec(&payload) {
+        let mut bytes = match serde_json::to_vec(&payload) {
             Ok(b) => b,
-            Err(e) => { warn!("UDP: serialize failed: {e}"); return; }
+            Err(e) => { warn!("UDP: serialize failed: {e}"); return None; }
         };
-
-        // Unicast to local hub
-        if let Err(e) = self.socket.send_to(&bytes, &self.unicast_addr) {
-            warn!("UDP: unicast send failed: {e}");
-        } else {
-            debug!("UDP → {} node_id={} y={:.2}m", self.unicast_addr, m.node_id, m.y_line_m);
+        // The tag covers exactly the bytes sent, trailer excluded
+        if let Some(key) = self.node_keys.get(&m.node_id) {
+            if let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(key) {
+                mac.update(&bytes);
+                bytes.push(b'\n');
+                bytes.extend_from_slice(hex::encode(mac.finalize().into_bytes()).as_bytes());
+            }
         }
+        Some(bytes)
+    }
 
-        // Optional multicast (mirrors real Ubiquiti AP relay behavior)
-        if let Some(mc) = &self.multicast_addr {
-            if let Err(e) = self.socket.send_to(&bytes, mc) {
-                warn!("UDP: multicast send failed: {e}");
-            }
+    fn binary_packet(&self, m: &EpochMeasurement) -> Option<Vec<u8>> {
+        let designation = NodeDesignation::from_u8(m.designation);
+        let q = Quat::from_euler(m.heel_rad as f64, m.pitch_rad as f64, (m.heading_deg as f64).to_radians());
+        // Fixed anchors report at their stated positions, no lever arm
+        let offset = if designation == NodeDesignation::Boat { self.ant_offset_body } else { [0.0; 3] };
+        let tx_timestamp_ns = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        let packet = MeasurementPacket {
+            node_id: m.node_id,
+            tx_timestamp_ns,
+            seq_num: m.seq_num,
+            designation,
+            battery_mv: battery_mv(m.battery_pct),
+            node_flags: if m.battery_pct < 20 { 0x01 } else { 0 },
+            orientation: uwb_types::Quat { x: q.x as f32, y: q.y as f32, z: q.z as f32, w: q.w as f32 },
+            ant_offset_body: uwb_types::Vec3 { x: offset[0] as f32, y: offset[1] as f32, z: offset[2] as f32 },
+            reports: m.peers.iter().map(|p| uwb_types::PeerReport {
+                peer_id: p.peer_id,
+                range_mm: (p.range_m * 1000.0).round() as i32,
+                azimuth_deg10: (p.pdoa_az_rad.to_degrees() * 10.0).round() as i16,
+                elevation_deg10: (p.pdoa_el_rad.to_degrees() * 10.0).round() as i16,
+                cir_snr_db10: p.snr_db10.max(0) as u16,
+                fp_index: p.fp_index,
+                quality_flags: p.nlos as u8,
+            }).collect(),
+            crc32: 0,
+        };
+
+        let key = self.node_keys.get(&m.node_id).and_then(|k| ccm_key(k));
+        let bytes = packet.encode(key.as_ref());
+        if bytes.is_none() {
+            warn!("UDP: sealing packet for node {} failed", m.node_id);
         }
+        bytes
     }
 }
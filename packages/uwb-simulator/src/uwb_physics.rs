@@ -41,7 +41,7 @@ pub struct RadioConfig {
 // ── Peer measurement (what one node reports about one peer) ───────────────────
 
 /// Matches the PeerReport struct in packages/uwb-types/src/lib.rs
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeerReport {
     pub peer_id:      u32,
     /// Measured range in meters (DS-TWR output, noisy)
@@ -59,7 +59,7 @@ pub struct PeerReport {
 }
 
 /// Full measurement packet from one node in one epoch
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EpochMeasurement {
     pub node_id:      u32,
     pub seq_num:      u32,
@@ -71,6 +71,11 @@ pub struct EpochMeasurement {
     pub vx_line_mps:  f32,
     pub vy_line_mps:  f32,
     pub heading_deg:  f32,
+    /// IMU attitude (orientation quaternion in the binary wire format)
+    #[serde(default)]
+    pub heel_rad:     f32,
+    #[serde(default)]
+    pub pitch_rad:    f32,
     pub fix_quality:  u8,
     pub batch_mode:   bool,
     /// Raw peer reports (used in "raw mode" for hub trilateration testing)
@@ -124,6 +129,8 @@ fn is_nlos(
 /// Generate all measurements for one epoch.
 /// Each boat's node ranges against all other visible nodes.
 /// All anchor nodes (MarkA, MarkB, Committee) are included as fixed peers.
+/// All draws come from `rng` (the run's `BoatSim::rng`), so a seeded run
+/// produces the same measurements epoch for epoch.
 ///
 /// invariant_ref: #5 — self-organizing mesh (all-to-all ranging in TDMA)
 pub fn generate_epoch(
@@ -134,8 +141,8 @@ pub fn generate_epoch(
     seq_nums: &mut std::collections::HashMap<u32, u32>,
     batch_mode: bool,
     t_to_gun: f64,
+    rng: &mut impl Rng,
 ) -> Vec<EpochMeasurement> {
-    let mut rng = rand::thread_rng();
 
     // Compute all antenna world positions (CoG + lever-arm + attitude)
     // Fixed anchors at their stated positions (no lever arm offset for buoys)
@@ -172,29 +179,29 @@ pub fn generate_epoch(
             let nlos = if *desig_i >= 1 && *desig_i <= 3 {
                 false
             } else {
-                is_nlos(pi, pj, boats, *ni, *nj, true_range, cfg, &mut rng)
+                is_nlos(pi, pj, boats, *ni, *nj, true_range, cfg, rng)
             };
 
             // DS-TWR range measurement with noise
             let sigma = if nlos { cfg.sigma_nlos_m } else { cfg.sigma_los_m };
             let noise_dist = Normal::new(0.0, sigma).unwrap();
-            let nlos_bias = if nlos { f64::max(Normal::new(0.3, 0.1).unwrap().sample(&mut rng), 0.0) } else { 0.0 };
-            let measured_range = (true_range + noise_dist.sample(&mut rng) + nlos_bias) as f32;
+            let nlos_bias = if nlos { f64::max(Normal::new(0.3, 0.1).unwrap().sample(rng), 0.0) } else { 0.0 };
+            let measured_range = (true_range + noise_dist.sample(rng) + nlos_bias) as f32;
 
             // PDoA — in receiver body frame (i.e., relative to boat attitude)
             let peer_vec_world = Vec3::new(pj.x - pi.x, pj.y - pi.y, pj.z - pi.z);
             let az_true = peer_vec_world.y.atan2(peer_vec_world.x);
             let el_true = peer_vec_world.z.atan2((peer_vec_world.x.powi(2) + peer_vec_world.y.powi(2)).sqrt());
-            let az_noise = Normal::new(0.0, cfg.sigma_azimuth_deg.to_radians()).unwrap().sample(&mut rng);
-            let el_noise = Normal::new(0.0, cfg.sigma_elevation_deg.to_radians()).unwrap().sample(&mut rng);
+            let az_noise = Normal::new(0.0, cfg.sigma_azimuth_deg.to_radians()).unwrap().sample(rng);
+            let el_noise = Normal::new(0.0, cfg.sigma_elevation_deg.to_radians()).unwrap().sample(rng);
 
             // CIR stats
             let (snr, fp_idx) = if nlos {
-                let snr = Uniform::new(cfg.snr_nlos_db_min, cfg.snr_nlos_db_max).sample(&mut rng);
+                let snr = Uniform::new(cfg.snr_nlos_db_min, cfg.snr_nlos_db_max).sample(rng);
                 let fp  = rng.gen_range(cfg.fp_index_nlos_min..=cfg.fp_index_nlos_max);
                 (snr, fp)
             } else {
-                let snr = Uniform::new(cfg.snr_los_db_min, cfg.snr_los_db_max).sample(&mut rng);
+                let snr = Uniform::new(cfg.snr_los_db_min, cfg.snr_los_db_max).sample(rng);
                 let fp  = rng.gen_range(cfg.fp_index_los_min..=cfg.fp_index_los_max);
                 (snr, fp)
             };
@@ -221,19 +228,21 @@ pub fn generate_epoch(
         // In Phase 2: boat reports its EKF position (which here = GT + small noise)
         // In raw mode the hub receives PeerReports and does trilateration itself
         let boat = boats.iter().find(|b| b.node_id == *ni);
-        let (x_line, y_line, vx_line, vy_line, heading, gt_y) = if let Some(b) = boat {
+        let (x_line, y_line, vx_line, vy_line, heading, heel, pitch, gt_y) = if let Some(b) = boat {
             let ekf_noise_m = Normal::new(0.0, 0.04).unwrap();  // 4cm EKF residual
             let gt_y = b.cog.y as f32;  // approximate GT as CoG y (close enough for sim)
             (
                 b.cog.x as f32,
-                (b.cog.y + ekf_noise_m.sample(&mut rng)) as f32,
+                (b.cog.y + ekf_noise_m.sample(rng)) as f32,
                 b.vel.x as f32,
                 b.vel.y as f32,
                 b.heading_deg as f32,
+                b.heel_rad as f32,
+                b.pitch_rad as f32,
                 gt_y,
             )
         } else {
-            (0.0, 0.0, 0.0, 0.0, 0.0, 0.0)
+            (0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)
         };
 
         measurements.push(EpochMeasurement {
@@ -246,6 +255,8 @@ pub fn generate_epoch(
             vx_line_mps: vx_line,
             vy_line_mps: vy_line,
             heading_deg: heading,
+            heel_rad:   heel,
+            pitch_rad:  pitch,
             fix_quality,
             batch_mode,
             peers,
@@ -106,7 +106,7 @@ impl Quat {
 // ── Per-Peer Ranging Report ───────────────────────────────────────────────────
 
 /// One DS-TWR + PDoA measurement to a single peer.
-/// 28 bytes on wire (matches C struct layout for direct DMA transfer).
+/// 16 bytes on wire, little-endian, in field order (`PEER_REPORT_LEN`).
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct PeerReport {
     /// Peer node ID
@@ -146,8 +146,8 @@ impl PeerReport {
 
 /// Primary packet broadcast by every UWB node every 50ms epoch.
 ///
-/// Wire format: AES-128-CCM encrypted, 192–384 bytes max.
-/// Matches `MeasurementPacket` C struct in uwb-firmware.
+/// Wire format: see `encode`; matches `MeasurementPacketHeader` and
+/// `MeasurementPacketPose` in uwb_types.h.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeasurementPacket {
     /// Globally unique node ID (provisioned at manufacture)
@@ -188,6 +188,202 @@ impl MeasurementPacket {
     }
 }
 
+// ── Measurement Packet Wire Format ───────────────────────────────────────────
+
+/// Schema version in the first byte of a binary measurement packet. A JSON
+/// envelope starts with `{`, so the hub can tell the two apart.
+pub const MEASUREMENT_WIRE_VERSION: u8 = 1;
+/// Clear header: version, wire flags, designation, report count, node_id,
+/// tx_timestamp_ns, seq_num, battery_mv, node_flags, reserved.
+pub const MEASUREMENT_HEADER_LEN: usize = 24;
+/// Orientation (4 × f32) and antenna offset (3 × f32) ahead of the reports.
+pub const MEASUREMENT_POSE_LEN: usize = 28;
+/// Bytes per `PeerReport`.
+pub const PEER_REPORT_LEN: usize = 16;
+/// Most reports one packet carries.
+pub const MAX_PEER_REPORTS: usize = 24;
+/// AES-128-CCM tag length, appended to the sealed payload.
+pub const CCM_TAG_LEN: usize = 8;
+/// Wire flag bit0: payload is AES-128-CCM sealed.
+pub const WIRE_FLAG_SEALED: u8 = 0x01;
+
+/// CRC-32 (IEEE 802.3, reflected, as zlib) of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// CCM nonce: node_id, seq_num and the low 5 bytes of tx_timestamp_ns (13 bytes).
+#[cfg(feature = "aes-ccm")]
+fn ccm_nonce(header: &[u8]) -> [u8; 13] {
+    let mut nonce = [0u8; 13];
+    nonce[..4].copy_from_slice(&header[4..8]);
+    nonce[4..8].copy_from_slice(&header[16..20]);
+    nonce[8..].copy_from_slice(&header[8..13]);
+    nonce
+}
+
+#[cfg(feature = "aes-ccm")]
+type PacketCcm = ccm::Ccm<aes::Aes128, ccm::consts::U8, ccm::consts::U13>;
+
+impl MeasurementPacket {
+    /// Encode to the binary wire format, little-endian:
+    /// `MEASUREMENT_HEADER_LEN` clear header, orientation + antenna offset,
+    /// `PEER_REPORT_LEN` per report (at most `MAX_PEER_REPORTS`), then the
+    /// CRC-32 of all preceding bytes. `crc32` is ignored and computed here.
+    ///
+    /// With a key the payload after the header is sealed with AES-128-CCM
+    /// (header as associated data, `CCM_TAG_LEN` tag appended) before the CRC,
+    /// so a corrupt packet is dropped before any decryption is attempted.
+    /// None only when sealing was asked for and failed.
+    pub fn encode(&self, key: Option<&[u8; 16]>) -> Option<Vec<u8>> {
+        let count = self.reports.len().min(MAX_PEER_REPORTS);
+        let mut buf = Vec::with_capacity(
+            MEASUREMENT_HEADER_LEN + MEASUREMENT_POSE_LEN + count * PEER_REPORT_LEN + CCM_TAG_LEN + 4,
+        );
+        buf.push(MEASUREMENT_WIRE_VERSION);
+        buf.push(if key.is_some() { WIRE_FLAG_SEALED } else { 0 });
+        buf.push(self.designation as u8);
+        buf.push(count as u8);
+        buf.extend_from_slice(&self.node_id.to_le_bytes());
+        buf.extend_from_slice(&self.tx_timestamp_ns.to_le_bytes());
+        buf.extend_from_slice(&self.seq_num.to_le_bytes());
+        buf.extend_from_slice(&self.battery_mv.to_le_bytes());
+        buf.push(self.node_flags);
+        buf.push(0);
+        let o = &self.orientation;
+        let a = &self.ant_offset_body;
+        for v in [o.x, o.y, o.z, o.w, a.x, a.y, a.z] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        for r in &self.reports[..count] {
+            buf.extend_from_slice(&r.peer_id.to_le_bytes());
+            buf.extend_from_slice(&r.range_mm.to_le_bytes());
+            buf.extend_from_slice(&r.azimuth_deg10.to_le_bytes());
+            buf.extend_from_slice(&r.elevation_deg10.to_le_bytes());
+            buf.extend_from_slice(&r.cir_snr_db10.to_le_bytes());
+            buf.push(r.fp_index);
+            buf.push(r.quality_flags);
+        }
+        if let Some(key) = key {
+            buf = Self::seal(buf, key)?;
+        }
+        let crc = crc32(&buf);
+        buf.extend_from_slice(&crc.to_le_bytes());
+        Some(buf)
+    }
+
+    #[cfg(feature = "aes-ccm")]
+    fn seal(mut buf: Vec<u8>, key: &[u8; 16]) -> Option<Vec<u8>> {
+        use ccm::aead::{generic_array::GenericArray, AeadInPlace, KeyInit};
+        let (header, payload) = buf.split_at_mut(MEASUREMENT_HEADER_LEN);
+        let cipher = PacketCcm::new(GenericArray::from_slice(key));
+        let nonce = ccm_nonce(header);
+        let tag = cipher.encrypt_in_place_detached(GenericArray::from_slice(&nonce), header, payload).ok()?;
+        buf.extend_from_slice(&tag);
+        Some(buf)
+    }
+
+    #[cfg(not(feature = "aes-ccm"))]
+    fn seal(_buf: Vec<u8>, _key: &[u8; 16]) -> Option<Vec<u8>> {
+        None
+    }
+
+    #[cfg(feature = "aes-ccm")]
+    fn open(header: &[u8], payload: &mut [u8], tag: &[u8], key: &[u8; 16]) -> Option<()> {
+        use ccm::aead::{generic_array::GenericArray, AeadInPlace, KeyInit};
+        let cipher = PacketCcm::new(GenericArray::from_slice(key));
+        let nonce = ccm_nonce(header);
+        cipher
+            .decrypt_in_place_detached(GenericArray::from_slice(&nonce), header, payload, GenericArray::from_slice(tag))
+            .ok()
+    }
+
+    #[cfg(not(feature = "aes-ccm"))]
+    fn open(_header: &[u8], _payload: &mut [u8], _tag: &[u8], _key: &[u8; 16]) -> Option<()> {
+        None
+    }
+
+    /// Node id and sealed flag from the clear header, so the receiver can pick
+    /// the node's key before `decode`. None when `buf` is not a binary packet.
+    pub fn wire_header(buf: &[u8]) -> Option<(u32, bool)> {
+        let header = buf.get(..MEASUREMENT_HEADER_LEN)?;
+        if header[0] != MEASUREMENT_WIRE_VERSION {
+            return None;
+        }
+        let node_id = u32::from_le_bytes(header[4..8].try_into().ok()?);
+        Some((node_id, header[1] & WIRE_FLAG_SEALED != 0))
+    }
+
+    /// Parse a binary packet; None for another version, a short buffer, a bad
+    /// CRC, or a sealed packet without the right key.
+    pub fn decode(buf: &[u8], key: Option<&[u8; 16]>) -> Option<Self> {
+        let (body, crc) = buf.split_at(buf.len().checked_sub(4)?);
+        let crc32_wire = u32::from_le_bytes(crc.try_into().ok()?);
+        if crc32(body) != crc32_wire {
+            return None;
+        }
+        let header = body.get(..MEASUREMENT_HEADER_LEN)?;
+        if header[0] != MEASUREMENT_WIRE_VERSION {
+            return None;
+        }
+        let count = header[3] as usize;
+        let payload_len = MEASUREMENT_POSE_LEN + count * PEER_REPORT_LEN;
+
+        let mut payload = body[MEASUREMENT_HEADER_LEN..].to_vec();
+        if header[1] & WIRE_FLAG_SEALED != 0 {
+            if payload.len() != payload_len + CCM_TAG_LEN {
+                return None;
+            }
+            let tag = payload.split_off(payload_len);
+            Self::open(header, &mut payload, &tag, key?)?;
+        } else if payload.len() != payload_len {
+            return None;
+        }
+
+        let u16_at = |b: &[u8], o: usize| u16::from_le_bytes([b[o], b[o + 1]]);
+        let u32_at = |b: &[u8], o: usize| u32::from_le_bytes([b[o], b[o + 1], b[o + 2], b[o + 3]]);
+        let f32_at = |b: &[u8], o: usize| f32::from_bits(u32_at(b, o));
+        let reports = payload[MEASUREMENT_POSE_LEN..].chunks_exact(PEER_REPORT_LEN).map(|r| PeerReport {
+            peer_id: u32_at(r, 0),
+            range_mm: u32_at(r, 4) as i32,
+            azimuth_deg10: u16_at(r, 8) as i16,
+            elevation_deg10: u16_at(r, 10) as i16,
+            cir_snr_db10: u16_at(r, 12),
+            fp_index: r[14],
+            quality_flags: r[15],
+        }).collect();
+
+        Some(Self {
+            node_id: u32_at(header, 4),
+            tx_timestamp_ns: u64::from_le_bytes(header[8..16].try_into().ok()?),
+            seq_num: u32_at(header, 16),
+            designation: NodeDesignation::from_u8(header[2]),
+            battery_mv: u16_at(header, 20),
+            node_flags: header[22],
+            orientation: Quat {
+                x: f32_at(&payload, 0),
+                y: f32_at(&payload, 4),
+                z: f32_at(&payload, 8),
+                w: f32_at(&payload, 12),
+            },
+            ant_offset_body: Vec3 {
+                x: f32_at(&payload, 16),
+                y: f32_at(&payload, 20),
+                z: f32_at(&payload, 24),
+            },
+            reports,
+            crc32: crc32_wire,
+        })
+    }
+}
+
 // ── Fused Position (Hub → All Clients) ───────────────────────────────────────
 
 /// Per-node 2D position in the live start-line frame.
@@ -249,6 +445,126 @@ impl FusedPositionPacket {
     }
 }
 
+// ── Control Downlink (Hub → Node) ────────────────────────────────────────────
+
+/// Command the hub sends to one node (UDP, to the address the node last sent from).
+///
+/// On the wire it is a JSON object `{ "type": "control", "node_id", "seq",
+/// "issued_ms", "cmd", ... , "hmac" }`; `hmac` is the HMAC-SHA256, under the
+/// node's provisioned key, of the compact key-sorted JSON without `hmac`. A node
+/// drops a command whose tag does not verify, whose `seq` is not above the last
+/// it applied, or whose `issued_ms` is more than a few seconds old.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Change the node's role (`set-mark-designation`)
+    SetDesignation { designation: NodeDesignation },
+}
+
+// ── Binary Telemetry Frame (Backend → Media / iOS) ───────────────────────────
+
+/// Schema version carried in every telemetry frame header.
+pub const TELEMETRY_VERSION: u8 = 1;
+/// Header bytes: version, flags, boat count, epoch_ms.
+pub const TELEMETRY_HEADER_LEN: usize = 12;
+/// Bytes per boat record.
+pub const TELEMETRY_BOAT_LEN: usize = 36;
+/// Boat ids are NUL-padded (or truncated) to this many bytes.
+pub const TELEMETRY_ID_LEN: usize = 16;
+
+/// One boat in a telemetry frame. 36 bytes on wire, little-endian.
+/// Matches `TelemetryBoat` C struct in uwb_types.h.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryBoat {
+    /// Boat id, UTF-8, at most `TELEMETRY_ID_LEN` bytes on wire
+    pub boat_id: String,
+    /// Latitude × 1e7
+    pub lat_e7: i32,
+    /// Longitude × 1e7
+    pub lon_e7: i32,
+    /// True heading × 100 (0–35999)
+    pub heading_cdeg: u16,
+    /// Speed over ground × 100, knots
+    pub speed_ckn: u16,
+    /// Distance to start line, centimeters
+    pub dtl_cm: i32,
+    /// Race rank (0 = unranked)
+    pub rank: u16,
+    /// Current leg index
+    pub leg_index: u8,
+    /// 0 = live, 1 = stale, 2 = offline
+    pub status: u8,
+}
+
+/// Packed position frame for the binary telemetry channel: a 12-byte header
+/// followed by `boats.len()` fixed-size `TelemetryBoat` records.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryFrame {
+    /// Server wall-clock timestamp (milliseconds)
+    pub epoch_ms: u64,
+    /// Reserved, 0
+    pub flags: u8,
+    pub boats: Vec<TelemetryBoat>,
+}
+
+impl TelemetryFrame {
+    pub fn encode(&self) -> Vec<u8> {
+        let count = self.boats.len().min(u16::MAX as usize);
+        let mut buf = Vec::with_capacity(TELEMETRY_HEADER_LEN + count * TELEMETRY_BOAT_LEN);
+        buf.push(TELEMETRY_VERSION);
+        buf.push(self.flags);
+        buf.extend_from_slice(&(count as u16).to_le_bytes());
+        buf.extend_from_slice(&self.epoch_ms.to_le_bytes());
+        for boat in &self.boats[..count] {
+            let mut id = [0u8; TELEMETRY_ID_LEN];
+            let mut len = boat.boat_id.len().min(TELEMETRY_ID_LEN);
+            while !boat.boat_id.is_char_boundary(len) {
+                len -= 1;
+            }
+            id[..len].copy_from_slice(&boat.boat_id.as_bytes()[..len]);
+            buf.extend_from_slice(&id);
+            buf.extend_from_slice(&boat.lat_e7.to_le_bytes());
+            buf.extend_from_slice(&boat.lon_e7.to_le_bytes());
+            buf.extend_from_slice(&boat.heading_cdeg.to_le_bytes());
+            buf.extend_from_slice(&boat.speed_ckn.to_le_bytes());
+            buf.extend_from_slice(&boat.dtl_cm.to_le_bytes());
+            buf.extend_from_slice(&boat.rank.to_le_bytes());
+            buf.push(boat.leg_index);
+            buf.push(boat.status);
+        }
+        buf
+    }
+
+    /// Parse a frame; None for a different schema version or a short buffer.
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        let header = buf.get(..TELEMETRY_HEADER_LEN)?;
+        if header[0] != TELEMETRY_VERSION {
+            return None;
+        }
+        let count = u16::from_le_bytes([header[2], header[3]]) as usize;
+        let epoch_ms = u64::from_le_bytes(header[4..12].try_into().ok()?);
+        let body = buf.get(TELEMETRY_HEADER_LEN..TELEMETRY_HEADER_LEN + count * TELEMETRY_BOAT_LEN)?;
+        let boats = body.chunks_exact(TELEMETRY_BOAT_LEN).map(|r| {
+            let i32_at = |o: usize| i32::from_le_bytes([r[o], r[o + 1], r[o + 2], r[o + 3]]);
+            let u16_at = |o: usize| u16::from_le_bytes([r[o], r[o + 1]]);
+            let id = &r[..TELEMETRY_ID_LEN];
+            let id_len = id.iter().position(|&b| b == 0).unwrap_or(TELEMETRY_ID_LEN);
+            TelemetryBoat {
+                boat_id: String::from_utf8_lossy(&id[..id_len]).into_owned(),
+                lat_e7: i32_at(16),
+                lon_e7: i32_at(20),
+                heading_cdeg: u16_at(24),
+                speed_ckn: u16_at(26),
+                dtl_cm: i32_at(28),
+                rank: u16_at(32),
+                leg_index: r[34],
+                status: r[35],
+            }
+        }).collect();
+        Some(Self { epoch_ms, flags: header[1], boats })
+    }
+}
+
 // ── Audit Log Entry (SHA-256 chained) ────────────────────────────────────────
 
 /// One block in the immutable SHA-256 chained audit log.
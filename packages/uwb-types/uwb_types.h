@@ -30,7 +30,7 @@ typedef struct {
     float x, y;
 } Vec2;
 
-// ── Per-peer ranging report (28 bytes) ───────────────────────────────────────
+// ── Per-peer ranging report (16 bytes) ───────────────────────────────────────
 typedef struct __attribute__((packed)) {
     uint32_t peer_id;
     int32_t  range_mm;           // DS-TWR Euclidean range, millimeters
@@ -41,20 +41,36 @@ typedef struct __attribute__((packed)) {
     uint8_t  quality_flags;      // bit0=NLOS, bit1=multipath, bit2=STS_fail
 } PeerReport;
 
-// ── UWB Measurement Packet header (fixed portion) ────────────────────────────
+// ── UWB Measurement Packet (binary wire format, little-endian) ──────────────
+#define UWB_MEASUREMENT_WIRE_VERSION 1    // never '{', so the hub tells it from JSON
+#define UWB_MEASUREMENT_HEADER_LEN   24
+#define UWB_MEASUREMENT_POSE_LEN     28
+#define UWB_PEER_REPORT_LEN          16
+#define UWB_CCM_TAG_LEN              8
+#define UWB_WIRE_FLAG_SEALED         0x01 // pose + reports sealed with AES-128-CCM
+
+// Clear header, also the CCM associated data
 typedef struct __attribute__((packed)) {
+    uint8_t        version;          // UWB_MEASUREMENT_WIRE_VERSION
+    uint8_t        wire_flags;       // UWB_WIRE_FLAG_SEALED
+    NodeDesignation designation;
+    uint8_t        num_reports;      // number of PeerReport entries following
     uint32_t       node_id;
     uint64_t       tx_timestamp_ns;
     uint32_t       seq_num;
-    NodeDesignation designation;
     uint16_t       battery_mv;
     uint8_t        node_flags;
+    uint8_t        reserved;         // 0
+} MeasurementPacketHeader;           // 24 bytes
+
+// Follows the header; sealed together with the reports when flagged
+typedef struct __attribute__((packed)) {
     Quat           orientation;
     Vec3           ant_offset_body;  // body-frame antenna lever arm to CoG
-    uint8_t        num_reports;      // number of PeerReport entries following
     // PeerReport reports[num_reports]  -- variable length
-    // uint32_t crc32                  -- after last report
-} MeasurementPacketHeader;
+    // uint8_t ccm_tag[UWB_CCM_TAG_LEN] -- only when sealed
+    // uint32_t crc32                   -- of all preceding bytes
+} MeasurementPacketPose;             // 28 bytes
 
 // ── Fused position per node (from hub → all clients) ─────────────────────────
 typedef struct {
@@ -80,6 +96,29 @@ typedef struct {
     // NodePosition2D nodes[num_nodes]  -- variable length
 } FusedPositionPacketHeader;
 
+// ── Binary telemetry frame (backend → media / iOS, WebSocket /telemetry) ────
+#define UWB_TELEMETRY_VERSION 1
+#define UWB_TELEMETRY_ID_LEN  16
+
+typedef struct __attribute__((packed)) {
+    uint8_t  version;        // UWB_TELEMETRY_VERSION
+    uint8_t  flags;          // reserved, 0
+    uint16_t num_boats;      // number of TelemetryBoat records following
+    uint64_t epoch_ms;       // server wall clock
+} TelemetryHeader;           // 12 bytes
+
+typedef struct __attribute__((packed)) {
+    char     boat_id[UWB_TELEMETRY_ID_LEN]; // UTF-8, NUL-padded
+    int32_t  lat_e7;         // latitude × 1e7
+    int32_t  lon_e7;         // longitude × 1e7
+    uint16_t heading_cdeg;   // true heading × 100
+    uint16_t speed_ckn;      // SOG knots × 100
+    int32_t  dtl_cm;         // distance to start line
+    uint16_t rank;           // 0 = unranked
+    uint8_t  leg_index;
+    uint8_t  status;         // 0 = live, 1 = stale, 2 = offline
+} TelemetryBoat;             // 36 bytes
+
 // ── OCS threshold constants ───────────────────────────────────────────────────
 #define UWB_OCS_THRESHOLD_M    0.10f   // 10 cm over line
 #define UWB_MIN_FIX_QUALITY    60      // minimum quality for OCS call